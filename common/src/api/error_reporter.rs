@@ -0,0 +1,127 @@
+use std::sync::OnceLock;
+
+use log::error;
+
+use crate::error::{EmError, EmResult};
+
+/// Log target used by [LogErrorReporter]. Logging configurations can route this target to a
+/// dedicated appender to separate internal errors from other log output.
+pub const INTERNAL_ERROR_LOG_TARGET: &str = "em::internal_error";
+
+/// Global [ErrorReporter] used when an [ApiResponse][crate::api::ApiResponse] is created for an
+/// internal error. Falls back to [LogErrorReporter] if no reporter has been set.
+static ERROR_REPORTER: OnceLock<Box<dyn ErrorReporter>> = OnceLock::new();
+
+/// Hook invoked for internal errors returned from an API endpoint. User input failures are not
+/// reported, so implementations can forward true internal errors to alerting systems without the
+/// noise of bad requests.
+pub trait ErrorReporter
+where
+    Self: Send + Sync,
+{
+    /// Report the internal `error`
+    fn report(&self, error: &EmError);
+}
+
+/// Default [ErrorReporter] that logs the internal error under the [INTERNAL_ERROR_LOG_TARGET]
+pub struct LogErrorReporter;
+
+impl ErrorReporter for LogErrorReporter {
+    fn report(&self, error: &EmError) {
+        error!(target: INTERNAL_ERROR_LOG_TARGET, "{error}");
+    }
+}
+
+/// Set the global [ErrorReporter] used for internal API errors. Should be called once during
+/// application startup before the API server is spawned.
+/// # Errors
+/// This function will return an error if a reporter has already been set
+pub fn set_error_reporter<R>(reporter: R) -> EmResult<()>
+where
+    R: ErrorReporter + 'static,
+{
+    ERROR_REPORTER
+        .set(Box::new(reporter))
+        .map_err(|_| "The API error reporter has already been set".into())
+}
+
+/// Report the internal `error` using the global [ErrorReporter]
+pub(crate) fn report_error(error: &EmError) {
+    match ERROR_REPORTER.get() {
+        Some(reporter) => reporter.report(error),
+        None => LogErrorReporter.report(error),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::sync::Mutex;
+
+    use rstest::rstest;
+
+    use super::{set_error_reporter, ErrorReporter, ERROR_REPORTER};
+    use crate::{
+        api::{ApiContentFormat, ApiResponse},
+        error::EmError,
+    };
+
+    /// Messages of every error reported by the [RecordingErrorReporter]
+    static REPORTED_ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    /// [ErrorReporter] that records each reported error message for inspection
+    struct RecordingErrorReporter;
+
+    impl ErrorReporter for RecordingErrorReporter {
+        fn report(&self, error: &EmError) {
+            REPORTED_ERRORS.lock().unwrap().push(error.to_string());
+        }
+    }
+
+    /// Install the [RecordingErrorReporter] if no reporter has been set yet
+    fn install_reporter() {
+        if ERROR_REPORTER.get().is_none() {
+            let _ = set_error_reporter(RecordingErrorReporter);
+        }
+    }
+
+    /// Returns true if an error containing `message` has been reported
+    fn was_reported(message: &str) -> bool {
+        REPORTED_ERRORS
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|reported| reported.contains(message))
+    }
+
+    #[rstest]
+    #[case::self_test(EmError::SelfTest("reporter_self_test_error".to_owned()), "reporter_self_test_error")]
+    #[case::exited_task(EmError::ExitedTask, "Exited remote task run unexpectedly")]
+    fn error_should_invoke_reporter_when_error_is_internal(
+        #[case] error: EmError,
+        #[case] message: &str,
+    ) {
+        install_reporter();
+
+        let _response = ApiResponse::<()>::error(error, ApiContentFormat::Json);
+
+        assert!(was_reported(message));
+    }
+
+    #[rstest]
+    #[case::generic(EmError::Generic("reporter_generic_failure".to_owned()), "reporter_generic_failure")]
+    #[case::missing_record(
+        EmError::MissingRecord { pk: "reporter_missing_record".to_owned() },
+        "reporter_missing_record",
+    )]
+    fn error_should_not_invoke_reporter_when_error_is_failure(
+        #[case] error: EmError,
+        #[case] message: &str,
+    ) {
+        install_reporter();
+
+        let _response = ApiResponse::<()>::error(error, ApiContentFormat::Json);
+
+        assert!(!was_reported(message));
+    }
+}
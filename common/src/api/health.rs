@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use actix_web::{
+    dev::HttpServiceFactory,
+    http::StatusCode,
+    web::{self, Data},
+    CustomizeResponder, Responder,
+};
+use log::error;
+
+use crate::{
+    api::{ApiResponse, QueryApiFormat},
+    database::Database,
+};
+
+/// Maximum amount of time the readiness probe waits for the database to respond before reporting
+/// the service as unavailable
+const READY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Service factory for the liveness and readiness probes of an API server. The routes are mounted
+/// at the root of the application (i.e. `/health` and `/ready`) rather than under the versioned
+/// `/api/v1` scope so orchestration tools can probe the server without knowledge of the API
+/// version. The readiness check requires the [Database::ConnectionPool] to be registered as app
+/// data.
+pub fn service<D>() -> impl HttpServiceFactory
+where
+    D: Database + 'static,
+    D::ConnectionPool: Send + Sync + 'static,
+{
+    (
+        web::resource("/health").route(web::get().to(health)),
+        web::resource("/ready").route(web::get().to(ready::<D>)),
+    )
+}
+
+/// API endpoint to check if the server process is up. Always returns a 200 response
+#[allow(clippy::unused_async)]
+async fn health(query: web::Query<QueryApiFormat>) -> ApiResponse<()> {
+    let format = query.into_inner();
+    ApiResponse::message("OK".to_owned(), format.f)
+}
+
+/// API endpoint to check if the server is able to handle requests. Runs a lightweight query
+/// against the database, returning a 503 response if the database cannot be reached within
+/// [READY_TIMEOUT].
+async fn ready<D>(
+    pool: Data<D::ConnectionPool>,
+    query: web::Query<QueryApiFormat>,
+) -> CustomizeResponder<ApiResponse<()>>
+where
+    D: Database,
+{
+    let format = query.into_inner();
+    match tokio::time::timeout(READY_TIMEOUT, D::ping(&pool)).await {
+        Ok(Ok(_)) => ApiResponse::message("Ready".to_owned(), format.f).customize(),
+        Ok(Err(error)) => {
+            error!("Readiness check failed. {error}");
+            ApiResponse::failure("Database cannot be reached", format.f)
+                .customize()
+                .with_status(StatusCode::SERVICE_UNAVAILABLE)
+        }
+        Err(_) => ApiResponse::failure("Database readiness check timed out", format.f)
+            .customize()
+            .with_status(StatusCode::SERVICE_UNAVAILABLE),
+    }
+}
@@ -0,0 +1,209 @@
+use std::{env, sync::OnceLock, time::Duration};
+
+use log::{error, warn};
+use reqwest::Client;
+
+use crate::error::{EmError, EmResult};
+
+/// Default maximum duration (in seconds) to establish a connection
+const DEFAULT_CONNECT_TIMEOUT: u64 = 10;
+/// Default maximum duration (in seconds) of a request made with the [shared_client]
+const DEFAULT_REQUEST_TIMEOUT: u64 = 30;
+/// Default duration (in seconds) that idle connections are kept alive for reuse
+const DEFAULT_KEEP_ALIVE: u64 = 90;
+/// Default maximum duration (in seconds) a response stream can go without sending data when read
+/// using the [streaming_client]
+const DEFAULT_STREAM_IDLE_TIMEOUT: u64 = 300;
+
+/// Configuration of the shared HTTP clients, read once from the environment
+static HTTP_CLIENT_CONFIG: OnceLock<HttpClientConfig> = OnceLock::new();
+/// Client shared by all outbound requests that have a total request deadline
+static SHARED_CLIENT: OnceLock<Client> = OnceLock::new();
+/// Client shared by all outbound requests whose response is a long-lived stream
+static STREAMING_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Timeouts and keep-alive settings of the outbound HTTP clients. Each value is a whole number of
+/// seconds when read from the environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpClientConfig {
+    /// Maximum duration to establish a connection
+    pub connect_timeout: Duration,
+    /// Maximum total duration of a request made with the [shared_client]
+    pub request_timeout: Duration,
+    /// Duration that idle pooled connections (and TCP keep-alive probes) are kept for reuse
+    pub keep_alive: Duration,
+    /// Maximum duration a response stream read with the [streaming_client] can go without sending
+    /// data
+    pub stream_idle_timeout: Duration,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT),
+            request_timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT),
+            keep_alive: Duration::from_secs(DEFAULT_KEEP_ALIVE),
+            stream_idle_timeout: Duration::from_secs(DEFAULT_STREAM_IDLE_TIMEOUT),
+        }
+    }
+}
+
+impl HttpClientConfig {
+    /// Create a new [HttpClientConfig] from environment variables, using the default value for any
+    /// variable that is not set. The environment variables read are:
+    /// - EM_HTTP_CONNECT_TIMEOUT -> connect timeout in seconds (default 10)
+    /// - EM_HTTP_REQUEST_TIMEOUT -> total request timeout in seconds (default 30)
+    /// - EM_HTTP_KEEP_ALIVE -> idle connection keep-alive in seconds (default 90)
+    /// - EM_HTTP_STREAM_IDLE_TIMEOUT -> response stream idle timeout in seconds (default 300)
+    /// # Errors
+    /// This function will return an error if an environment variable cannot be parsed
+    pub fn from_env() -> EmResult<Self> {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Create a new [HttpClientConfig] using the `lookup` function to find each configuration
+    /// value by name. Values that are not found are replaced with their default.
+    /// # Errors
+    /// This function will return an error if a value cannot be parsed
+    fn from_lookup<F>(lookup: F) -> EmResult<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let defaults = Self::default();
+        let seconds = |key: &str, default: Duration| -> EmResult<Duration> {
+            match lookup(key) {
+                Some(value) => Ok(Duration::from_secs(value.parse()?)),
+                None => Ok(default),
+            }
+        };
+        Ok(Self {
+            connect_timeout: seconds("EM_HTTP_CONNECT_TIMEOUT", defaults.connect_timeout)?,
+            request_timeout: seconds("EM_HTTP_REQUEST_TIMEOUT", defaults.request_timeout)?,
+            keep_alive: seconds("EM_HTTP_KEEP_ALIVE", defaults.keep_alive)?,
+            stream_idle_timeout: seconds(
+                "EM_HTTP_STREAM_IDLE_TIMEOUT",
+                defaults.stream_idle_timeout,
+            )?,
+        })
+    }
+
+    /// Build a [Client] using the connect timeout and keep-alive of this configuration. If
+    /// `request_timeout` is true, the total request timeout is also applied.
+    fn build_client(&self, request_timeout: bool) -> Client {
+        let mut builder = Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .pool_idle_timeout(self.keep_alive)
+            .tcp_keepalive(self.keep_alive);
+        if request_timeout {
+            builder = builder.timeout(self.request_timeout);
+        }
+        builder.build().unwrap_or_else(|error| {
+            error!("Could not build the configured HTTP client, using the default client. {error}");
+            Client::new()
+        })
+    }
+}
+
+/// Get the [HttpClientConfig] read from the environment. If the environment contains invalid
+/// values, the default configuration is used.
+pub fn http_client_config() -> &'static HttpClientConfig {
+    HTTP_CLIENT_CONFIG.get_or_init(|| {
+        HttpClientConfig::from_env().unwrap_or_else(|error| {
+            warn!("Invalid HTTP client configuration, using the defaults. {error}");
+            HttpClientConfig::default()
+        })
+    })
+}
+
+/// Get the [Client] shared by all outbound requests. The client is created on first use with the
+/// connect, request and keep-alive settings of the [http_client_config] so connections are pooled
+/// across requests. Cloning the client is cheap since the connection pool is reference counted.
+pub fn shared_client() -> Client {
+    SHARED_CLIENT
+        .get_or_init(|| http_client_config().build_client(true))
+        .clone()
+}
+
+/// Get the [Client] shared by all outbound requests that read long-lived response streams. Unlike
+/// the [shared_client], no total request timeout is applied so a slow but progressing stream is
+/// not interrupted. Callers should instead apply the
+/// [stream_idle_timeout][HttpClientConfig::stream_idle_timeout] between stream reads.
+pub fn streaming_client() -> Client {
+    STREAMING_CLIENT
+        .get_or_init(|| http_client_config().build_client(false))
+        .clone()
+}
+
+/// Check if the request `error` means the remote service could not be reached, i.e. a connection
+/// could not be established or the request timed out before a response was received
+pub fn is_unavailable_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout()
+}
+
+/// Convert an outbound request `error` into an [EmError]. Errors where the `service` could not be
+/// reached (see [is_unavailable_error]) become [EmError::ServiceUnavailable] so callers can tell
+/// a service that is down apart from other request failures. All other errors are kept as
+/// [EmError::Reqwest].
+pub fn request_error(service: &str, error: reqwest::Error) -> EmError {
+    if is_unavailable_error(&error) {
+        return EmError::ServiceUnavailable(format!("{service} cannot be reached. {error}"));
+    }
+    EmError::Reqwest(error)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::time::Duration;
+
+    use super::{request_error, shared_client, HttpClientConfig};
+    use crate::{
+        error::EmError,
+        test::{env_lookup, refused_url},
+    };
+
+    #[test]
+    fn from_lookup_should_use_defaults_when_not_set() {
+        let config = HttpClientConfig::from_lookup(env_lookup(&[])).unwrap();
+
+        assert_eq!(config, HttpClientConfig::default());
+    }
+
+    #[test]
+    fn from_lookup_should_override_defaults_when_set() {
+        let config = HttpClientConfig::from_lookup(env_lookup(&[
+            ("EM_HTTP_CONNECT_TIMEOUT", "5"),
+            ("EM_HTTP_STREAM_IDLE_TIMEOUT", "60"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.connect_timeout, Duration::from_secs(5));
+        assert_eq!(config.stream_idle_timeout, Duration::from_secs(60));
+        assert_eq!(
+            config.request_timeout,
+            HttpClientConfig::default().request_timeout
+        );
+    }
+
+    #[test]
+    fn from_lookup_should_fail_when_value_is_not_a_number() {
+        let result =
+            HttpClientConfig::from_lookup(env_lookup(&[("EM_HTTP_REQUEST_TIMEOUT", "30s")]));
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn request_error_should_be_service_unavailable_when_connection_refused() {
+        let error = shared_client()
+            .get(refused_url().unwrap())
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            request_error("test API", error),
+            EmError::ServiceUnavailable(_)
+        ));
+    }
+}
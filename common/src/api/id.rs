@@ -0,0 +1,98 @@
+use actix_web::web::PathConfig;
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::{
+    api::request::api_request_error,
+    error::{EmError, EmResult},
+};
+
+/// Validate that the `value` of a record id named `kind` is positive. Ids are generated by
+/// identity columns so zero and negative values never reference a record.
+/// # Errors
+/// This function will return an [EmError::InvalidId] if the `value` is not positive
+pub fn validate_id(kind: &'static str, value: i64) -> EmResult<i64> {
+    if value <= 0 {
+        return Err(EmError::InvalidId {
+            kind,
+            value: value.to_string(),
+            reason: "Ids must be positive",
+        });
+    }
+    Ok(value)
+}
+
+/// Parse the text `value` of a record id named `kind`. Surrounding whitespace is ignored.
+/// # Errors
+/// This function will return an [EmError::InvalidId] if the `value` is not a number or the number
+/// is not positive
+pub fn parse_id(kind: &'static str, value: &str) -> EmResult<i64> {
+    let Ok(id) = value.trim().parse::<i64>() else {
+        return Err(EmError::InvalidId {
+            kind,
+            value: value.to_owned(),
+            reason: "Ids must be a whole number",
+        });
+    };
+    validate_id(kind, id)
+}
+
+/// Deserialize a record id named `kind`, rejecting ids that are not positive (see
+/// [validate_id])
+/// # Errors
+/// This function will return an error if the value is not an integer or is not positive
+pub fn deserialize_id<'de, D>(kind: &'static str, deserializer: D) -> Result<i64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = i64::deserialize(deserializer)?;
+    validate_id(kind, value).map_err(D::Error::custom)
+}
+
+/// [PathConfig] that converts path extraction failures (e.g. an invalid id) into an
+/// [ApiResponse][crate::api::ApiResponse] failure rather than the default plain text response.
+/// Should be registered as app data for every API server.
+pub fn path_config() -> PathConfig {
+    PathConfig::default().error_handler(|error, req| {
+        api_request_error(
+            EmError::InvalidRequest {
+                request: req.path().to_owned(),
+                reason: format!("Path is not valid. {error}"),
+            },
+            req,
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::parse_id;
+    use crate::error::EmError;
+
+    #[rstest]
+    #[case::positive("1", 1)]
+    #[case::whitespace(" 42 ", 42)]
+    fn parse_id_should_succeed_when(#[case] value: &str, #[case] expected: i64) {
+        let result = parse_id("workflow_id", value);
+
+        assert!(matches!(result, Ok(id) if id == expected));
+    }
+
+    #[rstest]
+    #[case::zero("0")]
+    #[case::negative("-5")]
+    #[case::non_numeric("abc")]
+    #[case::empty("")]
+    fn parse_id_should_fail_when(#[case] value: &str) {
+        let result = parse_id("workflow_id", value);
+
+        assert!(matches!(
+            result,
+            Err(EmError::InvalidId {
+                kind: "workflow_id",
+                ..
+            })
+        ));
+    }
+}
@@ -1,33 +1,93 @@
+pub mod error_reporter;
+pub mod health;
+pub mod http_client;
+pub mod id;
+pub mod pagination;
 pub mod request;
+pub mod request_id;
+pub mod sort;
+pub mod tls;
 
-use std::fmt::Debug;
+use std::{env, fmt::Debug, io::Write, sync::OnceLock};
 
-use actix_web::Responder;
+use actix_web::{
+    http::{
+        header::{ACCEPT_ENCODING, CONTENT_ENCODING, VARY},
+        StatusCode,
+    },
+    HttpRequest, HttpResponse, Responder,
+};
+use flate2::{write::GzEncoder, Compression};
 use log::{error, warn};
 use serde::{Deserialize, Serialize};
 
-use crate::error::{EmError, EmResult};
+use crate::{
+    api::{error_reporter::report_error, request::ApiRequestPayloadError},
+    error::{EmError, EmResult},
+};
+
+/// Minimum size (in bytes) of a serialized response body before the body is compressed for
+/// clients that accept gzip encoding. Smaller bodies are sent uncompressed to avoid the overhead.
+pub const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Environment variable containing the [ApiContentFormat] used when a request does not specify a
+/// format. Accepts the same values as the `?f=` query parameter (`json` or `msgpack`).
+pub const DEFAULT_FORMAT_ENV: &str = "EM_DEFAULT_FORMAT";
+
+/// Default [ApiContentFormat] of the process, read once from [DEFAULT_FORMAT_ENV]
+static DEFAULT_CONTENT_FORMAT: OnceLock<ApiContentFormat> = OnceLock::new();
 
 /// Deserializable wrapper for allowing an API caller to send back content of an [ApiResponse].
 /// This type should be used in a route handler to deserialize a url query with the template of
-/// `?f={format}`.
+/// `?f={format}`. When `f` is not supplied, the [ApiContentFormat::default] is used.
 #[derive(Deserialize, Default)]
 pub struct QueryApiFormat {
+    #[serde(default)]
     pub f: ApiContentFormat,
 }
 
 /// Format variants that an [ApiResponse] supports for serialization and deserialization of API
 /// content
-#[derive(Default, Deserialize, Clone, Copy)]
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ApiContentFormat {
     #[serde(rename = "json")]
     Json,
-    #[default]
     #[serde(rename = "msgpack")]
     MessagePack,
 }
 
+impl Default for ApiContentFormat {
+    /// Format configured by the [DEFAULT_FORMAT_ENV] environment variable, falling back to
+    /// [ApiContentFormat::MessagePack] when the variable is not set or not valid
+    fn default() -> Self {
+        *DEFAULT_CONTENT_FORMAT.get_or_init(|| Self::default_from_lookup(|key| env::var(key).ok()))
+    }
+}
+
 impl ApiContentFormat {
+    /// Parse a format from the name used in the `?f=` query parameter
+    fn from_name(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "msgpack" => Some(Self::MessagePack),
+            _ => None,
+        }
+    }
+
+    /// Resolve the default format using `lookup` to read the [DEFAULT_FORMAT_ENV] variable
+    fn default_from_lookup<F>(lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let Some(value) = lookup(DEFAULT_FORMAT_ENV) else {
+            return Self::MessagePack;
+        };
+        Self::from_name(&value).unwrap_or_else(|| {
+            warn!("Unknown {DEFAULT_FORMAT_ENV} value '{value}'. Defaulting to msgpack");
+            Self::MessagePack
+        })
+    }
+
     fn from_mime(value: &mime::Mime) -> Option<Self> {
         if value.subtype() == mime::JSON || value.suffix() == Some(mime::JSON) {
             return Some(Self::Json);
@@ -52,7 +112,10 @@ pub enum ApiResponseBody<T: Serialize> {
 
 /// API response object to enable serializing a `body` using the specified `format`. This type
 /// can be used as a [Responder] for HTTP route handlers, always returning a 200 response unless
-/// the serialization of the `body` fails.
+/// the serialization of the `body` fails. Handlers that want error statuses to reflect the
+/// [EmError] should use [ApiResponse::into_http_with_status] instead. Serialized bodies larger than
+/// [COMPRESSION_THRESHOLD] are gzip compressed when the request's `Accept-Encoding` header allows
+/// it.
 #[derive(Serialize, Deserialize)]
 pub struct ApiResponse<T: Serialize> {
     #[serde(skip)]
@@ -86,15 +149,63 @@ where
                     .body(message.into_bytes());
             }
         };
-        actix_web::HttpResponse::Ok()
+        let mut builder = actix_web::HttpResponse::Ok();
+        builder
             .content_type(actix_web::http::header::ContentType(match self.format {
                 ApiContentFormat::Json => mime::APPLICATION_JSON,
                 ApiContentFormat::MessagePack => mime::APPLICATION_MSGPACK,
             }))
-            .body(bytes.into_iter().collect::<actix_web::web::Bytes>())
+            .insert_header((VARY, "Accept-Encoding"));
+        if bytes.len() <= COMPRESSION_THRESHOLD || !accepts_gzip(req) {
+            return builder.body(bytes);
+        }
+        match gzip(&bytes) {
+            Ok(compressed) => builder
+                .insert_header((CONTENT_ENCODING, "gzip"))
+                .body(compressed),
+            Err(error) => {
+                warn!(
+                    "Could not compress response for {}. Sending uncompressed. Error: {}",
+                    req.path(),
+                    error
+                );
+                builder.body(bytes)
+            }
+        }
     }
 }
 
+/// Returns true if the `Accept-Encoding` header of the `req` includes gzip with a non-zero
+/// quality value
+fn accepts_gzip(req: &HttpRequest) -> bool {
+    let Some(accept_encoding) = req
+        .headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    accept_encoding.split(',').any(|encoding| {
+        let (coding, params) = encoding.split_once(';').unwrap_or((encoding, ""));
+        let quality = params
+            .trim()
+            .strip_prefix("q=")
+            .and_then(|quality| quality.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        coding.trim().eq_ignore_ascii_case("gzip") && quality > 0.0
+    })
+}
+
+/// Compress the `bytes` using gzip
+/// # Errors
+/// This function will return an error if the encoder fails to write or finish the compressed
+/// output
+fn gzip(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(bytes.len() / 2), Compression::default());
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
 impl<T: Serialize> ApiResponse<T> {
     /// Generate an [ApiResponse] wrapping a [ApiResponseBody::Success]`
     pub const fn success(data: T, format: ApiContentFormat) -> Self {
@@ -126,48 +237,295 @@ impl<T: Serialize> ApiResponse<T> {
     /// Generate an [ApiResponse] for operations that return an [EmError]. Some [EmError] variants
     /// are downgraded to a [Failure][ApiResponseBody::Failure] if the `error` does not indicate an
     /// internal but rather bad user provided data or an error message the user could understand.
+    /// Only internal errors are passed to the configured
+    /// [ErrorReporter][error_reporter::ErrorReporter].
     pub fn error(error: EmError, format: ApiContentFormat) -> Self {
-        error!("{}", error);
         match error {
             EmError::Generic(message) => Self::failure(message, format),
             EmError::InvalidUser
             | EmError::MissingRecord { .. }
+            | EmError::InvalidId { .. }
             | EmError::InvalidRequest { .. }
             | EmError::InvalidPassword { .. }
-            | EmError::MissingPrivilege { .. } => Self::failure(format!("{error}"), format),
-            EmError::RmpDecode(_) => Self::failure("Could not decode the request object", format),
-            _ => Self {
-                format,
-                body: ApiResponseBody::Error(
-                    "Could not perform the required action due to an internal error".to_owned(),
-                ),
-            },
+            | EmError::MissingPrivilege { .. }
+            | EmError::ServiceUnavailable(_)
+            | EmError::ApiRequestPayload(_) => Self::failure(format!("{error}"), format),
+            EmError::RmpDecode(_) => {
+                warn!("{error}");
+                Self::failure("Could not decode the request object", format)
+            }
+            _ => {
+                report_error(&error);
+                Self {
+                    format,
+                    body: ApiResponseBody::Error(
+                        "Could not perform the required action due to an internal error".to_owned(),
+                    ),
+                }
+            }
+        }
+    }
+}
+
+impl<T> ApiResponse<T>
+where
+    T: Serialize + 'static,
+{
+    /// Generate an [HttpResponse] for an operation that returned an [EmError]. The body is the
+    /// same as [ApiResponse::error] but, rather than always returning a 200 response, the status
+    /// code is mapped from the `error` variant using [error_status_code].
+    pub fn into_http_with_status(
+        error: EmError,
+        format: ApiContentFormat,
+        req: &HttpRequest,
+    ) -> HttpResponse {
+        let status = error_status_code(&error);
+        let mut response = Self::error(error, format).respond_to(req);
+        if response.status().is_success() {
+            *response.status_mut() = status;
         }
+        response
     }
 }
 
+/// Map the `error` to the HTTP status code that best describes the failure. User input issues map
+/// to 4xx status codes while internal errors map to a `500 Internal Server Error`.
+pub const fn error_status_code(error: &EmError) -> StatusCode {
+    match error {
+        EmError::InvalidUser => StatusCode::UNAUTHORIZED,
+        EmError::MissingPrivilege { .. } => StatusCode::FORBIDDEN,
+        EmError::MissingRecord { .. } => StatusCode::NOT_FOUND,
+        EmError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        EmError::ApiRequestPayload(
+            ApiRequestPayloadError::Overflow { .. }
+            | ApiRequestPayloadError::OverflowKnownLength { .. },
+        ) => StatusCode::PAYLOAD_TOO_LARGE,
+        EmError::InvalidId { .. }
+        | EmError::InvalidRequest { .. }
+        | EmError::InvalidPassword { .. }
+        | EmError::InvalidCronExpression { .. }
+        | EmError::ApiRequestPayload(_)
+        | EmError::RmpDecode(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Prefix of each item when multiple validation messages are joined into a single message
+pub const VALIDATION_MESSAGE_ITEM_PREFIX: &str = "- ";
+
+/// Join the validation `messages` into a single readable message. A single message is returned
+/// as is, while multiple messages are listed on separate lines, each prefixed by
+/// [VALIDATION_MESSAGE_ITEM_PREFIX].
+pub fn join_validation_messages<S>(messages: Vec<S>) -> String
+where
+    S: Into<String>,
+{
+    let mut messages: Vec<String> = messages.into_iter().map(Into::into).collect();
+    if messages.len() == 1 {
+        return messages.pop().unwrap_or_default();
+    }
+    let mut joined = format!("{} problems found", messages.len());
+    for message in messages {
+        joined.push('\n');
+        joined.push_str(VALIDATION_MESSAGE_ITEM_PREFIX);
+        joined.push_str(&message);
+    }
+    joined
+}
+
 /// Validator for api requests that should have the request data verified
 pub trait ApiRequestValidator {
-    /// Type of the error message that is returned by the [validate][ApiRequestValidator::validate]
-    /// method. Must be able to converted to a [String].
+    /// Type of the error messages that are returned by the
+    /// [validate][ApiRequestValidator::validate] method. Must be able to converted to a [String].
     type ErrorMessage: Into<String>;
     /// Type of request this validator is processing. Must implement debug to convert into an
     /// [EmError] type.
     type Request: Debug;
     /// Perform checks against the `request` to confirm it meets specified requirements. Returns an
-    /// [Err] of a type that can be converted into a [String] if the request is not valid. Otherwise
-    /// [Ok] is returned.
+    /// [Err] containing every violation found (as types that can be converted into a [String]) if
+    /// the request is not valid. Otherwise [Ok] is returned.
     /// # Errors
     /// This function will return an error if the `request` cannot be validated
-    fn validate(request: &Self::Request) -> Result<(), Self::ErrorMessage>;
-    /// Performs the implemented validation against the `request`, mapping the error (if any) into a
-    /// specific validation [EmError]. If the validation succeeds, [Ok] is returned.
+    fn validate(request: &Self::Request) -> Result<(), Vec<Self::ErrorMessage>>;
+    /// Performs the implemented validation against the `request`, mapping the errors (if any) into
+    /// a specific validation [EmError] that lists every message. If the validation succeeds, [Ok]
+    /// is returned.
     /// # Errors
     /// This function will return an error if the `request` cannot be validated
     fn validate_request(request: &Self::Request) -> EmResult<()> {
-        if let Err(error) = Self::validate(request) {
-            return Err((request, error).into());
+        if let Err(errors) = Self::validate(request) {
+            return Err((request, join_validation_messages(errors)).into());
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::io::Read;
+
+    use actix_web::{
+        body::to_bytes,
+        http::{header::CONTENT_ENCODING, StatusCode},
+        test::TestRequest,
+        web, Responder,
+    };
+    use flate2::read::GzDecoder;
+    use rstest::rstest;
+
+    use super::{
+        join_validation_messages, ApiContentFormat, ApiResponse, ApiResponseBody, QueryApiFormat,
+        COMPRESSION_THRESHOLD, DEFAULT_FORMAT_ENV,
+    };
+    use crate::{api::request::ApiRequestPayloadError, error::EmError, test::env_lookup};
+
+    #[rstest]
+    #[case::unset(None, ApiContentFormat::MessagePack)]
+    #[case::json(Some("json"), ApiContentFormat::Json)]
+    #[case::json_uppercase(Some("JSON"), ApiContentFormat::Json)]
+    #[case::msgpack(Some("msgpack"), ApiContentFormat::MessagePack)]
+    #[case::invalid(Some("xml"), ApiContentFormat::MessagePack)]
+    fn default_from_lookup_should_return(
+        #[case] value: Option<&str>,
+        #[case] expected: ApiContentFormat,
+    ) {
+        let pairs: Vec<(&str, &str)> = value
+            .map(|value| (DEFAULT_FORMAT_ENV, value))
+            .into_iter()
+            .collect();
+
+        let format = ApiContentFormat::default_from_lookup(env_lookup(&pairs));
+
+        assert_eq!(format, expected);
+    }
+
+    #[test]
+    fn query_api_format_should_use_configured_default_when_format_not_supplied() {
+        let query = web::Query::<QueryApiFormat>::from_query("").unwrap();
+
+        assert_eq!(query.f, ApiContentFormat::default());
+        assert_eq!(QueryApiFormat::default().f, ApiContentFormat::default());
+    }
+
+    #[test]
+    fn query_api_format_should_use_supplied_format() {
+        let query = web::Query::<QueryApiFormat>::from_query("f=json").unwrap();
+
+        assert_eq!(query.f, ApiContentFormat::Json);
+    }
+
+    /// Create a message that serializes to a body larger than the [COMPRESSION_THRESHOLD]
+    fn large_message() -> String {
+        "workflow run ".repeat(COMPRESSION_THRESHOLD)
+    }
+
+    #[tokio::test]
+    async fn respond_to_should_compress_body_when_gzip_accepted_and_body_is_large() {
+        let message = large_message();
+        let request = TestRequest::get()
+            .insert_header(("Accept-Encoding", "deflate, gzip;q=0.8"))
+            .to_http_request();
+
+        let response =
+            ApiResponse::success(message.clone(), ApiContentFormat::Json).respond_to(&request);
+
+        let encoding = response.headers().get(CONTENT_ENCODING).unwrap();
+        assert_eq!(encoding, "gzip");
+        let bytes = to_bytes(response.into_body()).await.unwrap();
+        let mut decoded = Vec::new();
+        GzDecoder::new(bytes.as_ref())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        let body: ApiResponseBody<String> = serde_json::from_slice(&decoded).unwrap();
+        assert!(matches!(body, ApiResponseBody::Success(data) if data == message));
+    }
+
+    #[rstest]
+    #[case::small_body("message".to_owned(), Some("gzip"))]
+    #[case::gzip_not_accepted(large_message(), None)]
+    #[case::gzip_refused(large_message(), Some("gzip;q=0"))]
+    #[case::other_encoding(large_message(), Some("br"))]
+    fn respond_to_should_not_compress_body_when(
+        #[case] message: String,
+        #[case] accept_encoding: Option<&str>,
+    ) {
+        let mut request = TestRequest::get();
+        if let Some(accept_encoding) = accept_encoding {
+            request = request.insert_header(("Accept-Encoding", accept_encoding));
+        }
+
+        let response = ApiResponse::success(message, ApiContentFormat::MessagePack)
+            .respond_to(&request.to_http_request());
+
+        assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    }
+
+    #[rstest]
+    #[case::single(vec!["Name cannot be empty"], "Name cannot be empty")]
+    #[case::multiple(
+        vec!["Name cannot be empty", "Url cannot be empty"],
+        "2 problems found\n- Name cannot be empty\n- Url cannot be empty",
+    )]
+    fn join_validation_messages_should_list_messages_when(
+        #[case] messages: Vec<&str>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(join_validation_messages(messages), expected);
+    }
+
+    #[rstest]
+    #[case::invalid_user(EmError::InvalidUser, StatusCode::UNAUTHORIZED)]
+    #[case::missing_privilege(
+        EmError::MissingPrivilege { uid: uuid::Uuid::nil(), role: "admin" },
+        StatusCode::FORBIDDEN,
+    )]
+    #[case::missing_record(EmError::MissingRecord { pk: "1".to_owned() }, StatusCode::NOT_FOUND)]
+    #[case::invalid_password(
+        EmError::InvalidPassword { reason: "Must not be empty".to_owned() },
+        StatusCode::BAD_REQUEST,
+    )]
+    #[case::invalid_id(
+        EmError::InvalidId { kind: "job_id", value: "-1".to_owned(), reason: "Ids must be positive" },
+        StatusCode::BAD_REQUEST,
+    )]
+    #[case::invalid_cron_expression(
+        EmError::InvalidCronExpression {
+            expression: "* *".to_owned(),
+            reason: "Expected 5 fields".to_owned(),
+        },
+        StatusCode::BAD_REQUEST,
+    )]
+    #[case::payload_too_large(
+        EmError::ApiRequestPayload(ApiRequestPayloadError::Overflow { limit: 16 }),
+        StatusCode::PAYLOAD_TOO_LARGE,
+    )]
+    #[case::service_unavailable(
+        EmError::ServiceUnavailable("users API cannot be reached".to_owned()),
+        StatusCode::SERVICE_UNAVAILABLE,
+    )]
+    #[case::invalid_request(
+        EmError::InvalidRequest { request: "page".to_owned(), reason: "Bad limit".to_owned() },
+        StatusCode::BAD_REQUEST,
+    )]
+    #[case::generic(EmError::Generic("Unexpected state".to_owned()), StatusCode::INTERNAL_SERVER_ERROR)]
+    #[case::internal(EmError::ExitedTask, StatusCode::INTERNAL_SERVER_ERROR)]
+    #[tokio::test]
+    async fn into_http_with_status_should_map_status_when(
+        #[case] error: EmError,
+        #[case] expected: StatusCode,
+    ) {
+        let request = TestRequest::get().to_http_request();
+
+        let response =
+            ApiResponse::<()>::into_http_with_status(error, ApiContentFormat::Json, &request);
+
+        assert_eq!(response.status(), expected);
+        let bytes = to_bytes(response.into_body()).await.unwrap();
+        let body: ApiResponseBody<()> = serde_json::from_slice(&bytes).unwrap();
+        assert!(matches!(
+            body,
+            ApiResponseBody::Failure(_) | ApiResponseBody::Error(_)
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::ApiRequestValidator,
+    error::{EmError, EmResult},
+};
+
+/// Default number of records included in a page requested with a [CursorPagination] when no
+/// `limit` is provided
+pub const DEFAULT_CURSOR_LIMIT: i64 = 100;
+/// Maximum number of records that can be included in a page requested with a [CursorPagination]
+pub const MAX_PAGE_SIZE: i64 = 1000;
+
+/// Bounds of a single page of records requested from a service. Records are skipped until the
+/// `offset` is reached and then at most `limit` records are returned.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    /// Maximum number of records to include in the page
+    pub limit: i64,
+    /// Number of records to skip before the page starts
+    pub offset: i64,
+}
+
+impl Pagination {
+    /// Create a new [Pagination] with the specified `limit` and `offset`
+    pub const fn new(limit: i64, offset: i64) -> Self {
+        Self { limit, offset }
+    }
+
+    /// Create a new [Pagination] that includes every record in a single page
+    pub const fn unbounded() -> Self {
+        Self {
+            limit: i64::MAX,
+            offset: 0,
+        }
+    }
+}
+
+/// Single page of records returned from a service. Contains the `items` within the requested
+/// [Pagination] bounds as well as the `total_count` of records available so a caller can calculate
+/// the number of pages.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Page<T> {
+    /// Records within the requested page
+    pub items: Vec<T>,
+    /// Total number of records available, ignoring the page bounds
+    pub total_count: i64,
+}
+
+impl<T> Page<T> {
+    /// Create a new [Page] of `items` with the `total_count` of available records
+    pub const fn new(items: Vec<T>, total_count: i64) -> Self {
+        Self { items, total_count }
+    }
+}
+
+/// Default `limit` of a [CursorPagination] when deserialized without a value
+const fn default_cursor_limit() -> i64 {
+    DEFAULT_CURSOR_LIMIT
+}
+
+/// Bounds of a single page of records requested using a cursor. Unlike [Pagination], the page
+/// starts after the record referenced by the opaque `after` cursor (or the first record if no
+/// cursor is provided), so records inserted or deleted between page requests do not cause records
+/// to be duplicated or skipped. Can be deserialized from a url query with the template of
+/// `?limit={limit}&after={cursor}`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CursorPagination {
+    /// Maximum number of records to include in the page
+    #[serde(default = "default_cursor_limit")]
+    pub limit: i64,
+    /// Cursor returned as the `next_cursor` of the previous [CursorPage]
+    #[serde(default)]
+    pub after: Option<String>,
+}
+
+impl CursorPagination {
+    /// Create a new [CursorPagination] with the specified `limit` and `after` cursor
+    pub const fn new(limit: i64, after: Option<String>) -> Self {
+        Self { limit, after }
+    }
+
+    /// Decode the `after` cursor into the key of the last record of the previous page. Returns
+    /// [None] if the page should start at the first record.
+    /// # Errors
+    /// This function will return an error if the `after` cursor is not a valid cursor
+    pub fn after_key(&self) -> EmResult<Option<i64>> {
+        self.after.as_deref().map(decode_cursor).transpose()
+    }
+
+    /// Number of records a service should fetch for this page. One more record than the `limit`
+    /// is fetched to know if another page exists.
+    pub const fn fetch_limit(&self) -> i64 {
+        self.limit.saturating_add(1)
+    }
+}
+
+/// Validator for a [CursorPagination] received by an api. The `limit` must be within
+/// `1..=MAX_PAGE_SIZE` and the `after` cursor (if any) must be a valid cursor.
+pub struct CursorPaginationValidator;
+
+impl ApiRequestValidator for CursorPaginationValidator {
+    type ErrorMessage = String;
+    type Request = CursorPagination;
+
+    fn validate(request: &Self::Request) -> Result<(), Vec<Self::ErrorMessage>> {
+        let mut errors = Vec::new();
+        if !(1..=MAX_PAGE_SIZE).contains(&request.limit) {
+            errors.push(format!(
+                "Request 'limit' must be between 1 and {MAX_PAGE_SIZE}. Got {}",
+                request.limit
+            ));
+        }
+        if request.after_key().is_err() {
+            errors.push("Request 'after' is not a valid pagination cursor".to_owned());
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(())
+    }
+}
+
+/// Encode the monotonic `key` of a record into an opaque cursor
+pub fn encode_cursor(key: i64) -> String {
+    format!("{:016x}", u64::from_be_bytes(key.to_be_bytes()))
+}
+
+/// Decode an opaque `cursor` created by [encode_cursor] back into the key of a record
+/// # Errors
+/// This function will return an error if the `cursor` was not created by [encode_cursor]
+pub fn decode_cursor(cursor: &str) -> EmResult<i64> {
+    let invalid_cursor = || EmError::InvalidRequest {
+        request: format!("Pagination cursor '{cursor}'"),
+        reason: "Cursor was not created by the api".to_owned(),
+    };
+    if cursor.len() != 16 || !cursor.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid_cursor());
+    }
+    let key = u64::from_str_radix(cursor, 16).map_err(|_| invalid_cursor())?;
+    Ok(i64::from_be_bytes(key.to_be_bytes()))
+}
+
+/// Single page of records returned from a service for a [CursorPagination] request. Contains the
+/// `items` of the page and the `next_cursor` to request the following page, which is [None] when
+/// no more records are available.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CursorPage<T> {
+    /// Records within the requested page
+    pub items: Vec<T>,
+    /// Cursor to pass as the `after` value of the next [CursorPagination]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> CursorPage<T> {
+    /// Create a new [CursorPage] from `items` fetched using [CursorPagination::fetch_limit]. If
+    /// more items than the `page` limit were fetched, the extra item is removed and the
+    /// `next_cursor` is set to the key of the last item in the page, as extracted by `key`.
+    pub fn from_items<F>(mut items: Vec<T>, page: &CursorPagination, key: F) -> Self
+    where
+        F: Fn(&T) -> i64,
+    {
+        let limit = usize::try_from(page.limit).unwrap_or_default();
+        if items.len() <= limit {
+            return Self {
+                items,
+                next_cursor: None,
+            };
+        }
+        items.truncate(limit);
+        let next_cursor = items.last().map(|item| encode_cursor(key(item)));
+        Self { items, next_cursor }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use rstest::rstest;
+
+    use super::{
+        decode_cursor, encode_cursor, CursorPage, CursorPagination, CursorPaginationValidator,
+        MAX_PAGE_SIZE,
+    };
+    use crate::{api::ApiRequestValidator, error::EmError};
+
+    #[rstest]
+    #[case::zero(0)]
+    #[case::positive(42)]
+    #[case::max(i64::MAX)]
+    #[case::negative(-1)]
+    fn decode_cursor_should_succeed_when_cursor_is_encoded(#[case] key: i64) {
+        let cursor = encode_cursor(key);
+
+        assert_eq!(decode_cursor(&cursor).unwrap(), key);
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::not_hex("zzzzzzzzzzzzzzzz")]
+    #[case::too_short("00ff")]
+    #[case::multi_byte("00000000000000é")]
+    fn decode_cursor_should_fail_when(#[case] cursor: &str) {
+        assert!(decode_cursor(cursor).is_err());
+    }
+
+    #[rstest]
+    #[case::more_items(vec![1, 2, 3], 2, vec![1, 2], Some(2))]
+    #[case::exact_items(vec![1, 2], 2, vec![1, 2], None)]
+    #[case::no_items(vec![], 2, vec![], None)]
+    fn cursor_page_should_set_next_cursor_when(
+        #[case] items: Vec<i64>,
+        #[case] limit: i64,
+        #[case] expected_items: Vec<i64>,
+        #[case] expected_key: Option<i64>,
+    ) {
+        let page = CursorPage::from_items(items, &CursorPagination::new(limit, None), |i| *i);
+
+        assert_eq!(page.items, expected_items);
+        assert_eq!(page.next_cursor, expected_key.map(encode_cursor));
+    }
+
+    #[rstest]
+    #[case::min(CursorPagination::new(1, None))]
+    #[case::max(CursorPagination::new(MAX_PAGE_SIZE, None))]
+    #[case::with_cursor(CursorPagination::new(10, Some(encode_cursor(5))))]
+    fn cursor_pagination_validator_should_succeed_when(#[case] page: CursorPagination) {
+        assert!(CursorPaginationValidator::validate_request(&page).is_ok());
+    }
+
+    #[rstest]
+    #[case::zero_limit(CursorPagination::new(0, None))]
+    #[case::negative_limit(CursorPagination::new(-1, None))]
+    #[case::limit_too_large(CursorPagination::new(MAX_PAGE_SIZE + 1, None))]
+    #[case::invalid_cursor(CursorPagination::new(10, Some("invalid".to_owned())))]
+    fn cursor_pagination_validator_should_fail_when(#[case] page: CursorPagination) {
+        let result = CursorPaginationValidator::validate_request(&page);
+
+        assert!(matches!(result, Err(EmError::InvalidRequest { .. })));
+    }
+}
@@ -8,16 +8,19 @@ use std::{
 };
 
 use actix_web::{
-    dev::Payload, error::PayloadError, http::header, web::BytesMut, FromRequest, HttpMessage,
-    HttpRequest,
+    dev::Payload,
+    error::{InternalError, PayloadError},
+    http::{header, StatusCode},
+    web::{BytesMut, Query},
+    FromRequest, HttpMessage, HttpRequest, Responder,
 };
 use futures::Stream;
 use log::error;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use thiserror::Error;
 
-use super::ApiContentFormat;
-use crate::error::{EmError, EmResult};
+use super::{error_status_code, ApiContentFormat, ApiResponse, QueryApiFormat};
+use crate::error::EmError;
 
 /// Generic API request containing the extracted body of a request object. This type is
 /// constrained to requests that have a `Content-Type` header that matches the labels in the
@@ -25,7 +28,10 @@ use crate::error::{EmError, EmResult};
 /// route handler body.
 ///
 /// If your request does match the expected `Content-Type` options, this type can be used in a
-/// route handler to extract the request body into the desired type `T`.
+/// route handler to extract the request body into the desired type `T`. The body is deserialized
+/// as JSON for `application/json` and MessagePack for `application/msgpack`. Any other
+/// `Content-Type` is rejected with an [ApiResponse] failure, using the response format requested
+/// in the `f` query parameter.
 #[derive(Serialize, Deserialize)]
 pub struct ApiRequest<T>(T);
 
@@ -69,7 +75,7 @@ pub enum ApiRequestPayloadError {
 }
 
 impl<T: DeserializeOwned> FromRequest for ApiRequest<T> {
-    type Error = EmError;
+    type Error = actix_web::Error;
     type Future = ApiRequestExtractFut<T>;
 
     #[inline]
@@ -104,7 +110,7 @@ pub struct ApiRequestExtractFut<T> {
 
 #[allow(clippy::unwrap_used)]
 impl<T: DeserializeOwned> Future for ApiRequestExtractFut<T> {
-    type Output = EmResult<ApiRequest<T>>;
+    type Output = Result<ApiRequest<T>, actix_web::Error>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
@@ -115,15 +121,16 @@ impl<T: DeserializeOwned> Future for ApiRequestExtractFut<T> {
             Err(err) => {
                 let req = this.req.take().unwrap();
                 log::debug!(
-                    "Failed to deserialize Json from payload. Request path: {}",
+                    "Failed to deserialize API request from payload. Request path: {}",
                     req.path()
                 );
 
-                if let Some(err_handler) = this.err_handler.as_ref() {
-                    Err((*err_handler)(err, &req))
+                let error = if let Some(err_handler) = this.err_handler.as_ref() {
+                    (*err_handler)(err, &req)
                 } else {
-                    Err(err.into())
-                }
+                    err.into()
+                };
+                Err(api_request_error(error, &req))
             }
             Ok(data) => Ok(ApiRequest(data)),
         };
@@ -132,7 +139,26 @@ impl<T: DeserializeOwned> Future for ApiRequestExtractFut<T> {
     }
 }
 
-/// `ApiRequest` extractor configuration.
+/// Convert an extraction `error` into an [actix_web::Error] whose response is an [ApiResponse]
+/// serialized in the format requested by the `req` query (defaults to MessagePack). Payloads that
+/// exceed the configured limit respond with a `413 Payload Too Large` status.
+pub(crate) fn api_request_error(error: EmError, req: &HttpRequest) -> actix_web::Error {
+    let format = Query::<QueryApiFormat>::from_query(req.query_string())
+        .map(|query| query.into_inner().f)
+        .unwrap_or_default();
+    let message = error.to_string();
+    let status = error_status_code(&error);
+    let mut response = ApiResponse::<()>::error(error, format).respond_to(req);
+    if status == StatusCode::PAYLOAD_TOO_LARGE {
+        *response.status_mut() = status;
+    }
+    InternalError::from_response(message, response).into()
+}
+
+/// `ApiRequest` extractor configuration. Register the config as app data of the `App` to set the
+/// limit globally or as app data of a scope or resource to override the limit for those routes.
+/// Payloads exceeding the limit are rejected with a `413 Payload Too Large` response before the
+/// rest of the body is buffered.
 #[derive(Clone)]
 pub struct ApiRequestConfig {
     limit: usize,
@@ -167,11 +193,13 @@ impl ApiRequestConfig {
     }
 }
 
-const DEFAULT_LIMIT: usize = 2_097_152; // 2 mb
+/// Default maximum size (in bytes) of an [ApiRequest] payload when no [ApiRequestConfig] is
+/// registered as app data
+pub const DEFAULT_API_REQUEST_LIMIT: usize = 2_097_152; // 2 mb
 
 /// Allow shared refs used as default.
 const DEFAULT_CONFIG: ApiRequestConfig = ApiRequestConfig {
-    limit: DEFAULT_LIMIT,
+    limit: DEFAULT_API_REQUEST_LIMIT,
     err_handler: None,
 };
 
@@ -227,7 +255,7 @@ impl<T: DeserializeOwned> ApiRequestBody<T> {
         // And limit check to return an error variant of JsonBody happens there.
 
         Self::Body {
-            limit: DEFAULT_LIMIT,
+            limit: DEFAULT_API_REQUEST_LIMIT,
             content_type: api_content_type,
             length,
             payload: payload.take(),
@@ -312,3 +340,66 @@ impl<T: DeserializeOwned> Future for ApiRequestBody<T> {
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use actix_web::{
+        http::{header, StatusCode},
+        test::TestRequest,
+        FromRequest,
+    };
+    use serde_json::Value;
+
+    use super::{ApiRequest, ApiRequestConfig};
+
+    /// Extract an [ApiRequest] from the `request` and return the status of the error response
+    async fn rejected_status(request: TestRequest) -> StatusCode {
+        let (req, mut payload) = request
+            .app_data(ApiRequestConfig::default().limit(16))
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .to_http_parts();
+
+        let error = ApiRequest::<Value>::from_request(&req, &mut payload)
+            .await
+            .err()
+            .unwrap();
+
+        error.error_response().status()
+    }
+
+    #[tokio::test]
+    async fn api_request_should_reject_oversized_body_when_content_length_exceeds_limit() {
+        // The body is empty so the request can only be rejected using the declared length
+        let request = TestRequest::post().insert_header((header::CONTENT_LENGTH, "1048576"));
+
+        let status = rejected_status(request).await;
+
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn api_request_should_reject_oversized_body_when_payload_exceeds_limit() {
+        let request =
+            TestRequest::post().set_payload(format!(r#"{{"data":"{}"}}"#, "a".repeat(64)));
+
+        let status = rejected_status(request).await;
+
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn api_request_should_extract_body_when_within_limit() {
+        let (req, mut payload) = TestRequest::post()
+            .app_data(ApiRequestConfig::default().limit(16))
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload(r#"{"a":1}"#)
+            .to_http_parts();
+
+        let request = ApiRequest::<Value>::from_request(&req, &mut payload)
+            .await
+            .unwrap();
+
+        assert_eq!(request.into_inner(), serde_json::json!({"a": 1}));
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    fmt::{Display, Formatter},
+    future::{ready, Ready},
+};
+
+use actix_web::{
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    FromRequest, HttpMessage, HttpRequest,
+};
+use futures::future::LocalBoxFuture;
+use uuid::Uuid;
+
+/// Header used to receive and propagate the [RequestId] of a request across services
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Maximum length of a [RequestId] accepted from the `X-Request-Id` header. Longer values are
+/// replaced with a generated id.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    /// [RequestId] of the request currently being handled by the task
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+/// Correlation id of a single request. Read from the `X-Request-Id` header of incoming requests
+/// (or generated if missing) so a request can be traced across the logs of every service it
+/// touches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Generate a new random [RequestId]
+    pub fn generate() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    /// Use the header `value` as the [RequestId] if it is a valid id. Values that are empty, too
+    /// long or contain characters other than visible ASCII are rejected to keep log lines and
+    /// outbound headers safe.
+    fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?.trim();
+        if value.is_empty()
+            || value.len() > MAX_REQUEST_ID_LENGTH
+            || !value.chars().all(|c| c.is_ascii_graphic())
+        {
+            return None;
+        }
+        Some(Self(value.to_owned()))
+    }
+
+    /// Get the id as a string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let request_id = req
+            .extensions()
+            .get::<Self>()
+            .cloned()
+            .unwrap_or_else(Self::generate);
+        ready(Ok(request_id))
+    }
+}
+
+/// Get the [RequestId] of the request currently being handled, if the caller is running within a
+/// request wrapped by the [PropagateRequestId] middleware
+pub fn current_request_id() -> Option<RequestId> {
+    CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Middleware factory that assigns a [RequestId] to every request of the wrapped service. The id
+/// is read from the `X-Request-Id` header or generated if missing, stored in the request
+/// extensions, made available through [current_request_id] while the request is handled and
+/// echoed back in the response headers.
+pub struct PropagateRequestId;
+
+impl<S, B> Transform<S, ServiceRequest> for PropagateRequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    type InitError = ();
+    type Response = ServiceResponse<B>;
+    type Transform = PropagateRequestIdMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(PropagateRequestIdMiddleware { service }))
+    }
+}
+
+/// Service created by the [PropagateRequestId] middleware factory
+pub struct PropagateRequestIdMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for PropagateRequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+    type Response = ServiceResponse<B>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(RequestId::generate);
+        req.extensions_mut().insert(request_id.clone());
+        let future = CURRENT_REQUEST_ID.sync_scope(request_id.clone(), || self.service.call(req));
+        Box::pin(CURRENT_REQUEST_ID.scope(request_id.clone(), async move {
+            let mut response = future.await?;
+            if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+                response
+                    .headers_mut()
+                    .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Ok(response)
+        }))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use actix_web::{
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::{current_request_id, PropagateRequestId, RequestId, REQUEST_ID_HEADER};
+
+    /// Handler that responds with the [RequestId] visible to the task handling the request
+    async fn echo_request_id(request_id: RequestId) -> HttpResponse {
+        let current = current_request_id()
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+        HttpResponse::Ok().body(format!("{request_id}|{current}"))
+    }
+
+    #[tokio::test]
+    async fn propagate_request_id_should_use_header_when_provided() {
+        let app = init_service(
+            App::new()
+                .wrap(PropagateRequestId)
+                .route("/", web::get().to(echo_request_id)),
+        )
+        .await;
+        let request = TestRequest::get()
+            .uri("/")
+            .insert_header((REQUEST_ID_HEADER, "portal-1234"))
+            .to_request();
+
+        let response = call_service(&app, request).await;
+
+        assert_eq!(
+            response.headers().get(REQUEST_ID_HEADER).unwrap(),
+            "portal-1234"
+        );
+        assert_eq!(read_body(response).await, "portal-1234|portal-1234");
+    }
+
+    #[tokio::test]
+    async fn propagate_request_id_should_generate_id_when_header_missing_or_invalid() {
+        let app = init_service(
+            App::new()
+                .wrap(PropagateRequestId)
+                .route("/", web::get().to(echo_request_id)),
+        )
+        .await;
+        let request = TestRequest::get()
+            .uri("/")
+            .insert_header((REQUEST_ID_HEADER, "   "))
+            .to_request();
+
+        let response = call_service(&app, request).await;
+
+        let header = response
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert!(uuid::Uuid::parse_str(&header).is_ok(), "{header}");
+        assert_eq!(read_body(response).await, format!("{header}|{header}"));
+    }
+
+    #[test]
+    fn current_request_id_should_be_none_outside_request() {
+        assert!(current_request_id().is_none());
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+/// Default `ascending` value of a [Sort] when deserialized without a value
+const fn default_ascending() -> bool {
+    true
+}
+
+/// Requested order of the records returned from a service. The `column` is only a request and is
+/// never placed directly into a query. Services map the `column` through a [SortColumns] whitelist
+/// and fall back to their default order when the `column` is empty or unknown. Can be deserialized
+/// from a url query with the template of `?column={column}&ascending={true|false}`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Sort {
+    /// Name of the column to sort by
+    #[serde(default)]
+    pub column: String,
+    /// True if the records should be sorted in ascending order, otherwise descending
+    #[serde(default = "default_ascending")]
+    pub ascending: bool,
+}
+
+impl Sort {
+    /// Create a new [Sort] by the `column` in the specified direction
+    pub fn new<S>(column: S, ascending: bool) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            column: column.into(),
+            ascending,
+        }
+    }
+}
+
+impl Default for Sort {
+    fn default() -> Self {
+        Self {
+            column: String::new(),
+            ascending: default_ascending(),
+        }
+    }
+}
+
+/// Whitelist of columns that a service allows records to be sorted by. Each entry maps the column
+/// name accepted within a [Sort] to the SQL expression used in the `order by` clause so user input
+/// never becomes part of a query.
+pub struct SortColumns {
+    /// Pairs of accepted column names and the SQL expression to order by
+    columns: &'static [(&'static str, &'static str)],
+    /// `order by` clause contents used when no known column is requested. Also appended to every
+    /// requested order as a tie breaker so the order of records is stable.
+    default_order: &'static str,
+}
+
+impl SortColumns {
+    /// Create a new [SortColumns] whitelist of `columns` with the `default_order` of records
+    pub const fn new(
+        columns: &'static [(&'static str, &'static str)],
+        default_order: &'static str,
+    ) -> Self {
+        Self {
+            columns,
+            default_order,
+        }
+    }
+
+    /// Create the contents of an `order by` clause (without the keywords) for the `sort`
+    /// requested. Falls back to the default order when the column of the `sort` is not within the
+    /// whitelist.
+    pub fn order_by(&self, sort: &Sort) -> String {
+        let Some((_, expression)) = self.columns.iter().find(|(name, _)| *name == sort.column)
+        else {
+            return self.default_order.to_owned();
+        };
+        let direction = if sort.ascending { "asc" } else { "desc" };
+        format!("{expression} {direction}, {}", self.default_order)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::{Sort, SortColumns};
+
+    /// Whitelist of columns used to test the mapping of a [Sort]
+    const TEST_SORT_COLUMNS: SortColumns =
+        SortColumns::new(&[("status", "t.status"), ("start", "t.start")], "t.id desc");
+
+    #[rstest]
+    #[case::ascending(Sort::new("status", true), "t.status asc, t.id desc")]
+    #[case::descending(Sort::new("start", false), "t.start desc, t.id desc")]
+    #[case::default(Sort::default(), "t.id desc")]
+    #[case::unknown_column(Sort::new("name", true), "t.id desc")]
+    #[case::injection(Sort::new("status; drop table t", false), "t.id desc")]
+    #[case::expression_as_column(Sort::new("t.status", true), "t.id desc")]
+    fn order_by_should_map_sort_when(#[case] sort: Sort, #[case] expected: &str) {
+        assert_eq!(TEST_SORT_COLUMNS.order_by(&sort), expected);
+    }
+}
@@ -0,0 +1,161 @@
+use std::{env, path::PathBuf};
+
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls_pemfile::Item;
+
+use crate::{
+    error::{EmError, EmResult},
+    read_file,
+};
+
+/// Paths to the PEM encoded certificate chain and private key used to serve an API over HTTPS.
+/// When an API server is not given a [TlsConfig], it binds using plain HTTP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// Path to the PEM file containing the server's certificate chain, leaf certificate first
+    pub cert_path: PathBuf,
+    /// Path to the PEM file containing the server's private key (PKCS8, RSA or EC)
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    /// Create a new [TlsConfig] from the environment variables named `cert_var` and `key_var`.
+    /// Returns [None] if neither variable is set so the server falls back to plain HTTP.
+    /// # Errors
+    /// This function will return an error if only one of the variables is set
+    pub fn from_env(cert_var: &str, key_var: &str) -> EmResult<Option<Self>> {
+        Self::from_lookup(cert_var, key_var, |key| env::var(key).ok())
+    }
+
+    /// Create a new [TlsConfig] using the `lookup` function to find the certificate and key paths
+    /// by the names `cert_var` and `key_var`. Returns [None] if neither value is found.
+    /// # Errors
+    /// This function will return an error if only one of the values is found
+    pub fn from_lookup<F>(cert_var: &str, key_var: &str, lookup: F) -> EmResult<Option<Self>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        match (lookup(cert_var), lookup(key_var)) {
+            (Some(cert_path), Some(key_path)) => Ok(Some(Self {
+                cert_path: cert_path.into(),
+                key_path: key_path.into(),
+            })),
+            (None, None) => Ok(None),
+            (Some(_), None) => Err(EmError::Tls(format!(
+                "{cert_var} is set but {key_var} is missing. Both must be set to enable TLS"
+            ))),
+            (None, Some(_)) => Err(EmError::Tls(format!(
+                "{key_var} is set but {cert_var} is missing. Both must be set to enable TLS"
+            ))),
+        }
+    }
+
+    /// Load the certificate chain and private key files to build a rustls [ServerConfig]. Should
+    /// be called before the server is started so invalid TLS material stops the server at startup.
+    /// # Errors
+    /// This function will return an error if either file cannot be read, the files do not contain
+    /// a certificate or private key or rustls rejects the certificate and key pair
+    pub async fn load(&self) -> EmResult<ServerConfig> {
+        let cert_pem = read_file(&self.cert_path).await?;
+        let key_pem = read_file(&self.key_path).await?;
+        server_config_from_pem(&cert_pem, &key_pem).map_err(|error| match error {
+            EmError::Tls(reason) => EmError::Tls(format!(
+                "Could not load certificate {:?} and key {:?}. {reason}",
+                self.cert_path, self.key_path
+            )),
+            error => error,
+        })
+    }
+}
+
+/// Build a rustls [ServerConfig] from the contents of a PEM certificate chain and private key.
+/// The first private key found in `key_pem` is used.
+/// # Errors
+/// This function will return an error if the PEM contents cannot be parsed, no certificate or
+/// private key is found or rustls rejects the certificate and key pair
+fn server_config_from_pem(cert_pem: &str, key_pem: &str) -> EmResult<ServerConfig> {
+    let certs: Vec<Certificate> = rustls_pemfile::certs(&mut cert_pem.as_bytes())?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    if certs.is_empty() {
+        return Err(EmError::Tls(
+            "No certificates found in certificate file".to_owned(),
+        ));
+    }
+    let key = rustls_pemfile::read_all(&mut key_pem.as_bytes())?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| EmError::Tls("No private key found in key file".to_owned()))?;
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|error| EmError::Tls(error.to_string()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use super::{server_config_from_pem, TlsConfig};
+
+    const CERT_VAR: &str = "TEST_TLS_CERT";
+    const KEY_VAR: &str = "TEST_TLS_KEY";
+
+    #[rstest]
+    #[case::empty(HashMap::new(), None)]
+    #[case::both(
+        HashMap::from([(CERT_VAR, "cert.pem"), (KEY_VAR, "key.pem")]),
+        Some(TlsConfig { cert_path: "cert.pem".into(), key_path: "key.pem".into() }),
+    )]
+    fn from_lookup_should_succeed(
+        #[case] values: HashMap<&'static str, &'static str>,
+        #[case] expected: Option<TlsConfig>,
+    ) {
+        let config = TlsConfig::from_lookup(CERT_VAR, KEY_VAR, |key| {
+            values.get(key).map(|value| (*value).to_owned())
+        })
+        .unwrap();
+
+        assert_eq!(config, expected);
+    }
+
+    #[rstest]
+    #[case::cert_only(HashMap::from([(CERT_VAR, "cert.pem")]))]
+    #[case::key_only(HashMap::from([(KEY_VAR, "key.pem")]))]
+    fn from_lookup_should_fail_when_partially_configured(
+        #[case] values: HashMap<&'static str, &'static str>,
+    ) {
+        let result = TlsConfig::from_lookup(CERT_VAR, KEY_VAR, |key| {
+            values.get(key).map(|value| (*value).to_owned())
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn server_config_from_pem_should_fail_when_no_certificates() {
+        let result = server_config_from_pem("", "");
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn load_should_fail_when_files_missing() {
+        let config = TlsConfig {
+            cert_path: "missing_cert.pem".into(),
+            key_path: "missing_key.pem".into(),
+        };
+
+        let result = config.load().await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use sqlx::PgPool;
+
+use crate::{
+    audit::{AuditEvent, AuditEventFilter, AuditSink, MAX_AUDIT_EVENTS},
+    error::EmResult,
+};
+
+/// Postgresql implementation of the [AuditSink]. Events are stored in the `audit.events` table.
+#[derive(Clone)]
+pub struct PgAuditSink {
+    pool: PgPool,
+}
+
+impl PgAuditSink {
+    /// Create a new [PgAuditSink] with the referenced pool as the data source
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+impl AuditSink for PgAuditSink {
+    async fn record(&self, event: &AuditEvent) -> EmResult<()> {
+        sqlx::query("call audit.record_event($1,$2,$3,$4,$5)")
+            .bind(event.actor)
+            .bind(&event.action)
+            .bind(&event.target)
+            .bind(event.timestamp)
+            .bind(&event.details)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn read_many(&self, filter: &AuditEventFilter) -> EmResult<Vec<AuditEvent>> {
+        let result = sqlx::query_as(
+            r#"
+            select e.actor, e.action, e.target, e.event_timestamp, e.details
+            from audit.events e
+            where
+                ($1::uuid is null or e.actor = $1)
+                and ($2::text is null or e.target = $2)
+                and ($3::timestamp is null or e.event_timestamp >= $3)
+                and ($4::timestamp is null or e.event_timestamp < $4)
+            order by e.event_id desc
+            limit $5"#,
+        )
+        .bind(filter.actor)
+        .bind(&filter.target)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(MAX_AUDIT_EVENTS)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(result)
+    }
+}
@@ -1,9 +1,9 @@
-use std::{collections::HashSet, path::Path};
+use std::{collections::HashSet, env, path::Path};
 
 use log::error;
 use serde::Deserialize;
 
-use crate::{database::Database, error::EmResult, read_file, workspace_dir};
+use crate::{database::Database, error::EmResult, logging, read_file, workspace_dir};
 
 /// Database builder object defining the common database dependencies and the schema entries
 /// required.
@@ -41,23 +41,52 @@ impl DbBuild {
     }
 
     /// Run the database build operations by building the common schema requirements then
-    /// proceeding to run each [DbBuildEntry] to completion.
-    pub(crate) async fn run<B, P>(&self, directory: P, builder: &B) -> EmResult<()>
+    /// proceeding to run each [DbBuildEntry] to completion. Returns a [BuildReport] of the units
+    /// that were executed.
+    /// # Errors
+    /// This function will return an error if any unit fails to execute or any [DbBuildEntry] could
+    /// not be executed because its dependencies could not be met
+    pub(crate) async fn run<B, P>(&self, directory: P, builder: &B) -> EmResult<BuildReport>
     where
         B: DatabaseBuilder,
         P: AsRef<Path> + Send + Sync,
     {
+        let mut report = BuildReport::default();
         for dep in &self.common_dependencies {
-            build_common_schema(dep, builder).await?
+            let common_units = build_common_schema(dep, builder).await?;
+            report.common_units.extend(common_units);
         }
 
         for entry in self.entries_ordered() {
             entry.run(directory.as_ref(), builder).await?;
+            report.units.push(entry.name.clone());
         }
-        Ok(())
+        let unresolved: Vec<&str> = self
+            .entries
+            .iter()
+            .filter(|entry| !report.units.contains(&entry.name))
+            .map(|entry| entry.name.as_str())
+            .collect();
+        if !unresolved.is_empty() {
+            return Err(format!(
+                "Database build could not resolve the dependencies of units: {unresolved:?}"
+            )
+            .into());
+        }
+        Ok(report)
     }
 }
 
+/// Report of a completed database build listing the build units executed, in the order they were
+/// run.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BuildReport {
+    /// Common schema units executed, formatted as `{schema}/{unit name}`
+    pub common_units: Vec<String>,
+    /// Package build units executed
+    pub units: Vec<String>,
+}
+
 /// Database build entry specifying the name of the build unit contained with the `database`
 /// directory of the current package as well as any other required units that must have already
 /// been created.
@@ -148,18 +177,21 @@ impl<'e> Iterator for OrderIter<'e> {
 }
 
 /// Build the common `schema` by name. Extracts a [DbBuild] instance from the specified `schema`
-/// directory, building each entry in order as required by dependency hierarchy.
-async fn build_common_schema<B>(schema: &str, builder: &B) -> EmResult<()>
+/// directory, building each entry in order as required by dependency hierarchy. Returns the names
+/// of the units executed, prefixed by the `schema` name.
+async fn build_common_schema<B>(schema: &str, builder: &B) -> EmResult<Vec<String>>
 where
     B: DatabaseBuilder,
 {
     let schema_directory = workspace_dir()?.join("common-database").join(schema);
     let db_build = DbBuild::new(&schema_directory).await?;
 
+    let mut units = Vec::with_capacity(db_build.entries.len());
     for entry in db_build.entries_ordered() {
         entry.run(&schema_directory, builder).await?;
+        units.push(format!("{schema}/{}", entry.name));
     }
-    Ok(())
+    Ok(units)
 }
 
 /// Behaviour to allow for database to be populated with all the required objects. This type should
@@ -200,8 +232,8 @@ where
     D: Database,
     P: AsRef<Path>,
 {
-    if let Err(error) = log4rs::init_file(log_config_path, Default::default()) {
-        error!("Could not initialize log4rs. {error}");
+    if let Err(error) = logging::init(log_config_path) {
+        error!("Could not initialize logging. {error}");
         return;
     }
     let pool = match D::create_pool(options, 1, 1).await {
@@ -214,3 +246,170 @@ where
     let builder = B::create(pool);
     builder.build_database().await
 }
+
+/// Apply the build manifest (a "build.json" file) found within `directory` against the database
+/// connected to by `pool`, returning a [BuildReport] of the units that ran. Units are executed in
+/// dependency order as anonymous blocks through the [DatabaseBuilder] implementation so type
+/// definitions are skipped when they already exist and other units are expected to be written as
+/// re-runnable scripts (e.g. `create or replace`, `if not exists`). This allows a fresh database
+/// to be provisioned at startup without an external migration tool.
+/// # Errors
+/// This function will return an error if the manifest cannot be read, any unit fails to execute or
+/// any unit could not be executed because its dependencies could not be met
+pub async fn run_build<B, P>(
+    pool: <B::Database as Database>::ConnectionPool,
+    directory: P,
+) -> EmResult<BuildReport>
+where
+    B: DatabaseBuilder,
+    P: AsRef<Path> + Send + Sync,
+{
+    let builder = B::create(pool);
+    let db_build = DbBuild::new(directory.as_ref()).await?;
+    db_build.run(directory, &builder).await
+}
+
+/// Returns true if applying the package's build manifest at startup was requested through the
+/// `env_key` environment variable
+pub fn startup_build_requested(env_key: &str) -> bool {
+    env::var(env_key).is_ok_and(|value| value == "true" || value == "1")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::panic)]
+mod test {
+    use std::{path::Path, sync::Mutex};
+
+    use sqlx::PgPool;
+    use tempfile::TempDir;
+
+    use super::{BuildReport, DatabaseBuilder, DbBuild};
+    use crate::{database::postgres::Postgres, error::EmResult};
+
+    /// [DatabaseBuilder] that records each block executed rather than running it
+    #[derive(Default)]
+    struct RecordingBuilder {
+        /// Blocks executed against the builder, in order
+        blocks: Mutex<Vec<String>>,
+    }
+
+    impl DatabaseBuilder for RecordingBuilder {
+        type Database = Postgres;
+
+        fn create(_: PgPool) -> Self {
+            Self::default()
+        }
+
+        async fn build_database(&self) {}
+
+        async fn refresh_database(&self) -> EmResult<()> {
+            Ok(())
+        }
+
+        async fn execute_anonymous_block(&self, block: &str) -> EmResult<()> {
+            self.blocks.lock().unwrap().push(block.to_owned());
+            Ok(())
+        }
+    }
+
+    /// Create a new temporary build directory, writing a unit file for each of the `units` that
+    /// contains the unit's name. The directory is removed once the returned [TempDir] is dropped.
+    async fn build_directory(units: &[&str]) -> EmResult<TempDir> {
+        let directory = tempfile::tempdir()?;
+        for unit in units {
+            tokio::fs::write(directory.path().join(unit), *unit).await?;
+        }
+        Ok(directory)
+    }
+
+    /// Write the build `manifest` as the "build.json" file of the `directory`
+    async fn write_manifest(directory: &Path, manifest: &str) -> EmResult<()> {
+        tokio::fs::write(directory.join("build.json"), manifest).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_should_execute_units_in_dependency_order() -> EmResult<()> {
+        let directory = build_directory(&["types.pgsql", "table.pgsql"]).await?;
+        let db_build: DbBuild = serde_json::from_str(
+            r#"{
+                "common_dependencies": [],
+                "entries": [
+                    {"name": "table.pgsql", "dependencies": ["types.pgsql"]},
+                    {"name": "types.pgsql", "dependencies": []}
+                ]
+            }"#,
+        )?;
+        let builder = RecordingBuilder::default();
+
+        let report = db_build.run(directory.path(), &builder).await?;
+
+        let expected = BuildReport {
+            common_units: vec![],
+            units: vec!["types.pgsql".to_owned(), "table.pgsql".to_owned()],
+        };
+        assert_eq!(report, expected);
+        assert_eq!(*builder.blocks.lock().unwrap(), expected.units);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_should_fail_when_dependencies_are_missing() -> EmResult<()> {
+        let directory = build_directory(&["types.pgsql", "table.pgsql"]).await?;
+        let db_build: DbBuild = serde_json::from_str(
+            r#"{
+                "common_dependencies": [],
+                "entries": [
+                    {"name": "types.pgsql", "dependencies": []},
+                    {"name": "table.pgsql", "dependencies": ["missing.pgsql"]}
+                ]
+            }"#,
+        )?;
+        let builder = RecordingBuilder::default();
+
+        let result = db_build.run(directory.path(), &builder).await;
+
+        let Err(error) = result else {
+            panic!("Expected an error when a dependency is missing");
+        };
+        assert!(error.to_string().contains("table.pgsql"), "{error}");
+        assert_eq!(*builder.blocks.lock().unwrap(), vec!["types.pgsql"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_should_fail_when_unit_file_does_not_exist() -> EmResult<()> {
+        let directory = build_directory(&[]).await?;
+        let db_build: DbBuild = serde_json::from_str(
+            r#"{
+                "common_dependencies": [],
+                "entries": [{"name": "not_a_file.pgsql", "dependencies": []}]
+            }"#,
+        )?;
+        let builder = RecordingBuilder::default();
+
+        let result = db_build.run(directory.path(), &builder).await;
+
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn db_build_new_should_read_manifest_from_directory() -> EmResult<()> {
+        let directory = build_directory(&[]).await?;
+        write_manifest(
+            directory.path(),
+            r#"{
+                "common_dependencies": [],
+                "entries": [{"name": "types.pgsql", "dependencies": []}]
+            }"#,
+        )
+        .await?;
+
+        let db_build = DbBuild::new(directory.path()).await?;
+
+        assert!(db_build.common_dependencies.is_empty());
+        assert_eq!(db_build.entries.len(), 1);
+        Ok(())
+    }
+}
@@ -1,10 +1,117 @@
+use std::{env, time::Duration};
+
+use log::{debug, warn};
 use sqlx::{
     database::HasArguments, pool::PoolConnection, types::Uuid, Connection, Database, Encode,
     Executor, IntoArguments, Pool, Transaction, Type,
 };
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
 
 use crate::error::{EmError, EmResult};
 
+/// Default time (in seconds) between samples taken by a pool sampler
+const DEFAULT_POOL_SAMPLE_INTERVAL: u64 = 60;
+/// Default fraction of the maximum pool connections in use before a pool sampler logs a warning
+const DEFAULT_POOL_WARN_UTILIZATION: f64 = 0.9;
+
+/// Snapshot of the connections held by a [Pool]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Number of connections currently held by the pool, idle or in use
+    pub size: u32,
+    /// Number of connections held by the pool that are idle
+    pub idle: u32,
+    /// Number of connections held by the pool that are in use
+    pub in_use: u32,
+}
+
+impl PoolStats {
+    /// Take a snapshot of the connections currently held by the `pool`. Only reads counters kept
+    /// by the pool so the call is cheap and never waits on a connection.
+    pub fn from_pool<D: Database>(pool: &Pool<D>) -> Self {
+        let size = pool.size();
+        let idle = u32::try_from(pool.num_idle()).unwrap_or(u32::MAX).min(size);
+        Self {
+            size,
+            idle,
+            in_use: size - idle,
+        }
+    }
+
+    /// Fraction of the `max_connections` of the pool that are in use. A pool without any allowed
+    /// connections is always fully utilized.
+    pub fn utilization(&self, max_connections: u32) -> f64 {
+        if max_connections == 0 {
+            return 1.0;
+        }
+        f64::from(self.in_use) / f64::from(max_connections)
+    }
+}
+
+/// Configuration of the background pool sampler started by
+/// [ConnectionBuilder::spawn_pool_sampler]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolSamplerConfig {
+    /// Time between samples of the pool
+    pub interval: Duration,
+    /// Fraction of the maximum pool connections in use (0 to 1) at which a warning is logged
+    pub warn_utilization: f64,
+}
+
+impl Default for PoolSamplerConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(DEFAULT_POOL_SAMPLE_INTERVAL),
+            warn_utilization: DEFAULT_POOL_WARN_UTILIZATION,
+        }
+    }
+}
+
+impl PoolSamplerConfig {
+    /// Create a new [PoolSamplerConfig] from environment variables, using the default value for
+    /// any variable that is not set. The environment variables read are:
+    /// - EM_POOL_SAMPLE_INTERVAL -> time between samples in seconds (default 60)
+    /// - EM_POOL_WARN_UTILIZATION -> fraction of connections in use that is logged as a warning
+    ///   (default 0.9)
+    /// # Errors
+    /// This function will return an error if an environment variable cannot be parsed or is out of
+    /// range
+    pub fn from_env() -> EmResult<Self> {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Create a new [PoolSamplerConfig] using `lookup` to read the values of the environment
+    /// variables described in [PoolSamplerConfig::from_env]
+    fn from_lookup<F>(lookup: F) -> EmResult<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let defaults = Self::default();
+        let interval = match lookup("EM_POOL_SAMPLE_INTERVAL") {
+            Some(value) => Duration::from_secs(value.parse()?),
+            None => defaults.interval,
+        };
+        if interval.is_zero() {
+            return Err(EmError::Generic(
+                "EM_POOL_SAMPLE_INTERVAL must be greater than 0".to_owned(),
+            ));
+        }
+        let warn_utilization = match lookup("EM_POOL_WARN_UTILIZATION") {
+            Some(value) => value.parse()?,
+            None => defaults.warn_utilization,
+        };
+        if !(0.0..=1.0).contains(&warn_utilization) {
+            return Err(EmError::Generic(format!(
+                "EM_POOL_WARN_UTILIZATION must be between 0 and 1. Got {warn_utilization}"
+            )));
+        }
+        Ok(Self {
+            interval,
+            warn_utilization,
+        })
+    }
+}
+
 /// Implementors are able to provide connection pools specific to the specified [Database] type
 pub trait ConnectionBuilder<D: Database> {
     /// Return a new pool of database connections. Requires the connection `options` and min/max
@@ -21,6 +128,91 @@ pub trait ConnectionBuilder<D: Database> {
         max_connections: u32,
         min_connection: u32,
     ) -> Pool<D>;
+    /// Take a snapshot of the connections currently held by the `pool`. Used to export pool
+    /// metrics without waiting on a connection.
+    fn pool_stats(pool: &Pool<D>) -> PoolStats {
+        PoolStats::from_pool(pool)
+    }
+
+    /// Spawn a background task that samples the `pool` every [PoolSamplerConfig::interval]. Each
+    /// sample is logged at the debug level, or as a warning when the utilization of the pool's
+    /// `max_connections` (as specified when the pool was created) reaches
+    /// [PoolSamplerConfig::warn_utilization] so exhaustion of the pool does not go unnoticed. The
+    /// task exits once the pool is closed.
+    fn spawn_pool_sampler(
+        pool: &Pool<D>,
+        max_connections: u32,
+        config: PoolSamplerConfig,
+    ) -> JoinHandle<()> {
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(config.interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                if pool.is_closed() {
+                    break;
+                }
+                let stats = Self::pool_stats(&pool);
+                if stats.utilization(max_connections) >= config.warn_utilization {
+                    warn!(
+                        "Pool near exhaustion. size = {}, idle = {}, in use = {}, max = \
+                         {max_connections}",
+                        stats.size, stats.idle, stats.in_use
+                    );
+                } else {
+                    debug!(
+                        "Connection pool size = {}, idle = {}, in use = {}, max = \
+                         {max_connections}",
+                        stats.size, stats.idle, stats.in_use
+                    );
+                }
+            }
+        })
+    }
+}
+
+/// Pair of connection pools for deployments that offload read-only queries to a read replica.
+/// Writes (and reads that must observe the latest writes) use the [primary][DualPool::primary]
+/// pool while read-only queries (e.g. `read_*` service methods used for reporting) use the
+/// [replica][DualPool::replica] pool. Single database deployments can use [DualPool::single] so
+/// both roles share the same pool.
+pub struct DualPool<D: Database> {
+    /// Pool connected to the primary database
+    primary: Pool<D>,
+    /// Pool connected to the read replica
+    replica: Pool<D>,
+}
+
+impl<D: Database> DualPool<D> {
+    /// Create a new [DualPool] from a `primary` and `replica` pool
+    pub fn new(primary: &Pool<D>, replica: &Pool<D>) -> Self {
+        Self {
+            primary: primary.clone(),
+            replica: replica.clone(),
+        }
+    }
+
+    /// Create a new [DualPool] where reads and writes are both sent to the same `pool`
+    pub fn single(pool: &Pool<D>) -> Self {
+        Self::new(pool, pool)
+    }
+
+    /// Pool used for writes and reads that must observe the latest writes
+    pub const fn primary(&self) -> &Pool<D> {
+        &self.primary
+    }
+
+    /// Pool used for read-only queries. Data may lag behind the primary pool
+    pub const fn replica(&self) -> &Pool<D> {
+        &self.replica
+    }
+}
+
+impl<D: Database> Clone for DualPool<D> {
+    fn clone(&self) -> Self {
+        Self::new(&self.primary, &self.replica)
+    }
 }
 
 /// Acquire new pool connection and set the 'em.uid' parameter to the specified [Uuid]
@@ -76,3 +268,79 @@ where
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::{str::FromStr, time::Duration};
+
+    use rstest::rstest;
+    use sqlx::sqlite::SqliteConnectOptions;
+
+    use super::{ConnectionBuilder, DualPool, PoolSamplerConfig};
+    use crate::{
+        database::sqlite::connection::SqliteConnectionBuilder, error::EmResult, test::env_lookup,
+    };
+
+    #[tokio::test]
+    async fn pool_stats_should_count_connections_in_use() -> EmResult<()> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = SqliteConnectionBuilder::create_pool(options, 2, 1).await?;
+
+        let connection = pool.acquire().await?;
+        let stats = SqliteConnectionBuilder::pool_stats(&pool);
+        drop(connection);
+
+        assert_eq!(stats.in_use, 1);
+        assert_eq!(stats.size, stats.idle + stats.in_use);
+        assert!((stats.utilization(2) - 0.5).abs() < f64::EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn pool_sampler_config_from_lookup_should_use_defaults_when_not_set() {
+        let config = PoolSamplerConfig::from_lookup(env_lookup(&[])).unwrap();
+
+        assert_eq!(config, PoolSamplerConfig::default());
+    }
+
+    #[test]
+    fn pool_sampler_config_from_lookup_should_override_defaults_when_set() {
+        let config = PoolSamplerConfig::from_lookup(env_lookup(&[
+            ("EM_POOL_SAMPLE_INTERVAL", "5"),
+            ("EM_POOL_WARN_UTILIZATION", "0.75"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.interval, Duration::from_secs(5));
+        assert!((config.warn_utilization - 0.75).abs() < f64::EPSILON);
+    }
+
+    #[rstest]
+    #[case::zero_interval(&[("EM_POOL_SAMPLE_INTERVAL", "0")])]
+    #[case::utilization_too_high(&[("EM_POOL_WARN_UTILIZATION", "1.5")])]
+    #[case::not_a_number(&[("EM_POOL_WARN_UTILIZATION", "high")])]
+    fn pool_sampler_config_from_lookup_should_fail_when(#[case] pairs: &[(&str, &str)]) {
+        assert!(PoolSamplerConfig::from_lookup(env_lookup(pairs)).is_err());
+    }
+
+    #[tokio::test]
+    async fn single_should_read_from_primary_when_no_replica_is_configured() -> EmResult<()> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = SqliteConnectionBuilder::create_pool(options, 1, 1).await?;
+        let pools = DualPool::single(&pool);
+
+        sqlx::query("create table dual_pool_test(id integer)")
+            .execute(pools.primary())
+            .await?;
+        sqlx::query("insert into dual_pool_test(id) values(1)")
+            .execute(pools.primary())
+            .await?;
+        let count: i64 = sqlx::query_scalar("select count(*) from dual_pool_test")
+            .fetch_one(pools.replica())
+            .await?;
+
+        assert_eq!(count, 1);
+        Ok(())
+    }
+}
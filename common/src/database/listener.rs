@@ -1,4 +1,116 @@
-use crate::{database::Database, error::EmResult};
+use std::marker::PhantomData;
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::{
+    database::Database,
+    error::{EmError, EmResult},
+};
+
+/// Current version of the [NotificationPayload] envelope written by notifiers
+pub const NOTIFICATION_PAYLOAD_VERSION: u32 = 1;
+
+/// Versioned envelope of a notification payload. Notifiers prefix the serialized payload with the
+/// version of its shape (e.g. `v1:42`) so listeners can reject payloads they do not understand
+/// rather than misinterpreting them. On the SQL side this is done by sending
+/// `'v1:'||payload` through `pg_notify`. Payloads without a version prefix were sent before the
+/// envelope was introduced and are treated as version 0, which shares the shape of version 1.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NotificationPayload<'p> {
+    /// Version of the payload shape
+    version: u32,
+    /// Serialized payload without the version prefix
+    body: &'p str,
+}
+
+impl<'p> NotificationPayload<'p> {
+    /// Encode the `body` using the current [NOTIFICATION_PAYLOAD_VERSION]
+    pub fn encode(body: &str) -> String {
+        format!("v{NOTIFICATION_PAYLOAD_VERSION}:{body}")
+    }
+
+    /// Decode the raw notification `payload`. Payloads without a version prefix are decoded as
+    /// version 0.
+    /// # Errors
+    /// This function will return an error if the payload version is newer than
+    /// [NOTIFICATION_PAYLOAD_VERSION]
+    pub fn decode(payload: &'p str) -> EmResult<Self> {
+        let versioned = payload.strip_prefix('v').and_then(|rest| {
+            let (version, body) = rest.split_once(':')?;
+            Some((version.parse::<u32>().ok()?, body))
+        });
+        let Some((version, body)) = versioned else {
+            return Ok(Self {
+                version: 0,
+                body: payload,
+            });
+        };
+        if version > NOTIFICATION_PAYLOAD_VERSION {
+            return Err(EmError::PayloadParseError(format!(
+                "Unknown notification payload version {version} in `{payload}`"
+            )));
+        }
+        Ok(Self { version, body })
+    }
+
+    /// Version of the payload shape. Unprefixed payloads are version 0
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Serialized payload without the version prefix
+    pub const fn body(&self) -> &'p str {
+        self.body
+    }
+}
+
+/// Environment variable specifying the namespace prefixed to every notification channel
+pub const CHANNEL_NAMESPACE_ENV: &str = "EM_CHANNEL_NS";
+/// Database setting read by notifiers so they prefix channels with the same namespace as listeners
+pub const CHANNEL_NAMESPACE_SETTING: &str = "em.channel_ns";
+
+/// Namespace prefixed to notification channel names so multiple logical environments can share a
+/// single database without receiving each other's notifications. Namespaced channels are named
+/// `{namespace}_{channel}`. Without a namespace, channel names are left unchanged.
+///
+/// Notifiers must use the same namespace as listeners. On the SQL side, the namespace is read from
+/// the [CHANNEL_NAMESPACE_SETTING] of the session that sends the notification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelNamespace(Option<String>);
+
+impl ChannelNamespace {
+    /// Create a new [ChannelNamespace] using the `namespace` provided. Blank values are treated as
+    /// no namespace.
+    pub fn new<S: Into<String>>(namespace: S) -> Self {
+        let namespace = namespace.into();
+        let namespace = namespace.trim();
+        if namespace.is_empty() {
+            return Self(None);
+        }
+        Self(Some(namespace.to_owned()))
+    }
+
+    /// Create a new [ChannelNamespace] from the [CHANNEL_NAMESPACE_ENV] environment variable. If
+    /// the variable is not set, no namespace is used.
+    pub fn from_env() -> Self {
+        std::env::var(CHANNEL_NAMESPACE_ENV)
+            .map(Self::new)
+            .unwrap_or_default()
+    }
+
+    /// Return the namespace as a string slice, or [None] if no namespace is used
+    pub fn as_str(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+
+    /// Return the name of the `channel` within this namespace
+    pub fn channel(&self, channel: &str) -> String {
+        self.0.as_ref().map_or_else(
+            || channel.to_owned(),
+            |namespace| format!("{namespace}_{channel}"),
+        )
+    }
+}
 
 /// State change listener.
 pub trait ChangeListener
@@ -12,3 +124,115 @@ where
     /// Receive the next message from the listen channel
     async fn recv(&mut self) -> EmResult<Self::Message>;
 }
+
+/// In-memory implementation of a [ChangeListener] backed by a channel. Messages are pushed using
+/// the paired [InMemorySender] so tests can drive listener consumers deterministically without a
+/// database. The `D` type parameter is the [Database] the listener stands in for.
+pub struct InMemoryChangeListener<M, D> {
+    receiver: UnboundedReceiver<M>,
+    marker: PhantomData<fn() -> D>,
+}
+
+/// Sending half of an [InMemoryChangeListener]. Can be cloned to push messages from multiple
+/// places.
+#[derive(Clone)]
+pub struct InMemorySender<M> {
+    sender: UnboundedSender<M>,
+}
+
+impl<M> InMemorySender<M> {
+    /// Push a `message` to the paired [InMemoryChangeListener]
+    /// # Errors
+    /// This function will return an error if the paired listener has been dropped
+    pub fn send(&self, message: M) -> EmResult<()> {
+        self.sender
+            .send(message)
+            .map_err(|_| EmError::Generic("In-memory change listener was dropped".to_owned()))
+    }
+}
+
+/// Create a new [InMemoryChangeListener] and the [InMemorySender] used to push messages to it
+pub fn in_memory_channel<M, D>() -> (InMemorySender<M>, InMemoryChangeListener<M, D>) {
+    let (sender, receiver) = unbounded_channel();
+    (
+        InMemorySender { sender },
+        InMemoryChangeListener {
+            receiver,
+            marker: PhantomData,
+        },
+    )
+}
+
+impl<M, D> ChangeListener for InMemoryChangeListener<M, D>
+where
+    M: Send,
+    D: Database,
+{
+    type Database = D;
+    type Message = M;
+
+    async fn recv(&mut self) -> EmResult<M> {
+        self.receiver
+            .recv()
+            .await
+            .ok_or_else(|| EmError::Generic("In-memory change listener senders dropped".to_owned()))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::panic)]
+mod test {
+    use rstest::rstest;
+
+    use super::{ChannelNamespace, NotificationPayload, NOTIFICATION_PAYLOAD_VERSION};
+
+    #[rstest]
+    #[case::unprefixed("42", 0, "42")]
+    #[case::empty("", 0, "")]
+    #[case::v1("v1:42", 1, "42")]
+    #[case::v1_empty("v1:", 1, "")]
+    #[case::v1_json(r#"v1:{"status":"Running"}"#, 1, r#"{"status":"Running"}"#)]
+    #[case::word_starting_with_v("verbose", 0, "verbose")]
+    fn decode_should_succeed_when(#[case] payload: &str, #[case] version: u32, #[case] body: &str) {
+        let result = NotificationPayload::decode(payload);
+
+        let Ok(decoded) = result else {
+            panic!("Expected `{payload}` to decode");
+        };
+        assert_eq!(decoded.version(), version);
+        assert_eq!(decoded.body(), body);
+    }
+
+    #[test]
+    fn decode_should_fail_when_version_is_unknown() {
+        let payload = format!("v{}:42", NOTIFICATION_PAYLOAD_VERSION + 1);
+
+        let result = NotificationPayload::decode(&payload);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn encode_should_round_trip_through_decode() {
+        let payload = NotificationPayload::encode("cancel");
+
+        let result = NotificationPayload::decode(&payload);
+
+        assert!(matches!(
+            result,
+            Ok(decoded) if decoded.version() == NOTIFICATION_PAYLOAD_VERSION && decoded.body() == "cancel"
+        ));
+    }
+
+    #[rstest]
+    #[case::no_namespace(ChannelNamespace::default(), "exec_status_1")]
+    #[case::blank_namespace(ChannelNamespace::new("  "), "exec_status_1")]
+    #[case::namespace(ChannelNamespace::new("staging"), "staging_exec_status_1")]
+    #[case::trimmed_namespace(ChannelNamespace::new(" staging "), "staging_exec_status_1")]
+    fn channel_namespace_should_name_channel(
+        #[case] namespace: ChannelNamespace,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(namespace.channel("exec_status_1"), expected);
+    }
+}
@@ -1,11 +1,32 @@
-use crate::error::EmResult;
+use std::{future::Future, time::Duration};
+
+use log::{error, warn};
+use rand::Rng;
+use sqlx::{Pool, Transaction};
+
+use crate::{
+    database::connection::PoolStats,
+    error::{EmError, EmResult},
+};
 
 pub mod build;
 pub mod connection;
 pub mod listener;
 pub mod postgres;
+pub mod sqlite;
 pub mod test;
 
+/// Default number of attempts made by [with_retryable_transaction] before giving up
+pub const DEFAULT_TRANSACTION_ATTEMPTS: u32 = 3;
+/// SQLSTATE codes of errors caused by concurrent transactions (serialization failure and
+/// deadlock detected) that succeed when the transaction is retried
+const RETRYABLE_SQLSTATES: [&str; 2] = ["40001", "40P01"];
+/// Base delay (in milliseconds) before a transaction is retried. The delay grows with each
+/// attempt and a random jitter of up to the same amount is added.
+const RETRY_BASE_DELAY_MILLIS: u64 = 20;
+/// Maximum time to wait for the connections of a pool to be released when closing the pool
+pub const POOL_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Describes the high level abilities of the database operated against. Must
 pub trait Database {
     /// Options to allow for connecting to the database
@@ -24,6 +45,15 @@ pub trait Database {
         max_connections: u32,
         min_connection: u32,
     ) -> Self::ConnectionPool;
+    /// Run a lightweight query against the `pool` to verify the database can be reached
+    /// # Errors
+    /// This function will return an error if a connection cannot be acquired or the query fails
+    async fn ping(pool: &Self::ConnectionPool) -> EmResult<()>;
+    /// Take a snapshot of the connections currently held by the `pool`
+    fn pool_stats(pool: &Self::ConnectionPool) -> PoolStats;
+    /// Close the `pool`, waiting up to [POOL_CLOSE_TIMEOUT] for in-flight queries to release their
+    /// connections. Should be called during graceful shutdown once no more work will be started.
+    async fn close_pool(pool: &Self::ConnectionPool);
 }
 
 /// Container for multiple optional errors that could arise from an execution of an anonymous block
@@ -67,3 +97,223 @@ where
         self.transaction_error = Some(error);
     }
 }
+
+/// Close the `pool`, giving up after [POOL_CLOSE_TIMEOUT] so a hung query does not block shutdown.
+/// Connections still in use after the timeout are dropped when the process exits.
+async fn close_pool_with_timeout<D>(pool: &Pool<D>)
+where
+    D: sqlx::Database,
+{
+    if tokio::time::timeout(POOL_CLOSE_TIMEOUT, pool.close())
+        .await
+        .is_err()
+    {
+        warn!(
+            "Database pool did not close within {}s. Remaining connections will be dropped",
+            POOL_CLOSE_TIMEOUT.as_secs()
+        );
+    }
+}
+
+/// Check if the `error` was caused by a concurrent transaction (see [RETRYABLE_SQLSTATES]) so the
+/// transaction can be retried
+pub fn is_retryable_error(error: &EmError) -> bool {
+    let (EmError::Sql(sqlx::Error::Database(database_error))
+    | EmError::CommitError(sqlx::Error::Database(database_error))) = error
+    else {
+        return false;
+    };
+    database_error
+        .code()
+        .is_some_and(|code| RETRYABLE_SQLSTATES.contains(&&*code))
+}
+
+/// Delay before the transaction is retried after the failed `attempt`. The delay increases
+/// linearly with the attempt number and is randomly jittered so concurrent transactions that
+/// conflicted do not retry in lockstep.
+fn retry_delay(attempt: u32) -> Duration {
+    let base = RETRY_BASE_DELAY_MILLIS * u64::from(attempt);
+    let jitter = rand::thread_rng().gen_range(0..=base);
+    Duration::from_millis(base + jitter)
+}
+
+/// Run the `block` within a new transaction from the `pool`, committing the transaction if the
+/// block returns [Ok] and rolling back if the block returns [Err]. The block takes ownership of
+/// the transaction and must give it back alongside its result. If the block or the commit fails
+/// due to a serialization failure or deadlock (see [is_retryable_error]), the block is run again
+/// in a new transaction after a short jittered delay, up to `max_attempts` times in total.
+/// # Errors
+/// This function will return an error if the transaction cannot be started, the `block` returns
+/// an error that cannot be retried (or the last attempt fails) or the `COMMIT` fails
+pub async fn with_retryable_transaction<D, T, F, Fut>(
+    pool: &Pool<D>,
+    max_attempts: u32,
+    mut block: F,
+) -> EmResult<T>
+where
+    D: sqlx::Database,
+    F: FnMut(Transaction<'static, D>) -> Fut,
+    Fut: Future<Output = (Transaction<'static, D>, EmResult<T>)>,
+{
+    let mut attempt = 1;
+    loop {
+        let transaction = pool.begin().await?;
+        let (transaction, result) = block(transaction).await;
+        let result = match result {
+            Ok(inner) => transaction
+                .commit()
+                .await
+                .map(|_| inner)
+                .map_err(EmError::CommitError),
+            Err(block_error) => {
+                if let Err(rollback_error) = transaction.rollback().await {
+                    error!("Could not rollback transaction. {rollback_error}");
+                }
+                Err(block_error)
+            }
+        };
+        match result {
+            Err(error) if attempt < max_attempts && is_retryable_error(&error) => {
+                warn!("Retrying transaction after attempt {attempt} of {max_attempts}. {error}");
+                tokio::time::sleep(retry_delay(attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod retry_test {
+    use std::str::FromStr;
+
+    use rstest::rstest;
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+
+    use super::{
+        is_retryable_error, retry_delay, with_retryable_transaction, RETRY_BASE_DELAY_MILLIS,
+    };
+    use crate::{
+        database::{
+            connection::ConnectionBuilder, sqlite::connection::SqliteConnectionBuilder,
+            test::CodeError,
+        },
+        error::{EmError, EmResult},
+    };
+
+    /// Create an [EmError] for a database error with the SQLSTATE `code`
+    fn code_error(code: &'static str) -> EmError {
+        EmError::Sql(CodeError::sqlx_error(code))
+    }
+
+    /// Create an in memory database pool with a single table to insert into
+    async fn pool() -> EmResult<SqlitePool> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = SqliteConnectionBuilder::create_pool(options, 1, 1).await?;
+        sqlx::query("create table retry_test(id integer)")
+            .execute(&pool)
+            .await?;
+        Ok(pool)
+    }
+
+    /// Count the rows inserted into the test table
+    async fn row_count(pool: &SqlitePool) -> EmResult<i64> {
+        Ok(sqlx::query_scalar("select count(*) from retry_test")
+            .fetch_one(pool)
+            .await?)
+    }
+
+    #[test]
+    fn is_retryable_error_should_match_serialization_and_deadlock_errors() {
+        assert!(is_retryable_error(&code_error("40001")));
+        assert!(is_retryable_error(&code_error("40P01")));
+        assert!(!is_retryable_error(&code_error("23505")));
+        assert!(!is_retryable_error(&EmError::Generic("40001".to_owned())));
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    #[case(3)]
+    fn retry_delay_should_be_jittered_between_base_and_twice_the_base(#[case] attempt: u32) {
+        let base = RETRY_BASE_DELAY_MILLIS * u64::from(attempt);
+
+        for _ in 0..100 {
+            let delay = retry_delay(attempt).as_millis();
+            assert!((u128::from(base)..=u128::from(base * 2)).contains(&delay));
+        }
+    }
+
+    #[tokio::test]
+    async fn with_retryable_transaction_should_retry_when_serialization_fails() -> EmResult<()> {
+        let pool = pool().await?;
+        let mut attempts = 0;
+
+        let result = with_retryable_transaction(&pool, 3, |mut transaction| {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                let result = async {
+                    sqlx::query("insert into retry_test(id) values(1)")
+                        .execute(&mut transaction)
+                        .await?;
+                    if attempt == 1 {
+                        return Err(code_error("40001"));
+                    }
+                    Ok(attempt)
+                }
+                .await;
+                (transaction, result)
+            }
+        })
+        .await?;
+
+        assert_eq!(result, 2);
+        assert_eq!(row_count(&pool).await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn with_retryable_transaction_should_rollback_when_error_is_not_retryable() -> EmResult<()>
+    {
+        let pool = pool().await?;
+        let mut attempts = 0;
+
+        let result = with_retryable_transaction(&pool, 3, |mut transaction| {
+            attempts += 1;
+            async move {
+                let result = async {
+                    sqlx::query("insert into retry_test(id) values(1)")
+                        .execute(&mut transaction)
+                        .await?;
+                    Err::<(), _>(code_error("23505"))
+                }
+                .await;
+                (transaction, result)
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+        assert_eq!(row_count(&pool).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn with_retryable_transaction_should_stop_when_attempts_are_exhausted() -> EmResult<()> {
+        let pool = pool().await?;
+        let mut attempts = 0;
+
+        let result = with_retryable_transaction(&pool, 2, |transaction| {
+            attempts += 1;
+            async move { (transaction, Err::<(), _>(code_error("40P01"))) }
+        })
+        .await;
+
+        assert!(matches!(result, Err(error) if is_retryable_error(&error)));
+        assert_eq!(attempts, 2);
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use log::{error, info, warn};
 use sqlx::PgPool;
 
@@ -16,6 +18,28 @@ pub struct PgDatabaseBuilder {
     pool: PgPool,
 }
 
+impl PgDatabaseBuilder {
+    /// Run the `db_build` found in the `database_directory` and log a summary of the units built
+    /// for the `database_target`
+    /// # Errors
+    /// This function will return an error if the build fails, including when units are left
+    /// unresolved
+    async fn run_build(
+        &self,
+        db_build: &DbBuild,
+        database_directory: &Path,
+        database_target: &str,
+    ) -> EmResult<()> {
+        let report = db_build.run(database_directory, self).await?;
+        info!(
+            "Built '{database_target}' database with {} common unit(s) and {} unit(s)",
+            report.common_units.len(),
+            report.units.len()
+        );
+        Ok(())
+    }
+}
+
 impl DatabaseBuilder for PgDatabaseBuilder {
     type Database = Postgres;
 
@@ -62,7 +86,10 @@ impl DatabaseBuilder for PgDatabaseBuilder {
                 return;
             }
         };
-        if let Err(error) = db_build.run(&database_directory, self).await {
+        if let Err(error) = self
+            .run_build(&db_build, &database_directory, &database_target)
+            .await
+        {
             error!("Error building {} database. {}", database_target, error);
             return;
         }
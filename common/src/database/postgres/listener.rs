@@ -1,18 +1,39 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 
-use log::error;
-use sqlx::postgres::PgListener;
+use log::{error, warn};
+use sqlx::{
+    postgres::{PgConnectOptions, PgListener},
+    PgPool,
+};
+use tokio::sync::broadcast;
 
 use crate::{
-    database::{listener::ChangeListener, postgres::Postgres},
+    database::{
+        listener::{ChangeListener, ChannelNamespace, CHANNEL_NAMESPACE_SETTING},
+        postgres::Postgres,
+    },
     error::EmResult,
 };
 
+/// Default number of reconnect attempts before a [PgChangeListener] surfaces a connection error
+pub const DEFAULT_MAX_RECONNECT_ATTEMPTS: u32 = 5;
+/// Delay before the first reconnect attempt. Doubled for every subsequent attempt.
+const BASE_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+/// Maximum delay between reconnect attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Postgres implementation of a [ChangeListener]. Listens to one or more channels and converts the
+/// payload of each notification into the message type `M`. If the underlying connection is lost,
+/// the listener is re-established (with a capped exponential backoff) up to
+/// `max_reconnect_attempts` times before the error is returned to the caller.
 pub struct PgChangeListener<M>
 where
     M: for<'m> From<&'m str> + Send + Sync,
 {
     listener: PgListener,
+    pool: PgPool,
+    channels: Vec<String>,
+    max_reconnect_attempts: u32,
     marker: PhantomData<M>,
 }
 
@@ -20,14 +41,157 @@ impl<M> PgChangeListener<M>
 where
     M: for<'m> From<&'m str> + Send + Sync,
 {
-    pub fn new(listener: PgListener) -> Self {
-        Self {
+    /// Create a new [PgChangeListener] that listens to `channel` using a connection from `pool`
+    /// # Errors
+    /// This function will return an error if the listener cannot connect or `LISTEN` to `channel`
+    pub async fn connect(pool: &PgPool, channel: &str) -> EmResult<Self> {
+        Self::connect_many(pool, &[channel]).await
+    }
+
+    /// Create a new [PgChangeListener] that listens to every channel in `channels` using a single
+    /// connection from `pool`. Notifications from all channels are received in the order they
+    /// are sent, so the message type `M` must be able to tell the payloads apart.
+    /// # Errors
+    /// This function will return an error if the listener cannot connect or `LISTEN` to any of the
+    /// `channels`
+    pub async fn connect_many(pool: &PgPool, channels: &[&str]) -> EmResult<Self> {
+        let channels: Vec<String> = channels
+            .iter()
+            .map(|channel| (*channel).to_owned())
+            .collect();
+        let listener = Self::connect_listener(pool, &channels).await?;
+        Ok(Self {
             listener,
+            pool: pool.clone(),
+            channels,
+            max_reconnect_attempts: DEFAULT_MAX_RECONNECT_ATTEMPTS,
             marker: PhantomData,
-        }
+        })
+    }
+
+    /// Set the maximum number of reconnect attempts performed before a connection error is
+    /// returned from [ChangeListener::recv]. A value of 0 disables reconnecting.
+    pub const fn with_max_reconnect_attempts(mut self, max_reconnect_attempts: u32) -> Self {
+        self.max_reconnect_attempts = max_reconnect_attempts;
+        self
+    }
+
+    /// Create a new [PgListener] from the `pool` and `LISTEN` on the specified `channels`
+    /// # Errors
+    /// This function will return an error if the listener cannot connect or `LISTEN` to any of the
+    /// `channels`
+    async fn connect_listener(pool: &PgPool, channels: &[String]) -> EmResult<PgListener> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener
+            .listen_all(channels.iter().map(String::as_str))
+            .await?;
+        Ok(listener)
+    }
+
+    /// Replace the current [PgListener] with a newly connected listener on the same channels
+    /// # Errors
+    /// This function will return an error if the listener cannot connect or `LISTEN` to the
+    /// channels
+    async fn reconnect(&mut self) -> EmResult<()> {
+        self.listener = Self::connect_listener(&self.pool, &self.channels).await?;
+        Ok(())
+    }
+}
+
+/// Apply the `namespace` to the connection `options` as the [CHANNEL_NAMESPACE_SETTING] so
+/// notifications sent by sessions of the resulting pool use the same [ChannelNamespace] as
+/// listeners. Options are returned unchanged if no namespace is used.
+pub fn with_channel_namespace(
+    options: PgConnectOptions,
+    namespace: &ChannelNamespace,
+) -> PgConnectOptions {
+    match namespace.as_str() {
+        Some(namespace) => options.options([(CHANNEL_NAMESPACE_SETTING, namespace)]),
+        None => options,
     }
 }
 
+/// Returns true if the `error` is the result of a lost connection that might be recovered by
+/// reconnecting to the database
+fn is_recoverable(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed => true,
+        sqlx::Error::Database(db_error) => db_error
+            .code()
+            .is_some_and(|code| code.starts_with("08") || code.starts_with("57P")),
+        _ => false,
+    }
+}
+
+/// Delay before the reconnect `attempt` (starting at 1). Doubles the base delay for each attempt,
+/// capped at the maximum delay.
+fn reconnect_delay(attempt: u32) -> Duration {
+    BASE_RECONNECT_DELAY
+        .saturating_mul(2_u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_RECONNECT_DELAY)
+}
+
+/// Spawn a task that receives the notifications of every channel in `channels` through a single
+/// [PgChangeListener] and fans each message out to every subscriber of the returned
+/// [broadcast::Sender], so any number of clients share one database connection. Messages are
+/// converted by `map` before being sent and dropped when `map` returns [None]. Subscribers that
+/// fall more than `capacity` messages behind skip the oldest messages. If the listener fails after
+/// exhausting its reconnect attempts, a new listener is connected after the maximum reconnect
+/// delay. Must be called within a tokio runtime.
+pub fn spawn_broadcast<M, T, F>(
+    pool: &PgPool,
+    channels: &[&str],
+    capacity: usize,
+    map: F,
+) -> broadcast::Sender<T>
+where
+    M: for<'m> From<&'m str> + Send + Sync + 'static,
+    T: Clone + Send + 'static,
+    F: Fn(M) -> Option<T> + Send + 'static,
+{
+    let (sender, _) = broadcast::channel(capacity);
+    let pool = pool.clone();
+    let channels: Vec<String> = channels
+        .iter()
+        .map(|channel| (*channel).to_owned())
+        .collect();
+    let task_sender = sender.clone();
+    tokio::spawn(async move {
+        let channels: Vec<&str> = channels.iter().map(String::as_str).collect();
+        loop {
+            match PgChangeListener::<M>::connect_many(&pool, &channels).await {
+                Ok(mut listener) => loop {
+                    match listener.recv().await {
+                        Ok(message) => {
+                            if let Some(message) = map(message) {
+                                // Sending only fails when there are no subscribers
+                                let _ = task_sender.send(message);
+                            }
+                        }
+                        Err(error) => {
+                            error!(
+                                "Shared listener of '{}' closed.\n{}",
+                                channels.join(", "),
+                                error
+                            );
+                            break;
+                        }
+                    }
+                },
+                Err(error) => {
+                    error!(
+                        "Could not connect shared listener of '{}'.\n{}",
+                        channels.join(", "),
+                        error
+                    );
+                }
+            }
+            tokio::time::sleep(MAX_RECONNECT_DELAY).await;
+        }
+    });
+    sender
+}
+
 impl<M> ChangeListener for PgChangeListener<M>
 where
     M: for<'m> From<&'m str> + Send + Sync,
@@ -36,13 +200,75 @@ where
     type Message = M;
 
     async fn recv(&mut self) -> EmResult<M> {
-        let notification = match self.listener.recv().await {
-            Ok(notification) => notification,
-            Err(error) => {
+        let mut attempts = 0;
+        loop {
+            let error = match self.listener.recv().await {
+                Ok(notification) => return Ok(notification.payload().into()),
+                Err(error) => error,
+            };
+            if !is_recoverable(&error) || attempts >= self.max_reconnect_attempts {
                 error!("Error receiving notification.\n{:?}", error);
                 return Err(error.into());
             }
-        };
-        Ok(notification.payload().into())
+            attempts += 1;
+            let delay = reconnect_delay(attempts);
+            warn!(
+                "Lost connection listening to '{}'. Reconnect attempt {}/{} in {:?}.\n{}",
+                self.channels.join(", "),
+                attempts,
+                self.max_reconnect_attempts,
+                delay,
+                error
+            );
+            tokio::time::sleep(delay).await;
+            if let Err(error) = self.reconnect().await {
+                warn!(
+                    "Could not reconnect listener to '{}'.\n{}",
+                    self.channels.join(", "),
+                    error
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io, time::Duration};
+
+    use rstest::rstest;
+
+    use super::{is_recoverable, reconnect_delay, BASE_RECONNECT_DELAY, MAX_RECONNECT_DELAY};
+    use crate::database::test::CodeError;
+
+    #[rstest]
+    #[case::io(sqlx::Error::Io(io::Error::from(io::ErrorKind::ConnectionReset)), true)]
+    #[case::pool_timed_out(sqlx::Error::PoolTimedOut, true)]
+    #[case::worker_crashed(sqlx::Error::WorkerCrashed, true)]
+    #[case::connection_failure(CodeError::sqlx_error("08006"), true)]
+    #[case::admin_shutdown(CodeError::sqlx_error("57P01"), true)]
+    #[case::unique_violation(CodeError::sqlx_error("23505"), false)]
+    #[case::syntax_error(CodeError::sqlx_error("42601"), false)]
+    #[case::row_not_found(sqlx::Error::RowNotFound, false)]
+    #[case::pool_closed(sqlx::Error::PoolClosed, false)]
+    fn is_recoverable_should_only_match_lost_connection_errors(
+        #[case] error: sqlx::Error,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_recoverable(&error), expected, "{error}");
+    }
+
+    #[rstest]
+    #[case::no_attempt(0, BASE_RECONNECT_DELAY)]
+    #[case::first_attempt(1, BASE_RECONNECT_DELAY)]
+    #[case::second_attempt(2, BASE_RECONNECT_DELAY * 2)]
+    #[case::sixth_attempt(6, BASE_RECONNECT_DELAY * 32)]
+    #[case::capped(7, MAX_RECONNECT_DELAY)]
+    #[case::max_attempt(u32::MAX, MAX_RECONNECT_DELAY)]
+    fn reconnect_delay_should_double_until_capped(
+        #[case] attempt: u32,
+        #[case] expected: Duration,
+    ) {
+        assert_eq!(reconnect_delay(attempt), expected);
     }
 }
@@ -1,10 +1,18 @@
 use lazy_regex::{regex, Lazy, Regex};
 use sqlx::{
     postgres::{PgConnectOptions, PgPoolOptions},
-    PgPool,
+    PgPool, Transaction,
 };
 
-use crate::{database::Database, error::EmResult};
+use crate::{
+    database::{
+        close_pool_with_timeout,
+        connection::{ConnectionBuilder, PoolStats},
+        postgres::connection::PgConnectionBuilder,
+        Database,
+    },
+    error::EmResult,
+};
 
 pub mod build;
 pub mod connection;
@@ -41,6 +49,33 @@ impl Database for Postgres {
             .max_connections(max_connections)
             .connect_lazy_with(options)
     }
+
+    async fn ping(pool: &Self::ConnectionPool) -> EmResult<()> {
+        sqlx::query("select 1").execute(pool).await?;
+        Ok(())
+    }
+
+    fn pool_stats(pool: &Self::ConnectionPool) -> PoolStats {
+        PgConnectionBuilder::pool_stats(pool)
+    }
+
+    async fn close_pool(pool: &Self::ConnectionPool) {
+        close_pool_with_timeout(pool).await;
+    }
+}
+
+/// Begin a read only transaction on the `pool` with repeatable read isolation. Every query within
+/// the transaction reads from the same snapshot so related reads (e.g. a page of records and the
+/// total number of records) agree with each other.
+/// # Errors
+/// This function will return an error if the transaction cannot be started or the isolation level
+/// cannot be set
+pub async fn begin_snapshot(pool: &PgPool) -> EmResult<Transaction<'static, sqlx::Postgres>> {
+    let mut transaction = pool.begin().await?;
+    sqlx::query("set transaction isolation level repeatable read, read only")
+        .execute(&mut transaction)
+        .await?;
+    Ok(transaction)
 }
 
 /// Regex to find and parse a create type postgres statement
@@ -0,0 +1,28 @@
+use sqlx::{sqlite::SqliteConnectOptions, Sqlite as SqlxSqlite, SqlitePool};
+
+use crate::{
+    database::{connection::ConnectionBuilder, sqlite::Sqlite, Database},
+    error::EmResult,
+};
+
+/// [ConnectionBuilder] for SQLite pools. Delegates to the [Sqlite] implementation of [Database] so
+/// both create pools with the same options.
+pub struct SqliteConnectionBuilder;
+
+impl ConnectionBuilder<SqlxSqlite> for SqliteConnectionBuilder {
+    async fn create_pool(
+        options: SqliteConnectOptions,
+        max_connections: u32,
+        min_connection: u32,
+    ) -> EmResult<SqlitePool> {
+        Sqlite::create_pool(options, max_connections, min_connection).await
+    }
+
+    fn create_pool_lazy(
+        options: SqliteConnectOptions,
+        max_connections: u32,
+        min_connection: u32,
+    ) -> SqlitePool {
+        Sqlite::create_pool_lazy(options, max_connections, min_connection)
+    }
+}
@@ -0,0 +1,99 @@
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqlitePool,
+};
+
+use crate::{
+    database::{
+        close_pool_with_timeout,
+        connection::{ConnectionBuilder, PoolStats},
+        sqlite::connection::SqliteConnectionBuilder,
+        Database,
+    },
+    error::EmResult,
+};
+
+pub mod connection;
+
+/// SQLite implementation of the [Database] interface. Intended for local development and tests
+/// that should not depend on a running Postgresql server.
+///
+/// Only the [Database] trait and connection layer are supported. The services and database
+/// scripts are written for Postgresql, so the following features are not available:
+/// - LISTEN/NOTIFY, meaning there is no
+///   [ChangeListener][crate::database::listener::ChangeListener] implementation
+/// - stored procedures, functions and schemas used by the services
+/// - session parameters such as `em.uid` set by
+///   [get_connection_with_em_uid][crate::database::connection::get_connection_with_em_uid]
+pub struct Sqlite;
+
+impl Database for Sqlite {
+    type ConnectionOptions = SqliteConnectOptions;
+    type ConnectionPool = SqlitePool;
+
+    async fn create_pool(
+        options: Self::ConnectionOptions,
+        max_connections: u32,
+        min_connection: u32,
+    ) -> EmResult<Self::ConnectionPool> {
+        let pool = SqlitePoolOptions::new()
+            .min_connections(min_connection)
+            .max_connections(max_connections)
+            .connect_with(options)
+            .await?;
+        Ok(pool)
+    }
+
+    fn create_pool_lazy(
+        options: Self::ConnectionOptions,
+        max_connections: u32,
+        min_connection: u32,
+    ) -> Self::ConnectionPool {
+        SqlitePoolOptions::new()
+            .min_connections(min_connection)
+            .max_connections(max_connections)
+            .connect_lazy_with(options)
+    }
+
+    async fn ping(pool: &Self::ConnectionPool) -> EmResult<()> {
+        sqlx::query("select 1").execute(pool).await?;
+        Ok(())
+    }
+
+    fn pool_stats(pool: &Self::ConnectionPool) -> PoolStats {
+        SqliteConnectionBuilder::pool_stats(pool)
+    }
+
+    async fn close_pool(pool: &Self::ConnectionPool) {
+        close_pool_with_timeout(pool).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use sqlx::sqlite::SqliteConnectOptions;
+
+    use super::Sqlite;
+    use crate::{database::Database, error::EmResult};
+
+    #[tokio::test]
+    async fn ping_should_succeed_when_database_is_in_memory() -> EmResult<()> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = Sqlite::create_pool(options, 1, 1).await?;
+
+        Sqlite::ping(&pool).await
+    }
+
+    #[tokio::test]
+    async fn close_pool_should_close_pool() -> EmResult<()> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = Sqlite::create_pool(options, 1, 1).await?;
+
+        Sqlite::close_pool(&pool).await;
+
+        assert!(pool.is_closed());
+        Ok(())
+    }
+}
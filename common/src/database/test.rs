@@ -1,3 +1,9 @@
+#[cfg(test)]
+use std::{borrow::Cow, error::Error, fmt::Display};
+
+#[cfg(test)]
+use sqlx::error::DatabaseError;
+
 use crate::database::{Database, RolledBackTransactionResult};
 
 /// Behaviour to allow for databases to have various test scripts run. This type should be
@@ -25,3 +31,50 @@ where
         block: &str,
     ) -> RolledBackTransactionResult<Self::BlockError, Self::TransactionError>;
 }
+
+/// Database error reporting a fixed SQLSTATE code. Used to test the handling of database errors
+/// without a live database.
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct CodeError(pub &'static str);
+
+#[cfg(test)]
+impl CodeError {
+    /// Create a [sqlx::Error] for a database error with the SQLSTATE `code`
+    pub(crate) fn sqlx_error(code: &'static str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(Self(code)))
+    }
+}
+
+#[cfg(test)]
+impl Display for CodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SQLSTATE {}", self.0)
+    }
+}
+
+#[cfg(test)]
+impl Error for CodeError {}
+
+#[cfg(test)]
+impl DatabaseError for CodeError {
+    fn message(&self) -> &str {
+        self.0
+    }
+
+    fn code(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.0))
+    }
+
+    fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+        self
+    }
+}
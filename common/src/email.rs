@@ -1,4 +1,7 @@
-use std::env;
+use std::{
+    env,
+    fmt::{Debug, Formatter},
+};
 
 use lettre::{
     transport::smtp::{authentication::Credentials, response::Response},
@@ -19,52 +22,223 @@ pub trait EmailService {
     async fn send_email<S>(&self, to: S, subject: S, body: S) -> EmResult<Self::Response>
     where
         S: AsRef<str>;
+    /// Send a single email to every recipient in `to`, with the provided `subject` and `body`
+    /// # Errors
+    /// This function will return an error if `to` is empty, an error is returned creating the
+    /// email message or sending the email.
+    async fn send_to_many<S>(&self, to: &[S], subject: S, body: S) -> EmResult<Self::Response>
+    where
+        S: AsRef<str>;
+    /// Verify that the underlining email transport can be reached
+    /// # Errors
+    /// This function will return an error if the connection to the email transport fails or the
+    /// transport does not accept the connection
+    async fn test_connection(&self) -> EmResult<()>;
 }
 
-/// Default implementation of an [EmailService]
-pub struct ClippyEmailService {
-    mailer: AsyncSmtpTransport<Tokio1Executor>,
+/// Connection details and credentials required to create a [Mailer]. The password is never
+/// included in the [Debug] output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MailerConfig {
+    /// Username used to authenticate with the SMTP relay
+    pub username: String,
+    /// Password used to authenticate with the SMTP relay
+    pub password: String,
+    /// Host name of the SMTP relay
+    pub relay: String,
+    /// Mailbox that emails are sent from (e.g. `Clippy <clippy@example.com>`)
+    pub sender: String,
 }
 
-impl ClippyEmailService {
-    /// Create a new instance of [ClippyEmailService]. Reads environment variables to capture
-    /// details required to send emails as clippy.
-    /// # Errors
-    /// This function will returns an error if there are missing environment variables or the SMTP
-    /// transport cannot be created. Require environment variables are:
+impl Debug for MailerConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MailerConfig")
+            .field("username", &self.username)
+            .field("password", &"********")
+            .field("relay", &self.relay)
+            .field("sender", &self.sender)
+            .finish()
+    }
+}
+
+impl MailerConfig {
+    /// Create a new [MailerConfig] from environment variables. The environment variables read are:
     /// - CLIPPY_USERNAME -> email service username
     /// - CLIPPY_PASSWORD -> email service password
     /// - CLIPPY_RELAY -> email service relay
-    pub fn new() -> EmResult<Self> {
-        let username = env::var("CLIPPY_USERNAME")?;
-        let password = env::var("CLIPPY_PASSWORD")?;
-        let relay = env::var("CLIPPY_RELAY")?;
-        let credentials = Credentials::from((username, password));
-        let mailer = AsyncSmtpTransport::<Tokio1Executor>::relay(&relay)?
+    /// - CLIPPY_SENDER -> optional sender mailbox (default `Clippy <{CLIPPY_USERNAME}>`)
+    /// # Errors
+    /// This function will return an error if a required environment variable is missing
+    pub fn from_env() -> EmResult<Self> {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Create a new [MailerConfig] using the `lookup` function to find each configuration value by
+    /// name
+    /// # Errors
+    /// This function will return an error if a required value is not found
+    fn from_lookup<F>(lookup: F) -> EmResult<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let required = |key: &str| {
+            lookup(key).ok_or_else(|| format!("Missing required environment variable '{key}'"))
+        };
+        let username = required("CLIPPY_USERNAME")?;
+        let password = required("CLIPPY_PASSWORD")?;
+        let relay = required("CLIPPY_RELAY")?;
+        let sender = lookup("CLIPPY_SENDER").unwrap_or_else(|| format!("Clippy <{username}>"));
+        Ok(Self {
+            username,
+            password,
+            relay,
+            sender,
+        })
+    }
+}
+
+/// Default implementation of an [EmailService]. Sends emails through an SMTP relay using the
+/// credentials of a [MailerConfig].
+pub struct Mailer {
+    /// SMTP transport used to send every email
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    /// Mailbox that emails are sent from
+    sender: String,
+}
+
+impl Mailer {
+    /// Create a new [Mailer] using the connection details and credentials of the `config`
+    /// # Errors
+    /// This function will return an error if the SMTP transport cannot be created
+    pub fn new(config: MailerConfig) -> EmResult<Self> {
+        let credentials = Credentials::from((config.username, config.password));
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::relay(&config.relay)?
             .credentials(credentials)
             .build();
-        Ok(Self { mailer })
+        Ok(Self {
+            transport,
+            sender: config.sender,
+        })
+    }
+
+    /// Create a new [Mailer] using a [MailerConfig] read from the environment. See
+    /// [MailerConfig::from_env] for the environment variables read.
+    /// # Errors
+    /// This function will return an error if there are missing environment variables or the SMTP
+    /// transport cannot be created
+    pub fn from_env() -> EmResult<Self> {
+        Self::new(MailerConfig::from_env()?)
     }
 }
 
-impl EmailService for ClippyEmailService {
+impl EmailService for Mailer {
     type Response = Response;
 
     async fn send_email<S>(&self, to: S, subject: S, body: S) -> EmResult<Self::Response>
     where
         S: AsRef<str>,
     {
+        self.send_to_many(&[to], subject, body).await
+    }
+
+    async fn send_to_many<S>(&self, to: &[S], subject: S, body: S) -> EmResult<Self::Response>
+    where
+        S: AsRef<str>,
+    {
+        if to.is_empty() {
+            return Err("Cannot send an email without any recipients".into());
+        }
+        let recipients: Vec<&str> = to.iter().map(AsRef::as_ref).collect();
         info!(
-            "Sending error email to {} with message\n{}",
-            to.as_ref(),
+            "Sending email to {} with message\n{}",
+            recipients.join(", "),
             body.as_ref()
         );
-        let email = Message::builder()
-            .from("Clippy".parse()?)
-            .to(to.as_ref().parse()?)
-            .subject(subject.as_ref())
-            .body(body.as_ref().to_owned())?;
-        let response = self.mailer.send(email).await?;
+        let mut builder = Message::builder()
+            .from(self.sender.parse()?)
+            .subject(subject.as_ref());
+        for recipient in recipients {
+            builder = builder.to(recipient.parse()?);
+        }
+        let email = builder.body(body.as_ref().to_owned())?;
+        let response = self.transport.send(email).await?;
         Ok(response)
     }
+
+    async fn test_connection(&self) -> EmResult<()> {
+        if !self.transport.test_connection().await? {
+            return Err("SMTP relay did not accept the test connection".into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::panic)]
+mod test {
+    use rstest::rstest;
+
+    use super::{EmailService, Mailer, MailerConfig};
+    use crate::test::env_lookup;
+
+    #[test]
+    fn from_lookup_should_default_sender_to_username() {
+        let config = MailerConfig::from_lookup(env_lookup(&[
+            ("CLIPPY_USERNAME", "clippy@example.com"),
+            ("CLIPPY_PASSWORD", "secret"),
+            ("CLIPPY_RELAY", "smtp.example.com"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.sender, "Clippy <clippy@example.com>");
+    }
+
+    #[rstest]
+    #[case::username("CLIPPY_USERNAME")]
+    #[case::password("CLIPPY_PASSWORD")]
+    #[case::relay("CLIPPY_RELAY")]
+    fn from_lookup_should_fail_when_missing(#[case] missing_key: &str) {
+        let pairs: Vec<(&str, &str)> = [
+            ("CLIPPY_USERNAME", "clippy@example.com"),
+            ("CLIPPY_PASSWORD", "secret"),
+            ("CLIPPY_RELAY", "smtp.example.com"),
+        ]
+        .into_iter()
+        .filter(|(key, _)| *key != missing_key)
+        .collect();
+
+        let Err(error) = MailerConfig::from_lookup(env_lookup(&pairs)) else {
+            panic!("Expected an error when '{missing_key}' is missing");
+        };
+        assert!(error.to_string().contains(missing_key), "{error}");
+    }
+
+    #[test]
+    fn debug_should_not_include_password() {
+        let config = MailerConfig {
+            username: "clippy@example.com".to_owned(),
+            password: "secret".to_owned(),
+            relay: "smtp.example.com".to_owned(),
+            sender: "Clippy <clippy@example.com>".to_owned(),
+        };
+
+        let output = format!("{config:?}");
+
+        assert!(!output.contains("secret"), "{output}");
+    }
+
+    #[tokio::test]
+    async fn send_to_many_should_fail_when_no_recipients() {
+        let mailer = Mailer::new(MailerConfig {
+            username: "clippy@example.com".to_owned(),
+            password: "secret".to_owned(),
+            relay: "smtp.example.com".to_owned(),
+            sender: "Clippy <clippy@example.com>".to_owned(),
+        })
+        .unwrap();
+
+        let result = mailer.send_to_many(&[] as &[&str], "Subject", "Body").await;
+
+        assert!(result.is_err(), "Expected an error with no recipients");
+    }
 }
@@ -24,6 +24,18 @@ pub enum EmError {
     ExecutorInit(&'static str),
     #[error("Exited remote task run unexpectedly")]
     ExitedTask,
+    #[error("Remote task run exceeded the timeout of {0:?}")]
+    TaskTimeout(std::time::Duration),
+    #[error("Remote task run sent no response for {0:?}")]
+    TaskStreamIdle(std::time::Duration),
+    #[error("Remote task endpoint responded with HTTP status {status}. Response body: {body}")]
+    TaskHttpStatus { status: u16, body: String },
+    #[error("Task parameters are not valid\n{0}")]
+    InvalidTaskParameters(String),
+    #[error("Task url is not valid\n{0}")]
+    InvalidTaskUrl(String),
+    #[error("Cron expression `{expression}` is not valid. {reason}")]
+    InvalidCronExpression { expression: String, reason: String },
     #[error("MessagePack encode error\n{0}")]
     RmpEncode(#[from] rmp_serde::encode::Error),
     #[error("MessagePack decode error\n{0}")]
@@ -34,6 +46,8 @@ pub enum EmError {
     Reqwest(#[from] reqwest::Error),
     #[error("Generic error\n{0}")]
     Generic(String),
+    #[error("Service unavailable\n{0}")]
+    ServiceUnavailable(String),
     #[error("Duplicate Job Entry\nId: {0}\nNext Runs:{1:?}")]
     DuplicateJobId(i64, [NaiveDateTime; 2]),
     #[error("Notification Payload Parse Error\nNotification: `{0}`")]
@@ -46,6 +60,8 @@ pub enum EmError {
     SmtpError(#[from] StmpError),
     #[error("{0}")]
     ParseInt(#[from] std::num::ParseIntError),
+    #[error("{0}")]
+    ParseFloat(#[from] std::num::ParseFloatError),
     #[error("Environment Variable error\n{0}")]
     EnvVar(#[from] std::env::VarError),
     #[error("IO error\n{0}")]
@@ -55,13 +71,25 @@ pub enum EmError {
     #[error("User missing privilege. UID = {uid}, role = {role}")]
     MissingPrivilege { uid: Uuid, role: &'static str },
     #[error("Password is not valid. {reason}")]
-    InvalidPassword { reason: &'static str },
+    InvalidPassword { reason: String },
     #[error("Record cannot be found for `{pk}`")]
     MissingRecord { pk: String },
+    #[error("Invalid {kind} `{value}`. {reason}")]
+    InvalidId {
+        kind: &'static str,
+        value: String,
+        reason: &'static str,
+    },
     #[error("Contents of request '{request}' were not valid.\nReason: {reason}")]
     InvalidRequest { request: String, reason: String },
     #[error("{0}")]
     ApiRequestPayload(#[from] ApiRequestPayloadError),
+    #[error("Startup self-test failed\n{0}")]
+    SelfTest(String),
+    #[error("Metrics error\n{0}")]
+    Metrics(#[from] prometheus::Error),
+    #[error("TLS configuration error\n{0}")]
+    Tls(String),
 }
 
 impl From<&str> for EmError {
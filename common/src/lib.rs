@@ -51,9 +51,12 @@ use tokio::{fs::File, io::AsyncReadExt};
 use crate::error::EmResult;
 
 pub mod api;
+pub mod audit;
 pub mod database;
 pub mod email;
 pub mod error;
+pub mod logging;
+pub mod test;
 
 /// Returns a [PathBuf] pointing to the directory of the current package. Utilizes the
 /// 'CARGO_MANIFEST_DIR' cargo environment variable.
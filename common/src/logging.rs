@@ -0,0 +1,152 @@
+use std::{env, path::Path};
+
+use log::{LevelFilter, Log, Metadata, Record};
+use log4rs::{
+    append::console::ConsoleAppender,
+    config::{load_config_file, Appender, Root},
+    encode::json::JsonEncoder,
+    Config, Logger,
+};
+
+use crate::{api::request_id::current_request_id, error::EmResult};
+
+/// Environment variable that selects the log output format. Set to `json` to emit JSON lines
+const LOG_FORMAT_ENV: &str = "EM_LOG_FORMAT";
+/// Name of the console appender used when logging JSON lines
+const JSON_APPENDER: &str = "stdout";
+
+/// Output formats supported by [init]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Layout defined by a log4rs YAML configuration file
+    Pattern,
+    /// Machine-parseable JSON lines written to stdout. Each line contains the level, target,
+    /// timestamp and message of the log record.
+    Json,
+}
+
+impl LogFormat {
+    /// Read the [LogFormat] from the `EM_LOG_FORMAT` environment variable. Any value other than
+    /// `json` (or no value) selects the [Pattern][LogFormat::Pattern] format.
+    pub fn from_env() -> Self {
+        Self::from_value(env::var(LOG_FORMAT_ENV).ok().as_deref())
+    }
+
+    /// Parse the [LogFormat] from an optional environment variable `value`
+    fn from_value(value: Option<&str>) -> Self {
+        match value {
+            Some(value) if value.trim().eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Pattern,
+        }
+    }
+}
+
+/// [Log] implementation wrapping a log4rs [Logger]. Records logged while a request wrapped by the
+/// [PropagateRequestId][crate::api::request_id::PropagateRequestId] middleware is handled have the
+/// request's id prepended to the message so every log line of a request can be correlated.
+struct RequestIdLogger {
+    /// Logger that writes every record
+    inner: Logger,
+}
+
+impl Log for RequestIdLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let Some(request_id) = current_request_id() else {
+            self.inner.log(record);
+            return;
+        };
+        self.inner.log(
+            &Record::builder()
+                .args(format_args!("[{request_id}] {}", record.args()))
+                .metadata(record.metadata().clone())
+                .module_path(record.module_path())
+                .file(record.file())
+                .line(record.line())
+                .build(),
+        );
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Install a [RequestIdLogger] using the log4rs `config` as the global logger
+/// # Errors
+/// This function will return an error if a logger has already been initialized
+fn install(config: Config) -> EmResult<()> {
+    let logger = RequestIdLogger {
+        inner: Logger::new(config),
+    };
+    log::set_max_level(logger.inner.max_log_level());
+    log::set_boxed_logger(Box::new(logger))
+        .map_err(|error| format!("Could not initialize logging. {error}"))?;
+    Ok(())
+}
+
+/// Initialize logging for the current process. When `EM_LOG_FORMAT=json` is set, log records are
+/// written to stdout as JSON lines. Otherwise, the log4rs YAML configuration found at
+/// `config_path` is used. Log lines written while handling a request include the request's id.
+/// # Errors
+/// This function will return an error if the logger cannot be configured or a logger has already
+/// been initialized
+pub fn init<P>(config_path: P) -> EmResult<()>
+where
+    P: AsRef<Path>,
+{
+    match LogFormat::from_env() {
+        LogFormat::Json => init_json(),
+        LogFormat::Pattern => {
+            let config =
+                load_config_file(config_path.as_ref(), Default::default()).map_err(|error| {
+                    format!(
+                        "Could not initialize logging from {:?}. {error}",
+                        config_path.as_ref()
+                    )
+                })?;
+            install(config)
+        }
+    }
+}
+
+/// Initialize logging to stdout using a JSON encoder at the `info` level
+/// # Errors
+/// This function will return an error if the logger cannot be configured or a logger has already
+/// been initialized
+fn init_json() -> EmResult<()> {
+    let stdout = ConsoleAppender::builder()
+        .encoder(Box::new(JsonEncoder::new()))
+        .build();
+    let config = Config::builder()
+        .appender(Appender::builder().build(JSON_APPENDER, Box::new(stdout)))
+        .build(
+            Root::builder()
+                .appender(JSON_APPENDER)
+                .build(LevelFilter::Info),
+        )
+        .map_err(|error| format!("Could not build JSON logging configuration. {error}"))?;
+    install(config)
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::LogFormat;
+
+    #[rstest]
+    #[case::missing(None, LogFormat::Pattern)]
+    #[case::json(Some("json"), LogFormat::Json)]
+    #[case::json_uppercase(Some(" JSON "), LogFormat::Json)]
+    #[case::other(Some("pattern"), LogFormat::Pattern)]
+    fn from_value_should_select_format_when(
+        #[case] value: Option<&str>,
+        #[case] expected: LogFormat,
+    ) {
+        assert_eq!(LogFormat::from_value(value), expected);
+    }
+}
@@ -0,0 +1,74 @@
+//! Shared test support for the EnviroManager application suite. Provides lookup functions in place
+//! of environment variables and stub HTTP services for testing API clients.
+
+use std::{collections::HashMap, net::TcpListener as StdTcpListener};
+
+use serde::Serialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use crate::{api::ApiResponseBody, error::EmResult};
+
+/// Lookup function over the provided key value `pairs`. Used in place of an environment variable
+/// lookup when testing configuration that is read from the environment.
+pub fn env_lookup<'a>(pairs: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+    let values: HashMap<&str, &str> = pairs.iter().copied().collect();
+    move |key| values.get(key).map(|value| (*value).to_owned())
+}
+
+/// Get a local base url that nothing is listening on, so connections to it are refused
+/// # Errors
+/// This function will return an error if a local port cannot be bound to find an unused port
+pub fn refused_url() -> EmResult<String> {
+    let port = StdTcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    Ok(format!("http://127.0.0.1:{port}"))
+}
+
+/// Start a stub HTTP service that responds to a single request with the `status_line` and `body`.
+/// A `content_type` header is included when provided. Returns the base url of the stub service.
+/// # Errors
+/// This function will return an error if a local port cannot be bound for the stub service
+pub async fn stub_http_response(
+    status_line: &'static str,
+    content_type: Option<&'static str>,
+    body: Vec<u8>,
+) -> EmResult<String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    tokio::spawn(async move {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            return;
+        };
+        let mut buffer = [0; 4096];
+        if stream.read(&mut buffer).await.is_err() {
+            return;
+        }
+        let content_type = content_type
+            .map(|content_type| format!("content-type: {content_type}\r\n"))
+            .unwrap_or_default();
+        let head = format!(
+            "HTTP/1.1 {status_line}\r\n{content_type}content-length: {}\r\nconnection: \
+             close\r\n\r\n",
+            body.len()
+        );
+        if stream.write_all(head.as_bytes()).await.is_ok() {
+            let _ = stream.write_all(&body).await;
+        }
+    });
+    Ok(format!("http://127.0.0.1:{port}"))
+}
+
+/// Start a stub API that responds to a single request with a successful msgpack response
+/// containing the `data`. Returns the base url of the stub API.
+/// # Errors
+/// This function will return an error if the `data` cannot be serialized or a local port cannot
+/// be bound for the stub API
+pub async fn stub_api_success<T>(data: T) -> EmResult<String>
+where
+    T: Serialize,
+{
+    let body = rmp_serde::to_vec(&ApiResponseBody::Success(data))?;
+    stub_http_response("200 OK", Some("application/msgpack"), body).await
+}
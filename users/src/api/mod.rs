@@ -1,26 +1,88 @@
-use std::net::ToSocketAddrs;
+use std::env;
 
 use actix_web::{
     middleware::Logger,
-    web::{get, patch, post, Data},
+    web::{get, patch, post, resource, Data},
     App, HttpServer,
 };
 use actix_web_httpauth::extractors::bearer::BearerAuth;
 use common::{
-    api::{ApiContentFormat, ApiResponse},
+    api::{health, request_id::PropagateRequestId, tls::TlsConfig, ApiContentFormat, ApiResponse},
     database::Database,
-    error::EmResult,
+    error::{EmError, EmResult},
 };
 use log::error;
 use serde::Serialize;
 use uuid::Uuid;
 
+pub mod rate_limit;
 pub mod roles;
 pub mod users;
 
-use crate::service::{roles::RoleService, users::UserService};
+use self::rate_limit::{RateLimitConfig, RateLimiter, ValidateRateLimit};
+use crate::{
+    data::role::RoleName,
+    service::{roles::RoleService, users::UserService},
+};
 
 const BEARER_ERROR: &str = "Cannot parse bearer token";
+/// Default address the API server binds to when `USERS_API_ADDR` is not set
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8001";
+
+/// Configuration of the users API server. Contains the `address` to bind, the optional `tls`
+/// configuration used to serve the API over HTTPS and the `validate_rate_limit` applied to
+/// attempts to validate user credentials.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// Address (host and port) that the server binds to
+    pub address: String,
+    /// Certificate and key used to serve HTTPS. When [None], the server binds using plain HTTP
+    pub tls: Option<TlsConfig>,
+    /// Limits applied to attempts to validate user credentials
+    pub validate_rate_limit: RateLimitConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: DEFAULT_ADDRESS.to_owned(),
+            tls: None,
+            validate_rate_limit: RateLimitConfig::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Create a new [ServerConfig] from environment variables, using the default value for any
+    /// variable that is not set. The environment variables read are:
+    /// - USERS_API_ADDR -> address the server binds to (default `127.0.0.1:8001`)
+    /// - USERS_TLS_CERT -> path to the PEM certificate chain used to serve HTTPS (default is no
+    ///   TLS)
+    /// - USERS_TLS_KEY -> path to the PEM private key used to serve HTTPS (default is no TLS)
+    /// - the validate rate limit variables described in [RateLimitConfig::validate_from_env]
+    /// # Errors
+    /// This function will return an error if a rate limit variable cannot be parsed or only one of
+    /// the TLS variables is set
+    pub fn from_env() -> EmResult<Self> {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Create a new [ServerConfig] using the `lookup` function to find each configuration value by
+    /// name. Values that are not found are replaced with their default.
+    /// # Errors
+    /// This function will return an error if a rate limit value cannot be parsed or only one of the
+    /// TLS values is found
+    fn from_lookup<F>(lookup: F) -> EmResult<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        Ok(Self {
+            address: lookup("USERS_API_ADDR").unwrap_or_else(|| DEFAULT_ADDRESS.to_owned()),
+            tls: TlsConfig::from_lookup("USERS_TLS_CERT", "USERS_TLS_KEY", &lookup)?,
+            validate_rate_limit: RateLimitConfig::validate_from_lookup(&lookup)?,
+        })
+    }
+}
 
 /// Validation result of a [BearerAuth] extractor. Validates into the users uid or an [ApiResponse]
 /// when the bearer authorization cannot be parsed.
@@ -47,44 +109,236 @@ where
     BearerValidation::Valid(uid)
 }
 
+/// Require that the user authenticated by the `bearer` token has been granted the `privilege`
+/// (or is an admin). Returns the uid of the user so route handlers can continue processing the
+/// request on behalf of the user.
+///
+/// Other services (e.g. the workflow-engine) do not have access to the [RoleService] so they
+/// should consult the users API through
+/// [UsersApiClient::require_privilege][crate::client::UsersApiClient::require_privilege], passing
+/// along the bearer token received by the route handler, before performing destructive actions.
+/// # Errors
+/// This function will return an error if:
+/// - the bearer token is not a valid uid ([EmError::InvalidUser], 401)
+/// - the roles of the user cannot be read
+/// - the user does not have the `privilege` ([EmError::MissingPrivilege], 403)
+pub async fn require_privilege<R>(
+    service: &R,
+    bearer: &BearerAuth,
+    privilege: RoleName,
+) -> EmResult<Uuid>
+where
+    R: RoleService,
+{
+    let Ok(uid) = bearer.token().parse() else {
+        error!("Got invalid bearer token. Token = '{}'", bearer.token());
+        return Err(EmError::InvalidUser);
+    };
+    let roles = service.read_roles_for_user(&uid).await?;
+    if roles.iter().any(|role| role.grants(privilege)) {
+        return Ok(uid);
+    }
+    Err(EmError::MissingPrivilege {
+        uid,
+        role: privilege.into(),
+    })
+}
+
 /// Run generic API server. Creates all the required endpoints and resources. To run the api server,
 /// you must have created a [ConnectionBuilder], [RoleService] and [UserService] for your desired
 /// [Database] implementation. Each component depends of a [Database] type so the system cannot
-/// contain disjointed service implementations to operate.
+/// contain disjointed service implementations to operate. The `pool` is used for the `/health` and
+/// `/ready` probes which are mounted at the root of the server, outside the `/api/v1` scope.
+/// Attempts to validate user credentials are limited per client address and username using the
+/// `config`'s `validate_rate_limit`. Every request is assigned a request id (see
+/// [PropagateRequestId]) that is included in the log lines written while handling the request. The
+/// server binds to the `address` specified in the `config`. If the `config` contains a
+/// [TlsConfig], the certificate and key are loaded before the server starts and the server binds
+/// using HTTPS, otherwise plain HTTP is used.
 /// # Errors
-/// This function will return an error if the server is unable to bind to the specified `address` or
-/// the server's `run` method returns an error
-pub async fn spawn_api_server<A, D, R, U>(
+/// This function will return an error if the TLS certificate or key cannot be loaded, the server is
+/// unable to bind to the configured `address` or the server's `run` method returns an error
+pub async fn spawn_api_server<D, R, U>(
     users_service: U,
     roles_service: R,
-    address: A,
+    pool: D::ConnectionPool,
+    config: &ServerConfig,
 ) -> EmResult<()>
 where
-    A: ToSocketAddrs,
-    D: Database,
+    D: Database + 'static,
+    D::ConnectionPool: Send + Sync + 'static,
     R: RoleService<UserService = U> + Send + Sync + 'static,
     U: UserService<Database = D> + Send + Sync + 'static,
 {
     let roles_service_data: Data<R> = Data::new(roles_service);
     let users_service_data: Data<U> = Data::new(users_service);
-    HttpServer::new(move || {
-        App::new().service(
-            actix_web::web::scope("/api/v1")
-                .wrap(Logger::default())
-                .app_data(roles_service_data.clone())
-                .app_data(users_service_data.clone())
-                .route("/roles", get().to(roles::roles::<R>))
-                .route("/user", get().to(users::read_current_user::<U>))
-                .route("/user/{uid}", get().to(users::read_user::<U>))
-                .route("/users", get().to(users::read_users::<U>))
-                .route("/users", post().to(users::create_user::<U>))
-                .route("/users", patch().to(users::update_user::<U>))
-                .route("/users/validate", post().to(users::validate_user::<U>))
-                .route("/users/role", post().to(users::modify_user_role::<U>)),
-        )
-    })
-    .bind(address)?
-    .run()
-    .await?;
+    let pool_data = Data::new(pool);
+    let validate_limiter = RateLimiter::new(config.validate_rate_limit.clone());
+    let tls_config = match &config.tls {
+        Some(tls) => Some(tls.load().await?),
+        None => None,
+    };
+    let server = HttpServer::new(move || {
+        App::new()
+            .wrap(PropagateRequestId)
+            .app_data(pool_data.clone())
+            .service(health::service::<D>())
+            .service(
+                actix_web::web::scope("/api/v1")
+                    .wrap(Logger::default())
+                    .app_data(roles_service_data.clone())
+                    .app_data(users_service_data.clone())
+                    .route("/roles", get().to(roles::roles::<R>))
+                    .route("/roles/user", get().to(roles::read_current_user_roles::<R>))
+                    .route("/user", get().to(users::read_current_user::<U>))
+                    .route("/user/{uid}", get().to(users::read_user::<U>))
+                    .route("/users", get().to(users::read_users::<U>))
+                    .route("/users", post().to(users::create_user::<U>))
+                    .route("/users", patch().to(users::update_user::<U>))
+                    .service(
+                        resource("/users/validate")
+                            .wrap(ValidateRateLimit::new(validate_limiter.clone()))
+                            .route(post().to(users::validate_user::<U>)),
+                    )
+                    .route("/users/role", post().to(users::modify_user_role::<U>))
+                    .route(
+                        "/users/roles/batch",
+                        post().to(users::modify_user_roles::<U>),
+                    )
+                    .route(
+                        "/users/{uid}/deactivate",
+                        post().to(users::deactivate_user::<U>),
+                    )
+                    .route(
+                        "/users/{uid}/reactivate",
+                        post().to(users::reactivate_user::<U>),
+                    ),
+            )
+    });
+    let server = match tls_config {
+        Some(tls_config) => server.bind_rustls(config.address.as_str(), tls_config)?,
+        None => server.bind(config.address.as_str())?,
+    };
+    server.run().await?;
     Ok(())
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::{collections::HashMap, net::IpAddr, time::Duration};
+
+    use actix_web::{dev::Payload, test::TestRequest, FromRequest};
+    use actix_web_httpauth::extractors::bearer::BearerAuth;
+    use common::{api::tls::TlsConfig, error::EmError};
+    use rstest::rstest;
+    use sqlx::PgPool;
+    use uuid::{uuid, Uuid};
+
+    use super::{rate_limit::RateLimitConfig, require_privilege, ServerConfig};
+    use crate::{
+        data::role::RoleName,
+        service::{
+            hashing::HashConfig,
+            postgres::{roles::PgRoleService, test::database, users::PgUserService},
+        },
+    };
+
+    /// Extract a [BearerAuth] from a request containing the `token`
+    async fn bearer(token: &str) -> BearerAuth {
+        let request = TestRequest::default()
+            .insert_header(("Authorization", format!("Bearer {token}")))
+            .to_http_request();
+        BearerAuth::from_request(&request, &mut Payload::None)
+            .await
+            .unwrap()
+    }
+
+    #[rstest]
+    #[case::admin_user(uuid!("9363ab3f-0d62-4b40-b408-898bdea56282"), RoleName::ViewWorkflowEngine)]
+    #[case::user_with_role(uuid!("728ac060-9d38-47e9-b2fa-66d2954110e3"), RoleName::AddRole)]
+    #[tokio::test]
+    async fn require_privilege_should_succeed_when(
+        database: PgPool,
+        #[case] uid: Uuid,
+        #[case] privilege: RoleName,
+    ) {
+        let service = PgRoleService::new(&PgUserService::new(&database, HashConfig::default()));
+        let bearer = bearer(&uid.to_string()).await;
+
+        let result = require_privilege(&service, &bearer, privilege).await;
+
+        assert_eq!(result.unwrap(), uid);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn require_privilege_should_fail_when_user_missing_privilege(database: PgPool) {
+        let service = PgRoleService::new(&PgUserService::new(&database, HashConfig::default()));
+        let bearer = bearer("be4c1ef7-771a-4580-b0dd-ff137c64ab48").await;
+
+        let result = require_privilege(&service, &bearer, RoleName::ViewWorkflowEngine).await;
+
+        assert!(
+            matches!(result, Err(EmError::MissingPrivilege { .. })),
+            "{result:?}"
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn require_privilege_should_fail_when_bearer_is_not_a_uid(database: PgPool) {
+        let service = PgRoleService::new(&PgUserService::new(&database, HashConfig::default()));
+        let bearer = bearer("not-a-uid").await;
+
+        let result = require_privilege(&service, &bearer, RoleName::ViewWorkflowEngine).await;
+
+        assert!(matches!(result, Err(EmError::InvalidUser)), "{result:?}");
+    }
+
+    #[rstest]
+    #[case::empty(HashMap::new(), ServerConfig::default())]
+    #[case::all_values(
+        HashMap::from([
+            ("USERS_API_ADDR", "0.0.0.0:9001"),
+            ("USERS_TLS_CERT", "cert.pem"),
+            ("USERS_TLS_KEY", "key.pem"),
+            ("USERS_VALIDATE_RATE", "10"),
+            ("USERS_VALIDATE_WINDOW", "30"),
+            ("USERS_TRUSTED_PROXIES", "10.0.0.1"),
+        ]),
+        ServerConfig {
+            address: "0.0.0.0:9001".to_owned(),
+            tls: Some(TlsConfig { cert_path: "cert.pem".into(), key_path: "key.pem".into() }),
+            validate_rate_limit: RateLimitConfig::new(10, Duration::from_secs(30))
+                .with_trusted_proxies(vec![IpAddr::from([10, 0, 0, 1])]),
+        },
+    )]
+    #[case::partial_values(
+        HashMap::from([("USERS_API_ADDR", "0.0.0.0:9001")]),
+        ServerConfig { address: "0.0.0.0:9001".to_owned(), ..ServerConfig::default() },
+    )]
+    fn from_lookup_should_succeed_when(
+        #[case] values: HashMap<&str, &str>,
+        #[case] expected: ServerConfig,
+    ) {
+        let result =
+            ServerConfig::from_lookup(|key| values.get(key).map(|value| (*value).to_owned()));
+
+        assert!(matches!(result, Ok(config) if config == expected));
+    }
+
+    #[rstest]
+    #[case::validate_rate("USERS_VALIDATE_RATE")]
+    #[case::validate_window("USERS_VALIDATE_WINDOW")]
+    #[case::trusted_proxies("USERS_TRUSTED_PROXIES")]
+    #[case::cert_only("USERS_TLS_CERT")]
+    #[case::key_only("USERS_TLS_KEY")]
+    fn from_lookup_should_fail_when_value_is_invalid(#[case] key: &str) {
+        let result = ServerConfig::from_lookup(|lookup_key| {
+            (lookup_key == key).then(|| "not a number".to_owned())
+        });
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,527 @@
+use std::{
+    env,
+    future::{ready, Ready},
+    net::IpAddr,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::StatusCode,
+    web::{Bytes, Query},
+    HttpRequest, HttpResponse, Responder,
+};
+use common::{
+    api::{request::ApiRequestBody, ApiContentFormat, ApiResponse, QueryApiFormat},
+    error::{EmError, EmResult},
+};
+use dashmap::DashMap;
+use futures::future::LocalBoxFuture;
+use log::warn;
+
+use crate::service::users::ValidateUserRequest;
+
+/// Default number of attempts allowed per window
+pub const DEFAULT_RATE: u32 = 5;
+/// Default length of the window in which the number of attempts are allowed
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(60);
+/// Message returned to clients that have exceeded the rate limit
+const RATE_LIMITED_MESSAGE: &str = "Too many attempts. Please wait before trying again";
+/// Header containing the chain of client addresses added by proxies
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Configuration of a [RateLimiter]. Allows `rate` attempts within each `window` per client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// Maximum number of attempts allowed within the window. Also the burst capacity of a client
+    rate: u32,
+    /// Length of time over which `rate` attempts are replenished
+    window: Duration,
+    /// Addresses of proxies (e.g. the web portal) whose `X-Forwarded-For` header is trusted to
+    /// contain the address of the actual client
+    trusted_proxies: Vec<IpAddr>,
+}
+
+impl RateLimitConfig {
+    /// Create a new [RateLimitConfig] allowing `rate` attempts per `window` without any trusted
+    /// proxies
+    pub const fn new(rate: u32, window: Duration) -> Self {
+        Self {
+            rate,
+            window,
+            trusted_proxies: Vec::new(),
+        }
+    }
+
+    /// Trust the `X-Forwarded-For` header of requests sent by the `trusted_proxies`
+    pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
+    /// Create a new [RateLimitConfig] for the users validate endpoint using the environment
+    /// variables `USERS_VALIDATE_RATE` (attempts per window), `USERS_VALIDATE_WINDOW` (window
+    /// length in seconds) and `USERS_TRUSTED_PROXIES` (comma separated addresses of trusted
+    /// proxies). Missing variables fall back to [DEFAULT_RATE], [DEFAULT_WINDOW] and no trusted
+    /// proxies.
+    /// # Errors
+    /// This function will return an error if any environment variable cannot be parsed
+    pub fn validate_from_env() -> EmResult<Self> {
+        Self::validate_from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Create a new [RateLimitConfig] for the users validate endpoint using the `lookup` function
+    /// to find the values described in [RateLimitConfig::validate_from_env] by name
+    /// # Errors
+    /// This function will return an error if any value cannot be parsed
+    pub fn validate_from_lookup<F>(lookup: F) -> EmResult<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let rate = match lookup("USERS_VALIDATE_RATE") {
+            Some(value) => value.parse()?,
+            None => DEFAULT_RATE,
+        };
+        let window = match lookup("USERS_VALIDATE_WINDOW") {
+            Some(value) => Duration::from_secs(value.parse()?),
+            None => DEFAULT_WINDOW,
+        };
+        let trusted_proxies = match lookup("USERS_TRUSTED_PROXIES") {
+            Some(value) => parse_trusted_proxies(&value)?,
+            None => Vec::new(),
+        };
+        Ok(Self::new(rate, window).with_trusted_proxies(trusted_proxies))
+    }
+
+    /// Number of tokens replenished per second
+    fn refill_rate(&self) -> f64 {
+        f64::from(self.rate) / self.window.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_RATE, DEFAULT_WINDOW)
+    }
+}
+
+/// Parse the comma separated list of proxy addresses found in `value`. Blank entries are ignored.
+/// # Errors
+/// This function will return an error if an entry is not a valid IP address
+fn parse_trusted_proxies(value: &str) -> EmResult<Vec<IpAddr>> {
+    let mut trusted_proxies = Vec::new();
+    for entry in value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let proxy = entry.parse().map_err(|error| {
+            EmError::Generic(format!(
+                "USERS_TRUSTED_PROXIES contains an invalid address `{entry}`. {error}"
+            ))
+        })?;
+        trusted_proxies.push(proxy);
+    }
+    Ok(trusted_proxies)
+}
+
+/// Token bucket of a single client
+struct Bucket {
+    /// Tokens currently available. Each attempt consumes a single token
+    tokens: f64,
+    /// Last time the tokens were replenished
+    last_refill: Instant,
+}
+
+/// In-process token bucket rate limiter keyed by client. Cheap to clone since all clones share the
+/// same buckets. Buckets that have been idle for an entire window are fully replenished so they
+/// are swept at most once per window to keep the number of buckets bounded.
+#[derive(Clone)]
+pub struct RateLimiter {
+    /// Limits applied to every client
+    config: RateLimitConfig,
+    /// Token bucket of each client that has made an attempt
+    buckets: Arc<DashMap<String, Bucket>>,
+    /// Time the limiter was created. Reference point of `last_sweep`
+    created: Instant,
+    /// Milliseconds between `created` and the last sweep of idle buckets
+    last_sweep: Arc<AtomicU64>,
+}
+
+impl RateLimiter {
+    /// Create a new [RateLimiter] using the specified `config`
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Arc::new(DashMap::new()),
+            created: Instant::now(),
+            last_sweep: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Key used to identify the client that sent the `req` attempting to validate the credentials
+    /// of `username`. Combines the client address (see [client_ip]) with the username so clients
+    /// sharing an address (e.g. every user of the web portal) do not share a bucket.
+    pub fn key(&self, req: &HttpRequest, username: &str) -> String {
+        format!(
+            "{}|{}",
+            client_ip(req, &self.config.trusted_proxies),
+            username.trim().to_lowercase()
+        )
+    }
+
+    /// Attempt to consume a token for the client specified by `key`. Returns false if the client
+    /// has no tokens left and should be rejected.
+    pub fn try_acquire(&self, key: &str) -> bool {
+        let now = Instant::now();
+        self.sweep_if_due(now);
+        let capacity = f64::from(self.config.rate);
+        let mut bucket = self
+            .buckets
+            .entry(key.to_owned())
+            .or_insert_with(|| Bucket {
+                tokens: capacity,
+                last_refill: now,
+            });
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.config.refill_rate()).min(capacity);
+        bucket.last_refill = now;
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Reset the bucket of the client specified by `key`. Called after a successful attempt so a
+    /// client is not penalized for previous failures.
+    pub fn reset(&self, key: &str) {
+        self.buckets.remove(key);
+    }
+
+    /// Sweep idle buckets if at least one window has passed since the last sweep. Only a single
+    /// caller performs the sweep when multiple callers find the sweep is due.
+    fn sweep_if_due(&self, now: Instant) {
+        let elapsed = millis(now.duration_since(self.created));
+        let last_sweep = self.last_sweep.load(Ordering::Relaxed);
+        if elapsed.saturating_sub(last_sweep) < millis(self.config.window) {
+            return;
+        }
+        if self
+            .last_sweep
+            .compare_exchange(last_sweep, elapsed, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            self.sweep(now);
+        }
+    }
+
+    /// Remove every bucket that has not been used for an entire window as of `now`. Those buckets
+    /// are fully replenished, so removing them is the same as keeping them.
+    fn sweep(&self, now: Instant) {
+        let window = self.config.window;
+        self.buckets
+            .retain(|_, bucket| now.duration_since(bucket.last_refill) < window);
+    }
+}
+
+/// Whole milliseconds of the `duration`, saturating at [u64::MAX]
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Address of the client that sent the `req`. The peer address is used unless the peer is one of
+/// the `trusted_proxies`, in which case the last address of the `X-Forwarded-For` header that is
+/// not a trusted proxy is used. Forwarded headers sent by any other peer are ignored since they
+/// can be set by the client.
+pub fn client_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> String {
+    let Some(peer_ip) = req.peer_addr().map(|addr| addr.ip()) else {
+        return "unknown".to_owned();
+    };
+    if !trusted_proxies.contains(&peer_ip) {
+        return peer_ip.to_string();
+    }
+    req.headers()
+        .get(FORWARDED_FOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value
+                .rsplit(',')
+                .filter_map(|entry| entry.trim().parse::<IpAddr>().ok())
+                .find(|ip| !trusted_proxies.contains(ip))
+        })
+        .unwrap_or(peer_ip)
+        .to_string()
+}
+
+/// Middleware factory that limits attempts to validate user credentials using a [RateLimiter].
+/// Requests are keyed by client address and the username found in the [ValidateUserRequest] body
+/// (see [RateLimiter::key]) and rejected with a `429 Too Many Requests` response once the limit is
+/// exceeded. The body is buffered and restored so the wrapped handler can still extract it. A
+/// success response from the wrapped handler resets the bucket of the client.
+pub struct ValidateRateLimit {
+    /// Limiter shared by every worker of the server
+    limiter: RateLimiter,
+}
+
+impl ValidateRateLimit {
+    /// Create a new [ValidateRateLimit] that applies the `limiter`. The `limiter` should be created
+    /// once per server so every worker shares the same buckets.
+    pub const fn new(limiter: RateLimiter) -> Self {
+        Self { limiter }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ValidateRateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    type InitError = ();
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Transform = ValidateRateLimitMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ValidateRateLimitMiddleware {
+            service: Rc::new(service),
+            limiter: self.limiter.clone(),
+        }))
+    }
+}
+
+/// Service created by the [ValidateRateLimit] middleware factory
+pub struct ValidateRateLimitMiddleware<S> {
+    service: Rc<S>,
+    limiter: RateLimiter,
+}
+
+impl<S, B> Service<ServiceRequest> for ValidateRateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+    type Response = ServiceResponse<EitherBody<B>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let limiter = self.limiter.clone();
+        Box::pin(async move {
+            let body = req.extract::<Bytes>().await?;
+            let username = ApiRequestBody::<ValidateUserRequest>::new(
+                req.request(),
+                &mut Payload::from(body.clone()),
+            )
+            .await
+            .map(|request| request.username)
+            .unwrap_or_default();
+            req.set_payload(Payload::from(body));
+            let key = limiter.key(req.request(), &username);
+            if !limiter.try_acquire(&key) {
+                warn!("Rate limit exceeded for {} by client {key}", req.path());
+                let format = Query::<QueryApiFormat>::from_query(req.query_string())
+                    .map(|query| query.f)
+                    .unwrap_or_default();
+                let response = rate_limited_response(req.request(), format);
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+            let response = service.call(req).await?;
+            if response.status().is_success() {
+                limiter.reset(&key);
+            }
+            Ok(response.map_into_left_body())
+        })
+    }
+}
+
+/// Response sent to clients that have exceeded the rate limit. Uses a `429 Too Many Requests`
+/// status and an [ApiResponse::failure] body serialized with the requested `format`.
+pub fn rate_limited_response(req: &HttpRequest, format: ApiContentFormat) -> HttpResponse {
+    let mut response = ApiResponse::<()>::failure(RATE_LIMITED_MESSAGE, format).respond_to(req);
+    *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+    response
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::{
+        net::IpAddr,
+        time::{Duration, Instant},
+    };
+
+    use actix_web::{
+        http::StatusCode,
+        test::{call_service, init_service, TestRequest},
+        web::{post, resource, Json},
+        App, HttpResponse,
+    };
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use super::{
+        client_ip, parse_trusted_proxies, RateLimitConfig, RateLimiter, ValidateRateLimit,
+    };
+
+    /// Handler that accepts the credentials when the password is `valid`
+    async fn check_password(body: Json<Value>) -> HttpResponse {
+        if body.get("password").and_then(Value::as_str) == Some("valid") {
+            return HttpResponse::Ok().finish();
+        }
+        HttpResponse::Unauthorized().finish()
+    }
+
+    /// Create a validate request from `127.0.0.1` for the `username` using the `password`
+    fn validate_request(username: &str, password: &str) -> TestRequest {
+        TestRequest::post()
+            .uri("/validate?f=json")
+            .peer_addr("127.0.0.1:50000".parse().unwrap())
+            .set_json(json!({ "username": username, "password": password }))
+    }
+
+    /// Address of the proxy trusted by the tests
+    const PROXY: &str = "10.0.0.1";
+
+    #[test]
+    fn try_acquire_should_reject_when_failed_attempts_exceed_rate() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(3, Duration::from_secs(60)));
+
+        let attempts: Vec<bool> = (0..4).map(|_| limiter.try_acquire("127.0.0.1")).collect();
+
+        assert_eq!(attempts, vec![true, true, true, false]);
+    }
+
+    #[test]
+    fn try_acquire_should_succeed_when_bucket_reset() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(1, Duration::from_secs(60)));
+
+        assert!(limiter.try_acquire("127.0.0.1"));
+        assert!(!limiter.try_acquire("127.0.0.1"));
+        limiter.reset("127.0.0.1");
+
+        assert!(limiter.try_acquire("127.0.0.1"));
+    }
+
+    #[test]
+    fn try_acquire_should_track_clients_separately() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(1, Duration::from_secs(60)));
+
+        assert!(limiter.try_acquire("127.0.0.1"));
+
+        assert!(limiter.try_acquire("127.0.0.2"));
+    }
+
+    #[test]
+    fn sweep_should_remove_buckets_idle_for_a_window() {
+        let window = Duration::from_secs(60);
+        let limiter = RateLimiter::new(RateLimitConfig::new(1, window));
+        limiter.try_acquire("127.0.0.1");
+
+        limiter.sweep(Instant::now() + window);
+
+        assert!(limiter.buckets.is_empty());
+    }
+
+    #[test]
+    fn sweep_should_keep_buckets_used_within_a_window() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(1, Duration::from_secs(60)));
+        limiter.try_acquire("127.0.0.1");
+
+        limiter.sweep(Instant::now());
+
+        assert_eq!(limiter.buckets.len(), 1);
+    }
+
+    #[test]
+    fn key_should_separate_users_of_the_same_client() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let req = TestRequest::default()
+            .peer_addr("127.0.0.1:50000".parse().unwrap())
+            .to_http_request();
+
+        assert_ne!(limiter.key(&req, "admin"), limiter.key(&req, "other"));
+        assert_eq!(limiter.key(&req, "admin"), limiter.key(&req, " Admin "));
+    }
+
+    #[rstest]
+    #[case::untrusted_peer("127.0.0.1", Some("192.168.0.5"), "127.0.0.1")]
+    #[case::trusted_peer_without_header(PROXY, None, PROXY)]
+    #[case::trusted_peer_with_header(PROXY, Some("192.168.0.5"), "192.168.0.5")]
+    #[case::trusted_peer_with_chain(PROXY, Some("1.1.1.1, 192.168.0.5, 10.0.0.1"), "192.168.0.5")]
+    #[case::trusted_peer_with_invalid_header(PROXY, Some("not an address"), PROXY)]
+    fn client_ip_should_return(
+        #[case] peer: &str,
+        #[case] forwarded_for: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let trusted_proxies: Vec<IpAddr> = vec![PROXY.parse().unwrap()];
+        let mut builder =
+            TestRequest::default().peer_addr(format!("{peer}:50000").parse().unwrap());
+        if let Some(forwarded_for) = forwarded_for {
+            builder = builder.insert_header(("X-Forwarded-For", forwarded_for));
+        }
+
+        let ip = client_ip(&builder.to_http_request(), &trusted_proxies);
+
+        assert_eq!(ip, expected);
+    }
+
+    #[tokio::test]
+    async fn validate_rate_limit_should_reject_when_attempts_exceed_rate() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(1, Duration::from_secs(60)));
+        let app = init_service(
+            App::new().service(
+                resource("/validate")
+                    .wrap(ValidateRateLimit::new(limiter))
+                    .route(post().to(check_password)),
+            ),
+        )
+        .await;
+
+        let first = call_service(&app, validate_request("admin", "invalid").to_request()).await;
+        let second = call_service(&app, validate_request("admin", "invalid").to_request()).await;
+        let other_user =
+            call_service(&app, validate_request("other", "invalid").to_request()).await;
+
+        assert_eq!(first.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(other_user.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn validate_rate_limit_should_reset_bucket_when_attempt_succeeds() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(1, Duration::from_secs(60)));
+        let app = init_service(
+            App::new().service(
+                resource("/validate")
+                    .wrap(ValidateRateLimit::new(limiter))
+                    .route(post().to(check_password)),
+            ),
+        )
+        .await;
+
+        let first = call_service(&app, validate_request("admin", "valid").to_request()).await;
+        let second = call_service(&app, validate_request("admin", "invalid").to_request()).await;
+
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(second.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn parse_trusted_proxies_should_fail_when_entry_is_not_an_address() {
+        assert!(parse_trusted_proxies("10.0.0.1, proxy").is_err());
+    }
+}
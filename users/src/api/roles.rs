@@ -23,3 +23,23 @@ where
         Err(error) => ApiResponse::error(error, format.f),
     }
 }
+
+/// API endpoint to fetch the roles of the current user
+pub async fn read_current_user_roles<R>(
+    bearer: BearerAuth,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<Role>>
+where
+    R: RoleService,
+{
+    let format = query.into_inner();
+    let uid = match validate_bearer(&bearer, format.f) {
+        BearerValidation::Valid(uid) => uid,
+        BearerValidation::InValid(response) => return response,
+    };
+    match service.read_roles_for_user(&uid).await {
+        Ok(roles) => ApiResponse::success(roles, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
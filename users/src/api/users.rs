@@ -1,3 +1,4 @@
+use actix_web::{http::StatusCode, HttpRequest, HttpResponse, Responder};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
 use common::{
     api::{request::ApiRequest, ApiResponse, QueryApiFormat},
@@ -10,8 +11,8 @@ use super::{validate_bearer, BearerValidation};
 use crate::{
     data::{role::RoleName, user::User},
     service::users::{
-        CreateUserRequest, ModifyUserRoleRequest, UpdateUserRequest, UserService,
-        ValidateUserRequest,
+        CreateUserRequest, ModifyUserRoleRequest, ModifyUserRolesRequest, ReadUsersQuery,
+        UpdateUserRequest, UserService, ValidateUserRequest,
     },
 };
 
@@ -37,9 +38,11 @@ where
     }
 }
 
-/// API endpoint to read all users
+/// API endpoint to read all users. Deactivated users are excluded unless the `include_inactive`
+/// query parameter is true
 pub async fn read_users<U>(
     bearer: BearerAuth,
+    users_query: actix_web::web::Query<ReadUsersQuery>,
     service: actix_web::web::Data<U>,
     query: actix_web::web::Query<QueryApiFormat>,
 ) -> ApiResponse<Vec<User>>
@@ -51,7 +54,7 @@ where
         BearerValidation::Valid(uid) => uid,
         BearerValidation::InValid(response) => return response,
     };
-    match service.read_all(&uid).await {
+    match service.read_all(&uid, users_query.include_inactive).await {
         Ok(user) => ApiResponse::success(user, format.f),
         Err(error) => ApiResponse::error(error, format.f),
     }
@@ -111,7 +114,8 @@ where
     }
 }
 
-/// API endpoint to update a user
+/// API endpoint to update a user. Only the fields specified in the request are updated and the
+/// update is rejected if the request specifies a stale user version
 pub async fn update_user<U>(
     bearer: BearerAuth,
     api_request: ApiRequest<UpdateUserRequest>,
@@ -133,25 +137,74 @@ where
     }
 }
 
-/// API endpoint to validate a users credentials. If successful, a [User] instance is returned
+/// API endpoint to deactivate the user specified by `uid`
+pub async fn deactivate_user<U>(
+    bearer: BearerAuth,
+    update_uid: actix_web::web::Path<Uuid>,
+    service: actix_web::web::Data<U>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<User>
+where
+    U: UserService,
+{
+    let format = query.into_inner();
+    let uid = match validate_bearer(&bearer, format.f) {
+        BearerValidation::Valid(uid) => uid,
+        BearerValidation::InValid(response) => return response,
+    };
+    match service.deactivate_user(&uid, &update_uid).await {
+        Ok(user) => ApiResponse::success(user, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to reactivate the user specified by `uid`
+pub async fn reactivate_user<U>(
+    bearer: BearerAuth,
+    update_uid: actix_web::web::Path<Uuid>,
+    service: actix_web::web::Data<U>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<User>
+where
+    U: UserService,
+{
+    let format = query.into_inner();
+    let uid = match validate_bearer(&bearer, format.f) {
+        BearerValidation::Valid(uid) => uid,
+        BearerValidation::InValid(response) => return response,
+    };
+    match service.reactivate_user(&uid, &update_uid).await {
+        Ok(user) => ApiResponse::success(user, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to validate a users credentials. If successful, a [User] instance is returned.
+/// Invalid credentials are rejected with a `401 Unauthorized` response so clients can tell them
+/// apart from other failures. Attempts are rate limited by wrapping the route with the
+/// [ValidateRateLimit][super::rate_limit::ValidateRateLimit] middleware.
 pub async fn validate_user<U>(
+    req: HttpRequest,
     api_request: ApiRequest<ValidateUserRequest>,
     service: actix_web::web::Data<U>,
     query: actix_web::web::Query<QueryApiFormat>,
-) -> ApiResponse<User>
+) -> HttpResponse
 where
     U: UserService,
 {
     let format = query.into_inner();
     let user_request = api_request.into_inner();
     match service.validate_user(&user_request).await {
-        Ok(user) => ApiResponse::success(user, format.f),
-        Err(error) if matches!(error, EmError::InvalidUser) => {
-            ApiResponse::failure("Invalid user credentials", format.f)
+        Ok(user) => ApiResponse::success(user, format.f).respond_to(&req),
+        Err(EmError::InvalidUser) => {
+            let mut response =
+                ApiResponse::<User>::failure("Invalid user credentials", format.f).respond_to(&req);
+            *response.status_mut() = StatusCode::UNAUTHORIZED;
+            response
         }
         Err(error) => {
             error!("{error}");
-            ApiResponse::error(error, format.f)
+            ApiResponse::<User>::error(error, format.f).respond_to(&req)
         }
     }
 }
@@ -177,3 +230,161 @@ where
         Err(error) => ApiResponse::error(error, format.f),
     }
 }
+
+/// API endpoint to add/remove roles for many users at once. Either every role change is applied or
+/// none are
+pub async fn modify_user_roles<U>(
+    bearer: BearerAuth,
+    api_request: ApiRequest<ModifyUserRolesRequest>,
+    service: actix_web::web::Data<U>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<()>
+where
+    U: UserService,
+{
+    let format = query.into_inner();
+    let uid = match validate_bearer(&bearer, format.f) {
+        BearerValidation::Valid(uid) => uid,
+        BearerValidation::InValid(response) => return response,
+    };
+    let ModifyUserRolesRequest { assignments } = api_request.into_inner();
+    match service.modify_user_roles(&uid, &assignments).await {
+        Ok(()) => ApiResponse::message(
+            format!("Applied {} role change(s)", assignments.len()),
+            format.f,
+        ),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::panic)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::{
+        http::StatusCode,
+        test::{call_and_read_body_json, call_service, init_service, TestRequest},
+        web::{post, resource, Data},
+        App,
+    };
+    use common::api::ApiResponseBody;
+    use rstest::rstest;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use uuid::uuid;
+
+    use super::validate_user;
+    use crate::{
+        api::rate_limit::{RateLimitConfig, RateLimiter, ValidateRateLimit},
+        data::user::User,
+        service::{
+            hashing::HashConfig,
+            postgres::{test::database, users::PgUserService},
+        },
+    };
+
+    #[rstest]
+    #[tokio::test]
+    async fn validate_user_should_succeed_when_body_is_json(database: PgPool) {
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(PgUserService::new(
+                    &database,
+                    HashConfig::default(),
+                )))
+                .route("/users/validate", post().to(validate_user::<PgUserService>)),
+        )
+        .await;
+        let request = TestRequest::post()
+            .uri("/users/validate?f=json")
+            .set_json(json!({ "username": "admin", "password": "admin" }))
+            .to_request();
+
+        let body: ApiResponseBody<User> = call_and_read_body_json(&app, request).await;
+
+        let ApiResponseBody::Success(user) = body else {
+            panic!("Expected a success response");
+        };
+        assert_eq!(user.uid, uuid!("9363ab3f-0d62-4b40-b408-898bdea56282"));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn validate_user_should_fail_when_content_type_is_unsupported(database: PgPool) {
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(PgUserService::new(
+                    &database,
+                    HashConfig::default(),
+                )))
+                .route("/users/validate", post().to(validate_user::<PgUserService>)),
+        )
+        .await;
+        let request = TestRequest::post()
+            .uri("/users/validate?f=json")
+            .insert_header(("Content-Type", "text/plain"))
+            .set_payload("username=admin&password=admin")
+            .to_request();
+
+        let body: ApiResponseBody<User> = call_and_read_body_json(&app, request).await;
+
+        assert!(matches!(body, ApiResponseBody::Failure(_)));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn validate_user_should_respond_unauthorized_when_password_is_wrong(database: PgPool) {
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(PgUserService::new(
+                    &database,
+                    HashConfig::default(),
+                )))
+                .route("/users/validate", post().to(validate_user::<PgUserService>)),
+        )
+        .await;
+        let request = TestRequest::post()
+            .uri("/users/validate?f=json")
+            .set_json(json!({ "username": "admin", "password": "not the password" }))
+            .to_request();
+
+        let response = call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn validate_user_should_rate_limit_attempts_per_username(database: PgPool) {
+        let limiter = RateLimiter::new(RateLimitConfig::new(1, Duration::from_secs(60)));
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(PgUserService::new(
+                    &database,
+                    HashConfig::default(),
+                )))
+                .service(
+                    resource("/users/validate")
+                        .wrap(ValidateRateLimit::new(limiter))
+                        .route(post().to(validate_user::<PgUserService>)),
+                ),
+        )
+        .await;
+        let attempt = |username: &str| {
+            TestRequest::post()
+                .uri("/users/validate?f=json")
+                .peer_addr("127.0.0.1:50000".parse().unwrap())
+                .set_json(json!({ "username": username, "password": "not the password" }))
+                .to_request()
+        };
+
+        let first = call_service(&app, attempt("admin")).await.status();
+        let second = call_service(&app, attempt("admin")).await.status();
+        let other_user = call_service(&app, attempt("other")).await.status();
+
+        assert_eq!(first, StatusCode::UNAUTHORIZED);
+        assert_eq!(second, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(other_user, StatusCode::UNAUTHORIZED);
+    }
+}
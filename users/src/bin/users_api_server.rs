@@ -1,20 +1,39 @@
 use common::{
-    database::{postgres::Postgres, Database},
+    database::{
+        build::{run_build, startup_build_requested},
+        postgres::{build::PgDatabaseBuilder, Postgres},
+        Database,
+    },
     error::EmResult,
+    logging, package_dir,
 };
+use log::info;
 use users::{
-    api,
+    api::{self, ServerConfig},
     database::db_options,
-    service::postgres::{roles::PgRoleService, users::PgUserService},
+    service::{
+        hashing::HashConfig,
+        password_policy::{set_password_policy, PasswordPolicy},
+        postgres::{roles::PgRoleService, users::PgUserService},
+    },
 };
 
 #[tokio::main]
 async fn main() -> EmResult<()> {
-    log4rs::init_file("users/users_api_server_log.yml", Default::default()).unwrap();
+    logging::init("users/users_api_server_log.yml")?;
     let options = db_options()?;
     let pool = Postgres::create_pool(options, 20, 10).await?;
-    let users_service = PgUserService::new(&pool);
+    if startup_build_requested("USERS_DB_BUILD") {
+        let report =
+            run_build::<PgDatabaseBuilder, _>(pool.clone(), package_dir()?.join("database"))
+                .await?;
+        info!("Applied users database build. {report:?}");
+    }
+    let users_service = PgUserService::new(&pool, HashConfig::from_env()?);
     let roles_service = PgRoleService::new(&users_service);
-    api::spawn_api_server(users_service, roles_service, ("127.0.0.1", 8001)).await?;
+    set_password_policy(PasswordPolicy::from_env().await?)?;
+    let config = ServerConfig::from_env()?;
+    api::spawn_api_server(users_service, roles_service, pool.clone(), &config).await?;
+    Postgres::close_pool(&pool).await;
     Ok(())
 }
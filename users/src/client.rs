@@ -0,0 +1,295 @@
+use std::net::IpAddr;
+
+use common::{
+    api::{
+        http_client::{request_error, shared_client},
+        request_id::{current_request_id, REQUEST_ID_HEADER},
+        ApiResponseBody,
+    },
+    error::{EmError, EmResult},
+};
+use reqwest::{header::CONTENT_TYPE, Client, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    data::{
+        role::{Role, RoleName},
+        user::User,
+    },
+    service::users::ValidateUserRequest,
+};
+
+/// Name of the service reported when the users API cannot be reached
+const USERS_API_SERVICE: &str = "Users API";
+/// Header used to forward the address of the client a request is sent on behalf of
+const FORWARDED_FOR_HEADER: &str = "X-Forwarded-For";
+
+/// Request sent to the users API by a [UsersApiClient]
+struct ApiRequest<'r, B> {
+    /// HTTP method of the request
+    method: Method,
+    /// Path of the endpoint, relative to the base url of the API
+    path: &'r str,
+    /// If provided, the uid is sent as a bearer token
+    auth: Option<&'r Uuid>,
+    /// If provided, the address is sent as the `X-Forwarded-For` header
+    forwarded_for: Option<IpAddr>,
+    /// If provided, the body is sent as MessagePack
+    body: Option<&'r B>,
+}
+
+impl<'r, B> ApiRequest<'r, B> {
+    /// Create a new GET [ApiRequest] for the `path`, authenticated as the user specified by
+    /// `current_uid`
+    const fn get(path: &'r str, current_uid: &'r Uuid) -> Self {
+        Self {
+            method: Method::GET,
+            path,
+            auth: Some(current_uid),
+            forwarded_for: None,
+            body: None,
+        }
+    }
+}
+
+/// Typed client for the users API. Wraps the `base_url` of the API (e.g.
+/// `http://127.0.0.1:8001/api/v1`) and handles the content format of requests and responses as
+/// well as unwrapping the [ApiResponseBody] so callers only deal with the expected data types.
+#[derive(Clone)]
+pub struct UsersApiClient {
+    /// Base url of the users API, without a trailing slash
+    base_url: String,
+    /// Client used to send all requests
+    client: Client,
+}
+
+impl UsersApiClient {
+    /// Create a new [UsersApiClient] that sends requests to the API found at `base_url`
+    pub fn new<S: Into<String>>(base_url: S) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_owned(),
+            client: shared_client(),
+        }
+    }
+
+    /// Validate the user credentials contained within `request`. Returns the matching [User] if
+    /// the credentials are valid. The `client_ip` of the user attempting to login is forwarded
+    /// through the `X-Forwarded-For` header so the API can rate limit attempts per client when
+    /// this service is one of its trusted proxies.
+    /// # Errors
+    /// This function will return an error if the request fails or the API does not return a [User]
+    pub async fn validate(
+        &self,
+        request: &ValidateUserRequest,
+        client_ip: Option<IpAddr>,
+    ) -> EmResult<User> {
+        self.send(ApiRequest {
+            method: Method::POST,
+            path: "/users/validate",
+            auth: None,
+            forwarded_for: client_ip,
+            body: Some(request),
+        })
+        .await
+    }
+
+    /// Fetch the [User] specified by `uid`, performing the request as the user specified by
+    /// `current_uid`. The current user must have the privilege to read other users if `uid` is
+    /// not the current user.
+    /// # Errors
+    /// This function will return an error if the request fails or the API does not return a [User]
+    pub async fn fetch_user(&self, current_uid: &Uuid, uid: &Uuid) -> EmResult<User> {
+        let path = format!("/user/{uid}");
+        self.send(ApiRequest::<()>::get(&path, current_uid)).await
+    }
+
+    /// Fetch the [User] specified by `current_uid`
+    /// # Errors
+    /// This function will return an error if the request fails or the API does not return a [User]
+    pub async fn fetch_current_user(&self, current_uid: &Uuid) -> EmResult<User> {
+        self.send(ApiRequest::<()>::get("/user", current_uid)).await
+    }
+
+    /// Fetch the roles of the user specified by `current_uid`
+    /// # Errors
+    /// This function will return an error if the request fails or the API does not return the
+    /// roles
+    pub async fn fetch_roles(&self, current_uid: &Uuid) -> EmResult<Vec<Role>> {
+        self.send(ApiRequest::<()>::get("/roles/user", current_uid))
+            .await
+    }
+
+    /// Require that the user specified by `current_uid` has been granted the `privilege` (or is an
+    /// admin). Intended for services outside of the users API that need to check the privileges
+    /// of the caller before performing an action on their behalf.
+    /// # Errors
+    /// This function will return an error if the roles cannot be fetched or the user does not have
+    /// the `privilege` ([EmError::MissingPrivilege])
+    pub async fn require_privilege(&self, current_uid: &Uuid, privilege: RoleName) -> EmResult<()> {
+        let roles = self.fetch_roles(current_uid).await?;
+        if roles.iter().any(|role| role.grants(privilege)) {
+            return Ok(());
+        }
+        Err(EmError::MissingPrivilege {
+            uid: *current_uid,
+            role: privilege.into(),
+        })
+    }
+
+    /// Send the `request` to the API (see [ApiRequest] for how each field is sent). The current
+    /// request id (if any) is propagated through the `X-Request-Id` header. The response body is
+    /// decoded and unwrapped into the expected type.
+    /// # Errors
+    /// This function will return an error if:
+    /// - the API cannot be reached ([EmError::ServiceUnavailable])
+    /// - the request cannot be sent
+    /// - the response is a 401 status code ([EmError::InvalidUser])
+    /// - the response is not a success status code
+    /// - the response body cannot be decoded
+    /// - the response body is not a [ApiResponseBody::Success]
+    async fn send<B, T>(&self, request: ApiRequest<'_, B>) -> EmResult<T>
+    where
+        B: Serialize,
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        let url = format!("{}{}?f=msgpack", self.base_url, request.path);
+        let mut builder = self.client.request(request.method, url);
+        if let Some(request_id) = current_request_id() {
+            builder = builder.header(REQUEST_ID_HEADER, request_id.as_str());
+        }
+        if let Some(uid) = request.auth {
+            builder = builder.bearer_auth(uid);
+        }
+        if let Some(ip) = request.forwarded_for {
+            builder = builder.header(FORWARDED_FOR_HEADER, ip.to_string());
+        }
+        if let Some(body) = request.body {
+            builder = builder
+                .body(rmp_serde::to_vec(body)?)
+                .header(CONTENT_TYPE, "application/msgpack");
+        }
+        let response = builder
+            .send()
+            .await
+            .map_err(|error| request_error(USERS_API_SERVICE, error))?;
+        let status = response.status();
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(EmError::InvalidUser);
+        }
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(EmError::Generic(format!(
+                "Invalid API response: {status}. {text}"
+            )));
+        }
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_owned();
+        let bytes = response.bytes().await?;
+        unwrap_response_body(decode_response_body(&content_type, &bytes)?)
+    }
+}
+
+/// Decode the response `bytes` into an [ApiResponseBody] using the `content_type` of the response.
+/// JSON content is decoded as JSON, while all other content is treated as MessagePack.
+/// # Errors
+/// This function will return an error if the `bytes` cannot be decoded
+fn decode_response_body<T>(content_type: &str, bytes: &[u8]) -> EmResult<ApiResponseBody<T>>
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+{
+    if content_type.contains("json") {
+        return Ok(serde_json::from_slice(bytes)?);
+    }
+    Ok(rmp_serde::from_slice(bytes)?)
+}
+
+/// Unwrap the data of an [ApiResponseBody::Success]
+/// # Errors
+/// This function will return an error if the `body` is not a [ApiResponseBody::Success]
+fn unwrap_response_body<T>(body: ApiResponseBody<T>) -> EmResult<T>
+where
+    T: Serialize,
+{
+    match body {
+        ApiResponseBody::Success(data) => Ok(data),
+        ApiResponseBody::Message(message) => Err(EmError::Generic(format!(
+            "Expected data, got message. {message}"
+        ))),
+        ApiResponseBody::Failure(message) | ApiResponseBody::Error(message) => {
+            Err(EmError::Generic(message))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::panic)]
+mod test {
+    use common::{
+        api::ApiResponseBody,
+        error::EmError,
+        test::{refused_url, stub_http_response},
+    };
+    use rstest::rstest;
+
+    use super::{decode_response_body, unwrap_response_body, UsersApiClient};
+    use crate::service::users::ValidateUserRequest;
+
+    #[rstest]
+    #[case::json("application/json", serde_json::to_vec(&ApiResponseBody::Success(1)).unwrap())]
+    #[case::msgpack("application/msgpack", rmp_serde::to_vec(&ApiResponseBody::Success(1)).unwrap())]
+    fn decode_response_body_should_succeed_when(
+        #[case] content_type: &str,
+        #[case] bytes: Vec<u8>,
+    ) {
+        let body = decode_response_body::<i32>(content_type, &bytes).unwrap();
+        let data = unwrap_response_body(body).unwrap();
+
+        assert_eq!(data, 1);
+    }
+
+    #[rstest]
+    #[case::message(ApiResponseBody::Message("message".to_owned()), "message")]
+    #[case::failure(ApiResponseBody::Failure("failure".to_owned()), "failure")]
+    #[case::error(ApiResponseBody::Error("error".to_owned()), "error")]
+    fn unwrap_response_body_should_fail_when(
+        #[case] body: ApiResponseBody<i32>,
+        #[case] expected_message: &str,
+    ) {
+        let result = unwrap_response_body(body);
+
+        let Err(error) = result else {
+            panic!("Expected an error, got {result:?}");
+        };
+        assert!(error.to_string().contains(expected_message));
+    }
+
+    #[tokio::test]
+    async fn validate_should_fail_with_service_unavailable_when_connection_refused() {
+        let client = UsersApiClient::new(format!("{}/api/v1", refused_url().unwrap()));
+        let request = ValidateUserRequest::new("username", "password");
+
+        let result = client.validate(&request, None).await;
+
+        assert!(matches!(result, Err(EmError::ServiceUnavailable(_))));
+    }
+
+    #[tokio::test]
+    async fn validate_should_fail_with_invalid_user_when_api_responds_unauthorized() {
+        let client = UsersApiClient::new(format!(
+            "{}/api/v1",
+            stub_http_response("401 Unauthorized", None, Vec::new())
+                .await
+                .unwrap()
+        ));
+        let request = ValidateUserRequest::new("username", "password");
+
+        let result = client.validate(&request, None).await;
+
+        assert!(matches!(result, Err(EmError::InvalidUser)));
+    }
+}
@@ -10,6 +10,13 @@ pub struct Role {
     pub description: String,
 }
 
+impl Role {
+    /// Check if this role grants the `privilege`. The admin role grants every privilege.
+    pub fn grants(&self, privilege: RoleName) -> bool {
+        self.name == privilege || self.name == RoleName::Admin
+    }
+}
+
 /// All role names that exist as their common name
 #[derive(
     Serialize,
@@ -51,6 +58,9 @@ pub enum RoleName {
     #[serde(rename = "view-workflow-engine")]
     #[strum(serialize = "view-workflow-engine")]
     ViewWorkflowEngine,
+    #[serde(rename = "manage-workflow-engine")]
+    #[strum(serialize = "manage-workflow-engine")]
+    ManageWorkflowEngine,
 }
 
 impl RoleName {
@@ -78,6 +88,10 @@ impl RoleName {
                  workflow engine and all other workflow engine related roles implicitly have this \
                  role"
             }
+            Self::ManageWorkflowEngine => {
+                "Provides a user the ability to perform destructive actions within the workflow \
+                 engine, such as canceling workflow runs and executors"
+            }
         }
     }
 }
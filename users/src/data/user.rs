@@ -15,6 +15,12 @@ pub struct User {
     pub full_name: String,
     /// Collection of roles the user possesses
     pub roles: Vec<Role>,
+    /// Flag indicating if the user can authenticate. Deactivated users are kept rather than
+    /// deleted
+    pub is_active: bool,
+    /// Version of the user details, incremented on every update. Used to reject updates made
+    /// against stale data
+    pub version: i64,
 }
 
 impl User {
@@ -37,20 +43,31 @@ impl User {
     pub fn roles(&self) -> &[Role] {
         &self.roles
     }
+
+    /// Return true if the user is active and able to authenticate
+    pub const fn is_active(&self) -> bool {
+        self.is_active
+    }
+
+    /// Return the current version of the user's details
+    pub const fn version(&self) -> i64 {
+        self.version
+    }
 }
 
 impl User {
     /// Checks the current roles of the [User] against the `role` name provided. If any of the roles
     /// match or the user is an admin, return [Ok]. Otherwise, return an [EmError::MissingPrivilege]
-    /// error.
+    /// error. Inactive users never pass the check, regardless of their roles.
     /// # Errors
-    /// This function will return an error if the user does not have the `role` provided
+    /// This function will return an error if:
+    /// - the user is not active ([EmError::InvalidUser])
+    /// - the user does not have the `role` provided ([EmError::MissingPrivilege])
     pub fn check_role(&self, role: RoleName) -> EmResult<()> {
-        if self
-            .roles
-            .iter()
-            .any(|r| r.name == role || r.name == RoleName::Admin)
-        {
+        if !self.is_active {
+            return Err(EmError::InvalidUser);
+        }
+        if self.roles.iter().any(|r| r.grants(role)) {
             return Ok(());
         }
         Err(EmError::MissingPrivilege {
@@ -59,3 +76,42 @@ impl User {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use common::error::EmError;
+    use rstest::rstest;
+    use uuid::Uuid;
+
+    use super::User;
+    use crate::data::role::{Role, RoleName};
+
+    /// Create an admin [User] with the specified `is_active` flag
+    fn admin_user(is_active: bool) -> User {
+        User {
+            uid: Uuid::nil(),
+            username: "admin".to_owned(),
+            full_name: "Admin User".to_owned(),
+            roles: vec![Role {
+                name: RoleName::Admin,
+                description: RoleName::Admin.description().to_owned(),
+            }],
+            is_active,
+            version: 1,
+        }
+    }
+
+    #[rstest]
+    #[case::admin(RoleName::Admin)]
+    #[case::implied_by_admin(RoleName::ManageWorkflowEngine)]
+    fn check_role_should_succeed_when_active_user_has_role(#[case] role: RoleName) {
+        assert!(admin_user(true).check_role(role).is_ok());
+    }
+
+    #[test]
+    fn check_role_should_fail_with_invalid_user_when_user_inactive() {
+        let result = admin_user(false).check_role(RoleName::Admin);
+
+        assert!(matches!(result, Err(EmError::InvalidUser)), "{result:?}");
+    }
+}
@@ -45,6 +45,7 @@
 //! Users component of the EnivroManager application suite
 
 pub mod api;
+pub mod client;
 pub mod data;
 pub mod database;
 pub mod service;
@@ -0,0 +1,104 @@
+use std::{env, ops::RangeInclusive};
+
+use common::error::EmResult;
+
+/// Default bcrypt cost used to hash passwords. Matches the default cost of `gen_salt('bf')` so
+/// existing password hashes are not re-hashed unless the cost is raised.
+pub const DEFAULT_HASH_COST: i32 = 6;
+/// Range of bcrypt costs supported by the `pgcrypto` extension
+const HASH_COST_RANGE: RangeInclusive<i32> = 4..=31;
+
+/// Parameters used when hashing user passwords. Passwords are hashed with bcrypt, where each
+/// increment of the `cost` doubles the work required to hash (and therefore brute force) a
+/// password. Stored hashes with a lower cost are re-hashed the next time the user authenticates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashConfig {
+    /// bcrypt cost (log2 of the number of rounds) of new password hashes
+    cost: i32,
+}
+
+impl Default for HashConfig {
+    fn default() -> Self {
+        Self {
+            cost: DEFAULT_HASH_COST,
+        }
+    }
+}
+
+impl HashConfig {
+    /// Create a new [HashConfig] with the specified bcrypt `cost`
+    /// # Errors
+    /// This function will return an error if the `cost` is not within 4 and 31 (inclusive)
+    pub fn new(cost: i32) -> EmResult<Self> {
+        if !HASH_COST_RANGE.contains(&cost) {
+            return Err(format!(
+                "Password hash cost must be between {} and {}. Got {cost}",
+                HASH_COST_RANGE.start(),
+                HASH_COST_RANGE.end()
+            )
+            .into());
+        }
+        Ok(Self { cost })
+    }
+
+    /// Create a new [HashConfig] using the `USERS_HASH_COST` environment variable as the bcrypt
+    /// cost. Falls back to [DEFAULT_HASH_COST] if the variable is not set.
+    /// # Errors
+    /// This function will return an error if the variable cannot be parsed or the cost is out of
+    /// range
+    pub fn from_env() -> EmResult<Self> {
+        match env::var("USERS_HASH_COST") {
+            Ok(value) => Self::new(value.parse()?),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// bcrypt cost of new password hashes
+    pub const fn cost(&self) -> i32 {
+        self.cost
+    }
+
+    /// Check if a stored password hash created with the bcrypt `hash_cost` should be re-hashed
+    /// using the current parameters. Only hashes weaker than the current cost are re-hashed.
+    pub const fn needs_rehash(&self, hash_cost: i32) -> bool {
+        hash_cost < self.cost
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::HashConfig;
+
+    #[rstest]
+    #[case::minimum(4)]
+    #[case::maximum(31)]
+    fn new_should_succeed_when(#[case] cost: i32) {
+        let result = HashConfig::new(cost);
+
+        assert!(result.is_ok_and(|config| config.cost() == cost));
+    }
+
+    #[rstest]
+    #[case::too_low(3)]
+    #[case::too_high(32)]
+    fn new_should_fail_when(#[case] cost: i32) {
+        let result = HashConfig::new(cost);
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::lower_cost(5, true)]
+    #[case::same_cost(6, false)]
+    #[case::higher_cost(7, false)]
+    fn needs_rehash_should_only_be_true_when_hash_cost_lower(
+        #[case] hash_cost: i32,
+        #[case] expected: bool,
+    ) {
+        let config = HashConfig::default();
+
+        assert_eq!(config.needs_rehash(hash_cost), expected);
+    }
+}
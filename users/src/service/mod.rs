@@ -1,3 +1,5 @@
+pub mod hashing;
+pub mod password_policy;
 pub mod postgres;
 pub mod roles;
 pub mod users;
@@ -0,0 +1,172 @@
+use std::{collections::HashSet, env, path::Path, sync::OnceLock};
+
+use common::{
+    error::{EmError, EmResult},
+    read_file,
+};
+use lazy_regex::regex;
+
+/// Global [PasswordPolicy] used when validating user passwords. Falls back to the default policy
+/// if no policy has been set.
+static PASSWORD_POLICY: OnceLock<PasswordPolicy> = OnceLock::new();
+
+/// Rules that a user password must follow. The default policy requires a non-empty password with
+/// at least 1 uppercase, digit and non-alphanumeric character. Deployments can tune the thresholds
+/// and supply a list of common passwords to reject.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    /// Minimum number of characters in a password
+    pub min_length: usize,
+    /// Password must contain at least 1 uppercase character
+    pub require_uppercase: bool,
+    /// Password must contain at least 1 lowercase character
+    pub require_lowercase: bool,
+    /// Password must contain at least 1 digit character
+    pub require_digit: bool,
+    /// Password must contain at least 1 non-alphanumeric character
+    pub require_symbol: bool,
+    /// Lowercase passwords that are rejected regardless of the other rules
+    pub common_passwords: HashSet<String>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 1,
+            require_uppercase: true,
+            require_lowercase: false,
+            require_digit: true,
+            require_symbol: true,
+            common_passwords: HashSet::new(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Create a [PasswordPolicy] from the default policy and the environment variables
+    /// `USERS_PASSWORD_MIN_LENGTH` and `USERS_COMMON_PASSWORDS_FILE`. The common passwords file is
+    /// read using [load_common_passwords][PasswordPolicy::load_common_passwords].
+    /// # Errors
+    /// This function will return an error if the minimum length cannot be parsed or the common
+    /// passwords file cannot be read
+    pub async fn from_env() -> EmResult<Self> {
+        let mut policy = Self::default();
+        if let Ok(min_length) = env::var("USERS_PASSWORD_MIN_LENGTH") {
+            policy.min_length = min_length.parse()?;
+        }
+        if let Ok(path) = env::var("USERS_COMMON_PASSWORDS_FILE") {
+            policy = policy.load_common_passwords(path).await?;
+        }
+        Ok(policy)
+    }
+
+    /// Load the common passwords to reject from the file at `path`. The file is expected to
+    /// contain a single password per line. Blank lines are ignored and the comparison is case
+    /// insensitive.
+    /// # Errors
+    /// This function will return an error if the file cannot be read
+    pub async fn load_common_passwords<P>(mut self, path: P) -> EmResult<Self>
+    where
+        P: AsRef<Path> + Send,
+    {
+        let contents = read_file(path).await?;
+        self.common_passwords = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        Ok(self)
+    }
+
+    /// Validate that the `password` follows every rule of the policy
+    /// # Errors
+    /// This function will return an [EmError::InvalidPassword] with the first rule violated
+    pub fn validate(&self, password: &str) -> EmResult<()> {
+        let invalid = |reason: String| Err(EmError::InvalidPassword { reason });
+        if password.trim().is_empty() {
+            return invalid("Must not be an empty string or whitespace".to_owned());
+        }
+        if password.chars().count() < self.min_length {
+            return invalid(format!(
+                "Must contain at least {} characters",
+                self.min_length
+            ));
+        }
+        if self.require_uppercase && !regex!("[A-Z]").is_match(password) {
+            return invalid("Must contain at least 1 uppercase character".to_owned());
+        }
+        if self.require_lowercase && !regex!("[a-z]").is_match(password) {
+            return invalid("Must contain at least 1 lowercase character".to_owned());
+        }
+        if self.require_digit && !regex!(r"\d").is_match(password) {
+            return invalid("Must contain at least 1 digit character".to_owned());
+        }
+        if self.require_symbol && !regex!(r"\W").is_match(password) {
+            return invalid("Must contain at least 1 non-alphanumeric character".to_owned());
+        }
+        if self.common_passwords.contains(&password.to_lowercase()) {
+            return invalid("Must not be a commonly used password".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// Set the global [PasswordPolicy] used to validate user passwords. Should be called once during
+/// application startup before the API server is spawned.
+/// # Errors
+/// This function will return an error if a policy has already been set
+pub fn set_password_policy(policy: PasswordPolicy) -> EmResult<()> {
+    PASSWORD_POLICY
+        .set(policy)
+        .map_err(|_| "The password policy has already been set".into())
+}
+
+/// Get the global [PasswordPolicy], initializing the default policy if none has been set
+pub fn password_policy() -> &'static PasswordPolicy {
+    PASSWORD_POLICY.get_or_init(PasswordPolicy::default)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::panic)]
+mod test {
+    use rstest::rstest;
+
+    use super::PasswordPolicy;
+
+    /// Strict policy used to test each rule
+    fn strict_policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 12,
+            require_lowercase: true,
+            common_passwords: ["password-123!"].into_iter().map(str::to_owned).collect(),
+            ..PasswordPolicy::default()
+        }
+    }
+
+    #[rstest]
+    #[case::all_rules("Va1idPa$$word")]
+    #[case::long_passphrase("Correct-Horse-Battery-9")]
+    fn validate_should_succeed_when(#[case] password: &str) {
+        let result = strict_policy().validate(password);
+
+        assert!(result.is_ok(), "{:?}", result.unwrap_err());
+    }
+
+    #[rstest]
+    #[case::empty("", "empty")]
+    #[case::too_short("Sh0rt!", "at least 12 characters")]
+    #[case::missing_uppercase("lowercase-only-1", "uppercase")]
+    #[case::missing_lowercase("UPPERCASE-ONLY-1", "lowercase")]
+    #[case::missing_digit("No-Digits-Present", "digit")]
+    #[case::missing_symbol("NoSymbolsPresent1", "non-alphanumeric")]
+    #[case::common_password("Password-123!", "commonly used")]
+    fn validate_should_fail_when(#[case] password: &str, #[case] rule: &str) {
+        let result = strict_policy().validate(password);
+
+        let Err(error) = result else {
+            panic!("Expected an error for '{password}'");
+        };
+        assert!(error.to_string().contains(rule), "{error}");
+    }
+}
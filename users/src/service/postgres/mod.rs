@@ -3,7 +3,7 @@ pub mod users;
 
 #[cfg(test)]
 #[allow(clippy::expect_used)]
-mod test {
+pub(crate) mod test {
     use common::database::{
         connection::ConnectionBuilder, postgres::connection::PgConnectionBuilder,
     };
@@ -1,4 +1,6 @@
-use common::error::EmResult;
+use std::str::FromStr;
+
+use common::error::{EmError, EmResult};
 use sqlx::{
     database::HasArguments,
     decode::Decode,
@@ -19,10 +21,8 @@ impl<'r> Decode<'r, Postgres> for Role {
         value: PgValueRef<'r>,
     ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
         let value = <&'r str as Decode<'r, Postgres>>::decode(value)?;
-        let name = match value {
-            "admin" => RoleName::Admin,
-            "add-role" => RoleName::AddRole,
-            _ => return Err(format!("invalid value {value:?} for role name").into()),
+        let Ok(name) = RoleName::from_str(value) else {
+            return Err(format!("invalid value {value:?} for role name").into());
         };
         let description = name.description();
         Ok(Self {
@@ -117,11 +117,19 @@ impl RoleService for PgRoleService {
             .collect();
         Ok(roles)
     }
+
+    async fn read_roles_for_user(&self, uid: &Uuid) -> EmResult<Vec<Role>> {
+        let user = self.user_service.read_one(uid).await?;
+        if !user.is_active {
+            return Err(EmError::InvalidUser);
+        }
+        Ok(user.roles)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use common::error::EmResult;
+    use common::error::{EmError, EmResult};
     use rstest::rstest;
     use sqlx::PgPool;
     use strum::IntoEnumIterator;
@@ -131,8 +139,10 @@ mod test {
     use crate::{
         data::role::{Role, RoleName},
         service::{
+            hashing::HashConfig,
             postgres::{test::database, users::PgUserService},
             roles::RoleService,
+            users::{test::create_user_request, UserService},
         },
     };
 
@@ -140,7 +150,7 @@ mod test {
     #[case::privileged_user(uuid!("9363ab3f-0d62-4b40-b408-898bdea56282"))]
     #[tokio::test]
     async fn read_all_should_succeed_when(database: PgPool, #[case] uuid: Uuid) -> EmResult<()> {
-        let service = PgRoleService::new(&PgUserService::new(&database));
+        let service = PgRoleService::new(&PgUserService::new(&database, HashConfig::default()));
         let static_roles: Vec<Role> = RoleName::iter()
             .map(|name| {
                 let description = name.description();
@@ -163,7 +173,7 @@ mod test {
     #[case::non_privileged_user(uuid!("be4c1ef7-771a-4580-b0dd-ff137c64ab48"))]
     #[tokio::test]
     async fn read_all_should_fail_when(database: PgPool, #[case] uuid: Uuid) -> EmResult<()> {
-        let service = PgRoleService::new(&PgUserService::new(&database));
+        let service = PgRoleService::new(&PgUserService::new(&database, HashConfig::default()));
 
         let result = service.read_all(&uuid).await;
 
@@ -171,4 +181,70 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest]
+    #[case::admin_user(uuid!("9363ab3f-0d62-4b40-b408-898bdea56282"), vec![RoleName::Admin])]
+    #[case::add_role_user(uuid!("728ac060-9d38-47e9-b2fa-66d2954110e3"), vec![RoleName::AddRole])]
+    #[case::user_without_roles(uuid!("be4c1ef7-771a-4580-b0dd-ff137c64ab48"), vec![])]
+    #[tokio::test]
+    async fn read_roles_for_user_should_return_user_roles_when(
+        database: PgPool,
+        #[case] uuid: Uuid,
+        #[case] expected_roles: Vec<RoleName>,
+    ) -> EmResult<()> {
+        let service = PgRoleService::new(&PgUserService::new(&database, HashConfig::default()));
+
+        let roles = service.read_roles_for_user(&uuid).await?;
+
+        let role_names: Vec<RoleName> = roles.into_iter().map(|role| role.name).collect();
+        assert_eq!(role_names, expected_roles);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_roles_for_user_should_fail_when_user_does_not_exist(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let service = PgRoleService::new(&PgUserService::new(&database, HashConfig::default()));
+
+        let result = service.read_roles_for_user(&Uuid::nil()).await;
+
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_roles_for_user_should_fail_with_invalid_user_when_user_inactive(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let admin_uid = uuid!("9363ab3f-0d62-4b40-b408-898bdea56282");
+        let user_service = PgUserService::new(&database, HashConfig::default());
+        let service = PgRoleService::new(&user_service);
+        let user_request = create_user_request(
+            "Mr Inactive Roles",
+            "inactive-roles",
+            "Inactive1!",
+            &["admin"],
+        );
+
+        let action = async {
+            let user = user_service.create_user(&admin_uid, &user_request).await?;
+            user_service.deactivate_user(&admin_uid, &user.uid).await?;
+            EmResult::Ok(service.read_roles_for_user(&user.uid).await)
+        }
+        .await;
+        sqlx::query("delete from users.users where username = $1")
+            .bind(&user_request.username)
+            .execute(&database)
+            .await?;
+
+        let result = action?;
+        assert!(matches!(result, Err(EmError::InvalidUser)), "{result:?}");
+
+        Ok(())
+    }
 }
@@ -1,22 +1,31 @@
 use common::{
-    api::ApiRequestValidator,
+    api::{
+        join_validation_messages,
+        pagination::{Page, Pagination},
+        ApiRequestValidator,
+    },
     database::{
         connection::{finalize_transaction, get_connection_with_em_uid},
-        postgres::Postgres,
+        postgres::{begin_snapshot, Postgres},
     },
     error::{
         EmError::{self, InvalidUser},
         EmResult,
     },
 };
-use sqlx::{Connection, PgPool};
+use log::warn;
+use sqlx::{postgres::PgRow, Connection, FromRow, PgPool, Row};
 use uuid::Uuid;
 
 use crate::{
     data::{role::RoleName, user::User},
-    service::users::{
-        CreateUserRequest, CreateUserRequestValidator, ModifyUserRoleRequest, UpdateUserRequest,
-        UpdateUserRequestValidator, UserService, ValidateUserRequest,
+    service::{
+        hashing::HashConfig,
+        users::{
+            validate_password, CreateUserRequest, CreateUserRequestValidator,
+            ModifyUserRoleRequest, RoleChange, UpdateUserRequest, UpdateUserRequestValidator,
+            UserService, ValidateUserRequest,
+        },
     },
 };
 
@@ -25,21 +34,69 @@ use crate::{
 pub struct PgUserService {
     /// Postgres database connection pool used by this service
     pool: PgPool,
+    /// Parameters used to hash new passwords and decide when stored hashes are re-hashed
+    hash_config: HashConfig,
 }
 
 impl PgUserService {
-    /// Create new instance of a [UserService]
-    pub fn new(pool: &PgPool) -> Self {
-        Self { pool: pool.clone() }
+    /// Create new instance of a [UserService]. Passwords are hashed using the parameters of the
+    /// `hash_config`.
+    pub fn new(pool: &PgPool, hash_config: HashConfig) -> Self {
+        Self {
+            pool: pool.clone(),
+            hash_config,
+        }
+    }
+
+    /// Re-hash the password of the user specified by `uid` using the current [HashConfig]. Only
+    /// called when [HashConfig::needs_rehash] is true for the stored hash. Failures are logged but
+    /// not returned since the user has already been authenticated.
+    async fn rehash_password(&self, uid: &Uuid, password: &str) {
+        let result = async {
+            let mut connection = get_connection_with_em_uid(uid, &self.pool).await?;
+            sqlx::query("call users.rehash_password($1, $2, $3)")
+                .bind(uid)
+                .bind(password)
+                .bind(self.hash_config.cost())
+                .execute(&mut connection)
+                .await?;
+            EmResult::Ok(())
+        }
+        .await;
+        if let Err(error) = result {
+            warn!("Could not re-hash the password of user {uid}. {error}");
+        }
+    }
+
+    /// Set the active flag of the user specified by `uid` to `is_active`. The user specified as
+    /// `current_uid` must have the 'admin' role.
+    async fn set_user_active(
+        &self,
+        current_uid: &Uuid,
+        uid: &Uuid,
+        is_active: bool,
+    ) -> EmResult<User> {
+        let user = self.read_one(current_uid).await?;
+        user.check_role(RoleName::Admin)?;
+
+        let mut connection = get_connection_with_em_uid(current_uid, &self.pool).await?;
+        sqlx::query("call users.set_user_active($1, $2)")
+            .bind(uid)
+            .bind(is_active)
+            .execute(&mut connection)
+            .await?;
+        self.read_one(uid).await
     }
 
     /// Update the password of a user with the `uid` specified
     #[allow(unused)]
     async fn reset_password(&self, uid: &Uuid, new_password: &str) -> EmResult<()> {
+        validate_password(new_password)?;
         let mut connection = get_connection_with_em_uid(uid, &self.pool).await?;
-        sqlx::query("call users.reset_password($1, $2)")
+        sqlx::query("call users.reset_password($1, $2, $3)")
             .bind(uid)
             .bind(new_password)
+            .bind(self.hash_config.cost())
             .execute(&mut connection)
             .await?;
         Ok(())
@@ -52,7 +109,7 @@ impl UserService for PgUserService {
     type UpdateRequestValidator = UpdateUserRequestValidator;
 
     async fn create_user(&self, current_uid: &Uuid, request: &CreateUserRequest) -> EmResult<User> {
-        Self::CreateRequestValidator::validate(request)?;
+        Self::CreateRequestValidator::validate(request).map_err(join_validation_messages)?;
         let CreateUserRequest {
             full_name,
             username,
@@ -64,10 +121,11 @@ impl UserService for PgUserService {
 
         let mut connection = get_connection_with_em_uid(current_uid, &self.pool).await?;
         let mut transaction = connection.begin().await?;
-        let uid: Uuid = sqlx::query_scalar("select users.create_user($1, $2, $3)")
+        let uid: Uuid = sqlx::query_scalar("select users.create_user($1, $2, $3, $4)")
             .bind(full_name)
             .bind(username)
             .bind(password)
+            .bind(self.hash_config.cost())
             .fetch_one(&mut transaction)
             .await?;
 
@@ -83,24 +141,43 @@ impl UserService for PgUserService {
         self.read_one(&uid).await
     }
 
-    async fn read_all(&self, current_uid: &Uuid) -> EmResult<Vec<User>> {
+    async fn read_all_paged(
+        &self,
+        current_uid: &Uuid,
+        page: &Pagination,
+        include_inactive: bool,
+    ) -> EmResult<Page<User>> {
         let user = self.read_one(current_uid).await?;
         user.check_role(RoleName::Admin)?;
 
+        let mut transaction = begin_snapshot(&self.pool).await?;
+        let total_count =
+            sqlx::query_scalar("select count(*) from users.v_users u where $1 or u.is_active")
+                .bind(include_inactive)
+                .fetch_one(&mut transaction)
+                .await?;
         let users = sqlx::query_as(
             r#"
-            select u.uid, u.username, u.full_name, u.roles
-            from users.v_users u"#,
+            select u.uid, u.username, u.full_name, u.roles, u.is_active, u.version
+            from users.v_users u
+            where $3 or u.is_active
+            order by u.username
+            limit $1
+            offset $2"#,
         )
-        .fetch_all(&self.pool)
+        .bind(page.limit)
+        .bind(page.offset)
+        .bind(include_inactive)
+        .fetch_all(&mut transaction)
         .await?;
-        Ok(users)
+        transaction.commit().await?;
+        Ok(Page::new(users, total_count))
     }
 
     async fn read_one(&self, uuid: &Uuid) -> EmResult<User> {
         let user = sqlx::query_as(
             r#"
-            select u.uid, u.username, u.full_name, u.roles
+            select u.uid, u.username, u.full_name, u.roles, u.is_active, u.version
             from users.v_users u
             where u.uid = $1"#,
         )
@@ -116,35 +193,74 @@ impl UserService for PgUserService {
             return Err(EmError::InvalidUser);
         }
 
-        Self::UpdateRequestValidator::validate(request)?;
+        Self::UpdateRequestValidator::validate(request).map_err(join_validation_messages)?;
         let UpdateUserRequest {
             update_uid,
             new_name,
             new_username,
+            new_password,
+            version,
         } = request;
 
         let mut connection = get_connection_with_em_uid(current_uid, &self.pool).await?;
-        sqlx::query("call users.update_user($1, $2, $3)")
-            .bind(update_uid)
-            .bind(new_username)
-            .bind(new_name)
-            .execute(&mut connection)
-            .await?;
-        self.read_one(update_uid).await
+        let new_version: Option<i64> =
+            sqlx::query_scalar("select users.update_user($1, $2, $3, $4, $5, $6)")
+                .bind(update_uid)
+                .bind(new_username)
+                .bind(new_name)
+                .bind(new_password)
+                .bind(self.hash_config.cost())
+                .bind(version)
+                .fetch_one(&mut connection)
+                .await?;
+        // Drop connection to clean up the resource before reading the updated user
+        drop(connection);
+
+        let user = self.read_one(update_uid).await?;
+        if new_version.is_none() {
+            return Err(EmError::InvalidRequest {
+                request: format!("Update of user {update_uid}"),
+                reason: format!(
+                    "User has been modified since version {}. Current version is {}",
+                    version.unwrap_or_default(),
+                    user.version
+                ),
+            });
+        }
+        Ok(user)
     }
 
     async fn validate_user(&self, request: &ValidateUserRequest) -> EmResult<User> {
         let ValidateUserRequest { username, password } = request;
-        let result = sqlx::query_as(
+        let result: Option<PgRow> = sqlx::query(
             r#"
-            select v.uid, v.username, v.full_name, v.roles
+            select v.uid, v.username, v.full_name, v.roles, v.is_active, v.version, v.hash_cost
             from users.validate_user($1, $2) v"#,
         )
         .bind(username)
         .bind(password)
         .fetch_optional(&self.pool)
         .await?;
-        result.map_or_else(|| Err(InvalidUser), Ok)
+        let Some(row) = result else {
+            return Err(InvalidUser);
+        };
+        let user = User::from_row(&row)?;
+        let hash_cost: i32 = row.try_get("hash_cost")?;
+        if self.hash_config.needs_rehash(hash_cost) {
+            self.rehash_password(&user.uid, password).await;
+        }
+        Ok(user)
+    }
+
+    async fn deactivate_user(&self, current_uid: &Uuid, uid: &Uuid) -> EmResult<User> {
+        if current_uid == uid {
+            return Err("Users cannot deactivate themselves".into());
+        }
+        self.set_user_active(current_uid, uid, false).await
+    }
+
+    async fn reactivate_user(&self, current_uid: &Uuid, uid: &Uuid) -> EmResult<User> {
+        self.set_user_active(current_uid, uid, true).await
     }
 
     async fn modify_user_role(
@@ -170,12 +286,69 @@ impl UserService for PgUserService {
             .await?;
         self.read_one(uid).await
     }
+
+    async fn modify_user_roles(
+        &self,
+        current_uid: &Uuid,
+        assignments: &[(Uuid, RoleChange)],
+    ) -> EmResult<()> {
+        let user = self.read_one(current_uid).await?;
+        user.check_role(RoleName::AddRole)?;
+
+        let mut connection = get_connection_with_em_uid(current_uid, &self.pool).await?;
+        let mut transaction = connection.begin().await?;
+        let uids: Vec<Uuid> = assignments.iter().map(|(uid, _)| *uid).collect();
+        let existing_uids: Vec<Uuid> =
+            sqlx::query_scalar("select u.uid from users.users u where u.uid = any($1)")
+                .bind(&uids)
+                .fetch_all(&mut transaction)
+                .await?;
+
+        let mut errors = Vec::new();
+        for (i, (uid, change)) in assignments.iter().enumerate() {
+            if !existing_uids.contains(uid) {
+                errors.push(format!("Assignment {i}: User {uid} does not exist"));
+            }
+            if let Err(error) = user.check_role(change.role()) {
+                errors.push(format!("Assignment {i}: {error}"));
+            }
+        }
+        if !errors.is_empty() {
+            transaction.rollback().await?;
+            return Err(EmError::InvalidRequest {
+                request: format!("Batch of {} role assignment(s)", assignments.len()),
+                reason: join_validation_messages(errors),
+            });
+        }
+
+        let mut result = Ok(());
+        for (uid, change) in assignments {
+            let query = match change {
+                RoleChange::Add(_) => "call users.add_user_role($1, $2)",
+                RoleChange::Revoke(_) => "call users.revoke_user_role($1, $2)",
+            };
+            if let Err(error) = sqlx::query(query)
+                .bind(uid)
+                .bind(change.role())
+                .execute(&mut transaction)
+                .await
+            {
+                result = Err(error);
+                break;
+            }
+        }
+        finalize_transaction(result, transaction).await?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod test {
 
-    use common::error::EmResult;
+    use common::{
+        api::pagination::Pagination,
+        error::{EmError, EmResult},
+    };
     use rstest::rstest;
     use sqlx::PgPool;
     use uuid::{uuid, Uuid};
@@ -184,10 +357,11 @@ mod test {
     use crate::{
         data::role::RoleName,
         service::{
+            hashing::HashConfig,
             postgres::test::database,
             users::{
                 test::{create_user_request, validate_user_request},
-                CreateUserRequest, UserService, ValidateUserRequest,
+                CreateUserRequest, RoleChange, UpdateUserRequest, UserService, ValidateUserRequest,
             },
         },
     };
@@ -209,7 +383,7 @@ mod test {
         #[case] uid: Uuid,
         #[case] user_request: CreateUserRequest,
     ) -> EmResult<()> {
-        let service = PgUserService::new(&database);
+        let service = PgUserService::new(&database, HashConfig::default());
 
         let action = service.create_user(&uid, &user_request).await;
         cleanup_user_create(&user_request.username, &database).await?;
@@ -233,7 +407,7 @@ mod test {
         #[case] uid: Uuid,
         #[case] user_request: CreateUserRequest,
     ) -> EmResult<()> {
-        let service = PgUserService::new(&database);
+        let service = PgUserService::new(&database, HashConfig::default());
 
         let action = service.create_user(&uid, &user_request).await;
         if user_request.username != "none" {
@@ -256,10 +430,10 @@ mod test {
         #[case] full_name: &str,
         #[case] roles: Vec<&str>,
     ) -> EmResult<()> {
-        let service = PgUserService::new(&database);
+        let service = PgUserService::new(&database, HashConfig::default());
 
         let users = service
-            .read_all(&uuid!("9363ab3f-0d62-4b40-b408-898bdea56282"))
+            .read_all(&uuid!("9363ab3f-0d62-4b40-b408-898bdea56282"), false)
             .await?;
 
         let user = users
@@ -273,6 +447,27 @@ mod test {
         Ok(())
     }
 
+    #[rstest]
+    #[case::first_page(Pagination::new(1, 0), 1)]
+    #[case::past_last_page(Pagination::new(1, i64::MAX), 0)]
+    #[tokio::test]
+    async fn read_all_paged_should_limit_items_when(
+        database: PgPool,
+        #[case] page: Pagination,
+        #[case] expected_items: usize,
+    ) -> EmResult<()> {
+        let service = PgUserService::new(&database, HashConfig::default());
+        let admin_uid = uuid!("9363ab3f-0d62-4b40-b408-898bdea56282");
+
+        let users = service.read_all(&admin_uid, false).await?;
+        let users_page = service.read_all_paged(&admin_uid, &page, false).await?;
+
+        assert_eq!(users_page.items.len(), expected_items);
+        assert_eq!(users_page.total_count, users.len() as i64);
+
+        Ok(())
+    }
+
     #[rstest]
     #[case::valid_request(validate_user_request("admin", "admin"), uuid!("9363ab3f-0d62-4b40-b408-898bdea56282"))]
     #[tokio::test]
@@ -281,7 +476,7 @@ mod test {
         #[case] validate_user_request: ValidateUserRequest,
         #[case] uuid: Uuid,
     ) -> EmResult<()> {
-        let service = PgUserService::new(&database);
+        let service = PgUserService::new(&database, HashConfig::default());
 
         let user = service.validate_user(&validate_user_request).await?;
 
@@ -298,7 +493,7 @@ mod test {
         database: PgPool,
         #[case] validate_user_request: ValidateUserRequest,
     ) -> EmResult<()> {
-        let service = PgUserService::new(&database);
+        let service = PgUserService::new(&database, HashConfig::default());
 
         let action = service.validate_user(&validate_user_request).await;
 
@@ -306,4 +501,291 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn validate_user_should_rehash_password_when_cost_increased(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let admin_uid = uuid!("9363ab3f-0d62-4b40-b408-898bdea56282");
+        let user_request = create_user_request("Mr Rehash", "rehash", "Rehash1!", &[]);
+        let weak_service = PgUserService::new(&database, HashConfig::new(4)?);
+        let service = PgUserService::new(&database, HashConfig::new(5)?);
+        let request = validate_user_request("rehash", "Rehash1!");
+
+        let action = async {
+            weak_service.create_user(&admin_uid, &user_request).await?;
+            let read_hash = || {
+                sqlx::query_scalar::<_, String>(
+                    "select password from users.users where username = $1",
+                )
+                .bind("rehash")
+                .fetch_one(&database)
+            };
+            service.validate_user(&request).await?;
+            let hash = read_hash().await?;
+            let user = service.validate_user(&request).await?;
+            let current_hash = read_hash().await?;
+            EmResult::Ok((hash, user, current_hash))
+        }
+        .await;
+        cleanup_user_create(&user_request.username, &database).await?;
+
+        let (hash, user, current_hash) = action?;
+        assert!(hash.starts_with("$2a$05$"), "{hash}");
+        assert_eq!(user.username, "rehash");
+        assert_eq!(
+            current_hash, hash,
+            "Password should not be re-hashed when the hash cost is current"
+        );
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn validate_user_should_fail_with_invalid_user_when_user_deactivated(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let admin_uid = uuid!("9363ab3f-0d62-4b40-b408-898bdea56282");
+        let user_request = create_user_request("Mr Inactive", "inactive", "Inactive1!", &[]);
+        let service = PgUserService::new(&database, HashConfig::default());
+        let request = validate_user_request("inactive", "Inactive1!");
+
+        let action = async {
+            let user = service.create_user(&admin_uid, &user_request).await?;
+            let deactivated_user = service.deactivate_user(&admin_uid, &user.uid).await?;
+            let validate_result = service.validate_user(&request).await;
+            let users = service.read_all(&admin_uid, false).await?;
+            let all_users = service.read_all(&admin_uid, true).await?;
+            service.reactivate_user(&admin_uid, &user.uid).await?;
+            let reactivated_result = service.validate_user(&request).await;
+            EmResult::Ok((
+                user.uid,
+                deactivated_user,
+                validate_result,
+                users,
+                all_users,
+                reactivated_result,
+            ))
+        }
+        .await;
+        cleanup_user_create(&user_request.username, &database).await?;
+
+        let (uid, deactivated_user, validate_result, users, all_users, reactivated_result) =
+            action?;
+        assert!(!deactivated_user.is_active, "User should be deactivated");
+        assert!(
+            matches!(validate_result, Err(EmError::InvalidUser)),
+            "{validate_result:?}"
+        );
+        assert!(
+            users.iter().all(|user| user.uid != uid),
+            "Inactive user should be excluded by default"
+        );
+        assert!(
+            all_users.iter().any(|user| user.uid == uid),
+            "Inactive user should be included when requested"
+        );
+        assert!(
+            reactivated_result.is_ok_and(|user| user.is_active),
+            "Reactivated user should authenticate"
+        );
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::self_deactivation(
+        uuid!("9363ab3f-0d62-4b40-b408-898bdea56282"),
+        uuid!("9363ab3f-0d62-4b40-b408-898bdea56282"),
+    )]
+    #[case::missing_privilege(
+        uuid!("728ac060-9d38-47e9-b2fa-66d2954110e3"),
+        uuid!("be4c1ef7-771a-4580-b0dd-ff137c64ab48"),
+    )]
+    #[tokio::test]
+    async fn deactivate_user_should_fail_when(
+        database: PgPool,
+        #[case] current_uid: Uuid,
+        #[case] uid: Uuid,
+    ) {
+        let service = PgUserService::new(&database, HashConfig::default());
+
+        let result = service.deactivate_user(&current_uid, &uid).await;
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_should_only_change_full_name_when_only_full_name_specified(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let admin_uid = uuid!("9363ab3f-0d62-4b40-b408-898bdea56282");
+        let user_request = create_user_request("Mr Update", "update_name", "Update1!", &[]);
+        let service = PgUserService::new(&database, HashConfig::default());
+
+        let action = async {
+            let user = service.create_user(&admin_uid, &user_request).await?;
+            let request = UpdateUserRequest::new(user.uid, None, Some("Mr Updated".to_owned()));
+            let updated_user = service.update(&admin_uid, &request).await?;
+            EmResult::Ok((user, updated_user))
+        }
+        .await;
+        cleanup_user_create(&user_request.username, &database).await?;
+
+        let (user, updated_user) = action?;
+        assert_eq!(updated_user.full_name, "Mr Updated");
+        assert_eq!(updated_user.username, user.username);
+        assert_eq!(updated_user.version, user.version + 1);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_should_only_change_password_when_only_password_specified(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let admin_uid = uuid!("9363ab3f-0d62-4b40-b408-898bdea56282");
+        let user_request = create_user_request("Mr Password", "update_password", "Update1!", &[]);
+        let service = PgUserService::new(&database, HashConfig::default());
+
+        let action = async {
+            let user = service.create_user(&admin_uid, &user_request).await?;
+            let request = UpdateUserRequest::new(user.uid, None, None)
+                .with_new_password("Updated2!")
+                .with_version(user.version);
+            let updated_user = service.update(&admin_uid, &request).await?;
+            let old_password_result = service
+                .validate_user(&validate_user_request("update_password", "Update1!"))
+                .await;
+            let new_password_result = service
+                .validate_user(&validate_user_request("update_password", "Updated2!"))
+                .await;
+            EmResult::Ok((user, updated_user, old_password_result, new_password_result))
+        }
+        .await;
+        cleanup_user_create(&user_request.username, &database).await?;
+
+        let (user, updated_user, old_password_result, new_password_result) = action?;
+        assert_eq!(updated_user.full_name, user.full_name);
+        assert_eq!(updated_user.username, user.username);
+        assert!(
+            old_password_result.is_err(),
+            "Old password should no longer authenticate"
+        );
+        assert!(
+            new_password_result.is_ok(),
+            "New password should authenticate"
+        );
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_should_fail_with_invalid_request_when_version_is_stale(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let admin_uid = uuid!("9363ab3f-0d62-4b40-b408-898bdea56282");
+        let user_request = create_user_request("Mr Stale", "update_stale", "Update1!", &[]);
+        let service = PgUserService::new(&database, HashConfig::default());
+
+        let action = async {
+            let user = service.create_user(&admin_uid, &user_request).await?;
+            let first_request = UpdateUserRequest::new(user.uid, None, Some("Mr First".to_owned()))
+                .with_version(user.version);
+            service.update(&admin_uid, &first_request).await?;
+            let stale_request =
+                UpdateUserRequest::new(user.uid, None, Some("Mr Second".to_owned()))
+                    .with_version(user.version);
+            let stale_result = service.update(&admin_uid, &stale_request).await;
+            let current_user = service.read_one(&user.uid).await?;
+            EmResult::Ok((stale_result, current_user))
+        }
+        .await;
+        cleanup_user_create(&user_request.username, &database).await?;
+
+        let (stale_result, current_user) = action?;
+        assert!(
+            matches!(stale_result, Err(EmError::InvalidRequest { .. })),
+            "{stale_result:?}"
+        );
+        assert_eq!(current_user.full_name, "Mr First");
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn modify_user_roles_should_apply_every_change(database: PgPool) -> EmResult<()> {
+        let admin_uid = uuid!("9363ab3f-0d62-4b40-b408-898bdea56282");
+        let first_request = create_user_request("Mr Batch", "batch_first", "Batch1!", &["check"]);
+        let second_request = create_user_request("Ms Batch", "batch_second", "Batch1!", &[]);
+        let service = PgUserService::new(&database, HashConfig::default());
+
+        let action = async {
+            let first = service.create_user(&admin_uid, &first_request).await?;
+            let second = service.create_user(&admin_uid, &second_request).await?;
+            let assignments = [
+                (first.uid, RoleChange::Add(RoleName::LoadData)),
+                (first.uid, RoleChange::Revoke(RoleName::Check)),
+                (second.uid, RoleChange::Add(RoleName::LoadData)),
+            ];
+            service.modify_user_roles(&admin_uid, &assignments).await?;
+            let first = service.read_one(&first.uid).await?;
+            let second = service.read_one(&second.uid).await?;
+            EmResult::Ok((first, second))
+        }
+        .await;
+        cleanup_user_create(&first_request.username, &database).await?;
+        cleanup_user_create(&second_request.username, &database).await?;
+
+        let (first, second) = action?;
+        let first_roles: Vec<RoleName> = first.roles.iter().map(|r| r.name).collect();
+        let second_roles: Vec<RoleName> = second.roles.iter().map(|r| r.name).collect();
+        assert_eq!(first_roles, vec![RoleName::LoadData]);
+        assert_eq!(second_roles, vec![RoleName::LoadData]);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::user_does_not_exist(uuid!("9363ab3f-0d62-4b40-b408-898bdea56282"), Uuid::new_v4(), RoleName::LoadData)]
+    #[case::missing_privilege(uuid!("728ac060-9d38-47e9-b2fa-66d2954110e3"), uuid!("be4c1ef7-771a-4580-b0dd-ff137c64ab48"), RoleName::Admin)]
+    #[tokio::test]
+    async fn modify_user_roles_should_apply_nothing_when(
+        database: PgPool,
+        #[case] current_uid: Uuid,
+        #[case] invalid_uid: Uuid,
+        #[case] invalid_role: RoleName,
+    ) -> EmResult<()> {
+        let admin_uid = uuid!("9363ab3f-0d62-4b40-b408-898bdea56282");
+        let user_request = create_user_request("Mr Rollback", "batch_rollback", "Batch1!", &[]);
+        let service = PgUserService::new(&database, HashConfig::default());
+
+        let action = async {
+            let user = service.create_user(&admin_uid, &user_request).await?;
+            let assignments = [
+                (user.uid, RoleChange::Add(RoleName::AddRole)),
+                (invalid_uid, RoleChange::Add(invalid_role)),
+            ];
+            let result = service.modify_user_roles(&current_uid, &assignments).await;
+            let user = service.read_one(&user.uid).await?;
+            EmResult::Ok((result, user))
+        }
+        .await;
+        cleanup_user_create(&user_request.username, &database).await?;
+
+        let (result, user) = action?;
+        assert!(
+            matches!(result, Err(EmError::InvalidRequest { .. })),
+            "{result:?}"
+        );
+        assert!(user.roles.is_empty(), "No role changes should be applied");
+
+        Ok(())
+    }
 }
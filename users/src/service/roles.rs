@@ -14,4 +14,10 @@ where
 
     /// Read all roles found in the database. Must be an admin user to access roles
     async fn read_all(&self, current_uid: &Uuid) -> EmResult<Vec<Role>>;
+
+    /// Read the roles assigned to the user specified by `uid`. An admin user is only given the
+    /// admin role, which implicitly grants every other role. Inactive users are rejected with an
+    /// [EmError::InvalidUser][common::error::EmError::InvalidUser] since they cannot be granted
+    /// any privilege.
+    async fn read_roles_for_user(&self, uid: &Uuid) -> EmResult<Vec<Role>>;
 }
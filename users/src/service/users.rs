@@ -1,37 +1,23 @@
 use common::{
-    api::ApiRequestValidator,
+    api::{
+        pagination::{Page, Pagination},
+        ApiRequestValidator,
+    },
     database::Database,
-    error::{EmError, EmResult},
+    error::EmResult,
 };
-use lazy_regex::regex;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::data::{role::RoleName, user::User};
+use crate::{
+    data::{role::RoleName, user::User},
+    service::password_policy::password_policy,
+};
 
-/// Validate that the provided `password` meets the rules prescribed for password
-fn validate_password(password: &str) -> EmResult<()> {
-    if password.trim().is_empty() {
-        return Err(EmError::InvalidPassword {
-            reason: "Must not be an empty string or whitespace",
-        });
-    }
-    if !regex!("[A-Z]").is_match(password) {
-        return Err(EmError::InvalidPassword {
-            reason: "Must contain at least 1 uppercase character",
-        });
-    }
-    if !regex!(r"\d").is_match(password) {
-        return Err(EmError::InvalidPassword {
-            reason: "Must contain at least 1 digit character",
-        });
-    }
-    if !regex!(r"\W").is_match(password) {
-        return Err(EmError::InvalidPassword {
-            reason: "Must contain at least 1 non-alphanumeric character",
-        });
-    }
-    Ok(())
+/// Validate that the provided `password` meets the rules prescribed by the global
+/// [PasswordPolicy][crate::service::password_policy::PasswordPolicy]
+pub(crate) fn validate_password(password: &str) -> EmResult<()> {
+    password_policy().validate(password)
 }
 
 /// Request object for creating a new user
@@ -56,21 +42,26 @@ impl ApiRequestValidator for CreateUserRequestValidator {
     type ErrorMessage = String;
     type Request = CreateUserRequest;
 
-    fn validate(request: &Self::Request) -> Result<(), Self::ErrorMessage> {
+    fn validate(request: &Self::Request) -> Result<(), Vec<Self::ErrorMessage>> {
+        let mut errors = Vec::new();
         if request.full_name.trim().is_empty() {
-            Err("full_name cannot be empty or whitespace")?;
+            errors.push("full_name cannot be empty or whitespace".to_owned());
         }
         if request.username.trim().is_empty() {
-            Err("username cannot be empty or whitespace")?;
+            errors.push("username cannot be empty or whitespace".to_owned());
         }
         if let Err(error) = validate_password(&request.password) {
-            Err(format!("{error}"))?
+            errors.push(format!("{error}"));
+        }
+        if !errors.is_empty() {
+            return Err(errors);
         }
         Ok(())
     }
 }
 
-/// Request object for updating an existing user
+/// Request object for updating an existing user. Only the fields that are [Some] are updated, all
+/// other fields are left unchanged.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UpdateUserRequest {
     /// uuid of the user attempting to perform the action
@@ -79,6 +70,11 @@ pub struct UpdateUserRequest {
     pub(crate) new_username: Option<String>,
     /// User is attempting to update the user's full name if Some
     pub(crate) new_name: Option<String>,
+    /// User is attempting to update the user's password if Some
+    pub(crate) new_password: Option<String>,
+    /// Version of the user the update was made against. If Some, the update is rejected when the
+    /// user has been modified since that version
+    pub(crate) version: Option<i64>,
 }
 
 impl UpdateUserRequest {
@@ -92,8 +88,22 @@ impl UpdateUserRequest {
             update_uid,
             new_username,
             new_name,
+            new_password: None,
+            version: None,
         }
     }
+
+    /// Update the user's password to `new_password` as part of this request
+    pub fn with_new_password<S: Into<String>>(mut self, new_password: S) -> Self {
+        self.new_password = Some(new_password.into());
+        self
+    }
+
+    /// Only apply this request if the user is still at the `version` specified
+    pub const fn with_version(mut self, version: i64) -> Self {
+        self.version = Some(version);
+        self
+    }
 }
 
 /// Default [ApiRequestValidator] for [UpdateUserRequest]
@@ -103,17 +113,26 @@ impl ApiRequestValidator for UpdateUserRequestValidator {
     type ErrorMessage = String;
     type Request = UpdateUserRequest;
 
-    fn validate(request: &Self::Request) -> Result<(), Self::ErrorMessage> {
+    fn validate(request: &Self::Request) -> Result<(), Vec<Self::ErrorMessage>> {
+        let mut errors = Vec::new();
         if let Some(new_username) = &request.new_username {
             if new_username.trim().is_empty() {
-                Err("new_username cannot be empty or whitespace")?;
+                errors.push("new_username cannot be empty or whitespace".to_owned());
             }
         }
         if let Some(new_name) = &request.new_name {
             if new_name.trim().is_empty() {
-                Err("new_name cannot be empty or whitespace")?;
+                errors.push("new_name cannot be empty or whitespace".to_owned());
+            }
+        }
+        if let Some(new_password) = &request.new_password {
+            if let Err(error) = validate_password(new_password) {
+                errors.push(format!("{error}"));
             }
         }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
         Ok(())
     }
 }
@@ -138,6 +157,14 @@ impl ValidateUserRequest {
     }
 }
 
+/// Query parameters accepted when reading all users
+#[derive(Deserialize, Debug, Default)]
+pub struct ReadUsersQuery {
+    /// Include deactivated users in the result. Defaults to false
+    #[serde(default)]
+    pub(crate) include_inactive: bool,
+}
+
 /// Request object to allow an admin user to add or revoke another users role
 #[derive(Deserialize, Debug)]
 pub struct ModifyUserRoleRequest {
@@ -151,6 +178,40 @@ pub struct ModifyUserRoleRequest {
     pub(crate) add: bool,
 }
 
+/// Change to a single role of a user
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum RoleChange {
+    /// Add the role to the user
+    Add(RoleName),
+    /// Revoke the role from the user
+    Revoke(RoleName),
+}
+
+impl RoleChange {
+    /// Name of the role that is changed
+    pub const fn role(&self) -> RoleName {
+        match self {
+            Self::Add(role) | Self::Revoke(role) => *role,
+        }
+    }
+}
+
+/// Request object to allow an admin user to add or revoke roles for many users at once. Every
+/// assignment is the uuid of the user to change and the [RoleChange] to apply.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ModifyUserRolesRequest {
+    /// Role changes to apply as pairs of user uuid and [RoleChange]
+    pub(crate) assignments: Vec<(Uuid, RoleChange)>,
+}
+
+impl ModifyUserRolesRequest {
+    /// Create a new [ModifyUserRolesRequest] applying all the `assignments`
+    pub const fn new(assignments: Vec<(Uuid, RoleChange)>) -> Self {
+        Self { assignments }
+    }
+}
+
 /// Service for interacting with the user system. Allows for reading users as well as creating new
 /// and modifying existing users.
 pub trait UserService
@@ -164,16 +225,46 @@ where
     /// Create a new [User]. The user specified in `request` must have the 'admin' role to perform
     /// this action. Returns the newly created [User]
     async fn create_user(&self, current_uid: &Uuid, request: &CreateUserRequest) -> EmResult<User>;
-    /// Read all [User]s from the database. The user specified as `current_uid` must have the
-    /// 'admin' role to perform this action.
-    async fn read_all(&self, current_uid: &Uuid) -> EmResult<Vec<User>>;
+    /// Read all [User]s from the database. Deactivated users are only included if
+    /// `include_inactive` is true. The user specified as `current_uid` must have the 'admin' role
+    /// to perform this action.
+    async fn read_all(&self, current_uid: &Uuid, include_inactive: bool) -> EmResult<Vec<User>> {
+        let page = self
+            .read_all_paged(current_uid, &Pagination::unbounded(), include_inactive)
+            .await?;
+        Ok(page.items)
+    }
+    /// Read a single [Page] of [User]s within the bounds of `page`. The returned [Page] also
+    /// contains the total number of users available. Deactivated users are only included if
+    /// `include_inactive` is true. The user specified as `current_uid` must have the 'admin' role
+    /// to perform this action.
+    async fn read_all_paged(
+        &self,
+        current_uid: &Uuid,
+        page: &Pagination,
+        include_inactive: bool,
+    ) -> EmResult<Page<User>>;
     /// Read a single [User] from the database
     async fn read_one(&self, uuid: &Uuid) -> EmResult<User>;
     /// Update the user specified within the `request`. Once the user is validated, the update type
-    /// specified is performed and the new state of the [User] is returned.
+    /// specified is performed and the new state of the [User] is returned. Fields that are not
+    /// specified are left unchanged. If the `request` specifies a version that no longer matches
+    /// the user, an [EmError::InvalidRequest] error is returned.
+    ///
+    /// [EmError::InvalidRequest]: common::error::EmError::InvalidRequest
     async fn update(&self, current_uid: &Uuid, request: &UpdateUserRequest) -> EmResult<User>;
-    /// Validate that the specified user credentials match a user. If successful, return that [User]
+    /// Validate that the specified user credentials match an active user. If successful, return
+    /// that [User]. Deactivated users are rejected with an [EmError::InvalidUser] error.
+    ///
+    /// [EmError::InvalidUser]: common::error::EmError::InvalidUser
     async fn validate_user(&self, request: &ValidateUserRequest) -> EmResult<User>;
+    /// Deactivate the user specified by `uid` so they can no longer authenticate. The user is kept
+    /// rather than deleted. The user specified as `current_uid` must have the 'admin' role to
+    /// perform this action and cannot deactivate themselves. Returns the updated [User].
+    async fn deactivate_user(&self, current_uid: &Uuid, uid: &Uuid) -> EmResult<User>;
+    /// Reactivate the previously deactivated user specified by `uid`. The user specified as
+    /// `current_uid` must have the 'admin' role to perform this action. Returns the updated [User].
+    async fn reactivate_user(&self, current_uid: &Uuid, uid: &Uuid) -> EmResult<User>;
     /// Modify a role for the user specified within the `request`. The action user specified in the
     /// `request` must have the 'add-role' role and is only able to add/revoke roles that they have
     /// themselves
@@ -182,6 +273,18 @@ where
         current_uid: &Uuid,
         request: &ModifyUserRoleRequest,
     ) -> EmResult<User>;
+    /// Apply every role change within `assignments` in a single transaction so either all changes
+    /// are applied or none are. The user specified as `current_uid` must have the 'add-role' role
+    /// and is only able to add/revoke roles that they have themselves. Each target user must
+    /// exist. If any assignment is invalid, no changes are applied and an
+    /// [EmError::InvalidRequest] error listing the problem of each invalid assignment is returned.
+    ///
+    /// [EmError::InvalidRequest]: common::error::EmError::InvalidRequest
+    async fn modify_user_roles(
+        &self,
+        current_uid: &Uuid,
+        assignments: &[(Uuid, RoleChange)],
+    ) -> EmResult<()>;
 }
 
 #[cfg(test)]
@@ -196,8 +299,8 @@ pub(crate) mod test {
     use crate::{
         data::role::RoleName,
         service::users::{
-            validate_password, CreateUserRequest, CreateUserRequestValidator, UpdateUserRequest,
-            UpdateUserRequestValidator, ValidateUserRequest,
+            validate_password, CreateUserRequest, CreateUserRequestValidator, RoleChange,
+            UpdateUserRequest, UpdateUserRequestValidator, ValidateUserRequest,
         },
     };
 
@@ -267,6 +370,15 @@ pub(crate) mod test {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn create_user_request_should_report_every_violation_when_multiple_fields_invalid() {
+        let request = create_user_request("", " ", "test", &["admin"]);
+
+        let errors = CreateUserRequestValidator::validate(&request).unwrap_err();
+
+        assert_eq!(errors.len(), 3, "{errors:?}");
+    }
+
     #[rstest]
     #[case::valid_update_username_request(Uuid::new_v4(), Some(String::from("test")), None)]
     #[case::valid_update_full_name_request(Uuid::new_v4(), None, Some(String::from("test")))]
@@ -285,6 +397,13 @@ pub(crate) mod test {
         assert!(result.is_ok(), "{:?}", result.unwrap_err());
     }
 
+    #[test]
+    fn update_user_request_should_fail_when_new_password_invalid() {
+        let request = UpdateUserRequest::new(Uuid::new_v4(), None, None).with_new_password("test");
+        let result = UpdateUserRequestValidator::validate(&request);
+        assert!(result.is_err());
+    }
+
     #[rstest]
     #[case::new_username_empty(Uuid::new_v4(), Some(String::new()), None)]
     #[case::new_name_empty(Uuid::new_v4(), None, Some(String::new()))]
@@ -297,4 +416,12 @@ pub(crate) mod test {
         let result = UpdateUserRequestValidator::validate(&request);
         assert!(result.is_err());
     }
+
+    #[rstest]
+    #[case::add(r#"{"add":"load"}"#, RoleChange::Add(RoleName::LoadData))]
+    #[case::revoke(r#"{"revoke":"add-role"}"#, RoleChange::Revoke(RoleName::AddRole))]
+    fn role_change_should_deserialize_when(#[case] json: &str, #[case] expected: RoleChange) {
+        let change: RoleChange = serde_json::from_str(json).unwrap();
+        assert_eq!(change, expected);
+    }
 }
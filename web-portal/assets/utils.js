@@ -201,6 +201,40 @@ document.addEventListener('createToast', (e) => {
     toast.show();
 });
 
+document.addEventListener('htmx:configRequest', (e) => {
+    const csrfToken = document.querySelector('meta[name="csrf-token"]')?.content;
+    if (csrfToken) {
+        e.detail.headers['X-CSRF-Token'] = csrfToken;
+    }
+});
+
+/** Time between attempts to refresh the user session. Sessions are only renewed by the server when near expiry */
+const SESSION_REFRESH_INTERVAL_MS = 5 * 60 * 1000;
+
+window.addEventListener('DOMContentLoaded', () => {
+    if (!document.querySelector('a[href="/logout"]')) {
+        return;
+    }
+    setInterval(async () => {
+        const csrfToken = document.querySelector('meta[name="csrf-token"]')?.content ?? '';
+        const response = await fetch('/api/refresh', {
+            method: 'POST',
+            headers: {'X-CSRF-Token': csrfToken},
+        });
+        if (response.status === 401) {
+            window.location.assign('/login');
+        }
+    }, SESSION_REFRESH_INTERVAL_MS);
+});
+
+htmx.onLoad((element) => {
+    for (const flashToast of element.querySelectorAll('[data-flash-toast]')) {
+        const toast = new Toast(flashToast.dataset.flashToast);
+        toast.show();
+        flashToast.remove();
+    }
+});
+
 /** @type {(element: HTMLElement) => void} */
 window.closeModal = (element) => {
     const container = document.getElementById('modals');
@@ -319,6 +353,107 @@ class Toast {
     }
 }
 
+/** Delay before reconnecting a closed live updates socket. Tables keep polling in the meantime */
+const LIVE_UPDATES_RECONNECT_MS = 30 * 1000;
+
+/**
+ * Apply a live update envelope (`{type, data}`) sent by the portal's `/live-updates` socket.
+ * Workflow run cells tagged with `data-live-workflow-run` have their status/progress replaced and
+ * executor rows tagged with `data-live-executor` are removed once the executor is no longer active.
+ * @type {(update: {type: string, data: Object}) => void}
+ */
+const applyLiveUpdate = (update) => {
+    if (update.type === 'workflow_run') {
+        const {workflow_run_id, status, progress} = update.data;
+        for (const cell of document.querySelectorAll(`[data-live-workflow-run="${workflow_run_id}"]`)) {
+            if (cell.dataset.liveField === 'status') {
+                cell.textContent = status;
+            } else if (cell.dataset.liveField === 'progress') {
+                cell.textContent = progress ?? '-';
+            }
+        }
+    } else if (update.type === 'executor') {
+        const {executor_id, status} = update.data;
+        if (status === 'Active') {
+            return;
+        }
+        for (const cell of document.querySelectorAll(`[data-live-executor="${executor_id}"]`)) {
+            document.getElementById(`workflowRuns${executor_id}`)?.remove();
+            cell.closest('tr')?.remove();
+        }
+    }
+};
+
+/** @type {(url: string) => void} */
+const connectLiveUpdates = (url) => {
+    const protocol = window.location.protocol === 'https:' ? 'wss' : 'ws';
+    const socket = new WebSocket(`${protocol}://${window.location.host}${url}`);
+    socket.addEventListener('message', (e) => {
+        try {
+            applyLiveUpdate(JSON.parse(e.data));
+        } catch (error) {
+            console.warn('Could not apply live update', e.data, error);
+        }
+    });
+    socket.addEventListener('close', () => {
+        setTimeout(() => connectLiveUpdates(url), LIVE_UPDATES_RECONNECT_MS);
+    });
+};
+
+window.addEventListener('DOMContentLoaded', () => {
+    const liveUpdates = document.querySelector('[data-live-updates]');
+    if (!liveUpdates || !('WebSocket' in window)) {
+        return;
+    }
+    connectLiveUpdates(liveUpdates.dataset.liveUpdates);
+});
+
+/** Workflow run statuses that end a progress stream */
+const TERMINAL_WORKFLOW_RUN_STATUSES = ['Complete', 'Failed', 'Canceled'];
+
+/**
+ * Open a progress stream for every element tagged with `data-progress-stream` within the `root`.
+ * The `#status` and `#progress` fields of the workflow run display are updated with each event
+ * and the stream is closed once the run is done or the element is swapped out of the page.
+ * @type {(root: HTMLElement) => void}
+ */
+const attachProgressStreams = (root) => {
+    if (!('EventSource' in window)) {
+        return;
+    }
+    for (const element of root.querySelectorAll('[data-progress-stream]')) {
+        if (element.dataset.progressAttached) {
+            continue;
+        }
+        element.dataset.progressAttached = 'true';
+        const source = new EventSource(element.dataset.progressStream);
+        source.addEventListener('message', (e) => {
+            if (!element.isConnected) {
+                source.close();
+                return;
+            }
+            try {
+                const {status, progress} = JSON.parse(e.data);
+                const statusField = document.getElementById('status');
+                if (statusField) {
+                    statusField.value = status;
+                }
+                const progressField = document.getElementById('progress');
+                if (progressField) {
+                    progressField.value = progress ?? '-';
+                }
+                if (TERMINAL_WORKFLOW_RUN_STATUSES.includes(status)) {
+                    source.close();
+                }
+            } catch (error) {
+                console.warn('Could not apply progress update', e.data, error);
+            }
+        });
+    }
+};
+
+htmx.onLoad(attachProgressStreams);
+
 /** @type {(button: HTMLButtonElement) => void} */
 window.removeJobScheduleEntry = (button) => {
     const row = button.closest('.schedule-entry');
@@ -1,29 +1,68 @@
 use actix_session::Session;
-use actix_web::{web, HttpResponse};
-use common::api::ApiResponseBody;
-use reqwest::Method;
-use serde::{Deserialize, Serialize};
-use users::data::user::User;
+use actix_web::{web, HttpRequest, HttpResponse};
+use common::error::EmError;
+use serde::Deserialize;
+use serde_json::json;
+use users::service::users::ValidateUserRequest;
 
 use crate::{
-    utils, utils::HtmxResponseBuilder, ServerFnError, EM_UID_SESSION_KEY, INTERNAL_SERVICE_ERROR,
-    USERNAME_SESSION_KEY,
+    csrf, endpoints::ServiceEndpoints, return_to, session_expiry, utils,
+    utils::HtmxResponseBuilder, ServerFnError, EM_UID_SESSION_KEY, USERNAME_SESSION_KEY,
 };
 
 pub fn service() -> actix_web::Scope {
     web::scope("/login").route("", web::post().to(login_user))
 }
 
-#[derive(Deserialize, Serialize)]
-pub struct Credentials {
+pub fn refresh_service() -> actix_web::Resource {
+    web::resource("/refresh").route(web::post().to(refresh_session))
+}
+
+#[derive(Deserialize)]
+pub struct LoginFormData {
     username: String,
     password: String,
+    #[serde(default)]
+    csrf_token: String,
+    #[serde(default)]
+    return_to: Option<String>,
 }
 
-pub async fn login_user(session: Session, credentials: web::Form<Credentials>) -> HttpResponse {
-    let user = match login_user_api(credentials.0).await {
+pub async fn login_user(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    form: web::Form<LoginFormData>,
+) -> HttpResponse {
+    let LoginFormData {
+        username,
+        password,
+        csrf_token,
+        return_to,
+    } = form.into_inner();
+    if let Err(error) = csrf::validate_csrf_token(&session, &csrf_token) {
+        log::warn!("{error}");
+        return HtmxResponseBuilder::new()
+            .static_body("Login form has expired. Refresh the page and try again");
+    }
+    let credentials = ValidateUserRequest::new(username, password);
+    let client_ip = req.peer_addr().map(|addr| addr.ip());
+    let user = match endpoints
+        .users_api_client()
+        .validate(&credentials, client_ip)
+        .await
+    {
         Ok(inner) => inner,
-        Err(_) => return HtmxResponseBuilder::new().static_body("Could not login user"),
+        Err(EmError::InvalidUser) => {
+            return HttpResponse::Unauthorized().body("Invalid username or password");
+        }
+        Err(error @ EmError::ServiceUnavailable(_)) => {
+            return ServerFnError::UsersApi(error).to_response();
+        }
+        Err(error) => {
+            log::error!("{error}");
+            return HtmxResponseBuilder::new().static_body("Could not login user");
+        }
     };
     if let Err(error) = session.insert(EM_UID_SESSION_KEY, *user.uid()) {
         log::error!("{error}");
@@ -33,27 +72,23 @@ pub async fn login_user(session: Session, credentials: web::Form<Credentials>) -
         log::error!("{error}");
         return utils::internal_server_error!();
     }
-    HtmxResponseBuilder::location_home()
+    if let Err(error) = session_expiry::start_session_expiry(&session) {
+        log::error!("{error}");
+        return utils::internal_server_error!();
+    }
+    if let Err(error) = csrf::rotate_csrf_token(&session) {
+        log::error!("{error}");
+        return utils::internal_server_error!();
+    }
+    HtmxResponseBuilder::location(return_to::post_login_location(return_to.as_deref()))
 }
 
-async fn login_user_api(credentials: Credentials) -> Result<User, ServerFnError> {
-    let user_response = utils::api_request(
-        "http://127.0.0.1:8001/api/v1/users/validate?f=msgpack",
-        Method::POST,
-        None::<String>,
-        Some(credentials),
-    )
-    .await?;
-    let user = match user_response {
-        ApiResponseBody::Success(inner) => inner,
-        ApiResponseBody::Message(message) => {
-            log::error!("Expected data, got message. {message}");
-            return utils::server_fn_error!("Expected data, got message. {}", message);
-        }
-        ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
-            log::error!("{message}");
-            return utils::server_fn_static_error!(INTERNAL_SERVICE_ERROR);
-        }
-    };
-    Ok(user)
+/// Refresh the user session if it is near expiry, responding with the session's expiry. Expired
+/// or missing user sessions are rejected with a `401 Unauthorized` status.
+async fn refresh_session(session: Session) -> HttpResponse {
+    match session_expiry::refresh_session(&session) {
+        Ok(expires_at) => HttpResponse::Ok().json(json!({ "expires_at": expires_at })),
+        Err(ServerFnError::InvalidUser) => HttpResponse::Unauthorized().finish(),
+        Err(error) => error.to_response(),
+    }
 }
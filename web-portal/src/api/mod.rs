@@ -7,6 +7,7 @@ use actix_web::web;
 pub fn service() -> actix_web::Scope {
     web::scope("/api")
         .service(login::service())
+        .service(login::refresh_service())
         .service(workflow_engine::service())
         .service(users::service())
 }
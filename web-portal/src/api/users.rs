@@ -1,5 +1,5 @@
 use actix_session::Session;
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use common::api::ApiResponseBody;
 use leptos::*;
 use reqwest::Method;
@@ -9,6 +9,7 @@ use uuid::Uuid;
 
 use crate::{
     components::users::{EditUser, UsersTable},
+    endpoints::ServiceEndpoints,
     extract_session_uid, take_if, utils,
     utils::{get_user, HtmxResponseBuilder},
     ServerFnError,
@@ -24,12 +25,16 @@ pub fn service() -> actix_web::Scope {
         )
 }
 
-async fn all_users(session: Session) -> HttpResponse {
+async fn all_users(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
     let Ok(uid) = extract_session_uid(&session) else {
-        return utils::redirect_login_htmx!();
+        return utils::redirect_login_htmx!(req);
     };
 
-    let users = match get_all_users(uid).await {
+    let users = match get_all_users(&endpoints, uid).await {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
@@ -39,9 +44,12 @@ async fn all_users(session: Session) -> HttpResponse {
     })
 }
 
-pub async fn get_all_users(uid: Uuid) -> Result<Vec<User>, ServerFnError> {
+pub async fn get_all_users(
+    endpoints: &ServiceEndpoints,
+    uid: Uuid,
+) -> Result<Vec<User>, ServerFnError> {
     let users_response = utils::api_request(
-        "http://127.0.0.1:8001/api/v1/users?f=msgpack",
+        endpoints.users("users?f=msgpack"),
         Method::GET,
         Some(uid),
         None::<()>,
@@ -59,13 +67,18 @@ pub async fn get_all_users(uid: Uuid) -> Result<Vec<User>, ServerFnError> {
     Ok(users)
 }
 
-async fn edit_user_modal(session: Session, get_uid: web::Path<Uuid>) -> HttpResponse {
+async fn edit_user_modal(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    get_uid: web::Path<Uuid>,
+) -> HttpResponse {
     let get_uid = get_uid.into_inner();
     let Ok(session_uid) = extract_session_uid(&session) else {
-        return utils::redirect_login_htmx!();
+        return utils::redirect_login_htmx!(req);
     };
 
-    let get_user = match get_user(session_uid, Some(get_uid)).await {
+    let get_user = match get_user(&endpoints, session_uid, Some(get_uid)).await {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
@@ -82,14 +95,19 @@ struct UserEditForm {
     modal_id: String,
 }
 
-async fn edit_user(session: Session, form: web::Form<UserEditForm>) -> HttpResponse {
+async fn edit_user(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    form: web::Form<UserEditForm>,
+) -> HttpResponse {
     let UserEditForm {
         username,
         full_name,
         modal_id,
     } = form.into_inner();
     let Ok(session_uid) = extract_session_uid(&session) else {
-        return utils::redirect_login_htmx!();
+        return utils::redirect_login_htmx!(req);
     };
 
     let update_request = UpdateUserRequest::new(
@@ -98,13 +116,13 @@ async fn edit_user(session: Session, form: web::Form<UserEditForm>) -> HttpRespo
         take_if(full_name.clone(), |s| !s.is_empty()),
     );
 
-    if let Err(error) = update_user(session_uid, update_request).await {
+    if let Err(error) = update_user(&endpoints, session_uid, update_request).await {
         log::error!("{error}");
         return HtmxResponseBuilder::modal_error_message("Could not update user");
     }
 
     let toast_message = format!("Edited User: {full_name}");
-    let users = match get_all_users(session_uid).await {
+    let users = match get_all_users(&endpoints, session_uid).await {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
@@ -117,11 +135,12 @@ async fn edit_user(session: Session, form: web::Form<UserEditForm>) -> HttpRespo
 }
 
 async fn update_user(
+    endpoints: &ServiceEndpoints,
     session_uid: Uuid,
     update_request: UpdateUserRequest,
 ) -> Result<(), ServerFnError> {
     let executors_response: ApiResponseBody<()> = utils::api_request(
-        "http://127.0.0.1:8001/api/v1/users?f=msgpack",
+        endpoints.users("users?f=msgpack"),
         Method::PATCH,
         Some(session_uid),
         Some(update_request),
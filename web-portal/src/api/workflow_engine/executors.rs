@@ -1,12 +1,19 @@
 use actix_session::Session;
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use common::api::ApiResponseBody;
 use leptos::*;
 use reqwest::Method;
-use workflow_engine::executor::data::{Executor, ExecutorId};
+use users::data::role::RoleName;
+use workflow_engine::{
+    executor::data::{Executor, ExecutorId},
+    workflow_run::data::ExecutorWorkflowRun,
+};
 
 use crate::{
-    components::workflow_engine::main_page::{ActiveExecutors, ActiveExecutorsTab},
+    components::workflow_engine::main_page::{
+        ActiveExecutors, ActiveExecutorsTab, ExecutorWorkflowRuns,
+    },
+    endpoints::ServiceEndpoints,
     extract_session_uid, utils,
     utils::HtmxResponseBuilder,
     ServerFnError,
@@ -16,20 +23,28 @@ pub fn service() -> actix_web::Scope {
     web::scope("/executors")
         .route("", web::get().to(active_executors))
         .route("/tab", web::get().to(active_executors_tab))
+        .route(
+            "/{executor_id}/workflow-runs",
+            web::get().to(executor_workflow_runs),
+        )
         .route("/clean", web::post().to(clean_executors))
         .route("/cancel/{executor_id}", web::post().to(cancel_executor))
         .route("/shutdown/{executor_id}", web::post().to(shutdown_executor))
 }
 
-async fn active_executors_html(is_tab: bool) -> HttpResponse {
-    active_executors_html_with_toast(is_tab, "").await
+async fn active_executors_html(endpoints: &ServiceEndpoints, is_tab: bool) -> HttpResponse {
+    active_executors_html_with_toast(endpoints, is_tab, "").await
 }
 
-async fn active_executors_html_with_toast<S>(is_tab: bool, toast_message: S) -> HttpResponse
+async fn active_executors_html_with_toast<S>(
+    endpoints: &ServiceEndpoints,
+    is_tab: bool,
+    toast_message: S,
+) -> HttpResponse
 where
     S: AsRef<str>,
 {
-    let executors = match get_active_executors().await {
+    let executors = match get_active_executors(endpoints).await {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
@@ -47,23 +62,52 @@ where
     })
 }
 
-async fn active_executors(session: Session) -> HttpResponse {
+async fn active_executors(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
-    active_executors_html(false).await
+    active_executors_html(&endpoints, false).await
 }
 
-async fn active_executors_tab(session: Session) -> HttpResponse {
+async fn active_executors_tab(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
-    active_executors_html(true).await
+    active_executors_html(&endpoints, true).await
 }
 
-async fn get_active_executors() -> Result<Vec<Executor>, ServerFnError> {
+async fn executor_workflow_runs(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    executor_id: web::Path<ExecutorId>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    let workflow_runs = match get_executor_workflow_runs(&endpoints, executor_id.into_inner()).await
+    {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
+    HtmxResponseBuilder::new().html_chunk(|cx| {
+        view! { cx, <ExecutorWorkflowRuns workflow_runs=workflow_runs/> }
+    })
+}
+
+async fn get_active_executors(
+    endpoints: &ServiceEndpoints,
+) -> Result<Vec<Executor>, ServerFnError> {
     let executors_response = utils::api_request(
-        "http://127.0.0.1:8000/api/v1/executors?f=msgpack",
+        endpoints.workflow_engine("executors?f=msgpack"),
         Method::GET,
         None::<String>,
         None::<()>,
@@ -81,20 +125,70 @@ async fn get_active_executors() -> Result<Vec<Executor>, ServerFnError> {
     Ok(executors)
 }
 
-async fn clean_executors(session: Session) -> HttpResponse {
+/// Fetch the active executors that can accept another workflow run, least loaded first
+pub async fn get_available_executors(
+    endpoints: &ServiceEndpoints,
+) -> Result<Vec<Executor>, ServerFnError> {
+    let executors_response = utils::api_request(
+        endpoints.workflow_engine("executors/available?f=msgpack"),
+        Method::GET,
+        None::<String>,
+        None::<()>,
+    )
+    .await?;
+    let executors = match executors_response {
+        ApiResponseBody::Success(inner) => inner,
+        ApiResponseBody::Message(message) => {
+            return utils::server_fn_error!("Expected data, got message. {}", message)
+        }
+        ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
+            return utils::server_fn_error!(message)
+        }
+    };
+    Ok(executors)
+}
+
+async fn get_executor_workflow_runs(
+    endpoints: &ServiceEndpoints,
+    executor_id: ExecutorId,
+) -> Result<Vec<ExecutorWorkflowRun>, ServerFnError> {
+    let workflow_runs_response = utils::api_request(
+        endpoints.workflow_engine(format!("executors/{executor_id}/workflow-runs?f=msgpack")),
+        Method::GET,
+        None::<String>,
+        None::<()>,
+    )
+    .await?;
+    let workflow_runs = match workflow_runs_response {
+        ApiResponseBody::Success(inner) => inner,
+        ApiResponseBody::Message(message) => {
+            return utils::server_fn_error!("Expected data, got message. {}", message)
+        }
+        ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
+            return utils::server_fn_error!(message)
+        }
+    };
+    Ok(workflow_runs)
+}
+
+async fn clean_executors(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
-    if let Err(error) = post_clean_executors().await {
+    if let Err(error) = post_clean_executors(&endpoints).await {
         return error.to_response();
     }
 
-    active_executors_html_with_toast(false, "Cleaned inactive executors").await
+    active_executors_html_with_toast(&endpoints, false, "Cleaned inactive executors").await
 }
 
-async fn post_clean_executors() -> Result<(), ServerFnError> {
+async fn post_clean_executors(endpoints: &ServiceEndpoints) -> Result<(), ServerFnError> {
     let clean_executors_response = utils::api_request(
-        "http://127.0.0.1:8000/api/v1/executors/clean?f=msgpack",
+        endpoints.workflow_engine("executors/clean?f=msgpack"),
         Method::POST,
         None::<String>,
         None::<()>,
@@ -114,21 +208,36 @@ async fn post_clean_executors() -> Result<(), ServerFnError> {
     }
 }
 
-async fn cancel_executor(session: Session, executor_id: web::Path<ExecutorId>) -> HttpResponse {
-    if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
-    }
-    let executor_id = executor_id.into_inner();
-    if let Err(error) = post_cancel_executor(executor_id).await {
+async fn cancel_executor(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    executor_id: web::Path<ExecutorId>,
+) -> HttpResponse {
+    let Ok(uid) = extract_session_uid(&session) else {
+        return HtmxResponseBuilder::location_login_return(&req);
+    };
+    if let Err(error) =
+        utils::require_privilege(&endpoints, uid, RoleName::ManageWorkflowEngine).await
+    {
         return error.to_response();
     }
+    let toast_message = match post_cancel_executor(&endpoints, executor_id.into_inner()).await {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
 
-    active_executors_html_with_toast(false, format!("Canceled Executor ID: {executor_id}")).await
+    active_executors_html_with_toast(&endpoints, false, toast_message).await
 }
 
-async fn post_cancel_executor(executor_id: ExecutorId) -> Result<(), ServerFnError> {
+/// Request the cancel of the executor specified by `executor_id`. Returns the message to show the
+/// user, which states when no signal was sent because the executor was no longer active.
+async fn post_cancel_executor(
+    endpoints: &ServiceEndpoints,
+    executor_id: ExecutorId,
+) -> Result<String, ServerFnError> {
     let clean_executors_response: ApiResponseBody<Executor> = utils::api_request(
-        format!("http://127.0.0.1:8000/api/v1/executors/cancel/{executor_id}?f=msgpack"),
+        endpoints.workflow_engine(format!("executors/cancel/{executor_id}?f=msgpack")),
         Method::POST,
         None::<String>,
         None::<()>,
@@ -137,10 +246,11 @@ async fn post_cancel_executor(executor_id: ExecutorId) -> Result<(), ServerFnErr
     match clean_executors_response {
         ApiResponseBody::Success(executor) => {
             log::info!("Canceled executor: {}", executor.executor_id);
-            Ok(())
+            Ok(format!("Canceled Executor ID: {executor_id}"))
         }
         ApiResponseBody::Message(message) => {
-            utils::server_fn_error!("Expected data, got message. {}", message)
+            log::info!("{message}");
+            Ok(message)
         }
         ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
             utils::server_fn_error!(message)
@@ -148,21 +258,32 @@ async fn post_cancel_executor(executor_id: ExecutorId) -> Result<(), ServerFnErr
     }
 }
 
-async fn shutdown_executor(session: Session, executor_id: web::Path<ExecutorId>) -> HttpResponse {
+async fn shutdown_executor(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    executor_id: web::Path<ExecutorId>,
+) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
-    }
-    let executor_id = executor_id.into_inner();
-    if let Err(error) = post_shutdown_executor(executor_id).await {
-        return error.to_response();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
+    let toast_message = match post_shutdown_executor(&endpoints, executor_id.into_inner()).await {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
 
-    active_executors_html_with_toast(false, format!("Shutdown Executor ID: {executor_id}")).await
+    active_executors_html_with_toast(&endpoints, false, toast_message).await
 }
 
-async fn post_shutdown_executor(executor_id: ExecutorId) -> Result<(), ServerFnError> {
+/// Request the graceful shutdown of the executor specified by `executor_id`. Returns the message
+/// to show the user, which states when no signal was sent because the executor was no longer
+/// active.
+async fn post_shutdown_executor(
+    endpoints: &ServiceEndpoints,
+    executor_id: ExecutorId,
+) -> Result<String, ServerFnError> {
     let clean_executors_response: ApiResponseBody<Executor> = utils::api_request(
-        format!("http://127.0.0.1:8000/api/v1/executors/shutdown/{executor_id}?f=msgpack"),
+        endpoints.workflow_engine(format!("executors/shutdown/{executor_id}?f=msgpack")),
         Method::POST,
         None::<String>,
         None::<()>,
@@ -170,11 +291,12 @@ async fn post_shutdown_executor(executor_id: ExecutorId) -> Result<(), ServerFnE
     .await?;
     match clean_executors_response {
         ApiResponseBody::Success(executor) => {
-            log::info!("Canceled executor: {}", executor.executor_id);
-            Ok(())
+            log::info!("Shutdown executor: {}", executor.executor_id);
+            Ok(format!("Shutdown Executor ID: {executor_id}"))
         }
         ApiResponseBody::Message(message) => {
-            utils::server_fn_error!("Expected data, got message. {}", message)
+            log::info!("{message}");
+            Ok(message)
         }
         ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
             utils::server_fn_error!(message)
@@ -1,7 +1,7 @@
 use std::{str::FromStr, string::FromUtf8Error};
 
 use actix_session::Session;
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::{NaiveDateTime, NaiveTime};
 use common::api::ApiResponseBody;
 use leptos::*;
@@ -9,16 +9,20 @@ use reqwest::Method;
 use serde::Deserialize;
 use thiserror::Error;
 use workflow_engine::{
-    job::data::{Job, JobId, JobRequest, JobType, JobTypeEnum, ScheduleEntry},
+    job::data::{
+        parse_cron_expression, Job, JobId, JobRequest, JobType, JobTypeEnum, ScheduleEntry,
+        MICROSECONDS_PER_MINUTE,
+    },
     workflow::data::WorkflowId,
 };
 
 use crate::{
     api::workflow_engine::workflows::get_workflows,
     components::workflow_engine::main_page::{
-        JobScheduleEntry, Jobs, JobsTab, NewIntervalJob, NewJobModal, NewJobNextRun,
+        JobScheduleEntry, Jobs, JobsTab, NewCronJob, NewIntervalJob, NewJobModal, NewJobNextRun,
         NewScheduledJob,
     },
+    endpoints::ServiceEndpoints,
     error_if, extract_session_uid, utils,
     utils::HtmxResponseBuilder,
     ServerFnError,
@@ -36,18 +40,16 @@ pub fn service() -> actix_web::Scope {
         .route("/next-run", web::get().to(next_run_input))
         .route("/job-type", web::get().to(job_type_container))
         .route("/job-schedule-entry", web::get().to(job_schedule_entry))
+        .route("/run-now/{job_id}", web::post().to(run_job_now))
 }
 
 async fn jobs_html_with_extras(
-    session: Session,
+    endpoints: &ServiceEndpoints,
     is_tab: bool,
     modal_id: Option<String>,
     toast_message: Option<String>,
 ) -> HttpResponse {
-    if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
-    }
-    let jobs = match get_jobs().await {
+    let jobs = match get_jobs(endpoints).await {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
@@ -68,21 +70,37 @@ async fn jobs_html_with_extras(
     })
 }
 
-async fn jobs_html(session: Session, is_tab: bool) -> HttpResponse {
-    jobs_html_with_extras(session, is_tab, None, None).await
+async fn jobs_html(
+    req: &HttpRequest,
+    session: Session,
+    endpoints: &ServiceEndpoints,
+    is_tab: bool,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(req);
+    }
+    jobs_html_with_extras(endpoints, is_tab, None, None).await
 }
 
-async fn jobs(session: Session) -> HttpResponse {
-    jobs_html(session, false).await
+async fn jobs(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
+    jobs_html(&req, session, &endpoints, false).await
 }
 
-async fn jobs_tab(session: Session) -> HttpResponse {
-    jobs_html(session, true).await
+async fn jobs_tab(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
+    jobs_html(&req, session, &endpoints, true).await
 }
 
-async fn get_jobs() -> Result<Vec<Job>, ServerFnError> {
+async fn get_jobs(endpoints: &ServiceEndpoints) -> Result<Vec<Job>, ServerFnError> {
     let jobs_response = utils::api_request(
-        "http://127.0.0.1:8000/api/v1/jobs?f=msgpack",
+        endpoints.workflow_engine("jobs?f=msgpack"),
         Method::GET,
         None::<String>,
         None::<()>,
@@ -100,12 +118,58 @@ async fn get_jobs() -> Result<Vec<Job>, ServerFnError> {
     Ok(jobs)
 }
 
-async fn create_job_modal(session: Session) -> HttpResponse {
+async fn run_job_now(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    job_id: web::Path<JobId>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    let job = match post_run_job_now(&endpoints, job_id.into_inner()).await {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
+    let message = format!(
+        "Started workflow run for job {}. The job's schedule is unchanged",
+        job.job_id
+    );
+    jobs_html_with_extras(&endpoints, false, None, Some(message)).await
+}
+
+async fn post_run_job_now(
+    endpoints: &ServiceEndpoints,
+    job_id: JobId,
+) -> Result<Job, ServerFnError> {
+    let run_job_response = utils::api_request(
+        endpoints.workflow_engine(format!("jobs/{job_id}/run-now?f=msgpack")),
+        Method::POST,
+        None::<String>,
+        None::<()>,
+    )
+    .await?;
+    match run_job_response {
+        ApiResponseBody::Success(job) => Ok(job),
+        ApiResponseBody::Message(message) => {
+            utils::server_fn_error!("Expected data, got message. {}", message)
+        }
+        ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
+            utils::server_fn_error!(message)
+        }
+    }
+}
+
+async fn create_job_modal(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
 
-    let workflows = match get_workflows().await {
+    let workflows = match get_workflows(&endpoints).await {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
@@ -139,6 +203,7 @@ async fn job_type_container(query: web::Query<JobTypeQuery>) -> HttpResponse {
     HtmxResponseBuilder::new().html_chunk(move |cx| match query.job_type {
         JobTypeEnum::Scheduled => view! { cx, <NewScheduledJob/> },
         JobTypeEnum::Interval => view! { cx, <NewIntervalJob/> },
+        JobTypeEnum::Cron => view! { cx, <NewCronJob/> },
     })
 }
 
@@ -169,6 +234,8 @@ enum CreateJobBuilderError {
     NegativeIntervalValue,
     #[error("Cannot provide an interval of zero")]
     ZeroInterval,
+    #[error("{0}")]
+    InvalidCronExpression(String),
     #[error("Could not deserialize payload, `{payload}`. Error: {error}")]
     Deserialize {
         payload: String,
@@ -178,6 +245,8 @@ enum CreateJobBuilderError {
     Parse(String),
     #[error("{0}")]
     RuleBroken(String),
+    #[error(transparent)]
+    InvalidId(#[from] common::error::EmError),
 }
 
 impl From<(&'static str, &str, &'static str)> for CreateJobBuilderError {
@@ -201,6 +270,7 @@ struct CreateJobBuilder {
     months: Option<i32>,
     days: Option<i32>,
     minutes: Option<i32>,
+    cron_expression: Option<String>,
     modal_id: Option<String>,
 }
 
@@ -269,6 +339,16 @@ impl CreateJobBuilder {
         self.minutes = Some(minutes);
     }
 
+    fn cron_expression(&mut self, cron_expression: String) {
+        if self.cron_expression.is_some() {
+            log::warn!(
+                "Found duplicate cron_expression in create_job request, `{cron_expression}`"
+            );
+            return;
+        }
+        self.cron_expression = Some(cron_expression);
+    }
+
     fn modal_id(&mut self, modal_id: String) {
         if self.modal_id.is_some() {
             log::warn!("Found duplicate modal_id in create_job request, `{modal_id}`");
@@ -306,7 +386,7 @@ impl CreateJobBuilder {
                     .ok_or(CreateJobBuilderError::MissingField("days"))?;
                 let minutes = self
                     .minutes
-                    .map(|m| (m as i64) * 60 * 1000 * 1000)
+                    .map(|m| i64::from(m) * MICROSECONDS_PER_MINUTE)
                     .ok_or(CreateJobBuilderError::MissingField("minutes"))?;
                 if months < 0 || days < 0 || minutes < 0 {
                     return Err(CreateJobBuilderError::NegativeIntervalValue);
@@ -316,6 +396,18 @@ impl CreateJobBuilder {
                 }
                 JobType::new_interval(months, days, minutes)
             }
+            JobTypeEnum::Cron => {
+                let expression = self
+                    .cron_expression
+                    .ok_or(CreateJobBuilderError::MissingField("cron_expression"))?;
+                let expression = expression.trim().to_owned();
+                if let Err(error) = parse_cron_expression(&expression) {
+                    return Err(CreateJobBuilderError::InvalidCronExpression(
+                        error.to_string(),
+                    ));
+                }
+                JobType::new_cron(expression)
+            }
         };
         let maintainer = self
             .maintainer
@@ -362,10 +454,7 @@ impl FromStr for CreateJob {
             };
             match key {
                 "workflow_id" => {
-                    let workflow_id = value
-                        .parse::<i64>()
-                        .map_err(|_| ("workflow_id", value, "WorkflowId"))?;
-                    builder.workflow_id(workflow_id.into());
+                    builder.workflow_id(WorkflowId::from_str(value)?);
                 }
                 "maintainer" => builder.maintainer(value.to_owned()),
                 "job_type" => builder.job_type(
@@ -407,6 +496,7 @@ impl FromStr for CreateJob {
                     }
                     builder.minutes(value.parse().map_err(|_| ("minutes", value, "i32"))?)
                 }
+                "cron_expression" => builder.cron_expression(value.replace('+', " ")),
                 "modal_id" => builder.modal_id(value.to_owned()),
                 _ => continue,
             }
@@ -415,9 +505,14 @@ impl FromStr for CreateJob {
     }
 }
 
-async fn create_job(session: Session, payload: String) -> HttpResponse {
+async fn create_job(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    payload: String,
+) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
 
     let CreateJob {
@@ -432,17 +527,20 @@ async fn create_job(session: Session, payload: String) -> HttpResponse {
     };
 
     let job_request = JobRequest::new(workflow_id, maintainer, job_type, next_run);
-    let toast_message = match post_create_job(job_request).await {
+    let toast_message = match post_create_job(&endpoints, job_request).await {
         Ok(job_id) => format!("Created new job, ID: {job_id}"),
         Err(error) => return error.to_response(),
     };
 
-    jobs_html_with_extras(session, false, Some(modal_id), Some(toast_message)).await
+    jobs_html_with_extras(&endpoints, false, Some(modal_id), Some(toast_message)).await
 }
 
-async fn post_create_job(job_request: JobRequest) -> Result<JobId, ServerFnError> {
+async fn post_create_job(
+    endpoints: &ServiceEndpoints,
+    job_request: JobRequest,
+) -> Result<JobId, ServerFnError> {
     let clean_executors_response: ApiResponseBody<Job> = utils::api_request(
-        "http://127.0.0.1:8000/api/v1/jobs?f=msgpack",
+        endpoints.workflow_engine("jobs?f=msgpack"),
         Method::POST,
         None::<String>,
         Some(job_request),
@@ -1,42 +1,68 @@
 use actix_session::Session;
-use actix_web::{web, HttpResponse};
-use common::api::ApiResponseBody;
+use actix_web::{web, HttpRequest, HttpResponse};
+use common::api::{http_client::streaming_client, ApiResponseBody};
+use futures::StreamExt;
 use leptos::*;
 use reqwest::Method;
-use workflow_engine::workflow_run::data::{WorkflowRun, WorkflowRunId};
+use serde::Deserialize;
+use workflow_engine::workflow_run::data::{TaskQueueRequest, WorkflowRun, WorkflowRunId};
 
 use crate::{
-    components::workflow_engine::workflow_run_page::WorkflowRunDisplay,
+    components::workflow_engine::workflow_run_page::{WorkflowRunDisplay, WorkflowRunTaskTable},
+    endpoints::ServiceEndpoints,
     extract_session_uid,
     utils::{self, HtmxResponseBuilder},
     ServerFnError,
 };
 
 pub fn service() -> actix_web::Scope {
-    web::scope("/workflow-run").service(
-        web::resource("/{workflow_run_id}")
-            .route(web::post().to(enter_workflow_run))
-            .route(web::get().to(workflow_run)),
-    )
+    web::scope("/workflow-run")
+        .service(
+            web::resource("/{workflow_run_id}")
+                .route(web::post().to(enter_workflow_run))
+                .route(web::get().to(workflow_run)),
+        )
+        .route(
+            "/{workflow_run_id}/progress",
+            web::get().to(workflow_run_progress),
+        )
+        .route(
+            "/{workflow_run_id}/retry/{task_order}",
+            web::post().to(retry_task),
+        )
+        .route(
+            "/{workflow_run_id}/skip/{task_order}",
+            web::post().to(skip_task),
+        )
 }
 
 async fn enter_workflow_run(
+    req: HttpRequest,
     session: Session,
     workflow_run_id: web::Path<WorkflowRunId>,
 ) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
     let workflow_run_id = workflow_run_id.into_inner();
-    HtmxResponseBuilder::location(format!("/workflow-engine/workflow-run/{}", workflow_run_id))
+    HtmxResponseBuilder::redirect_with_toast(
+        &session,
+        format!("/workflow-engine/workflow-run/{}", workflow_run_id),
+        format!("Entered Workflow Run ID: {workflow_run_id}"),
+    )
 }
 
-async fn workflow_run(session: Session, workflow_run_id: web::Path<WorkflowRunId>) -> HttpResponse {
+async fn workflow_run(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    workflow_run_id: web::Path<WorkflowRunId>,
+) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
     let workflow_run_id = workflow_run_id.into_inner();
-    let workflow_run = match get_workflow_run(workflow_run_id).await {
+    let workflow_run = match get_workflow_run(&endpoints, workflow_run_id).await {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
@@ -46,11 +72,47 @@ async fn workflow_run(session: Session, workflow_run_id: web::Path<WorkflowRunId
     })
 }
 
+async fn workflow_run_progress(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    workflow_run_id: web::Path<WorkflowRunId>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    let workflow_run_id = workflow_run_id.into_inner();
+    // The stream stays open until the run is done so the total timeout of the shared client
+    // cannot be applied
+    let response = match streaming_client()
+        .get(endpoints.workflow_engine(format!(
+            "workflow-runs/progress/{workflow_run_id}?f=msgpack"
+        )))
+        .send()
+        .await
+    {
+        Ok(inner) => inner,
+        Err(error) => return ServerFnError::ApiRequest(error).to_response(),
+    };
+    if !response.status().is_success() {
+        let status = response.status();
+        return ServerFnError::ApiResponse(status, response.text().await.ok()).to_response();
+    }
+    let events = response
+        .bytes_stream()
+        .map(|chunk| chunk.map_err(actix_web::error::ErrorBadGateway));
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
 pub async fn get_workflow_run(
+    endpoints: &ServiceEndpoints,
     workflow_run_id: WorkflowRunId,
 ) -> Result<WorkflowRun, ServerFnError> {
     let clean_executors_response: ApiResponseBody<WorkflowRun> = utils::api_request(
-        format!("http://127.0.0.1:8000/api/v1/workflow-runs/{workflow_run_id}?f=msgpack"),
+        endpoints.workflow_engine(format!("workflow-runs/{workflow_run_id}?f=msgpack")),
         Method::GET,
         None::<String>,
         None::<()>,
@@ -66,3 +128,111 @@ pub async fn get_workflow_run(
         }
     }
 }
+
+#[derive(Deserialize)]
+struct TaskQueuePath {
+    workflow_run_id: WorkflowRunId,
+    task_order: i32,
+}
+
+async fn workflow_run_tasks_html<S>(
+    endpoints: &ServiceEndpoints,
+    workflow_run_id: WorkflowRunId,
+    toast_message: S,
+) -> HttpResponse
+where
+    S: AsRef<str>,
+{
+    let workflow_run = match get_workflow_run(endpoints, workflow_run_id).await {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
+
+    HtmxResponseBuilder::new()
+        .add_create_toast_event(toast_message.as_ref())
+        .html_chunk(move |cx| {
+            view! { cx,
+                <WorkflowRunTaskTable
+                    workflow_run_id=workflow_run.workflow_run_id
+                    tasks=workflow_run.tasks/>
+            }
+        })
+}
+
+async fn retry_task(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    path: web::Path<TaskQueuePath>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    let TaskQueuePath {
+        workflow_run_id,
+        task_order,
+    } = path.into_inner();
+    let request = TaskQueueRequest::new(workflow_run_id, task_order);
+    if let Err(error) = post_task_queue_action(&endpoints, "retry", request).await {
+        return error.to_response();
+    }
+
+    workflow_run_tasks_html(
+        &endpoints,
+        workflow_run_id,
+        format!("Retrying task {task_order}"),
+    )
+    .await
+}
+
+async fn skip_task(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    path: web::Path<TaskQueuePath>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    let TaskQueuePath {
+        workflow_run_id,
+        task_order,
+    } = path.into_inner();
+    let request = TaskQueueRequest::new(workflow_run_id, task_order);
+    if let Err(error) = post_task_queue_action(&endpoints, "complete", request).await {
+        return error.to_response();
+    }
+
+    workflow_run_tasks_html(
+        &endpoints,
+        workflow_run_id,
+        format!("Skipped task {task_order}"),
+    )
+    .await
+}
+
+async fn post_task_queue_action(
+    endpoints: &ServiceEndpoints,
+    action: &'static str,
+    request: TaskQueueRequest,
+) -> Result<(), ServerFnError> {
+    let task_queue_response: ApiResponseBody<()> = utils::api_request(
+        endpoints.workflow_engine(format!("task-queue/{action}?f=msgpack")),
+        Method::POST,
+        None::<String>,
+        Some(request),
+    )
+    .await?;
+    match task_queue_response {
+        ApiResponseBody::Success(_) => {
+            utils::server_fn_error!("Expected message, got data")
+        }
+        ApiResponseBody::Message(message) => {
+            log::info!("{message}");
+            Ok(())
+        }
+        ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
+            utils::server_fn_error!(message)
+        }
+    }
+}
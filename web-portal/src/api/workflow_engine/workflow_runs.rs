@@ -1,32 +1,60 @@
 use actix_session::Session;
-use actix_web::{web, HttpResponse};
-use common::api::ApiResponseBody;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use common::api::{sort::Sort, ApiResponseBody};
 use leptos::*;
 use reqwest::Method;
 use serde::Deserialize;
+use users::data::role::RoleName;
 use workflow_engine::{
+    executor::data::ExecutorId,
     workflow::data::WorkflowId,
-    workflow_run::data::{WorkflowRun, WorkflowRunId},
+    workflow_run::data::{
+        WorkflowRun, WorkflowRunCancelRequest, WorkflowRunHistory, WorkflowRunId,
+        WorkflowRunSummary,
+    },
 };
 
 use crate::{
-    api::workflow_engine::workflows::get_workflows,
+    api::workflow_engine::{
+        executors::get_available_executors, workflow_run::get_workflow_run,
+        workflows::get_workflows,
+    },
     components::workflow_engine::main_page::{
-        ActiveWorkflowRuns, ActiveWorkflowRunsTab, NewWorkflowRunModal,
+        ActiveWorkflowRuns, ActiveWorkflowRunsTab, NewWorkflowRunModal, ScheduleWorkflowRunModal,
+        WorkflowRunHistoryTab, WorkflowRunTasks, WorkflowRunsHistory,
     },
+    endpoints::ServiceEndpoints,
     extract_session_uid,
     utils::{self, HtmxResponseBuilder},
     ServerFnError,
 };
 
+/// Header sent by htmx containing the user's response to an `hx-prompt`
+const HX_PROMPT_HEADER: &str = "HX-Prompt";
+
 pub fn service() -> actix_web::Scope {
     web::scope("/workflow-runs")
         .route("", web::get().to(active_workflow_runs))
         .route("/tab", web::get().to(active_workflow_runs_tab))
+        .route("/history", web::get().to(workflow_runs_history))
+        .route("/history/tab", web::get().to(workflow_runs_history_tab))
+        .route(
+            "/tasks/{workflow_run_id}",
+            web::get().to(workflow_run_tasks),
+        )
         .route(
             "/schedule/{workflow_run_id}",
             web::post().to(schedule_workflow_run),
         )
+        .route(
+            "/schedule-modal/{workflow_run_id}",
+            web::post().to(schedule_workflow_run_modal),
+        )
+        .route(
+            "/schedule/{workflow_run_id}/executor",
+            web::post().to(schedule_workflow_run_on_executor),
+        )
         .route(
             "/cancel/{workflow_run_id}",
             web::post().to(cancel_workflow_run),
@@ -35,12 +63,20 @@ pub fn service() -> actix_web::Scope {
             "/restart/{workflow_run_id}",
             web::post().to(restart_workflow_run),
         )
+        .route(
+            "/pause/{workflow_run_id}",
+            web::post().to(pause_workflow_run),
+        )
+        .route(
+            "/resume/{workflow_run_id}",
+            web::post().to(resume_workflow_run),
+        )
         .route("/init-modal", web::post().to(new_workflow_run_modal))
         .route("/init", web::post().to(new_workflow_run))
 }
 
-async fn active_workflow_runs_html(is_tab: bool) -> HttpResponse {
-    let workflow_runs = match get_active_workflow_runs().await {
+async fn active_workflow_runs_html(endpoints: &ServiceEndpoints, is_tab: bool) -> HttpResponse {
+    let workflow_runs = match get_active_workflow_runs(endpoints).await {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
@@ -53,23 +89,33 @@ async fn active_workflow_runs_html(is_tab: bool) -> HttpResponse {
     })
 }
 
-async fn active_workflow_runs(session: Session) -> HttpResponse {
+async fn active_workflow_runs(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
-    active_workflow_runs_html(false).await
+    active_workflow_runs_html(&endpoints, false).await
 }
 
-async fn active_workflow_runs_tab(session: Session) -> HttpResponse {
+async fn active_workflow_runs_tab(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
-    active_workflow_runs_html(true).await
+    active_workflow_runs_html(&endpoints, true).await
 }
 
-async fn get_active_workflow_runs() -> Result<Vec<WorkflowRun>, ServerFnError> {
+async fn get_active_workflow_runs(
+    endpoints: &ServiceEndpoints,
+) -> Result<Vec<WorkflowRunSummary>, ServerFnError> {
     let workflow_runs_response = utils::api_request(
-        "http://127.0.0.1:8000/api/v1/workflow-runs?f=msgpack",
+        endpoints.workflow_engine("workflow-runs/summary?f=msgpack"),
         Method::GET,
         None::<String>,
         None::<()>,
@@ -87,23 +133,126 @@ async fn get_active_workflow_runs() -> Result<Vec<WorkflowRun>, ServerFnError> {
     Ok(executors)
 }
 
+/// Task rows of a single workflow run, fetched when the details of an active workflow run row are
+/// first expanded
+async fn workflow_run_tasks(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    workflow_run_id: web::Path<WorkflowRunId>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    let workflow_run = match get_workflow_run(&endpoints, workflow_run_id.into_inner()).await {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
+    HtmxResponseBuilder::new().html_chunk(|cx| {
+        view! { cx, <WorkflowRunTasks tasks=workflow_run.tasks/> }
+    })
+}
+
+/// Number of days of workflow run history shown in the portal
+const HISTORY_DAYS: i64 = 7;
+
+async fn workflow_runs_history_html(
+    endpoints: &ServiceEndpoints,
+    is_tab: bool,
+    sort: Sort,
+) -> HttpResponse {
+    let workflow_runs = match get_workflow_runs_history(endpoints, &sort).await {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
+    HtmxResponseBuilder::new().html_chunk(move |cx| {
+        if is_tab {
+            view! { cx, <WorkflowRunHistoryTab workflow_runs=workflow_runs sort=sort /> }
+        } else {
+            view! { cx, <WorkflowRunsHistory workflow_runs=workflow_runs sort=sort /> }
+        }
+    })
+}
+
+async fn workflow_runs_history(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    sort: web::Query<Sort>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    workflow_runs_history_html(&endpoints, false, sort.into_inner()).await
+}
+
+async fn workflow_runs_history_tab(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    workflow_runs_history_html(&endpoints, true, Sort::default()).await
+}
+
+async fn get_workflow_runs_history(
+    endpoints: &ServiceEndpoints,
+    sort: &Sort,
+) -> Result<Vec<WorkflowRunHistory>, ServerFnError> {
+    let started_before = Utc::now().naive_utc();
+    let started_after = started_before - Duration::days(HISTORY_DAYS);
+    let workflow_runs_response = utils::api_request(
+        endpoints.workflow_engine(format!(
+            "workflow-runs/history?f=msgpack&started_after={}&started_before={}&column={}&\
+             ascending={}",
+            started_after.format("%Y-%m-%dT%H:%M:%S"),
+            started_before.format("%Y-%m-%dT%H:%M:%S"),
+            urlencoding::encode(&sort.column),
+            sort.ascending,
+        )),
+        Method::GET,
+        None::<String>,
+        None::<()>,
+    )
+    .await?;
+    let workflow_runs = match workflow_runs_response {
+        ApiResponseBody::Success(inner) => inner,
+        ApiResponseBody::Message(message) => {
+            return utils::server_fn_error!("Expected data, got message. {}", message)
+        }
+        ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
+            return utils::server_fn_error!(message)
+        }
+    };
+    Ok(workflow_runs)
+}
+
 async fn schedule_workflow_run(
+    req: HttpRequest,
     session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
     workflow_run_id: web::Path<WorkflowRunId>,
 ) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
-    if let Err(error) = post_schedule_workflow_run(workflow_run_id.into_inner()).await {
+    if let Err(error) = post_schedule_workflow_run(&endpoints, workflow_run_id.into_inner()).await {
         return error.to_response();
     }
 
-    active_workflow_runs_html(false).await
+    active_workflow_runs_html(&endpoints, false).await
 }
 
-async fn post_schedule_workflow_run(workflow_run_id: WorkflowRunId) -> Result<(), ServerFnError> {
+async fn post_schedule_workflow_run(
+    endpoints: &ServiceEndpoints,
+    workflow_run_id: WorkflowRunId,
+) -> Result<(), ServerFnError> {
     let schedule_workflow_run_response: ApiResponseBody<WorkflowRun> = utils::api_request(
-        format!("http://127.0.0.1:8000/api/v1/workflow-runs/schedule/{workflow_run_id}?f=msgpack"),
+        endpoints.workflow_engine(format!(
+            "workflow-runs/schedule/{workflow_run_id}?f=msgpack"
+        )),
         Method::POST,
         None::<String>,
         None::<()>,
@@ -123,28 +272,143 @@ async fn post_schedule_workflow_run(workflow_run_id: WorkflowRunId) -> Result<()
     }
 }
 
-async fn cancel_workflow_run(
+async fn schedule_workflow_run_modal(
+    req: HttpRequest,
     session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
     workflow_run_id: web::Path<WorkflowRunId>,
 ) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
-    if let Err(error) = post_cancel_workflow_run(workflow_run_id.into_inner()).await {
+    let workflow_run_id = workflow_run_id.into_inner();
+    let executors = match get_available_executors(&endpoints).await {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
+    HtmxResponseBuilder::new().html_chunk(move |cx| {
+        view! { cx,
+            <ScheduleWorkflowRunModal workflow_run_id=workflow_run_id executors=executors/>
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct ScheduleOnExecutorForm {
+    executor_id: ExecutorId,
+    modal_id: String,
+}
+
+async fn schedule_workflow_run_on_executor(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    workflow_run_id: web::Path<WorkflowRunId>,
+    form: web::Form<ScheduleOnExecutorForm>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    let workflow_run_id = workflow_run_id.into_inner();
+    let ScheduleOnExecutorForm {
+        executor_id,
+        modal_id,
+    } = form.into_inner();
+
+    if let Err(error) =
+        post_schedule_workflow_run_on_executor(&endpoints, workflow_run_id, executor_id).await
+    {
         return error.to_response();
     }
 
-    active_workflow_runs_html(false).await
+    let workflow_runs = match get_active_workflow_runs(&endpoints).await {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
+    HtmxResponseBuilder::new()
+        .add_close_modal_event(modal_id)
+        .add_create_toast_event(format!(
+            "Scheduled Workflow Run {workflow_run_id} on Executor {executor_id}"
+        ))
+        .html_chunk(move |cx| {
+            view! { cx, <ActiveWorkflowRuns workflow_runs=workflow_runs /> }
+        })
 }
 
-async fn post_cancel_workflow_run(workflow_run_id: WorkflowRunId) -> Result<(), ServerFnError> {
-    let cancel_workflow_run_response: ApiResponseBody<WorkflowRun> = utils::api_request(
-        format!("http://127.0.0.1:8000/api/v1/workflow-runs/cancel/{workflow_run_id}?f=msgpack"),
+async fn post_schedule_workflow_run_on_executor(
+    endpoints: &ServiceEndpoints,
+    workflow_run_id: WorkflowRunId,
+    executor_id: ExecutorId,
+) -> Result<(), ServerFnError> {
+    let schedule_workflow_run_response: ApiResponseBody<WorkflowRun> = utils::api_request(
+        endpoints.workflow_engine(format!(
+            "workflow-runs/schedule/{workflow_run_id}/executor/{executor_id}?f=msgpack"
+        )),
         Method::POST,
         None::<String>,
         None::<()>,
     )
     .await?;
+    match schedule_workflow_run_response {
+        ApiResponseBody::Success(workflow_run) => {
+            log::info!(
+                "Scheduled workflow run {} on executor {}",
+                workflow_run.workflow_run_id,
+                executor_id
+            );
+            Ok(())
+        }
+        ApiResponseBody::Message(message) => {
+            utils::server_fn_error!("Expected data, got message. {}", message)
+        }
+        ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
+            utils::server_fn_error!(message)
+        }
+    }
+}
+
+async fn cancel_workflow_run(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    workflow_run_id: web::Path<WorkflowRunId>,
+) -> HttpResponse {
+    let Ok(uid) = extract_session_uid(&session) else {
+        return HtmxResponseBuilder::location_login_return(&req);
+    };
+    if let Err(error) =
+        utils::require_privilege(&endpoints, uid, RoleName::ManageWorkflowEngine).await
+    {
+        return error.to_response();
+    }
+    let reason = req
+        .headers()
+        .get(HX_PROMPT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    if let Err(error) =
+        post_cancel_workflow_run(&endpoints, workflow_run_id.into_inner(), reason).await
+    {
+        return error.to_response();
+    }
+
+    active_workflow_runs_html(&endpoints, false).await
+}
+
+async fn post_cancel_workflow_run(
+    endpoints: &ServiceEndpoints,
+    workflow_run_id: WorkflowRunId,
+    reason: Option<String>,
+) -> Result<(), ServerFnError> {
+    let cancel_workflow_run_response: ApiResponseBody<WorkflowRun> = utils::api_request(
+        endpoints.workflow_engine(format!(
+            "workflow-runs/cancel/{workflow_run_id}/reason?f=msgpack"
+        )),
+        Method::POST,
+        None::<String>,
+        Some(WorkflowRunCancelRequest::new(reason)),
+    )
+    .await?;
     match cancel_workflow_run_response {
         ApiResponseBody::Success(workflow_run) => {
             log::info!("Canceled workflow run: {}", workflow_run.workflow_run_id);
@@ -160,22 +424,27 @@ async fn post_cancel_workflow_run(workflow_run_id: WorkflowRunId) -> Result<(),
 }
 
 async fn restart_workflow_run(
+    req: HttpRequest,
     session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
     workflow_run_id: web::Path<WorkflowRunId>,
 ) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
-    if let Err(error) = post_restart_workflow_run(workflow_run_id.into_inner()).await {
+    if let Err(error) = post_restart_workflow_run(&endpoints, workflow_run_id.into_inner()).await {
         return error.to_response();
     }
 
-    active_workflow_runs_html(false).await
+    active_workflow_runs_html(&endpoints, false).await
 }
 
-async fn post_restart_workflow_run(workflow_run_id: WorkflowRunId) -> Result<(), ServerFnError> {
+async fn post_restart_workflow_run(
+    endpoints: &ServiceEndpoints,
+    workflow_run_id: WorkflowRunId,
+) -> Result<(), ServerFnError> {
     let restart_workflow_run_response: ApiResponseBody<WorkflowRun> = utils::api_request(
-        format!("http://127.0.0.1:8000/api/v1/workflow-runs/restart/{workflow_run_id}?f=msgpack"),
+        endpoints.workflow_engine(format!("workflow-runs/restart/{workflow_run_id}?f=msgpack")),
         Method::POST,
         None::<String>,
         None::<()>,
@@ -195,8 +464,90 @@ async fn post_restart_workflow_run(workflow_run_id: WorkflowRunId) -> Result<(),
     }
 }
 
-async fn new_workflow_run_modal() -> HttpResponse {
-    let workflows = match get_workflows().await {
+async fn pause_workflow_run(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    workflow_run_id: web::Path<WorkflowRunId>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    if let Err(error) = post_pause_workflow_run(&endpoints, workflow_run_id.into_inner()).await {
+        return error.to_response();
+    }
+
+    active_workflow_runs_html(&endpoints, false).await
+}
+
+async fn post_pause_workflow_run(
+    endpoints: &ServiceEndpoints,
+    workflow_run_id: WorkflowRunId,
+) -> Result<(), ServerFnError> {
+    let pause_workflow_run_response: ApiResponseBody<WorkflowRun> = utils::api_request(
+        endpoints.workflow_engine(format!("workflow-runs/pause/{workflow_run_id}?f=msgpack")),
+        Method::POST,
+        None::<String>,
+        None::<()>,
+    )
+    .await?;
+    match pause_workflow_run_response {
+        ApiResponseBody::Success(workflow_run) => {
+            log::info!("Paused workflow run: {}", workflow_run.workflow_run_id);
+            Ok(())
+        }
+        ApiResponseBody::Message(message) => {
+            utils::server_fn_error!("Expected data, got message. {}", message)
+        }
+        ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
+            utils::server_fn_error!(message)
+        }
+    }
+}
+
+async fn resume_workflow_run(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    workflow_run_id: web::Path<WorkflowRunId>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HtmxResponseBuilder::location_login_return(&req);
+    }
+    if let Err(error) = post_resume_workflow_run(&endpoints, workflow_run_id.into_inner()).await {
+        return error.to_response();
+    }
+
+    active_workflow_runs_html(&endpoints, false).await
+}
+
+async fn post_resume_workflow_run(
+    endpoints: &ServiceEndpoints,
+    workflow_run_id: WorkflowRunId,
+) -> Result<(), ServerFnError> {
+    let resume_workflow_run_response: ApiResponseBody<WorkflowRun> = utils::api_request(
+        endpoints.workflow_engine(format!("workflow-runs/resume/{workflow_run_id}?f=msgpack")),
+        Method::POST,
+        None::<String>,
+        None::<()>,
+    )
+    .await?;
+    match resume_workflow_run_response {
+        ApiResponseBody::Success(workflow_run) => {
+            log::info!("Resumed workflow run: {}", workflow_run.workflow_run_id);
+            Ok(())
+        }
+        ApiResponseBody::Message(message) => {
+            utils::server_fn_error!("Expected data, got message. {}", message)
+        }
+        ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
+            utils::server_fn_error!(message)
+        }
+    }
+}
+
+async fn new_workflow_run_modal(endpoints: web::Data<ServiceEndpoints>) -> HttpResponse {
+    let workflows = match get_workflows(&endpoints).await {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
@@ -211,21 +562,26 @@ struct NewWorkflowForm {
     modal_id: String,
 }
 
-async fn new_workflow_run(session: Session, form: web::Form<NewWorkflowForm>) -> HttpResponse {
+async fn new_workflow_run(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    form: web::Form<NewWorkflowForm>,
+) -> HttpResponse {
     if extract_session_uid(&session).is_err() {
-        return HtmxResponseBuilder::location_login();
+        return HtmxResponseBuilder::location_login_return(&req);
     }
     let NewWorkflowForm {
         workflow_id,
         modal_id,
     } = form.into_inner();
 
-    let toast_message = match post_init_workflow_run(workflow_id).await {
+    let toast_message = match post_init_workflow_run(&endpoints, workflow_id).await {
         Ok(workflow_run_id) => format!("Created new Workflow Run. ID: {workflow_run_id}"),
         Err(error) => return error.to_response(),
     };
 
-    let workflow_runs = match get_active_workflow_runs().await {
+    let workflow_runs = match get_active_workflow_runs(&endpoints).await {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
@@ -237,9 +593,12 @@ async fn new_workflow_run(session: Session, form: web::Form<NewWorkflowForm>) ->
         })
 }
 
-async fn post_init_workflow_run(workflow_id: WorkflowId) -> Result<WorkflowRunId, ServerFnError> {
+async fn post_init_workflow_run(
+    endpoints: &ServiceEndpoints,
+    workflow_id: WorkflowId,
+) -> Result<WorkflowRunId, ServerFnError> {
     let restart_workflow_run_response: ApiResponseBody<WorkflowRun> = utils::api_request(
-        format!("http://127.0.0.1:8000/api/v1/workflow-runs/init/{workflow_id}?f=msgpack"),
+        endpoints.workflow_engine(format!("workflow-runs/init/{workflow_id}?f=msgpack")),
         Method::POST,
         None::<String>,
         None::<()>,
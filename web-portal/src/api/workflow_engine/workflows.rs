@@ -2,11 +2,11 @@ use common::api::ApiResponseBody;
 use reqwest::Method;
 use workflow_engine::workflow::data::Workflow;
 
-use crate::{utils, ServerFnError};
+use crate::{endpoints::ServiceEndpoints, utils, ServerFnError};
 
-pub async fn get_workflows() -> Result<Vec<Workflow>, ServerFnError> {
+pub async fn get_workflows(endpoints: &ServiceEndpoints) -> Result<Vec<Workflow>, ServerFnError> {
     let workflows_response = utils::api_request(
-        format!("http://127.0.0.1:8000/api/v1/workflows?f=msgpack"),
+        endpoints.workflow_engine("workflows?f=msgpack"),
         Method::GET,
         None::<String>,
         None::<()>,
@@ -103,8 +103,13 @@ pub fn BasePage(
     #[prop(optional)] user: Option<User>,
     #[prop(optional)] stylesheet_href: &'static str,
     #[prop(optional)] script_src: &'static str,
+    #[prop(optional)] csrf_token: Option<String>,
+    #[prop(optional_no_strip)] toast: Option<String>,
     #[prop(optional)] children: Option<Children>,
 ) -> impl IntoView {
+    let csrf_meta = csrf_token.map(|token| view! { cx, <meta name="csrf-token" content=token /> });
+    let flash_toast =
+        toast.map(|message| view! { cx, <div hidden data-flash-toast=message></div> });
     let page_stylesheet = if !stylesheet_href.is_empty() {
         Some(view! { cx, <link rel="stylesheet" href=stylesheet_href /> })
     } else {
@@ -125,6 +130,7 @@ pub fn BasePage(
                 <meta charset="utf-8" />
                 <meta name="viewport" content="width=device-width, initial-scale=1" />
                 <meta name="theme-color" content="#000000" />
+                {csrf_meta}
                 <link rel="icon" type="image/ico" href="/assets/favicon.ico" />
                 <link rel="stylesheet" href="/assets/style.css" />
                 <link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/bootstrap@5.3.0/dist/css/bootstrap.min.css"
@@ -143,6 +149,7 @@ pub fn BasePage(
                     {children.map(|f| f(cx))}
                 </div>
                 <div class="toast-container top-0 end-0 p-3" id="toasts"></div>
+                {flash_toast}
                 <div id="modals"></div>
                 {page_script}
             </body>
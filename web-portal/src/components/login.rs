@@ -1,11 +1,24 @@
 use leptos::*;
 
+/// htmx handler that swaps in the response of a rejected login (401) or an unavailable
+/// authentication service (503) so the reason is shown in the error message of the form
+const LOGIN_BEFORE_SWAP: &str = "htmx:beforeSwap: if (event.detail.xhr.status === 401) {
+    event.detail.shouldSwap = true;
+} else if (event.detail.xhr.status === 503) {
+    event.detail.shouldSwap = true;
+    event.detail.serverResponse = 'Authentication service unavailable. Try again later';
+}";
+
 #[component]
-pub fn LoginForm(cx: Scope) -> impl IntoView {
+pub fn LoginForm(cx: Scope, csrf_token: String, return_to: Option<String>) -> impl IntoView {
     view! { cx,
         <h3 class="login-form mx-auto">"Login to EnviroManager"</h3>
         <form id="loginForm" class="login-form mx-auto" hx-post="/api/login"
-            hx-target="#errorMessage" hx-swap="innerHTML">
+            hx-target="#errorMessage" hx-swap="innerHTML" hx-on=LOGIN_BEFORE_SWAP>
+            <input type="hidden" name="csrf_token" value=csrf_token />
+            {return_to.map(|return_to| view! { cx,
+                <input type="hidden" name="return_to" value=return_to />
+            })}
             <div class="form-group">
                 <label for="username">"Username"</label>
                 <input class="form-control" type="text" id="username" name="username" required />
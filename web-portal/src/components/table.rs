@@ -1,3 +1,4 @@
+use common::api::sort::Sort;
 use leptos::*;
 
 use crate::take_if;
@@ -60,14 +61,22 @@ pub fn RowAction<S>(
     icon: &'static str,
     #[prop(optional)] target: &'static str,
     #[prop(optional)] swap: &'static str,
+    #[prop(optional)] prompt: &'static str,
 ) -> impl IntoView
 where
     S: Into<String>,
 {
     let target = take_if(target, |t| !t.is_empty());
     let swap = take_if(swap, |s| !s.is_empty());
+    let prompt = take_if(prompt, |p| !p.is_empty());
     view! { cx,
-        <button class="btn btn-primary me-1" hx-post=api_url.into() title=title hx-target=target hx-swap=swap>
+        <button
+            class="btn btn-primary me-1"
+            hx-post=api_url.into()
+            title=title
+            hx-target=target
+            hx-swap=swap
+            hx-prompt=prompt>
             <i class=format!("fa-solid {icon}")></i>
         </button>
     }
@@ -110,6 +119,48 @@ where
     }
 }
 
+/// Row like [RowWithDetails] where the details rows are fetched from `details_source` the first
+/// time the details are expanded, instead of being rendered with the row
+#[component]
+pub fn LazyRowWithDetails<IV>(
+    cx: Scope,
+    children: Children,
+    details_id: String,
+    details_header: IV,
+    details_source: String,
+    column_count: u8,
+) -> impl IntoView
+where
+    IV: IntoView,
+{
+    let hm_on = format!(
+        "click: toggleDisplay(document.getElementById('{}'))",
+        details_id
+    );
+    let details_target = format!("#{details_id} tbody");
+    view! { cx,
+        <tr>
+            <td>
+                <button class="btn btn-primary" hx-on=hm_on hx-get=details_source
+                    hx-trigger="click once" hx-target=details_target>
+                    <i class="fa-solid fa-plus"></i>
+                </button>
+            </td>
+            {children(cx)}
+        </tr>
+        <tr id=details_id class="d-none">
+            <td colspan=column_count>
+                <table class="table table-stripped">
+                    <thead>
+                        {details_header}
+                    </thead>
+                    <tbody></tbody>
+                </table>
+            </td>
+        </tr>
+    }
+}
+
 #[component]
 pub fn DetailsTable<IV, R, F, IV2>(
     cx: Scope,
@@ -144,6 +195,38 @@ where
     }
 }
 
+/// Header cell of a table that requests the table data from `data_source` sorted by `column` when
+/// clicked. Clicking the column the table is currently sorted by (as specified by `sort`) toggles
+/// the direction of the sort.
+#[component]
+pub fn SortableHeader<S>(
+    cx: Scope,
+    title: &'static str,
+    column: &'static str,
+    data_source: S,
+    sort: Sort,
+) -> impl IntoView
+where
+    S: AsRef<str>,
+{
+    let is_sorted = sort.column == column;
+    let ascending = !(is_sorted && sort.ascending);
+    let icon = match (is_sorted, sort.ascending) {
+        (true, true) => "fa-solid fa-sort-up",
+        (true, false) => "fa-solid fa-sort-down",
+        (false, _) => "fa-solid fa-sort",
+    };
+    view! { cx,
+        <th role="button" hx-get=format!(
+            "{}?column={column}&ascending={ascending}",
+            data_source.as_ref()
+        )>
+            {title}
+            <i class=format!("{icon} ms-1")></i>
+        </th>
+    }
+}
+
 #[component]
 pub fn DataTableExtras<IV, R, F, IV2, E>(
     cx: Scope,
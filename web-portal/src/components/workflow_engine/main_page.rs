@@ -1,22 +1,109 @@
 use chrono::NaiveDateTime;
+use common::api::sort::Sort;
 use leptos::*;
 use strum::{EnumIter, IntoEnumIterator};
 use workflow_engine::{
     executor::data::{Executor, ExecutorId},
     job::data::{Job, JobId, JobType, ScheduleEntry},
     workflow::data::{Workflow, WorkflowId},
-    workflow_run::data::{WorkflowRun, WorkflowRunId, WorkflowRunStatus, WorkflowRunTask},
+    workflow_run::data::{
+        ExecutorWorkflowRun, TaskLog, TaskRule, TaskStatus, WorkflowRunHistory, WorkflowRunId,
+        WorkflowRunStatus, WorkflowRunSummary, WorkflowRunTask,
+    },
 };
 
 use crate::components::{
     grid::{Col, Row},
     into_view, into_view_option,
     modal::{CreateModal, ADD_MODAL_SWAP, ADD_MODAL_TARGET},
-    table::{DataTableExtras, ExtraTableButton, RowAction, RowWithDetails},
+    table::{
+        DataTableExtras, ExtraTableButton, LazyRowWithDetails, RowAction, RowWithDetails,
+        SortableHeader,
+    },
 };
 
 #[component]
-pub fn WorkflowRunTask(cx: Scope, workflow_run_task: WorkflowRunTask) -> impl IntoView {
+fn WorkflowRunTaskActions(
+    cx: Scope,
+    workflow_run_id: WorkflowRunId,
+    task_order: i32,
+    task_status: TaskStatus,
+) -> impl IntoView {
+    let actions = match task_status {
+        TaskStatus::Failed | TaskStatus::RuleBroken => Some(view! { cx,
+            <RowAction
+                title="Retry Task"
+                api_url=format!("/api/workflow-engine/workflow-run/{workflow_run_id}/retry/{task_order}")
+                icon="fa-rotate-right"/>
+            <RowAction
+                title="Skip Task"
+                api_url=format!("/api/workflow-engine/workflow-run/{workflow_run_id}/skip/{task_order}")
+                icon="fa-forward"/>
+        }),
+        _ => None,
+    };
+    view! { cx, <td>{actions}</td> }
+}
+
+/// Log lines of a single task, rendered as preformatted text with the oldest line first
+#[component]
+fn TaskLogs(cx: Scope, logs: Vec<TaskLog>) -> impl IntoView {
+    if logs.is_empty() {
+        return view! { cx, "-" }.into_view(cx);
+    }
+    let lines = logs
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join("\n");
+    view! { cx, <pre class="mb-0">{lines}</pre> }.into_view(cx)
+}
+
+/// Rules checked by a single task, rendered as a list with the rule name, a pass/fail badge and
+/// the optional message of each rule
+#[component]
+fn TaskRules(cx: Scope, rules: Vec<TaskRule>) -> impl IntoView {
+    if rules.is_empty() {
+        return view! { cx, "-" }.into_view(cx);
+    }
+    let items = rules
+        .into_iter()
+        .map(|rule| {
+            let (badge_class, badge_text) = if rule.failed() {
+                ("badge bg-danger", "Failed")
+            } else {
+                ("badge bg-success", "Passed")
+            };
+            view! { cx,
+                <li>
+                    <span class="me-1">{rule.name().to_owned()}</span>
+                    <span class=badge_class>{badge_text}</span>
+                    {rule.message().map(|message| view! { cx,
+                        <div class="text-muted small">{message.to_owned()}</div>
+                    })}
+                </li>
+            }
+        })
+        .collect_view(cx);
+    view! { cx, <ul class="list-unstyled mb-0">{items}</ul> }.into_view(cx)
+}
+
+/// Table row for a single task within a workflow run. If a `workflow_run_id` is provided, an extra
+/// column is rendered with the actions available for the task's current status.
+#[component]
+pub fn WorkflowRunTask(
+    cx: Scope,
+    workflow_run_task: WorkflowRunTask,
+    #[prop(optional)] workflow_run_id: Option<WorkflowRunId>,
+) -> impl IntoView {
+    let actions = workflow_run_id.map(|workflow_run_id| {
+        view! { cx,
+            <WorkflowRunTaskActions
+                workflow_run_id=workflow_run_id
+                task_order=workflow_run_task.task_order
+                task_status=workflow_run_task.task_status.clone()/>
+        }
+    });
     view! { cx,
         <tr>
             <td>{into_view(workflow_run_task.task_order)}</td>
@@ -26,23 +113,28 @@ pub fn WorkflowRunTask(cx: Scope, workflow_run_task: WorkflowRunTask) -> impl In
             <td>{into_view(workflow_run_task.task_status)}</td>
             <td>{into_view_option(workflow_run_task.parameters)}</td>
             <td>{into_view_option(workflow_run_task.output)}</td>
-            <td>
-            {
-                match workflow_run_task.rules {
-                    Some(rules) => format!("{:?}", rules),
-                    None => "-".to_owned()
-                }
-            }
-            </td>
+            <td><TaskRules rules=workflow_run_task.rules.unwrap_or_default()/></td>
             <td>{into_view_option(workflow_run_task.task_start)}</td>
             <td>{into_view_option(workflow_run_task.task_end)}</td>
             <td>{into_view_option(workflow_run_task.progress)}</td>
+            <td><TaskLogs logs=workflow_run_task.logs.unwrap_or_default()/></td>
+            <td>{format!("{}/{}", workflow_run_task.retry_count, workflow_run_task.max_retries)}</td>
+            {actions}
         </tr>
     }
 }
 
+/// Task rows of a workflow run, loaded into the details of an active workflow run row when expanded
 #[component]
-fn WorkflowRun(cx: Scope, workflow_run: WorkflowRun) -> impl IntoView {
+pub fn WorkflowRunTasks(cx: Scope, tasks: Vec<WorkflowRunTask>) -> impl IntoView {
+    tasks
+        .into_iter()
+        .map(|task| view! { cx, <WorkflowRunTask workflow_run_task=task/> })
+        .collect_view(cx)
+}
+
+#[component]
+fn WorkflowRun(cx: Scope, workflow_run: WorkflowRunSummary) -> impl IntoView {
     let details_id = format!("tasks{}", workflow_run.workflow_run_id);
     let actions = match workflow_run.status {
         WorkflowRunStatus::Waiting => Some(view! { cx,
@@ -50,27 +142,43 @@ fn WorkflowRun(cx: Scope, workflow_run: WorkflowRun) -> impl IntoView {
                 title="Schedule Workflow Run"
                 api_url=format!("/api/workflow-engine/workflow-runs/schedule/{}", workflow_run.workflow_run_id)
                 icon="fa-play"/>
-        }),
+            <RowAction
+                title="Schedule Workflow Run on Executor"
+                api_url=format!("/api/workflow-engine/workflow-runs/schedule-modal/{}", workflow_run.workflow_run_id)
+                icon="fa-server"
+                target=ADD_MODAL_TARGET
+                swap=ADD_MODAL_SWAP/>
+        }.into_view(cx)),
         WorkflowRunStatus::Running => Some(view! { cx,
+            <RowAction
+                title="Pause Workflow Run"
+                api_url=format!("/api/workflow-engine/workflow-runs/pause/{}", workflow_run.workflow_run_id)
+                icon="fa-pause"/>
             <RowAction
                 title="Cancel Workflow Run"
                 api_url=format!("/api/workflow-engine/workflow-runs/cancel/{}", workflow_run.workflow_run_id)
-                icon="fa-stop"/>
-        }),
+                icon="fa-stop"
+                prompt="Reason for canceling the workflow run (optional)"/>
+        }.into_view(cx)),
+        WorkflowRunStatus::Paused => Some(view! { cx,
+            <RowAction
+                title="Resume Workflow Run"
+                api_url=format!("/api/workflow-engine/workflow-runs/resume/{}", workflow_run.workflow_run_id)
+                icon="fa-play"/>
+        }.into_view(cx)),
         WorkflowRunStatus::Failed | WorkflowRunStatus::Canceled => Some(view! { cx,
             <RowAction
                 title="Restart Workflow Run"
                 api_url=format!("/api/workflow-engine/workflow-runs/restart/{}", workflow_run.workflow_run_id)
                 icon="fa-rotate-right"/>
-        }),
-        WorkflowRunStatus::Complete | WorkflowRunStatus::Scheduled | WorkflowRunStatus::Paused => {
-            None
-        }
+        }.into_view(cx)),
+        WorkflowRunStatus::Complete | WorkflowRunStatus::Scheduled => None,
     };
     view! { cx,
-        <RowWithDetails
+        <LazyRowWithDetails
             details_id=details_id
-            column_count=7
+            details_source=format!("/api/workflow-engine/workflow-runs/tasks/{}", workflow_run.workflow_run_id)
+            column_count=8
             details_header=view! { cx,
                 <tr>
                     <th>"Order"</th>
@@ -84,16 +192,21 @@ fn WorkflowRun(cx: Scope, workflow_run: WorkflowRun) -> impl IntoView {
                     <th>"Start"</th>
                     <th>"End"</th>
                     <th>"Progress"</th>
+                    <th>"Logs"</th>
+                    <th>"Retries"</th>
                 </tr>
             }
-            details=workflow_run.tasks
-            details_row_builder=|cx, task| view! { cx, <WorkflowRunTask workflow_run_task=task/> }
         >
             <td>{into_view(workflow_run.workflow_run_id)}</td>
             <td>{into_view(workflow_run.workflow_id)}</td>
-            <td>{into_view(workflow_run.status)}</td>
+            <td data-live-workflow-run=workflow_run.workflow_run_id.to_string() data-live-field="status">
+                {into_view(workflow_run.status)}
+            </td>
             <td>{into_view_option(workflow_run.executor_id)}</td>
-            <td>{into_view_option(workflow_run.progress)}</td>
+            <td data-live-workflow-run=workflow_run.workflow_run_id.to_string() data-live-field="progress">
+                {into_view_option(workflow_run.progress)}
+            </td>
+            <td>{into_view(workflow_run.priority)}</td>
             <td>
                 {actions}
                 <RowAction
@@ -101,14 +214,14 @@ fn WorkflowRun(cx: Scope, workflow_run: WorkflowRun) -> impl IntoView {
                     api_url=format!("/api/workflow-engine/workflow-run/{}", workflow_run.workflow_run_id)
                     icon="fa-right-to-bracket"/>
             </td>
-        </RowWithDetails>
+        </LazyRowWithDetails>
     }
 }
 
 const WORKFLOW_RUNS_TABLE_ID: &str = "active-workflow-runs-tbl";
 
 #[component]
-pub fn ActiveWorkflowRuns(cx: Scope, workflow_runs: Vec<WorkflowRun>) -> impl IntoView {
+pub fn ActiveWorkflowRuns(cx: Scope, workflow_runs: Vec<WorkflowRunSummary>) -> impl IntoView {
     view! { cx,
         <DataTableExtras
             id=WORKFLOW_RUNS_TABLE_ID
@@ -121,6 +234,7 @@ pub fn ActiveWorkflowRuns(cx: Scope, workflow_runs: Vec<WorkflowRun>) -> impl In
                     <th>"Status"</th>
                     <th>"Executor ID"</th>
                     <th>"Progress"</th>
+                    <th>"Priority"</th>
                     <th>"Actions"</th>
                 </tr>
             }
@@ -141,15 +255,107 @@ pub fn ActiveWorkflowRuns(cx: Scope, workflow_runs: Vec<WorkflowRun>) -> impl In
 }
 
 #[component]
-pub fn ActiveWorkflowRunsTab(cx: Scope, workflow_runs: Vec<WorkflowRun>) -> impl IntoView {
+pub fn ActiveWorkflowRunsTab(cx: Scope, workflow_runs: Vec<WorkflowRunSummary>) -> impl IntoView {
     view! { cx,
         <Tabs selected_tab=WorkflowEngineMainPageTabs::WorkflowRuns/>
         <ActiveWorkflowRuns workflow_runs=workflow_runs/>
     }
 }
 
+#[component]
+fn WorkflowRunHistoryRow(cx: Scope, workflow_run: WorkflowRunHistory) -> impl IntoView {
+    view! { cx,
+        <tr>
+            <td>{into_view(workflow_run.workflow_run_id)}</td>
+            <td>{into_view(workflow_run.workflow_id)}</td>
+            <td>{workflow_run.workflow_name}</td>
+            <td>{into_view(workflow_run.status)}</td>
+            <td>{into_view_option(workflow_run.executor_id)}</td>
+            <td>{into_view_option(workflow_run.progress)}</td>
+            <td>{into_view(workflow_run.run_start)}</td>
+            <td>{into_view_option(workflow_run.run_end)}</td>
+            <td>
+                <RowAction
+                    title="Enter Workflow Run"
+                    api_url=format!("/api/workflow-engine/workflow-run/{}", workflow_run.workflow_run_id)
+                    icon="fa-right-to-bracket"/>
+            </td>
+        </tr>
+    }
+}
+
+/// Workflow run history table. The `sort` is the order of the `workflow_runs`, used to display
+/// the sort direction within the sortable headers
+#[component]
+pub fn WorkflowRunsHistory(
+    cx: Scope,
+    workflow_runs: Vec<WorkflowRunHistory>,
+    sort: Sort,
+) -> impl IntoView {
+    let data_source = WorkflowEngineMainPageTabs::WorkflowRunHistory
+        .get_url()
+        .trim_end_matches("/tab");
+    view! { cx,
+        <DataTableExtras
+            id="workflow-runs-history-tbl"
+            caption="Workflow Run History (Last 7 Days)"
+            header=view! { cx,
+                <tr>
+                    <th>"ID"</th>
+                    <th>"Workflow ID"</th>
+                    <th>"Workflow Name"</th>
+                    <SortableHeader title="Status" column="status" data_source=data_source
+                        sort=sort.clone()/>
+                    <th>"Executor ID"</th>
+                    <SortableHeader title="Progress" column="progress" data_source=data_source
+                        sort=sort.clone()/>
+                    <SortableHeader title="Start" column="run_start" data_source=data_source
+                        sort=sort/>
+                    <th>"End"</th>
+                    <th>"Actions"</th>
+                </tr>
+            }
+            items=workflow_runs
+            row_builder=|cx, workflow_run| view! { cx, <WorkflowRunHistoryRow workflow_run=workflow_run/> }
+            data_source=data_source.to_owned()
+            refresh=true
+            extra_buttons=vec![]/>
+    }
+}
+
+#[component]
+pub fn WorkflowRunHistoryTab(
+    cx: Scope,
+    workflow_runs: Vec<WorkflowRunHistory>,
+    sort: Sort,
+) -> impl IntoView {
+    view! { cx,
+        <Tabs selected_tab=WorkflowEngineMainPageTabs::WorkflowRunHistory/>
+        <WorkflowRunsHistory workflow_runs=workflow_runs sort=sort/>
+    }
+}
+
+/// Workflow run rows owned by an executor, loaded into the details of an active executor row when
+/// expanded
+#[component]
+pub fn ExecutorWorkflowRuns(cx: Scope, workflow_runs: Vec<ExecutorWorkflowRun>) -> impl IntoView {
+    workflow_runs
+        .into_iter()
+        .map(|workflow_run| {
+            view! { cx,
+                <tr>
+                    <td>{into_view(workflow_run.workflow_run_id)}</td>
+                    <td>{into_view(workflow_run.status)}</td>
+                    <td>{into_view(workflow_run.is_valid)}</td>
+                </tr>
+            }
+        })
+        .collect_view(cx)
+}
+
 #[component]
 fn Executor(cx: Scope, executor: Executor) -> impl IntoView {
+    let details_id = format!("workflowRuns{}", executor.executor_id);
     let actions = if executor.session_active {
         let cancel_post: String = format!(
             "/api/workflow-engine/executors/cancel/{}",
@@ -167,8 +373,21 @@ fn Executor(cx: Scope, executor: Executor) -> impl IntoView {
         None
     };
     view! { cx,
-        <tr>
-            <td>{into_view(executor.executor_id)}</td>
+        <LazyRowWithDetails
+            details_id=details_id
+            details_source=format!("/api/workflow-engine/executors/{}/workflow-runs", executor.executor_id)
+            column_count=11
+            details_header=view! { cx,
+                <tr>
+                    <th>"Workflow Run ID"</th>
+                    <th>"Status"</th>
+                    <th>"Valid"</th>
+                </tr>
+            }
+        >
+            <td data-live-executor=executor.executor_id.to_string()>
+                {into_view(executor.executor_id)}
+            </td>
             <td>{into_view(executor.pid)}</td>
             <td>{into_view(executor.username)}</td>
             <td>{into_view(executor.application_name)}</td>
@@ -178,7 +397,7 @@ fn Executor(cx: Scope, executor: Executor) -> impl IntoView {
             <td>{into_view(executor.session_active)}</td>
             <td>{into_view(executor.workflow_run_count)}</td>
             <td>{actions}</td>
-        </tr>
+        </LazyRowWithDetails>
     }
 }
 
@@ -190,6 +409,7 @@ pub fn ActiveExecutors(cx: Scope, executors: Vec<Executor>) -> impl IntoView {
             caption="Active Executors"
             header=view! { cx,
                 <tr>
+                    <th>"Workflow Runs"</th>
                     <th>"ID"</th>
                     <th>"PID"</th>
                     <th>"Username"</th>
@@ -203,7 +423,9 @@ pub fn ActiveExecutors(cx: Scope, executors: Vec<Executor>) -> impl IntoView {
                 </tr>
             }
             items=executors
-            row_builder=|cx, executor| view! { cx, <Executor executor=executor/> }
+            row_builder=|cx, executor| view! { cx,
+                <Executor executor=executor/>
+            }
             data_source=WorkflowEngineMainPageTabs::Executors.get_url().trim_end_matches("/tab").to_owned()
             refresh=true
             extra_buttons=vec![
@@ -224,6 +446,30 @@ pub fn ActiveExecutorsTab(cx: Scope, executors: Vec<Executor>) -> impl IntoView
     }
 }
 
+/// Actions available for a job row. A job can be run immediately as long as it does not have a
+/// workflow run in flight
+#[component]
+fn JobActions(
+    cx: Scope,
+    job_id: JobId,
+    workflow_run_status: Option<WorkflowRunStatus>,
+) -> impl IntoView {
+    let is_in_flight = !matches!(
+        workflow_run_status,
+        None | Some(
+            WorkflowRunStatus::Complete | WorkflowRunStatus::Failed | WorkflowRunStatus::Canceled
+        )
+    );
+    (!is_in_flight).then(|| {
+        view! { cx,
+            <RowAction
+                title="Run Job Now"
+                api_url=format!("/api/workflow-engine/jobs/run-now/{job_id}")
+                icon="fa-forward"/>
+        }
+    })
+}
+
 #[component]
 fn ScheduledJob(
     cx: Scope,
@@ -265,9 +511,12 @@ fn ScheduledJob(
             <td>{into_view(is_paused)}</td>
             <td>{into_view(next_run)}</td>
             <td>{into_view_option(current_workflow_run_id)}</td>
-            <td>{into_view_option(workflow_run_status)}</td>
+            <td>{into_view_option(workflow_run_status.as_ref())}</td>
             <td>{into_view_option(executor_id)}</td>
             <td>{into_view_option(progress)}</td>
+            <td>
+                <JobActions job_id=job_id workflow_run_status=workflow_run_status/>
+            </td>
         </RowWithDetails>
     }
 }
@@ -287,7 +536,8 @@ fn IntervalJob(
     progress: Option<i16>,
     months: i32,
     days: i32,
-    minutes: i32,
+    minutes: i64,
+    seconds: i64,
 ) -> impl IntoView {
     view! { cx,
         <RowWithDetails
@@ -297,14 +547,16 @@ fn IntervalJob(
                     <th>"Months"</th>
                     <th>"Days"</th>
                     <th>"Minutes"</th>
+                    <th>"Seconds"</th>
                 </tr>
             }
-            details=vec![(months, days, minutes)]
+            details=vec![(months, days, minutes, seconds)]
             details_row_builder=|cx, interval| view! { cx,
                 <tr>
                     <td>{interval.0}</td>
                     <td>{interval.1}</td>
                     <td>{interval.2}</td>
+                    <td>{interval.3}</td>
                 </tr>
             }
             column_count=13
@@ -317,9 +569,61 @@ fn IntervalJob(
             <td>{into_view(is_paused)}</td>
             <td>{into_view(next_run)}</td>
             <td>{into_view_option(current_workflow_run_id)}</td>
-            <td>{into_view_option(workflow_run_status)}</td>
+            <td>{into_view_option(workflow_run_status.as_ref())}</td>
+            <td>{into_view_option(executor_id)}</td>
+            <td>{into_view_option(progress)}</td>
+            <td>
+                <JobActions job_id=job_id workflow_run_status=workflow_run_status/>
+            </td>
+        </RowWithDetails>
+    }
+}
+
+#[component]
+fn CronJob(
+    cx: Scope,
+    job_id: JobId,
+    workflow_id: WorkflowId,
+    workflow_name: String,
+    maintainer: String,
+    is_paused: bool,
+    next_run: NaiveDateTime,
+    current_workflow_run_id: Option<WorkflowRunId>,
+    workflow_run_status: Option<WorkflowRunStatus>,
+    executor_id: Option<ExecutorId>,
+    progress: Option<i16>,
+    expression: String,
+) -> impl IntoView {
+    view! { cx,
+        <RowWithDetails
+            details_id=job_id.to_string()
+            details_header=view! { cx,
+                <tr>
+                    <th>"Cron Expression (UTC)"</th>
+                </tr>
+            }
+            details=vec![expression]
+            details_row_builder=|cx, expression| view! { cx,
+                <tr>
+                    <td><code>{expression}</code></td>
+                </tr>
+            }
+            column_count=13
+        >
+            <td>{into_view(job_id)}</td>
+            <td>{into_view(workflow_id)}</td>
+            <td>{workflow_name}</td>
+            <td>"Cron"</td>
+            <td>{maintainer}</td>
+            <td>{into_view(is_paused)}</td>
+            <td>{into_view(next_run)}</td>
+            <td>{into_view_option(current_workflow_run_id)}</td>
+            <td>{into_view_option(workflow_run_status.as_ref())}</td>
             <td>{into_view_option(executor_id)}</td>
             <td>{into_view_option(progress)}</td>
+            <td>
+                <JobActions job_id=job_id workflow_run_status=workflow_run_status/>
+            </td>
         </RowWithDetails>
     }
 }
@@ -341,8 +645,9 @@ fn JobRow(cx: Scope, job: Job) -> impl IntoView {
                 progress=job.progress
                 entries=entries/>
         },
-        JobType::Interval { interval } => {
-            let minutes = (interval.microseconds / 60 * 1_000_000) as i32;
+        JobType::Interval { .. } => {
+            let (months, days, minutes, seconds) =
+                job.job_type.interval_components().unwrap_or_default();
             view! { cx,
                 <IntervalJob
                     job_id=job.job_id
@@ -355,11 +660,26 @@ fn JobRow(cx: Scope, job: Job) -> impl IntoView {
                     workflow_run_status=job.workflow_run_status
                     executor_id=job.executor_id
                     progress=job.progress
-                    months=interval.months
-                    days=interval.days
-                    minutes=minutes/>
+                    months=months
+                    days=days
+                    minutes=minutes
+                    seconds=seconds/>
             }
         }
+        JobType::Cron { expression } => view! { cx,
+            <CronJob
+                job_id=job.job_id
+                workflow_id=job.workflow_id
+                workflow_name=job.workflow_name
+                maintainer=job.maintainer
+                is_paused=job.is_paused
+                next_run=job.next_run
+                current_workflow_run_id=job.current_workflow_run_id
+                workflow_run_status=job.workflow_run_status
+                executor_id=job.executor_id
+                progress=job.progress
+                expression=expression/>
+        },
     }
 }
 
@@ -382,6 +702,7 @@ pub fn Jobs(cx: Scope, jobs: Vec<Job>) -> impl IntoView {
                     <th rowspan=2>"Paused?"</th>
                     <th rowspan=2>"Next Run"</th>
                     <th colspan=4>"Current Workflow Run"</th>
+                    <th rowspan=2>"Actions"</th>
                 </tr>
                 <tr>
                     <th>"ID"</th>
@@ -418,6 +739,7 @@ pub fn JobsTab(cx: Scope, jobs: Vec<Job>) -> impl IntoView {
 pub enum WorkflowEngineMainPageTabs {
     Executors,
     WorkflowRuns,
+    WorkflowRunHistory,
     Jobs,
 }
 
@@ -426,6 +748,7 @@ impl WorkflowEngineMainPageTabs {
         match self {
             Self::Executors => "executors-tab",
             Self::WorkflowRuns => "workflow-runs-tab",
+            Self::WorkflowRunHistory => "workflow-run-history-tab",
             Self::Jobs => "jobs-tab",
         }
     }
@@ -434,6 +757,7 @@ impl WorkflowEngineMainPageTabs {
         match self {
             Self::Executors => "Executors",
             Self::WorkflowRuns => "Workflow Runs",
+            Self::WorkflowRunHistory => "Workflow Run History",
             Self::Jobs => "Jobs",
         }
     }
@@ -442,6 +766,7 @@ impl WorkflowEngineMainPageTabs {
         match self {
             Self::Executors => "/api/workflow-engine/executors/tab",
             Self::WorkflowRuns => "/api/workflow-engine/workflow-runs/tab",
+            Self::WorkflowRunHistory => "/api/workflow-engine/workflow-runs/history/tab",
             Self::Jobs => "/api/workflow-engine/jobs/tab",
         }
     }
@@ -518,6 +843,54 @@ pub fn NewWorkflowRunModal(cx: Scope, workflows: Vec<Workflow>) -> impl IntoView
     }
 }
 
+#[component]
+fn ExecutorOptions(cx: Scope, executors: Vec<Executor>) -> impl IntoView {
+    let options = executors
+        .into_iter()
+        .enumerate()
+        .map(|(i, e)| {
+            let capacity = e
+                .max_workflow_runs
+                .map_or_else(|| "unlimited".to_owned(), |max| max.to_string());
+            view! { cx,
+                <option value=e.executor_id.to_string() selected=i == 0>
+                    {format!("{} ({}/{} workflow runs)", e.executor_id, e.workflow_run_count, capacity)}
+                </option>
+            }
+        })
+        .collect_view(cx);
+    view! { cx,
+        <select class="form-select" id="executor" name="executor_id" required>
+            {options}
+        </select>
+    }
+}
+
+/// Modal to schedule the workflow run specified by `workflow_run_id` on one of the available
+/// `executors`, listed with the least loaded executor first
+#[component]
+pub fn ScheduleWorkflowRunModal(
+    cx: Scope,
+    workflow_run_id: WorkflowRunId,
+    executors: Vec<Executor>,
+) -> impl IntoView {
+    view! { cx,
+        <CreateModal
+            id="scheduleWorkflowRun"
+            title=format!("Schedule Workflow Run {workflow_run_id}")
+            target=format!("#{WORKFLOW_RUNS_TABLE_ID}Container")
+            form=view! { cx,
+                <div class="row mb-3">
+                    <label for="executor" class="col-sm-3 col-form-label">"Executor"</label>
+                    <div class="col-sm-9">
+                        <ExecutorOptions executors=executors/>
+                    </div>
+                </div>
+            }
+            post_url=format!("/api/workflow-engine/workflow-runs/schedule/{workflow_run_id}/executor")/>
+    }
+}
+
 #[component]
 pub fn NewJobNextRun(cx: Scope) -> impl IntoView {
     view! { cx,
@@ -610,6 +983,21 @@ pub fn NewIntervalJob(cx: Scope) -> impl IntoView {
     }
 }
 
+#[component]
+pub fn NewCronJob(cx: Scope) -> impl IntoView {
+    view! { cx,
+        <Row>
+            <Col>
+                <label class="form-label" for="cron_expression">"Cron Expression (UTC)"</label>
+            </Col>
+            <Col>
+                <input class="form-control" id="cron_expression" name="cron_expression" type="text"
+                    placeholder="0 2 * * 1-5" required/>
+            </Col>
+        </Row>
+    }
+}
+
 #[component]
 pub fn NewJobModal(cx: Scope, workflows: Vec<Workflow>) -> impl IntoView {
     view! { cx,
@@ -649,6 +1037,15 @@ pub fn NewJobModal(cx: Scope, workflows: Vec<Workflow>) -> impl IntoView {
                             "Interval"
                         </label>
                     </div>
+                    <div class="form-check col">
+                        <input class="form-check-input" type="radio" name="job_type"
+                            id="jobTypeCron" value="cron"
+                            hx-get="/api/workflow-engine/jobs/job-type"
+                            hx-target="#jobTypeContainer" hx-swap="innerHTML"/>
+                        <label class="form-check-label" for="jobTypeCron">
+                            "Cron"
+                        </label>
+                    </div>
                 </div>
                 <fieldset id="jobTypeContainer" class="text-center">
                     <NewScheduledJob/>
@@ -1,5 +1,5 @@
 use leptos::*;
-use workflow_engine::workflow_run::data::{WorkflowRun, WorkflowRunTask};
+use workflow_engine::workflow_run::data::{WorkflowRun, WorkflowRunId, WorkflowRunTask};
 
 use crate::components::{
     data_display::{DataDisplay, DataField},
@@ -9,11 +9,17 @@ use crate::components::{
     workflow_engine::main_page::WorkflowRunTask,
 };
 
+pub const WORKFLOW_RUN_TASKS_TABLE_ID: &str = "workflow_run_tasks";
+
 #[component]
-fn WorkflowRunTaskTable(cx: Scope, tasks: Vec<WorkflowRunTask>) -> impl IntoView {
+pub fn WorkflowRunTaskTable(
+    cx: Scope,
+    workflow_run_id: WorkflowRunId,
+    tasks: Vec<WorkflowRunTask>,
+) -> impl IntoView {
     view! { cx,
         <DataTable
-            id="workflow_run_tasks"
+            id=WORKFLOW_RUN_TASKS_TABLE_ID
             caption="Tasks"
             header=view! { cx,
                 <th>"Order"</th>
@@ -27,16 +33,25 @@ fn WorkflowRunTaskTable(cx: Scope, tasks: Vec<WorkflowRunTask>) -> impl IntoView
                 <th>"Start"</th>
                 <th>"End"</th>
                 <th>"Progress"</th>
+                <th>"Logs"</th>
+                <th>"Retries"</th>
+                <th>"Actions"</th>
             }
             items=tasks
-            row_builder=|cx, task| view! { cx,
-                <WorkflowRunTask workflow_run_task=task/>
+            row_builder=move |cx, task| view! { cx,
+                <WorkflowRunTask workflow_run_task=task workflow_run_id=workflow_run_id/>
             }/>
     }
 }
 
 #[component]
 pub fn WorkflowRunDisplay(cx: Scope, workflow_run: WorkflowRun) -> impl IntoView {
+    let progress_stream = (!workflow_run.status.is_terminal()).then(|| {
+        format!(
+            "/api/workflow-engine/workflow-run/{}/progress",
+            workflow_run.workflow_run_id
+        )
+    });
     view! { cx,
         <DataDisplay
             id="workflowRunDisplay"
@@ -70,11 +85,84 @@ pub fn WorkflowRunDisplay(cx: Scope, workflow_run: WorkflowRun) -> impl IntoView
                         label="Progress"
                         column_width=2
                         data=into_view_option(workflow_run.progress)/>
+                    <DataField
+                        id="cancel_reason"
+                        label="Cancel Reason"
+                        column_width=4
+                        data=into_view_option(workflow_run.cancel_reason)/>
                 </Row>
+                <div class="d-none" data-progress-stream=progress_stream></div>
             }
             table=view! { cx,
-                <WorkflowRunTaskTable tasks=workflow_run.tasks/>
+                <WorkflowRunTaskTable
+                    workflow_run_id=workflow_run.workflow_run_id
+                    tasks=workflow_run.tasks/>
             }
             refresh=format!("/api/workflow-engine/workflow-run/{}", workflow_run.workflow_run_id)/>
     }
 }
+
+#[cfg(test)]
+mod test {
+    use leptos::*;
+    use rstest::rstest;
+    use workflow_engine::workflow_run::data::{TaskStatus, WorkflowRunId, WorkflowRunTask};
+
+    use super::WorkflowRunTaskTable;
+
+    /// Utility method for creating a new [WorkflowRunTask] with the specified `task_order` and
+    /// `task_status`
+    fn workflow_run_task(task_order: i32, task_status: TaskStatus) -> WorkflowRunTask {
+        WorkflowRunTask {
+            task_order,
+            task_id: 1.into(),
+            name: "test".to_owned(),
+            description: "test".to_owned(),
+            task_status,
+            parameters: None,
+            output: None,
+            rules: None,
+            task_start: None,
+            task_end: None,
+            progress: None,
+            logs: None,
+            retry_count: 0,
+            max_retries: 0,
+        }
+    }
+
+    /// Render the task table of workflow run 1 containing a single task with the specified
+    /// `task_order` and `task_status`
+    fn render_task_table(task_order: i32, task_status: TaskStatus) -> String {
+        let workflow_run_id = WorkflowRunId::from(1);
+        let tasks = vec![workflow_run_task(task_order, task_status)];
+        leptos::ssr::render_to_string(move |cx| {
+            view! { cx, <WorkflowRunTaskTable workflow_run_id=workflow_run_id tasks=tasks/> }
+        })
+    }
+
+    #[rstest]
+    #[case::failed(TaskStatus::Failed)]
+    #[case::rule_broken(TaskStatus::RuleBroken)]
+    fn task_row_should_render_retry_and_skip_actions_when(#[case] task_status: TaskStatus) {
+        let html = render_task_table(2, task_status);
+        assert!(
+            html.contains("/api/workflow-engine/workflow-run/1/retry/2"),
+            "{html}"
+        );
+        assert!(
+            html.contains("/api/workflow-engine/workflow-run/1/skip/2"),
+            "{html}"
+        );
+    }
+
+    #[rstest]
+    #[case::waiting(TaskStatus::Waiting)]
+    #[case::running(TaskStatus::Running)]
+    #[case::complete(TaskStatus::Complete)]
+    fn task_row_should_not_render_actions_when(#[case] task_status: TaskStatus) {
+        let html = render_task_table(2, task_status);
+        assert!(!html.contains("/retry/"), "{html}");
+        assert!(!html.contains("/skip/"), "{html}");
+    }
+}
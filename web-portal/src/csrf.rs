@@ -0,0 +1,231 @@
+use std::future::{ready, Ready};
+
+use actix_session::{Session, SessionExt};
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::Method,
+    HttpRequest, HttpResponse,
+};
+use futures::future::LocalBoxFuture;
+use uuid::Uuid;
+
+use crate::ServerFnError;
+
+pub const CSRF_TOKEN_SESSION_KEY: &str = "csrf_token";
+/// Header that htmx requests use to send the CSRF token of the page. Set for every htmx request
+/// by `assets/utils.js` using the `csrf-token` meta tag of the page.
+pub const CSRF_TOKEN_HEADER: &str = "X-CSRF-Token";
+/// Route that validates the CSRF token submitted within its form rather than the header
+const LOGIN_ROUTE: &str = "/api/login";
+
+/// Get the CSRF token tied to the `session`, generating and storing a new token if the session
+/// does not have one yet. The token should be embedded in any form that submits to a state changing
+/// endpoint and checked using [validate_csrf_token].
+pub fn csrf_token(session: &Session) -> Result<String, ServerFnError> {
+    if let Some(token) = session.get::<String>(CSRF_TOKEN_SESSION_KEY)? {
+        return Ok(token);
+    }
+    let token = Uuid::new_v4().simple().to_string();
+    session.insert(CSRF_TOKEN_SESSION_KEY, &token)?;
+    Ok(token)
+}
+
+/// Replace the CSRF token tied to the `session` with a newly generated token. Should be called
+/// whenever the privilege level of the session changes (e.g. login).
+pub fn rotate_csrf_token(session: &Session) -> Result<String, ServerFnError> {
+    let token = Uuid::new_v4().simple().to_string();
+    session.insert(CSRF_TOKEN_SESSION_KEY, &token)?;
+    Ok(token)
+}
+
+/// Validate that the `token` submitted with a request matches the CSRF token stored in the
+/// `session`. Returns [ServerFnError::InvalidCsrfToken] if the session has no token or the tokens
+/// do not match.
+pub fn validate_csrf_token(session: &Session, token: &str) -> Result<(), ServerFnError> {
+    let Some(expected) = session.get::<String>(CSRF_TOKEN_SESSION_KEY)? else {
+        return Err(ServerFnError::InvalidCsrfToken);
+    };
+    if token.is_empty() || !constant_time_eq(expected.as_bytes(), token.as_bytes()) {
+        return Err(ServerFnError::InvalidCsrfToken);
+    }
+    Ok(())
+}
+
+/// Returns true if the `req` can change state and must include a valid CSRF token. Safe methods
+/// and the login route (which checks the token within the form) are exempt.
+fn requires_csrf_check(req: &HttpRequest) -> bool {
+    !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        && req.path() != LOGIN_ROUTE
+}
+
+/// Validate the CSRF token sent in the [CSRF_TOKEN_HEADER] of the `req` against the token of the
+/// request's session
+fn validate_csrf_header(req: &HttpRequest) -> Result<(), ServerFnError> {
+    let token = req
+        .headers()
+        .get(CSRF_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    validate_csrf_token(&req.get_session(), token)
+}
+
+/// Middleware factory that rejects state changing requests without a valid CSRF token header with
+/// a `403 Forbidden` response
+pub struct CsrfProtection;
+
+impl<S, B> Transform<S, ServiceRequest> for CsrfProtection
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    type InitError = ();
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Transform = CsrfProtectionMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CsrfProtectionMiddleware { service }))
+    }
+}
+
+/// Service created by the [CsrfProtection] middleware factory
+pub struct CsrfProtectionMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for CsrfProtectionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+    type Response = ServiceResponse<EitherBody<B>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if requires_csrf_check(req.request()) {
+            if let Err(error) = validate_csrf_header(req.request()) {
+                log::warn!("Rejected request to {}. {error}", req.path());
+                let response = HttpResponse::Forbidden().body(error.to_string());
+                return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+            }
+        }
+        let future = self.service.call(req);
+        Box::pin(async move { Ok(future.await?.map_into_left_body()) })
+    }
+}
+
+/// Compare 2 byte slices without short-circuiting on the first mismatched byte
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    left.iter()
+        .zip(right.iter())
+        .fold(0u8, |acc, (l, r)| acc | (l ^ r))
+        == 0
+}
+
+#[cfg(test)]
+mod test {
+    use actix_session::{Session, SessionExt};
+    use actix_web::test::TestRequest;
+    use rstest::rstest;
+
+    use super::{
+        csrf_token, requires_csrf_check, rotate_csrf_token, validate_csrf_header,
+        validate_csrf_token, CSRF_TOKEN_HEADER, CSRF_TOKEN_SESSION_KEY,
+    };
+    use crate::ServerFnError;
+
+    const SESSION_TOKEN: &str = "session-token";
+
+    /// Utility method for creating a new [Session], optionally containing a CSRF `token`
+    fn session(token: Option<&str>) -> Session {
+        let session = TestRequest::default().to_http_request().get_session();
+        if let Some(token) = token {
+            session.insert(CSRF_TOKEN_SESSION_KEY, token).unwrap();
+        }
+        session
+    }
+
+    #[rstest]
+    #[case::matching_token(SESSION_TOKEN)]
+    fn validate_csrf_token_should_succeed_when(#[case] token: &str) {
+        let session = session(Some(SESSION_TOKEN));
+        let result = validate_csrf_token(&session, token);
+        assert!(result.is_ok(), "{:?}", result.unwrap_err());
+    }
+
+    #[rstest]
+    #[case::missing_token(Some(SESSION_TOKEN), "")]
+    #[case::mismatched_token(Some(SESSION_TOKEN), "not-the-session-token")]
+    #[case::missing_session_token(None, SESSION_TOKEN)]
+    fn validate_csrf_token_should_fail_when(
+        #[case] session_token: Option<&str>,
+        #[case] token: &str,
+    ) {
+        let session = session(session_token);
+        let result = validate_csrf_token(&session, token);
+        assert!(matches!(result, Err(ServerFnError::InvalidCsrfToken)));
+    }
+
+    #[test]
+    fn csrf_token_should_be_accepted_when_generated_for_session() {
+        let session = session(None);
+        let token = csrf_token(&session).unwrap();
+        assert_eq!(token, csrf_token(&session).unwrap());
+        assert!(validate_csrf_token(&session, &token).is_ok());
+    }
+
+    #[test]
+    fn rotate_csrf_token_should_invalidate_previous_token() {
+        let session = session(None);
+        let old_token = csrf_token(&session).unwrap();
+
+        let new_token = rotate_csrf_token(&session).unwrap();
+
+        assert_ne!(old_token, new_token);
+        assert!(validate_csrf_token(&session, &old_token).is_err());
+        assert!(validate_csrf_token(&session, &new_token).is_ok());
+    }
+
+    #[rstest]
+    #[case::post(TestRequest::post().uri("/api/workflow-engine/jobs"), true)]
+    #[case::patch(TestRequest::patch().uri("/api/users"), true)]
+    #[case::get(TestRequest::get().uri("/api/workflow-engine/jobs"), false)]
+    #[case::login(TestRequest::post().uri("/api/login"), false)]
+    fn requires_csrf_check_should_match_state_changing_requests(
+        #[case] request: TestRequest,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(requires_csrf_check(&request.to_http_request()), expected);
+    }
+
+    #[rstest]
+    #[case::missing_header(None, false)]
+    #[case::mismatched_header(Some("not-the-session-token"), false)]
+    #[case::matching_header(Some(SESSION_TOKEN), true)]
+    fn validate_csrf_header_should_check_header_token(
+        #[case] header: Option<&str>,
+        #[case] is_valid: bool,
+    ) {
+        let mut request = TestRequest::post().uri("/api/workflow-engine/jobs");
+        if let Some(header) = header {
+            request = request.insert_header((CSRF_TOKEN_HEADER, header));
+        }
+        let request = request.to_http_request();
+        request
+            .get_session()
+            .insert(CSRF_TOKEN_SESSION_KEY, SESSION_TOKEN)
+            .unwrap();
+
+        assert_eq!(validate_csrf_header(&request).is_ok(), is_valid);
+    }
+}
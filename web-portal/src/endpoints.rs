@@ -0,0 +1,132 @@
+use std::env;
+
+use users::client::UsersApiClient;
+
+/// Default base url of the workflow engine API when `WORKFLOW_ENGINE_URL` is not set
+pub const DEFAULT_WORKFLOW_ENGINE_URL: &str = "http://127.0.0.1:8000";
+/// Default base url of the users API when `USERS_URL` is not set
+pub const DEFAULT_USERS_URL: &str = "http://127.0.0.1:8001";
+
+/// Base urls of the internal services called by the web portal. Read once at startup and provided
+/// to every request handler as actix `Data`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceEndpoints {
+    /// Base url (scheme, host and port) of the workflow engine API
+    workflow_engine_url: String,
+    /// Base url (scheme, host and port) of the users API
+    users_url: String,
+}
+
+impl Default for ServiceEndpoints {
+    fn default() -> Self {
+        Self::new(DEFAULT_WORKFLOW_ENGINE_URL, DEFAULT_USERS_URL)
+    }
+}
+
+impl ServiceEndpoints {
+    /// Create a new [ServiceEndpoints] with the base urls of the workflow engine and users APIs.
+    /// Trailing slashes are removed from the base urls.
+    pub fn new<S1, S2>(workflow_engine_url: S1, users_url: S2) -> Self
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        Self {
+            workflow_engine_url: workflow_engine_url
+                .as_ref()
+                .trim_end_matches('/')
+                .to_owned(),
+            users_url: users_url.as_ref().trim_end_matches('/').to_owned(),
+        }
+    }
+
+    /// Create a new [ServiceEndpoints] from environment variables, using the default localhost
+    /// url for any variable that is not set. The environment variables read are:
+    /// - WORKFLOW_ENGINE_URL -> base url of the workflow engine API (default
+    ///   `http://127.0.0.1:8000`)
+    /// - USERS_URL -> base url of the users API (default `http://127.0.0.1:8001`)
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Create a new [ServiceEndpoints] using the `lookup` function to find each base url by name.
+    /// Values that are not found are replaced with their default.
+    fn from_lookup<F>(lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        Self::new(
+            lookup("WORKFLOW_ENGINE_URL").unwrap_or_else(|| DEFAULT_WORKFLOW_ENGINE_URL.to_owned()),
+            lookup("USERS_URL").unwrap_or_else(|| DEFAULT_USERS_URL.to_owned()),
+        )
+    }
+
+    /// Full url of the workflow engine API endpoint at `path`, relative to the `/api/v1` scope
+    pub fn workflow_engine<S>(&self, path: S) -> String
+    where
+        S: AsRef<str>,
+    {
+        format!(
+            "{}/api/v1/{}",
+            self.workflow_engine_url,
+            path.as_ref().trim_start_matches('/')
+        )
+    }
+
+    /// Full url of the users API endpoint at `path`, relative to the `/api/v1` scope
+    pub fn users<S>(&self, path: S) -> String
+    where
+        S: AsRef<str>,
+    {
+        format!(
+            "{}/api/v1/{}",
+            self.users_url,
+            path.as_ref().trim_start_matches('/')
+        )
+    }
+
+    /// Create a new [UsersApiClient] pointing to the users API
+    pub fn users_api_client(&self) -> UsersApiClient {
+        UsersApiClient::new(format!("{}/api/v1", self.users_url))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use super::ServiceEndpoints;
+
+    #[rstest]
+    #[case::defaults(&[], "http://127.0.0.1:8000/api/v1/executors?f=msgpack")]
+    #[case::env_value(
+        &[("WORKFLOW_ENGINE_URL", "https://engine.internal")],
+        "https://engine.internal/api/v1/executors?f=msgpack"
+    )]
+    #[case::trailing_slash(
+        &[("WORKFLOW_ENGINE_URL", "https://engine.internal/")],
+        "https://engine.internal/api/v1/executors?f=msgpack"
+    )]
+    fn workflow_engine_should_build_url_when(
+        #[case] pairs: &[(&str, &str)],
+        #[case] expected: &str,
+    ) {
+        let values: HashMap<&str, &str> = pairs.iter().copied().collect();
+        let endpoints =
+            ServiceEndpoints::from_lookup(|key| values.get(key).map(|value| (*value).to_owned()));
+
+        assert_eq!(endpoints.workflow_engine("/executors?f=msgpack"), expected);
+    }
+
+    #[test]
+    fn users_should_build_url_from_default() {
+        let endpoints = ServiceEndpoints::default();
+
+        assert_eq!(
+            endpoints.users("users?f=msgpack"),
+            "http://127.0.0.1:8001/api/v1/users?f=msgpack"
+        );
+    }
+}
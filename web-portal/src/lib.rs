@@ -1,10 +1,20 @@
 pub mod api;
 pub mod components;
+pub mod csrf;
+pub mod endpoints;
+pub mod live_updates;
 pub mod pages;
+pub mod return_to;
+pub mod session_actor;
+pub mod session_config;
+pub mod session_expiry;
 
 use actix_session::Session;
-use actix_web::HttpResponse;
-use common::api::{ApiContentFormat, ApiResponse};
+use actix_web::{http::StatusCode as HttpStatusCode, HttpResponse};
+use common::{
+    api::{ApiContentFormat, ApiResponse},
+    error::EmError,
+};
 use reqwest::StatusCode;
 use serde::Serialize;
 use thiserror::Error;
@@ -12,7 +22,11 @@ use uuid::Uuid;
 
 pub const EM_UID_SESSION_KEY: &str = "em_uid";
 pub const USERNAME_SESSION_KEY: &str = "username";
+pub const FLASH_TOAST_SESSION_KEY: &str = "flash_toast";
 pub const INTERNAL_SERVICE_ERROR: &str = "Error contacting internal service";
+pub const AUTH_SERVICE_UNAVAILABLE: &str = "authentication service unavailable";
+pub const SERVICE_UNAVAILABLE: &str = "service unavailable";
+pub const MISSING_PRIVILEGE: &str = "missing the privilege required for this action";
 
 pub mod utils;
 
@@ -24,14 +38,22 @@ pub enum ServerFnError {
     Deserialization(#[from] rmp_serde::decode::Error),
     #[error("Error performing API request. {0}")]
     ApiRequest(reqwest::Error),
+    #[error("Internal service cannot be reached. {0}")]
+    ServiceUnavailable(reqwest::Error),
     #[error("Invalid API response: {0}. {1:?}")]
     ApiResponse(StatusCode, Option<String>),
     #[error("Api response body cannot be processed. {0}")]
     ApiResponseBody(reqwest::Error),
     #[error(transparent)]
     Session(#[from] actix_session::SessionGetError),
+    #[error(transparent)]
+    SessionInsert(#[from] actix_session::SessionInsertError),
+    #[error(transparent)]
+    UsersApi(#[from] common::error::EmError),
     #[error("User attempted to access endpoint without a valid session")]
     InvalidUser,
+    #[error("Request CSRF token is missing or does not match the session")]
+    InvalidCsrfToken,
     #[error("{0}")]
     Generic(String),
     #[error("{0}")]
@@ -39,17 +61,57 @@ pub enum ServerFnError {
 }
 
 impl ServerFnError {
+    /// Status code that best describes the error. Internal services that cannot be reached map to
+    /// `503 Service Unavailable`, rejected users map to `401 Unauthorized` and users missing a
+    /// privilege map to `403 Forbidden`, while everything else is a `500 Internal Server Error`.
+    pub fn status_code(&self) -> HttpStatusCode {
+        match self {
+            Self::UsersApi(EmError::MissingPrivilege { .. }) => HttpStatusCode::FORBIDDEN,
+            Self::ApiResponse(status, _) if *status == StatusCode::FORBIDDEN => {
+                HttpStatusCode::FORBIDDEN
+            }
+            Self::ServiceUnavailable(_) | Self::UsersApi(EmError::ServiceUnavailable(_)) => {
+                HttpStatusCode::SERVICE_UNAVAILABLE
+            }
+            Self::InvalidUser | Self::UsersApi(EmError::InvalidUser) => {
+                HttpStatusCode::UNAUTHORIZED
+            }
+            _ => HttpStatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Failure message shown when the service behind the request cannot be reached. The users
+    /// API is reported as the authentication service since it backs every login and session.
+    fn unavailable_message(&self) -> &'static str {
+        match self {
+            Self::UsersApi(_) => AUTH_SERVICE_UNAVAILABLE,
+            _ => SERVICE_UNAVAILABLE,
+        }
+    }
+
     pub fn to_api_response<T>(self, format: ApiContentFormat) -> ApiResponse<T>
     where
         T: Serialize,
     {
         log::error!("{}", self);
+        if self.status_code() == HttpStatusCode::SERVICE_UNAVAILABLE {
+            return ApiResponse::failure(self.unavailable_message(), format);
+        }
         ApiResponse::failure("Error during internal API request", format)
     }
 
     pub fn to_response(&self) -> HttpResponse {
         log::error!("{}", self);
-        utils::internal_server_error!()
+        match self.status_code() {
+            HttpStatusCode::SERVICE_UNAVAILABLE => HttpResponse::ServiceUnavailable().json(
+                ApiResponse::<()>::failure(self.unavailable_message(), ApiContentFormat::Json),
+            ),
+            HttpStatusCode::UNAUTHORIZED => HttpResponse::Unauthorized().finish(),
+            HttpStatusCode::FORBIDDEN => HttpResponse::Forbidden().json(
+                ApiResponse::<()>::failure(MISSING_PRIVILEGE, ApiContentFormat::Json),
+            ),
+            _ => utils::internal_server_error!(),
+        }
     }
 }
 
@@ -57,6 +119,7 @@ fn extract_session_uid(session: &Session) -> Result<Uuid, ServerFnError> {
     let Some(user) = session.get(EM_UID_SESSION_KEY)? else {
         return Err(ServerFnError::InvalidUser);
     };
+    session_expiry::check_session_expiry(session)?;
     Ok(user)
 }
 
@@ -0,0 +1,309 @@
+//! Live updates pushed to the browser over a WebSocket so tables can update rows as soon as an
+//! executor or workflow run changes, rather than waiting for the next poll.
+//!
+//! Every WebSocket text message is a JSON envelope with a `type` tag and the update `data`:
+//! ```json
+//! {"type": "executor", "data": {"executor_id": 1, "status": "Canceled"}}
+//! {"type": "workflow_run", "data": {"workflow_run_id": 1, "status": "Running", "progress": 50}}
+//! ```
+//! The envelope is produced by the database notifications on the [EXECUTOR_STATUS_CHANNEL] and
+//! [WORKFLOW_RUN_STATUS_CHANNEL] channels and validated by the portal before being forwarded.
+//! Clients that cannot open the WebSocket (or when live updates are not configured) keep using
+//! the polling refresh of each table.
+//!
+//! A single database listener is shared by every connected client. Each update is serialized once
+//! and fanned out to the clients through a [broadcast] channel. Clients are pinged every
+//! [PING_INTERVAL] and disconnected when they stop responding for [CLIENT_TIMEOUT].
+use std::time::{Duration, Instant};
+
+use actix_session::Session;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_ws::{Message, MessageStream};
+use common::{
+    database::{
+        connection::ConnectionBuilder,
+        listener::{ChannelNamespace, NotificationPayload},
+        postgres::{connection::PgConnectionBuilder, listener::spawn_broadcast},
+    },
+    error::EmResult,
+};
+use futures::StreamExt;
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use workflow_engine::{
+    database::db_options,
+    executor::data::{ExecutorId, ExecutorStatus},
+    workflow_run::data::{WorkflowRunId, WorkflowRunStatus},
+};
+
+use crate::extract_session_uid;
+
+/// Channel notified by the database whenever the status of an executor changes
+pub const EXECUTOR_STATUS_CHANNEL: &str = "exec_status";
+/// Channel notified by the database whenever the status or progress of a workflow run changes
+pub const WORKFLOW_RUN_STATUS_CHANNEL: &str = "wr_status";
+/// Maximum number of database connections held for the shared live update listener
+const MAX_LISTENER_CONNECTIONS: u32 = 2;
+/// Number of live updates buffered for each client before the oldest updates are skipped
+const UPDATE_BUFFER_SIZE: usize = 256;
+/// Time between pings sent to each WebSocket client
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+/// Time without a message from a WebSocket client before the client is disconnected
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Status change of an executor
+#[derive(Serialize, Deserialize)]
+pub struct ExecutorUpdate {
+    /// ID of the executor that changed
+    pub executor_id: ExecutorId,
+    /// New status of the executor
+    pub status: ExecutorStatus,
+}
+
+/// Status or progress change of a workflow run
+#[derive(Serialize, Deserialize)]
+pub struct WorkflowRunUpdate {
+    /// ID of the workflow run that changed
+    pub workflow_run_id: WorkflowRunId,
+    /// Current status of the workflow run
+    pub status: WorkflowRunStatus,
+    /// Current progress of the workflow run, if reported
+    pub progress: Option<i16>,
+}
+
+/// Envelope of a single live update sent to the browser. See the module documentation for the
+/// serialized format.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum LiveUpdate {
+    Executor(ExecutorUpdate),
+    WorkflowRun(WorkflowRunUpdate),
+}
+
+/// Message received from the shared live update listener
+pub enum LiveUpdateMessage {
+    /// Valid update to forward to the browser
+    Update(LiveUpdate),
+    /// Notification payload that could not be parsed as a [LiveUpdate]
+    MalformedPayload(String),
+}
+
+impl From<&str> for LiveUpdateMessage {
+    fn from(value: &str) -> Self {
+        let Ok(payload) = NotificationPayload::decode(value) else {
+            return Self::MalformedPayload(value.to_owned());
+        };
+        match serde_json::from_str(payload.body()) {
+            Ok(update) => Self::Update(update),
+            Err(_) => Self::MalformedPayload(value.to_owned()),
+        }
+    }
+}
+
+impl LiveUpdateMessage {
+    /// Serialize the [LiveUpdate] into the text sent to WebSocket clients. Returns [None] (after
+    /// logging the problem) if the message is malformed or cannot be serialized.
+    fn into_text(self) -> Option<String> {
+        match self {
+            Self::Update(update) => match serde_json::to_string(&update) {
+                Ok(text) => Some(text),
+                Err(error) => {
+                    error!("Could not serialize live update. {error}");
+                    None
+                }
+            },
+            Self::MalformedPayload(payload) => {
+                warn!("Received malformed live update payload, '{payload}'");
+                None
+            }
+        }
+    }
+}
+
+/// Shared source of live updates. A single listener bridging the executor status and workflow
+/// run status channels forwards every serialized [LiveUpdate] to all subscribed WebSocket clients.
+#[derive(Clone)]
+pub struct LiveUpdates {
+    sender: broadcast::Sender<String>,
+}
+
+impl LiveUpdates {
+    /// Create a new [LiveUpdates] using the workflow engine database options (see [db_options])
+    /// and spawn the shared listener. Returns [None] when the `WE_HOST` environment variable is
+    /// not set, disabling live updates so the portal only uses polling. Channels are namespaced
+    /// using the `EM_CHANNEL_NS` environment variable. Must be called within a tokio runtime.
+    /// # Errors
+    /// This function will return an error if the database options are only partially configured
+    pub fn from_env() -> EmResult<Option<Self>> {
+        if std::env::var("WE_HOST").is_err() {
+            return Ok(None);
+        }
+        let pool =
+            PgConnectionBuilder::create_pool_lazy(db_options()?, MAX_LISTENER_CONNECTIONS, 0);
+        let channel_namespace = ChannelNamespace::from_env();
+        let executor_channel = channel_namespace.channel(EXECUTOR_STATUS_CHANNEL);
+        let workflow_run_channel = channel_namespace.channel(WORKFLOW_RUN_STATUS_CHANNEL);
+        let sender = spawn_broadcast(
+            &pool,
+            &[&executor_channel, &workflow_run_channel],
+            UPDATE_BUFFER_SIZE,
+            LiveUpdateMessage::into_text,
+        );
+        Ok(Some(Self { sender }))
+    }
+
+    /// Subscribe to every live update sent after this call
+    fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+}
+
+pub fn service() -> actix_web::Resource {
+    web::resource("/live-updates").route(web::get().to(live_updates))
+}
+
+/// WebSocket endpoint that forwards [LiveUpdate]s to the connected client. Responds with
+/// `503 Service Unavailable` when live updates are not configured so the client falls back to
+/// polling.
+async fn live_updates(
+    req: HttpRequest,
+    body: web::Payload,
+    session: Session,
+    live_updates: Option<web::Data<LiveUpdates>>,
+) -> HttpResponse {
+    if extract_session_uid(&session).is_err() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let Some(live_updates) = live_updates else {
+        return HttpResponse::ServiceUnavailable().finish();
+    };
+    let (response, ws_session, messages) = match actix_ws::handle(&req, body) {
+        Ok(inner) => inner,
+        Err(error) => return error.error_response(),
+    };
+    actix_web::rt::spawn(forward_updates(
+        live_updates.subscribe(),
+        ws_session,
+        messages,
+    ));
+    response
+}
+
+/// Forward every live update received by the `updates` subscription to the WebSocket `session`
+/// until the client disconnects, stops responding to pings for [CLIENT_TIMEOUT] or the shared
+/// listener is dropped.
+async fn forward_updates(
+    mut updates: broadcast::Receiver<String>,
+    mut session: actix_ws::Session,
+    mut messages: MessageStream,
+) {
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    let mut last_message = Instant::now();
+    loop {
+        tokio::select! {
+            message = messages.next() => match message {
+                Some(Ok(Message::Ping(bytes))) => {
+                    last_message = Instant::now();
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => last_message = Instant::now(),
+            },
+            _ = ping.tick() => {
+                if last_message.elapsed() > CLIENT_TIMEOUT {
+                    warn!("Live updates client stopped responding to pings");
+                    break;
+                }
+                if session.ping(b"").await.is_err() {
+                    return;
+                }
+            },
+            update = updates.recv() => match update {
+                Ok(text) => {
+                    if session.text(text).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Live updates client fell behind, skipped {skipped} updates");
+                }
+                Err(RecvError::Closed) => {
+                    error!("Live updates listener closed");
+                    break;
+                }
+            },
+        }
+    }
+    let _ = session.close(None).await;
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::{LiveUpdate, LiveUpdateMessage};
+
+    #[rstest]
+    #[case::executor(
+        r#"v1:{"type":"executor","data":{"executor_id":1,"status":"Canceled"}}"#,
+        r#"{"type":"executor","data":{"executor_id":1,"status":"Canceled"}}"#
+    )]
+    #[case::workflow_run(
+        r#"v1:{"type":"workflow_run","data":{"workflow_run_id":2,"status":"Running","progress":50}}"#,
+        r#"{"type":"workflow_run","data":{"workflow_run_id":2,"status":"Running","progress":50}}"#
+    )]
+    #[case::no_progress(
+        r#"v1:{"type":"workflow_run","data":{"workflow_run_id":2,"status":"Scheduled","progress":null}}"#,
+        r#"{"type":"workflow_run","data":{"workflow_run_id":2,"status":"Scheduled","progress":null}}"#
+    )]
+    fn live_update_message_should_forward_envelope_when_valid(
+        #[case] payload: &str,
+        #[case] expected: &str,
+    ) {
+        let message = LiveUpdateMessage::from(payload);
+
+        let forwarded = match message {
+            LiveUpdateMessage::Update(update) => serde_json::to_string(&update).ok(),
+            LiveUpdateMessage::MalformedPayload(_) => None,
+        };
+        assert_eq!(forwarded.as_deref(), Some(expected));
+    }
+
+    #[rstest]
+    #[case::unknown_type(r#"v1:{"type":"job","data":{"job_id":1}}"#)]
+    #[case::missing_data(r#"v1:{"type":"executor"}"#)]
+    #[case::newer_version(r#"v9:{"type":"executor","data":{"executor_id":1,"status":"Active"}}"#)]
+    #[case::not_json("v1:42")]
+    fn live_update_message_should_be_malformed_when(#[case] payload: &str) {
+        let message = LiveUpdateMessage::from(payload);
+
+        assert!(matches!(message, LiveUpdateMessage::MalformedPayload(_)));
+    }
+
+    #[rstest]
+    #[case::valid(
+        r#"v1:{"type":"executor","data":{"executor_id":1,"status":"Canceled"}}"#,
+        Some(r#"{"type":"executor","data":{"executor_id":1,"status":"Canceled"}}"#)
+    )]
+    #[case::malformed(r#"v1:{"type":"job","data":{"job_id":1}}"#, None)]
+    fn live_update_message_into_text_should_serialize_only_valid_updates(
+        #[case] payload: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let text = LiveUpdateMessage::from(payload).into_text();
+
+        assert_eq!(text.as_deref(), expected);
+    }
+
+    #[test]
+    fn live_update_should_reject_non_positive_ids() {
+        let result = serde_json::from_str::<LiveUpdate>(
+            r#"{"type":"executor","data":{"executor_id":0,"status":"Active"}}"#,
+        );
+
+        assert!(result.is_err());
+    }
+}
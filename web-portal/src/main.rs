@@ -1,23 +1,41 @@
 use actix_session::{storage::RedisActorSessionStore, SessionMiddleware};
-use actix_web::{cookie::Key, middleware::Logger, App, HttpServer};
-use common::error::EmResult;
-use web_portal::{api, pages::Pages};
+use actix_web::{cookie::Key, middleware::Logger, web::Data, App, HttpServer};
+use common::{api::request_id::PropagateRequestId, error::EmResult, logging};
+use web_portal::{
+    api, csrf::CsrfProtection, endpoints::ServiceEndpoints, live_updates,
+    live_updates::LiveUpdates, pages::Pages, session_actor::SessionActor,
+    session_config::SessionConfig,
+};
 
 #[actix_web::main]
 async fn main() -> EmResult<()> {
-    log4rs::init_file("web-portal/web_portal_log.yml", Default::default()).unwrap();
+    logging::init("web-portal/web_portal_log.yml")?;
     let secret = std::env::var("SECRET_KEY")?;
     let secret_key = Key::from(secret.as_bytes());
     let redis_connection_string = std::env::var("REDIS_CONNECTION")?;
+    let endpoints = ServiceEndpoints::from_env();
+    let live_updates = LiveUpdates::from_env()?;
+    let session_config = SessionConfig::from_env()?;
     HttpServer::new(move || {
-        App::new()
+        let mut app = App::new().app_data(Data::new(endpoints.clone()));
+        if let Some(live_updates) = &live_updates {
+            app = app.app_data(Data::new(live_updates.clone()));
+        }
+        app.wrap(CsrfProtection)
+            .wrap(SessionActor)
             .wrap(Logger::default())
-            .wrap(SessionMiddleware::new(
-                RedisActorSessionStore::new(&redis_connection_string),
-                secret_key.clone(),
-            ))
+            .wrap(
+                session_config
+                    .apply(SessionMiddleware::builder(
+                        RedisActorSessionStore::new(&redis_connection_string),
+                        secret_key.clone(),
+                    ))
+                    .build(),
+            )
+            .wrap(PropagateRequestId)
             .service(actix_files::Files::new("/assets", "web-portal/assets").show_files_listing())
             .add_pages()
+            .service(live_updates::service())
             .service(api::service())
     })
     .bind(("127.0.0.1", 8080))?
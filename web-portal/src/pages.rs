@@ -1,6 +1,7 @@
 use actix_session::Session;
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use leptos::*;
+use serde::Deserialize;
 use users::data::{role::RoleName, user::User};
 use workflow_engine::workflow_run::data::WorkflowRunId;
 
@@ -15,77 +16,127 @@ use crate::{
             main_page::default_workflow_engine_tab_url, workflow_run_page::WorkflowRunDisplay,
         },
     },
-    extract_session_uid,
+    csrf,
+    endpoints::ServiceEndpoints,
+    extract_session_uid, return_to,
     utils::{self, html_page, HtmxResponseBuilder, HOME_LOCATION},
     ServerFnError,
 };
 
-async fn login(session: Session) -> HttpResponse {
+#[derive(Deserialize)]
+struct LoginQuery {
+    return_to: Option<String>,
+}
+
+async fn login(session: Session, query: web::Query<LoginQuery>) -> HttpResponse {
+    let return_to = query
+        .into_inner()
+        .return_to
+        .filter(|path| return_to::safe_return_to(path).is_some());
     if extract_session_uid(&session).is_ok() {
-        return utils::redirect_home!();
+        return match return_to {
+            Some(path) => HttpResponse::Found()
+                .insert_header(("location", path))
+                .finish(),
+            None => utils::redirect_home!(),
+        };
     }
+    let csrf_token = match csrf::csrf_token(&session) {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
     html_page(|cx| {
         view! { cx,
             <BasePage title="Index">
-                <LoginForm />
+                <LoginForm csrf_token=csrf_token return_to=return_to />
             </BasePage>
         }
     })
 }
 
-async fn index(session: Session) -> HttpResponse {
-    let user = match utils::get_user_session(session).await {
+async fn index(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
+    let user = match utils::get_user_session(&endpoints, session.clone()).await {
+        Ok(inner) => inner,
+        Err(ServerFnError::InvalidUser) => return utils::redirect_login!(req),
+        Err(error) => return error.to_response(),
+    };
+    let csrf_token = match csrf::csrf_token(&session) {
         Ok(inner) => inner,
-        Err(ServerFnError::InvalidUser) => return utils::redirect_login!(),
         Err(error) => return error.to_response(),
     };
     html_page(|cx| {
         view! { cx,
-            <BasePage title="Index" user=user>
+            <BasePage title="Index" user=user csrf_token=csrf_token>
             </BasePage>
         }
     })
 }
 
-async fn workflow_engine(session: Session) -> HttpResponse {
-    let user = match utils::get_user_session(session).await {
+async fn workflow_engine(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
+    let user = match utils::get_user_session(&endpoints, session.clone()).await {
+        Ok(inner) => inner,
+        Err(ServerFnError::InvalidUser) => return utils::redirect_login!(req),
+        Err(error) => return error.to_response(),
+    };
+    let csrf_token = match csrf::csrf_token(&session) {
         Ok(inner) => inner,
-        Err(ServerFnError::InvalidUser) => return utils::redirect_login!(),
         Err(error) => return error.to_response(),
     };
     html_page(|cx| {
         view! { cx,
-            <BasePage title="Index" user=user>
+            <BasePage title="Index" user=user csrf_token=csrf_token>
                 <div id="tabs" hx-get={default_workflow_engine_tab_url()} hx-trigger="load"
-                    hx-target="#tabs" hx-swap="innerHTML"></div>
+                    hx-target="#tabs" hx-swap="innerHTML" data-live-updates="/live-updates"></div>
             </BasePage>
         }
     })
 }
 
-async fn workflow_run(session: Session, workflow_run_id: web::Path<WorkflowRunId>) -> HttpResponse {
-    let user = match utils::get_user_session(session).await {
+async fn workflow_run(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+    workflow_run_id: web::Path<WorkflowRunId>,
+) -> HttpResponse {
+    let user = match utils::get_user_session(&endpoints, session.clone()).await {
+        Ok(inner) => inner,
+        Err(ServerFnError::InvalidUser) => return utils::redirect_login!(req),
+        Err(error) => return error.to_response(),
+    };
+    let workflow_run = match get_workflow_run(&endpoints, workflow_run_id.into_inner()).await {
         Ok(inner) => inner,
-        Err(ServerFnError::InvalidUser) => return utils::redirect_login!(),
         Err(error) => return error.to_response(),
     };
-    let workflow_run = match get_workflow_run(workflow_run_id.into_inner()).await {
+    let csrf_token = match csrf::csrf_token(&session) {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
+    let toast = utils::take_flash_toast(&session);
     html_page(|cx| {
         view! { cx,
-            <BasePage title="Workflow Run" user=user>
+            <BasePage title="Workflow Run" user=user csrf_token=csrf_token toast=toast>
                 <WorkflowRunDisplay workflow_run=workflow_run/>
             </BasePage>
         }
     })
 }
 
-async fn users(session: Session) -> HttpResponse {
-    let user = match utils::get_user_session(session).await {
+async fn users(
+    req: HttpRequest,
+    session: Session,
+    endpoints: web::Data<ServiceEndpoints>,
+) -> HttpResponse {
+    let user = match utils::get_user_session(&endpoints, session.clone()).await {
         Ok(inner) => inner,
-        Err(ServerFnError::InvalidUser) => return utils::redirect_login!(),
+        Err(ServerFnError::InvalidUser) => return utils::redirect_login!(req),
         Err(error) => return error.to_response(),
     };
 
@@ -95,14 +146,18 @@ async fn users(session: Session) -> HttpResponse {
         return missing_role(user, required_role);
     }
 
-    let users = match get_all_users(user.uid).await {
+    let users = match get_all_users(&endpoints, user.uid).await {
+        Ok(inner) => inner,
+        Err(error) => return error.to_response(),
+    };
+    let csrf_token = match csrf::csrf_token(&session) {
         Ok(inner) => inner,
         Err(error) => return error.to_response(),
     };
     let uid = user.uid;
     html_page(move |cx| {
         view! { cx,
-            <BasePage title="Users" user=user>
+            <BasePage title="Users" user=user csrf_token=csrf_token>
                 <UsersTable uid=uid users=users/>
             </BasePage>
         }
@@ -0,0 +1,141 @@
+use actix_web::HttpRequest;
+
+use crate::utils::{HOME_LOCATION, LOGIN_LOCATION};
+
+/// Query parameter of the login page that holds the path to return to after a successful login
+pub const RETURN_TO_PARAM: &str = "return_to";
+/// Header sent by htmx containing the full url of the page that made the request
+const HX_CURRENT_URL_HEADER: &str = "HX-Current-URL";
+
+/// Check that `path` is a same-origin relative path that can be used as a redirect target after
+/// login. Returns [None] when the path could point to another origin (e.g. `//evil.com` or
+/// `https://evil.com`), is not an absolute path within the portal or points to a location that is
+/// not a page (login page and api routes).
+pub fn safe_return_to(path: &str) -> Option<&str> {
+    if !path.starts_with('/')
+        || path.starts_with("//")
+        || path.starts_with("/\\")
+        || path.chars().any(char::is_control)
+    {
+        return None;
+    }
+    let route = path.split(['?', '#']).next().unwrap_or(path);
+    if route == LOGIN_LOCATION || route == "/api" || route.starts_with("/api/") {
+        return None;
+    }
+    Some(path)
+}
+
+/// Extract the path of the page that originated the `req`. For htmx requests, this is the page
+/// that issued the request (only if it shares the origin of the portal). Otherwise, it is the path
+/// and query of the request itself.
+pub fn originating_path(req: &HttpRequest) -> Option<String> {
+    let current_url = req
+        .headers()
+        .get(HX_CURRENT_URL_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if let Some(current_url) = current_url {
+        let connection_info = req.connection_info();
+        let (_, url) = current_url.split_once("://")?;
+        let path = url.strip_prefix(connection_info.host())?;
+        return safe_return_to(path).map(str::to_owned);
+    }
+    let path_and_query = req.uri().path_and_query()?.as_str();
+    safe_return_to(path_and_query).map(str::to_owned)
+}
+
+/// Build the location of the login page, including the `return_to` path as a query parameter if
+/// it is a valid redirect target and not the home page
+pub fn login_location(return_to: Option<&str>) -> String {
+    match return_to.and_then(safe_return_to) {
+        Some(path) if path != HOME_LOCATION => format!(
+            "{LOGIN_LOCATION}?{RETURN_TO_PARAM}={}",
+            urlencoding::encode(path)
+        ),
+        _ => LOGIN_LOCATION.to_owned(),
+    }
+}
+
+/// Location to send the user after a successful login. Uses the `return_to` path if it is a valid
+/// redirect target, otherwise the home page.
+pub fn post_login_location(return_to: Option<&str>) -> &str {
+    return_to.and_then(safe_return_to).unwrap_or(HOME_LOCATION)
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::test::TestRequest;
+    use rstest::rstest;
+
+    use super::{login_location, originating_path, post_login_location, safe_return_to};
+
+    #[rstest]
+    #[case::page("/workflow-engine")]
+    #[case::page_with_query("/users?page=2")]
+    #[case::nested_page("/workflow-engine/workflow-run/1")]
+    fn safe_return_to_should_succeed_when(#[case] path: &str) {
+        assert_eq!(safe_return_to(path), Some(path));
+    }
+
+    #[rstest]
+    #[case::external_url("https://evil.com/users")]
+    #[case::protocol_relative("//evil.com/users")]
+    #[case::backslash("/\\evil.com")]
+    #[case::relative_path("users")]
+    #[case::login_page("/login")]
+    #[case::api_route("/api/users")]
+    #[case::control_character("/users\n")]
+    fn safe_return_to_should_fail_when(#[case] path: &str) {
+        assert_eq!(safe_return_to(path), None);
+    }
+
+    #[rstest]
+    #[case::page_request(TestRequest::get().uri("/workflow-engine"), Some("/workflow-engine"))]
+    #[case::htmx_request(
+        TestRequest::post()
+            .uri("/api/workflow-engine/jobs")
+            .insert_header(("HX-Current-URL", "http://localhost:8080/workflow-engine"))
+            .insert_header(("Host", "localhost:8080")),
+        Some("/workflow-engine"),
+    )]
+    #[case::htmx_request_other_origin(
+        TestRequest::post()
+            .uri("/api/workflow-engine/jobs")
+            .insert_header(("HX-Current-URL", "http://evil.com/workflow-engine"))
+            .insert_header(("Host", "localhost:8080")),
+        None,
+    )]
+    #[case::api_request(TestRequest::post().uri("/api/users"), None)]
+    fn originating_path_should_capture_page_when(
+        #[case] request: TestRequest,
+        #[case] expected: Option<&str>,
+    ) {
+        let request = request.to_http_request();
+
+        assert_eq!(originating_path(&request).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case::return_to(Some("/workflow-engine"), "/login?return_to=%2Fworkflow-engine")]
+    #[case::home(Some("/"), "/login")]
+    #[case::external(Some("https://evil.com"), "/login")]
+    #[case::none(None, "/login")]
+    fn login_location_should_include_return_to_when(
+        #[case] return_to: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(login_location(return_to), expected);
+    }
+
+    #[rstest]
+    #[case::return_to(Some("/workflow-engine"), "/workflow-engine")]
+    #[case::external(Some("https://evil.com"), "/")]
+    #[case::protocol_relative(Some("//evil.com"), "/")]
+    #[case::none(None, "/")]
+    fn post_login_location_should_return_to_page_when(
+        #[case] return_to: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(post_login_location(return_to), expected);
+    }
+}
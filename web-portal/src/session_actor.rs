@@ -0,0 +1,64 @@
+use std::future::{ready, Ready};
+
+use actix_session::SessionExt;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use common::audit::with_actor;
+use futures::future::LocalBoxFuture;
+use uuid::Uuid;
+
+use crate::EM_UID_SESSION_KEY;
+
+/// Middleware factory that sets the uid of the logged in user as the
+/// [current_actor][common::audit::current_actor] while a request is handled. Requests made to the
+/// backend services then carry the user as their bearer token (when no other credentials are
+/// provided) so the [PropagateActor][common::audit::PropagateActor] middleware of the services can
+/// attribute mutations to the user in the audit log. Must be wrapped inside the session middleware.
+pub struct SessionActor;
+
+impl<S, B> Transform<S, ServiceRequest> for SessionActor
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    type InitError = ();
+    type Response = ServiceResponse<B>;
+    type Transform = SessionActorMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SessionActorMiddleware { service }))
+    }
+}
+
+/// Service created by the [SessionActor] middleware factory
+pub struct SessionActorMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for SessionActorMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+    type Response = ServiceResponse<B>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let uid = req
+            .get_session()
+            .get::<Uuid>(EM_UID_SESSION_KEY)
+            .ok()
+            .flatten();
+        let future = self.service.call(req);
+        match uid {
+            Some(uid) => Box::pin(with_actor(uid, future)),
+            None => Box::pin(future),
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::env;
+
+use actix_session::{
+    config::{PersistentSession, SessionMiddlewareBuilder},
+    storage::SessionStore,
+};
+use actix_web::cookie::{time::Duration, SameSite};
+use common::error::{EmError, EmResult};
+use log::warn;
+
+/// Default path of the session cookie
+const DEFAULT_COOKIE_PATH: &str = "/";
+
+/// Attributes of the session cookie set by the portal. The defaults are the secure values used
+/// when nothing is configured: a `Secure`, `HttpOnly`, `SameSite=Strict` cookie scoped to `/`
+/// with no domain that only lasts for the browser session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionConfig {
+    /// Path the session cookie is scoped to. Set when the portal is served behind a path prefix
+    pub path: String,
+    /// Domain the session cookie is scoped to. [None] scopes the cookie to the host of the portal
+    pub domain: Option<String>,
+    /// `SameSite` attribute of the session cookie
+    pub same_site: SameSite,
+    /// True if the session cookie is only sent over HTTPS
+    pub secure: bool,
+    /// Lifetime of the session cookie in seconds. [None] makes the cookie last for the browser
+    /// session
+    pub max_age: Option<i64>,
+    /// True if the portal is served to users over HTTPS (directly or through a proxy)
+    pub https: bool,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            path: DEFAULT_COOKIE_PATH.to_owned(),
+            domain: None,
+            same_site: SameSite::Strict,
+            secure: true,
+            max_age: None,
+            https: false,
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Create a new [SessionConfig] from environment variables, using the default value for any
+    /// variable that is not set. The environment variables read are:
+    /// - SESSION_COOKIE_PATH -> path of the cookie (default `/`)
+    /// - SESSION_COOKIE_DOMAIN -> domain of the cookie (default none)
+    /// - SESSION_COOKIE_SAME_SITE -> `strict`, `lax` or `none` (default `strict`)
+    /// - SESSION_COOKIE_SECURE -> `true` or `false` (default `true`)
+    /// - SESSION_COOKIE_MAX_AGE -> lifetime of the cookie in seconds (default browser session)
+    /// - PORTAL_HTTPS -> `true` if the portal is served over HTTPS (default `false`)
+    ///
+    /// Each issue found by [SessionConfig::warnings] is logged as a warning.
+    /// # Errors
+    /// This function will return an error if a value cannot be parsed
+    pub fn from_env() -> EmResult<Self> {
+        let config = Self::from_lookup(|key| env::var(key).ok())?;
+        for warning in config.warnings() {
+            warn!("{warning}");
+        }
+        Ok(config)
+    }
+
+    /// Create a new [SessionConfig] using the `lookup` function to find each configuration value
+    /// by name. Values that are not found are replaced with their default.
+    /// # Errors
+    /// This function will return an error if a value cannot be parsed
+    fn from_lookup<F>(lookup: F) -> EmResult<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let defaults = Self::default();
+        Ok(Self {
+            path: lookup("SESSION_COOKIE_PATH").unwrap_or(defaults.path),
+            domain: lookup("SESSION_COOKIE_DOMAIN").filter(|domain| !domain.trim().is_empty()),
+            same_site: match lookup("SESSION_COOKIE_SAME_SITE") {
+                Some(value) => parse_same_site(&value)?,
+                None => defaults.same_site,
+            },
+            secure: match lookup("SESSION_COOKIE_SECURE") {
+                Some(value) => parse_bool("SESSION_COOKIE_SECURE", &value)?,
+                None => defaults.secure,
+            },
+            max_age: match lookup("SESSION_COOKIE_MAX_AGE") {
+                Some(value) => Some(parse_max_age(&value)?),
+                None => defaults.max_age,
+            },
+            https: match lookup("PORTAL_HTTPS") {
+                Some(value) => parse_bool("PORTAL_HTTPS", &value)?,
+                None => defaults.https,
+            },
+        })
+    }
+
+    /// Issues with the configuration that do not stop the portal from running but weaken the
+    /// session cookie
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.https && !self.secure {
+            warnings.push(
+                "The portal is served over HTTPS but SESSION_COOKIE_SECURE is false. Session \
+                 cookies can leak over plain HTTP"
+                    .to_owned(),
+            );
+        }
+        if self.same_site == SameSite::None && !self.secure {
+            warnings.push(
+                "SESSION_COOKIE_SAME_SITE is none but SESSION_COOKIE_SECURE is false. Browsers \
+                 reject SameSite=None cookies that are not Secure"
+                    .to_owned(),
+            );
+        }
+        warnings
+    }
+
+    /// Apply the cookie attributes to the session middleware `builder`. The cookie is always
+    /// `HttpOnly` since the session is never read by scripts.
+    pub fn apply<S>(&self, builder: SessionMiddlewareBuilder<S>) -> SessionMiddlewareBuilder<S>
+    where
+        S: SessionStore,
+    {
+        let builder = builder
+            .cookie_path(self.path.clone())
+            .cookie_domain(self.domain.clone())
+            .cookie_same_site(self.same_site)
+            .cookie_secure(self.secure)
+            .cookie_http_only(true);
+        match self.max_age {
+            Some(seconds) => builder.session_lifecycle(
+                PersistentSession::default().session_ttl(Duration::seconds(seconds)),
+            ),
+            None => builder,
+        }
+    }
+}
+
+/// Parse the `SameSite` attribute `value`, ignoring case
+/// # Errors
+/// This function will return an error if the value is not `strict`, `lax` or `none`
+fn parse_same_site(value: &str) -> EmResult<SameSite> {
+    match value.trim().to_lowercase().as_str() {
+        "strict" => Ok(SameSite::Strict),
+        "lax" => Ok(SameSite::Lax),
+        "none" => Ok(SameSite::None),
+        _ => Err(EmError::Generic(format!(
+            "SESSION_COOKIE_SAME_SITE must be strict, lax or none. Found `{value}`"
+        ))),
+    }
+}
+
+/// Parse the boolean `value` of the variable `key`
+/// # Errors
+/// This function will return an error if the value is not `true` or `false`
+fn parse_bool(key: &str, value: &str) -> EmResult<bool> {
+    value
+        .trim()
+        .to_lowercase()
+        .parse()
+        .map_err(|_| EmError::Generic(format!("{key} must be true or false. Found `{value}`")))
+}
+
+/// Parse the cookie max-age `value` as a positive number of seconds
+/// # Errors
+/// This function will return an error if the value is not a positive whole number
+fn parse_max_age(value: &str) -> EmResult<i64> {
+    match value.trim().parse::<i64>() {
+        Ok(seconds) if seconds > 0 => Ok(seconds),
+        _ => Err(EmError::Generic(format!(
+            "SESSION_COOKIE_MAX_AGE must be a positive number of seconds. Found `{value}`"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::cookie::SameSite;
+    use common::test::env_lookup;
+    use rstest::rstest;
+
+    use super::SessionConfig;
+
+    #[test]
+    fn from_lookup_should_use_secure_defaults_when_not_set() {
+        let config = SessionConfig::from_lookup(env_lookup(&[])).unwrap();
+
+        assert_eq!(config, SessionConfig::default());
+        assert!(config.secure);
+        assert_eq!(config.same_site, SameSite::Strict);
+        assert!(config.warnings().is_empty());
+    }
+
+    #[test]
+    fn from_lookup_should_override_defaults_when_set() {
+        let config = SessionConfig::from_lookup(env_lookup(&[
+            ("SESSION_COOKIE_PATH", "/portal"),
+            ("SESSION_COOKIE_DOMAIN", "example.com"),
+            ("SESSION_COOKIE_SAME_SITE", "Lax"),
+            ("SESSION_COOKIE_MAX_AGE", "3600"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.path, "/portal");
+        assert_eq!(config.domain.as_deref(), Some("example.com"));
+        assert_eq!(config.same_site, SameSite::Lax);
+        assert_eq!(config.max_age, Some(3600));
+        assert!(config.secure);
+    }
+
+    #[rstest]
+    #[case::same_site(&[("SESSION_COOKIE_SAME_SITE", "sometimes")])]
+    #[case::secure(&[("SESSION_COOKIE_SECURE", "yes")])]
+    #[case::max_age(&[("SESSION_COOKIE_MAX_AGE", "0")])]
+    #[case::https(&[("PORTAL_HTTPS", "1")])]
+    fn from_lookup_should_fail_when_value_is_invalid(#[case] pairs: &[(&str, &str)]) {
+        let result = SessionConfig::from_lookup(env_lookup(pairs));
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::https_without_secure(&[("PORTAL_HTTPS", "true"), ("SESSION_COOKIE_SECURE", "false")])]
+    #[case::same_site_none_without_secure(&[
+        ("SESSION_COOKIE_SAME_SITE", "none"),
+        ("SESSION_COOKIE_SECURE", "false"),
+    ])]
+    fn warnings_should_not_be_empty_when(#[case] pairs: &[(&str, &str)]) {
+        let config = SessionConfig::from_lookup(env_lookup(pairs)).unwrap();
+
+        assert_eq!(config.warnings().len(), 1);
+    }
+
+    #[test]
+    fn warnings_should_be_empty_when_served_over_https_with_secure_cookie() {
+        let config = SessionConfig::from_lookup(env_lookup(&[("PORTAL_HTTPS", "true")])).unwrap();
+
+        assert!(config.warnings().is_empty());
+    }
+}
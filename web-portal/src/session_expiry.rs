@@ -0,0 +1,103 @@
+use actix_session::Session;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::ServerFnError;
+
+/// Session key of the timestamp (seconds since the unix epoch) when the user session expires.
+/// Sessions are stored server-side so clients cannot forge the expiry.
+pub const EXPIRES_AT_SESSION_KEY: &str = "expires_at";
+/// Length of time (in seconds) a user session is valid after login or a refresh
+pub const SESSION_LIFETIME_SECONDS: i64 = 8 * 60 * 60;
+/// Length of time (in seconds) before expiry that a user session can be refreshed
+pub const REFRESH_WINDOW_SECONDS: i64 = 30 * 60;
+
+/// Returns true if a session with the `expires_at` timestamp is expired at the time `now`
+fn is_expired(expires_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    expires_at <= now
+}
+
+/// Returns true if a session with the `expires_at` timestamp is still valid at the time `now` but
+/// will expire within the [REFRESH_WINDOW_SECONDS]
+fn is_near_expiry(expires_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    !is_expired(expires_at, now) && expires_at - now <= Duration::seconds(REFRESH_WINDOW_SECONDS)
+}
+
+/// Get the expiry of the user `session`. Returns [None] if the session has no expiry
+pub fn session_expiry(session: &Session) -> Result<Option<DateTime<Utc>>, ServerFnError> {
+    let Some(timestamp) = session.get::<i64>(EXPIRES_AT_SESSION_KEY)? else {
+        return Ok(None);
+    };
+    Ok(Utc.timestamp_opt(timestamp, 0).single())
+}
+
+/// Set the expiry of the user `session` to [SESSION_LIFETIME_SECONDS] from now. Should be called
+/// when a user logs in. Returns the new expiry.
+pub fn start_session_expiry(session: &Session) -> Result<DateTime<Utc>, ServerFnError> {
+    let expires_at = Utc::now() + Duration::seconds(SESSION_LIFETIME_SECONDS);
+    session.insert(EXPIRES_AT_SESSION_KEY, expires_at.timestamp())?;
+    Ok(expires_at)
+}
+
+/// Check that the user `session` has not expired. Sessions without an expiry are treated as
+/// expired. Expired sessions are purged and a [ServerFnError::InvalidUser] is returned.
+pub fn check_session_expiry(session: &Session) -> Result<(), ServerFnError> {
+    let expires_at = session_expiry(session)?;
+    if expires_at.is_none_or(|expires_at| is_expired(expires_at, Utc::now())) {
+        session.purge();
+        return Err(ServerFnError::InvalidUser);
+    }
+    Ok(())
+}
+
+/// Refresh the user `session` if it will expire within the [REFRESH_WINDOW_SECONDS]. A refreshed
+/// session is issued a new session key (invalidating the previous session cookie) and its expiry
+/// is extended by [SESSION_LIFETIME_SECONDS]. Sessions that are not near expiry are left
+/// unchanged. Returns the expiry of the session after the refresh.
+/// # Errors
+/// Returns a [ServerFnError::InvalidUser] if the session has expired (purging the session) or
+/// does not belong to a logged in user
+pub fn refresh_session(session: &Session) -> Result<DateTime<Utc>, ServerFnError> {
+    crate::extract_session_uid(session)?;
+    let Some(expires_at) = session_expiry(session)? else {
+        return Err(ServerFnError::InvalidUser);
+    };
+    if !is_near_expiry(expires_at, Utc::now()) {
+        return Ok(expires_at);
+    }
+    session.renew();
+    start_session_expiry(session)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use rstest::rstest;
+
+    use super::{is_expired, is_near_expiry, REFRESH_WINDOW_SECONDS};
+
+    /// Offset from now to the boundary of the refresh window, shifted by `minutes`
+    fn refresh_window(minutes: i64) -> Duration {
+        Duration::seconds(REFRESH_WINDOW_SECONDS) + Duration::minutes(minutes)
+    }
+
+    #[rstest]
+    #[case::past(Duration::minutes(-1), true)]
+    #[case::now(Duration::zero(), true)]
+    #[case::future(Duration::minutes(1), false)]
+    fn is_expired_should_return(#[case] offset: Duration, #[case] expected: bool) {
+        let now = Utc::now();
+
+        assert_eq!(is_expired(now + offset, now), expected);
+    }
+
+    #[rstest]
+    #[case::expired(Duration::minutes(-1), false)]
+    #[case::within_window(refresh_window(-1), true)]
+    #[case::window_boundary(refresh_window(0), true)]
+    #[case::outside_window(refresh_window(1), false)]
+    fn is_near_expiry_should_return(#[case] offset: Duration, #[case] expected: bool) {
+        let now = Utc::now();
+
+        assert_eq!(is_near_expiry(now + offset, now), expected);
+    }
+}
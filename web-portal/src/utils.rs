@@ -1,15 +1,26 @@
 use std::fmt::Display;
 
 use actix_session::Session;
-use actix_web::{HttpResponse, HttpResponseBuilder};
-use common::api::ApiResponseBody;
-use reqwest::{Client, IntoUrl, Method, Response};
+use actix_web::{HttpRequest, HttpResponse, HttpResponseBuilder};
+use common::{
+    api::{
+        http_client::{is_unavailable_error, shared_client},
+        request_id::{current_request_id, REQUEST_ID_HEADER},
+        ApiResponseBody, VALIDATION_MESSAGE_ITEM_PREFIX,
+    },
+    audit::{current_actor, SYSTEM_ACTOR},
+};
+use leptos::{view, IntoView};
+use reqwest::{IntoUrl, Method, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use users::data::user::User;
+use users::data::{role::RoleName, user::User};
 use uuid::Uuid;
 
-use crate::{components::modal::MODAL_ERROR_MESSAGE_ID, extract_session_uid, ServerFnError};
+use crate::{
+    components::modal::MODAL_ERROR_MESSAGE_ID, endpoints::ServiceEndpoints, extract_session_uid,
+    return_to, ServerFnError, FLASH_TOAST_SESSION_KEY,
+};
 
 async fn send_request<U, D, T>(
     url: U,
@@ -22,8 +33,14 @@ where
     D: Display,
     T: Serialize,
 {
-    let client = Client::new();
-    let mut builder = client.request(method, url);
+    let mut builder = shared_client().request(method, url);
+    if let Some(request_id) = current_request_id() {
+        builder = builder.header(REQUEST_ID_HEADER, request_id.as_str());
+    }
+    let actor = current_actor();
+    let auth = auth
+        .map(|auth| auth.to_string())
+        .or_else(|| (actor != SYSTEM_ACTOR).then(|| actor.to_string()));
     if let Some(auth) = auth {
         builder = builder.header("Authorization", format!("Bearer {auth}"))
     }
@@ -33,7 +50,12 @@ where
             .body(body)
             .header("Content-Type", "application/msgpack")
     }
-    let response = builder.send().await.map_err(ServerFnError::ApiRequest)?;
+    let response = builder.send().await.map_err(|error| {
+        if is_unavailable_error(&error) {
+            return ServerFnError::ServiceUnavailable(error);
+        }
+        ServerFnError::ApiRequest(error)
+    })?;
     Ok(response)
 }
 
@@ -41,6 +63,9 @@ async fn process_response<T>(response: Response) -> Result<ApiResponseBody<T>, S
 where
     T: Serialize + for<'de> Deserialize<'de>,
 {
+    if response.status() == StatusCode::UNAUTHORIZED {
+        return Err(ServerFnError::InvalidUser);
+    }
     if !response.status().is_success() {
         let status_code = response.status();
         let text = match response.text().await {
@@ -77,29 +102,41 @@ where
     Ok(data)
 }
 
-pub async fn get_user_session(session: Session) -> Result<User, ServerFnError> {
+pub async fn get_user_session(
+    endpoints: &ServiceEndpoints,
+    session: Session,
+) -> Result<User, ServerFnError> {
     let uid = extract_session_uid(&session)?;
-    get_user(uid, None).await
+    get_user(endpoints, uid, None).await
 }
 
-pub async fn get_user(current_uid: Uuid, other_uid: Option<Uuid>) -> Result<User, ServerFnError> {
-    let url = match other_uid {
-        Some(uid) => format!("http://127.0.0.1:8001/api/v1/user/{uid}?f=msgpack"),
-        None => "http://127.0.0.1:8001/api/v1/user?f=msgpack".to_owned(),
-    };
-    let user_response = api_request(url, Method::GET, Some(current_uid), None::<()>).await?;
-    let user = match user_response {
-        ApiResponseBody::Success(inner) => inner,
-        ApiResponseBody::Message(message) => {
-            return server_fn_error!("Expected data, got message. {}", message)
-        }
-        ApiResponseBody::Error(message) | ApiResponseBody::Failure(message) => {
-            return server_fn_error!(message)
-        }
+pub async fn get_user(
+    endpoints: &ServiceEndpoints,
+    current_uid: Uuid,
+    other_uid: Option<Uuid>,
+) -> Result<User, ServerFnError> {
+    let client = endpoints.users_api_client();
+    let user = match other_uid {
+        Some(uid) => client.fetch_user(&current_uid, &uid).await?,
+        None => client.fetch_current_user(&current_uid).await?,
     };
     Ok(user)
 }
 
+/// Require that the user specified by `uid` has been granted the `privilege` (or is an admin)
+/// before performing an action on their behalf
+pub async fn require_privilege(
+    endpoints: &ServiceEndpoints,
+    uid: Uuid,
+    privilege: RoleName,
+) -> Result<(), ServerFnError> {
+    endpoints
+        .users_api_client()
+        .require_privilege(&uid, privilege)
+        .await?;
+    Ok(())
+}
+
 pub const HOME_LOCATION: &str = "/";
 pub const LOGIN_LOCATION: &str = "/login";
 
@@ -198,14 +235,40 @@ impl HtmxResponseBuilder {
         self
     }
 
+    /// Display the error `message` within the modal's error message element. Messages that
+    /// contain multiple validation problems (lines prefixed by [VALIDATION_MESSAGE_ITEM_PREFIX])
+    /// are rendered as a bulleted list.
     pub fn modal_error_message<S>(message: S) -> HttpResponse
     where
         S: Into<String>,
     {
-        Self::new()
+        let message = message.into();
+        let (items, header): (Vec<&str>, Vec<&str>) = message
+            .lines()
+            .partition(|line| line.starts_with(VALIDATION_MESSAGE_ITEM_PREFIX));
+        let mut builder = Self::new();
+        builder
             .target(format!("#{MODAL_ERROR_MESSAGE_ID}"))
-            .swap("innerHTML")
-            .raw_body(message.into())
+            .swap("innerHTML");
+        if items.len() < 2 {
+            return builder.raw_body(message);
+        }
+        let header = header.join(" ");
+        let items: Vec<String> = items
+            .into_iter()
+            .filter_map(|item| item.strip_prefix(VALIDATION_MESSAGE_ITEM_PREFIX))
+            .map(str::to_owned)
+            .collect();
+        builder.html_chunk(move |cx| {
+            view! { cx,
+                <>
+                    <p>{header}</p>
+                    <ul>
+                        {items.into_iter().map(|item| view! { cx, <li>{item}</li> }).collect::<Vec<_>>()}
+                    </ul>
+                </>
+            }
+        })
     }
 
     pub fn static_body(&mut self, html: &'static str) -> HttpResponse {
@@ -236,6 +299,11 @@ impl HtmxResponseBuilder {
         Self::location(LOGIN_LOCATION)
     }
 
+    pub fn location_login_return(req: &HttpRequest) -> HttpResponse {
+        let return_to = return_to::originating_path(req);
+        Self::location(return_to::login_location(return_to.as_deref()))
+    }
+
     pub fn location<S>(location: S) -> HttpResponse
     where
         S: Into<String>,
@@ -246,6 +314,28 @@ impl HtmxResponseBuilder {
             .insert_header(("HX-Location", location.into()))
             .finish()
     }
+
+    /// Navigate to the `location` and display a toast with the `message` once the destination
+    /// page has loaded. The message is stashed in the `session` until the destination page reads
+    /// it using [take_flash_toast].
+    pub fn redirect_with_toast<S1, S2>(session: &Session, location: S1, message: S2) -> HttpResponse
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        if let Err(error) = session.insert(FLASH_TOAST_SESSION_KEY, message.into()) {
+            log::error!("Could not stash toast message in session. {error}");
+        }
+        Self::location(location)
+    }
+}
+
+/// Remove and return the toast message stashed in the `session` by
+/// [HtmxResponseBuilder::redirect_with_toast], if any
+pub fn take_flash_toast(session: &Session) -> Option<String> {
+    session
+        .remove_as::<String>(FLASH_TOAST_SESSION_KEY)
+        .and_then(Result::ok)
 }
 
 macro_rules! server_fn_error {
@@ -259,14 +349,6 @@ macro_rules! server_fn_error {
         Err(crate::ServerFnError::Generic($string.to_owned()))
     };
 }
-macro_rules! server_fn_static_error {
-    ($item:ident) => {
-        Err(crate::ServerFnError::StaticGeneric($item))
-    };
-    ($item:literal) => {
-        Err(crate::ServerFnError::StaticGeneric($item))
-    };
-}
 
 macro_rules! internal_server_error {
     ($error:ident) => {
@@ -307,6 +389,16 @@ macro_rules! redirect_login {
             .insert_header(("location", "/login"))
             .finish()
     };
+    ($req:ident) => {
+        HttpResponse::Found()
+            .insert_header((
+                "location",
+                crate::return_to::login_location(
+                    crate::return_to::originating_path(&$req).as_deref(),
+                ),
+            ))
+            .finish()
+    };
 }
 
 macro_rules! redirect_login_htmx {
@@ -315,6 +407,16 @@ macro_rules! redirect_login_htmx {
             .insert_header(("HX-Redirect", "/login"))
             .finish()
     };
+    ($req:ident) => {
+        HttpResponse::Found()
+            .insert_header((
+                "HX-Redirect",
+                crate::return_to::login_location(
+                    crate::return_to::originating_path(&$req).as_deref(),
+                ),
+            ))
+            .finish()
+    };
 }
 
 pub(crate) use internal_server_error;
@@ -322,4 +424,109 @@ pub(crate) use redirect_home;
 pub(crate) use redirect_login;
 pub(crate) use redirect_login_htmx;
 pub(crate) use server_fn_error;
-pub(crate) use server_fn_static_error;
+
+#[cfg(test)]
+mod test {
+    use actix_web::http::StatusCode;
+    use common::test::{refused_url, stub_api_success, stub_http_response};
+    use reqwest::Method;
+    use users::data::role::{Role, RoleName};
+    use uuid::Uuid;
+
+    use super::{api_request, get_user, require_privilege};
+    use crate::{
+        endpoints::ServiceEndpoints, ServerFnError, AUTH_SERVICE_UNAVAILABLE, MISSING_PRIVILEGE,
+    };
+
+    #[tokio::test]
+    async fn api_request_should_fail_with_service_unavailable_when_connection_refused() {
+        let url = refused_url().unwrap();
+
+        let result = api_request::<_, Uuid, (), ()>(url, Method::GET, None, None::<()>).await;
+
+        let Err(error) = result else {
+            panic!("Expected an error");
+        };
+        assert!(matches!(error, ServerFnError::ServiceUnavailable(_)));
+        assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn api_request_should_fail_with_invalid_user_when_upstream_responds_unauthorized() {
+        let url = stub_http_response("401 Unauthorized", None, Vec::new())
+            .await
+            .unwrap();
+
+        let result = api_request::<_, Uuid, (), ()>(url, Method::GET, None, None::<()>).await;
+
+        let Err(error) = result else {
+            panic!("Expected an error");
+        };
+        assert!(matches!(error, ServerFnError::InvalidUser));
+        assert_eq!(error.status_code(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn get_user_should_respond_service_unavailable_when_users_service_is_down() {
+        let endpoints = ServiceEndpoints::new(refused_url().unwrap(), refused_url().unwrap());
+
+        let Err(error) = get_user(&endpoints, Uuid::nil(), None).await else {
+            panic!("Expected an error");
+        };
+        let response = error.to_response();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains(AUTH_SERVICE_UNAVAILABLE));
+    }
+
+    #[tokio::test]
+    async fn get_user_should_respond_unauthorized_when_users_service_rejects_user() {
+        let users_url = stub_http_response("401 Unauthorized", None, Vec::new())
+            .await
+            .unwrap();
+        let endpoints = ServiceEndpoints::new(refused_url().unwrap(), users_url);
+
+        let Err(error) = get_user(&endpoints, Uuid::nil(), None).await else {
+            panic!("Expected an error");
+        };
+
+        assert_eq!(error.to_response().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn require_privilege_should_respond_forbidden_when_user_missing_privilege() {
+        let users_url = stub_api_success(Vec::<Role>::new()).await.unwrap();
+        let endpoints = ServiceEndpoints::new(refused_url().unwrap(), users_url);
+
+        let Err(error) =
+            require_privilege(&endpoints, Uuid::nil(), RoleName::ManageWorkflowEngine).await
+        else {
+            panic!("Expected an error");
+        };
+        let response = error.to_response();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains(MISSING_PRIVILEGE));
+    }
+
+    #[tokio::test]
+    async fn require_privilege_should_succeed_when_user_has_privilege() {
+        let role = Role {
+            name: RoleName::ManageWorkflowEngine,
+            description: RoleName::ManageWorkflowEngine.description().to_owned(),
+        };
+        let users_url = stub_api_success(vec![role]).await.unwrap();
+        let endpoints = ServiceEndpoints::new(refused_url().unwrap(), users_url);
+
+        let result =
+            require_privilege(&endpoints, Uuid::nil(), RoleName::ManageWorkflowEngine).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -1,11 +1,39 @@
-use std::net::ToSocketAddrs;
+use std::{
+    env,
+    future::{ready, Ready},
+    rc::Rc,
+    thread::available_parallelism,
+};
 
-use actix_web::{web::Data, App, HttpServer};
-use common::{database::Database, error::EmResult};
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web::{Data, Query},
+    App, HttpServer,
+};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use common::{
+    api::{
+        health,
+        id::path_config,
+        request::{ApiRequestConfig, DEFAULT_API_REQUEST_LIMIT},
+        request_id::PropagateRequestId,
+        tls::TlsConfig,
+        ApiResponse, QueryApiFormat,
+    },
+    audit::{self, AuditSink, PropagateActor},
+    database::Database,
+    error::{EmError, EmResult},
+};
+use futures::future::LocalBoxFuture;
+use log::error;
+use users::{client::UsersApiClient, data::role::RoleName};
+use uuid::Uuid;
 
 use crate::{
     executor::{api as executors_api, service::ExecutorService},
     job::{api as jobs_api, service::JobService},
+    metrics::{self, EngineMetrics},
     workflow::{
         api as workflows_api,
         service::{TaskService, WorkflowsService},
@@ -16,14 +44,224 @@ use crate::{
     },
 };
 
+/// Default address the API server binds to when `WE_API_ADDR` is not set
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8000";
+/// Default maximum number of pool connections when `WE_MAX_CONN` is not set
+const DEFAULT_MAX_CONNECTIONS: u32 = 20;
+/// Default minimum number of pool connections when `WE_MIN_CONN` is not set
+const DEFAULT_MIN_CONNECTIONS: u32 = 1;
+/// Default base url of the users API when `WE_USERS_URL` is not set
+const DEFAULT_USERS_URL: &str = "http://127.0.0.1:8001";
+
+/// Configuration of the workflow engine API server. Contains the `address` to bind, the pool
+/// sizing used when creating the database pool, the number of Actix `workers` to spawn, the
+/// optional `tls` configuration used to serve the API over HTTPS and the `users_url` of the users
+/// API that authorizes destructive actions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// Address (host and port) that the server binds to
+    pub address: String,
+    /// Maximum number of connections held by the database pool
+    pub max_connections: u32,
+    /// Minimum number of connections held by the database pool
+    pub min_connections: u32,
+    /// Number of worker threads spawned by the server
+    pub workers: usize,
+    /// Certificate and key used to serve HTTPS. When [None], the server binds using plain HTTP
+    pub tls: Option<TlsConfig>,
+    /// Maximum size (in bytes) of an API request body. Larger bodies are rejected with a
+    /// `413 Payload Too Large` response
+    pub max_request_body_size: usize,
+    /// Base url (scheme, host and port) of the users API consulted to check the privileges of
+    /// callers performing destructive actions
+    pub users_url: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: DEFAULT_ADDRESS.to_owned(),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            min_connections: DEFAULT_MIN_CONNECTIONS,
+            workers: default_workers(),
+            tls: None,
+            max_request_body_size: DEFAULT_API_REQUEST_LIMIT,
+            users_url: DEFAULT_USERS_URL.to_owned(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Create a new [ServerConfig] from environment variables, using the default value for any
+    /// variable that is not set. The environment variables read are:
+    /// - WE_API_ADDR -> address the server binds to (default `127.0.0.1:8000`)
+    /// - WE_MAX_CONN -> maximum number of pool connections (default 20)
+    /// - WE_MIN_CONN -> minimum number of pool connections (default 1)
+    /// - WE_WORKERS -> number of server workers (default is the number of available cores)
+    /// - WE_TLS_CERT -> path to the PEM certificate chain used to serve HTTPS (default is no TLS)
+    /// - WE_TLS_KEY -> path to the PEM private key used to serve HTTPS (default is no TLS)
+    /// - WE_MAX_BODY_SIZE -> maximum size of an API request body in bytes (default 2MB)
+    /// - WE_USERS_URL -> base url of the users API (default `http://127.0.0.1:8001`)
+    /// # Errors
+    /// This function will return an error if a numeric environment variable cannot be parsed or
+    /// only one of the TLS variables is set
+    pub fn from_env() -> EmResult<Self> {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Create a new [ServerConfig] using the `lookup` function to find each configuration value by
+    /// name. Values that are not found are replaced with their default.
+    /// # Errors
+    /// This function will return an error if a numeric value cannot be parsed or only one of the
+    /// TLS values is found
+    fn from_lookup<F>(lookup: F) -> EmResult<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let defaults = Self::default();
+        Ok(Self {
+            address: lookup("WE_API_ADDR").unwrap_or(defaults.address),
+            max_connections: match lookup("WE_MAX_CONN") {
+                Some(value) => value.parse()?,
+                None => defaults.max_connections,
+            },
+            min_connections: match lookup("WE_MIN_CONN") {
+                Some(value) => value.parse()?,
+                None => defaults.min_connections,
+            },
+            workers: match lookup("WE_WORKERS") {
+                Some(value) => value.parse()?,
+                None => defaults.workers,
+            },
+            tls: TlsConfig::from_lookup("WE_TLS_CERT", "WE_TLS_KEY", &lookup)?,
+            max_request_body_size: match lookup("WE_MAX_BODY_SIZE") {
+                Some(value) => value.parse()?,
+                None => defaults.max_request_body_size,
+            },
+            users_url: lookup("WE_USERS_URL").unwrap_or(defaults.users_url),
+        })
+    }
+}
+
+/// Default number of server workers, matching the number of available cores (or 1 if that cannot
+/// be determined)
+fn default_workers() -> usize {
+    available_parallelism().map_or(1, |count| count.get())
+}
+
+/// Require that the user authenticated by the `bearer` token has been granted the `privilege` (or
+/// is an admin), consulting the users API through the `users_client`. Returns the uid of the user
+/// so route handlers can continue processing the request on behalf of the user.
+/// # Errors
+/// This function will return an error if:
+/// - the bearer token is not a valid uid ([EmError::InvalidUser], 401)
+/// - the users API cannot be reached or rejects the user
+/// - the user does not have the `privilege` ([EmError::MissingPrivilege], 403)
+pub(crate) async fn require_privilege(
+    users_client: &UsersApiClient,
+    bearer: &BearerAuth,
+    privilege: RoleName,
+) -> EmResult<Uuid> {
+    let Ok(uid) = bearer.token().parse() else {
+        error!("Got invalid bearer token. Token = '{}'", bearer.token());
+        return Err(EmError::InvalidUser);
+    };
+    users_client.require_privilege(&uid, privilege).await?;
+    Ok(uid)
+}
+
+/// Middleware factory that requires the user authenticated by the request's bearer token to have
+/// been granted the wrapped [RoleName] (see [require_privilege]) before the wrapped service is
+/// called. The privileges are checked through the [UsersApiClient] registered as app data.
+/// Rejected requests receive an [ApiResponse] failure, in the format requested by the `f` query
+/// parameter, with the status code mapped from the error (e.g. `403 Forbidden` when the privilege
+/// is missing).
+pub struct RequirePrivilege(pub RoleName);
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePrivilege
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    type InitError = ();
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Transform = RequirePrivilegeMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePrivilegeMiddleware {
+            service: Rc::new(service),
+            privilege: self.0,
+        }))
+    }
+}
+
+/// Service created by the [RequirePrivilege] middleware factory
+pub struct RequirePrivilegeMiddleware<S> {
+    service: Rc<S>,
+    privilege: RoleName,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePrivilegeMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+    type Response = ServiceResponse<EitherBody<B>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let privilege = self.privilege;
+        Box::pin(async move {
+            let bearer = req.extract::<BearerAuth>().await?;
+            let result = match req.app_data::<Data<UsersApiClient>>() {
+                Some(users_client) => require_privilege(users_client, &bearer, privilege).await,
+                None => Err(EmError::Generic(
+                    "Users API client is not registered with the server".to_owned(),
+                )),
+            };
+            if let Err(error) = result {
+                let format = Query::<QueryApiFormat>::from_query(req.query_string())
+                    .map(|query| query.f)
+                    .unwrap_or_default();
+                let response =
+                    ApiResponse::<()>::into_http_with_status(error, format, req.request());
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+            let response = service.call(req).await?;
+            Ok(response.map_into_left_body())
+        })
+    }
+}
+
 /// Run generic API server. Creates all the required endpoints and resources. To run the api server,
 /// you must have created an [ExecutorService], [WorkflowRunsService], [TaskQueueService],
 /// [TaskService], [WorkflowsService] and [JobService] for your desired [Database] implementation.
 /// Each component depends on a [Database] type so the system cannot contain disjointed service
-/// implementations to operate.
+/// implementations to operate. The `pool` is used for the `/health` and `/ready` probes which are
+/// mounted at the root of the server, outside the `/api/v1` scope. The `engine_metrics` are
+/// exported in the Prometheus text format by the `/metrics` endpoint, also mounted at the root of
+/// the server. Every request is assigned a request id (see [PropagateRequestId]) that is included
+/// in the log lines written while handling the request. The user a request is made on behalf of is
+/// read from the request's bearer token by [PropagateActor] so mutations can be attributed in the
+/// [AuditEvent][audit::AuditEvent]s recorded by the `audit_sink`, which are queried through the
+/// `/api/v1/audit` endpoint. Destructive routes check the privileges of the caller through the
+/// users API found at the `config`'s `users_url`. The server binds to the `address` and spawns the
+/// number of `workers` specified in the `config`. If the `config` contains a [TlsConfig], the
+/// certificate and key are loaded before the server starts and the server binds using HTTPS,
+/// otherwise plain HTTP is used. Request bodies larger than the `config`'s `max_request_body_size`
+/// are rejected with a `413 Payload Too Large` response. A route can override the limit by
+/// registering its own [ApiRequestConfig] as app data.
 /// # Errors
-/// This function will return an error if the server is unable to bind to the specified `address` or
-/// the server's `run` method returns an error
+/// This function will return an error if the TLS certificate or key cannot be loaded, the server is
+/// unable to bind to the configured `address` or the server's `run` method returns an error
 #[allow(clippy::too_many_lines, clippy::too_many_arguments)]
 pub async fn spawn_api_server<A, D, E, J, Q, R, T, W>(
     executor_service: E,
@@ -32,11 +270,15 @@ pub async fn spawn_api_server<A, D, E, J, Q, R, T, W>(
     task_service: T,
     workflow_service: W,
     job_service: J,
-    address: A,
+    audit_sink: A,
+    pool: D::ConnectionPool,
+    engine_metrics: EngineMetrics,
+    config: &ServerConfig,
 ) -> EmResult<()>
 where
-    A: ToSocketAddrs,
-    D: Database,
+    A: AuditSink,
+    D: Database + 'static,
+    D::ConnectionPool: Send + Sync + 'static,
     E: ExecutorService<Database = D> + Send + Sync + 'static,
     J: JobService<Database = D, WorkflowRunService = R> + Send + Sync + 'static,
     Q: TaskQueueService<Database = D, WorkflowRunService = R> + Send + Sync + 'static,
@@ -50,25 +292,125 @@ where
     let tasks_service_data = Data::new(task_service);
     let workflows_service_data = Data::new(workflow_service);
     let jobs_service_data = Data::new(job_service);
-    HttpServer::new(move || {
-        App::new().service(
-            actix_web::web::scope("/api/v1")
-                .app_data(executors_service_data.clone())
-                .app_data(jobs_service_data.clone())
-                .app_data(task_queue_service_data.clone())
-                .app_data(tasks_service_data.clone())
-                .app_data(workflow_runs_service_data.clone())
-                .app_data(workflows_service_data.clone())
-                .service(executors_api::service::<E>())
-                .service(jobs_api::service::<J>())
-                .service(workflow_runs_api::task_queue_service::<Q, R>())
-                .service(workflow_runs_api::workflow_runs_service::<R>())
-                .service(workflows_api::tasks_service::<T>())
-                .service(workflows_api::workflows_service::<W>()),
-        )
+    let audit_sink_data = Data::new(audit_sink);
+    let users_client_data = Data::new(UsersApiClient::new(format!(
+        "{}/api/v1",
+        config.users_url.trim_end_matches('/')
+    )));
+    let pool_data = Data::new(pool);
+    let registry_data = Data::new(engine_metrics.registry().clone());
+    let metrics_data = Data::new(engine_metrics);
+    let tls_config = match &config.tls {
+        Some(tls) => Some(tls.load().await?),
+        None => None,
+    };
+    let request_config = ApiRequestConfig::default().limit(config.max_request_body_size);
+    let server = HttpServer::new(move || {
+        App::new()
+            .wrap(PropagateActor)
+            .wrap(PropagateRequestId)
+            .app_data(path_config())
+            .app_data(request_config.clone())
+            .app_data(pool_data.clone())
+            .app_data(registry_data.clone())
+            .app_data(metrics_data.clone())
+            .app_data(executors_service_data.clone())
+            .app_data(jobs_service_data.clone())
+            .service(health::service::<D>())
+            .service(metrics::service::<D, E, J>())
+            .service(
+                actix_web::web::scope("/api/v1")
+                    .app_data(task_queue_service_data.clone())
+                    .app_data(tasks_service_data.clone())
+                    .app_data(workflow_runs_service_data.clone())
+                    .app_data(workflows_service_data.clone())
+                    .app_data(audit_sink_data.clone())
+                    .app_data(users_client_data.clone())
+                    .service(audit::service::<A>())
+                    .service(executors_api::service::<E, R>())
+                    .service(jobs_api::service::<J>())
+                    .service(workflow_runs_api::task_queue_service::<Q, R>())
+                    .service(workflow_runs_api::workflow_runs_service::<R>())
+                    .service(workflows_api::tasks_service::<T>())
+                    .service(workflows_api::workflows_service::<W, R>()),
+            )
     })
-    .bind(address)? //("127.0.0.1", 8080))?;
-    .run()
-    .await?;
+    .workers(config.workers);
+    let server = match tls_config {
+        Some(tls_config) => server.bind_rustls(config.address.as_str(), tls_config)?,
+        None => server.bind(config.address.as_str())?,
+    };
+    server.run().await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use common::api::tls::TlsConfig;
+    use rstest::rstest;
+
+    use super::ServerConfig;
+
+    #[rstest]
+    #[case::empty(HashMap::new(), ServerConfig::default())]
+    #[case::all_values(
+        HashMap::from([
+            ("WE_API_ADDR", "0.0.0.0:9000"),
+            ("WE_MAX_CONN", "50"),
+            ("WE_MIN_CONN", "5"),
+            ("WE_WORKERS", "2"),
+            ("WE_TLS_CERT", "cert.pem"),
+            ("WE_TLS_KEY", "key.pem"),
+            ("WE_MAX_BODY_SIZE", "1024"),
+            ("WE_USERS_URL", "http://users:8001"),
+        ]),
+        ServerConfig {
+            address: "0.0.0.0:9000".to_owned(),
+            max_connections: 50,
+            min_connections: 5,
+            workers: 2,
+            tls: Some(TlsConfig { cert_path: "cert.pem".into(), key_path: "key.pem".into() }),
+            max_request_body_size: 1024,
+            users_url: "http://users:8001".to_owned(),
+        },
+    )]
+    #[case::partial_values(
+        HashMap::from([("WE_MAX_CONN", "10")]),
+        ServerConfig { max_connections: 10, ..ServerConfig::default() },
+    )]
+    fn from_lookup_should_succeed_when(
+        #[case] values: HashMap<&str, &str>,
+        #[case] expected: ServerConfig,
+    ) {
+        let result =
+            ServerConfig::from_lookup(|key| values.get(key).map(|value| (*value).to_owned()));
+
+        assert!(matches!(result, Ok(config) if config == expected));
+    }
+
+    #[rstest]
+    #[case::max_connections("WE_MAX_CONN")]
+    #[case::min_connections("WE_MIN_CONN")]
+    #[case::workers("WE_WORKERS")]
+    #[case::max_request_body_size("WE_MAX_BODY_SIZE")]
+    fn from_lookup_should_fail_when_value_is_not_numeric(#[case] key: &str) {
+        let result = ServerConfig::from_lookup(|lookup_key| {
+            (lookup_key == key).then(|| "not a number".to_owned())
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::cert_only("WE_TLS_CERT")]
+    #[case::key_only("WE_TLS_KEY")]
+    fn from_lookup_should_fail_when_tls_partially_configured(#[case] key: &str) {
+        let result = ServerConfig::from_lookup(|lookup_key| {
+            (lookup_key == key).then(|| "file.pem".to_owned())
+        });
+
+        assert!(result.is_err());
+    }
+}
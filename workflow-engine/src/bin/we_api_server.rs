@@ -1,28 +1,74 @@
 use common::{
-    database::{postgres::Postgres, Database},
+    audit::postgres::PgAuditSink,
+    database::{
+        build::{run_build, startup_build_requested},
+        connection::{ConnectionBuilder, DualPool, PoolSamplerConfig},
+        postgres::{build::PgDatabaseBuilder, connection::PgConnectionBuilder, Postgres},
+        Database,
+    },
     error::EmResult,
+    logging, package_dir,
 };
+use log::info;
 use workflow_engine::{
-    api,
-    database::db_options,
+    api::{self, ServerConfig},
+    database::{db_options, replica_db_options, self_test},
     executor::service::postgres::PgExecutorService,
     job::service::postgres::PgJobsService,
+    metrics::EngineMetrics,
     workflow::service::postgres::{PgTasksService, PgWorkflowsService},
-    workflow_run::service::postgres::{PgTaskQueueService, PgWorkflowRunsService},
+    workflow_run::{
+        service::postgres::{PgTaskQueueService, PgWorkflowRunsService},
+        supervisor::{spawn_orphan_recovery, RecoveryConfig},
+    },
 };
 
 #[tokio::main]
 async fn main() -> EmResult<()> {
-    log4rs::init_file("workflow-engine/api_server_log.yml", Default::default()).unwrap();
+    logging::init("workflow-engine/api_server_log.yml")?;
+    let config = ServerConfig::from_env()?;
     let options = db_options()?;
-    let pool = Postgres::create_pool(options, 20, 1).await?;
+    let pool =
+        Postgres::create_pool(options, config.max_connections, config.min_connections).await?;
+    if startup_build_requested("WE_DB_BUILD") {
+        let report =
+            run_build::<PgDatabaseBuilder, _>(pool.clone(), package_dir()?.join("database"))
+                .await?;
+        info!("Applied workflow engine database build. {report:?}");
+    }
+    if self_test::self_test_requested() {
+        self_test::run_self_test(&pool).await?;
+    }
+    PgConnectionBuilder::spawn_pool_sampler(
+        &pool,
+        config.max_connections,
+        PoolSamplerConfig::from_env()?,
+    );
+    let pools = match replica_db_options()? {
+        Some(replica_options) => {
+            let replica_pool = Postgres::create_pool(
+                replica_options,
+                config.max_connections,
+                config.min_connections,
+            )
+            .await?;
+            DualPool::new(&pool, &replica_pool)
+        }
+        None => DualPool::single(&pool),
+    };
 
-    let executor_service = PgExecutorService::new(&pool);
+    let audit_sink = PgAuditSink::new(&pool);
+    let executor_service = PgExecutorService::new(&pool).with_audit_sink(&audit_sink);
     let task_service = PgTasksService::new(&pool);
     let workflow_service = PgWorkflowsService::new(&pool);
-    let workflow_runs_service = PgWorkflowRunsService::new(&pool, &workflow_service);
-    let task_queue_service = PgTaskQueueService::new(&pool, &workflow_runs_service);
-    let job_service = PgJobsService::new(&pool, &workflow_runs_service);
+    let workflow_runs_service =
+        PgWorkflowRunsService::with_pools(&pools, &workflow_service).with_audit_sink(&audit_sink);
+    spawn_orphan_recovery(&workflow_runs_service, RecoveryConfig::from_env()?);
+    let engine_metrics = EngineMetrics::new()?;
+    let task_queue_service =
+        PgTaskQueueService::new(&pool, &workflow_runs_service).with_metrics(&engine_metrics);
+    let job_service =
+        PgJobsService::new(&pool, &workflow_runs_service).with_audit_sink(&audit_sink);
     api::spawn_api_server(
         executor_service,
         workflow_runs_service,
@@ -30,8 +76,13 @@ async fn main() -> EmResult<()> {
         task_service,
         workflow_service,
         job_service,
-        ("127.0.0.1", 8000),
+        audit_sink,
+        pool.clone(),
+        engine_metrics,
+        &config,
     )
     .await?;
+    Postgres::close_pool(pools.replica()).await;
+    Postgres::close_pool(pools.primary()).await;
     Ok(())
 }
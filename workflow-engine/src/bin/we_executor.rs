@@ -1,28 +1,88 @@
+use std::{env, time::Duration};
+
 use common::{
-    database::{postgres::Postgres, Database},
-    error::EmResult,
+    database::{
+        connection::{ConnectionBuilder, PoolSamplerConfig},
+        postgres::{connection::PgConnectionBuilder, Postgres},
+        Database,
+    },
+    error::{EmError, EmResult},
+    logging,
 };
 use log::{error, info};
 use workflow_engine::{
-    database::db_options,
-    executor::{service::postgres::PgExecutorService, worker::Executor},
+    database::{db_options, self_test},
+    executor::{
+        service::postgres::PgExecutorService,
+        worker::{Executor, DEFAULT_HEARTBEAT_INTERVAL},
+    },
+    metrics::{self, EngineMetrics},
     workflow::service::postgres::PgWorkflowsService,
-    workflow_run::service::postgres::{PgTaskQueueService, PgWorkflowRunsService},
+    workflow_run::{
+        data::TaskUrlVariables,
+        service::postgres::{PgTaskQueueService, PgWorkflowRunsService},
+    },
 };
 
+/// Maximum number of connections held by the executor database pool
+const MAX_CONNECTIONS: u32 = 20;
+
 #[tokio::main]
 async fn main() -> EmResult<()> {
-    log4rs::init_file("workflow-engine/executor_log.yml", Default::default()).unwrap();
+    logging::init("workflow-engine/executor_log.yml")?;
 
     info!("Initializing Executor");
     let options = db_options()?;
-    let pool = Postgres::create_pool(options, 20, 1).await?;
-    let executor_service = PgExecutorService::new(&pool);
+    let pool = Postgres::create_pool(options, MAX_CONNECTIONS, 1).await?;
+    if self_test::self_test_requested() {
+        self_test::run_self_test(&pool).await?;
+    }
+    PgConnectionBuilder::spawn_pool_sampler(&pool, MAX_CONNECTIONS, PoolSamplerConfig::from_env()?);
+    let heartbeat_interval = match env::var("WE_HEARTBEAT_INTERVAL") {
+        Ok(value) => match value.parse()? {
+            0 => {
+                return Err(EmError::Generic(
+                    "WE_HEARTBEAT_INTERVAL must be greater than 0 seconds".to_owned(),
+                ))
+            }
+            seconds => Duration::from_secs(seconds),
+        },
+        Err(_) => DEFAULT_HEARTBEAT_INTERVAL,
+    };
+    let heartbeat_threshold = match env::var("WE_HEARTBEAT_THRESHOLD") {
+        Ok(value) => Duration::from_secs(value.parse()?),
+        Err(_) => heartbeat_interval * 4,
+    };
+    let executor_service =
+        PgExecutorService::new(&pool).with_heartbeat_threshold(heartbeat_threshold);
     let workflow_service = PgWorkflowsService::new(&pool);
-    let wr_service = PgWorkflowRunsService::new(&pool, &workflow_service);
-    let tq_service = PgTaskQueueService::new(&pool, &wr_service);
-    let executor = match Executor::new(&executor_service, &wr_service, &tq_service).await {
-        Ok(executor) => executor,
+    let priority_aging = match env::var("WE_PRIORITY_AGING") {
+        Ok(value) => value.parse()?,
+        Err(_) => 0.0,
+    };
+    let wr_service =
+        PgWorkflowRunsService::new(&pool, &workflow_service).with_priority_aging(priority_aging);
+    let engine_metrics = EngineMetrics::new()?;
+    if let Ok(address) = env::var("WE_EXECUTOR_METRICS_ADDRESS") {
+        metrics::spawn_metrics_server(&engine_metrics, &address)?;
+        info!("Exporting executor metrics at http://{address}/metrics");
+    }
+    let tq_service = PgTaskQueueService::new(&pool, &wr_service)
+        .with_url_variables(TaskUrlVariables::from_env())
+        .with_metrics(&engine_metrics);
+    let max_concurrent_runs = match env::var("WE_MAX_CONCURRENT_RUNS") {
+        Ok(value) => Some(value.parse()?),
+        Err(_) => None,
+    };
+    let executor = match Executor::new(
+        &executor_service,
+        &wr_service,
+        &tq_service,
+        max_concurrent_runs,
+    )
+    .await
+    {
+        Ok(executor) => executor.with_heartbeat_interval(heartbeat_interval),
         Err(error) => {
             error!("{}", error);
             return Ok(());
@@ -32,6 +92,7 @@ async fn main() -> EmResult<()> {
 
     info!("Running Executor, id = {}", executor_id);
     executor.run().await;
+    Postgres::close_pool(&pool).await;
     info!("Exiting executor, id = {}", executor_id);
     Ok(())
 }
@@ -1,26 +1,38 @@
 use common::{
-    database::{connection::ConnectionBuilder, postgres::connection::PgConnectionBuilder},
-    email::ClippyEmailService,
+    database::{
+        connection::{ConnectionBuilder, PoolSamplerConfig},
+        postgres::{connection::PgConnectionBuilder, Postgres},
+        Database,
+    },
+    email::Mailer,
     error::EmResult,
+    logging,
 };
 use log::{error, info};
 use workflow_engine::{
-    database::db_options,
+    database::{db_options, self_test},
     job::{service::postgres::PgJobsService, worker::JobWorker},
     workflow::service::postgres::PgWorkflowsService,
     workflow_run::service::postgres::PgWorkflowRunsService,
 };
 
+/// Maximum number of connections held by the job worker database pool
+const MAX_CONNECTIONS: u32 = 20;
+
 #[tokio::main]
 async fn main() -> EmResult<()> {
-    log4rs::init_file("workflow-engine/job_worker_log.yml", Default::default()).unwrap();
+    logging::init("workflow-engine/job_worker_log.yml")?;
 
     info!("Initializing Worker");
-    let pool = PgConnectionBuilder::create_pool(db_options()?, 20, 1).await?;
+    let pool = PgConnectionBuilder::create_pool(db_options()?, MAX_CONNECTIONS, 1).await?;
     let workflow_service = PgWorkflowsService::new(&pool);
     let workflow_runs_service = PgWorkflowRunsService::new(&pool, &workflow_service);
     let jobs_service = PgJobsService::new(&pool, &workflow_runs_service);
-    let email_service = ClippyEmailService::new()?;
+    let email_service = Mailer::from_env()?;
+    if self_test::self_test_requested() {
+        self_test::run_worker_self_test(&pool, &email_service).await?;
+    }
+    PgConnectionBuilder::spawn_pool_sampler(&pool, MAX_CONNECTIONS, PoolSamplerConfig::from_env()?);
     let worker = match JobWorker::new(jobs_service, email_service) {
         Ok(worker) => worker,
         Err(error) => {
@@ -34,6 +46,7 @@ async fn main() -> EmResult<()> {
         error!("Error during worker run\n{}", error)
     }
 
+    Postgres::close_pool(&pool).await;
     info!("Exiting Worker");
     Ok(())
 }
@@ -1,6 +1,11 @@
+pub mod self_test;
+
 use std::env;
 
-use common::error::EmResult;
+use common::{
+    database::{listener::ChannelNamespace, postgres::listener::with_channel_namespace},
+    error::EmResult,
+};
 use sqlx::postgres::PgConnectOptions;
 
 /// Return database connect options
@@ -13,6 +18,9 @@ use sqlx::postgres::PgConnectOptions;
 /// - WE_DB -> name of the database to connect
 /// - WE_USER -> name of the user to connect as
 /// - WE_PASSWORD -> password of the user to connect as
+///
+/// If the `EM_CHANNEL_NS` environment variable is set, every session uses it as the
+/// [ChannelNamespace] for notifications sent by the database.
 pub fn db_options() -> EmResult<PgConnectOptions> {
     let port = env::var("WE_PORT")?.parse()?;
     let options = PgConnectOptions::new()
@@ -21,20 +29,53 @@ pub fn db_options() -> EmResult<PgConnectOptions> {
         .database(&env::var("WE_DB")?)
         .username(&env::var("WE_USER")?)
         .password(&env::var("WE_PASSWORD")?);
-    Ok(options)
+    Ok(with_channel_namespace(
+        options,
+        &ChannelNamespace::from_env(),
+    ))
+}
+
+/// Return database connect options for the optional read replica. [None] is returned if the
+/// `WE_REPLICA_HOST` environment variable is not present, meaning all queries go to the primary
+/// database.
+/// # Errors
+/// This function returns an error if any of the required environment variables are not present or
+/// the port environment variable cannot be parsed as a [u16]. The environment variables used are:
+/// - WE_REPLICA_HOST -> address to the postgres read replica server
+/// - WE_REPLICA_PORT -> port that the read replica is listening (defaults to WE_PORT)
+/// - WE_DB, WE_USER, WE_PASSWORD -> same as [db_options]
+pub fn replica_db_options() -> EmResult<Option<PgConnectOptions>> {
+    let Ok(host) = env::var("WE_REPLICA_HOST") else {
+        return Ok(None);
+    };
+    let port = match env::var("WE_REPLICA_PORT") {
+        Ok(port) => port.parse()?,
+        Err(_) => env::var("WE_PORT")?.parse()?,
+    };
+    let options = PgConnectOptions::new()
+        .host(&host)
+        .port(port)
+        .database(&env::var("WE_DB")?)
+        .username(&env::var("WE_USER")?)
+        .password(&env::var("WE_PASSWORD")?);
+    Ok(Some(options))
 }
 
 #[cfg(test)]
 #[allow(clippy::expect_used)]
 pub(crate) mod test {
+    use chrono::{Duration, NaiveTime, Utc};
     use common::{
+        audit::{postgres::PgAuditSink, AuditEvent, AuditEventFilter, AuditSink},
         database::{connection::ConnectionBuilder, postgres::connection::PgConnectionBuilder},
         error::EmResult,
     };
     use rstest::{fixture, rstest};
-    use sqlx::PgPool;
+    use serde_json::json;
+    use sqlx::{PgExecutor, PgPool};
+    use uuid::Uuid;
 
-    use crate::database::db_options;
+    use crate::{database::db_options, workflow::data::WorkflowId};
 
     #[fixture]
     pub(crate) fn database() -> PgPool {
@@ -42,9 +83,133 @@ pub(crate) mod test {
         PgConnectionBuilder::create_pool_lazy(options, 1, 1)
     }
 
+    /// Create a new workflow with `task_count` tasks for testing. Names are made unique using the
+    /// `prefix` and the current timestamp. The task service, task and workflow all share the same
+    /// name so they can be removed by [cleanup_workflow]. Accepts any `executor` so the workflow
+    /// can also be created within a test's rolled back transaction.
+    pub(crate) async fn create_test_workflow<'c, E>(
+        executor: E,
+        prefix: &str,
+        task_count: i32,
+    ) -> EmResult<WorkflowId>
+    where
+        E: PgExecutor<'c>,
+    {
+        let name = format!("{prefix}_{}", Utc::now().timestamp_millis());
+        let workflow_id = sqlx::query_scalar(
+            r#"
+            with task_service as (
+                insert into workflow.task_services(name, base_url)
+                values($1, 'http://127.0.0.1')
+                returning service_id
+            ), task as (
+                insert into workflow.tasks(name, description, task_service_id, url)
+                select $1, 'workflow engine test', ts.service_id, $1
+                from task_service ts
+                returning task_id
+            ), workflow as (
+                insert into workflow.workflows(name)
+                values($1)
+                returning workflow_id
+            ), workflow_tasks as (
+                insert into workflow.workflow_tasks(workflow_id, task_order, task_id)
+                select w.workflow_id, s.task_order, t.task_id
+                from workflow w
+                cross join task t
+                cross join generate_series(1, $2) s(task_order)
+            )
+            select workflow_id
+            from workflow"#,
+        )
+        .bind(&name)
+        .bind(task_count)
+        .fetch_one(executor)
+        .await?;
+        Ok(workflow_id)
+    }
+
+    /// Cleanup function for workflows that are created during tests. Removes the workflow with
+    /// its workflow tasks as well as the jobs and workflow runs (including queued and archived
+    /// tasks) created from the workflow. The task and task service sharing the workflow's name
+    /// (see [create_test_workflow]) are also removed.
+    pub(crate) async fn cleanup_workflow(pool: &PgPool, workflow_id: WorkflowId) -> EmResult<()> {
+        let name: Option<String> =
+            sqlx::query_scalar("select name from workflow.workflows where workflow_id = $1")
+                .bind(workflow_id)
+                .fetch_optional(pool)
+                .await?;
+        sqlx::query("delete from job.jobs where workflow_id = $1")
+            .bind(workflow_id)
+            .execute(pool)
+            .await?;
+        let workflow_run_ids: Vec<i64> = sqlx::query_scalar(
+            "select workflow_run_id from workflow_run.workflow_runs where workflow_id = $1",
+        )
+        .bind(workflow_id)
+        .fetch_all(pool)
+        .await?;
+        sqlx::query("delete from workflow_run.task_queue where workflow_run_id = any($1)")
+            .bind(&workflow_run_ids)
+            .execute(pool)
+            .await?;
+        sqlx::query("delete from workflow_run.task_queue_archive where workflow_run_id = any($1)")
+            .bind(&workflow_run_ids)
+            .execute(pool)
+            .await?;
+        sqlx::query("delete from workflow_run.workflow_runs where workflow_id = $1")
+            .bind(workflow_id)
+            .execute(pool)
+            .await?;
+        sqlx::query("delete from workflow.workflow_tasks where workflow_id = $1")
+            .bind(workflow_id)
+            .execute(pool)
+            .await?;
+        sqlx::query("update workflow.workflows set new_workflow = null where new_workflow = $1")
+            .bind(workflow_id)
+            .execute(pool)
+            .await?;
+        sqlx::query("delete from workflow.workflows where workflow_id = $1")
+            .bind(workflow_id)
+            .execute(pool)
+            .await?;
+        sqlx::query(
+            r#"
+            delete from workflow.tasks t
+            where
+                t.name = $1
+                and not exists(
+                    select 1
+                    from workflow.workflow_tasks wt
+                    where wt.task_id = t.task_id
+                )"#,
+        )
+        .bind(&name)
+        .execute(pool)
+        .await?;
+        sqlx::query(
+            r#"
+            delete from workflow.task_services ts
+            where
+                ts.name = $1
+                and not exists(
+                    select 1
+                    from workflow.tasks t
+                    where t.task_service_id = ts.service_id
+                )"#,
+        )
+        .bind(&name)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
     #[rstest]
     #[case::clean_executors("executor/clean_executors.pgsql")]
+    #[case::clean_executors_grace("executor/clean_executors_grace.pgsql")]
+    #[case::clean_executors_heartbeat("executor/clean_executors_heartbeat.pgsql")]
     #[case::next_run_job_schedule("job/next_run_job_schedule.pgsql")]
+    #[case::next_workflow_run("workflow_run/next_workflow_run.pgsql")]
+    #[case::next_workflow_run_aging("workflow_run/next_workflow_run_aging.pgsql")]
     #[tokio::test]
     async fn database_test(database: PgPool, #[case] test_file: &str) -> EmResult<()> {
         common::database::postgres::test::run_db_test(&database, test_file).await
@@ -70,4 +235,85 @@ pub(crate) mod test {
     async fn check_for_composite(database: PgPool, #[case] file_name: &str) -> EmResult<()> {
         common::database::postgres::test::check_for_composite(&database, file_name).await
     }
+
+    /// Record 3 [AuditEvent]s for a new actor against 2 targets, each event an hour apart and
+    /// recorded oldest first. Returns the actor and the recorded events.
+    async fn record_test_audit_events(sink: &PgAuditSink) -> EmResult<(Uuid, Vec<AuditEvent>)> {
+        let actor = Uuid::new_v4();
+        let start = Utc::now().date_naive().and_time(NaiveTime::MIN);
+        let events: Vec<AuditEvent> = (0..3)
+            .map(|index| AuditEvent {
+                actor,
+                action: "workflow_run.cancel".to_owned(),
+                target: format!("workflow_run:{}", index % 2),
+                timestamp: start + Duration::hours(index),
+                details: Some(json!({ "index": index })),
+            })
+            .collect();
+        for event in &events {
+            sink.record(event).await?;
+        }
+        Ok((actor, events))
+    }
+
+    /// Remove every audit event performed by the `actor`
+    async fn cleanup_audit_events(pool: &PgPool, actor: Uuid) -> EmResult<()> {
+        sqlx::query("delete from audit.events where actor = $1")
+            .bind(actor)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn audit_sink_should_read_recorded_events_newest_first(database: PgPool) -> EmResult<()> {
+        let sink = PgAuditSink::new(&database);
+        let (actor, events) = record_test_audit_events(&sink).await?;
+        let filter = AuditEventFilter {
+            actor: Some(actor),
+            ..AuditEventFilter::default()
+        };
+
+        let action = sink.read_many(&filter).await;
+        cleanup_audit_events(&database, actor).await?;
+        let read_events = action?;
+
+        let expected: Vec<AuditEvent> = events.into_iter().rev().collect();
+        assert_eq!(read_events, expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn audit_sink_should_filter_events_by_target_and_time_range(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let sink = PgAuditSink::new(&database);
+        let (actor, events) = record_test_audit_events(&sink).await?;
+        let target_filter = AuditEventFilter {
+            actor: Some(actor),
+            target: Some("workflow_run:0".to_owned()),
+            ..AuditEventFilter::default()
+        };
+        let range_filter = AuditEventFilter {
+            actor: Some(actor),
+            from: events.get(1).map(|event| event.timestamp),
+            to: events.get(2).map(|event| event.timestamp),
+            ..AuditEventFilter::default()
+        };
+
+        let target_action = sink.read_many(&target_filter).await;
+        let range_action = sink.read_many(&range_filter).await;
+        cleanup_audit_events(&database, actor).await?;
+        let target_events = target_action?;
+        let range_events = range_action?;
+
+        assert_eq!(target_events.len(), 2);
+        assert!(target_events
+            .iter()
+            .all(|event| event.target == "workflow_run:0"));
+        assert_eq!(Some(range_events.as_slice()), events.get(1..2));
+        Ok(())
+    }
 }
@@ -0,0 +1,206 @@
+use std::env;
+
+use common::{
+    email::EmailService,
+    error::{EmError, EmResult},
+};
+use log::info;
+use sqlx::PgPool;
+
+/// Command line flag that requests the startup self-test
+const SELF_TEST_FLAG: &str = "--self-test";
+/// Environment variable that requests the startup self-test when set to `true` or `1`
+const SELF_TEST_ENV: &str = "WE_SELF_TEST";
+
+/// Functions and procedures that the workflow engine services call
+pub const EXPECTED_ROUTINES: &[&str] = &[
+    "executor.register_executor",
+    "executor.cancel_executor",
+    "executor.clean_executors",
+    "executor.close_executor",
+    "executor.heartbeat_executor",
+    "executor.post_executor_error_message",
+    "executor.shutdown_all_executors",
+    "executor.shutdown_executor",
+    "job.create_cron_job",
+    "job.create_interval_job",
+    "job.create_scheduled_job",
+    "job.complete_job",
+    "job.pause_job",
+    "job.set_job_as_running",
+    "job.skip_job",
+    "workflow.create_task",
+    "workflow.create_workflow",
+    "workflow.deprecate_and_migrate_workflow",
+    "workflow.deprecate_workflow",
+    "workflow.set_workflow_tasks",
+    "workflow.update_task",
+    "workflow.update_workflow",
+    "workflow_run.append_task_log",
+    "workflow_run.append_task_rule",
+    "workflow_run.cancel_workflow_run",
+    "workflow_run.complete_task",
+    "workflow_run.complete_task_run",
+    "workflow_run.complete_workflow_run",
+    "workflow_run.complete_workflow_run_move",
+    "workflow_run.executor_workflows",
+    "workflow_run.fail_task_run",
+    "workflow_run.initialize_workflow_run",
+    "workflow_run.initialize_workflow_runs",
+    "workflow_run.next_tasks",
+    "workflow_run.next_workflow_run",
+    "workflow_run.pause_workflow_run",
+    "workflow_run.recover_orphaned_workflow_runs",
+    "workflow_run.restart_workflow_run",
+    "workflow_run.resume_workflow_run",
+    "workflow_run.retry_all_failed_tasks",
+    "workflow_run.retry_task",
+    "workflow_run.schedule_workflow_run",
+    "workflow_run.set_task_progress",
+    "workflow_run.set_workflow_run_progress",
+    "workflow_run.start_task_run",
+    "workflow_run.start_workflow_run",
+    "workflow_run.start_workflow_run_move",
+    "workflow_run.task_outputs",
+];
+
+/// Views that the workflow engine services read from
+pub const EXPECTED_VIEWS: &[&str] = &[
+    "executor.v_executors",
+    "job.v_jobs",
+    "job.v_queued_jobs",
+    "workflow.v_tasks",
+    "workflow.v_workflows",
+    "workflow_run.v_task_queue_detail",
+    "workflow_run.v_task_queue_record",
+    "workflow_run.v_workflow_run_history",
+    "workflow_run.v_workflow_runs",
+];
+
+/// Returns true if the startup self-test was requested through the `--self-test` command line flag
+/// or the `WE_SELF_TEST` environment variable
+pub fn self_test_requested() -> bool {
+    env::args().any(|arg| arg == SELF_TEST_FLAG)
+        || env::var(SELF_TEST_ENV).is_ok_and(|value| value == "true" || value == "1")
+}
+
+/// Check the database behind `pool` for connectivity and the presence of the specified `routines`
+/// and `views`. Returns a list of every problem found, which is empty if the database is healthy.
+/// Names must be schema qualified (e.g. `workflow.create_workflow`).
+pub async fn check_database(pool: &PgPool, routines: &[&str], views: &[&str]) -> Vec<String> {
+    if let Err(error) = sqlx::query("select 1").execute(pool).await {
+        return vec![format!("Could not connect to the database. {error}")];
+    }
+    let mut failures = Vec::new();
+    for routine in routines {
+        let Some((schema, name)) = routine.split_once('.') else {
+            failures.push(format!("Routine name is not schema qualified: {routine}"));
+            continue;
+        };
+        let result: Result<bool, sqlx::Error> = sqlx::query_scalar(
+            r#"
+            select exists(
+                select 1
+                from pg_proc p
+                join pg_namespace n on p.pronamespace = n.oid
+                where
+                    n.nspname = $1
+                    and p.proname = $2
+            )"#,
+        )
+        .bind(schema)
+        .bind(name)
+        .fetch_one(pool)
+        .await;
+        match result {
+            Ok(true) => {}
+            Ok(false) => failures.push(format!("Missing procedure or function: {routine}")),
+            Err(error) => failures.push(format!("Could not check routine {routine}. {error}")),
+        }
+    }
+    for view in views {
+        let result: Result<bool, sqlx::Error> =
+            sqlx::query_scalar("select to_regclass($1) is not null")
+                .bind(view)
+                .fetch_one(pool)
+                .await;
+        match result {
+            Ok(true) => {}
+            Ok(false) => failures.push(format!("Missing view: {view}")),
+            Err(error) => failures.push(format!("Could not check view {view}. {error}")),
+        }
+    }
+    failures
+}
+
+/// Convert the list of self-test `failures` into a result, aggregating the failures into a single
+/// report if any are present
+fn into_report(failures: &[String]) -> EmResult<()> {
+    if !failures.is_empty() {
+        return Err(EmError::SelfTest(failures.join("\n")));
+    }
+    info!("Startup self-test passed");
+    Ok(())
+}
+
+/// Run the startup self-test against the database behind `pool`
+/// # Errors
+/// This function will return an [EmError::SelfTest] containing an aggregated report if any check
+/// fails
+pub async fn run_self_test(pool: &PgPool) -> EmResult<()> {
+    info!("Running startup self-test");
+    let failures = check_database(pool, EXPECTED_ROUTINES, EXPECTED_VIEWS).await;
+    into_report(&failures)
+}
+
+/// Run the startup self-test against the database behind `pool` as well as the `email_service`
+/// used by the job worker. All checks are performed before returning so the report contains every
+/// problem found.
+/// # Errors
+/// This function will return an [EmError::SelfTest] containing an aggregated report if any check
+/// fails
+pub async fn run_worker_self_test<E>(pool: &PgPool, email_service: &E) -> EmResult<()>
+where
+    E: EmailService,
+{
+    info!("Running startup self-test");
+    let mut failures = check_database(pool, EXPECTED_ROUTINES, EXPECTED_VIEWS).await;
+    if let Err(error) = email_service.test_connection().await {
+        failures.push(format!("Could not reach the SMTP relay. {error}"));
+    }
+    into_report(&failures)
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+    use sqlx::PgPool;
+
+    use super::{check_database, EXPECTED_ROUTINES, EXPECTED_VIEWS};
+    use crate::database::test::database;
+
+    #[rstest]
+    #[tokio::test]
+    async fn check_database_should_succeed_when_database_is_healthy(database: PgPool) {
+        let failures = check_database(&database, EXPECTED_ROUTINES, EXPECTED_VIEWS).await;
+        assert!(failures.is_empty(), "{failures:?}");
+    }
+
+    #[rstest]
+    #[case::missing_procedure(&["workflow.missing_procedure"], &[], "workflow.missing_procedure")]
+    #[case::missing_view(&[], &["workflow.v_missing_view"], "workflow.v_missing_view")]
+    #[tokio::test]
+    async fn check_database_should_fail_when(
+        database: PgPool,
+        #[case] routines: &[&str],
+        #[case] views: &[&str],
+        #[case] missing_name: &str,
+    ) {
+        let failures = check_database(&database, routines, views).await;
+        assert_eq!(failures.len(), 1);
+        assert!(
+            failures.iter().any(|f| f.contains(missing_name)),
+            "{failures:?}"
+        );
+    }
+}
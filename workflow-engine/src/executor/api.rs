@@ -1,26 +1,39 @@
 use actix_web::{web, Scope};
 use common::api::{ApiResponse, QueryApiFormat};
+use users::data::role::RoleName;
 
-use crate::executor::{
-    data::{Executor, ExecutorId},
-    service::ExecutorService,
+use crate::{
+    api::RequirePrivilege,
+    executor::{
+        data::{Executor, ExecutorId, ExecutorSignalOutcome, ExecutorStatus},
+        service::ExecutorService,
+    },
+    workflow_run::{data::ExecutorWorkflowRun, service::WorkflowRunsService},
 };
 
-pub fn service<E>() -> Scope
+pub fn service<E, R>() -> Scope
 where
     E: ExecutorService + Send + Sync + 'static,
+    R: WorkflowRunsService + Send + Sync + 'static,
 {
     web::scope("/executors")
         .route("", web::get().to(active_executors::<E>))
+        .route("/available", web::get().to(available_executors::<E>))
         .route(
             "/shutdown/{executor_id}",
             web::post().to(shutdown_executor::<E>),
         )
-        .route(
-            "/cancel/{executor_id}",
-            web::post().to(cancel_executor::<E>),
+        .route("/shutdown-all", web::post().to(shutdown_all_executors::<E>))
+        .service(
+            web::resource("/cancel/{executor_id}")
+                .wrap(RequirePrivilege(RoleName::ManageWorkflowEngine))
+                .route(web::post().to(cancel_executor::<E>)),
         )
         .route("/clean", web::post().to(clean_executors::<E>))
+        .route(
+            "/{executor_id}/workflow-runs",
+            web::get().to(executor_workflow_runs::<E, R>),
+        )
 }
 
 /// API endpoint to fetch all active executors
@@ -38,7 +51,33 @@ where
     }
 }
 
-/// API endpoint to start the graceful shutdown of the executor specified by `executor_id`
+/// API endpoint to fetch the active executors that can accept another workflow run, ordered by the
+/// least loaded executor first
+async fn available_executors<E>(
+    service: actix_web::web::Data<E>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<Executor>>
+where
+    E: ExecutorService,
+{
+    let format = query.into_inner();
+    match service.read_available().await {
+        Ok(executors) => ApiResponse::success(executors, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// Message returned in place of the [Executor] when the `action` signal was not sent because the
+/// `executor` already had the terminal `status`
+fn signal_not_sent_message(action: &str, executor: &Executor, status: &ExecutorStatus) -> String {
+    format!(
+        "Executor {} is already {status:?}. No {action} signal was sent",
+        executor.executor_id
+    )
+}
+
+/// API endpoint to start the graceful shutdown of the executor specified by `executor_id`. If the
+/// executor is no longer active, a message stating that no signal was sent is returned instead.
 async fn shutdown_executor<E>(
     executor_id: actix_web::web::Path<ExecutorId>,
     service: actix_web::web::Data<E>,
@@ -49,12 +88,35 @@ where
 {
     let format = query.into_inner();
     match service.shutdown(&executor_id).await {
-        Ok(executor) => ApiResponse::success(executor, format.f),
+        Ok(ExecutorSignalOutcome::Sent(executor)) => ApiResponse::success(executor, format.f),
+        Ok(ExecutorSignalOutcome::NotSent(executor, status)) => ApiResponse::message(
+            signal_not_sent_message("shutdown", &executor, &status),
+            format.f,
+        ),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to start the graceful shutdown of all active executors. Returns every executor
+/// that received the shutdown signal
+async fn shutdown_all_executors<E>(
+    service: actix_web::web::Data<E>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<Executor>>
+where
+    E: ExecutorService,
+{
+    let format = query.into_inner();
+    match service.shutdown_all().await {
+        Ok(executors) => ApiResponse::success(executors, format.f),
         Err(error) => ApiResponse::error(error, format.f),
     }
 }
 
-/// API endpoint to the forceful shutdown of the executor specified by `executor_id`
+/// API endpoint to the forceful shutdown of the executor specified by `executor_id`. The route is
+/// wrapped by [RequirePrivilege] so only users with the [RoleName::ManageWorkflowEngine] privilege
+/// reach this handler. If the executor is no longer active, a message stating that no signal was
+/// sent is returned instead.
 async fn cancel_executor<E>(
     executor_id: actix_web::web::Path<ExecutorId>,
     service: actix_web::web::Data<E>,
@@ -65,7 +127,11 @@ where
 {
     let format = query.into_inner();
     match service.cancel(&executor_id).await {
-        Ok(executor) => ApiResponse::success(executor, format.f),
+        Ok(ExecutorSignalOutcome::Sent(executor)) => ApiResponse::success(executor, format.f),
+        Ok(ExecutorSignalOutcome::NotSent(executor, status)) => ApiResponse::message(
+            signal_not_sent_message("cancel", &executor, &status),
+            format.f,
+        ),
         Err(error) => ApiResponse::error(error, format.f),
     }
 }
@@ -84,3 +150,28 @@ where
         Err(error) => ApiResponse::error(error, format.f),
     }
 }
+
+/// API endpoint to fetch the workflow runs owned by the executor specified by `executor_id`.
+/// Returns an array of [ExecutorWorkflowRun] entries or a failure if the executor does not exist.
+async fn executor_workflow_runs<E, R>(
+    executor_id: actix_web::web::Path<ExecutorId>,
+    executor_service: actix_web::web::Data<E>,
+    workflow_runs_service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<ExecutorWorkflowRun>>
+where
+    E: ExecutorService,
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    if let Err(error) = executor_service.read_one(&executor_id).await {
+        return ApiResponse::error(error, format.f);
+    }
+    match workflow_runs_service
+        .all_executor_workflows(&executor_id)
+        .await
+    {
+        Ok(workflow_runs) => ApiResponse::success(workflow_runs, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
@@ -1,12 +1,16 @@
 use std::str::FromStr;
 
 use chrono::NaiveDateTime;
-use serde::{de::Visitor, Deserialize, Serialize};
+use common::{
+    api::id::{deserialize_id, parse_id},
+    error::EmError,
+};
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
 use sqlx::types::ipnetwork::IpNetwork;
 
 /// Status of an [Executor][crate::executor::Executor] as found in the database as a simple
 /// Postgresql enum type
-#[derive(sqlx::Type, Serialize, PartialEq, Debug)]
+#[derive(sqlx::Type, Serialize, Deserialize, PartialEq, Debug)]
 #[sqlx(type_name = "executor_status")]
 pub enum ExecutorStatus {
     Active,
@@ -65,14 +69,68 @@ pub struct Executor {
     pub session_active: bool,
     #[sqlx(rename = "wr_count")]
     pub workflow_run_count: i64,
+    /// Maximum number of workflow runs the executor runs at once. [None] if unlimited
+    #[sqlx(default)]
+    #[serde(default)]
+    pub max_workflow_runs: Option<i32>,
+}
+
+/// Outcome of requesting the shutdown or cancel of an executor. A signal is only sent to executors
+/// that are still active.
+pub enum ExecutorSignalOutcome {
+    /// The signal was sent to the executor. Contains the executor after the status update.
+    Sent(Executor),
+    /// The executor was no longer active so no signal was sent. Contains the unchanged executor
+    /// and the status it already had.
+    NotSent(Executor, ExecutorStatus),
+}
+
+/// Details of a newly registered executor as returned by `executor.register_executor()`
+#[derive(sqlx::FromRow)]
+pub struct ExecutorRegistration {
+    /// ID assigned to the new executor
+    pub executor_id: ExecutorId,
+    /// Process ID of the database session recorded for the new executor
+    pub pid: i32,
 }
 
 /// Wrapper for an `executor_id` value. Made to ensure data passed as the id of an executor is
 /// correct and not just any i64 value.
-#[derive(sqlx::Type, Clone, Deserialize, Serialize, Copy)]
+#[derive(sqlx::Type, Clone, Serialize, Copy)]
 #[sqlx(transparent)]
 pub struct ExecutorId(i64);
 
+impl FromStr for ExecutorId {
+    type Err = EmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(parse_id("executor_id", s)?))
+    }
+}
+
+impl TryFrom<&str> for ExecutorId {
+    type Error = EmError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl<'de> Deserialize<'de> for ExecutorId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_id("executor_id", deserializer).map(Self)
+    }
+}
+
+impl From<i64> for ExecutorId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
 impl std::fmt::Display for ExecutorId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -6,7 +6,7 @@ use common::{
 };
 
 use crate::executor::{
-    data::{Executor, ExecutorId, ExecutorStatus},
+    data::{Executor, ExecutorId, ExecutorRegistration, ExecutorSignalOutcome, ExecutorStatus},
     utilities::ExecutorStatusUpdate,
 };
 
@@ -22,8 +22,13 @@ where
     type Listener: ChangeListener<Message = ExecutorStatusUpdate>;
 
     /// Register a new executor with the database. Creates a record for future processes to
-    /// attribute workflow runs to the new executor.
-    async fn register_executor(&self) -> EmResult<ExecutorId>;
+    /// attribute workflow runs to the new executor. The executor runs at most `max_workflow_runs`
+    /// workflow runs at once or an unlimited number of workflow runs if [None]. Returns the
+    /// assigned id and the pid recorded for the executor.
+    async fn register_executor(
+        &self,
+        max_workflow_runs: Option<usize>,
+    ) -> EmResult<ExecutorRegistration>;
     /// Read the [Executor] record to gain information about the specified `executor_id`. If no
     /// executor matches the id provided, [None] will be returned.
     async fn read_one(&self, executor_id: &ExecutorId) -> EmResult<Executor>;
@@ -36,14 +41,23 @@ where
     /// Read all [Executor] records, excluding those that are labeled as inactive. The output does
     /// include records with an underlining session/pool that is no longer active.
     async fn read_active(&self) -> EmResult<Vec<Executor>>;
+    /// Read the active [Executor] records with an active session that can accept another workflow
+    /// run, ordered by the least loaded executor first. Executors without a configured maximum
+    /// number of workflow runs are always considered available.
+    async fn read_available(&self) -> EmResult<Vec<Executor>>;
     /// Update the status of the executor specified by `executor_id` to [ExecutorStatus::Shutdown].
     /// This internally sends a signal to the [Executor][crate::executor::Executor] instance to
-    /// gracefully shutdown all operation and close.
-    async fn shutdown(&self, executor_id: &ExecutorId) -> EmResult<Executor>;
+    /// gracefully shutdown all operation and close. If the executor is no longer active, no signal
+    /// is sent and the unchanged [Executor] is returned as [ExecutorSignalOutcome::NotSent].
+    async fn shutdown(&self, executor_id: &ExecutorId) -> EmResult<ExecutorSignalOutcome>;
     /// Update the status of the executor specified by `executor_id` to [ExecutorStatus::Canceled].
     /// This internally sends a signal to the [Executor][crate::executor::Executor] instance to
-    /// forcefully shutdown all operation and close.
-    async fn cancel(&self, executor_id: &ExecutorId) -> EmResult<Executor>;
+    /// forcefully shutdown all operation and close. If the executor is no longer active, no signal
+    /// is sent and the unchanged [Executor] is returned as [ExecutorSignalOutcome::NotSent].
+    async fn cancel(&self, executor_id: &ExecutorId) -> EmResult<ExecutorSignalOutcome>;
+    /// Start the graceful shutdown of every active executor. Executors that are already shutting
+    /// down (or canceled) are skipped. Returns every [Executor] that received the shutdown signal.
+    async fn shutdown_all(&self) -> EmResult<Vec<Executor>>;
     /// Clean up database entries linked to the `executor_id` specified. Acts as the final step to
     /// ending an [Executor][crate::executor::Executor] instance and should only be called from
     /// the [Executor][crate::executor::Executor] itself.
@@ -51,8 +65,14 @@ where
     /// Post the specified `error` message to the `executor_id` record. If the SQL call happens to
     /// fail that error will be logged alongside the original `error`.
     async fn post_error(&self, executor_id: &ExecutorId, error: EmError);
+    /// Report that the executor specified by `executor_id` is still alive. Executors that stop
+    /// reporting heartbeats are eventually treated as dead by
+    /// [clean_executors][ExecutorService::clean_executors].
+    async fn heartbeat(&self, executor_id: &ExecutorId) -> EmResult<()>;
     /// Clean executor database records, setting correct statuses for executors that are no longer
-    /// alive but marked as active.
+    /// alive (or have not reported a heartbeat recently) but marked as active. Executors that
+    /// registered within the implementation's grace window are never cleaned so peers starting at
+    /// the same time do not reap each other.
     async fn clean_executors(&self) -> EmResult<()>;
     /// Get a new [ChangeListener] for the executor status update channel. Channel name is specific
     /// to the executor's id.
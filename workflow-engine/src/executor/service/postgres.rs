@@ -1,27 +1,174 @@
+use std::time::Duration;
+
 use common::{
-    database::postgres::{listener::PgChangeListener, Postgres},
+    audit::{postgres::PgAuditSink, AuditEvent, AuditSink},
+    database::{
+        listener::ChannelNamespace,
+        postgres::{listener::PgChangeListener, Postgres},
+    },
     error::{EmError, EmResult},
 };
-use log::error;
-use sqlx::{postgres::PgListener, PgPool};
+use log::{error, info};
+use sqlx::{
+    postgres::{PgHasArrayType, PgTypeInfo},
+    Acquire, PgExecutor, PgPool,
+};
 
 use crate::executor::{
-    data::{Executor, ExecutorId, ExecutorStatus},
+    data::{Executor, ExecutorId, ExecutorRegistration, ExecutorSignalOutcome, ExecutorStatus},
     service::ExecutorService,
     utilities::ExecutorStatusUpdate,
 };
 
+impl PgHasArrayType for ExecutorId {
+    fn array_type_info() -> PgTypeInfo {
+        <i64 as PgHasArrayType>::array_type_info()
+    }
+}
+
+/// Default time after registration during which an executor is never cleaned by
+/// [clean_executors][ExecutorService::clean_executors]
+pub const DEFAULT_REGISTRATION_GRACE: Duration = Duration::from_secs(30);
+
 /// Postgresql implementation of the [ExecutorService]. Wraps a [PgPool] and provides interaction
 /// methods for the API and [Executor][crate::executor::Executor] instances.
 #[derive(Clone)]
 pub struct PgExecutorService {
     pool: PgPool,
+    heartbeat_threshold: Option<Duration>,
+    registration_grace: Duration,
+    audit_sink: Option<PgAuditSink>,
+    channel_namespace: ChannelNamespace,
 }
 
 impl PgExecutorService {
     /// Create a new instance of [PgExecutorService] using the data source provided
     pub fn new(pool: &PgPool) -> Self {
-        Self { pool: pool.clone() }
+        Self {
+            pool: pool.clone(),
+            heartbeat_threshold: None,
+            registration_grace: DEFAULT_REGISTRATION_GRACE,
+            audit_sink: None,
+            channel_namespace: ChannelNamespace::from_env(),
+        }
+    }
+
+    /// Set the maximum age of an executor's last heartbeat before
+    /// [clean_executors][ExecutorService::clean_executors] treats the executor as dead, even if
+    /// the executor's session is still attached to the database. Heartbeats are not checked by
+    /// default.
+    pub const fn with_heartbeat_threshold(mut self, heartbeat_threshold: Duration) -> Self {
+        self.heartbeat_threshold = Some(heartbeat_threshold);
+        self
+    }
+
+    /// Set the time after registration during which an executor is never cleaned by
+    /// [clean_executors][ExecutorService::clean_executors]. Defaults to
+    /// [DEFAULT_REGISTRATION_GRACE].
+    pub const fn with_registration_grace(mut self, registration_grace: Duration) -> Self {
+        self.registration_grace = registration_grace;
+        self
+    }
+
+    /// Set the [PgAuditSink] that receives an [AuditEvent] when an executor is shutdown or
+    /// canceled. Mutations are not audited by default.
+    pub fn with_audit_sink(mut self, audit_sink: &PgAuditSink) -> Self {
+        self.audit_sink = Some(audit_sink.clone());
+        self
+    }
+
+    /// Set the [ChannelNamespace] used to name the channels of listeners created by this service.
+    /// Defaults to the namespace of the `EM_CHANNEL_NS` environment variable. Must match the
+    /// namespace of the sessions that send notifications (see [with_channel_namespace]).
+    ///
+    /// [with_channel_namespace]: common::database::postgres::listener::with_channel_namespace
+    pub fn with_channel_namespace(mut self, channel_namespace: ChannelNamespace) -> Self {
+        self.channel_namespace = channel_namespace;
+        self
+    }
+
+    /// Emit an [AuditEvent] for the `action` performed against the `executor_id` if an audit sink
+    /// has been set
+    async fn audit(&self, action: &str, executor_id: &ExecutorId) {
+        if let Some(audit_sink) = &self.audit_sink {
+            let target = format!("executor:{executor_id}");
+            audit_sink.emit(AuditEvent::new(action, target, None)).await;
+        }
+    }
+
+    /// Check that the executor specified by `executor_id` is still active before sending it the
+    /// `action` signal. Executors already in a terminal state have no session left to notify, so
+    /// the skipped `action` is logged and the unchanged executor is returned as
+    /// [ExecutorSignalOutcome::NotSent]. Returns [None] if the signal should be sent.
+    /// # Errors
+    /// This function will return an error if the executor cannot be read
+    async fn skipped_signal(
+        &self,
+        action: &str,
+        executor_id: &ExecutorId,
+    ) -> EmResult<Option<ExecutorSignalOutcome>> {
+        let status = self.read_status(executor_id).await?;
+        if status == ExecutorStatus::Active {
+            return Ok(None);
+        }
+        info!("Executor {executor_id} is already {status:?}. Skipping {action}");
+        let executor = self.read_one(executor_id).await?;
+        Ok(Some(ExecutorSignalOutcome::NotSent(executor, status)))
+    }
+
+    /// Fetch the active executors that can accept another workflow run using the `executor`.
+    /// Executors with the fewest workflow runs are returned first.
+    /// # Errors
+    /// This function will return an error if the `v_executors` query fails
+    async fn fetch_available<'c, E>(executor: E) -> EmResult<Vec<Executor>>
+    where
+        E: PgExecutor<'c>,
+    {
+        let result = sqlx::query_as(
+            r#"
+            select
+                e.executor_id, e.pid, e.username, e.application_name, e.client_addr, e.client_port,
+                e.exec_start, e.session_active, e.wr_count, e.max_workflow_runs
+            from executor.v_executors e
+            where
+                e.status = 'Active'::executor.executor_status
+                and e.session_active
+                and (e.max_workflow_runs is null or e.wr_count < e.max_workflow_runs)
+            order by e.wr_count, e.executor_id"#,
+        )
+        .fetch_all(executor)
+        .await?;
+        Ok(result)
+    }
+
+    /// Shutdown every active executor using a connection acquired from `connection`, returning
+    /// the executors that were shutdown. See [ExecutorService::shutdown_all] for more details.
+    /// # Errors
+    /// This function will return an error if the shutdown or `v_executors` query fails
+    async fn shutdown_active<'c, A>(&self, connection: A) -> EmResult<Vec<Executor>>
+    where
+        A: Acquire<'c, Database = sqlx::Postgres>,
+    {
+        let mut connection = connection.acquire().await?;
+        let executor_ids: Vec<ExecutorId> =
+            sqlx::query_scalar("select executor.shutdown_all_executors()")
+                .fetch_all(&mut *connection)
+                .await?;
+        for executor_id in &executor_ids {
+            self.audit("executor.shutdown", executor_id).await;
+        }
+        let result = sqlx::query_as(
+            r#"
+            select
+                e.executor_id, e.pid, e.username, e.application_name, e.client_addr, e.client_port,
+                e.exec_start, e.session_active, e.wr_count, e.max_workflow_runs
+            from executor.v_executors e
+            where e.executor_id = any($1)"#,
+        )
+        .bind(&executor_ids)
+        .fetch_all(&mut *connection)
+        .await?;
+        Ok(result)
     }
 }
 
@@ -29,11 +176,17 @@ impl ExecutorService for PgExecutorService {
     type Database = Postgres;
     type Listener = PgChangeListener<ExecutorStatusUpdate>;
 
-    async fn register_executor(&self) -> EmResult<ExecutorId> {
-        let executor_id = sqlx::query_scalar("select executor.register_executor()")
-            .fetch_one(&self.pool)
-            .await?;
-        Ok(executor_id)
+    async fn register_executor(
+        &self,
+        max_workflow_runs: Option<usize>,
+    ) -> EmResult<ExecutorRegistration> {
+        let max_workflow_runs = max_workflow_runs.map(|max| i32::try_from(max).unwrap_or(i32::MAX));
+        let registration =
+            sqlx::query_as("select r.executor_id, r.pid from executor.register_executor($1) r")
+                .bind(max_workflow_runs)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(registration)
     }
 
     async fn read_one(&self, executor_id: &ExecutorId) -> EmResult<Executor> {
@@ -41,7 +194,7 @@ impl ExecutorService for PgExecutorService {
             r#"
             select
                 e.executor_id, e.pid, e.username, e.application_name, e.client_addr, e.client_port,
-                e.exec_start, e.session_active, e.wr_count
+                e.exec_start, e.session_active, e.wr_count, e.max_workflow_runs
             from executor.v_executors e
             where e.executor_id = $1"#,
         )
@@ -83,7 +236,7 @@ impl ExecutorService for PgExecutorService {
             r#"
             select
                 e.executor_id, e.pid, e.username, e.application_name, e.client_addr, e.client_port,
-                e.exec_start, e.session_active, e.wr_count
+                e.exec_start, e.session_active, e.wr_count, e.max_workflow_runs
             from executor.v_executors e"#,
         )
         .fetch_all(&self.pool)
@@ -96,7 +249,7 @@ impl ExecutorService for PgExecutorService {
             r#"
             select
                 e.executor_id, e.pid, e.username, e.application_name, e.client_addr, e.client_port,
-                e.exec_start, e.session_active, e.wr_count
+                e.exec_start, e.session_active, e.wr_count, e.max_workflow_runs
             from executor.v_executors e
             where e.status = 'Active'::executor.executor_status"#,
         )
@@ -105,20 +258,40 @@ impl ExecutorService for PgExecutorService {
         Ok(result)
     }
 
-    async fn shutdown(&self, executor_id: &ExecutorId) -> EmResult<Executor> {
+    async fn read_available(&self) -> EmResult<Vec<Executor>> {
+        Self::fetch_available(&self.pool).await
+    }
+
+    async fn shutdown(&self, executor_id: &ExecutorId) -> EmResult<ExecutorSignalOutcome> {
+        if let Some(outcome) = self.skipped_signal("shutdown", executor_id).await? {
+            return Ok(outcome);
+        }
         sqlx::query("call executor.shutdown_executor($1)")
             .bind(executor_id)
             .execute(&self.pool)
             .await?;
-        self.read_one(executor_id).await
+        self.audit("executor.shutdown", executor_id).await;
+        Ok(ExecutorSignalOutcome::Sent(
+            self.read_one(executor_id).await?,
+        ))
     }
 
-    async fn cancel(&self, executor_id: &ExecutorId) -> EmResult<Executor> {
+    async fn cancel(&self, executor_id: &ExecutorId) -> EmResult<ExecutorSignalOutcome> {
+        if let Some(outcome) = self.skipped_signal("cancel", executor_id).await? {
+            return Ok(outcome);
+        }
         sqlx::query("call executor.cancel_executor($1)")
             .bind(executor_id)
             .execute(&self.pool)
             .await?;
-        self.read_one(executor_id).await
+        self.audit("executor.cancel", executor_id).await;
+        Ok(ExecutorSignalOutcome::Sent(
+            self.read_one(executor_id).await?,
+        ))
+    }
+
+    async fn shutdown_all(&self) -> EmResult<Vec<Executor>> {
+        self.shutdown_active(&self.pool).await
     }
 
     async fn close(&self, executor_id: &ExecutorId, is_cancelled: bool) -> EmResult<()> {
@@ -146,18 +319,252 @@ impl ExecutorService for PgExecutorService {
         error!("Executor fatal error. {}", message);
     }
 
-    async fn clean_executors(&self) -> EmResult<()> {
-        sqlx::query("call executor.clean_executors()")
+    async fn heartbeat(&self, executor_id: &ExecutorId) -> EmResult<()> {
+        sqlx::query("call executor.heartbeat_executor($1)")
+            .bind(executor_id)
             .execute(&self.pool)
             .await?;
         Ok(())
     }
 
+    async fn clean_executors(&self) -> EmResult<()> {
+        sqlx::query(
+            "call executor.clean_executors($1 * interval '1 second', $2 * interval '1 second')",
+        )
+        .bind(
+            self.heartbeat_threshold
+                .map(|threshold| threshold.as_secs_f64()),
+        )
+        .bind(self.registration_grace.as_secs_f64())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
     async fn status_listener(&self, executor_id: &ExecutorId) -> EmResult<Self::Listener> {
-        let mut listener = PgListener::connect_with(&self.pool).await?;
-        listener
-            .listen(&format!("exec_status_{}", executor_id))
+        let channel = self
+            .channel_namespace
+            .channel(&format!("exec_status_{executor_id}"));
+        PgChangeListener::connect(&self.pool, &channel).await
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::panic)]
+mod test {
+    use std::time::Duration;
+
+    use common::{
+        database::listener::{ChangeListener, ChannelNamespace},
+        error::EmResult,
+    };
+    use rstest::rstest;
+    use sqlx::{PgExecutor, PgPool};
+
+    use super::PgExecutorService;
+    use crate::{
+        database::test::database,
+        executor::{
+            data::{Executor, ExecutorId, ExecutorSignalOutcome, ExecutorStatus},
+            service::ExecutorService,
+            utilities::ExecutorStatusUpdate,
+        },
+    };
+
+    #[rstest]
+    #[tokio::test]
+    async fn status_listener_should_only_receive_notifications_on_namespaced_channel(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let executor_id = ExecutorId::from(i64::MAX);
+        let service = PgExecutorService::new(&database)
+            .with_channel_namespace(ChannelNamespace::new("listener_test"));
+        let mut listener = service.status_listener(&executor_id).await?;
+
+        sqlx::query("select pg_notify($1, 'v1:cancel')")
+            .bind(format!("exec_status_{executor_id}"))
+            .execute(&database)
+            .await?;
+        sqlx::query("select pg_notify($1, 'v1:shutdown')")
+            .bind(format!("listener_test_exec_status_{executor_id}"))
+            .execute(&database)
             .await?;
-        Ok(PgChangeListener::new(listener))
+        let update = tokio::time::timeout(Duration::from_secs(5), listener.recv())
+            .await
+            .map_err(|_| "Timed out waiting for a notification")??;
+
+        assert!(matches!(update, ExecutorStatusUpdate::Shutdown));
+        Ok(())
+    }
+
+    /// Insert a new executor with the `status` for the current database session with the optional
+    /// `max_workflow_runs`. Returns the new executor id.
+    async fn insert_executor<'c, E>(
+        executor: E,
+        status: ExecutorStatus,
+        max_workflow_runs: Option<i32>,
+    ) -> EmResult<i64>
+    where
+        E: PgExecutor<'c>,
+    {
+        let executor_id = sqlx::query_scalar(
+            r#"
+            insert into executor.executors(
+                pid, username, application_name, client_addr, client_port, status,
+                max_workflow_runs
+            )
+            values(
+                pg_backend_pid(), current_user, 'executor_service_test', '127.0.0.1', 0, $1, $2
+            )
+            returning executor_id"#,
+        )
+        .bind(status)
+        .bind(max_workflow_runs)
+        .fetch_one(executor)
+        .await?;
+        Ok(executor_id)
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_available_should_exclude_executors_at_capacity(database: PgPool) -> EmResult<()> {
+        // Run within a rolled back transaction so the active executors are never handed the
+        // workflow runs of other tests
+        let mut transaction = database.begin().await?;
+        let mut executor_ids = Vec::new();
+        for max_workflow_runs in [Some(1), Some(2), None] {
+            executor_ids.push(
+                insert_executor(&mut transaction, ExecutorStatus::Active, max_workflow_runs)
+                    .await?,
+            );
+        }
+        let [full_executor_id, limited_executor_id, unlimited_executor_id] =
+            executor_ids.as_slice()
+        else {
+            panic!("Expected 3 executors to be inserted");
+        };
+        sqlx::query(
+            r#"
+            with workflow as (
+                insert into workflow.workflows(name)
+                values('read_available_'||clock_timestamp()::text)
+                returning workflow_id
+            )
+            insert into workflow_run.workflow_runs(workflow_id, executor_id)
+            select w.workflow_id, e.executor_id
+            from workflow w
+            cross join unnest($1::bigint[]) e(executor_id)"#,
+        )
+        .bind(vec![*full_executor_id, *limited_executor_id])
+        .execute(&mut transaction)
+        .await?;
+
+        let available: Vec<String> = PgExecutorService::fetch_available(&mut transaction)
+            .await?
+            .into_iter()
+            .map(|executor| executor.executor_id.to_string())
+            .collect();
+        transaction.rollback().await?;
+
+        assert!(!available.contains(&full_executor_id.to_string()));
+        assert!(available.contains(&limited_executor_id.to_string()));
+        assert!(available.contains(&unlimited_executor_id.to_string()));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn cancel_should_leave_executor_unchanged_when_already_shutdown(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let executor_id = insert_executor(&database, ExecutorStatus::Shutdown, None).await?;
+        let service = PgExecutorService::new(&database);
+
+        let action = async {
+            let executor_id = ExecutorId::from(executor_id);
+            let outcome = service.cancel(&executor_id).await?;
+            let status = service.read_status(&executor_id).await?;
+            EmResult::Ok((outcome, status))
+        }
+        .await;
+        sqlx::query("delete from executor.executors where executor_id = $1")
+            .bind(executor_id)
+            .execute(&database)
+            .await?;
+        let (outcome, status) = action?;
+
+        let ExecutorSignalOutcome::NotSent(executor, skipped_status) = outcome else {
+            panic!("Cancel signal should not be sent to a shutdown executor");
+        };
+        assert_eq!(executor.executor_id.to_string(), executor_id.to_string());
+        assert!(skipped_status == ExecutorStatus::Shutdown);
+        assert!(status == ExecutorStatus::Shutdown);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn shutdown_all_should_shutdown_each_active_executor_once(
+        database: PgPool,
+    ) -> EmResult<()> {
+        // Run within a rolled back transaction so executors of other tests are left active
+        let service = PgExecutorService::new(&database);
+        let mut transaction = database.begin().await?;
+        let mut executor_ids = Vec::new();
+        for status in [ExecutorStatus::Active, ExecutorStatus::Shutdown] {
+            executor_ids.push(insert_executor(&mut transaction, status, None).await?);
+        }
+        let shutdown_ids = |executors: Vec<Executor>| -> Vec<String> {
+            executors
+                .iter()
+                .map(|executor| executor.executor_id.to_string())
+                .collect()
+        };
+        let first = shutdown_ids(service.shutdown_active(&mut transaction).await?);
+        let second = shutdown_ids(service.shutdown_active(&mut transaction).await?);
+        let active_count: i64 = sqlx::query_scalar(
+            r#"
+            select count(*)
+            from executor.executors
+            where
+                executor_id = any($1)
+                and status = 'Active'::executor.executor_status"#,
+        )
+        .bind(&executor_ids)
+        .fetch_one(&mut transaction)
+        .await?;
+        transaction.rollback().await?;
+
+        let [active_executor_id, shutdown_executor_id] = executor_ids.as_slice() else {
+            panic!("Expected 2 executors to be inserted");
+        };
+        assert!(first.contains(&active_executor_id.to_string()));
+        assert!(!first.contains(&shutdown_executor_id.to_string()));
+        assert!(!second.contains(&active_executor_id.to_string()));
+        assert_eq!(active_count, 0);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::no_namespace("", "exec_status_1")]
+    #[case::namespace("listener_test", "listener_test_exec_status_1")]
+    #[tokio::test]
+    async fn channel_name_should_use_session_namespace(
+        database: PgPool,
+        #[case] namespace: &str,
+        #[case] expected: &str,
+    ) -> EmResult<()> {
+        let mut transaction = database.begin().await?;
+        sqlx::query("select set_config('em.channel_ns', $1, true)")
+            .bind(namespace)
+            .execute(&mut transaction)
+            .await?;
+        let channel: String = sqlx::query_scalar("select executor.channel_name('exec_status_1')")
+            .fetch_one(&mut transaction)
+            .await?;
+        transaction.rollback().await?;
+
+        assert_eq!(channel, expected);
+        Ok(())
     }
 }
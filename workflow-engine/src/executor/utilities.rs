@@ -1,6 +1,6 @@
 //! Utilities module for components of an [Executor][crate::executor::Executor]
 
-use common::error::EmError;
+use common::{database::listener::NotificationPayload, error::EmError};
 use log::warn;
 use tokio::task::JoinHandle;
 
@@ -10,7 +10,8 @@ use crate::workflow_run::data::WorkflowRunId;
 /// tuple of [WorkflowRunId] and an optional error if the workflow run failed.
 pub type WorkflowRunWorkerResult = JoinHandle<(WorkflowRunId, Option<EmError>)>;
 
-/// Executor status notification payload values
+/// Executor status notification payload values. Payloads of an unknown [NotificationPayload]
+/// version are treated as [ExecutorStatusUpdate::NoOp].
 #[derive(PartialEq, Debug)]
 pub enum ExecutorStatusUpdate {
     Cancel,
@@ -20,7 +21,14 @@ pub enum ExecutorStatusUpdate {
 
 impl<'m> From<&'m str> for ExecutorStatusUpdate {
     fn from(s: &'m str) -> Self {
-        match s {
+        let body = match NotificationPayload::decode(s) {
+            Ok(payload) => payload.body(),
+            Err(error) => {
+                warn!("{error}");
+                return Self::NoOp;
+            }
+        };
+        match body {
             "cancel" => Self::Cancel,
             "shutdown" => Self::Shutdown,
             _ => Self::NoOp,
@@ -39,12 +47,20 @@ impl ExecutorStatusUpdate {
 }
 
 /// Container for a notification message indicating that a workflow run is to be cancelled. If the
-/// inner content is [None] then the message was not valid and should be ignored.
+/// inner content is [None] then the message was not valid (or of an unknown [NotificationPayload]
+/// version) and should be ignored.
 pub struct WorkflowRunCancelMessage(pub Option<WorkflowRunId>);
 
 impl<'m> From<&'m str> for WorkflowRunCancelMessage {
     fn from(s: &str) -> Self {
-        match s.parse() {
+        let body = match NotificationPayload::decode(s) {
+            Ok(payload) => payload.body(),
+            Err(error) => {
+                warn!("{error}");
+                return Self(None);
+            }
+        };
+        match body.parse() {
             Ok(workflow_run_id) => Self(Some(workflow_run_id)),
             Err(error) => {
                 warn!("Cannot parse workflow_run_id from `{}`. {}", s, error);
@@ -1,11 +1,17 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use common::{
     database::listener::ChangeListener,
     error::{EmError, EmResult},
 };
+use futures::{future::select_all, stream::FuturesUnordered, StreamExt};
 use log::{error, info, warn};
-use tokio::{signal::ctrl_c, task::JoinError};
+use serde_json::Value;
+use tokio::{
+    signal::ctrl_c,
+    task::JoinError,
+    time::{Interval, MissedTickBehavior},
+};
 
 use super::{
     data::{ExecutorId, ExecutorStatus},
@@ -16,10 +22,29 @@ use super::{
     },
 };
 use crate::workflow_run::{
-    data::{ExecutorWorkflowRun, TaskQueueRecord, WorkflowRunId, WorkflowRunStatus},
+    data::{
+        ExecutorWorkflowRun, TaskQueueRecord, TaskQueueRequest, WorkflowRunId, WorkflowRunStatus,
+    },
     service::{TaskQueueService, WorkflowRunsService},
 };
 
+/// Default time between heartbeats reported by an [Executor]
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// Delay before the first automatic retry of a failed task
+const AUTO_RETRY_BASE_DELAY: Duration = Duration::from_secs(5);
+/// Maximum delay before an automatic retry of a failed task
+const AUTO_RETRY_MAX_DELAY: Duration = Duration::from_secs(300);
+
+/// Delay before the automatic retry of a task that has already been retried `retry_count` times.
+/// The delay doubles with each retry, starting at [AUTO_RETRY_BASE_DELAY] and capped at
+/// [AUTO_RETRY_MAX_DELAY].
+fn auto_retry_delay(retry_count: i16) -> Duration {
+    let exponent = u32::try_from(retry_count).unwrap_or_default().min(16);
+    AUTO_RETRY_BASE_DELAY
+        .saturating_mul(2u32.pow(exponent))
+        .min(AUTO_RETRY_MAX_DELAY)
+}
+
 /// Next operations available to an [Executor] after performing various checks on the status of
 /// listeners, queues and signals.
 ///
@@ -37,11 +62,15 @@ use crate::workflow_run::{
 /// [Listen][ExecutorNextOperation::Listen] occurs when no new workflows are available to process
 /// and the executor should move into standby mode. This means the executor is only listening for
 /// wake-up notifications are a SIGINT signal.
+///
+/// [Heartbeat][ExecutorNextOperation::Heartbeat] occurs after the executor has reported a
+/// heartbeat. The executor stays in its current mode.
 enum ExecutorNextOperation {
     Continue,
     Break(ExecutorStatusUpdate),
     NextWorkflowRun(WorkflowRunId, WorkflowRunWorkerResult),
     Listen,
+    Heartbeat,
 }
 
 /// Main unit of work for the workflow engine. Manages
@@ -59,6 +88,13 @@ enum ExecutorNextOperation {
 /// listens for the previous notifications/signals but also listens for new workflow runs scheduled
 /// for pick-up.
 ///
+/// An [Executor] can be limited to a maximum number of concurrent workflow runs. Once the limit is
+/// reached, the [Executor] stops claiming new workflow runs and enters listen mode until a
+/// workflow run worker finishes, leaving the remaining scheduled workflow runs to other executors.
+///
+/// While running in either mode, the [Executor] reports a heartbeat every `heartbeat_interval` so
+/// executors that are hung (but still connected to the database) can be found and cleaned.
+///
 /// After the [Executor] has completed it's run (either through graceful shutdown, cancel or error)
 /// the [Executor] enters shutdown and cleaning mode to free workflow runs that are currently in
 /// progress (if any). After cleaning all relevant resources, the [Executor] instance is dropped to
@@ -74,6 +110,8 @@ where
     wr_service: W,
     tq_service: T,
     wr_handles: HashMap<WorkflowRunId, WorkflowRunWorkerResult>,
+    heartbeat_interval: Duration,
+    max_concurrent_runs: Option<usize>,
 }
 
 impl<U, C, S, E, W, T> Executor<E, W, T>
@@ -87,22 +125,67 @@ where
 {
     /// Create a new [Executor] using the provided services. Cleans output unused or stale
     /// executors in the database before registering the current [Executor] and returning the new
-    /// [Executor].
+    /// [Executor]. The [Executor] runs at most `max_concurrent_runs` workflow runs at once or an
+    /// unlimited number of workflow runs if [None].
     /// # Errors
     /// This function will return an error if either the cleaning of existing executors or the
     /// registering of this new executor fails.
-    pub async fn new(executor_service: &E, wr_service: &W, tq_service: &T) -> EmResult<Self> {
+    pub async fn new(
+        executor_service: &E,
+        wr_service: &W,
+        tq_service: &T,
+        max_concurrent_runs: Option<usize>,
+    ) -> EmResult<Self> {
         executor_service.clean_executors().await?;
-        let executor_id = executor_service.register_executor().await?;
+        let registration = executor_service
+            .register_executor(max_concurrent_runs)
+            .await?;
+        info!(
+            "Registered executor_id = {} with pid = {}",
+            registration.executor_id, registration.pid
+        );
+        let executor_id = registration.executor_id;
         Ok(Self {
             executor_id,
             executor_service: executor_service.clone(),
             wr_service: wr_service.clone(),
             tq_service: tq_service.clone(),
             wr_handles: HashMap::new(),
+            heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
+            max_concurrent_runs,
         })
     }
 
+    /// Set the time between heartbeats reported by the [Executor]. Defaults to
+    /// [DEFAULT_HEARTBEAT_INTERVAL], which is also used when `heartbeat_interval` is zero since a
+    /// zero period interval cannot be ticked.
+    pub const fn with_heartbeat_interval(mut self, heartbeat_interval: Duration) -> Self {
+        if !heartbeat_interval.is_zero() {
+            self.heartbeat_interval = heartbeat_interval;
+        }
+        self
+    }
+
+    /// Returns true if the [Executor] is running the maximum number of concurrent workflow runs
+    /// and should not claim any more workflow runs
+    fn is_at_capacity(&self) -> bool {
+        self.max_concurrent_runs
+            .is_some_and(|max_concurrent_runs| self.wr_handles.len() >= max_concurrent_runs)
+    }
+
+    /// Returns true if the [Executor] should wait in listen mode. An active [Executor] switches to
+    /// listen mode once it [is at capacity][Executor::is_at_capacity].
+    fn should_listen(&self, is_listen_mode: bool) -> bool {
+        if is_listen_mode || !self.is_at_capacity() {
+            return is_listen_mode;
+        }
+        info!(
+            "Executor at capacity with {} workflow runs. Switching to listen mode.",
+            self.wr_handles.len()
+        );
+        true
+    }
+
     /// Return a reference to the [ExecutorId] of the [Executor].
     pub const fn executor_id(&self) -> &ExecutorId {
         &self.executor_id
@@ -130,6 +213,8 @@ where
             .await?;
         let mut workflow_run_cancel_listener =
             self.wr_service.cancel_listener(&self.executor_id).await?;
+        let mut heartbeat = tokio::time::interval(self.heartbeat_interval);
+        heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
         loop {
             match self.status().await? {
                 ExecutorStatus::Active => {}
@@ -144,18 +229,22 @@ where
             }
             self.cleanup_workflows().await?;
 
+            is_listen_mode = self.should_listen(is_listen_mode);
+
             let next_operation = if is_listen_mode {
                 info!("Starting listen mode.");
                 self.next_operation_listen(
                     &mut executor_status_listener,
                     &mut workflow_run_cancel_listener,
                     &mut workflow_run_scheduled_listener,
+                    &mut heartbeat,
                 )
                 .await?
             } else {
                 self.next_operation_active(
                     &mut executor_status_listener,
                     &mut workflow_run_cancel_listener,
+                    &mut heartbeat,
                 )
                 .await?
             };
@@ -178,6 +267,9 @@ where
                     is_listen_mode = true;
                     info!("No more workflow runs available. Switching to listen mode.")
                 }
+                ExecutorNextOperation::Heartbeat => {
+                    executor_signal = ExecutorStatusUpdate::NoOp;
+                }
             }
         }
         self.close_executor(executor_signal).await?;
@@ -211,11 +303,18 @@ where
         ExecutorNextOperation::Break(ExecutorStatusUpdate::Shutdown)
     }
 
-    /// Select the next operation when in the active state of an executor. 1 of 4 operations are
+    /// Report a heartbeat for the current executor through the database service
+    async fn heartbeat(&self) -> EmResult<ExecutorNextOperation> {
+        self.executor_service.heartbeat(&self.executor_id).await?;
+        Ok(ExecutorNextOperation::Heartbeat)
+    }
+
+    /// Select the next operation when in the active state of an executor. 1 of 5 operations are
     /// awaited for first completion (priority given respective to order):
     /// - ctrl+c
     /// - executor status notification
     /// - workflow run cancel notification
+    /// - heartbeat interval elapsed
     /// - next workflow run available polled
     ///
     /// Whichever operation completes first will handle the completed future and return an
@@ -224,6 +323,7 @@ where
         &mut self,
         executor_status_listener: &mut U,
         workflow_run_cancel_listener: &mut C,
+        heartbeat: &mut Interval,
     ) -> EmResult<ExecutorNextOperation> {
         Ok(tokio::select! {
             biased;
@@ -232,6 +332,7 @@ where
                 handle_executor_status_notification(notification?),
             notification = workflow_run_cancel_listener.recv() => self
                 .handle_workflow_run_cancel_notification(notification?).await?,
+            _ = heartbeat.tick() => self.heartbeat().await?,
             workflow_run_id = self.next_workflow_run() => {
                 let Some((workflow_run_id, run_result)) = workflow_run_id? else {
                     return Ok(ExecutorNextOperation::Listen)
@@ -241,12 +342,14 @@ where
         })
     }
 
-    /// Select the next operation when in the listen state of an executor. 1 of 4 operations are
+    /// Select the next operation when in the listen state of an executor. 1 of 6 operations are
     /// awaited for first completion (priority given respective to order):
     /// - ctrl+c
     /// - executor status notification
     /// - workflow run cancel notification
+    /// - heartbeat interval elapsed
     /// - workflow run scheduled notification
+    /// - workflow run worker finished, freeing capacity for a new workflow run
     ///
     /// Whichever operation completes first will handle the completed future and return an
     /// [ExecutorNextOperation] variant to tell the executor what to do as the next step.
@@ -255,6 +358,7 @@ where
         executor_status_listener: &mut U,
         workflow_run_cancel_listener: &mut C,
         workflow_run_scheduled_listener: &mut S,
+        heartbeat: &mut Interval,
     ) -> EmResult<ExecutorNextOperation> {
         Ok(tokio::select! {
             biased;
@@ -263,8 +367,14 @@ where
                 handle_executor_status_notification(notification?),
             notification = workflow_run_cancel_listener.recv() => self
                 .handle_workflow_run_cancel_notification(notification?).await?,
+            _ = heartbeat.tick() => self.heartbeat().await?,
             notification = workflow_run_scheduled_listener.recv() => Self::
                 handle_workflow_run_scheduled_notification(notification)?,
+            (workflow_run_id, result) = self.next_finished_worker() => {
+                self.wr_handles.remove(&workflow_run_id);
+                Self::handle_worker_result(&workflow_run_id, result);
+                ExecutorNextOperation::Continue
+            }
         })
     }
 
@@ -306,6 +416,37 @@ where
         })
     }
 
+    /// Join the next workflow run worker to finish, returning the [WorkflowRunId] of the worker
+    /// alongside the join result. The handle is left in the executor's handles for the caller to
+    /// remove. Never completes when the executor has no workflow run workers.
+    async fn next_finished_worker(
+        &mut self,
+    ) -> (
+        WorkflowRunId,
+        Result<(WorkflowRunId, Option<EmError>), JoinError>,
+    ) {
+        if self.wr_handles.is_empty() {
+            return std::future::pending().await;
+        }
+        let workers = self.wr_handles.iter_mut().map(|(workflow_run_id, handle)| {
+            Box::pin(async move { (*workflow_run_id, handle.await) })
+        });
+        let (finished, ..) = select_all(workers).await;
+        finished
+    }
+
+    /// Handle the `result` of joining a finished workflow run worker. Errors returned by the
+    /// worker itself are logged within the worker so only a [JoinError] is handled.
+    fn handle_worker_result(
+        workflow_run_id: &WorkflowRunId,
+        result: Result<(WorkflowRunId, Option<EmError>), JoinError>,
+    ) {
+        match result {
+            Ok(_) => info!("Joined finished worker for workflow_run_id = {workflow_run_id}"),
+            Err(error) => Self::handle_join_error(workflow_run_id, &error),
+        }
+    }
+
     /// Handle [JoinError] returned when a tokio task does not complete successfully when joined.
     /// Simply logs and discards the error.
     fn handle_join_error(workflow_run_id: &WorkflowRunId, error: &JoinError) {
@@ -433,7 +574,10 @@ where
                 "Removing finished handle for workflow_run_id = {}",
                 workflow_run_id
             );
-            self.wr_handles.remove(&workflow_run_id);
+            let Some(handle) = self.wr_handles.remove(&workflow_run_id) else {
+                continue;
+            };
+            Self::handle_worker_result(&workflow_run_id, handle.await);
         }
 
         info!("Checking owned workflows");
@@ -525,7 +669,14 @@ where
 }
 
 /// Container with the workflow run ID associated with the worker and the necessary services to
-/// complete workflow run operations
+/// complete workflow run operations.
+///
+/// A worker can run multiple tasks of its workflow run concurrently. Every task returned by
+/// [TaskQueueService::next_tasks] is started immediately and, each time a running task finishes,
+/// the worker claims the tasks that have since become runnable. Once a task fails, no new tasks
+/// are claimed but tasks that are already running are allowed to finish. The same applies once the
+/// workflow run is paused, which is checked before claiming new tasks. The workflow run is
+/// completed exactly once, after no task is running and no more tasks are runnable.
 struct WorkflowRunWorker<W, T>
 where
     W: WorkflowRunsService,
@@ -562,39 +713,164 @@ where
         record: &TaskQueueRecord,
         is_paused: bool,
         message: Option<String>,
+        output: Option<Value>,
     ) -> EmResult<()> {
         self.tq_service
-            .complete_task_run(record, is_paused, message)
+            .complete_task_run(record, is_paused, message, output)
             .await
     }
 
     /// Fail the task run, updating the database record with error information
     async fn fail_task(&self, record: &TaskQueueRecord, error: EmError) -> EmResult<()> {
         error!("Task failed, {:?}", record);
+        self.tq_service.fail_task_run(record, error).await
+    }
+
+    /// Fail the task run then retry the task once the backoff delay has elapsed. The task stays
+    /// failed during the delay so no other task of the workflow run is claimed in the meantime.
+    async fn auto_retry_task(&self, record: &TaskQueueRecord, error: EmError) -> EmResult<()> {
+        let delay = auto_retry_delay(record.retry_count);
+        warn!(
+            "Task failed, retry {} of {} in {:?}, {:?}",
+            record.retry_count + 1,
+            record.max_retries,
+            delay,
+            record
+        );
         self.tq_service.fail_task_run(record, error).await?;
-        self.wr_service.complete(&self.workflow_run_id).await
+        tokio::time::sleep(delay).await;
+        let request = TaskQueueRequest::new(record.workflow_run_id, record.task_order);
+        self.tq_service.retry_task(&request).await
+    }
+
+    /// Run the task `record` to completion, updating the database record with the run results.
+    /// Failed task runs are automatically retried until the task's `max_retries` is reached.
+    /// Returns false if the task run failed without being retried. If the outcome of the run
+    /// cannot be recorded, the task is failed with that error and false is returned so a single
+    /// task never stops the worker from waiting on the other running tasks.
+    async fn run_task(&self, record: TaskQueueRecord) -> bool {
+        match self.try_run_task(&record).await {
+            Ok(is_success) => is_success,
+            Err(error) => {
+                error!("Could not record the outcome of task run, {error}. {record:?}");
+                if let Err(fail_error) = self.fail_task(&record, error).await {
+                    error!("Could not fail task, {fail_error}. {record:?}");
+                }
+                false
+            }
+        }
+    }
+
+    /// Run the task `record` and record the outcome. See [run_task][WorkflowRunWorker::run_task]
+    /// # Errors
+    /// This function will return an error if the outcome of the task run cannot be recorded
+    async fn try_run_task(&self, record: &TaskQueueRecord) -> EmResult<bool> {
+        info!("Running task, {record:?}");
+        match self.tq_service.run_task(record).await {
+            Ok((is_paused, message, output)) => {
+                self.complete_task(record, is_paused, message, output)
+                    .await?;
+                Ok(true)
+            }
+            Err(error) if record.retry_count < record.max_retries => {
+                self.auto_retry_task(record, error).await?;
+                Ok(true)
+            }
+            Err(error) => {
+                self.fail_task(record, error).await?;
+                Ok(false)
+            }
+        }
     }
 
-    /// Entry point for running the worker. Continues to get the next task to run until no more
-    /// tasks are available or a task fails. Once this is completed, the worker is dropped.
+    /// Check if the workflow run has been paused since the worker started
+    async fn is_paused(&self) -> EmResult<bool> {
+        let workflow_run = self.wr_service.read_one(&self.workflow_run_id).await?;
+        Ok(workflow_run.status == WorkflowRunStatus::Paused)
+    }
+
+    /// Claim the next runnable tasks of the workflow run. Returns [None] if the workflow run has
+    /// been paused.
+    /// # Errors
+    /// This function will return an error if the workflow run cannot be read or the tasks cannot
+    /// be claimed
+    async fn claim_tasks(&self) -> EmResult<Option<Vec<TaskQueueRecord>>> {
+        if self.is_paused().await? {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.tq_service.next_tasks(&self.workflow_run_id).await?,
+        ))
+    }
+
+    /// Entry point for running the worker. Continues to claim and run the runnable tasks until no
+    /// tasks are running or available, a task fails or the workflow run is paused. Claimed tasks
+    /// run concurrently, bounded by the workflow's `max_parallel_tasks` which is enforced when
+    /// claiming tasks. Once this is completed, the workflow run is completed (exactly once, after
+    /// every running task has finished) and the worker is dropped. If the worker cannot check the
+    /// workflow run or claim tasks, no more tasks are claimed and the error is returned once every
+    /// running task has finished.
     async fn run(self) -> EmResult<()> {
+        let mut running_tasks = FuturesUnordered::new();
+        let mut has_failed_task = false;
+        let mut is_paused = false;
+        let mut claim_error = None;
         loop {
-            let Some(next_task) = self.tq_service.next_task(&self.workflow_run_id).await? else {
-                self.wr_service.complete(&self.workflow_run_id).await?;
-                info!("No available task to run. Exiting worker");
+            if !has_failed_task && !is_paused && claim_error.is_none() {
+                match self.claim_tasks().await {
+                    Ok(Some(records)) => {
+                        for record in records {
+                            running_tasks.push(self.run_task(record));
+                        }
+                    }
+                    Ok(None) => {
+                        info!(
+                            "Workflow run = {} paused. Waiting for running tasks to finish",
+                            self.workflow_run_id
+                        );
+                        is_paused = true;
+                    }
+                    Err(error) => {
+                        error!(
+                            "Could not claim tasks for workflow run = {}. Waiting for running \
+                             tasks to finish. {error}",
+                            self.workflow_run_id
+                        );
+                        claim_error = Some(error);
+                    }
+                }
+            }
+            let Some(is_success) = running_tasks.next().await else {
                 break;
             };
-            info!("Running task, {:?}", next_task);
-            match self.tq_service.run_task(&next_task).await {
-                Ok((is_paused, message)) => {
-                    self.complete_task(&next_task, is_paused, message).await?
-                }
-                Err(error) => {
-                    self.fail_task(&next_task, error).await?;
-                    break;
-                }
+            if !is_success {
+                has_failed_task = true;
             }
         }
+        if let Some(error) = claim_error {
+            return Err(error);
+        }
+        self.wr_service.complete(&self.workflow_run_id).await?;
+        info!("No available task to run. Exiting worker");
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use rstest::rstest;
+
+    use super::auto_retry_delay;
+
+    #[rstest]
+    #[case::first_retry(0, Duration::from_secs(5))]
+    #[case::second_retry(1, Duration::from_secs(10))]
+    #[case::third_retry(2, Duration::from_secs(20))]
+    #[case::capped(10, Duration::from_secs(300))]
+    #[case::negative(-1, Duration::from_secs(5))]
+    fn auto_retry_delay_should_return(#[case] retry_count: i16, #[case] expected: Duration) {
+        assert_eq!(auto_retry_delay(retry_count), expected);
+    }
+}
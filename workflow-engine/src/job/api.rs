@@ -1,9 +1,13 @@
 use actix_web::{web, Scope};
-use common::api::{request::ApiRequest, ApiResponse, QueryApiFormat};
+use common::api::{
+    pagination::{CursorPage, CursorPagination},
+    request::ApiRequest,
+    ApiResponse, QueryApiFormat,
+};
 use log::error;
 
 use crate::job::{
-    data::{Job, JobId, JobRequest},
+    data::{Job, JobId, JobMin, JobRequest},
     service::JobService,
 };
 
@@ -17,7 +21,11 @@ where
                 .route(web::get().to(jobs::<J>))
                 .route(web::post().to(create_job::<J>)),
         )
+        .route("/page", web::get().to(jobs_page::<J>))
+        .route("/queue", web::get().to(job_queue::<J>))
         .route("/{job_id}", web::get().to(job::<J>))
+        .route("/{job_id}/skip", web::post().to(skip_job::<J>))
+        .route("/{job_id}/run-now", web::post().to(run_job_now::<J>))
 }
 
 /// API endpoint to fetch all `Job`s currently registered
@@ -38,6 +46,22 @@ where
     }
 }
 
+/// API endpoint to fetch a page of `Job`s after the job referenced by the `after` cursor
+async fn jobs_page<J>(
+    page: actix_web::web::Query<CursorPagination>,
+    service: actix_web::web::Data<J>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<CursorPage<Job>>
+where
+    J: JobService,
+{
+    let format = query.into_inner();
+    match service.read_many_after(&page).await {
+        Ok(jobs) => ApiResponse::success(jobs, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
 /// API endpoint to fetch the [Job] details of a cron job specified by `job_id`
 async fn job<J>(
     job_id: actix_web::web::Path<JobId>,
@@ -76,3 +100,70 @@ where
         }
     }
 }
+
+/// API endpoint to fetch the current job queue as seen by the job worker. Also notifies the job
+/// worker to reload its queue so it re-syncs with the returned entries.
+async fn job_queue<J>(
+    service: actix_web::web::Data<J>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<JobMin>>
+where
+    J: JobService,
+{
+    let format = query.into_inner();
+    let queue = match service.read_queued().await {
+        Ok(queue) => queue,
+        Err(error) => {
+            error!("{error}");
+            return ApiResponse::error(error, format.f);
+        }
+    };
+    match service.refresh_queue().await {
+        Ok(_) => ApiResponse::success(queue, format.f),
+        Err(error) => {
+            error!("{error}");
+            ApiResponse::error(error, format.f)
+        }
+    }
+}
+
+/// API endpoint to advance the next run of the [Job] specified by `job_id` past its current tick
+/// without running the job. Fails if the job has a workflow run in progress.
+async fn skip_job<J>(
+    job_id: actix_web::web::Path<JobId>,
+    service: actix_web::web::Data<J>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Job>
+where
+    J: JobService,
+{
+    let format = query.into_inner();
+    match service.skip_job(&job_id).await {
+        Ok(job) => ApiResponse::success(job, format.f),
+        Err(error) => {
+            error!("{error}");
+            ApiResponse::error(error, format.f)
+        }
+    }
+}
+
+/// API endpoint to immediately start a workflow run for the [Job] specified by `job_id`, even if
+/// the job is paused. The schedule of the job is not changed. Fails if the job already has a
+/// workflow run in flight.
+async fn run_job_now<J>(
+    job_id: actix_web::web::Path<JobId>,
+    service: actix_web::web::Data<J>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Job>
+where
+    J: JobService,
+{
+    let format = query.into_inner();
+    match service.force_run(&job_id).await {
+        Ok(job) => ApiResponse::success(job, format.f),
+        Err(error) => {
+            error!("{error}");
+            ApiResponse::error(error, format.f)
+        }
+    }
+}
@@ -1,7 +1,14 @@
 use std::str::FromStr;
 
-use chrono::{NaiveDateTime, NaiveTime};
-use common::{api::ApiRequestValidator, error::EmError};
+use chrono::{NaiveDateTime, NaiveTime, TimeZone, Utc};
+use common::{
+    api::{
+        id::{deserialize_id, parse_id},
+        ApiRequestValidator,
+    },
+    error::{EmError, EmResult},
+};
+use cron::Schedule;
 use serde::{
     de::{MapAccess, Visitor},
     ser::SerializeStruct,
@@ -24,6 +31,8 @@ pub enum JobTypeEnum {
     Scheduled,
     #[serde(rename = "interval")]
     Interval,
+    #[serde(rename = "cron")]
+    Cron,
 }
 
 impl FromStr for JobTypeEnum {
@@ -33,9 +42,11 @@ impl FromStr for JobTypeEnum {
         match s {
             "scheduled" => Ok(Self::Scheduled),
             "interval" => Ok(Self::Interval),
-            _ => Err(EmError::Generic(format!(
-                "Parse JobTypeEnum from string. Expected `scheduled` or `interval` but got `{s}`"
-            ))),
+            "cron" => Ok(Self::Cron),
+            _ => Err(EmError::InvalidRequest {
+                request: format!("JobTypeEnum `{s}`"),
+                reason: "Expected `scheduled`, `interval` or `cron`".to_owned(),
+            }),
         }
     }
 }
@@ -83,7 +94,7 @@ impl sqlx::postgres::PgHasArrayType for ScheduleEntry {
 /// Minimum details about a job to execute. Details fetched from `job.v_queued_jobs` and later
 /// packed into a hashmap (key = `job_id`). The `next_run` value is the next time the job needs to
 /// be executed.
-#[derive(sqlx::FromRow)]
+#[derive(sqlx::FromRow, Serialize, Deserialize)]
 pub struct JobMin {
     pub job_id: JobId,
     pub next_run: NaiveDateTime,
@@ -192,8 +203,113 @@ where
     pg_interval.end()
 }
 
+/// Number of fields within a standard cron expression (minute, hour, day of month, month and day
+/// of week)
+const CRON_EXPRESSION_FIELDS: usize = 5;
+
+/// Parse a single day of the week from a standard cron expression. Sunday can be either 0 or 7
+fn parse_cron_day_of_week(value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(day) if day <= 7 => Ok(day),
+        _ => Err(format!(
+            "Day of week must be a number between 0 and 7 or a day name but found `{value}`"
+        )),
+    }
+}
+
+/// Convert the day of week field of a standard cron expression (Sunday = 0 or 7, Monday = 1) to
+/// the numbering expected by the `cron` crate (Sunday = 1, Saturday = 7). Numeric ranges and steps
+/// are expanded into a list of days. Wildcards and day names are kept as is since both formats
+/// share their meaning.
+fn convert_cron_day_of_week(field: &str) -> Result<String, String> {
+    let mut days: Vec<String> = Vec::new();
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step = match step.parse() {
+                    Ok(step) if step > 0 => step,
+                    _ => return Err(format!("Step must be a positive number but found `{step}`")),
+                };
+                (range, Some(step))
+            }
+            None => (item, None),
+        };
+        if range == "*" || range.chars().any(|c| c.is_ascii_alphabetic()) {
+            days.push(item.to_owned());
+            continue;
+        }
+        let (start, end) = match (range.split_once('-'), step) {
+            (Some((start, end)), _) => {
+                (parse_cron_day_of_week(start)?, parse_cron_day_of_week(end)?)
+            }
+            (None, Some(_)) => (parse_cron_day_of_week(range)?, 6),
+            (None, None) => {
+                let day = parse_cron_day_of_week(range)?;
+                (day, day)
+            }
+        };
+        if start > end {
+            return Err(format!(
+                "Day of week range must be ascending but found `{range}`"
+            ));
+        }
+        for day in (start..=end).step_by(step.unwrap_or(1)) {
+            let day = (day % 7 + 1).to_string();
+            if !days.contains(&day) {
+                days.push(day);
+            }
+        }
+    }
+    Ok(days.join(","))
+}
+
+/// Parse a standard 5 field cron `expression` (minute, hour, day of month, month and day of week)
+/// into a [Schedule]. The `cron` crate expects a leading seconds field and numbers the days of the
+/// week starting from Sunday so the expression is converted before parsing. Note that unlike most
+/// cron implementations, a job with both a day of month and a day of week restriction only runs
+/// when both match.
+/// # Errors
+/// This function returns [EmError::InvalidCronExpression] if the expression does not have 5 fields
+/// or any field cannot be parsed.
+pub fn parse_cron_expression(expression: &str) -> EmResult<Schedule> {
+    let invalid = |reason: String| EmError::InvalidCronExpression {
+        expression: expression.to_owned(),
+        reason,
+    };
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let [minute, hour, day_of_month, month, day_of_week] = fields.as_slice() else {
+        return Err(invalid(format!(
+            "Expected {CRON_EXPRESSION_FIELDS} fields (minute hour day-of-month month \
+             day-of-week) but found {}",
+            fields.len()
+        )));
+    };
+    let day_of_week = convert_cron_day_of_week(day_of_week).map_err(invalid)?;
+    Schedule::from_str(&format!(
+        "0 {minute} {hour} {day_of_month} {month} {day_of_week}"
+    ))
+    .map_err(|error| invalid(error.to_string()))
+}
+
+/// Find the first time strictly after `after` that matches the cron `expression`. All times are
+/// in UTC, matching the `next_run` of jobs.
+/// # Errors
+/// This function returns [EmError::InvalidCronExpression] if the expression cannot be parsed or
+/// no future time matches the expression.
+pub fn next_cron_run(expression: &str, after: &NaiveDateTime) -> EmResult<NaiveDateTime> {
+    parse_cron_expression(expression)?
+        .after(&Utc.from_utc_datetime(after))
+        .next()
+        .map(|next_run| next_run.naive_utc())
+        .ok_or_else(|| EmError::InvalidCronExpression {
+            expression: expression.to_owned(),
+            reason: format!("No run matches the expression after {after}"),
+        })
+}
+
 /// Describes the only difference between job entry types. Jobs are either scheduled with a 1 or
-/// more weekly schedule entries or follow an interval schedule of a defined period between runs.
+/// more weekly schedule entries, follow an interval schedule of a defined period between runs or
+/// run whenever a standard 5 field cron expression matches (evaluated in UTC).
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type")]
 pub enum JobType {
@@ -207,8 +323,16 @@ pub enum JobType {
     Interval {
         interval: PgInterval,
     },
+    Cron {
+        expression: String,
+    },
 }
 
+/// Number of microseconds within a second of a [PgInterval]
+pub const MICROSECONDS_PER_SECOND: i64 = 1_000_000;
+/// Number of microseconds within a minute of a [PgInterval]
+pub const MICROSECONDS_PER_MINUTE: i64 = 60 * MICROSECONDS_PER_SECOND;
+
 impl JobType {
     /// Create a new instance of an [Interval][JobType::Interval] job type
     pub const fn new_interval(months: i32, days: i32, microseconds: i64) -> Self {
@@ -225,6 +349,25 @@ impl JobType {
     pub const fn new_scheduled(entries: Vec<ScheduleEntry>) -> Self {
         Self::Scheduled { entries }
     }
+
+    /// Create a new instance of a [Cron][JobType::Cron] job type
+    pub const fn new_cron(expression: String) -> Self {
+        Self::Cron { expression }
+    }
+
+    /// Split the interval of an [Interval][JobType::Interval] job type into its human readable
+    /// `(months, days, minutes, seconds)` components. The time portion of the interval is kept as
+    /// whole minutes (i.e. hours are not carried into days) with the remaining whole seconds,
+    /// matching how postgres stores the interval. Returns [None] for a
+    /// [Scheduled][JobType::Scheduled] or [Cron][JobType::Cron] job type.
+    pub const fn interval_components(&self) -> Option<(i32, i32, i64, i64)> {
+        let Self::Interval { interval } = self else {
+            return None;
+        };
+        let minutes = interval.microseconds / MICROSECONDS_PER_MINUTE;
+        let seconds = (interval.microseconds % MICROSECONDS_PER_MINUTE) / MICROSECONDS_PER_SECOND;
+        Some((interval.months, interval.days, minutes, seconds))
+    }
 }
 
 /// Job details as fetched from `job.v_jobs`. Contains the job and underlining workflow details as
@@ -278,6 +421,9 @@ where
             JobTypeEnum::Interval => JobType::Interval {
                 interval: row.try_get("job_interval")?,
             },
+            JobTypeEnum::Cron => JobType::Cron {
+                expression: row.try_get("cron_expression")?,
+            },
         };
         Ok(Self {
             job_id: row.try_get("job_id")?,
@@ -333,37 +479,60 @@ impl ApiRequestValidator for JobRequestValidator {
     type ErrorMessage = &'static str;
     type Request = JobRequest;
 
-    fn validate(request: &Self::Request) -> Result<(), Self::ErrorMessage> {
+    fn validate(request: &Self::Request) -> Result<(), Vec<Self::ErrorMessage>> {
+        let mut errors = Vec::new();
         if request.maintainer.trim().is_empty() {
-            return Err("Maintainer must not be empty or whitespace");
+            errors.push("Maintainer must not be empty or whitespace");
         }
 
-        let JobType::Scheduled { entries } = &request.job_type else {
-            return Ok(());
-        };
-
-        if entries.is_empty() {
-            return Err("Schedule cannot be empty");
-        }
-        let mut seen = std::collections::HashSet::new();
-        for entry in entries {
-            if entry.day_of_the_week > 7 || entry.day_of_the_week < 1 {
-                return Err(
+        if let JobType::Scheduled { entries } = &request.job_type {
+            if entries.is_empty() {
+                errors.push("Schedule cannot be empty");
+            }
+            let mut seen = std::collections::HashSet::new();
+            let mut invalid_day = false;
+            let mut duplicate_entry = false;
+            for entry in entries {
+                invalid_day |= entry.day_of_the_week > 7 || entry.day_of_the_week < 1;
+                duplicate_entry |= !seen.insert(entry);
+            }
+            if invalid_day {
+                errors.push(
                     "All schedule entries must have a 'day_of_the_week' attribute between 1 and 7",
                 );
             }
-            if !seen.insert(entry) {
-                return Err("Schedule Entry objects must not duplicate for a single job");
+            if duplicate_entry {
+                errors.push("Schedule Entry objects must not duplicate for a single job");
+            }
+        }
+
+        if let Some((months, days, minutes, seconds)) = request.job_type.interval_components() {
+            if months < 0 || days < 0 || minutes < 0 || seconds < 0 {
+                errors.push("Interval must not have negative components");
+            } else if months == 0 && days == 0 && minutes == 0 && seconds == 0 {
+                errors.push("Interval must be at least 1 second");
             }
         }
 
+        if let JobType::Cron { expression } = &request.job_type {
+            if parse_cron_expression(expression).is_err() {
+                errors.push(
+                    "Cron expression must be a valid 5 field expression (minute hour day-of-month \
+                     month day-of-week)",
+                );
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
         Ok(())
     }
 }
 
 /// Wrapper for a `job_id` value. Made to ensure data passed as the id of a job is correct and not
 /// just any i64 value.
-#[derive(sqlx::Type, Eq, Hash, PartialEq, Deserialize, Serialize, Clone, Copy)]
+#[derive(sqlx::Type, Eq, Hash, PartialEq, Serialize, Clone, Copy)]
 #[sqlx(transparent)]
 pub struct JobId(i64);
 
@@ -373,6 +542,31 @@ impl From<i64> for JobId {
     }
 }
 
+impl FromStr for JobId {
+    type Err = EmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(parse_id("job_id", s)?))
+    }
+}
+
+impl TryFrom<&str> for JobId {
+    type Error = EmError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl<'de> Deserialize<'de> for JobId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_id("job_id", deserializer).map(Self)
+    }
+}
+
 impl JobId {
     /// Extract the inner [`i64`] value
     pub const fn into_inner(self) -> i64 {
@@ -385,3 +579,100 @@ impl std::fmt::Display for JobId {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+    use common::{api::ApiRequestValidator, error::EmError};
+    use rstest::rstest;
+
+    use super::{
+        next_cron_run, parse_cron_expression, JobRequest, JobRequestValidator, JobType,
+        ScheduleEntry, MICROSECONDS_PER_MINUTE, MICROSECONDS_PER_SECOND,
+    };
+    use crate::workflow::data::WorkflowId;
+
+    /// Create a datetime within June 2023 for testing cron expressions
+    fn datetime(day: u32, hour: u32, minute: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(2023, 6, day).and_then(|date| date.and_hms_opt(hour, minute, 0))
+    }
+
+    #[rstest]
+    #[case::sub_minute(JobType::new_interval(0, 0, 45 * MICROSECONDS_PER_SECOND), (0, 0, 0, 45))]
+    #[case::multi_hour(
+        JobType::new_interval(0, 0, 150 * MICROSECONDS_PER_MINUTE + 30 * MICROSECONDS_PER_SECOND),
+        (0, 0, 150, 30),
+    )]
+    #[case::multi_day(JobType::new_interval(0, 3, 90 * MICROSECONDS_PER_MINUTE), (0, 3, 90, 0))]
+    #[case::multi_day_in_time(
+        JobType::new_interval(1, 0, 50 * 60 * MICROSECONDS_PER_MINUTE),
+        (1, 0, 3000, 0),
+    )]
+    fn interval_components_should_split_interval(
+        #[case] job_type: JobType,
+        #[case] expected: (i32, i32, i64, i64),
+    ) {
+        assert_eq!(job_type.interval_components(), Some(expected));
+    }
+
+    #[test]
+    fn interval_components_should_be_none_when_scheduled() {
+        let job_type = JobType::new_scheduled(vec![ScheduleEntry::new(1, NaiveTime::MIN)]);
+
+        assert_eq!(job_type.interval_components(), None);
+    }
+
+    #[rstest]
+    #[case::weekdays_over_weekend("0 2 * * 1-5", datetime(2, 3, 0), datetime(5, 2, 0))]
+    #[case::sunday_as_zero("30 8 * * 0", datetime(5, 0, 0), datetime(11, 8, 30))]
+    #[case::sunday_as_seven("30 8 * * 7", datetime(5, 0, 0), datetime(11, 8, 30))]
+    #[case::day_names("0 12 * * Sat", datetime(5, 0, 0), datetime(10, 12, 0))]
+    #[case::day_step("0 0 * * 1/2", datetime(6, 0, 0), datetime(7, 0, 0))]
+    #[case::every_fifteen_minutes("*/15 * * * *", datetime(5, 10, 7), datetime(5, 10, 15))]
+    #[case::exactly_on_match("0 2 * * *", datetime(5, 2, 0), datetime(6, 2, 0))]
+    fn next_cron_run_should_find_next_match(
+        #[case] expression: &str,
+        #[case] after: Option<NaiveDateTime>,
+        #[case] expected: Option<NaiveDateTime>,
+    ) {
+        let next_run = after.and_then(|after| next_cron_run(expression, &after).ok());
+
+        assert_eq!(next_run, expected);
+    }
+
+    #[rstest]
+    #[case::too_few_fields("0 2 * *")]
+    #[case::seconds_field("0 0 2 * * 1-5")]
+    #[case::minute_out_of_range("61 * * * *")]
+    #[case::day_of_week_out_of_range("0 2 * * 8")]
+    #[case::descending_day_of_week("0 2 * * 5-1")]
+    #[case::zero_step("0 2 * * 1/0")]
+    #[case::blank("   ")]
+    fn parse_cron_expression_should_fail_when(#[case] expression: &str) {
+        let result = parse_cron_expression(expression);
+
+        assert!(
+            matches!(result, Err(EmError::InvalidCronExpression { .. })),
+            "Expected an invalid cron expression error for `{expression}`"
+        );
+    }
+
+    #[rstest]
+    #[case::valid("0 2 * * 1-5", true)]
+    #[case::invalid("0 2 * * 1-5 *", false)]
+    fn job_request_validator_should_check_cron_expression(
+        #[case] expression: &str,
+        #[case] is_valid: bool,
+    ) {
+        let request = JobRequest::new(
+            WorkflowId::from(1),
+            "test@example.com".to_owned(),
+            JobType::new_cron(expression.to_owned()),
+            None,
+        );
+
+        let result = JobRequestValidator::validate(&request);
+
+        assert_eq!(result.is_ok(), is_valid);
+    }
+}
@@ -1,7 +1,10 @@
 pub mod postgres;
 
 use common::{
-    api::ApiRequestValidator,
+    api::{
+        pagination::{CursorPage, CursorPagination},
+        ApiRequestValidator,
+    },
     database::{listener::ChangeListener, Database},
     error::EmResult,
 };
@@ -24,7 +27,8 @@ where
     type WorkflowRunService: WorkflowRunsService<Database = Self::Database>;
 
     /// Create a new job with the data contained within `request`. Branches to specific calls for
-    /// [JobType::Scheduled] and [JobType::Interval].
+    /// [JobType::Scheduled], [JobType::Interval] and [JobType::Cron]. Cron jobs without a
+    /// requested `next_run` first run at the next time matching the cron expression.
     async fn create_job(&self, request: &JobRequest) -> EmResult<Job>;
     /// Read a single job record from `job.v_jobs` for the specified `job_id`. Will return [Err]
     /// when the id does not match a record
@@ -32,15 +36,34 @@ where
 
     /// Read all job records found from `job.v_jobs`
     async fn read_many(&self) -> EmResult<Vec<Job>>;
+    /// Read a page of job records from `job.v_jobs`, ordered by `job_id`. The page starts after
+    /// the job referenced by the `page` cursor so iteration is stable when jobs are created
+    /// between page requests.
+    async fn read_many_after(&self, page: &CursorPagination) -> EmResult<CursorPage<Job>>;
     /// Read all job records from `job.v_queued_jobs`. This excludes all job entries that are
     /// paused or currently have a workflow run that not complete. Ordered by the `next_run` field
     async fn read_queued(&self) -> EmResult<Vec<JobMin>>;
     /// Run the job specified by the `job_id`. Returns the [Job] entry if the `job_id` matches a
-    /// record
+    /// record. The `next_run` of a cron job is recomputed from its cron expression
     async fn run_job(&self, job_id: &JobId) -> EmResult<Job>;
-    /// Complete the job specified by the `job_id`. Returns the [Job] entry if the `job_id` matches
-    /// a record
+    /// Immediately start a workflow run for the job specified by the `job_id`, even if the job is
+    /// paused. The `next_run` and paused state of the job are not changed so the normal schedule
+    /// continues as before. Returns the [Job] entry if the `job_id` matches a record. Will return
+    /// [Err] when the job already has a workflow run that is still in flight.
+    async fn force_run(&self, job_id: &JobId) -> EmResult<Job>;
+    /// Complete the job specified by the `job_id`. A failed run pauses the job unless the run was
+    /// started by [force_run][JobService::force_run], which never changes the paused state of the
+    /// job. Returns the [Job] entry if the `job_id` matches a record
     async fn complete_job(&self, job_id: &JobId) -> EmResult<Job>;
+    /// Pause the job specified by the `job_id`, removing it from `job.v_queued_jobs`. Returns the
+    /// [Job] entry if the `job_id` matches a record
+    async fn pause_job(&self, job_id: &JobId) -> EmResult<Job>;
+    /// Advance the `next_run` of the job specified by the `job_id` past its current tick without
+    /// running the job. Returns the [Job] entry if the `job_id` matches a record. Will return [Err]
+    /// when the job has a workflow run that is not complete
+    async fn skip_job(&self, job_id: &JobId) -> EmResult<Job>;
+    /// Notify the job worker that it should reload the job queue
+    async fn refresh_queue(&self) -> EmResult<()>;
     /// Get a [ChangeListener] for updates on the job queue this service is watching.
     async fn listener(&self) -> EmResult<Self::Listener>;
 }
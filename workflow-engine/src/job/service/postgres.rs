@@ -1,20 +1,27 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, Utc};
 use common::{
-    api::ApiRequestValidator,
+    api::{
+        join_validation_messages,
+        pagination::{CursorPage, CursorPagination, CursorPaginationValidator},
+        ApiRequestValidator,
+    },
+    audit::{postgres::PgAuditSink, AuditEvent, AuditSink},
     database::{
         connection::finalize_transaction,
+        listener::{ChannelNamespace, NotificationPayload},
         postgres::{listener::PgChangeListener, Postgres},
     },
     error::{EmError, EmResult},
 };
-use sqlx::{
-    postgres::{types::PgInterval, PgListener},
-    PgPool,
-};
+use serde_json::{json, Value};
+use sqlx::{postgres::types::PgInterval, PgPool};
 
 use crate::{
     job::{
-        data::{Job, JobId, JobMin, JobRequest, JobRequestValidator, JobType, ScheduleEntry},
+        data::{
+            next_cron_run, Job, JobId, JobMin, JobRequest, JobRequestValidator, JobType,
+            ScheduleEntry,
+        },
         service::JobService,
         worker::NotificationAction,
     },
@@ -29,6 +36,8 @@ use crate::{
 pub struct PgJobsService {
     pool: PgPool,
     workflow_runs_service: PgWorkflowRunsService,
+    audit_sink: Option<PgAuditSink>,
+    channel_namespace: ChannelNamespace,
 }
 
 impl PgJobsService {
@@ -37,6 +46,36 @@ impl PgJobsService {
         Self {
             pool: pool.clone(),
             workflow_runs_service: workflow_runs_service.clone(),
+            audit_sink: None,
+            channel_namespace: ChannelNamespace::from_env(),
+        }
+    }
+
+    /// Set the [PgAuditSink] that receives an [AuditEvent] for every mutation of a job (e.g.
+    /// creating, running or pausing a job). Mutations are not audited by default.
+    pub fn with_audit_sink(mut self, audit_sink: &PgAuditSink) -> Self {
+        self.audit_sink = Some(audit_sink.clone());
+        self
+    }
+
+    /// Set the [ChannelNamespace] used to name the channels of listeners created by this service.
+    /// Defaults to the namespace of the `EM_CHANNEL_NS` environment variable. Must match the
+    /// namespace of the sessions that send notifications (see [with_channel_namespace]).
+    ///
+    /// [with_channel_namespace]: common::database::postgres::listener::with_channel_namespace
+    pub fn with_channel_namespace(mut self, channel_namespace: ChannelNamespace) -> Self {
+        self.channel_namespace = channel_namespace;
+        self
+    }
+
+    /// Emit an [AuditEvent] for the `action` performed against the `job_id` if an audit sink has
+    /// been set
+    async fn audit(&self, action: &str, job_id: &JobId, details: Option<Value>) {
+        if let Some(audit_sink) = &self.audit_sink {
+            let target = format!("job:{job_id}");
+            audit_sink
+                .emit(AuditEvent::new(action, target, details))
+                .await;
         }
     }
 
@@ -73,6 +112,27 @@ impl PgJobsService {
             .await?;
         Ok(job_id)
     }
+
+    /// Create a new cron job using the specified details from the parameters. If no `next_run` is
+    /// provided, the first run is the next time matching the cron `expression`
+    async fn create_cron_job(
+        &self,
+        workflow_id: &WorkflowId,
+        maintainer: &str,
+        expression: &str,
+        next_run: &Option<NaiveDateTime>,
+    ) -> EmResult<JobId> {
+        let next_run =
+            next_run.map_or_else(|| next_cron_run(expression, &Utc::now().naive_utc()), Ok)?;
+        let job_id = sqlx::query_scalar("select job.create_cron_job($1,$2,$3,$4)")
+            .bind(workflow_id)
+            .bind(maintainer)
+            .bind(expression)
+            .bind(next_run)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(job_id)
+    }
 }
 
 impl JobService for PgJobsService {
@@ -82,7 +142,7 @@ impl JobService for PgJobsService {
     type WorkflowRunService = PgWorkflowRunsService;
 
     async fn create_job(&self, request: &JobRequest) -> EmResult<Job> {
-        Self::CreateRequestValidator::validate(request)?;
+        Self::CreateRequestValidator::validate(request).map_err(join_validation_messages)?;
         let JobRequest {
             workflow_id,
             maintainer,
@@ -98,7 +158,17 @@ impl JobService for PgJobsService {
                 self.create_interval_job(workflow_id, maintainer, interval, next_run)
                     .await?
             }
+            JobType::Cron { expression } => {
+                self.create_cron_job(workflow_id, maintainer, expression.trim(), next_run)
+                    .await?
+            }
         };
+        self.audit(
+            "job.create",
+            &job_id,
+            Some(json!({ "workflow_id": workflow_id, "maintainer": maintainer })),
+        )
+        .await;
         self.read_one(&job_id).await
     }
 
@@ -107,8 +177,8 @@ impl JobService for PgJobsService {
             r#"
             select
                 job_id, workflow_id, workflow_name, job_type, maintainer, job_schedule,
-                job_interval, is_paused, next_run, current_workflow_run_id, workflow_run_status,
-                progress, executor_id
+                job_interval, cron_expression, is_paused, next_run, current_workflow_run_id,
+                workflow_run_status, progress, executor_id
             from job.v_jobs
             where job_id = $1"#,
         )
@@ -130,8 +200,8 @@ impl JobService for PgJobsService {
             r#"
             select
                 job_id, workflow_id, workflow_name, job_type, maintainer, job_schedule,
-                job_interval, is_paused, next_run, current_workflow_run_id, workflow_run_status,
-                progress, executor_id
+                job_interval, cron_expression, is_paused, next_run, current_workflow_run_id,
+                workflow_run_status, progress, executor_id
             from job.v_jobs"#,
         )
         .fetch_all(&self.pool)
@@ -139,6 +209,28 @@ impl JobService for PgJobsService {
         Ok(result)
     }
 
+    async fn read_many_after(&self, page: &CursorPagination) -> EmResult<CursorPage<Job>> {
+        CursorPaginationValidator::validate_request(page)?;
+        let items = sqlx::query_as(
+            r#"
+            select
+                job_id, workflow_id, workflow_name, job_type, maintainer, job_schedule,
+                job_interval, cron_expression, is_paused, next_run, current_workflow_run_id,
+                workflow_run_status, progress, executor_id
+            from job.v_jobs
+            where $1::bigint is null or job_id > $1
+            order by job_id
+            limit $2"#,
+        )
+        .bind(page.after_key()?)
+        .bind(page.fetch_limit())
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(CursorPage::from_items(items, page, |job: &Job| {
+            job.job_id.into_inner()
+        }))
+    }
+
     async fn read_queued(&self) -> EmResult<Vec<JobMin>> {
         let result = sqlx::query_as(
             r#"
@@ -152,9 +244,9 @@ impl JobService for PgJobsService {
 
     async fn run_job(&self, job_id: &JobId) -> EmResult<Job> {
         let mut transaction = self.pool.begin().await?;
-        let job_option: Option<(WorkflowId, bool)> = sqlx::query_as(
+        let job_option: Option<(WorkflowId, bool, Option<String>)> = sqlx::query_as(
             r#"
-                select j.workflow_id, j.is_paused
+                select j.workflow_id, j.is_paused, j.cron_expression
                 from job.jobs j
                 where j.job_id = $1
                 for update
@@ -164,12 +256,12 @@ impl JobService for PgJobsService {
         .fetch_optional(&mut transaction)
         .await?;
 
-        let workflow_id = match job_option {
-            Some((_, is_paused)) if is_paused => {
+        let (workflow_id, cron_expression) = match job_option {
+            Some((_, is_paused, _)) if is_paused => {
                 transaction.commit().await?;
                 return Err(EmError::Generic(format!("Job, id = {job_id}, is paused")));
             }
-            Some((workflow_id, _)) => workflow_id,
+            Some((workflow_id, _, cron_expression)) => (workflow_id, cron_expression),
             None => {
                 return Err(EmError::MissingRecord {
                     pk: job_id.to_string(),
@@ -177,28 +269,119 @@ impl JobService for PgJobsService {
             }
         };
 
-        let workflow_run_id = match self.workflow_runs_service.initialize(&workflow_id).await {
-            Ok(WorkflowRun {
-                workflow_run_id, ..
-            }) => workflow_run_id,
+        let next_run = match cron_expression
+            .map(|expression| next_cron_run(&expression, &Utc::now().naive_utc()))
+            .transpose()
+        {
+            Ok(next_run) => next_run,
             Err(error) => {
                 transaction.rollback().await?;
                 return Err(error);
             }
         };
 
-        if let Err(error) = self.workflow_runs_service.schedule(&workflow_run_id).await {
-            transaction.rollback().await?;
-            return Err(error);
+        let workflow_run_id = match self
+            .workflow_runs_service
+            .initialize_scheduled(&workflow_id, &mut transaction)
+            .await
+        {
+            Ok(workflow_run_id) => workflow_run_id,
+            Err(error) => {
+                transaction.rollback().await?;
+                return Err(error);
+            }
         };
 
-        let query_result = sqlx::query("call job.set_job_as_running($1,$2)")
+        let query_result = sqlx::query("call job.set_job_as_running($1,$2,$3)")
+            .bind(job_id)
+            .bind(workflow_run_id)
+            .bind(next_run)
+            .execute(&mut transaction)
+            .await;
+
+        finalize_transaction(query_result, transaction).await?;
+        self.workflow_runs_service
+            .audit_initialize_scheduled(&workflow_run_id, &workflow_id)
+            .await;
+        self.audit(
+            "job.run",
+            job_id,
+            Some(json!({ "workflow_run_id": workflow_run_id })),
+        )
+        .await;
+        self.read_one(job_id).await
+    }
+
+    async fn force_run(&self, job_id: &JobId) -> EmResult<Job> {
+        let mut transaction = self.pool.begin().await?;
+        let job_option: Option<(WorkflowId, Option<WorkflowRunId>, Option<WorkflowRunStatus>)> =
+            sqlx::query_as(
+                r#"
+                select j.workflow_id, j.current_workflow_run_id, wr.status
+                from job.jobs j
+                left join workflow_run.workflow_runs wr
+                on j.current_workflow_run_id = wr.workflow_run_id
+                where j.job_id = $1
+                for update of j"#,
+            )
+            .bind(job_id)
+            .fetch_optional(&mut transaction)
+            .await?;
+
+        let workflow_id = match job_option {
+            Some((_, Some(workflow_run_id), Some(status)))
+                if !matches!(
+                    status,
+                    WorkflowRunStatus::Complete
+                        | WorkflowRunStatus::Failed
+                        | WorkflowRunStatus::Canceled
+                ) =>
+            {
+                transaction.commit().await?;
+                return Err(EmError::InvalidRequest {
+                    request: format!("Force run job, id = {job_id}"),
+                    reason: format!(
+                        "Cannot force run while its workflow run, id = {workflow_run_id}, is \
+                         {status}"
+                    ),
+                });
+            }
+            Some((workflow_id, ..)) => workflow_id,
+            None => {
+                return Err(EmError::MissingRecord {
+                    pk: job_id.to_string(),
+                })
+            }
+        };
+
+        let workflow_run_id = match self
+            .workflow_runs_service
+            .initialize_scheduled(&workflow_id, &mut transaction)
+            .await
+        {
+            Ok(workflow_run_id) => workflow_run_id,
+            Err(error) => {
+                transaction.rollback().await?;
+                return Err(error);
+            }
+        };
+
+        let query_result = sqlx::query("call job.force_run_job($1,$2)")
             .bind(job_id)
             .bind(workflow_run_id)
             .execute(&mut transaction)
             .await;
 
         finalize_transaction(query_result, transaction).await?;
+        self.workflow_runs_service
+            .audit_initialize_scheduled(&workflow_run_id, &workflow_id)
+            .await;
+        self.audit(
+            "job.force_run",
+            job_id,
+            Some(json!({ "workflow_run_id": workflow_run_id })),
+        )
+        .await;
         self.read_one(job_id).await
     }
 
@@ -224,7 +407,9 @@ impl JobService for PgJobsService {
             }
             None => {
                 transaction.commit().await?;
-                return Err(EmError::Generic(format!("No job for job_id = {job_id}")));
+                return Err(EmError::MissingRecord {
+                    pk: job_id.to_string(),
+                });
             }
         };
 
@@ -255,9 +440,326 @@ impl JobService for PgJobsService {
         self.read_one(job_id).await
     }
 
+    async fn pause_job(&self, job_id: &JobId) -> EmResult<Job> {
+        sqlx::query("call job.pause_job($1)")
+            .bind(job_id)
+            .execute(&self.pool)
+            .await?;
+        self.audit("job.pause", job_id, None).await;
+        self.read_one(job_id).await
+    }
+
+    async fn skip_job(&self, job_id: &JobId) -> EmResult<Job> {
+        let cron_job: Option<(String, NaiveDateTime)> = sqlx::query_as(
+            r#"
+            select j.cron_expression, j.next_run
+            from job.jobs j
+            where
+                j.job_id = $1
+                and j.cron_expression is not null"#,
+        )
+        .bind(job_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let next_run = cron_job
+            .map(|(expression, next_run)| {
+                next_cron_run(&expression, &next_run.max(Utc::now().naive_utc()))
+            })
+            .transpose()?;
+        sqlx::query("call job.skip_job($1,$2)")
+            .bind(job_id)
+            .bind(next_run)
+            .execute(&self.pool)
+            .await?;
+        self.audit("job.skip", job_id, None).await;
+        self.read_one(job_id).await
+    }
+
+    async fn refresh_queue(&self) -> EmResult<()> {
+        sqlx::query("select pg_notify($1, $2)")
+            .bind(self.channel_namespace.channel("jobs"))
+            .bind(NotificationPayload::encode(""))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     async fn listener(&self) -> EmResult<Self::Listener> {
-        let mut listener = PgListener::connect_with(&self.pool).await?;
-        listener.listen("jobs").await?;
-        Ok(PgChangeListener::new(listener))
+        PgChangeListener::connect(&self.pool, &self.channel_namespace.channel("jobs")).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Datelike, Duration, NaiveDateTime, Timelike, Utc};
+    use common::error::EmResult;
+    use rstest::rstest;
+    use sqlx::PgPool;
+
+    use super::PgJobsService;
+    use crate::{
+        database::test::{cleanup_workflow, create_test_workflow, database},
+        job::{
+            data::{next_cron_run, Job, JobId, JobRequest, JobType, ScheduleEntry},
+            service::JobService,
+        },
+        workflow::{data::WorkflowId, service::postgres::PgWorkflowsService},
+        workflow_run::{data::WorkflowRunStatus, service::postgres::PgWorkflowRunsService},
+    };
+
+    /// Cron expression used by the tests. Runs once a year so the next match does not change
+    /// while a test is running.
+    const YEARLY_CRON: &str = "0 0 1 1 *";
+
+    /// Create a [PgJobsService] backed by the `pool`
+    fn jobs_service(pool: &PgPool) -> PgJobsService {
+        let workflows_service = PgWorkflowsService::new(pool);
+        PgJobsService::new(pool, &PgWorkflowRunsService::new(pool, &workflows_service))
+    }
+
+    /// Create a new cron job for the `workflow_id` that runs at [YEARLY_CRON] with the optional
+    /// `next_run` override
+    async fn create_cron_job(
+        service: &PgJobsService,
+        workflow_id: WorkflowId,
+        next_run: Option<NaiveDateTime>,
+    ) -> EmResult<Job> {
+        let request = JobRequest::new(
+            workflow_id,
+            "test@example.com".to_owned(),
+            JobType::new_cron(YEARLY_CRON.to_owned()),
+            next_run,
+        );
+        service.create_job(&request).await
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_job_should_use_next_cron_match_when_cron_job_has_no_next_run(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "create_cron_job", 1).await?;
+        let service = jobs_service(&database);
+
+        let action = create_cron_job(&service, workflow_id, None).await;
+        cleanup_workflow(&database, workflow_id).await?;
+
+        let job = action?;
+        let expected = next_cron_run(YEARLY_CRON, &Utc::now().naive_utc())?;
+        assert!(
+            matches!(job.job_type, JobType::Cron { ref expression } if expression == YEARLY_CRON)
+        );
+        assert_eq!(job.next_run, expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn run_job_should_move_cron_job_next_run_to_next_cron_match(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "run_cron_job", 1).await?;
+        let service = jobs_service(&database);
+        let next_run = Utc::now().naive_utc() + Duration::minutes(5);
+
+        let action = async {
+            let job = create_cron_job(&service, workflow_id, Some(next_run)).await?;
+            service.run_job(&job.job_id).await
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+
+        let job = action?;
+        let expected = next_cron_run(YEARLY_CRON, &Utc::now().naive_utc())?;
+        assert!(job.current_workflow_run_id.is_some());
+        assert_eq!(job.next_run, expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn skip_job_should_move_cron_job_next_run_past_current_tick(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "skip_cron_job", 1).await?;
+        let service = jobs_service(&database);
+        let next_run = Utc::now().naive_utc() + Duration::minutes(5);
+
+        let action = async {
+            let job = create_cron_job(&service, workflow_id, Some(next_run)).await?;
+            service.skip_job(&job.job_id).await
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+
+        let job = action?;
+        let expected = next_cron_run(YEARLY_CRON, &next_run)?;
+        assert!(job.current_workflow_run_id.is_none());
+        assert_eq!(job.next_run, expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn skip_job_should_move_interval_job_next_run_by_one_interval(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "skip_interval_job", 1).await?;
+        let service = jobs_service(&database);
+        let request = JobRequest::new(
+            workflow_id,
+            "test@example.com".to_owned(),
+            JobType::new_interval(0, 1, 0),
+            Some(Utc::now().naive_utc() + Duration::minutes(5)),
+        );
+
+        let action = async {
+            let job = service.create_job(&request).await?;
+            let skipped_job = service.skip_job(&job.job_id).await?;
+            EmResult::Ok((job, skipped_job))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+
+        let (job, skipped_job) = action?;
+        assert_eq!(skipped_job.next_run, job.next_run + Duration::days(1));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn skip_job_should_move_scheduled_job_next_run_to_next_schedule_entry(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "skip_scheduled_job", 1).await?;
+        let service = jobs_service(&database);
+        let next_run = (Utc::now().naive_utc() + Duration::days(2))
+            .with_second(0)
+            .and_then(|next_run| next_run.with_nanosecond(0))
+            .ok_or("Could not truncate next run to the minute")?;
+        let entry = ScheduleEntry::new(
+            next_run.weekday().number_from_monday() as i16,
+            next_run.time(),
+        );
+        let request = JobRequest::new(
+            workflow_id,
+            "test@example.com".to_owned(),
+            JobType::new_scheduled(vec![entry]),
+            Some(next_run),
+        );
+
+        let action = async {
+            let job = service.create_job(&request).await?;
+            service.skip_job(&job.job_id).await
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+
+        let job = action?;
+        assert_eq!(job.next_run, next_run + Duration::days(7));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn skip_job_should_fail_when_workflow_run_is_in_progress(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "skip_running_job", 1).await?;
+        let service = jobs_service(&database);
+        let next_run = Utc::now().naive_utc() + Duration::minutes(5);
+
+        let action = async {
+            let job = create_cron_job(&service, workflow_id, Some(next_run)).await?;
+            let job = service.force_run(&job.job_id).await?;
+            let result = service.skip_job(&job.job_id).await;
+            let after_skip = service.read_one(&job.job_id).await?;
+            EmResult::Ok((job, result, after_skip))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+
+        let (job, result, after_skip) = action?;
+        assert!(result.is_err());
+        assert_eq!(after_skip.next_run, job.next_run);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::paused_job_run_complete(true, WorkflowRunStatus::Complete)]
+    #[case::active_job_run_failed(false, WorkflowRunStatus::Failed)]
+    #[tokio::test]
+    async fn complete_job_should_keep_paused_state_when_run_was_forced(
+        database: PgPool,
+        #[case] is_paused: bool,
+        #[case] status: WorkflowRunStatus,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "complete_forced_job", 1).await?;
+        let service = jobs_service(&database);
+
+        let action = async {
+            let job = create_cron_job(&service, workflow_id, None).await?;
+            if is_paused {
+                service.pause_job(&job.job_id).await?;
+            }
+            let job = service.force_run(&job.job_id).await?;
+            sqlx::query(
+                "update workflow_run.workflow_runs set status = $2 where workflow_run_id = $1",
+            )
+            .bind(job.current_workflow_run_id)
+            .bind(status)
+            .execute(&database)
+            .await?;
+            service.complete_job(&job.job_id).await
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+
+        let job = action?;
+        assert_eq!(job.is_paused, is_paused);
+        assert!(job.current_workflow_run_id.is_none());
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn force_run_should_not_leave_workflow_run_when_job_update_fails(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "force_run_rollback", 1).await?;
+        let service = jobs_service(&database);
+
+        let action = async {
+            // Every update of a job must pass the next_run check so a job that becomes overdue
+            // fails when force_run links the new workflow run
+            let job_id: JobId = sqlx::query_scalar(
+                r#"
+                insert into job.jobs(workflow_id, job_type, maintainer, job_interval, next_run)
+                values(
+                    $1, 'Interval'::job.job_type, 'test@example.com', interval '1 day',
+                    now() at time zone 'UTC' + interval '1 second'
+                )
+                returning job_id"#,
+            )
+            .bind(workflow_id)
+            .fetch_one(&database)
+            .await?;
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            let result = service.force_run(&job_id).await;
+            let workflow_run_count: i64 = sqlx::query_scalar(
+                "select count(*) from workflow_run.workflow_runs where workflow_id = $1",
+            )
+            .bind(workflow_id)
+            .fetch_one(&database)
+            .await?;
+            EmResult::Ok((result, workflow_run_count))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+
+        let (result, workflow_run_count) = action?;
+        assert!(result.is_err());
+        assert_eq!(workflow_run_count, 0);
+        Ok(())
     }
 }
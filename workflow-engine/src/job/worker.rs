@@ -1,22 +1,38 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, env};
 
 use chrono::{NaiveDateTime, Utc};
 use common::{
-    database::listener::ChangeListener,
+    database::listener::{ChangeListener, NotificationPayload},
     email::EmailService,
     error::{EmError, EmResult},
 };
 use log::{error, info, warn};
-use tokio::{
-    signal::ctrl_c,
-    time::{sleep as tokio_sleep, Duration as StdDuration},
-};
+#[cfg(not(unix))]
+use tokio::signal::ctrl_c;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, Signal, SignalKind};
+use tokio::time::{sleep as tokio_sleep, Duration as StdDuration};
 
 use crate::job::{data::JobId, service::JobService};
 
-/// Action to perform after receiving a job worker notification. Notification payload should be a
-/// workflow run id (as an i64/bigint) to tell the job worker a job has been completed or an empty
-/// payload to tell the worker to refresh the job list.
+/// Environment variable holding the number of consecutive completion failures allowed before a
+/// job is paused
+const MAX_JOB_FAILURES_ENV: &str = "CLIPPY_MAX_JOB_FAILURES";
+/// Number of consecutive completion failures allowed before a job is paused when the
+/// `CLIPPY_MAX_JOB_FAILURES` environment variable is not set
+const DEFAULT_MAX_JOB_FAILURES: u32 = 3;
+/// Minimum time the worker sleeps before running the next job, even when the job is overdue
+const MIN_JOB_SLEEP: StdDuration = StdDuration::from_millis(100);
+/// Initial backoff applied when the same overdue job is selected again without its `next_run`
+/// advancing
+const INITIAL_JOB_BACKOFF: StdDuration = StdDuration::from_secs(1);
+/// Maximum backoff applied when the same overdue job is repeatedly selected
+const MAX_JOB_BACKOFF: StdDuration = StdDuration::from_secs(60);
+
+/// Action to perform after receiving a job worker notification. Notification payload (within the
+/// [NotificationPayload] envelope) should be a job id (as an i64/bigint) to tell the job worker a
+/// job has been completed or an empty payload to tell the worker to refresh the job list.
+/// Payloads of an unknown version are treated as malformed.
 pub enum NotificationAction {
     LoadJobs,
     CompleteJob(JobId),
@@ -25,10 +41,17 @@ pub enum NotificationAction {
 
 impl<'m> From<&'m str> for NotificationAction {
     fn from(s: &str) -> Self {
-        if s.is_empty() {
+        let body = match NotificationPayload::decode(s) {
+            Ok(payload) => payload.body(),
+            Err(error) => {
+                warn!("{error}");
+                return Self::MalformedPayload(s.to_owned());
+            }
+        };
+        if body.is_empty() {
             return Self::LoadJobs;
         }
-        let Ok(job_id) = s.parse::<i64>() else {
+        let Ok(job_id) = body.parse::<i64>() else {
             return Self::MalformedPayload(s.to_owned());
         };
         info!("Received notification of \"{}\"", s);
@@ -36,6 +59,120 @@ impl<'m> From<&'m str> for NotificationAction {
     }
 }
 
+/// Listener for the OS signals that should trigger a graceful shutdown of a [JobWorker]. On unix
+/// platforms both SIGINT (ctrl+c) and SIGTERM are handled. Other platforms only listen for ctrl+c.
+/// The signal streams are registered once so signals received while the worker is busy are not
+/// lost before the next wait.
+#[cfg_attr(not(unix), allow(clippy::empty_structs_with_brackets))]
+struct ShutdownSignal {
+    #[cfg(unix)]
+    interrupt: Signal,
+    #[cfg(unix)]
+    terminate: Signal,
+}
+
+impl ShutdownSignal {
+    /// Register the signal listeners
+    /// # Errors
+    /// This function will return an error if a signal handler cannot be registered
+    #[cfg(unix)]
+    fn new() -> EmResult<Self> {
+        Ok(Self {
+            interrupt: signal(SignalKind::interrupt())?,
+            terminate: signal(SignalKind::terminate())?,
+        })
+    }
+
+    /// Register the signal listeners
+    /// # Errors
+    /// This function does not return an error on non-unix platforms
+    #[cfg(not(unix))]
+    #[allow(clippy::unnecessary_wraps)]
+    fn new() -> EmResult<Self> {
+        Ok(Self {})
+    }
+
+    /// Wait until a shutdown signal is received
+    #[cfg(unix)]
+    async fn recv(&mut self) {
+        tokio::select! {
+            _ = self.interrupt.recv() => info!("Received SIGINT"),
+            _ = self.terminate.recv() => info!("Received SIGTERM"),
+        }
+    }
+
+    /// Wait until a shutdown signal is received
+    #[cfg(not(unix))]
+    #[allow(clippy::unused_self)]
+    async fn recv(&mut self) {
+        if let Err(error) = ctrl_c().await {
+            error!("Error listening for ctrl+c signal. {error}");
+        }
+    }
+}
+
+/// Tracks the job last run by a [JobWorker] to stop the worker from busy-looping when a job's
+/// `next_run` does not advance after it is run (e.g. a corrupt schedule row). Every repeated run of
+/// the same job doubles the backoff up to [MAX_JOB_BACKOFF]. Running a different job or the same
+/// job with a new `next_run` resets the backoff. Waking up for any other reason (e.g. a
+/// notification) never escalates the backoff.
+#[derive(Default)]
+struct JobBackoff {
+    /// Last job run and its `next_run` at the time of the run
+    last_run: Option<(JobId, NaiveDateTime)>,
+    /// Backoff applied before the last run. [StdDuration::ZERO] when not backing off
+    backoff: StdDuration,
+}
+
+impl JobBackoff {
+    /// Get the duration to sleep before running `job_id` scheduled at `next_run`, given the time
+    /// `until_run` remaining until the job's `next_run`. Jobs scheduled in the future are never
+    /// delayed beyond their scheduled time, but overdue jobs always wait at least [MIN_JOB_SLEEP].
+    /// Overdue jobs that were already run without their `next_run` advancing wait for the next
+    /// backoff instead.
+    fn delay(&self, job_id: JobId, next_run: NaiveDateTime, until_run: StdDuration) -> StdDuration {
+        if !until_run.is_zero() {
+            return until_run.max(MIN_JOB_SLEEP);
+        }
+        if self.last_run == Some((job_id, next_run)) {
+            return self.next_backoff();
+        }
+        MIN_JOB_SLEEP
+    }
+
+    /// Record that `job_id` scheduled at `next_run` is being run after sleeping for the
+    /// [delay][JobBackoff::delay]. If the same job was already run at the same `next_run`, the
+    /// backoff is escalated. Otherwise the backoff is reset to track the new run.
+    fn record_run(&mut self, job_id: JobId, next_run: NaiveDateTime) {
+        if self.last_run != Some((job_id, next_run)) {
+            self.last_run = Some((job_id, next_run));
+            self.backoff = StdDuration::ZERO;
+            return;
+        }
+        self.backoff = self.next_backoff();
+        warn!(
+            "Job_id = {job_id} was run again without next_run advancing past {next_run}. Backed \
+             off for {:?}",
+            self.backoff
+        );
+    }
+
+    /// Backoff to apply if the last run job is selected again without its `next_run` advancing
+    fn next_backoff(&self) -> StdDuration {
+        if self.backoff.is_zero() {
+            INITIAL_JOB_BACKOFF
+        } else {
+            (self.backoff * 2).min(MAX_JOB_BACKOFF)
+        }
+    }
+
+    /// Clear the last run job and backoff
+    const fn reset(&mut self) {
+        self.last_run = None;
+        self.backoff = StdDuration::ZERO;
+    }
+}
+
 /// Main unit of the recurring job run process. An instance of the worker is meant to be created
 /// and run as the lifecycle of the instance (dropped at the end of the  method).
 pub struct JobWorker<J, E> {
@@ -43,6 +180,9 @@ pub struct JobWorker<J, E> {
     jobs: HashMap<JobId, NaiveDateTime>,
     next_job: JobId,
     email_service: E,
+    failure_counts: HashMap<JobId, u32>,
+    max_job_failures: u32,
+    backoff: JobBackoff,
 }
 
 impl<J, E> JobWorker<J, E>
@@ -50,60 +190,62 @@ where
     J: JobService,
     E: EmailService,
 {
-    /// Create a new job worker, initializing with a reference to a [JobService] and creating a
-    /// mailer to send job related emails to maintainers.
+    /// Create a new job worker, initializing with a reference to a [JobService] and an
+    /// [EmailService] to send job related emails to maintainers.
     /// # Errors
-    /// This function will returns an error if there are missing environment variables or the SMTP
-    /// transport cannot be created. Require environment variables are:
-    /// - CLIPPY_USERNAME -> email service username
-    /// - CLIPPY_PASSWORD -> email service password
-    /// - CLIPPY_RELAY -> email service relay
+    /// This function will returns an error if an environment variable cannot be parsed. Optional
+    /// environment variables are:
+    /// - CLIPPY_MAX_JOB_FAILURES -> number of consecutive completion failures before a job is
+    ///   paused (default 3)
     pub fn new(job_service: J, email_service: E) -> EmResult<Self> {
+        let max_job_failures = match env::var(MAX_JOB_FAILURES_ENV) {
+            Ok(value) => value.parse()?,
+            Err(_) => DEFAULT_MAX_JOB_FAILURES,
+        };
         Ok(Self {
             job_service,
             jobs: HashMap::new(),
             next_job: 0.into(),
             email_service,
+            failure_counts: HashMap::new(),
+            max_job_failures,
+            backoff: JobBackoff::default(),
         })
     }
 
     /// Run the main action of the worker. Continuously listens for notification and executes the
     /// next job when ready. If there are no jobs available for the worker, it will wait for a
-    /// shutdown signal (ctrl+c) or a new notification to load jobs.
+    /// shutdown signal (ctrl+c or SIGTERM on unix) or a new notification to load jobs. Shutdown
+    /// signals are only acted upon between actions so a job completion (and any error email) that
+    /// is in progress is always finished before the worker returns.
     /// # Errors
     /// This function will return an error if an error is returned:
+    /// - registering the shutdown signal handlers
     /// - creating the job change listener
     /// - loading the new job map
     /// - parsing the job listener notification
     /// - handling the job listener notification
     /// - running the next job
     pub async fn run(mut self) -> EmResult<()> {
+        let mut shutdown_signal = ShutdownSignal::new()?;
         let mut job_channel = self.job_service.listener().await?;
         self.load_jobs().await?;
         loop {
-            let next_run = self
-                .jobs
-                .get(&self.next_job)
-                .map(|next_run| {
-                    let duration = next_run.timestamp_millis() - Utc::now().timestamp_millis();
-                    StdDuration::from_millis(duration.clamp(0, i64::max_value()) as u64)
-                })
-                .unwrap_or(StdDuration::MAX);
-            if next_run != StdDuration::MAX {
-                info!("Next run in {:?}", next_run);
-            } else {
-                info!("Waiting for job update notification");
-            }
+            let next_run = self.next_run();
             tokio::select! {
                 biased;
-                _ = ctrl_c() => {
+                _ = shutdown_signal.recv() => {
                     info!("Received shutdown signal. Starting graceful shutdown");
                     break;
                 }
                 notification = job_channel.recv() => {
+                    self.backoff.reset();
                     self.handle_action(notification?).await?
                 }
                 _ = tokio_sleep(next_run) => {
+                    if let Some(next_run) = self.jobs.get(&self.next_job) {
+                        self.backoff.record_run(self.next_job, *next_run);
+                    }
                     self.run_next_job().await?;
                     self.load_jobs().await?;
                 }
@@ -112,6 +254,22 @@ where
         Ok(())
     }
 
+    /// Time to wait before running the next job, including any backoff applied to a job that is
+    /// repeatedly due. Returns [StdDuration::MAX] if there is no next job, meaning the worker
+    /// should wait for a job update notification.
+    fn next_run(&mut self) -> StdDuration {
+        let Some(next_run) = self.jobs.get(&self.next_job) else {
+            self.backoff.reset();
+            info!("Waiting for job update notification");
+            return StdDuration::MAX;
+        };
+        let duration = next_run.timestamp_millis() - Utc::now().timestamp_millis();
+        let until_run = StdDuration::from_millis(duration.clamp(0, i64::MAX) as u64);
+        let next_run = self.backoff.delay(self.next_job, *next_run, until_run);
+        info!("Next run in {next_run:?}");
+        next_run
+    }
+
     /// Load all available jobs from the job queue in `job.jobs`. If the job queue becomes polluted
     /// with a duplicate job id, an error will be returned (although this should never happen
     /// unless the database is corrupt/altered). Once jobs are fetched, if any jobs exist, the
@@ -176,9 +334,11 @@ where
     }
 
     /// Complete the specified job after the workflow run is complete. If something went wrong or
-    /// the job failed, the maintainer of the job will be notified with an email. If the `job_id`
-    /// is not valid then warning messages will be printed but the worker will continue.
-    async fn complete_job(&self, job_id: &JobId) -> EmResult<()> {
+    /// the job failed, the maintainer of the job will be notified with an email. Once a job fails
+    /// to complete the configured number of consecutive times, the job is paused and the
+    /// maintainer is sent a single notification instead. If the `job_id` is not valid then warning
+    /// messages will be printed but the worker will continue.
+    async fn complete_job(&mut self, job_id: &JobId) -> EmResult<()> {
         if !self.jobs.contains_key(job_id) {
             warn!(
                 "Received a message to complete a job that is not in the job queue. Job_id = {}",
@@ -189,9 +349,40 @@ where
         let job = self.job_service.read_one(job_id).await?;
         info!("Completing run for job_id = {}", job_id);
         let Err(error) = self.job_service.complete_job(job_id).await else {
+            self.failure_counts.remove(job_id);
             return Ok(());
         };
-        self.send_error_email(&job.maintainer, format!("{error}"))
+        let failure_count = self.failure_counts.entry(*job_id).or_default();
+        *failure_count += 1;
+        let failure_count = *failure_count;
+        if failure_count < self.max_job_failures {
+            self.send_error_email(&job.maintainer, format!("{error}"))
+                .await?;
+            return Ok(());
+        }
+        self.pause_job(job_id, &job.maintainer, failure_count, &error)
+            .await
+    }
+
+    /// Pause the specified job after `failure_count` consecutive completion failures and notify
+    /// the `maintainer` with the failure count and the last `error` encountered
+    async fn pause_job(
+        &mut self,
+        job_id: &JobId,
+        maintainer: &str,
+        failure_count: u32,
+        error: &EmError,
+    ) -> EmResult<()> {
+        warn!("Pausing job_id = {job_id} after {failure_count} consecutive completion failures");
+        self.job_service.pause_job(job_id).await?;
+        self.failure_counts.remove(job_id);
+        let message = format!(
+            "Job, id = {job_id}, was paused after {failure_count} consecutive completion \
+             failures.\nLast error: {error}"
+        );
+        let _response = self
+            .email_service
+            .send_email(maintainer, "Job Paused After Repeated Failures", &message)
             .await?;
         Ok(())
     }
@@ -209,3 +400,253 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use common::{
+        api::pagination::{CursorPage, CursorPagination},
+        database::{
+            listener::{in_memory_channel, ChangeListener, InMemoryChangeListener},
+            postgres::Postgres,
+        },
+        email::EmailService,
+        error::{EmError, EmResult},
+    };
+    use rstest::rstest;
+    use tokio::time::Duration as StdDuration;
+
+    use super::{
+        JobBackoff, JobWorker, NotificationAction, INITIAL_JOB_BACKOFF, MAX_JOB_BACKOFF,
+        MIN_JOB_SLEEP,
+    };
+    use crate::{
+        job::{
+            data::{Job, JobId, JobMin, JobRequest, JobRequestValidator},
+            service::JobService,
+        },
+        workflow_run::service::postgres::PgWorkflowRunsService,
+    };
+
+    /// Error returned by [QueueOnlyJobService] for operations that are not part of the job queue
+    fn unsupported() -> EmError {
+        EmError::Generic("Operation not supported by test job service".to_owned())
+    }
+
+    /// [JobService] that serves a fixed job queue and fails every other operation
+    #[derive(Clone)]
+    struct QueueOnlyJobService {
+        queued_job_ids: Vec<i64>,
+    }
+
+    impl JobService for QueueOnlyJobService {
+        type CreateRequestValidator = JobRequestValidator;
+        type Database = Postgres;
+        type Listener = InMemoryChangeListener<NotificationAction, Postgres>;
+        type WorkflowRunService = PgWorkflowRunsService;
+
+        async fn create_job(&self, _request: &JobRequest) -> EmResult<Job> {
+            Err(unsupported())
+        }
+
+        async fn read_one(&self, _job_id: &JobId) -> EmResult<Job> {
+            Err(unsupported())
+        }
+
+        async fn read_many(&self) -> EmResult<Vec<Job>> {
+            Err(unsupported())
+        }
+
+        async fn read_many_after(&self, _page: &CursorPagination) -> EmResult<CursorPage<Job>> {
+            Err(unsupported())
+        }
+
+        async fn read_queued(&self) -> EmResult<Vec<JobMin>> {
+            let next_run = Utc::now().naive_utc() + Duration::hours(1);
+            Ok(self
+                .queued_job_ids
+                .iter()
+                .map(|job_id| JobMin {
+                    job_id: (*job_id).into(),
+                    next_run,
+                })
+                .collect())
+        }
+
+        async fn run_job(&self, _job_id: &JobId) -> EmResult<Job> {
+            Err(unsupported())
+        }
+
+        async fn force_run(&self, _job_id: &JobId) -> EmResult<Job> {
+            Err(unsupported())
+        }
+
+        async fn complete_job(&self, _job_id: &JobId) -> EmResult<Job> {
+            Err(unsupported())
+        }
+
+        async fn pause_job(&self, _job_id: &JobId) -> EmResult<Job> {
+            Err(unsupported())
+        }
+
+        async fn skip_job(&self, _job_id: &JobId) -> EmResult<Job> {
+            Err(unsupported())
+        }
+
+        async fn refresh_queue(&self) -> EmResult<()> {
+            Err(unsupported())
+        }
+
+        async fn listener(&self) -> EmResult<Self::Listener> {
+            Err(unsupported())
+        }
+    }
+
+    /// [EmailService] that discards every email
+    struct NoopEmailService;
+
+    impl EmailService for NoopEmailService {
+        type Response = ();
+
+        async fn send_email<S>(&self, _to: S, _subject: S, _body: S) -> EmResult<Self::Response>
+        where
+            S: AsRef<str>,
+        {
+            Ok(())
+        }
+
+        async fn send_to_many<S>(
+            &self,
+            _to: &[S],
+            _subject: S,
+            _body: S,
+        ) -> EmResult<Self::Response>
+        where
+            S: AsRef<str>,
+        {
+            Ok(())
+        }
+
+        async fn test_connection(&self) -> EmResult<()> {
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    #[case::load_jobs(NotificationAction::LoadJobs, 2)]
+    #[case::malformed_payload(NotificationAction::MalformedPayload("job".to_owned()), 0)]
+    #[tokio::test]
+    async fn handle_action_should_update_job_queue_when(
+        #[case] action: NotificationAction,
+        #[case] expected_job_count: usize,
+    ) -> EmResult<()> {
+        let job_service = QueueOnlyJobService {
+            queued_job_ids: vec![1, 2],
+        };
+        let mut worker = JobWorker::new(job_service, NoopEmailService)?;
+        let (sender, mut listener) = in_memory_channel::<NotificationAction, Postgres>();
+
+        sender.send(action)?;
+        let received_action = listener.recv().await?;
+        worker.handle_action(received_action).await?;
+
+        assert_eq!(worker.jobs.len(), expected_job_count);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::unprefixed_load("", true)]
+    #[case::v1_load("v1:", true)]
+    #[case::unknown_version("v99:", false)]
+    fn notification_action_should_load_jobs_when(#[case] payload: &str, #[case] expected: bool) {
+        let action = NotificationAction::from(payload);
+
+        assert_eq!(matches!(action, NotificationAction::LoadJobs), expected);
+    }
+
+    #[rstest]
+    #[case::unprefixed("42")]
+    #[case::v1("v1:42")]
+    fn notification_action_should_complete_job_when(#[case] payload: &str) {
+        let action = NotificationAction::from(payload);
+
+        assert!(matches!(
+            action,
+            NotificationAction::CompleteJob(job_id) if job_id == JobId::from(42)
+        ));
+    }
+
+    #[test]
+    fn job_backoff_should_apply_floor_when_job_is_overdue() {
+        let backoff = JobBackoff::default();
+        let next_run = Utc::now().naive_utc();
+
+        let delay = backoff.delay(1.into(), next_run, StdDuration::ZERO);
+
+        assert_eq!(delay, MIN_JOB_SLEEP);
+    }
+
+    #[test]
+    fn job_backoff_should_grow_and_cap_when_same_job_is_repeatedly_run() {
+        let mut backoff = JobBackoff::default();
+        let next_run = Utc::now().naive_utc();
+        backoff.record_run(1.into(), next_run);
+
+        let first = backoff.delay(1.into(), next_run, StdDuration::ZERO);
+        backoff.record_run(1.into(), next_run);
+        let second = backoff.delay(1.into(), next_run, StdDuration::ZERO);
+        for _ in 0..10 {
+            backoff.record_run(1.into(), next_run);
+        }
+        let capped = backoff.delay(1.into(), next_run, StdDuration::ZERO);
+
+        assert_eq!(first, INITIAL_JOB_BACKOFF);
+        assert_eq!(second, INITIAL_JOB_BACKOFF * 2);
+        assert_eq!(capped, MAX_JOB_BACKOFF);
+    }
+
+    #[test]
+    fn job_backoff_should_not_grow_when_job_is_not_run() {
+        let mut backoff = JobBackoff::default();
+        let next_run = Utc::now().naive_utc();
+        backoff.record_run(1.into(), next_run);
+
+        let first = backoff.delay(1.into(), next_run, StdDuration::ZERO);
+        let second = backoff.delay(1.into(), next_run, StdDuration::ZERO);
+
+        assert_eq!(first, INITIAL_JOB_BACKOFF);
+        assert_eq!(second, INITIAL_JOB_BACKOFF);
+    }
+
+    #[rstest]
+    #[case::different_job(2, 0)]
+    #[case::advanced_next_run(1, 60)]
+    fn job_backoff_should_reset_when(#[case] job_id: i64, #[case] advance_seconds: i64) {
+        let mut backoff = JobBackoff::default();
+        let next_run = Utc::now().naive_utc();
+        backoff.record_run(1.into(), next_run);
+        backoff.record_run(1.into(), next_run);
+        let job_id = job_id.into();
+        let new_next_run = next_run + Duration::seconds(advance_seconds);
+        backoff.record_run(job_id, new_next_run);
+
+        let delay = backoff.delay(job_id, new_next_run, StdDuration::ZERO);
+        let previous_delay = backoff.delay(1.into(), next_run, StdDuration::ZERO);
+
+        assert_eq!(delay, INITIAL_JOB_BACKOFF);
+        assert_eq!(previous_delay, MIN_JOB_SLEEP);
+    }
+
+    #[test]
+    fn job_backoff_should_apply_floor_after_reset() {
+        let mut backoff = JobBackoff::default();
+        let next_run = Utc::now().naive_utc();
+        backoff.record_run(1.into(), next_run);
+        backoff.record_run(1.into(), next_run);
+
+        backoff.reset();
+        let delay = backoff.delay(1.into(), next_run, StdDuration::ZERO);
+
+        assert_eq!(delay, MIN_JOB_SLEEP);
+    }
+}
@@ -48,5 +48,6 @@ pub mod api;
 pub mod database;
 pub mod executor;
 pub mod job;
+pub mod metrics;
 pub mod workflow;
 pub mod workflow_run;
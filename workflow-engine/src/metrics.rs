@@ -0,0 +1,320 @@
+use std::time::Duration;
+
+use actix_web::{
+    dev::HttpServiceFactory,
+    web::{self, Data},
+    App, HttpResponse, HttpServer,
+};
+use common::{
+    database::{connection::PoolStats, Database},
+    error::EmResult,
+};
+use log::error;
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, IntGauge, Registry, TextEncoder};
+
+use crate::{executor::service::ExecutorService, job::service::JobService};
+
+/// Buckets (in seconds) of the `run_task` duration histogram. Remote tasks can run anywhere from
+/// a few milliseconds to hours so the buckets span that entire range.
+const RUN_TASK_DURATION_BUCKETS: &[f64] = &[
+    0.1, 0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0, 1800.0, 3600.0, 7200.0,
+];
+
+/// Prometheus metrics of the workflow engine. All metrics are registered to a single [Registry]
+/// that is exported by the `/metrics` endpoint. Cheap to clone since every clone shares the same
+/// underlying metrics.
+#[derive(Clone)]
+pub struct EngineMetrics {
+    /// Registry containing every engine metric
+    registry: Registry,
+    /// Number of task runs that completed
+    tasks_completed: IntCounter,
+    /// Number of task runs that failed
+    tasks_failed: IntCounter,
+    /// Number of executors that are currently active. Sampled when the metrics are scraped
+    active_executors: IntGauge,
+    /// Number of jobs that are currently queued. Sampled when the metrics are scraped
+    queued_jobs: IntGauge,
+    /// Number of connections held by the database pool. Sampled when the metrics are scraped
+    pool_size: IntGauge,
+    /// Number of idle connections held by the database pool. Sampled when the metrics are scraped
+    pool_idle: IntGauge,
+    /// Number of database pool connections in use. Sampled when the metrics are scraped
+    pool_in_use: IntGauge,
+    /// Duration of remote task runs performed by [TaskQueueService::run_task]
+    ///
+    /// [TaskQueueService::run_task]: crate::workflow_run::service::TaskQueueService::run_task
+    run_task_duration: Histogram,
+}
+
+impl EngineMetrics {
+    /// Create a new [EngineMetrics] instance with every metric registered to a new [Registry]
+    /// # Errors
+    /// This function will return an error if a metric cannot be created or registered
+    pub fn new() -> EmResult<Self> {
+        let registry = Registry::new();
+        let tasks_completed = IntCounter::new(
+            "we_tasks_completed_total",
+            "Number of task runs that completed",
+        )?;
+        let tasks_failed =
+            IntCounter::new("we_tasks_failed_total", "Number of task runs that failed")?;
+        let active_executors = IntGauge::new(
+            "we_active_executors",
+            "Number of currently active executors",
+        )?;
+        let queued_jobs = IntGauge::new("we_queued_jobs", "Number of currently queued jobs")?;
+        let pool_size = IntGauge::new(
+            "we_db_pool_size",
+            "Number of connections held by the database pool",
+        )?;
+        let pool_idle = IntGauge::new(
+            "we_db_pool_idle",
+            "Number of idle connections held by the database pool",
+        )?;
+        let pool_in_use = IntGauge::new(
+            "we_db_pool_in_use",
+            "Number of database pool connections in use",
+        )?;
+        let run_task_duration = Histogram::with_opts(
+            HistogramOpts::new(
+                "we_run_task_duration_seconds",
+                "Duration of remote task runs in seconds",
+            )
+            .buckets(RUN_TASK_DURATION_BUCKETS.to_vec()),
+        )?;
+        registry.register(Box::new(tasks_completed.clone()))?;
+        registry.register(Box::new(tasks_failed.clone()))?;
+        registry.register(Box::new(active_executors.clone()))?;
+        registry.register(Box::new(queued_jobs.clone()))?;
+        registry.register(Box::new(pool_size.clone()))?;
+        registry.register(Box::new(pool_idle.clone()))?;
+        registry.register(Box::new(pool_in_use.clone()))?;
+        registry.register(Box::new(run_task_duration.clone()))?;
+        Ok(Self {
+            registry,
+            tasks_completed,
+            tasks_failed,
+            active_executors,
+            queued_jobs,
+            pool_size,
+            pool_idle,
+            pool_in_use,
+            run_task_duration,
+        })
+    }
+
+    /// Registry containing every engine metric
+    pub const fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Increment the number of completed task runs
+    pub fn record_task_completed(&self) {
+        self.tasks_completed.inc();
+    }
+
+    /// Increment the number of failed task runs
+    pub fn record_task_failed(&self) {
+        self.tasks_failed.inc();
+    }
+
+    /// Record the `duration` of a single remote task run
+    pub fn observe_run_task(&self, duration: Duration) {
+        self.run_task_duration.observe(duration.as_secs_f64());
+    }
+
+    /// Set the number of currently active executors
+    fn set_active_executors(&self, count: usize) {
+        self.active_executors
+            .set(i64::try_from(count).unwrap_or(i64::MAX));
+    }
+
+    /// Set the number of currently queued jobs
+    fn set_queued_jobs(&self, count: usize) {
+        self.queued_jobs
+            .set(i64::try_from(count).unwrap_or(i64::MAX));
+    }
+
+    /// Set the database pool gauges from a snapshot of the pool
+    fn set_pool_stats(&self, stats: &PoolStats) {
+        self.pool_size.set(i64::from(stats.size));
+        self.pool_idle.set(i64::from(stats.idle));
+        self.pool_in_use.set(i64::from(stats.in_use));
+    }
+}
+
+/// Encode every metric of the `registry` using the Prometheus text format
+/// # Errors
+/// This function will return an error if the metrics cannot be encoded
+fn encode(registry: &Registry) -> EmResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&registry.gather(), &mut buffer)?;
+    Ok(buffer)
+}
+
+/// Service factory for the `/metrics` endpoint. Like the health probes, the route is mounted at
+/// the root of the application rather than under the versioned `/api/v1` scope. Requires the
+/// [EngineMetrics], its [Registry], the [Database::ConnectionPool] and the `E` and `J` services to
+/// be registered as app data.
+pub fn service<D, E, J>() -> impl HttpServiceFactory
+where
+    D: Database + 'static,
+    D::ConnectionPool: Send + Sync + 'static,
+    E: ExecutorService<Database = D> + Send + Sync + 'static,
+    J: JobService<Database = D> + Send + Sync + 'static,
+{
+    web::resource("/metrics").route(web::get().to(metrics::<D, E, J>))
+}
+
+/// API endpoint to scrape the engine metrics in the Prometheus text format. The active executor,
+/// queued job and database pool gauges are sampled before the metrics are encoded. If a sample
+/// cannot be taken, the previous value of the gauge is kept.
+async fn metrics<D, E, J>(
+    engine_metrics: Data<EngineMetrics>,
+    registry: Data<Registry>,
+    pool: Data<D::ConnectionPool>,
+    executor_service: Data<E>,
+    job_service: Data<J>,
+) -> HttpResponse
+where
+    D: Database,
+    E: ExecutorService<Database = D>,
+    J: JobService<Database = D>,
+{
+    engine_metrics.set_pool_stats(&D::pool_stats(&pool));
+    match executor_service.read_active().await {
+        Ok(executors) => engine_metrics.set_active_executors(executors.len()),
+        Err(error) => error!("Could not sample active executors for metrics. {error}"),
+    }
+    match job_service.read_queued().await {
+        Ok(jobs) => engine_metrics.set_queued_jobs(jobs.len()),
+        Err(error) => error!("Could not sample queued jobs for metrics. {error}"),
+    }
+    encoded_response(&registry)
+}
+
+/// Service factory for the `/metrics` endpoint of processes that only export the metrics they
+/// record, such as the executor. Unlike [service], no gauges are sampled so only the [Registry]
+/// needs to be registered as app data.
+pub fn registry_service() -> impl HttpServiceFactory {
+    web::resource("/metrics").route(web::get().to(registry_metrics))
+}
+
+/// API endpoint to scrape the metrics of the `registry` in the Prometheus text format
+async fn registry_metrics(registry: Data<Registry>) -> HttpResponse {
+    encoded_response(&registry)
+}
+
+/// Response containing every metric of the `registry` encoded using the Prometheus text format.
+/// If the metrics cannot be encoded, the error is logged and a `500` response is returned.
+fn encoded_response(registry: &Registry) -> HttpResponse {
+    match encode(registry) {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(TextEncoder::new().format_type())
+            .body(body),
+        Err(error) => {
+            error!("Could not encode metrics. {error}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Spawn a single worker HTTP server bound to the `address` that exports the `engine_metrics`
+/// through the `/metrics` endpoint (see [registry_service]). Used by processes that do not run
+/// the API server but still record metrics. The server runs in the background until the process
+/// exits.
+/// # Errors
+/// This function will return an error if the server is unable to bind to the `address`
+pub fn spawn_metrics_server(engine_metrics: &EngineMetrics, address: &str) -> EmResult<()> {
+    let registry_data = Data::new(engine_metrics.registry().clone());
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(registry_data.clone())
+            .service(registry_service())
+    })
+    .workers(1)
+    .bind(address)?
+    .run();
+    tokio::spawn(async move {
+        if let Err(error) = server.await {
+            error!("Metrics server exited with an error. {error}");
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::{
+        http::StatusCode,
+        test::{call_service, init_service, read_body, TestRequest},
+        web::Data,
+        App,
+    };
+    use common::database::connection::PoolStats;
+
+    use super::{encode, registry_service, EngineMetrics};
+
+    #[test]
+    fn encode_should_include_recorded_metrics() {
+        let metrics = EngineMetrics::new().unwrap();
+        metrics.record_task_completed();
+        metrics.record_task_completed();
+        metrics.record_task_failed();
+        metrics.observe_run_task(Duration::from_secs(2));
+        metrics.set_active_executors(3);
+        metrics.set_queued_jobs(4);
+        metrics.set_pool_stats(&PoolStats {
+            size: 5,
+            idle: 2,
+            in_use: 3,
+        });
+
+        let output = String::from_utf8(encode(metrics.registry()).unwrap()).unwrap();
+
+        assert!(output.contains("we_tasks_completed_total 2"), "{output}");
+        assert!(output.contains("we_tasks_failed_total 1"), "{output}");
+        assert!(output.contains("we_active_executors 3"), "{output}");
+        assert!(output.contains("we_queued_jobs 4"), "{output}");
+        assert!(output.contains("we_db_pool_size 5"), "{output}");
+        assert!(output.contains("we_db_pool_idle 2"), "{output}");
+        assert!(output.contains("we_db_pool_in_use 3"), "{output}");
+        assert!(
+            output.contains("we_run_task_duration_seconds_count 1"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn clone_should_share_metrics() {
+        let metrics = EngineMetrics::new().unwrap();
+        let cloned = metrics.clone();
+
+        cloned.record_task_failed();
+
+        let output = String::from_utf8(encode(metrics.registry()).unwrap()).unwrap();
+        assert!(output.contains("we_tasks_failed_total 1"), "{output}");
+    }
+
+    #[tokio::test]
+    async fn registry_service_should_export_recorded_metrics() {
+        let metrics = EngineMetrics::new().unwrap();
+        metrics.record_task_completed();
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(metrics.registry().clone()))
+                .service(registry_service()),
+        )
+        .await;
+
+        let response = call_service(&app, TestRequest::get().uri("/metrics").to_request()).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let output = String::from_utf8(read_body(response).await.to_vec()).unwrap();
+        assert!(output.contains("we_tasks_completed_total 1"), "{output}");
+    }
+}
@@ -1,18 +1,25 @@
-use actix_web::{web, Scope};
+use actix_web::{
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web, HttpRequest, HttpResponse, Responder, Scope,
+};
 use common::api::{request::ApiRequest, ApiResponse, QueryApiFormat};
 
 use super::data::WorkflowUpdateRequest;
-use crate::workflow::{
-    data::{
-        Task, TaskId, TaskRequest, Workflow, WorkflowCreateRequest, WorkflowDeprecationRequest,
-        WorkflowId,
+use crate::{
+    workflow::{
+        data::{
+            Task, TaskId, TaskRequest, Workflow, WorkflowCreateRequest, WorkflowDeprecationRequest,
+            WorkflowExport, WorkflowId,
+        },
+        service::{TaskService, WorkflowsService},
     },
-    service::{TaskService, WorkflowsService},
+    workflow_run::{data::ValidationReport, service::WorkflowRunsService},
 };
 
-pub fn workflows_service<W>() -> Scope
+pub fn workflows_service<W, R>() -> Scope
 where
     W: WorkflowsService + Send + Sync + 'static,
+    R: WorkflowRunsService + Send + Sync + 'static,
 {
     web::scope("/workflows")
         .service(
@@ -21,8 +28,19 @@ where
                 .route(web::post().to(create_workflow::<W>))
                 .route(web::patch().to(update_workflow::<W>)),
         )
+        .route("/by-name/{name}", web::get().to(workflow_by_name::<W>))
+        .route("/import", web::post().to(import_workflow::<W>))
         .route("/{workflow_id}", web::get().to(workflow::<W>))
+        .route("/{workflow_id}/export", web::get().to(export_workflow::<W>))
+        .route(
+            "/{workflow_id}/validate",
+            web::get().to(validate_workflow::<R>),
+        )
         .route("/deprecate", web::post().to(deprecate_workflow::<W>))
+        .route(
+            "/deprecate-and-migrate",
+            web::post().to(deprecate_and_migrate_workflow::<W>),
+        )
 }
 
 pub fn tasks_service<T>() -> Scope
@@ -35,6 +53,7 @@ where
                 .route(web::get().to(tasks::<T>))
                 .route(web::post().to(create_task::<T>)),
         )
+        .route("/batch", web::post().to(create_tasks::<T>))
         .route("/{task_id}", web::get().to(task::<T>))
 }
 
@@ -70,6 +89,65 @@ where
     }
 }
 
+/// API endpoint to fetch the workflow with the exact `name` specified. Returns a single
+/// [Workflow] if exactly 1 workflow matches the name
+async fn workflow_by_name<W>(
+    name: actix_web::web::Path<String>,
+    service: actix_web::web::Data<W>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Workflow>
+where
+    W: WorkflowsService,
+{
+    let format = query.into_inner();
+    match service.read_one_by_name(&name).await {
+        Ok(workflow) => ApiResponse::success(workflow, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to export the workflow specified by `workflow_id` as a [WorkflowExport]. The
+/// response is always a JSON document sent as an attachment so it can be downloaded and kept
+/// under version control.
+async fn export_workflow<W>(
+    req: HttpRequest,
+    workflow_id: actix_web::web::Path<WorkflowId>,
+    service: actix_web::web::Data<W>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> HttpResponse
+where
+    W: WorkflowsService,
+{
+    let format = query.into_inner();
+    let export = match service.export(&workflow_id).await {
+        Ok(inner) => inner,
+        Err(error) => return ApiResponse::<()>::error(error, format.f).respond_to(&req),
+    };
+    let disposition = ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(format!("{}.json", export.name))],
+    };
+    HttpResponse::Ok().insert_header(disposition).json(export)
+}
+
+/// API endpoint to recreate a workflow, and any missing tasks, from a [WorkflowExport]. Returns
+/// the new [Workflow] created.
+async fn import_workflow<W>(
+    api_request: ApiRequest<WorkflowExport>,
+    service: actix_web::web::Data<W>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Workflow>
+where
+    W: WorkflowsService,
+{
+    let format = query.into_inner();
+    let export = api_request.into_inner();
+    match service.import(&export).await {
+        Ok(workflow) => ApiResponse::success(workflow, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
 /// API endpoint to create a new workflow using encoded data from `workflow`
 async fn create_workflow<W>(
     api_request: ApiRequest<WorkflowCreateRequest>,
@@ -124,6 +202,47 @@ where
     }
 }
 
+/// API endpoint to deprecate an existing workflow and migrate all jobs that are not paused to the
+/// replacement workflow. The `new_workflow_id` of the request is required.
+async fn deprecate_and_migrate_workflow<W>(
+    api_request: ApiRequest<WorkflowDeprecationRequest>,
+    service: actix_web::web::Data<W>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<()>
+where
+    W: WorkflowsService,
+{
+    let format = query.into_inner();
+    let request = api_request.into_inner();
+    match service.deprecate_and_migrate(&request).await {
+        Ok(migrated_count) => ApiResponse::message(
+            format!(
+                "Successfully deprecated workflow_id = {} and migrated {} job(s)",
+                request.workflow_id, migrated_count
+            ),
+            format.f,
+        ),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to validate the tasks of the workflow specified by `workflow_id` without creating
+/// a workflow run. Returns a [ValidationReport] with the result of each task.
+async fn validate_workflow<R>(
+    workflow_id: actix_web::web::Path<WorkflowId>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<ValidationReport>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    match service.validate_workflow(&workflow_id).await {
+        Ok(report) => ApiResponse::success(report, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
 /// API endpoint to fetch all tasks. Return an array of [Task] entries
 async fn tasks<T>(
     service: actix_web::web::Data<T>,
@@ -172,3 +291,21 @@ where
         Err(error) => ApiResponse::error(error, format.f),
     }
 }
+
+/// API endpoint to create multiple tasks in a single transaction. Returns the new [Task] entries
+/// in the same order as the requests.
+async fn create_tasks<T>(
+    api_request: ApiRequest<Vec<TaskRequest>>,
+    service: actix_web::web::Data<T>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<Task>>
+where
+    T: TaskService,
+{
+    let format = query.into_inner();
+    let requests = api_request.into_inner();
+    match service.create_tasks(&requests).await {
+        Ok(tasks) => ApiResponse::success(tasks, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
@@ -1,5 +1,14 @@
-use common::api::ApiRequestValidator;
-use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use common::{
+    api::{
+        id::{deserialize_id, parse_id},
+        ApiRequestValidator,
+    },
+    error::EmError,
+};
+use jsonschema::JSONSchema;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 
 /// Task data as it can be seen from it's parent, a [Workflow] instance. Contains the underlining
@@ -23,6 +32,9 @@ pub struct WorkflowTask {
     pub service_name: String,
     /// Url to be called as per the task execution
     pub url: String,
+    /// Task order values of the tasks that must complete before this task can run. [None] if the
+    /// task depends on every previous task in the workflow
+    pub depends_on: Option<Vec<i32>>,
 }
 
 /// Task information required to create a `task.workflow_tasks` entry
@@ -33,6 +45,37 @@ pub struct WorkflowTaskRequest {
     pub(crate) task_id: TaskId,
     /// Optional parameters passed to the task executor to allow for custom behaviour
     pub(crate) parameters: Option<Value>,
+    /// Optional task order values (1-based position within the request's tasks) of the tasks that
+    /// must complete before this task can run. Tasks with no value depend on every previous task,
+    /// keeping the default strictly linear execution order. Dependencies must point to previous
+    /// tasks.
+    #[serde(default)]
+    pub(crate) depends_on: Option<Vec<i32>>,
+}
+
+/// Validate that the `depends_on` values of every task in `tasks` point to a previous task. Since
+/// dependencies can only point backwards, the resulting task graph is always acyclic.
+fn validate_task_dependencies(tasks: &[WorkflowTaskRequest]) -> Result<(), &'static str> {
+    let all_valid = tasks.iter().zip(1..).all(|(task, task_order)| {
+        task.depends_on.as_ref().is_none_or(|depends_on| {
+            depends_on
+                .iter()
+                .all(|dependency| (1..task_order).contains(dependency))
+        })
+    });
+    if !all_valid {
+        return Err("Request 'tasks' can only depend on previous tasks");
+    }
+    Ok(())
+}
+
+/// Default maximum number of tasks of a single workflow run that are running at the same time.
+/// Workflow runs execute their tasks sequentially by default.
+pub const DEFAULT_MAX_PARALLEL_TASKS: i16 = 1;
+
+/// Default value of [WorkflowCreateRequest::max_parallel_tasks] when not provided
+const fn default_max_parallel_tasks() -> i16 {
+    DEFAULT_MAX_PARALLEL_TASKS
 }
 
 /// API request body when attempting to create a new `workflow.workflows` entry. Defines the name
@@ -43,6 +86,10 @@ pub struct WorkflowCreateRequest {
     pub(crate) name: String,
     /// Tasks that are run as part of this new workflow
     pub(crate) tasks: Vec<WorkflowTaskRequest>,
+    /// Maximum number of tasks of a single workflow run that are running at the same time.
+    /// Defaults to [DEFAULT_MAX_PARALLEL_TASKS]
+    #[serde(default = "default_max_parallel_tasks")]
+    pub(crate) max_parallel_tasks: i16,
 }
 
 pub struct WorkflowCreateRequestValidator;
@@ -51,9 +98,19 @@ impl ApiRequestValidator for WorkflowCreateRequestValidator {
     type ErrorMessage = &'static str;
     type Request = WorkflowCreateRequest;
 
-    fn validate(request: &Self::Request) -> Result<(), Self::ErrorMessage> {
+    fn validate(request: &Self::Request) -> Result<(), Vec<Self::ErrorMessage>> {
+        let mut errors = Vec::new();
         if request.name.trim().is_empty() {
-            return Err("Request 'name' cannot be empty or whitespace");
+            errors.push("Request 'name' cannot be empty or whitespace");
+        }
+        if let Err(error) = validate_task_dependencies(&request.tasks) {
+            errors.push(error);
+        }
+        if request.max_parallel_tasks < 1 {
+            errors.push("Request 'max_parallel_tasks' must be greater than 0");
+        }
+        if !errors.is_empty() {
+            return Err(errors);
         }
         Ok(())
     }
@@ -71,6 +128,10 @@ pub struct WorkflowUpdateRequest {
     /// Tasks that are run as part of this new workflow
     #[serde(default)]
     pub(crate) tasks: Option<Vec<WorkflowTaskRequest>>,
+    /// New maximum number of tasks of a single workflow run that are running at the same time.
+    /// [None] if no change should occur
+    #[serde(default)]
+    pub(crate) max_parallel_tasks: Option<i16>,
 }
 
 pub struct WorkflowUpdateRequestValidator;
@@ -79,19 +140,33 @@ impl ApiRequestValidator for WorkflowUpdateRequestValidator {
     type ErrorMessage = &'static str;
     type Request = WorkflowUpdateRequest;
 
-    fn validate(request: &Self::Request) -> Result<(), Self::ErrorMessage> {
-        if request.name.is_none() && request.tasks.is_none() {
-            return Err("Update request must have a new name or list of tasks");
+    fn validate(request: &Self::Request) -> Result<(), Vec<Self::ErrorMessage>> {
+        if request.name.is_none() && request.tasks.is_none() && request.max_parallel_tasks.is_none()
+        {
+            return Err(vec![
+                "Update request must have a new name, list of tasks or max_parallel_tasks",
+            ]);
         }
+        let mut errors = Vec::new();
         if let Some(name) = &request.name {
             if name.trim().is_empty() {
-                return Err("Update request 'name' cannot be empty or whitespace");
+                errors.push("Update request 'name' cannot be empty or whitespace");
             }
         }
         if let Some(tasks) = &request.tasks {
             if tasks.is_empty() {
-                return Err("Update request 'tasks' cannot be an empty collection");
+                errors.push("Update request 'tasks' cannot be an empty collection");
             }
+            if let Err(error) = validate_task_dependencies(tasks) {
+                errors.push(error);
+            }
+        }
+        if matches!(request.max_parallel_tasks, Some(max_parallel_tasks) if max_parallel_tasks < 1)
+        {
+            errors.push("Update request 'max_parallel_tasks' must be greater than 0");
+        }
+        if !errors.is_empty() {
+            return Err(errors);
         }
         Ok(())
     }
@@ -122,11 +197,57 @@ pub struct Workflow {
     pub new_workflow: Option<WorkflowId>,
     /// Tasks that are executed as part of this workflow
     pub tasks: Vec<WorkflowTask>,
+    /// Maximum number of tasks of a single workflow run that are running at the same time
+    pub max_parallel_tasks: i16,
+}
+
+/// Portable definition of a task within a [WorkflowExport]. Tasks are referenced by name and
+/// task service name instead of ids so the definition can be imported into another database.
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WorkflowTaskExport {
+    /// Name of the task
+    pub name: String,
+    /// Short description of the task
+    pub description: String,
+    /// Name of the task service that executes this task
+    pub task_service_name: String,
+    /// Url of the task, relative to the base url of the task service
+    pub url: String,
+    /// Maximum number of seconds a run of the task can take before it is failed. [None] if the
+    /// task has no timeout
+    #[serde(default)]
+    pub timeout_seconds: Option<i64>,
+    /// JSON Schema that the parameters of the task must satisfy. [None] if the parameters are not
+    /// validated
+    #[serde(default)]
+    pub parameters_schema: Option<Value>,
+    /// Number of times a failed run of the task is automatically retried
+    #[serde(default)]
+    pub max_retries: i16,
+    /// Optional parameters passed to the task executor to allow for custom behaviour
+    pub parameters: Option<Value>,
+    /// Task order values of the tasks that must complete before this task can run. [None] if the
+    /// task depends on every previous task in the workflow
+    pub depends_on: Option<Vec<i32>>,
+}
+
+/// Portable JSON document of a workflow definition, containing the workflow metadata and the
+/// ordered tasks executed by the workflow. Produced by
+/// [export][crate::workflow::service::WorkflowsService::export] and consumed by
+/// [import][crate::workflow::service::WorkflowsService::import].
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct WorkflowExport {
+    /// Name of the workflow
+    pub name: String,
+    /// Maximum number of tasks of a single workflow run that are running at the same time
+    pub max_parallel_tasks: i16,
+    /// Tasks executed as part of the workflow, in task order
+    pub tasks: Vec<WorkflowTaskExport>,
 }
 
 /// Wrapper for a `workflow_id` value. Made to ensure data passed as the id of a workflow is correct
 /// and not just any i64 value.
-#[derive(sqlx::Type, Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+#[derive(sqlx::Type, Serialize, Debug, PartialEq, Clone, Copy)]
 #[sqlx(transparent)]
 pub struct WorkflowId(i64);
 
@@ -136,6 +257,31 @@ impl From<i64> for WorkflowId {
     }
 }
 
+impl FromStr for WorkflowId {
+    type Err = EmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(parse_id("workflow_id", s)?))
+    }
+}
+
+impl TryFrom<&str> for WorkflowId {
+    type Error = EmError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl<'de> Deserialize<'de> for WorkflowId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_id("workflow_id", deserializer).map(Self)
+    }
+}
+
 impl std::fmt::Display for WorkflowId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -155,6 +301,14 @@ pub struct Task {
     pub(crate) url: String,
     /// Name of the task service that executes this task
     pub(crate) task_service_name: String,
+    /// Maximum number of seconds a run of this task can take before it is failed. [None] if the
+    /// task has no timeout
+    pub(crate) timeout_seconds: Option<i64>,
+    /// JSON Schema that the parameters of this task must satisfy. [None] if the parameters are
+    /// not validated
+    pub(crate) parameters_schema: Option<Value>,
+    /// Number of times a failed run of this task is automatically retried
+    pub(crate) max_retries: i16,
 }
 
 /// Data required to create or update the contents of task entry (the id cannot be updated)
@@ -168,6 +322,18 @@ pub struct TaskRequest {
     pub(crate) task_service_id: i64,
     /// Relative url from the task service referenced by `task_service_id`
     pub(crate) url: String,
+    /// Optional maximum number of seconds a run of this task can take before it is failed. Must be
+    /// positive when specified
+    #[serde(default)]
+    pub(crate) timeout_seconds: Option<i64>,
+    /// Optional JSON Schema that the parameters of this task must satisfy. Must compile as a valid
+    /// schema when specified
+    #[serde(default)]
+    pub(crate) parameters_schema: Option<Value>,
+    /// Number of times a failed run of this task is automatically retried. Defaults to 0 and
+    /// cannot be negative
+    #[serde(default)]
+    pub(crate) max_retries: i16,
 }
 
 pub struct TaskRequestValidator;
@@ -176,15 +342,29 @@ impl ApiRequestValidator for TaskRequestValidator {
     type ErrorMessage = &'static str;
     type Request = TaskRequest;
 
-    fn validate(request: &Self::Request) -> Result<(), Self::ErrorMessage> {
+    fn validate(request: &Self::Request) -> Result<(), Vec<Self::ErrorMessage>> {
+        let mut errors = Vec::new();
         if request.name.trim().is_empty() {
-            return Err("Request 'name' cannot be empty or whitespace");
+            errors.push("Request 'name' cannot be empty or whitespace");
         }
         if request.description.trim().is_empty() {
-            return Err("Request 'description' cannot be empty or whitespace");
+            errors.push("Request 'description' cannot be empty or whitespace");
         }
         if request.url.trim().is_empty() {
-            return Err("Request 'url' cannot be empty or whitespace");
+            errors.push("Request 'url' cannot be empty or whitespace");
+        }
+        if matches!(request.timeout_seconds, Some(timeout) if timeout <= 0) {
+            errors.push("Request 'timeout_seconds' must be positive when specified");
+        }
+        if matches!(&request.parameters_schema, Some(schema) if JSONSchema::compile(schema).is_err())
+        {
+            errors.push("Request 'parameters_schema' must be a valid JSON Schema when specified");
+        }
+        if request.max_retries < 0 {
+            errors.push("Request 'max_retries' cannot be negative");
+        }
+        if !errors.is_empty() {
+            return Err(errors);
         }
         Ok(())
     }
@@ -207,3 +387,215 @@ impl std::fmt::Display for TaskId {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use common::{api::ApiRequestValidator, error::EmError};
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use super::{
+        validate_task_dependencies, TaskRequest, TaskRequestValidator, WorkflowCreateRequest,
+        WorkflowCreateRequestValidator, WorkflowTaskRequest, WorkflowUpdateRequest,
+        WorkflowUpdateRequestValidator, DEFAULT_MAX_PARALLEL_TASKS,
+    };
+
+    /// Create task requests where each entry of `depends_on` is the dependencies of a task
+    fn task_requests(depends_on: Vec<Option<Vec<i32>>>) -> Vec<WorkflowTaskRequest> {
+        depends_on
+            .into_iter()
+            .map(|depends_on| {
+                serde_json::from_value(json!({"task_id": 1, "depends_on": depends_on})).unwrap()
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case::linear(vec![None, None, None])]
+    #[case::fan_out(vec![None, Some(vec![1]), Some(vec![1])])]
+    #[case::fan_in(vec![Some(vec![]), Some(vec![]), Some(vec![1, 2])])]
+    fn validate_task_dependencies_should_succeed_when(#[case] depends_on: Vec<Option<Vec<i32>>>) {
+        let tasks = task_requests(depends_on);
+
+        assert!(validate_task_dependencies(&tasks).is_ok());
+    }
+
+    #[rstest]
+    #[case::self_dependency(vec![None, Some(vec![2])])]
+    #[case::forward_dependency(vec![Some(vec![2]), None])]
+    #[case::non_positive(vec![None, Some(vec![0])])]
+    fn validate_task_dependencies_should_fail_when(#[case] depends_on: Vec<Option<Vec<i32>>>) {
+        let tasks = task_requests(depends_on);
+
+        assert!(validate_task_dependencies(&tasks).is_err());
+    }
+
+    #[test]
+    fn workflow_create_request_should_default_max_parallel_tasks() {
+        let request: WorkflowCreateRequest =
+            serde_json::from_value(json!({"name": "test", "tasks": [{"task_id": 1}]})).unwrap();
+
+        assert_eq!(request.max_parallel_tasks, DEFAULT_MAX_PARALLEL_TASKS);
+    }
+
+    #[test]
+    fn workflow_create_request_validator_should_fail_when_max_parallel_tasks_not_positive() {
+        let request: WorkflowCreateRequest = serde_json::from_value(
+            json!({"name": "test", "tasks": [{"task_id": 1}], "max_parallel_tasks": 0}),
+        )
+        .unwrap();
+
+        assert!(WorkflowCreateRequestValidator::validate(&request).is_err());
+    }
+
+    #[rstest]
+    #[case::only_max_parallel_tasks(json!({"workflow_id": 1, "max_parallel_tasks": 2}), true)]
+    #[case::not_positive(json!({"workflow_id": 1, "max_parallel_tasks": 0}), false)]
+    fn workflow_update_request_validator_should_validate_max_parallel_tasks(
+        #[case] body: serde_json::Value,
+        #[case] is_valid: bool,
+    ) {
+        let request: WorkflowUpdateRequest = serde_json::from_value(body).unwrap();
+
+        assert_eq!(
+            WorkflowUpdateRequestValidator::validate(&request).is_ok(),
+            is_valid
+        );
+    }
+
+    #[rstest]
+    #[case::empty("", false)]
+    #[case::whitespace("   ", false)]
+    #[case::valid("test", true)]
+    fn workflow_create_request_validator_should_validate_name(
+        #[case] name: &str,
+        #[case] is_valid: bool,
+    ) {
+        let request: WorkflowCreateRequest =
+            serde_json::from_value(json!({"name": name, "tasks": [{"task_id": 1}]})).unwrap();
+
+        assert_eq!(
+            WorkflowCreateRequestValidator::validate(&request).is_ok(),
+            is_valid
+        );
+    }
+
+    #[rstest]
+    #[case::empty("", false)]
+    #[case::whitespace("   ", false)]
+    #[case::valid("test", true)]
+    fn workflow_update_request_validator_should_validate_name(
+        #[case] name: &str,
+        #[case] is_valid: bool,
+    ) {
+        let request: WorkflowUpdateRequest =
+            serde_json::from_value(json!({"workflow_id": 1, "name": name})).unwrap();
+
+        assert_eq!(
+            WorkflowUpdateRequestValidator::validate(&request).is_ok(),
+            is_valid
+        );
+    }
+
+    #[rstest]
+    #[case::empty("", false)]
+    #[case::whitespace("   ", false)]
+    #[case::valid("test", true)]
+    fn task_request_validator_should_validate_name(#[case] name: &str, #[case] is_valid: bool) {
+        let request = TaskRequest {
+            name: name.to_owned(),
+            description: "test".to_owned(),
+            task_service_id: 1,
+            url: "test".to_owned(),
+            timeout_seconds: None,
+            parameters_schema: None,
+            max_retries: 0,
+        };
+
+        assert_eq!(TaskRequestValidator::validate(&request).is_ok(), is_valid);
+    }
+
+    #[test]
+    fn task_request_validator_should_return_invalid_request_when_name_blank() {
+        let request = TaskRequest {
+            name: "   ".to_owned(),
+            description: "test".to_owned(),
+            task_service_id: 1,
+            url: "test".to_owned(),
+            timeout_seconds: None,
+            parameters_schema: None,
+            max_retries: 0,
+        };
+
+        let error = TaskRequestValidator::validate_request(&request).unwrap_err();
+
+        assert!(
+            matches!(error, EmError::InvalidRequest { reason, .. } if reason.contains("'name'"))
+        );
+    }
+
+    #[rstest]
+    #[case::none(None, true)]
+    #[case::negative(Some(-1), false)]
+    #[case::zero(Some(0), false)]
+    #[case::positive(Some(30), true)]
+    fn task_request_validator_should_validate_timeout_seconds(
+        #[case] timeout_seconds: Option<i64>,
+        #[case] is_valid: bool,
+    ) {
+        let request = TaskRequest {
+            name: "test".to_owned(),
+            description: "test".to_owned(),
+            task_service_id: 1,
+            url: "test".to_owned(),
+            timeout_seconds,
+            parameters_schema: None,
+            max_retries: 0,
+        };
+
+        assert_eq!(TaskRequestValidator::validate(&request).is_ok(), is_valid);
+    }
+
+    #[rstest]
+    #[case::none(None, true)]
+    #[case::invalid_type(Some(json!({ "type": "not-a-type" })), false)]
+    #[case::valid(Some(json!({ "type": "object", "required": ["id"] })), true)]
+    fn task_request_validator_should_validate_parameters_schema(
+        #[case] parameters_schema: Option<Value>,
+        #[case] is_valid: bool,
+    ) {
+        let request = TaskRequest {
+            name: "test".to_owned(),
+            description: "test".to_owned(),
+            task_service_id: 1,
+            url: "test".to_owned(),
+            timeout_seconds: None,
+            parameters_schema,
+            max_retries: 0,
+        };
+
+        assert_eq!(TaskRequestValidator::validate(&request).is_ok(), is_valid);
+    }
+
+    #[rstest]
+    #[case::negative(-1, false)]
+    #[case::zero(0, true)]
+    #[case::positive(3, true)]
+    fn task_request_validator_should_validate_max_retries(
+        #[case] max_retries: i16,
+        #[case] is_valid: bool,
+    ) {
+        let request = TaskRequest {
+            name: "test".to_owned(),
+            description: "test".to_owned(),
+            task_service_id: 1,
+            url: "test".to_owned(),
+            timeout_seconds: None,
+            parameters_schema: None,
+            max_retries,
+        };
+
+        assert_eq!(TaskRequestValidator::validate(&request).is_ok(), is_valid);
+    }
+}
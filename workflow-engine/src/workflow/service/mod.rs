@@ -1,12 +1,22 @@
 pub mod postgres;
 
-use common::{api::ApiRequestValidator, database::Database, error::EmResult};
+use common::{
+    api::{
+        pagination::{Page, Pagination},
+        ApiRequestValidator,
+    },
+    database::Database,
+    error::EmResult,
+};
 
 use super::data::{
     Task, TaskId, TaskRequest, Workflow, WorkflowCreateRequest, WorkflowDeprecationRequest,
-    WorkflowId, WorkflowUpdateRequest,
+    WorkflowExport, WorkflowId, WorkflowUpdateRequest,
 };
 
+/// Maximum number of tasks that can be created in a single call to [TaskService::create_tasks]
+pub const MAX_CREATE_TASKS_BATCH_SIZE: usize = 100;
+
 /// Service for fetching and interacting with workflow run data. Wraps a [Pool] and provides
 /// interaction methods for the API.
 pub trait WorkflowsService
@@ -22,8 +32,17 @@ where
     /// Read a single [Workflow] record for the specified `workflow_id`. Returns [Err] if the id
     /// does not match any record in the database.
     async fn read_one(&self, workflow_id: &WorkflowId) -> EmResult<Workflow>;
+    /// Read a single [Workflow] record with the exact (case-sensitive) `name` specified. Returns
+    /// [Err] if the name does not match any record or matches more than 1 record.
+    async fn read_one_by_name(&self, name: &str) -> EmResult<Workflow>;
     /// Read all [Workflow] records in the database
-    async fn read_many(&self) -> EmResult<Vec<Workflow>>;
+    async fn read_many(&self) -> EmResult<Vec<Workflow>> {
+        let page = self.read_many_paged(&Pagination::unbounded()).await?;
+        Ok(page.items)
+    }
+    /// Read a single [Page] of [Workflow] records within the bounds of `page`. The returned [Page]
+    /// also contains the total number of workflows available.
+    async fn read_many_paged(&self, page: &Pagination) -> EmResult<Page<Workflow>>;
     /// Update an existing workflow using the `request` data. Returns the new state of the
     /// [Workflow] updated.
     async fn update_workflow(&self, request: &WorkflowUpdateRequest) -> EmResult<Workflow>;
@@ -31,6 +50,21 @@ where
     /// if the `request` contains a `new_workflow_id` value. Returns the `workflow_id` that was
     /// updated as a response.
     async fn deprecate(&self, request: &WorkflowDeprecationRequest) -> EmResult<WorkflowId>;
+    /// Deprecate the workflow specified within the `request` data and, within the same
+    /// transaction, repoint every job that is not paused from the deprecated workflow to the
+    /// `new_workflow_id` of the `request`. Returns the number of jobs migrated. Returns an
+    /// [common::error::EmError::InvalidRequest] if the `new_workflow_id` is missing, matches the
+    /// deprecated workflow, does not exist or is already deprecated.
+    async fn deprecate_and_migrate(&self, request: &WorkflowDeprecationRequest) -> EmResult<i32>;
+    /// Export the workflow specified by `workflow_id` as a portable [WorkflowExport] containing
+    /// the workflow metadata and the definition of each task in task order. Returns [Err] if the
+    /// id does not match any record in the database.
+    async fn export(&self, workflow_id: &WorkflowId) -> EmResult<WorkflowExport>;
+    /// Recreate the workflow described by `export` within a single transaction. Tasks that
+    /// already exist for the named task service are reused, while missing tasks are created. All
+    /// created tasks and the workflow itself go through the usual request validators. Returns the
+    /// new [Workflow] created.
+    async fn import(&self, export: &WorkflowExport) -> EmResult<Workflow>;
 }
 
 /// Service for fetching and interacting with task data. Wraps a `pool` and provides interaction
@@ -44,11 +78,22 @@ where
 
     /// Create a new task with the data contained within `request`
     async fn create_task(&self, request: &TaskRequest) -> EmResult<Task>;
+    /// Create a new task for each entry in `requests` within a single transaction. Every request
+    /// is validated before any task is created and the whole batch is rolled back if a single
+    /// task cannot be created. Returns the new tasks in the same order as `requests`. Returns
+    /// [Err] if `requests` is empty or has more than [MAX_CREATE_TASKS_BATCH_SIZE] entries.
+    async fn create_tasks(&self, requests: &[TaskRequest]) -> EmResult<Vec<Task>>;
     /// Read a single task record from `task.v_tasks` for the specified `task_id`. Will return
     /// [Err] when the id does not match a record.
     async fn read_one(&self, task_id: &TaskId) -> EmResult<Task>;
     /// Read all task records found from `task.v_tasks`
-    async fn read_many(&self) -> EmResult<Vec<Task>>;
+    async fn read_many(&self) -> EmResult<Vec<Task>> {
+        let page = self.read_many_paged(&Pagination::unbounded()).await?;
+        Ok(page.items)
+    }
+    /// Read a single [Page] of task records within the bounds of `page`. The returned [Page] also
+    /// contains the total number of tasks available.
+    async fn read_many_paged(&self, page: &Pagination) -> EmResult<Page<Task>>;
     /// Update a task specified by `task_id` with the new details contained within `request`
     async fn update(&self, task_id: &TaskId, request: &TaskRequest) -> EmResult<Task>;
 }
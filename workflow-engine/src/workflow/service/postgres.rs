@@ -1,22 +1,34 @@
 use common::{
-    api::ApiRequestValidator,
-    database::{connection::finalize_transaction, postgres::Postgres},
+    api::{
+        pagination::{Page, Pagination},
+        ApiRequestValidator,
+    },
+    database::{
+        connection::finalize_transaction,
+        postgres::{begin_snapshot, Postgres},
+    },
     error::{EmError, EmResult},
 };
+use serde_json::Value;
 use sqlx::{
     postgres::{PgHasArrayType, PgTypeInfo},
-    PgPool,
+    PgPool, Transaction,
 };
 
 use crate::workflow::{
     data::{
         Task, TaskId, TaskRequest, TaskRequestValidator, Workflow, WorkflowCreateRequest,
-        WorkflowCreateRequestValidator, WorkflowDeprecationRequest, WorkflowId, WorkflowTask,
-        WorkflowTaskRequest, WorkflowUpdateRequest, WorkflowUpdateRequestValidator,
+        WorkflowCreateRequestValidator, WorkflowDeprecationRequest, WorkflowExport, WorkflowId,
+        WorkflowTask, WorkflowTaskExport, WorkflowTaskRequest, WorkflowUpdateRequest,
+        WorkflowUpdateRequestValidator,
     },
-    service::{TaskService, WorkflowsService},
+    service::{TaskService, WorkflowsService, MAX_CREATE_TASKS_BATCH_SIZE},
 };
 
+/// Type alias for the definition of an existing task found when importing a task. Contains the
+/// task id, description, url, timeout (in seconds), parameters schema and max retries.
+type ExistingTaskRow = (TaskId, String, String, Option<i64>, Option<Value>, i16);
+
 impl PgHasArrayType for WorkflowTask {
     fn array_type_info() -> PgTypeInfo {
         PgTypeInfo::with_name("_workflow_task")
@@ -29,6 +41,12 @@ impl PgHasArrayType for WorkflowTaskRequest {
     }
 }
 
+impl PgHasArrayType for TaskId {
+    fn array_type_info() -> PgTypeInfo {
+        <i64 as PgHasArrayType>::array_type_info()
+    }
+}
+
 /// Postgres implementation of [WorkflowsService]
 #[derive(Clone)]
 pub struct PgWorkflowsService {
@@ -40,6 +58,89 @@ impl PgWorkflowsService {
     pub fn new(pool: &PgPool) -> Self {
         Self { pool: pool.clone() }
     }
+
+    /// Find or create the task described by `task` within the `transaction`. The task service is
+    /// found by name and an existing task with the same name for that service is reused when the
+    /// rest of its definition matches. Otherwise a new task is created after passing the
+    /// [TaskRequestValidator].
+    /// # Errors
+    /// This function will return an error if the task service does not exist, an existing task
+    /// has a different definition, the new task is not valid or any query fails
+    async fn import_task<'c>(
+        transaction: &mut Transaction<'c, sqlx::Postgres>,
+        task: &WorkflowTaskExport,
+    ) -> EmResult<TaskId> {
+        let task_service_id: Option<i64> = sqlx::query_scalar(
+            r#"
+            select ts.service_id
+            from workflow.task_services ts
+            where ts.name = $1"#,
+        )
+        .bind(&task.task_service_name)
+        .fetch_optional(&mut *transaction)
+        .await?;
+        let Some(task_service_id) = task_service_id else {
+            return Err(EmError::MissingRecord {
+                pk: task.task_service_name.clone(),
+            });
+        };
+
+        let existing_task: Option<ExistingTaskRow> = sqlx::query_as(
+            r#"
+                select
+                    t.task_id, t.description, t.url, extract(epoch from t.timeout)::bigint,
+                    t.parameters_schema, t.max_retries
+                from workflow.tasks t
+                where
+                    t.name = $1
+                    and t.task_service_id = $2"#,
+        )
+        .bind(&task.name)
+        .bind(task_service_id)
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if let Some((task_id, description, url, timeout_seconds, parameters_schema, max_retries)) =
+            existing_task
+        {
+            let is_same_task = description == task.description
+                && url == task.url
+                && timeout_seconds == task.timeout_seconds
+                && parameters_schema == task.parameters_schema
+                && max_retries == task.max_retries;
+            if !is_same_task {
+                return Err(EmError::InvalidRequest {
+                    request: format!("Import of task '{}'", task.name),
+                    reason: format!(
+                        "Task already exists for task service '{}' with a different definition",
+                        task.task_service_name
+                    ),
+                });
+            }
+            return Ok(task_id);
+        }
+
+        let request = TaskRequest {
+            name: task.name.clone(),
+            description: task.description.clone(),
+            task_service_id,
+            url: task.url.clone(),
+            timeout_seconds: task.timeout_seconds,
+            parameters_schema: task.parameters_schema.clone(),
+            max_retries: task.max_retries,
+        };
+        TaskRequestValidator::validate_request(&request)?;
+        let task_id = sqlx::query_scalar("select workflow.create_task($1,$2,$3,$4,$5,$6,$7)")
+            .bind(request.name.trim())
+            .bind(&request.description)
+            .bind(request.task_service_id)
+            .bind(&request.url)
+            .bind(request.timeout_seconds)
+            .bind(&request.parameters_schema)
+            .bind(request.max_retries)
+            .fetch_one(&mut *transaction)
+            .await?;
+        Ok(task_id)
+    }
 }
 
 impl WorkflowsService for PgWorkflowsService {
@@ -48,10 +149,11 @@ impl WorkflowsService for PgWorkflowsService {
     type UpdateRequestValidator = WorkflowUpdateRequestValidator;
 
     async fn create_workflow(&self, request: &WorkflowCreateRequest) -> EmResult<Workflow> {
-        Self::CreateRequestValidator::validate(request)?;
+        Self::CreateRequestValidator::validate_request(request)?;
         let mut transaction = self.pool.begin().await?;
-        let workflow_id = sqlx::query_scalar("select workflow.create_workflow($1)")
-            .bind(&request.name)
+        let workflow_id = sqlx::query_scalar("select workflow.create_workflow($1,$2)")
+            .bind(request.name.trim())
+            .bind(request.max_parallel_tasks)
             .fetch_one(&mut transaction)
             .await?;
         let result = sqlx::query("call workflow.set_workflow_tasks($1,$2)")
@@ -66,7 +168,9 @@ impl WorkflowsService for PgWorkflowsService {
     async fn read_one(&self, workflow_id: &WorkflowId) -> EmResult<Workflow> {
         sqlx::query_as(
             r#"
-            select w.workflow_id, w.name, w.is_deprecated, w.new_workflow, w.tasks
+            select
+                w.workflow_id, w.name, w.is_deprecated, w.new_workflow, w.tasks,
+                w.max_parallel_tasks
             from workflow.v_workflows w
             where w.workflow_id = $1"#,
         )
@@ -83,25 +187,66 @@ impl WorkflowsService for PgWorkflowsService {
         )
     }
 
-    async fn read_many(&self) -> EmResult<Vec<Workflow>> {
-        let result = sqlx::query_as(
+    async fn read_one_by_name(&self, name: &str) -> EmResult<Workflow> {
+        let mut workflows: Vec<Workflow> = sqlx::query_as(
             r#"
-            select w.workflow_id, w.name, w.is_deprecated, w.new_workflow, w.tasks
-            from workflow.v_workflows w"#,
+            select
+                w.workflow_id, w.name, w.is_deprecated, w.new_workflow, w.tasks,
+                w.max_parallel_tasks
+            from workflow.v_workflows w
+            where w.name = $1
+            limit 2"#,
         )
+        .bind(name)
         .fetch_all(&self.pool)
         .await?;
-        Ok(result)
+        if workflows.len() > 1 {
+            return Err(EmError::Generic(format!(
+                "Multiple workflows match the name '{name}'"
+            )));
+        }
+        workflows.pop().map_or_else(
+            || {
+                Err(EmError::MissingRecord {
+                    pk: name.to_owned(),
+                })
+            },
+            Ok,
+        )
+    }
+
+    async fn read_many_paged(&self, page: &Pagination) -> EmResult<Page<Workflow>> {
+        let mut transaction = begin_snapshot(&self.pool).await?;
+        let total_count = sqlx::query_scalar("select count(*) from workflow.v_workflows")
+            .fetch_one(&mut transaction)
+            .await?;
+        let items = sqlx::query_as(
+            r#"
+            select
+                w.workflow_id, w.name, w.is_deprecated, w.new_workflow, w.tasks,
+                w.max_parallel_tasks
+            from workflow.v_workflows w
+            order by w.workflow_id
+            limit $1
+            offset $2"#,
+        )
+        .bind(page.limit)
+        .bind(page.offset)
+        .fetch_all(&mut transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(Page::new(items, total_count))
     }
 
     async fn update_workflow(&self, request: &WorkflowUpdateRequest) -> EmResult<Workflow> {
-        Self::UpdateRequestValidator::validate(request)?;
+        Self::UpdateRequestValidator::validate_request(request)?;
         let mut transaction = self.pool.begin().await?;
 
-        if let Some(new_name) = &request.name {
-            let result = sqlx::query("call workflow.update_workflow($1,$2)")
+        if request.name.is_some() || request.max_parallel_tasks.is_some() {
+            let result = sqlx::query("call workflow.update_workflow($1,$2,$3)")
                 .bind(request.workflow_id)
-                .bind(new_name)
+                .bind(request.name.as_deref().map(str::trim))
+                .bind(request.max_parallel_tasks)
                 .execute(&mut transaction)
                 .await;
             if let Err(error) = result {
@@ -134,6 +279,133 @@ impl WorkflowsService for PgWorkflowsService {
             .await?;
         Ok(request.workflow_id)
     }
+
+    async fn deprecate_and_migrate(&self, request: &WorkflowDeprecationRequest) -> EmResult<i32> {
+        let Some(new_workflow_id) = request.new_workflow_id else {
+            return Err(EmError::InvalidRequest {
+                request: format!("{request:?}"),
+                reason: "Cannot migrate jobs without a new_workflow_id".to_owned(),
+            });
+        };
+        if new_workflow_id == request.workflow_id {
+            return Err(EmError::InvalidRequest {
+                request: format!("{request:?}"),
+                reason: "Cannot migrate jobs from a workflow to itself".to_owned(),
+            });
+        }
+        let new_workflow_is_deprecated: Option<bool> = sqlx::query_scalar(
+            r#"
+            select w.is_deprecated
+            from workflow.workflows w
+            where w.workflow_id = $1"#,
+        )
+        .bind(new_workflow_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let reason = match new_workflow_is_deprecated {
+            None => Some("The new_workflow_id does not exist"),
+            Some(true) => Some("The new_workflow_id is deprecated"),
+            Some(false) => None,
+        };
+        if let Some(reason) = reason {
+            return Err(EmError::InvalidRequest {
+                request: format!("{request:?}"),
+                reason: reason.to_owned(),
+            });
+        }
+        let migrated_count =
+            sqlx::query_scalar("select workflow.deprecate_and_migrate_workflow($1,$2)")
+                .bind(request.workflow_id)
+                .bind(new_workflow_id)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(migrated_count)
+    }
+
+    async fn export(&self, workflow_id: &WorkflowId) -> EmResult<WorkflowExport> {
+        let mut transaction = self.pool.begin().await?;
+        let workflow: Option<(String, i16)> = sqlx::query_as(
+            r#"
+            select w.name, w.max_parallel_tasks
+            from workflow.workflows w
+            where w.workflow_id = $1"#,
+        )
+        .bind(workflow_id)
+        .fetch_optional(&mut transaction)
+        .await?;
+        let Some((name, max_parallel_tasks)) = workflow else {
+            transaction.rollback().await?;
+            return Err(EmError::MissingRecord {
+                pk: workflow_id.to_string(),
+            });
+        };
+        let tasks = sqlx::query_as(
+            r#"
+            select
+                t.name, t.description, ts.name task_service_name, t.url,
+                extract(epoch from t.timeout)::bigint timeout_seconds, t.parameters_schema,
+                t.max_retries, wt.parameters, wt.depends_on
+            from workflow.workflow_tasks wt
+            join workflow.tasks t on wt.task_id = t.task_id
+            join workflow.task_services ts on t.task_service_id = ts.service_id
+            where wt.workflow_id = $1
+            order by wt.task_order"#,
+        )
+        .bind(workflow_id)
+        .fetch_all(&mut transaction)
+        .await;
+        let tasks = finalize_transaction(tasks, transaction).await?;
+        Ok(WorkflowExport {
+            name,
+            max_parallel_tasks,
+            tasks,
+        })
+    }
+
+    async fn import(&self, export: &WorkflowExport) -> EmResult<Workflow> {
+        let mut transaction = self.pool.begin().await?;
+        let mut tasks = Vec::with_capacity(export.tasks.len());
+        for task in &export.tasks {
+            match Self::import_task(&mut transaction, task).await {
+                Ok(task_id) => tasks.push(WorkflowTaskRequest {
+                    task_id,
+                    parameters: task.parameters.clone(),
+                    depends_on: task.depends_on.clone(),
+                }),
+                Err(error) => {
+                    transaction.rollback().await?;
+                    return Err(error);
+                }
+            }
+        }
+
+        let request = WorkflowCreateRequest {
+            name: export.name.clone(),
+            tasks,
+            max_parallel_tasks: export.max_parallel_tasks,
+        };
+        if let Err(error) = Self::CreateRequestValidator::validate_request(&request) {
+            transaction.rollback().await?;
+            return Err(error);
+        }
+        let workflow_id: WorkflowId =
+            match sqlx::query_scalar("select workflow.create_workflow($1,$2)")
+                .bind(request.name.trim())
+                .bind(request.max_parallel_tasks)
+                .fetch_one(&mut transaction)
+                .await
+            {
+                Ok(inner) => inner,
+                Err(error) => return finalize_transaction(Err(error), transaction).await,
+            };
+        let result = sqlx::query("call workflow.set_workflow_tasks($1,$2)")
+            .bind(workflow_id)
+            .bind(&request.tasks)
+            .execute(&mut transaction)
+            .await;
+        finalize_transaction(result, transaction).await?;
+        self.read_one(&workflow_id).await
+    }
 }
 
 /// Postgres implementation of [TaskService]
@@ -154,20 +426,74 @@ impl TaskService for PgTasksService {
     type RequestValidator = TaskRequestValidator;
 
     async fn create_task(&self, request: &TaskRequest) -> EmResult<Task> {
-        let task_id: TaskId = sqlx::query_scalar("select workflow.create_task($1,$2,$3,$4)")
-            .bind(&request.name)
-            .bind(&request.description)
-            .bind(request.task_service_id)
-            .bind(&request.url)
-            .fetch_one(&self.pool)
-            .await?;
+        Self::RequestValidator::validate_request(request)?;
+        let task_id: TaskId =
+            sqlx::query_scalar("select workflow.create_task($1,$2,$3,$4,$5,$6,$7)")
+                .bind(request.name.trim())
+                .bind(&request.description)
+                .bind(request.task_service_id)
+                .bind(&request.url)
+                .bind(request.timeout_seconds)
+                .bind(&request.parameters_schema)
+                .bind(request.max_retries)
+                .fetch_one(&self.pool)
+                .await?;
         self.read_one(&task_id).await
     }
 
+    async fn create_tasks(&self, requests: &[TaskRequest]) -> EmResult<Vec<Task>> {
+        if requests.is_empty() || requests.len() > MAX_CREATE_TASKS_BATCH_SIZE {
+            return Err(EmError::InvalidRequest {
+                request: format!("Create {} tasks", requests.len()),
+                reason: format!(
+                    "Task batch size must be between 1 and {MAX_CREATE_TASKS_BATCH_SIZE}"
+                ),
+            });
+        }
+        for request in requests {
+            Self::RequestValidator::validate_request(request)?;
+        }
+        let mut transaction = self.pool.begin().await?;
+        let mut task_ids: Vec<i64> = Vec::with_capacity(requests.len());
+        for request in requests {
+            let result = sqlx::query_scalar("select workflow.create_task($1,$2,$3,$4,$5,$6,$7)")
+                .bind(request.name.trim())
+                .bind(&request.description)
+                .bind(request.task_service_id)
+                .bind(&request.url)
+                .bind(request.timeout_seconds)
+                .bind(&request.parameters_schema)
+                .bind(request.max_retries)
+                .fetch_one(&mut transaction)
+                .await;
+            match result {
+                Ok(task_id) => task_ids.push(task_id),
+                Err(error) => return finalize_transaction(Err(error), transaction).await,
+            }
+        }
+        let result = sqlx::query_as(
+            r#"
+            select
+                t.task_id, t.name, t.description, t.url, t.task_service_name,
+                extract(epoch from t.timeout)::bigint timeout_seconds, t.parameters_schema,
+                t.max_retries
+            from unnest($1::bigint[]) with ordinality i(task_id, task_index)
+            join workflow.v_tasks t on t.task_id = i.task_id
+            order by i.task_index"#,
+        )
+        .bind(&task_ids)
+        .fetch_all(&mut transaction)
+        .await;
+        finalize_transaction(result, transaction).await
+    }
+
     async fn read_one(&self, task_id: &TaskId) -> EmResult<Task> {
         let result = sqlx::query_as(
             r#"
-            select task_id, name, description, url, task_service_name
+            select
+                task_id, name, description, url, task_service_name,
+                extract(epoch from timeout)::bigint timeout_seconds, parameters_schema,
+                max_retries
             from workflow.v_tasks
             where task_id = $1"#,
         )
@@ -184,26 +510,694 @@ impl TaskService for PgTasksService {
         )
     }
 
-    async fn read_many(&self) -> EmResult<Vec<Task>> {
-        let result = sqlx::query_as(
+    async fn read_many_paged(&self, page: &Pagination) -> EmResult<Page<Task>> {
+        let mut transaction = begin_snapshot(&self.pool).await?;
+        let total_count = sqlx::query_scalar("select count(*) from workflow.v_tasks")
+            .fetch_one(&mut transaction)
+            .await?;
+        let items = sqlx::query_as(
             r#"
-            select task_id, name, description, url, task_service_name
-            from workflow.v_tasks"#,
+            select
+                task_id, name, description, url, task_service_name,
+                extract(epoch from timeout)::bigint timeout_seconds, parameters_schema,
+                max_retries
+            from workflow.v_tasks
+            order by task_id
+            limit $1
+            offset $2"#,
         )
-        .fetch_all(&self.pool)
+        .bind(page.limit)
+        .bind(page.offset)
+        .fetch_all(&mut transaction)
         .await?;
-        Ok(result)
+        transaction.commit().await?;
+        Ok(Page::new(items, total_count))
     }
 
     async fn update(&self, task_id: &TaskId, request: &TaskRequest) -> EmResult<Task> {
-        sqlx::query("call workflow.update_task($1,$2,$3,$4,$5)")
+        Self::RequestValidator::validate_request(request)?;
+        sqlx::query("call workflow.update_task($1,$2,$3,$4,$5,$6,$7,$8)")
             .bind(task_id)
-            .bind(&request.name)
+            .bind(request.name.trim())
             .bind(&request.description)
             .bind(request.task_service_id)
             .bind(&request.url)
+            .bind(request.timeout_seconds)
+            .bind(&request.parameters_schema)
+            .bind(request.max_retries)
             .execute(&self.pool)
             .await?;
         self.read_one(task_id).await
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::panic)]
+mod test {
+    use chrono::Utc;
+    use common::error::{EmError, EmResult};
+    use rstest::rstest;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use super::{PgTasksService, PgWorkflowsService};
+    use crate::{
+        database::test::{cleanup_workflow, database},
+        workflow::{
+            data::{
+                TaskRequest, WorkflowCreateRequest, WorkflowDeprecationRequest, WorkflowExport,
+                WorkflowId, WorkflowTaskExport, WorkflowTaskRequest,
+            },
+            service::{TaskService, WorkflowsService},
+        },
+    };
+
+    /// Create a new task service for testing tasks. Name is made unique using the `prefix` and
+    /// the current timestamp.
+    async fn create_test_task_service(pool: &PgPool, prefix: &str) -> EmResult<i64> {
+        let name = format!("{prefix}_{}", Utc::now().timestamp_millis());
+        let service_id = sqlx::query_scalar(
+            r#"
+            insert into workflow.task_services(name, base_url)
+            values($1, 'http://127.0.0.1')
+            returning service_id"#,
+        )
+        .bind(&name)
+        .fetch_one(pool)
+        .await?;
+        Ok(service_id)
+    }
+
+    /// Cleanup function for task services that are created during tests. Removes the task service
+    /// and its tasks as well as every workflow that uses one of the tasks.
+    async fn cleanup_task_service(pool: &PgPool, task_service_id: i64) -> EmResult<()> {
+        let workflow_ids: Vec<WorkflowId> = sqlx::query_scalar(
+            r#"
+            select distinct wt.workflow_id
+            from workflow.workflow_tasks wt
+            join workflow.tasks t
+            on wt.task_id = t.task_id
+            where t.task_service_id = $1"#,
+        )
+        .bind(task_service_id)
+        .fetch_all(pool)
+        .await?;
+        for workflow_id in workflow_ids {
+            cleanup_workflow(pool, workflow_id).await?;
+        }
+        sqlx::query("delete from workflow.tasks where task_service_id = $1")
+            .bind(task_service_id)
+            .execute(pool)
+            .await?;
+        sqlx::query("delete from workflow.task_services where service_id = $1")
+            .bind(task_service_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Create a [TaskRequest] for the `task_service_id` with a name of `name`
+    fn task_request(name: &str, task_service_id: i64) -> TaskRequest {
+        TaskRequest {
+            name: name.to_owned(),
+            description: "create_tasks test".to_owned(),
+            task_service_id,
+            url: name.to_owned(),
+            timeout_seconds: None,
+            parameters_schema: None,
+            max_retries: 0,
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_one_by_name_should_succeed_when_name_matches(database: PgPool) -> EmResult<()> {
+        let name = format!("read_one_by_name_{}", Utc::now().timestamp_millis());
+        let workflow_id = sqlx::query_scalar(
+            "insert into workflow.workflows(name) values($1) returning workflow_id",
+        )
+        .bind(&name)
+        .fetch_one(&database)
+        .await?;
+        let service = PgWorkflowsService::new(&database);
+
+        let action = service.read_one_by_name(&name).await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let workflow = action?;
+
+        assert_eq!(workflow.name, name);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_task_should_trim_name(database: PgPool) -> EmResult<()> {
+        let task_service_id = create_test_task_service(&database, "create_task_trim").await?;
+        let name = format!("create_task_trim_{}", Utc::now().timestamp_millis());
+        let service = PgTasksService::new(&database);
+
+        let action = service
+            .create_task(&task_request(&format!("  {name}  "), task_service_id))
+            .await;
+        cleanup_task_service(&database, task_service_id).await?;
+        let task = action?;
+
+        assert_eq!(task.name, name);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_task_and_update_should_set_timeout(database: PgPool) -> EmResult<()> {
+        let task_service_id = create_test_task_service(&database, "create_task_timeout").await?;
+        let name = format!("create_task_timeout_{}", Utc::now().timestamp_millis());
+        let service = PgTasksService::new(&database);
+
+        let action = async {
+            let task = service
+                .create_task(&TaskRequest {
+                    timeout_seconds: Some(90),
+                    ..task_request(&name, task_service_id)
+                })
+                .await?;
+            let updated_task = service
+                .update(&task.task_id, &task_request(&name, task_service_id))
+                .await?;
+            EmResult::Ok((task, updated_task))
+        }
+        .await;
+        cleanup_task_service(&database, task_service_id).await?;
+        let (task, updated_task) = action?;
+
+        assert_eq!(task.timeout_seconds, Some(90));
+        assert_eq!(updated_task.timeout_seconds, None);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_task_should_set_parameters_schema(database: PgPool) -> EmResult<()> {
+        let task_service_id = create_test_task_service(&database, "create_task_schema").await?;
+        let name = format!("create_task_schema_{}", Utc::now().timestamp_millis());
+        let parameters_schema = json!({ "type": "object", "required": ["id"] });
+        let service = PgTasksService::new(&database);
+
+        let action = service
+            .create_task(&TaskRequest {
+                parameters_schema: Some(parameters_schema.clone()),
+                ..task_request(&name, task_service_id)
+            })
+            .await;
+        cleanup_task_service(&database, task_service_id).await?;
+        let task = action?;
+
+        assert_eq!(task.parameters_schema, Some(parameters_schema));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_task_and_update_should_set_max_retries(database: PgPool) -> EmResult<()> {
+        let task_service_id = create_test_task_service(&database, "create_task_retries").await?;
+        let name = format!("create_task_retries_{}", Utc::now().timestamp_millis());
+        let service = PgTasksService::new(&database);
+
+        let action = async {
+            let task = service
+                .create_task(&TaskRequest {
+                    max_retries: 2,
+                    ..task_request(&name, task_service_id)
+                })
+                .await?;
+            let updated_task = service
+                .update(
+                    &task.task_id,
+                    &TaskRequest {
+                        max_retries: 5,
+                        ..task_request(&name, task_service_id)
+                    },
+                )
+                .await?;
+            EmResult::Ok((task, updated_task))
+        }
+        .await;
+        cleanup_task_service(&database, task_service_id).await?;
+        let (task, updated_task) = action?;
+
+        assert_eq!(task.max_retries, 2);
+        assert_eq!(updated_task.max_retries, 5);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_task_should_fail_when_name_is_whitespace(database: PgPool) -> EmResult<()> {
+        let task_service_id = create_test_task_service(&database, "create_task_blank").await?;
+        let service = PgTasksService::new(&database);
+
+        let result = service
+            .create_task(&task_request("   ", task_service_id))
+            .await;
+        cleanup_task_service(&database, task_service_id).await?;
+
+        assert!(matches!(result, Err(EmError::InvalidRequest { .. })));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::missing_name("missing", |_: &str| "read_one_by_name_missing_workflow".to_owned())]
+    #[case::different_case("case", str::to_uppercase)]
+    #[tokio::test]
+    async fn read_one_by_name_should_fail_when(
+        database: PgPool,
+        #[case] prefix: &str,
+        #[case] lookup_name: fn(&str) -> String,
+    ) -> EmResult<()> {
+        let name = format!(
+            "read_one_by_name_{prefix}_{}",
+            Utc::now().timestamp_millis()
+        );
+        let workflow_id = sqlx::query_scalar(
+            "insert into workflow.workflows(name) values($1) returning workflow_id",
+        )
+        .bind(&name)
+        .fetch_one(&database)
+        .await?;
+        let service = PgWorkflowsService::new(&database);
+
+        let result = service.read_one_by_name(&lookup_name(&name)).await;
+        cleanup_workflow(&database, workflow_id).await?;
+
+        assert!(matches!(result, Err(EmError::MissingRecord { .. })));
+        Ok(())
+    }
+
+    /// Create a new workflow without tasks for testing. Name is made unique using the `prefix`
+    /// and the current timestamp.
+    async fn create_test_workflow(pool: &PgPool, prefix: &str) -> EmResult<WorkflowId> {
+        let name = format!("{prefix}_{}", Utc::now().timestamp_millis());
+        let workflow_id = sqlx::query_scalar(
+            r#"
+            insert into workflow.workflows(name)
+            values($1)
+            returning workflow_id"#,
+        )
+        .bind(&name)
+        .fetch_one(pool)
+        .await?;
+        Ok(workflow_id)
+    }
+
+    /// Create a new interval job for the `workflow_id`, paused if `is_paused` is true. Returns
+    /// the new `job_id`.
+    async fn create_test_job(
+        pool: &PgPool,
+        workflow_id: WorkflowId,
+        is_paused: bool,
+    ) -> EmResult<i64> {
+        let job_id = sqlx::query_scalar(
+            r#"
+            insert into job.jobs(workflow_id, job_type, maintainer, job_interval, next_run, is_paused)
+            values(
+                $1, 'Interval'::job.job_type, 'test@example.com', interval '1 day',
+                now() at time zone 'UTC' + interval '1 day', $2
+            )
+            returning job_id"#,
+        )
+        .bind(workflow_id)
+        .bind(is_paused)
+        .fetch_one(pool)
+        .await?;
+        Ok(job_id)
+    }
+
+    /// Read the deprecation state (`is_deprecated` and `new_workflow`) of the `workflow_id`.
+    /// Workflows without tasks are not found in `workflow.v_workflows` so the table is read.
+    async fn workflow_deprecation(
+        pool: &PgPool,
+        workflow_id: WorkflowId,
+    ) -> EmResult<(bool, Option<WorkflowId>)> {
+        let deprecation = sqlx::query_as(
+            r#"
+            select is_deprecated, new_workflow
+            from workflow.workflows
+            where workflow_id = $1"#,
+        )
+        .bind(workflow_id)
+        .fetch_one(pool)
+        .await?;
+        Ok(deprecation)
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn deprecate_and_migrate_should_repoint_active_jobs(database: PgPool) -> EmResult<()> {
+        let old_workflow_id = create_test_workflow(&database, "deprecate_and_migrate_old").await?;
+        let new_workflow_id = create_test_workflow(&database, "deprecate_and_migrate_new").await?;
+        let active_job_id = create_test_job(&database, old_workflow_id, false).await?;
+        let paused_job_id = create_test_job(&database, old_workflow_id, true).await?;
+        let service = PgWorkflowsService::new(&database);
+        let request = WorkflowDeprecationRequest {
+            workflow_id: old_workflow_id,
+            new_workflow_id: Some(new_workflow_id),
+        };
+
+        let action = async {
+            let migrated_count = service.deprecate_and_migrate(&request).await?;
+            let job_workflow_id = |job_id: i64| {
+                sqlx::query_scalar::<_, WorkflowId>(
+                    "select workflow_id from job.jobs where job_id = $1",
+                )
+                .bind(job_id)
+                .fetch_one(&database)
+            };
+            let active_job_workflow_id = job_workflow_id(active_job_id).await?;
+            let paused_job_workflow_id = job_workflow_id(paused_job_id).await?;
+            let deprecation = workflow_deprecation(&database, old_workflow_id).await?;
+            EmResult::Ok((
+                migrated_count,
+                active_job_workflow_id,
+                paused_job_workflow_id,
+                deprecation,
+            ))
+        }
+        .await;
+        cleanup_workflow(&database, old_workflow_id).await?;
+        cleanup_workflow(&database, new_workflow_id).await?;
+        let (
+            migrated_count,
+            active_job_workflow_id,
+            paused_job_workflow_id,
+            (is_deprecated, new_workflow),
+        ) = action?;
+
+        assert_eq!(migrated_count, 1);
+        assert_eq!(active_job_workflow_id, new_workflow_id);
+        assert_eq!(paused_job_workflow_id, old_workflow_id);
+        assert!(is_deprecated, "Old workflow should be deprecated");
+        assert_eq!(new_workflow, Some(new_workflow_id));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::no_new_workflow("deprecate_and_migrate_none", |_| None)]
+    #[case::same_workflow("deprecate_and_migrate_same", Some)]
+    #[case::missing_new_workflow("deprecate_and_migrate_missing", |_| Some(WorkflowId::from(-1)))]
+    #[tokio::test]
+    async fn deprecate_and_migrate_should_fail_with_invalid_request_when_new_workflow_invalid(
+        database: PgPool,
+        #[case] prefix: &str,
+        #[case] new_workflow_id: fn(WorkflowId) -> Option<WorkflowId>,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, prefix).await?;
+        let service = PgWorkflowsService::new(&database);
+        let request = WorkflowDeprecationRequest {
+            workflow_id,
+            new_workflow_id: new_workflow_id(workflow_id),
+        };
+
+        let result = service.deprecate_and_migrate(&request).await;
+        let deprecation = workflow_deprecation(&database, workflow_id).await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (is_deprecated, _) = deprecation?;
+
+        assert!(
+            matches!(result, Err(EmError::InvalidRequest { .. })),
+            "Expected an invalid request error, got {result:?}"
+        );
+        assert!(!is_deprecated, "Workflow should not be deprecated");
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn deprecate_and_migrate_should_fail_with_invalid_request_when_new_workflow_deprecated(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "deprecate_and_migrate_old_dep").await?;
+        let new_workflow_id =
+            create_test_workflow(&database, "deprecate_and_migrate_new_dep").await?;
+        let service = PgWorkflowsService::new(&database);
+        let request = WorkflowDeprecationRequest {
+            workflow_id,
+            new_workflow_id: Some(new_workflow_id),
+        };
+
+        let action = async {
+            service
+                .deprecate(&WorkflowDeprecationRequest {
+                    workflow_id: new_workflow_id,
+                    new_workflow_id: None,
+                })
+                .await?;
+            let result = service.deprecate_and_migrate(&request).await;
+            let deprecation = workflow_deprecation(&database, workflow_id).await?;
+            EmResult::Ok((result, deprecation))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        cleanup_workflow(&database, new_workflow_id).await?;
+        let (result, (is_deprecated, _)) = action?;
+
+        assert!(
+            matches!(result, Err(EmError::InvalidRequest { .. })),
+            "Expected an invalid request error, got {result:?}"
+        );
+        assert!(!is_deprecated, "Workflow should not be deprecated");
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_tasks_should_return_tasks_in_request_order(database: PgPool) -> EmResult<()> {
+        let prefix = format!("create_tasks_{}", Utc::now().timestamp_millis());
+        let task_service_id = create_test_task_service(&database, &prefix).await?;
+        let names: Vec<String> = (1..=3).map(|i| format!("{prefix}_{i}")).collect();
+        let requests: Vec<TaskRequest> = names
+            .iter()
+            .rev()
+            .map(|name| task_request(name, task_service_id))
+            .collect();
+        let service = PgTasksService::new(&database);
+
+        let action = service.create_tasks(&requests).await;
+        cleanup_task_service(&database, task_service_id).await?;
+        let tasks = action?;
+
+        let task_names: Vec<&str> = tasks.iter().map(|task| task.name.as_str()).collect();
+        let request_names: Vec<&str> = requests
+            .iter()
+            .map(|request| request.name.as_str())
+            .collect();
+        assert_eq!(task_names, request_names);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::invalid_request("invalid", task_request("", 0))]
+    #[case::missing_task_service("missing_service", task_request("create_tasks_missing", -1))]
+    #[tokio::test]
+    async fn create_tasks_should_rollback_batch_when(
+        database: PgPool,
+        #[case] prefix: &str,
+        #[case] failing_request: TaskRequest,
+    ) -> EmResult<()> {
+        let name = format!("create_tasks_{prefix}_{}", Utc::now().timestamp_millis());
+        let task_service_id = create_test_task_service(&database, &name).await?;
+        let requests = vec![task_request(&name, task_service_id), failing_request];
+        let service = PgTasksService::new(&database);
+
+        let result = service.create_tasks(&requests).await;
+
+        let task_count =
+            sqlx::query_scalar::<_, i64>("select count(*) from workflow.tasks where name = $1")
+                .bind(&name)
+                .fetch_one(&database)
+                .await;
+        cleanup_task_service(&database, task_service_id).await?;
+        let task_count = task_count?;
+
+        assert!(result.is_err());
+        assert_eq!(task_count, 0);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn import_should_recreate_exported_workflow(database: PgPool) -> EmResult<()> {
+        let prefix = format!("export_{}", Utc::now().timestamp_millis());
+        let task_service_id = create_test_task_service(&database, &prefix).await?;
+        let requests: Vec<TaskRequest> = (1..=2)
+            .map(|i| TaskRequest {
+                timeout_seconds: Some(60),
+                parameters_schema: Some(json!({ "type": "object" })),
+                max_retries: i,
+                ..task_request(&format!("{prefix}_{i}"), task_service_id)
+            })
+            .collect();
+        let service = PgWorkflowsService::new(&database);
+        let imported_name = format!("{prefix}_imported");
+
+        let action = async {
+            let tasks = PgTasksService::new(&database)
+                .create_tasks(&requests)
+                .await?;
+            let workflow_id = service
+                .create_workflow(&WorkflowCreateRequest {
+                    name: prefix.clone(),
+                    tasks: tasks
+                        .iter()
+                        .zip(1..)
+                        .map(|(task, task_order)| WorkflowTaskRequest {
+                            task_id: task.task_id,
+                            parameters: Some(json!({ "order": task_order })),
+                            depends_on: (task_order > 1).then(Vec::new),
+                        })
+                        .collect(),
+                    max_parallel_tasks: 2,
+                })
+                .await?
+                .workflow_id;
+            let export = service.export(&workflow_id).await?;
+            let workflow = service
+                .import(&WorkflowExport {
+                    name: imported_name.clone(),
+                    ..export
+                })
+                .await?;
+            let round_trip = service.export(&workflow.workflow_id).await?;
+            let export = service.export(&workflow_id).await?;
+            EmResult::Ok((export, workflow, round_trip))
+        }
+        .await;
+        cleanup_task_service(&database, task_service_id).await?;
+        let (export, workflow, round_trip) = action?;
+
+        assert_eq!(workflow.name, imported_name);
+        assert_eq!(round_trip.tasks, export.tasks);
+        assert_eq!(round_trip.max_parallel_tasks, export.max_parallel_tasks);
+        assert_eq!(export.tasks.len(), 2);
+        assert!(export
+            .tasks
+            .iter()
+            .all(|task| task.timeout_seconds == Some(60)
+                && task.parameters_schema == Some(json!({ "type": "object" }))));
+        Ok(())
+    }
+
+    /// Create a [WorkflowTaskExport] named `name` for the task service named `task_service_name`
+    fn task_export(name: &str, task_service_name: &str) -> WorkflowTaskExport {
+        WorkflowTaskExport {
+            name: name.to_owned(),
+            description: "import test".to_owned(),
+            task_service_name: task_service_name.to_owned(),
+            url: name.to_owned(),
+            timeout_seconds: Some(30),
+            parameters_schema: Some(json!({ "type": "object" })),
+            max_retries: 3,
+            parameters: Some(json!({})),
+            depends_on: None,
+        }
+    }
+
+    /// Read the name of the task service with the `task_service_id`
+    async fn task_service_name(pool: &PgPool, task_service_id: i64) -> EmResult<String> {
+        let name = sqlx::query_scalar(
+            "select ts.name from workflow.task_services ts where ts.service_id = $1",
+        )
+        .bind(task_service_id)
+        .fetch_one(pool)
+        .await?;
+        Ok(name)
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn import_should_create_missing_tasks(database: PgPool) -> EmResult<()> {
+        let prefix = format!("import_create_{}", Utc::now().timestamp_millis());
+        let task_service_id = create_test_task_service(&database, &prefix).await?;
+        let task_service_name = task_service_name(&database, task_service_id).await?;
+        let export = WorkflowExport {
+            name: prefix.clone(),
+            max_parallel_tasks: 1,
+            tasks: vec![task_export(&format!("{prefix}_task"), &task_service_name)],
+        };
+        let service = PgWorkflowsService::new(&database);
+
+        let action = async {
+            let workflow = service.import(&export).await?;
+            let round_trip = service.export(&workflow.workflow_id).await?;
+            let Some(task) = workflow.tasks.first() else {
+                panic!("Imported workflow should contain a task");
+            };
+            let task = PgTasksService::new(&database)
+                .read_one(&task.task_id)
+                .await?;
+            EmResult::Ok((round_trip, task))
+        }
+        .await;
+        cleanup_task_service(&database, task_service_id).await?;
+        let (round_trip, task) = action?;
+
+        assert_eq!(round_trip, export);
+        assert_eq!(task.timeout_seconds, Some(30));
+        assert_eq!(task.parameters_schema, Some(json!({ "type": "object" })));
+        assert_eq!(task.max_retries, 3);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::description(|task: &mut WorkflowTaskExport| task.description = "changed".to_owned())]
+    #[case::url(|task: &mut WorkflowTaskExport| task.url = "changed".to_owned())]
+    #[case::timeout(|task: &mut WorkflowTaskExport| task.timeout_seconds = None)]
+    #[case::max_retries(|task: &mut WorkflowTaskExport| task.max_retries = 0)]
+    #[tokio::test]
+    async fn import_should_fail_when_existing_task_differs(
+        database: PgPool,
+        #[case] change: fn(&mut WorkflowTaskExport),
+    ) -> EmResult<()> {
+        let prefix = format!("import_differs_{}", Utc::now().timestamp_millis());
+        let task_service_id = create_test_task_service(&database, &prefix).await?;
+        let task_service_name = task_service_name(&database, task_service_id).await?;
+        let service = PgWorkflowsService::new(&database);
+        let mut task = task_export(&format!("{prefix}_task"), &task_service_name);
+        let imported_name = format!("{prefix}_changed");
+
+        let action = async {
+            service
+                .import(&WorkflowExport {
+                    name: prefix.clone(),
+                    max_parallel_tasks: 1,
+                    tasks: vec![task_export(&task.name, &task_service_name)],
+                })
+                .await?;
+            change(&mut task);
+            let result = service
+                .import(&WorkflowExport {
+                    name: imported_name.clone(),
+                    max_parallel_tasks: 1,
+                    tasks: vec![task],
+                })
+                .await;
+            let workflow = service.read_one_by_name(&imported_name).await;
+            EmResult::Ok((result, workflow))
+        }
+        .await;
+        cleanup_task_service(&database, task_service_id).await?;
+        let (result, workflow) = action?;
+
+        assert!(matches!(result, Err(EmError::InvalidRequest { .. })));
+        assert!(matches!(workflow, Err(EmError::MissingRecord { .. })));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn export_should_fail_when_workflow_missing(database: PgPool) {
+        let service = PgWorkflowsService::new(&database);
+
+        let result = service.export(&WorkflowId::from(-1)).await;
+
+        assert!(matches!(result, Err(EmError::MissingRecord { .. })));
+    }
+}
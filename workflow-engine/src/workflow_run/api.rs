@@ -1,11 +1,27 @@
-use actix_web::{web, Scope};
-use common::api::{request::ApiRequest, ApiResponse, QueryApiFormat};
+use actix_web::{web, HttpRequest, HttpResponse, Responder, Scope};
+use common::api::{
+    pagination::{CursorPage, CursorPagination},
+    request::ApiRequest,
+    sort::Sort,
+    ApiResponse, QueryApiFormat,
+};
+use futures::{stream, StreamExt};
+use log::{error, warn};
+use tokio::sync::broadcast::error::RecvError;
+use users::data::role::RoleName;
 
 use super::data::WorkflowRunTask;
 use crate::{
+    api::RequirePrivilege,
+    executor::data::ExecutorId,
     workflow::data::WorkflowId,
     workflow_run::{
-        data::{TaskQueueRequest, WorkflowRun, WorkflowRunId},
+        data::{
+            RunTimingSummary, TaskDetail, TaskQueueRequest, TaskRule, TaskStatus, WorkflowRun,
+            WorkflowRunCancelRequest, WorkflowRunFilter, WorkflowRunHistory,
+            WorkflowRunHistoryQuery, WorkflowRunId, WorkflowRunProgress, WorkflowRunSummary,
+            WorkflowRunTasksQuery,
+        },
         service::{TaskQueueService, WorkflowRunsService},
     },
 };
@@ -15,7 +31,31 @@ where
     R: WorkflowRunsService + Send + Sync + 'static,
 {
     web::scope("/workflow-runs")
+        .route("/page", web::get().to(workflow_runs_page::<R>))
+        .route("/history", web::get().to(workflow_runs_history::<R>))
+        .route("/status", web::post().to(workflow_runs_status::<R>))
+        .route(
+            "/recover",
+            web::post().to(recover_orphaned_workflow_runs::<R>),
+        )
+        .route("/summary", web::get().to(workflow_run_summaries::<R>))
+        .route(
+            "/summary/{workflow_run_id}",
+            web::get().to(workflow_run_summary::<R>),
+        )
+        .route(
+            "/with-task-status/{status}",
+            web::get().to(workflow_runs_with_task_status::<R>),
+        )
+        .route(
+            "/progress/{workflow_run_id}",
+            web::get().to(workflow_run_progress::<R>),
+        )
         .route("/{workflow_run_id}", web::get().to(workflow_run::<R>))
+        .route(
+            "/{workflow_run_id}/timing",
+            web::get().to(workflow_run_timing::<R>),
+        )
         .route(
             "/tasks/{workflow_run_id}",
             web::get().to(workflow_run_tasks::<R>),
@@ -25,18 +65,44 @@ where
             "/init/{workflow_id}",
             web::post().to(init_workflow_run::<R>),
         )
-        .route(
-            "/cancel/{workflow_run_id}",
-            web::post().to(cancel_workflow_run::<R>),
+        .service(
+            web::resource("/cancel/{workflow_run_id}")
+                .wrap(RequirePrivilege(RoleName::ManageWorkflowEngine))
+                .route(web::post().to(cancel_workflow_run::<R>)),
+        )
+        .service(
+            web::resource("/cancel/{workflow_run_id}/reason")
+                .wrap(RequirePrivilege(RoleName::ManageWorkflowEngine))
+                .route(web::post().to(cancel_workflow_run_with_reason::<R>)),
         )
         .route(
             "/schedule/{workflow_run_id}",
             web::post().to(schedule_workflow_run::<R>),
         )
+        .route(
+            "/schedule/{workflow_run_id}/executor/{executor_id}",
+            web::post().to(schedule_workflow_run_with_executor::<R>),
+        )
+        .route(
+            "/schedule/{workflow_run_id}/priority/{priority}",
+            web::post().to(schedule_workflow_run_with_priority::<R>),
+        )
         .route(
             "/restart/{workflow_run_id}",
             web::post().to(restart_workflow_run::<R>),
         )
+        .route(
+            "/pause/{workflow_run_id}",
+            web::post().to(pause_workflow_run::<R>),
+        )
+        .route(
+            "/resume/{workflow_run_id}",
+            web::post().to(resume_workflow_run::<R>),
+        )
+        .route(
+            "/clone/{workflow_run_id}",
+            web::post().to(clone_workflow_run::<R>),
+        )
 }
 
 pub fn task_queue_service<Q, R>() -> Scope
@@ -46,13 +112,27 @@ where
 {
     web::scope("/task-queue")
         .route("/retry", web::post().to(task_queue_retry::<Q>))
+        .route(
+            "/retry-all/{workflow_run_id}",
+            web::post().to(task_queue_retry_all::<Q>),
+        )
         .route("/complete", web::post().to(task_queue_complete::<Q>))
+        .route(
+            "/{workflow_run_id}/{task_order}",
+            web::get().to(task_queue_detail::<Q>),
+        )
+        .route(
+            "/{workflow_run_id}/{task_order}/rules",
+            web::get().to(task_queue_rules::<Q>),
+        )
 }
 
 /// API endpoint to fetch the specified workflow run by the `workflow_run_id`. Returns a single
-/// [WorkflowRun] if the run can be found
+/// [WorkflowRun] if the run can be found. The optional `task_status` query parameter (comma
+/// separated [TaskStatus] values) limits the tasks included in the response.
 async fn workflow_run<R>(
     workflow_run_id: actix_web::web::Path<WorkflowRunId>,
+    tasks_query: actix_web::web::Query<WorkflowRunTasksQuery>,
     service: actix_web::web::Data<R>,
     query: actix_web::web::Query<QueryApiFormat>,
 ) -> ApiResponse<WorkflowRun>
@@ -60,12 +140,173 @@ where
     R: WorkflowRunsService,
 {
     let format = query.into_inner();
-    match service.read_one(&workflow_run_id).await {
+    let task_status_filter = match tasks_query.task_status_filter() {
+        Ok(inner) => inner,
+        Err(error) => return ApiResponse::error(error, format.f),
+    };
+    match service
+        .read_one_filtered(&workflow_run_id, task_status_filter)
+        .await
+    {
         Ok(workflow_run) => ApiResponse::success(workflow_run, format.f),
         Err(error) => ApiResponse::error(error, format.f),
     }
 }
 
+/// API endpoint to fetch the header fields of the specified workflow run by the `workflow_run_id`.
+/// Returns a single [WorkflowRunSummary] if the run can be found
+async fn workflow_run_summary<R>(
+    workflow_run_id: actix_web::web::Path<WorkflowRunId>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<WorkflowRunSummary>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    match service.read_one_summary(&workflow_run_id).await {
+        Ok(workflow_run) => ApiResponse::success(workflow_run, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to fetch the timing of the specified workflow run by the `workflow_run_id`.
+/// Returns a [RunTimingSummary] with the queue wait, run duration and per task durations if the
+/// run can be found
+async fn workflow_run_timing<R>(
+    workflow_run_id: actix_web::web::Path<WorkflowRunId>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<RunTimingSummary>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    match service.read_timing(&workflow_run_id).await {
+        Ok(timing) => ApiResponse::success(timing, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to fetch the header fields of all active workflow runs. Returns a
+/// [WorkflowRunSummary] for each run, without the tasks of the run
+async fn workflow_run_summaries<R>(
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<WorkflowRunSummary>>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    match service.read_active_summaries().await {
+        Ok(workflow_runs) => ApiResponse::success(workflow_runs, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to fetch the header fields of all workflow runs that contain at least one task
+/// with the specified `status`. Returns a [WorkflowRunSummary] for each matching run
+async fn workflow_runs_with_task_status<R>(
+    status: actix_web::web::Path<TaskStatus>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<WorkflowRunSummary>>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    match service.read_by_task_status(status.into_inner()).await {
+        Ok(workflow_runs) => ApiResponse::success(workflow_runs, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to fetch the workflow runs specified by the list of ids in the request body.
+/// Returns the [WorkflowRun] records in the order of the requested ids, omitting any id that does
+/// not match a workflow run
+async fn workflow_runs_status<R>(
+    api_request: ApiRequest<Vec<WorkflowRunId>>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<WorkflowRun>>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    let workflow_run_ids = api_request.into_inner();
+    match service.read_many_by_ids(&workflow_run_ids).await {
+        Ok(workflow_runs) => ApiResponse::success(workflow_runs, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// Format a [WorkflowRunProgress] as a single server-sent event frame
+fn progress_event(progress: &WorkflowRunProgress) -> web::Bytes {
+    let data = serde_json::to_string(progress).unwrap_or_default();
+    web::Bytes::from(format!("data: {data}\n\n"))
+}
+
+/// API endpoint to stream the progress of the specified workflow run by the `workflow_run_id` as
+/// server-sent events. The current state of the run is sent as the first event, followed by an
+/// event for every progress or status change. The stream ends once the workflow run reaches a
+/// terminal status.
+async fn workflow_run_progress<R>(
+    req: HttpRequest,
+    workflow_run_id: actix_web::web::Path<WorkflowRunId>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> HttpResponse
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    let workflow_run_id = workflow_run_id.into_inner();
+    // Subscribe before reading the current state so no change between the 2 is missed
+    let updates = service.progress_updates();
+    let workflow_run = match service.read_one(&workflow_run_id).await {
+        Ok(inner) => inner,
+        Err(error) => return ApiResponse::<()>::error(error, format.f).respond_to(&req),
+    };
+    let initial = WorkflowRunProgress {
+        workflow_run_id,
+        status: workflow_run.status,
+        progress: workflow_run.progress,
+    };
+    let first_event = progress_event(&initial);
+    let is_done = initial.status.is_terminal();
+    let updates = stream::unfold(
+        (updates, is_done),
+        move |(mut updates, is_done)| async move {
+            if is_done {
+                return None;
+            }
+            loop {
+                match updates.recv().await {
+                    Ok(progress) if progress.workflow_run_id == workflow_run_id => {
+                        let is_done = progress.status.is_terminal();
+                        return Some((progress_event(&progress), (updates, is_done)));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Workflow run progress stream skipped {skipped} updates");
+                    }
+                    Err(RecvError::Closed) => {
+                        error!("Workflow run progress stream closed");
+                        return None;
+                    }
+                }
+            }
+        },
+    );
+    let events = stream::once(async move { first_event })
+        .chain(updates)
+        .map(Ok::<_, actix_web::Error>);
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
 /// API endpoint to fetch the specified workflow run by the `workflow_run_id`. Returns a single
 /// [WorkflowRun] if the run can be found
 async fn workflow_run_tasks<R>(
@@ -99,6 +340,47 @@ where
     }
 }
 
+/// API endpoint to fetch a page of workflow runs after the workflow run referenced by the `after`
+/// cursor. Returns a [CursorPage] of [WorkflowRun] records ordered by `workflow_run_id`.
+async fn workflow_runs_page<R>(
+    page: actix_web::web::Query<CursorPagination>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<CursorPage<WorkflowRun>>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    match service.read_many_after(&page).await {
+        Ok(workflow_runs) => ApiResponse::success(workflow_runs, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to fetch the history of workflow runs matching the filter provided as query
+/// parameters. Returns a list of [WorkflowRunHistory] records ordered by the [Sort] provided as
+/// query parameters (`status`, `run_start` or `progress`), defaulting to the most recent start
+/// first.
+async fn workflow_runs_history<R>(
+    history_query: actix_web::web::Query<WorkflowRunHistoryQuery>,
+    sort: actix_web::web::Query<Sort>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<WorkflowRunHistory>>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    let filter = match WorkflowRunFilter::try_from(history_query.into_inner()) {
+        Ok(filter) => filter,
+        Err(error) => return ApiResponse::error(error, format.f),
+    };
+    match service.read_history(&filter, &sort).await {
+        Ok(workflow_runs) => ApiResponse::success(workflow_runs, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
 /// API endpoint to initialize a workflow run for the specified `workflow_id`. Returns the new
 /// [WorkflowRun] if the `workflow_id` is valid and the init does not fail.
 async fn init_workflow_run<R>(
@@ -117,7 +399,9 @@ where
 }
 
 /// API endpoint to cancel the workflow run specified by the `workflow_run_id`. Returns the
-/// canceled [WorkflowRun] if the operation was a success.
+/// canceled [WorkflowRun] if the operation was a success. The route is wrapped by
+/// [RequirePrivilege] so only users with the [RoleName::ManageWorkflowEngine] privilege reach
+/// this handler.
 async fn cancel_workflow_run<R>(
     workflow_run_id: actix_web::web::Path<WorkflowRunId>,
     service: actix_web::web::Data<R>,
@@ -133,6 +417,30 @@ where
     }
 }
 
+/// API endpoint to cancel the workflow run specified by the `workflow_run_id`, recording the
+/// optional reason within the `api_request`. Returns the canceled [WorkflowRun] if the operation
+/// was a success. The route is wrapped by [RequirePrivilege] so only users with the
+/// [RoleName::ManageWorkflowEngine] privilege reach this handler.
+async fn cancel_workflow_run_with_reason<R>(
+    workflow_run_id: actix_web::web::Path<WorkflowRunId>,
+    api_request: ApiRequest<WorkflowRunCancelRequest>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<WorkflowRun>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    let request = api_request.into_inner();
+    match service
+        .cancel_with_reason(&workflow_run_id, request.reason())
+        .await
+    {
+        Ok(workflow_run) => ApiResponse::success(workflow_run, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
 /// API endpoint to set a workflow run specified by `workflow_run_id` as `Scheduled`. Returns the
 /// [WorkflowRun] if the operation was successful
 async fn schedule_workflow_run<R>(
@@ -150,6 +458,49 @@ where
     }
 }
 
+/// API endpoint to set a workflow run specified by `workflow_run_id` as `Scheduled`, assigned to
+/// the executor specified by `executor_id`. Returns the [WorkflowRun] if the operation was
+/// successful
+async fn schedule_workflow_run_with_executor<R>(
+    path: actix_web::web::Path<(WorkflowRunId, ExecutorId)>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<WorkflowRun>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    let (workflow_run_id, executor_id) = path.into_inner();
+    match service
+        .schedule_with_executor(&workflow_run_id, &executor_id)
+        .await
+    {
+        Ok(workflow_run) => ApiResponse::success(workflow_run, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to set a workflow run specified by `workflow_run_id` as `Scheduled` with the
+/// specified `priority`. Returns the [WorkflowRun] if the operation was successful
+async fn schedule_workflow_run_with_priority<R>(
+    path: actix_web::web::Path<(WorkflowRunId, i16)>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<WorkflowRun>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    let (workflow_run_id, priority) = path.into_inner();
+    match service
+        .schedule_with_priority(&workflow_run_id, priority)
+        .await
+    {
+        Ok(workflow_run) => ApiResponse::success(workflow_run, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
 /// API endpoint to restart a workflow run specified by `workflow_run_id`. Returns the
 /// [WorkflowRun] if the operation was successful
 async fn restart_workflow_run<R>(
@@ -167,6 +518,113 @@ where
     }
 }
 
+/// API endpoint to recover 'Scheduled' and 'Running' workflow runs whose owning executor is no
+/// longer active. Returns the ids of the recovered workflow runs
+async fn recover_orphaned_workflow_runs<R>(
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<WorkflowRunId>>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    match service.recover_orphaned().await {
+        Ok(workflow_run_ids) => ApiResponse::success(workflow_run_ids, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to pause the running workflow run specified by `workflow_run_id` once its running
+/// tasks are done. Returns the paused [WorkflowRun] if the operation was successful
+async fn pause_workflow_run<R>(
+    workflow_run_id: actix_web::web::Path<WorkflowRunId>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<WorkflowRun>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    match service.pause(&workflow_run_id).await {
+        Ok(workflow_run) => ApiResponse::success(workflow_run, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to resume the paused workflow run specified by `workflow_run_id`. Returns the
+/// rescheduled [WorkflowRun] if the operation was successful
+async fn resume_workflow_run<R>(
+    workflow_run_id: actix_web::web::Path<WorkflowRunId>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<WorkflowRun>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    match service.resume(&workflow_run_id).await {
+        Ok(workflow_run) => ApiResponse::success(workflow_run, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to create a new workflow run using the same workflow and task parameters as the
+/// workflow run specified by `workflow_run_id`. Returns the new [WorkflowRun] if the operation was
+/// successful
+async fn clone_workflow_run<R>(
+    workflow_run_id: actix_web::web::Path<WorkflowRunId>,
+    service: actix_web::web::Data<R>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<WorkflowRun>
+where
+    R: WorkflowRunsService,
+{
+    let format = query.into_inner();
+    match service.clone_run(&workflow_run_id).await {
+        Ok(workflow_run) => ApiResponse::success(workflow_run, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to fetch the full detail of the task queue entry specified by the
+/// `workflow_run_id` and `task_order`. Returns a single [TaskDetail] if the task can be found
+async fn task_queue_detail<T>(
+    path: actix_web::web::Path<(WorkflowRunId, i32)>,
+    service: actix_web::web::Data<T>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<TaskDetail>
+where
+    T: TaskQueueService,
+{
+    let format = query.into_inner();
+    let (workflow_run_id, task_order) = path.into_inner();
+    let request = TaskQueueRequest::new(workflow_run_id, task_order);
+    match service.read_task_detail(&request).await {
+        Ok(task_detail) => ApiResponse::success(task_detail, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
+/// API endpoint to fetch the task rules of the task queue entry specified by the
+/// `workflow_run_id` and `task_order`. Returns the [TaskRule] entries in the order they were
+/// checked, or an empty list if the task has not checked any rules yet
+async fn task_queue_rules<T>(
+    path: actix_web::web::Path<(WorkflowRunId, i32)>,
+    service: actix_web::web::Data<T>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<Vec<TaskRule>>
+where
+    T: TaskQueueService,
+{
+    let format = query.into_inner();
+    let (workflow_run_id, task_order) = path.into_inner();
+    let request = TaskQueueRequest::new(workflow_run_id, task_order);
+    match service.read_task_rules(&request).await {
+        Ok(rules) => ApiResponse::success(rules, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
 /// API endpoint to retry the task queue entry specified by `request`
 async fn task_queue_retry<T>(
     api_request: ApiRequest<TaskQueueRequest>,
@@ -187,6 +645,23 @@ where
     }
 }
 
+/// API endpoint to retry every failed or rule broken task queue entry of the workflow run
+/// specified by `workflow_run_id`. Responds with the number of tasks retried.
+async fn task_queue_retry_all<T>(
+    workflow_run_id: actix_web::web::Path<WorkflowRunId>,
+    service: actix_web::web::Data<T>,
+    query: actix_web::web::Query<QueryApiFormat>,
+) -> ApiResponse<usize>
+where
+    T: TaskQueueService,
+{
+    let format = query.into_inner();
+    match service.retry_all_failed(&workflow_run_id).await {
+        Ok(retried_count) => ApiResponse::success(retried_count, format.f),
+        Err(error) => ApiResponse::error(error, format.f),
+    }
+}
+
 /// API endpoint complete the task queue entry specified by `request`
 async fn task_queue_complete<T>(
     api_request: ApiRequest<TaskQueueRequest>,
@@ -208,3 +683,94 @@ where
         Err(error) => ApiResponse::error(error, format.f),
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use actix_web::{
+        http::StatusCode,
+        test::{call_service, init_service, TestRequest},
+        web::Data,
+        App,
+    };
+    use common::{error::EmResult, test::stub_api_success};
+    use rstest::rstest;
+    use sqlx::PgPool;
+    use users::{client::UsersApiClient, data::role::Role};
+
+    use super::workflow_runs_service;
+    use crate::{
+        database::test::{cleanup_workflow, create_test_workflow, database},
+        workflow::service::postgres::PgWorkflowsService,
+        workflow_run::{
+            data::WorkflowRunStatus,
+            service::{postgres::PgWorkflowRunsService, WorkflowRunsService},
+        },
+    };
+
+    #[rstest]
+    #[case::cancel("")]
+    #[case::cancel_with_reason("/reason")]
+    #[tokio::test]
+    async fn cancel_workflow_run_should_respond_forbidden_when_user_missing_privilege(
+        database: PgPool,
+        #[case] suffix: &str,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "cancel_forbidden", 1).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+        let workflow_run = runs_service.initialize(&workflow_id).await?;
+        let users_url = stub_api_success(Vec::<Role>::new()).await?;
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(UsersApiClient::new(format!(
+                    "{users_url}/api/v1"
+                ))))
+                .app_data(Data::new(runs_service.clone()))
+                .service(workflow_runs_service::<PgWorkflowRunsService>()),
+        )
+        .await;
+        let request = TestRequest::post()
+            .uri(&format!(
+                "/workflow-runs/cancel/{}{suffix}?f=json",
+                workflow_run.workflow_run_id
+            ))
+            .insert_header((
+                "Authorization",
+                "Bearer be4c1ef7-771a-4580-b0dd-ff137c64ab48",
+            ))
+            .set_payload(r#"{"reason":"forbidden"}"#)
+            .insert_header(("Content-Type", "application/json"))
+            .to_request();
+
+        let response = call_service(&app, request).await;
+        let action = runs_service.read_one(&workflow_run.workflow_run_id).await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let workflow_run = action?;
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(workflow_run.status == WorkflowRunStatus::Waiting);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn cancel_workflow_run_should_respond_unauthorized_when_bearer_missing(database: PgPool) {
+        let workflow_service = PgWorkflowsService::new(&database);
+        let runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(UsersApiClient::new("http://127.0.0.1:1/api/v1")))
+                .app_data(Data::new(runs_service))
+                .service(workflow_runs_service::<PgWorkflowRunsService>()),
+        )
+        .await;
+        let request = TestRequest::post()
+            .uri("/workflow-runs/cancel/1?f=json")
+            .to_request();
+
+        let response = call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,14 +1,24 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::HashMap, fmt::Display, str::FromStr, time::Duration};
 
 use chrono::NaiveDateTime;
-use common::error::EmError;
-use serde::{Deserialize, Serialize};
+use common::{
+    api::id::{deserialize_id, parse_id},
+    database::listener::NotificationPayload,
+    error::{EmError, EmResult},
+};
+use jsonschema::JSONSchema;
+use log::warn;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
+use sqlx::{
+    postgres::{types::PgInterval, PgRow},
+    Row,
+};
 
-use crate::workflow::data::TaskId;
+use crate::workflow::data::{TaskId, WorkflowId, WorkflowTask};
 
 /// Status of a workflow run as found in the database as a simple Postgresql enum type
-#[derive(sqlx::Type, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(sqlx::Type, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
 #[sqlx(type_name = "workflow_run_status")]
 pub enum WorkflowRunStatus {
     Waiting,
@@ -35,6 +45,37 @@ impl Display for WorkflowRunStatus {
     }
 }
 
+impl WorkflowRunStatus {
+    /// True if the workflow run has finished and will not progress any further without being
+    /// restarted
+    pub const fn is_terminal(&self) -> bool {
+        match self {
+            Self::Failed | Self::Complete | Self::Canceled => true,
+            Self::Waiting | Self::Scheduled | Self::Running | Self::Paused => false,
+        }
+    }
+}
+
+impl FromStr for WorkflowRunStatus {
+    type Err = EmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Waiting" => Ok(Self::Waiting),
+            "Scheduled" => Ok(Self::Scheduled),
+            "Running" => Ok(Self::Running),
+            "Paused" => Ok(Self::Paused),
+            "Failed" => Ok(Self::Failed),
+            "Complete" => Ok(Self::Complete),
+            "Canceled" => Ok(Self::Canceled),
+            _ => Err(EmError::InvalidRequest {
+                request: format!("WorkflowRunStatus `{s}`"),
+                reason: "Unknown status".to_owned(),
+            }),
+        }
+    }
+}
+
 /// Task information for entries under a [WorkflowRun]
 #[derive(Serialize, Deserialize)]
 pub struct WorkflowRunTask {
@@ -60,6 +101,12 @@ pub struct WorkflowRunTask {
     pub task_end: Option<NaiveDateTime>,
     /// Optional progress value passed back from the task executor
     pub progress: Option<i16>,
+    /// Optional list of the most recent log lines sent by the task executor
+    pub logs: Option<Vec<TaskLog>>,
+    /// Number of times the task has been retried during the workflow run
+    pub retry_count: i16,
+    /// Number of times a failed task run is automatically retried
+    pub max_retries: i16,
 }
 
 /// Workflow run data as fetched from `workflow.v_workflow_runs`
@@ -75,35 +122,408 @@ pub struct WorkflowRun {
     pub executor_id: Option<i64>,
     /// Optional Progress of the workflow run
     pub progress: Option<i16>,
+    /// Priority of the workflow run when claimed by an executor. Higher values are claimed first
+    pub priority: i16,
+    /// Optional reason provided when the workflow run was canceled. Cleared on restart
+    pub cancel_reason: Option<String>,
     /// Tasks that are part of this workflow run
     pub tasks: Vec<WorkflowRunTask>,
 }
 
+/// Header fields of a workflow run as fetched from `workflow_run.workflow_runs`. Same as
+/// [WorkflowRun] without the `tasks` so list views do not pay for the task aggregation.
+#[derive(sqlx::FromRow, Serialize, Deserialize)]
+pub struct WorkflowRunSummary {
+    /// ID of the workflow run
+    pub workflow_run_id: WorkflowRunId,
+    /// ID of the workflow that is executed for this workflow run
+    pub workflow_id: i64,
+    /// Status of the workflow run
+    pub status: WorkflowRunStatus,
+    /// Optional ID of the executor that owns this workflow run, [None] if not currently running
+    pub executor_id: Option<i64>,
+    /// Optional Progress of the workflow run
+    pub progress: Option<i16>,
+    /// Priority of the workflow run when claimed by an executor. Higher values are claimed first
+    pub priority: i16,
+    /// Optional reason provided when the workflow run was canceled. Cleared on restart
+    pub cancel_reason: Option<String>,
+}
+
+/// Progress update of a workflow run. Sent as a JSON object through the `wr_progress_updates`
+/// channel whenever the progress or status of any workflow run changes, e.g.
+/// `{"workflow_run_id":1,"status":"Running","progress":50}`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WorkflowRunProgress {
+    /// ID of the workflow run
+    pub workflow_run_id: WorkflowRunId,
+    /// Status of the workflow run
+    pub status: WorkflowRunStatus,
+    /// Optional Progress of the workflow run
+    pub progress: Option<i16>,
+}
+
+/// Container for a notification message with the progress of a workflow run. If the inner
+/// content is [None] then the message was not valid (or of an unknown [NotificationPayload]
+/// version) and should be ignored.
+pub struct WorkflowRunProgressMessage(pub Option<WorkflowRunProgress>);
+
+impl From<&str> for WorkflowRunProgressMessage {
+    fn from(s: &str) -> Self {
+        let body = match NotificationPayload::decode(s) {
+            Ok(payload) => payload.body(),
+            Err(error) => {
+                warn!("{error}");
+                return Self(None);
+            }
+        };
+        match serde_json::from_str(body) {
+            Ok(progress) => Self(Some(progress)),
+            Err(error) => {
+                warn!("Cannot parse workflow run progress from `{s}`. {error}");
+                Self(None)
+            }
+        }
+    }
+}
+
+/// Container for a notification message with the new status of a workflow run. Sent through the
+/// `wr_status_{workflow_run_id}` channel as the name of the status whenever the status of the
+/// workflow run changes, e.g. `v1:Running`. If the inner content is [None] then the message was
+/// not valid (or of an unknown [NotificationPayload] version) and should be ignored.
+pub struct WorkflowRunStatusMessage(pub Option<WorkflowRunStatus>);
+
+impl From<&str> for WorkflowRunStatusMessage {
+    fn from(s: &str) -> Self {
+        let body = match NotificationPayload::decode(s) {
+            Ok(payload) => payload.body(),
+            Err(error) => {
+                warn!("{error}");
+                return Self(None);
+            }
+        };
+        match body.parse() {
+            Ok(status) => Self(Some(status)),
+            Err(error) => {
+                warn!("Cannot parse workflow run status from `{s}`. {error}");
+                Self(None)
+            }
+        }
+    }
+}
+
+/// Past workflow run data as fetched from `workflow_run.v_workflow_run_history`
+#[derive(sqlx::FromRow, Serialize, Deserialize)]
+pub struct WorkflowRunHistory {
+    /// ID of the workflow run
+    pub workflow_run_id: WorkflowRunId,
+    /// ID of the workflow that is executed for this workflow run
+    pub workflow_id: WorkflowId,
+    /// Name of the workflow that is executed for this workflow run
+    pub workflow_name: String,
+    /// Status of the workflow run
+    pub status: WorkflowRunStatus,
+    /// Optional ID of the executor that owns this workflow run, [None] if not currently running
+    pub executor_id: Option<i64>,
+    /// Optional Progress of the workflow run
+    pub progress: Option<i16>,
+    /// Priority of the workflow run when claimed by an executor
+    pub priority: i16,
+    /// Start of the last run of the workflow run
+    pub run_start: NaiveDateTime,
+    /// End of the last task executed within the workflow run, [None] if no task has finished
+    pub run_end: Option<NaiveDateTime>,
+}
+
+/// Filter applied when reading [WorkflowRunHistory] records. Only workflow runs started within the
+/// `started_between` range (inclusive) are included. Optionally, the records can be limited to a
+/// single `workflow_id` and a set of statuses (an empty `status` list includes all statuses).
+pub struct WorkflowRunFilter {
+    /// Optional ID of the workflow that the workflow runs must execute
+    pub workflow_id: Option<WorkflowId>,
+    /// Statuses that the workflow runs must have. Empty to include all statuses
+    pub status: Vec<WorkflowRunStatus>,
+    /// Range that the start of the workflow runs must fall within
+    pub started_between: (NaiveDateTime, NaiveDateTime),
+}
+
+/// Result of validating a single task of a workflow without running the task. Part of a
+/// [ValidationReport].
+#[derive(Serialize, Deserialize)]
+pub struct TaskValidation {
+    /// Order of the task within the workflow
+    pub task_order: i32,
+    /// ID of the task validated
+    pub task_id: TaskId,
+    /// Name of the task validated
+    pub name: String,
+    /// Full url that is called when the task is run
+    pub url: String,
+    /// True if the task passed every check
+    pub passed: bool,
+    /// Description of every check that failed for the task. Empty if the task `passed`
+    pub errors: Vec<String>,
+}
+
+impl TaskValidation {
+    /// Create a new [TaskValidation] for the workflow `task` with the failed check `errors`
+    pub fn new(task: &WorkflowTask, errors: Vec<String>) -> Self {
+        Self {
+            task_order: task.task_order,
+            task_id: task.task_id,
+            name: task.name.clone(),
+            url: task.url.clone(),
+            passed: errors.is_empty(),
+            errors,
+        }
+    }
+}
+
+/// Dry-run validation of every task within a workflow, as returned by
+/// [WorkflowRunsService::validate_workflow][crate::workflow_run::service::WorkflowRunsService::validate_workflow]
+#[derive(Serialize, Deserialize)]
+pub struct ValidationReport {
+    /// ID of the workflow validated
+    pub workflow_id: WorkflowId,
+    /// True if every task of the workflow passed validation
+    pub is_valid: bool,
+    /// Validation result of each task, ordered by `task_order`
+    pub tasks: Vec<TaskValidation>,
+}
+
+impl ValidationReport {
+    /// Create a new [ValidationReport] for the `workflow_id` from the results of each task
+    pub fn new(workflow_id: WorkflowId, mut tasks: Vec<TaskValidation>) -> Self {
+        tasks.sort_by_key(|task| task.task_order);
+        Self {
+            workflow_id,
+            is_valid: tasks.iter().all(|task| task.passed),
+            tasks,
+        }
+    }
+}
+
+/// Milliseconds elapsed from `start` to `end`. Clamped to zero if the clocks used to record the
+/// timestamps disagree and `end` comes first.
+fn elapsed_milliseconds(start: &NaiveDateTime, end: &NaiveDateTime) -> i64 {
+    (*end - *start).num_milliseconds().max(0)
+}
+
+impl WorkflowRunTask {
+    /// Duration of the task run in milliseconds. Tasks that have started but not ended (i.e. are
+    /// still running) are measured up to `now`. Returns [None] if the task has not started.
+    pub fn duration_milliseconds(&self, now: &NaiveDateTime) -> Option<i64> {
+        let task_start = self.task_start.as_ref()?;
+        Some(elapsed_milliseconds(
+            task_start,
+            self.task_end.as_ref().unwrap_or(now),
+        ))
+    }
+}
+
+/// Timing of a single task within a [RunTimingSummary]
+#[derive(Serialize, Deserialize)]
+pub struct TaskTiming {
+    /// Order of the task within the workflow run
+    pub task_order: i32,
+    /// ID of the task executed
+    pub task_id: TaskId,
+    /// Name of the task
+    pub name: String,
+    /// Status of the task
+    pub task_status: TaskStatus,
+    /// Duration of the task run in milliseconds, [None] if the task has not started. Running
+    /// tasks are measured up to the time the summary was created
+    pub duration_ms: Option<i64>,
+}
+
+/// Aggregate timing of a workflow run, as returned by
+/// [WorkflowRunsService::read_timing][crate::workflow_run::service::WorkflowRunsService::read_timing].
+/// Durations of workflow runs or tasks that are still in progress are measured up to the
+/// `computed_at` time of the summary.
+#[derive(Serialize, Deserialize)]
+pub struct RunTimingSummary {
+    /// ID of the workflow run
+    pub workflow_run_id: WorkflowRunId,
+    /// Status of the workflow run
+    pub status: WorkflowRunStatus,
+    /// Time that the summary was computed, used as the end of any in progress timing
+    pub computed_at: NaiveDateTime,
+    /// Time that the workflow run was last scheduled, [None] if never scheduled
+    pub scheduled_at: Option<NaiveDateTime>,
+    /// Start of the last run of the workflow run, [None] if never started
+    pub run_start: Option<NaiveDateTime>,
+    /// End of the last task executed, [None] if no task has ended
+    pub run_end: Option<NaiveDateTime>,
+    /// Milliseconds the workflow run waited between being scheduled and being started by an
+    /// executor. [None] if the workflow run has not been scheduled
+    pub queue_wait_ms: Option<i64>,
+    /// Milliseconds between the start and end of the workflow run. [None] if the workflow run has
+    /// not started or finished without any task ending
+    pub run_duration_ms: Option<i64>,
+    /// Order of the task with the longest duration, [None] if no task has started
+    pub slowest_task_order: Option<i32>,
+    /// Timing of each task within the workflow run, ordered by `task_order`
+    pub tasks: Vec<TaskTiming>,
+}
+
+impl RunTimingSummary {
+    /// Create a new [RunTimingSummary] for the `workflow_run` that was last scheduled at
+    /// `scheduled_at` and started at `run_start`. Timing that is still in progress is measured up
+    /// to `now`.
+    pub fn new(
+        workflow_run: WorkflowRun,
+        scheduled_at: Option<NaiveDateTime>,
+        run_start: Option<NaiveDateTime>,
+        now: NaiveDateTime,
+    ) -> Self {
+        let queue_wait_ms = match (&scheduled_at, &run_start) {
+            (Some(scheduled_at), Some(run_start)) if run_start >= scheduled_at => {
+                Some(elapsed_milliseconds(scheduled_at, run_start))
+            }
+            (Some(scheduled_at), _) if !workflow_run.status.is_terminal() => {
+                Some(elapsed_milliseconds(scheduled_at, &now))
+            }
+            _ => None,
+        };
+        let run_end = workflow_run
+            .tasks
+            .iter()
+            .filter_map(|task| task.task_end)
+            .max();
+        let run_duration_ms = match (&run_start, workflow_run.status.is_terminal()) {
+            (Some(run_start), false) => Some(elapsed_milliseconds(run_start, &now)),
+            (Some(run_start), true) => run_end
+                .as_ref()
+                .map(|run_end| elapsed_milliseconds(run_start, run_end)),
+            (None, _) => None,
+        };
+        let mut tasks: Vec<TaskTiming> = workflow_run
+            .tasks
+            .into_iter()
+            .map(|task| TaskTiming {
+                duration_ms: task.duration_milliseconds(&now),
+                task_order: task.task_order,
+                task_id: task.task_id,
+                name: task.name,
+                task_status: task.task_status,
+            })
+            .collect();
+        tasks.sort_by_key(|task| task.task_order);
+        let slowest_task_order = tasks
+            .iter()
+            .filter_map(|task| task.duration_ms.map(|duration| (duration, task.task_order)))
+            .max_by_key(|(duration, _)| *duration)
+            .map(|(_, task_order)| task_order);
+        Self {
+            workflow_run_id: workflow_run.workflow_run_id,
+            status: workflow_run.status,
+            computed_at: now,
+            scheduled_at,
+            run_start,
+            run_end,
+            queue_wait_ms,
+            run_duration_ms,
+            slowest_task_order,
+            tasks,
+        }
+    }
+}
+
+/// Query parameters accepted by the workflow run history endpoint. Converted into a
+/// [WorkflowRunFilter] where `status` is a comma separated list of [WorkflowRunStatus] values.
+#[derive(Deserialize)]
+pub struct WorkflowRunHistoryQuery {
+    /// Optional ID of the workflow that the workflow runs must execute
+    pub workflow_id: Option<WorkflowId>,
+    /// Optional comma separated list of statuses that the workflow runs must have
+    pub status: Option<String>,
+    /// Earliest start of the workflow runs
+    pub started_after: NaiveDateTime,
+    /// Latest start of the workflow runs
+    pub started_before: NaiveDateTime,
+}
+
+impl TryFrom<WorkflowRunHistoryQuery> for WorkflowRunFilter {
+    type Error = EmError;
+
+    fn try_from(value: WorkflowRunHistoryQuery) -> Result<Self, Self::Error> {
+        if value.started_after > value.started_before {
+            return Err(EmError::InvalidRequest {
+                request: "Workflow run history query".to_owned(),
+                reason: format!(
+                    "Invalid start range. `started_after` ({}) must not be after `started_before` \
+                     ({})",
+                    value.started_after, value.started_before
+                ),
+            });
+        }
+        let status = match value.status {
+            Some(status) => status
+                .split(',')
+                .map(str::trim)
+                .filter(|status| !status.is_empty())
+                .map(WorkflowRunStatus::from_str)
+                .collect::<EmResult<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            workflow_id: value.workflow_id,
+            status,
+            started_between: (value.started_after, value.started_before),
+        })
+    }
+}
+
 /// Workflow run data as fetched from the function `executor.all_executor_workflows`. Contains the
 /// `workflow_run_id`, `status` of the workflow run and `is_valid` to denote if the workflow run is
 /// valid when an [Executor][crate::executor::Executor] checks owned workflow runs.
-#[derive(sqlx::FromRow)]
+#[derive(sqlx::FromRow, Serialize, Deserialize)]
 pub struct ExecutorWorkflowRun {
     /// ID of the workflow run
-    pub(crate) workflow_run_id: WorkflowRunId,
+    pub workflow_run_id: WorkflowRunId,
     /// Status of the workflow run
-    pub(crate) status: WorkflowRunStatus,
+    pub status: WorkflowRunStatus,
     /// Flag indicating if the workflow run is valid. Valid workflow runs are when there are only
     /// `task_queue` records for the workflow run that are 'Waiting' or 'Complete'
-    pub(crate) is_valid: bool,
+    pub is_valid: bool,
 }
 
 /// Wrapper for a `workflow_run_id` value. Made to ensure data passed as the id of a workflow run is
 /// correct and not just any i64 value.
-#[derive(sqlx::Type, Eq, PartialEq, Hash, Clone, Copy, Serialize, Deserialize, Debug)]
+#[derive(sqlx::Type, Eq, PartialEq, Hash, Clone, Copy, Serialize, Debug)]
 #[sqlx(transparent)]
 pub struct WorkflowRunId(i64);
 
+impl WorkflowRunId {
+    /// Extract the inner [`i64`] value
+    pub const fn into_inner(self) -> i64 {
+        self.0
+    }
+}
+
 impl FromStr for WorkflowRunId {
     type Err = EmError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(s.parse::<i64>()?.into())
+        Ok(Self(parse_id("workflow_run_id", s)?))
+    }
+}
+
+impl TryFrom<&str> for WorkflowRunId {
+    type Error = EmError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl<'de> Deserialize<'de> for WorkflowRunId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_id("workflow_run_id", deserializer).map(Self)
     }
 }
 
@@ -149,6 +569,56 @@ impl Display for TaskStatus {
     }
 }
 
+impl FromStr for TaskStatus {
+    type Err = EmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Waiting" => Ok(Self::Waiting),
+            "Running" => Ok(Self::Running),
+            "Complete" => Ok(Self::Complete),
+            "Failed" => Ok(Self::Failed),
+            "Rule Broken" => Ok(Self::RuleBroken),
+            "Paused" => Ok(Self::Paused),
+            "Canceled" => Ok(Self::Canceled),
+            _ => Err(EmError::InvalidRequest {
+                request: format!("TaskStatus `{s}`"),
+                reason: "Unknown status".to_owned(),
+            }),
+        }
+    }
+}
+
+/// Query parameters accepted by the workflow run detail endpoint to limit the tasks included in
+/// the [WorkflowRun]. `task_status` is a comma separated list of [TaskStatus] values.
+#[derive(Deserialize)]
+pub struct WorkflowRunTasksQuery {
+    /// Optional comma separated list of statuses that the tasks of the workflow run must have
+    pub task_status: Option<String>,
+}
+
+impl WorkflowRunTasksQuery {
+    /// Parse the `task_status` query parameter into the list of [TaskStatus] values to filter the
+    /// tasks by. Returns [None] when the parameter is missing or empty so all tasks are included.
+    /// # Errors
+    /// This function will return an error if any of the statuses is not a valid [TaskStatus]
+    pub fn task_status_filter(&self) -> EmResult<Option<Vec<TaskStatus>>> {
+        let Some(task_status) = &self.task_status else {
+            return Ok(None);
+        };
+        let statuses = task_status
+            .split(',')
+            .map(str::trim)
+            .filter(|status| !status.is_empty())
+            .map(TaskStatus::from_str)
+            .collect::<EmResult<Vec<_>>>()?;
+        if statuses.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(statuses))
+    }
+}
+
 /// Check performed during a task run to validate the current state of a task or the system that the
 /// task is operating on. Rules must always have a non-empty and unique `name` per task, as well as
 /// a `failed` status and optional `message` to provide details of what the rule checked.
@@ -162,6 +632,23 @@ pub struct TaskRule {
     pub(crate) message: Option<String>,
 }
 
+impl TaskRule {
+    /// Descriptive name of the task rule
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// True if the task rule failed during the check
+    pub const fn failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Optional message included in the task rule completion
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
 impl Display for TaskRule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -174,8 +661,49 @@ impl Display for TaskRule {
     }
 }
 
+/// Severity of a log line sent by a remote task as found in the database as a simple Postgresql
+/// enum type
+#[derive(sqlx::Type, Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[sqlx(type_name = "task_log_level")]
+pub enum TaskLogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Display for TaskLogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self {
+            Self::Debug => "Debug",
+            Self::Info => "Info",
+            Self::Warn => "Warn",
+            Self::Error => "Error",
+        };
+        write!(f, "{val}")
+    }
+}
+
+/// Log line sent by a remote task during execution. Only the most recent lines of each task are
+/// kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskLog {
+    /// Severity of the log line
+    pub level: TaskLogLevel,
+    /// Contents of the log line
+    pub message: String,
+    /// Timestamp when the log line was received
+    pub logged_at: NaiveDateTime,
+}
+
+impl Display for TaskLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}] {}", self.logged_at, self.level, self.message)
+    }
+}
+
 /// Represents a row from the `task.task_queue` table
-#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TaskQueueRecord {
     /// ID of the Workflow run that owns this task queue record
     pub(crate) workflow_run_id: WorkflowRunId,
@@ -189,10 +717,270 @@ pub struct TaskQueueRecord {
     pub(crate) parameters: Option<Value>,
     /// Url to be called as per the task execution
     pub(crate) url: String,
+    /// Maximum duration of the task run as defined by the task. [None] if the task run is not
+    /// bounded by a timeout. Not sent to the remote task.
+    #[serde(skip)]
+    pub(crate) timeout: Option<Duration>,
+    /// JSON Schema that the `parameters` must satisfy as defined by the task. [None] if the
+    /// parameters are not validated. Not sent to the remote task.
+    #[serde(skip)]
+    pub(crate) parameters_schema: Option<Value>,
+    /// Number of times the task has been retried during the workflow run. Not sent to the remote
+    /// task.
+    #[serde(skip)]
+    pub(crate) retry_count: i16,
+    /// Number of times a failed task run is automatically retried as defined by the task. Not sent
+    /// to the remote task.
+    #[serde(skip)]
+    pub(crate) max_retries: i16,
+    /// Structured output of the complete tasks within the same workflow run, keyed by task order.
+    /// [None] if no previous task has produced structured output. Only used to render the
+    /// `parameters` so it is not sent to the remote task.
+    #[serde(skip)]
+    pub(crate) task_outputs: Option<Value>,
+}
+
+impl TaskQueueRecord {
+    /// Validate the `parameters` of this record against the task's `parameters_schema` (if any).
+    /// Missing parameters are validated as a JSON `null` value.
+    /// # Errors
+    /// This function will return an [EmError::InvalidTaskParameters] if the schema cannot be
+    /// compiled or the parameters violate the schema
+    pub(crate) fn validate_parameters(&self) -> EmResult<()> {
+        validate_parameters_schema(self.parameters.as_ref(), self.parameters_schema.as_ref())
+    }
+
+    /// Replace every templated value within the `parameters` of this record with the matching
+    /// value from the `task_outputs`. A templated value is a string of the form
+    /// `{{ tasks.<task_order>.<key>... }}` where the keys (or array indexes) navigate the output of
+    /// the task. The matched value replaces the whole string so the output type is preserved.
+    /// # Errors
+    /// This function will return an [EmError::InvalidTaskParameters] if a templated value cannot
+    /// be resolved from the `task_outputs`
+    pub(crate) fn render_parameters(&mut self) -> EmResult<()> {
+        if let Some(parameters) = self.parameters.as_mut() {
+            render_template_values(parameters, self.task_outputs.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Replace every `${NAME}` template within the `url` of this record with the value of the
+    /// matching variable in `variables`. Urls without templates are left untouched.
+    /// # Errors
+    /// This function will return an [EmError::InvalidTaskUrl] if a template is malformed or
+    /// references a variable that is not defined
+    pub(crate) fn expand_url(&mut self, variables: &TaskUrlVariables) -> EmResult<()> {
+        if self.url.contains("${") {
+            self.url = variables.expand(&self.url)?;
+        }
+        Ok(())
+    }
+}
+
+/// Prefix of the environment variables loaded by [TaskUrlVariables::from_env]
+pub const TASK_URL_VARIABLE_PREFIX: &str = "WE_TASK_URL_";
+
+/// Variables available to `${NAME}` templates within task urls so the same task definitions can be
+/// run against different environments (e.g. `${TASK_HOST}/run/foo`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskUrlVariables(HashMap<String, String>);
+
+impl TaskUrlVariables {
+    /// Create a new [TaskUrlVariables] from the environment. Every environment variable starting
+    /// with [TASK_URL_VARIABLE_PREFIX] is available using the remainder of its name, e.g.
+    /// `WE_TASK_URL_TASK_HOST` is referenced as `${TASK_HOST}`. Only prefixed variables are
+    /// loaded so unrelated values (such as credentials) cannot leak into task urls.
+    pub fn from_env() -> Self {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Create a new [TaskUrlVariables] from the environment style `vars`, keeping the entries
+    /// described in [TaskUrlVariables::from_env]
+    fn from_vars<I>(vars: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        Self(
+            vars.into_iter()
+                .filter_map(|(key, value)| {
+                    let name = key.strip_prefix(TASK_URL_VARIABLE_PREFIX)?;
+                    (!name.is_empty()).then(|| (name.to_owned(), value))
+                })
+                .collect(),
+        )
+    }
+
+    /// Define (or replace) the variable `name` with the specified `value`
+    pub fn with_variable<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.0.insert(name.into(), value.into());
+        self
+    }
+
+    /// Expand every `${NAME}` template within `url` using the defined variables
+    /// # Errors
+    /// This function will return an [EmError::InvalidTaskUrl] if a template is not closed, has an
+    /// empty name or references a variable that is not defined
+    pub fn expand(&self, url: &str) -> EmResult<String> {
+        let mut expanded = String::with_capacity(url.len());
+        let mut rest = url;
+        while let Some((before, template)) = rest.split_once("${") {
+            expanded.push_str(before);
+            let Some((name, after)) = template.split_once('}') else {
+                return Err(EmError::InvalidTaskUrl(format!(
+                    "Unterminated variable template in '{url}'"
+                )));
+            };
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(EmError::InvalidTaskUrl(format!(
+                    "Empty variable template in '{url}'"
+                )));
+            }
+            let Some(value) = self.0.get(name) else {
+                return Err(EmError::InvalidTaskUrl(format!(
+                    "Variable '{name}' referenced in '{url}' is not defined"
+                )));
+            };
+            expanded.push_str(value);
+            rest = after;
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+}
+
+/// Recursively replace the templated string values within `value` using the `task_outputs`. See
+/// [TaskQueueRecord::render_parameters] for the expected template format.
+/// # Errors
+/// This function will return an [EmError::InvalidTaskParameters] if a templated value cannot be
+/// resolved from the `task_outputs`
+fn render_template_values(value: &mut Value, task_outputs: Option<&Value>) -> EmResult<()> {
+    match value {
+        Value::String(text) => {
+            let Some(path) = template_path(text) else {
+                return Ok(());
+            };
+            let pointer = format!("/{}", path.replace('.', "/"));
+            let Some(output) = task_outputs.and_then(|outputs| outputs.pointer(&pointer)) else {
+                return Err(EmError::InvalidTaskParameters(format!(
+                    "Could not resolve task output for template '{text}'"
+                )));
+            };
+            *value = output.clone();
+        }
+        Value::Array(values) => {
+            for value in values {
+                render_template_values(value, task_outputs)?;
+            }
+        }
+        Value::Object(map) => {
+            for value in map.values_mut() {
+                render_template_values(value, task_outputs)?;
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+    Ok(())
+}
+
+/// Extract the path of a `{{ tasks.<path> }}` template from `text`. Returns [None] if the text is
+/// not a template.
+fn template_path(text: &str) -> Option<&str> {
+    let path = text
+        .trim()
+        .strip_prefix("{{")?
+        .strip_suffix("}}")?
+        .trim()
+        .strip_prefix("tasks.")?;
+    if path.is_empty() {
+        return None;
+    }
+    Some(path)
+}
+
+/// Validate the task `parameters` against the task's `parameters_schema` (if any). Missing
+/// parameters are validated as a JSON `null` value.
+/// # Errors
+/// This function will return an [EmError::InvalidTaskParameters] if the schema cannot be compiled
+/// or the parameters violate the schema
+pub(crate) fn validate_parameters_schema(
+    parameters: Option<&Value>,
+    parameters_schema: Option<&Value>,
+) -> EmResult<()> {
+    let Some(schema) = parameters_schema else {
+        return Ok(());
+    };
+    let compiled_schema = JSONSchema::compile(schema).map_err(|error| {
+        EmError::InvalidTaskParameters(format!("Task parameters schema is not valid. {error}"))
+    })?;
+    let parameters = parameters.unwrap_or(&Value::Null);
+    if let Err(errors) = compiled_schema.validate(parameters) {
+        let violations: Vec<String> = errors
+            .map(|error| format!("{}: {error}", error.instance_path))
+            .collect();
+        return Err(EmError::InvalidTaskParameters(violations.join("\n")));
+    }
+    Ok(())
+}
+
+impl<'r> sqlx::FromRow<'r, PgRow> for TaskQueueRecord {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let timeout: Option<PgInterval> = row.try_get("timeout")?;
+        Ok(Self {
+            workflow_run_id: row.try_get("workflow_run_id")?,
+            task_order: row.try_get("task_order")?,
+            task_id: row.try_get("task_id")?,
+            status: row.try_get("status")?,
+            parameters: row.try_get("parameters")?,
+            url: row.try_get("url")?,
+            timeout: timeout.as_ref().map(interval_to_duration),
+            parameters_schema: row.try_get("parameters_schema")?,
+            retry_count: row.try_get("retry_count")?,
+            max_retries: row.try_get("max_retries")?,
+            task_outputs: row.try_get("task_outputs")?,
+        })
+    }
+}
+
+/// Convert a [PgInterval] into a [Duration]. Months are treated as 30 days and negative intervals
+/// are clamped to zero.
+fn interval_to_duration(interval: &PgInterval) -> Duration {
+    let days = i64::from(interval.months) * 30 + i64::from(interval.days);
+    let microseconds = days
+        .saturating_mul(86_400_000_000)
+        .saturating_add(interval.microseconds);
+    Duration::from_micros(u64::try_from(microseconds).unwrap_or_default())
+}
+
+/// API request body when canceling a workflow run with an optional `reason`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WorkflowRunCancelRequest {
+    /// Optional reason the workflow run is being canceled
+    pub(crate) reason: Option<String>,
+}
+
+impl WorkflowRunCancelRequest {
+    /// Create a new [WorkflowRunCancelRequest] with the optional `reason`
+    pub const fn new(reason: Option<String>) -> Self {
+        Self { reason }
+    }
+
+    /// Trimmed cancel reason of the request. Returns [None] if no reason was provided or the reason
+    /// is blank.
+    pub fn reason(&self) -> Option<&str> {
+        self.reason
+            .as_deref()
+            .map(str::trim)
+            .filter(|reason| !reason.is_empty())
+    }
 }
 
 /// Container for the data required to fetch/update a single `task.task_queue` record
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct TaskQueueRequest {
     /// ID of the  workflow run to be accessed
     pub(crate) workflow_run_id: WorkflowRunId,
@@ -200,17 +988,609 @@ pub struct TaskQueueRequest {
     pub(crate) task_order: i32,
 }
 
+impl TaskQueueRequest {
+    /// Create a new [TaskQueueRequest] for the task at `task_order` within the workflow run
+    pub const fn new(workflow_run_id: WorkflowRunId, task_order: i32) -> Self {
+        Self {
+            workflow_run_id,
+            task_order,
+        }
+    }
+}
+
+/// Full detail of a single task queue record as fetched from `workflow_run.v_task_queue_detail`.
+/// Contains the accumulated rules, output and logs of the task for drill-down views of a single
+/// task.
+#[derive(sqlx::FromRow, Serialize, Deserialize)]
+pub struct TaskDetail {
+    /// ID of the workflow run that owns this task queue record
+    pub workflow_run_id: WorkflowRunId,
+    /// Order within the workflow run
+    pub task_order: i32,
+    /// ID of the task that is executed
+    pub task_id: TaskId,
+    /// Name of the task
+    pub name: String,
+    /// Short description of the task
+    pub description: String,
+    /// Status of the task
+    pub status: TaskStatus,
+    /// Optional parameters passed to the task executor to allow for custom behaviour
+    pub parameters: Option<Value>,
+    /// Optional output message for the task
+    pub output: Option<String>,
+    /// Optional list of task rules for the task
+    pub rules: Option<Vec<TaskRule>>,
+    /// Start of the task execution
+    pub task_start: Option<NaiveDateTime>,
+    /// End of the task execution
+    pub task_end: Option<NaiveDateTime>,
+    /// Optional progress value passed back from the task executor
+    pub progress: Option<i16>,
+    /// Optional list of the most recent log lines sent by the task executor
+    pub logs: Option<Vec<TaskLog>>,
+    /// Number of times the task has been retried during the workflow run
+    pub retry_count: i16,
+    /// Number of times a failed task run is automatically retried
+    pub max_retries: i16,
+    /// Optional structured output of the task run
+    pub output_data: Option<Value>,
+}
+
 /// Container for the various task run responses a task execution service can stream back to an
 /// [Executor][crate::executor::Executor]. The responses are a [TaskResponse::Progress] update
-/// (0-100%), a [TaskResponse::Rule] check that has completed or the terminal [TaskResponse::Done]
-/// message that contains a success flag and an optional message.
+/// (0-100%), a [TaskResponse::Rule] check that has completed, a [TaskResponse::Log] line or the
+/// terminal [TaskResponse::Done] message that contains a success flag, an optional message and
+/// optional structured output. The output is made available to the subsequent tasks of the
+/// workflow run (see [TaskQueueRecord::render_parameters]).
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type")]
 pub enum TaskResponse {
     Progress(i16),
     Rule(TaskRule),
+    Log {
+        level: TaskLogLevel,
+        message: String,
+    },
     Done {
         success: bool,
         message: Option<String>,
+        #[serde(default)]
+        output: Option<Value>,
     },
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use chrono::NaiveDateTime;
+    use common::error::EmError;
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use super::{
+        RunTimingSummary, TaskLogLevel, TaskQueueRecord, TaskResponse, TaskStatus,
+        TaskUrlVariables, TaskValidation, ValidationReport, WorkflowRun, WorkflowRunCancelRequest,
+        WorkflowRunFilter, WorkflowRunHistoryQuery, WorkflowRunId, WorkflowRunProgressMessage,
+        WorkflowRunStatus, WorkflowRunStatusMessage, WorkflowRunTask, WorkflowRunTasksQuery,
+    };
+    use crate::{
+        executor::data::ExecutorId,
+        job::data::JobId,
+        workflow::data::{TaskId, WorkflowTask},
+    };
+
+    #[rstest]
+    #[case::positive("1", 1)]
+    #[case::large("9007199254740993", 9_007_199_254_740_993)]
+    fn workflow_run_id_from_str_should_succeed_when(#[case] value: &str, #[case] expected: i64) {
+        let id: WorkflowRunId = value.parse().unwrap();
+
+        assert_eq!(id.into_inner(), expected);
+    }
+
+    #[rstest]
+    #[case::zero("0")]
+    #[case::negative("-1")]
+    #[case::non_numeric("abc")]
+    fn workflow_run_id_try_from_should_fail_when(#[case] value: &str) {
+        let result = WorkflowRunId::try_from(value);
+
+        assert!(matches!(
+            result,
+            Err(EmError::InvalidId {
+                kind: "workflow_run_id",
+                ..
+            })
+        ));
+    }
+
+    #[rstest]
+    #[case::negative(json!(-1))]
+    #[case::non_numeric(json!("abc"))]
+    fn ids_should_fail_to_deserialize_when(#[case] value: Value) {
+        assert!(serde_json::from_value::<WorkflowRunId>(value.clone()).is_err());
+        assert!(serde_json::from_value::<JobId>(value.clone()).is_err());
+        assert!(serde_json::from_value::<ExecutorId>(value).is_err());
+    }
+
+    #[test]
+    fn ids_should_deserialize_when_positive() {
+        let job_id: JobId = serde_json::from_value(json!(5)).unwrap();
+        let workflow_run_id: WorkflowRunId = serde_json::from_value(json!(5)).unwrap();
+
+        assert_eq!(job_id.into_inner(), 5);
+        assert_eq!(workflow_run_id.into_inner(), 5);
+    }
+
+    /// Create a [TaskQueueRecord] with the specified `parameters` and `parameters_schema`
+    fn task_queue_record(
+        parameters: Option<Value>,
+        parameters_schema: Option<Value>,
+    ) -> TaskQueueRecord {
+        TaskQueueRecord {
+            workflow_run_id: 1.into(),
+            task_order: 1,
+            task_id: 1.into(),
+            status: TaskStatus::Waiting,
+            parameters,
+            url: "http://127.0.0.1/task".to_owned(),
+            timeout: None,
+            parameters_schema,
+            retry_count: 0,
+            max_retries: 0,
+            task_outputs: None,
+        }
+    }
+
+    /// JSON Schema requiring an object with an integer `count` property
+    fn count_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": { "count": { "type": "integer" } },
+            "required": ["count"]
+        })
+    }
+
+    #[rstest]
+    #[case::no_schema(task_queue_record(Some(json!({ "other": true })), None))]
+    #[case::valid_parameters(task_queue_record(Some(json!({ "count": 1 })), Some(count_schema())))]
+    fn validate_parameters_should_succeed_when(#[case] record: TaskQueueRecord) {
+        assert!(record.validate_parameters().is_ok());
+    }
+
+    #[rstest]
+    #[case::missing_property(task_queue_record(Some(json!({})), Some(count_schema())))]
+    #[case::wrong_type(task_queue_record(Some(json!({ "count": "1" })), Some(count_schema())))]
+    #[case::missing_parameters(task_queue_record(None, Some(count_schema())))]
+    fn validate_parameters_should_fail_when(#[case] record: TaskQueueRecord) {
+        assert!(record.validate_parameters().is_err());
+    }
+
+    #[rstest]
+    #[case::not_templated("http://127.0.0.1/task", "http://127.0.0.1/task")]
+    #[case::host("${TASK_HOST}/run/foo", "http://10.0.0.1:8080/run/foo")]
+    #[case::multiple("${TASK_HOST}/${ PATH }", "http://10.0.0.1:8080/run/foo")]
+    fn task_url_variables_expand_should_succeed_when(#[case] url: &str, #[case] expected: &str) {
+        let variables = TaskUrlVariables::default()
+            .with_variable("TASK_HOST", "http://10.0.0.1:8080")
+            .with_variable("PATH", "run/foo");
+
+        let expanded = variables.expand(url).unwrap();
+
+        assert_eq!(expanded, expected);
+    }
+
+    #[rstest]
+    #[case::undefined("${MISSING}/run/foo")]
+    #[case::unterminated("${TASK_HOST/run/foo")]
+    #[case::empty_name("${}/run/foo")]
+    fn task_url_variables_expand_should_fail_when(#[case] url: &str) {
+        let variables = TaskUrlVariables::default().with_variable("TASK_HOST", "http://10.0.0.1");
+
+        let result = variables.expand(url);
+
+        assert!(matches!(result, Err(EmError::InvalidTaskUrl(_))));
+    }
+
+    #[test]
+    fn task_url_variables_from_vars_should_only_keep_prefixed_variables() {
+        let variables = TaskUrlVariables::from_vars([
+            (
+                "WE_TASK_URL_TASK_HOST".to_owned(),
+                "http://10.0.0.1".to_owned(),
+            ),
+            ("WE_TASK_URL_".to_owned(), "empty".to_owned()),
+            ("DATABASE_URL".to_owned(), "postgres://secret".to_owned()),
+        ]);
+
+        assert_eq!(
+            variables,
+            TaskUrlVariables::default().with_variable("TASK_HOST", "http://10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn expand_url_should_replace_templates_in_record_url() {
+        let mut record = task_queue_record(None, None);
+        record.url = "${TASK_HOST}/task".to_owned();
+        let variables = TaskUrlVariables::default().with_variable("TASK_HOST", "http://10.0.0.1");
+
+        record.expand_url(&variables).unwrap();
+
+        assert_eq!(record.url, "http://10.0.0.1/task");
+    }
+
+    #[test]
+    fn render_parameters_should_replace_templates_with_task_outputs() {
+        let mut record = task_queue_record(
+            Some(json!({
+                "rows": "{{ tasks.1.row_count }}",
+                "files": ["{{tasks.2.paths.0}}", "static"],
+                "name": "not a template",
+            })),
+            None,
+        );
+        record.task_outputs = Some(json!({
+            "1": { "row_count": 25 },
+            "2": { "paths": ["/data/file.csv"] },
+        }));
+
+        record.render_parameters().unwrap();
+
+        assert_eq!(
+            record.parameters,
+            Some(json!({
+                "rows": 25,
+                "files": ["/data/file.csv", "static"],
+                "name": "not a template",
+            })),
+            "Templates should be replaced by the referenced task outputs"
+        );
+    }
+
+    #[rstest]
+    #[case::missing_key(Some(json!({ "1": { "row_count": 25 } })))]
+    #[case::no_outputs(None)]
+    fn render_parameters_should_fail_when(#[case] task_outputs: Option<Value>) {
+        let mut record = task_queue_record(Some(json!({ "path": "{{ tasks.1.path }}" })), None);
+        record.task_outputs = task_outputs;
+
+        assert!(record.render_parameters().is_err());
+    }
+
+    #[test]
+    fn task_queue_record_should_not_serialize_task_outputs() {
+        let mut record =
+            task_queue_record(Some(json!({ "rows": "{{ tasks.1.row_count }}" })), None);
+        record.task_outputs = Some(json!({ "1": { "row_count": 25, "secret": "value" } }));
+        record.render_parameters().unwrap();
+
+        let value = serde_json::to_value(&record).unwrap();
+
+        assert!(
+            value.get("task_outputs").is_none(),
+            "Task outputs should not be sent to the remote task"
+        );
+        assert_eq!(value.get("parameters"), Some(&json!({ "rows": 25 })));
+    }
+
+    /// Create a [WorkflowRunHistoryQuery] with the specified `status` list and start range
+    fn history_query(
+        status: Option<&str>,
+        started_after: &str,
+        started_before: &str,
+    ) -> WorkflowRunHistoryQuery {
+        WorkflowRunHistoryQuery {
+            workflow_id: None,
+            status: status.map(str::to_owned),
+            started_after: started_after.parse::<NaiveDateTime>().unwrap(),
+            started_before: started_before.parse::<NaiveDateTime>().unwrap(),
+        }
+    }
+
+    #[rstest]
+    #[case::no_status(history_query(None, "2023-01-01T00:00:00", "2023-01-02T00:00:00"), vec![])]
+    #[case::single_status(
+        history_query(Some("Failed"), "2023-01-01T00:00:00", "2023-01-02T00:00:00"),
+        vec![WorkflowRunStatus::Failed],
+    )]
+    #[case::multiple_status(
+        history_query(Some("Complete, Failed"), "2023-01-01T00:00:00", "2023-01-01T00:00:00"),
+        vec![WorkflowRunStatus::Complete, WorkflowRunStatus::Failed],
+    )]
+    fn workflow_run_filter_should_succeed_when(
+        #[case] query: WorkflowRunHistoryQuery,
+        #[case] expected_status: Vec<WorkflowRunStatus>,
+    ) {
+        let filter = WorkflowRunFilter::try_from(query).unwrap();
+
+        assert!(filter.status == expected_status);
+    }
+
+    #[rstest]
+    #[case::unknown_status(history_query(
+        Some("Finished"),
+        "2023-01-01T00:00:00",
+        "2023-01-02T00:00:00"
+    ))]
+    #[case::inverted_range(history_query(None, "2023-01-02T00:00:00", "2023-01-01T00:00:00"))]
+    fn workflow_run_filter_should_fail_when(#[case] query: WorkflowRunHistoryQuery) {
+        assert!(WorkflowRunFilter::try_from(query).is_err());
+    }
+
+    #[rstest]
+    #[case::unprefixed(r#"{"workflow_run_id":1,"status":"Running","progress":50}"#)]
+    #[case::v1(r#"v1:{"workflow_run_id":1,"status":"Running","progress":50}"#)]
+    fn workflow_run_progress_message_should_parse_payload_when_valid_json(#[case] payload: &str) {
+        let message = WorkflowRunProgressMessage::from(payload);
+
+        let progress = message.0.unwrap();
+        assert_eq!(progress.workflow_run_id, 1.into());
+        assert!(progress.status == WorkflowRunStatus::Running);
+        assert_eq!(progress.progress, Some(50));
+    }
+
+    #[rstest]
+    #[case::not_json("1")]
+    #[case::unknown_status(r#"{"workflow_run_id":1,"status":"Finished","progress":null}"#)]
+    #[case::unknown_version(r#"v2:{"workflow_run_id":1,"status":"Running","progress":50}"#)]
+    fn workflow_run_progress_message_should_be_empty_when(#[case] payload: &str) {
+        let message = WorkflowRunProgressMessage::from(payload);
+
+        assert!(message.0.is_none());
+    }
+
+    #[rstest]
+    #[case::unprefixed("Running", WorkflowRunStatus::Running)]
+    #[case::v1("v1:Complete", WorkflowRunStatus::Complete)]
+    fn workflow_run_status_message_should_parse_payload_when_valid(
+        #[case] payload: &str,
+        #[case] expected: WorkflowRunStatus,
+    ) {
+        let message = WorkflowRunStatusMessage::from(payload);
+
+        assert!(message.0 == Some(expected));
+    }
+
+    #[rstest]
+    #[case::unknown_status("v1:Finished")]
+    #[case::unknown_version("v2:Running")]
+    fn workflow_run_status_message_should_be_empty_when(#[case] payload: &str) {
+        let message = WorkflowRunStatusMessage::from(payload);
+
+        assert!(message.0.is_none());
+    }
+
+    #[rstest]
+    #[case::missing(None, None)]
+    #[case::blank(Some("   "), None)]
+    #[case::padded(Some(" Wrong parameters "), Some("Wrong parameters"))]
+    fn cancel_request_reason_should_be_trimmed_when(
+        #[case] reason: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        let request = WorkflowRunCancelRequest::new(reason.map(str::to_owned));
+
+        assert_eq!(request.reason(), expected);
+    }
+
+    #[test]
+    fn task_response_should_decode_log_message() {
+        let bytes = rmp_serde::to_vec(&TaskResponse::Log {
+            level: TaskLogLevel::Warn,
+            message: "Retrying connection".to_owned(),
+        })
+        .unwrap();
+
+        let response: TaskResponse = rmp_serde::from_slice(&bytes).unwrap();
+
+        assert!(matches!(
+            response,
+            TaskResponse::Log { level: TaskLogLevel::Warn, message } if message == "Retrying connection"
+        ));
+    }
+
+    #[rstest]
+    #[case::without_output(json!({ "type": "Done", "success": true, "message": null }), None)]
+    #[case::with_output(
+        json!({ "type": "Done", "success": true, "message": null, "output": { "rows": 2 } }),
+        Some(json!({ "rows": 2 })),
+    )]
+    fn task_response_done_should_deserialize_when(
+        #[case] message: Value,
+        #[case] expected_output: Option<Value>,
+    ) {
+        let bytes = rmp_serde::to_vec(&message).unwrap();
+
+        let response: TaskResponse = rmp_serde::from_slice(&bytes).unwrap();
+
+        assert!(matches!(
+            response,
+            TaskResponse::Done { success: true, message: None, output } if output == expected_output
+        ));
+    }
+
+    /// Create a [TaskValidation] for a task with the specified `task_order` and check `errors`
+    fn task_validation(task_order: i32, errors: &[&str]) -> TaskValidation {
+        let task = WorkflowTask {
+            task_order,
+            task_id: 1.into(),
+            name: format!("Task {task_order}"),
+            description: String::new(),
+            parameters: None,
+            service_name: "test".to_owned(),
+            url: "http://127.0.0.1/task".to_owned(),
+            depends_on: None,
+        };
+        TaskValidation::new(
+            &task,
+            errors.iter().map(|&error| error.to_owned()).collect(),
+        )
+    }
+
+    #[rstest]
+    #[case::all_passed(vec![task_validation(2, &[]), task_validation(1, &[])], true)]
+    #[case::one_failed(vec![task_validation(2, &[]), task_validation(1, &["Invalid"])], false)]
+    fn validation_report_should_order_tasks_and_set_is_valid_when(
+        #[case] tasks: Vec<TaskValidation>,
+        #[case] expected: bool,
+    ) {
+        let report = ValidationReport::new(1.into(), tasks);
+
+        assert_eq!(report.is_valid, expected);
+        let orders: Vec<i32> = report.tasks.iter().map(|task| task.task_order).collect();
+        assert_eq!(orders, vec![1, 2]);
+    }
+
+    #[rstest]
+    #[case::missing(None, None)]
+    #[case::empty(Some(" , "), None)]
+    #[case::single(Some("Failed"), Some(vec![TaskStatus::Failed]))]
+    #[case::multiple(
+        Some("Failed, Rule Broken"),
+        Some(vec![TaskStatus::Failed, TaskStatus::RuleBroken]),
+    )]
+    fn task_status_filter_should_parse_statuses_when(
+        #[case] task_status: Option<&str>,
+        #[case] expected: Option<Vec<TaskStatus>>,
+    ) {
+        let query = WorkflowRunTasksQuery {
+            task_status: task_status.map(str::to_owned),
+        };
+
+        let filter = query.task_status_filter().unwrap();
+
+        assert_eq!(filter, expected);
+    }
+
+    #[test]
+    fn task_status_filter_should_fail_when_status_unknown() {
+        let query = WorkflowRunTasksQuery {
+            task_status: Some("Failed,Broken".to_owned()),
+        };
+
+        assert!(query.task_status_filter().is_err());
+    }
+
+    /// Parse a timestamp of the form `2023-06-01T12:00:00` for timing tests
+    fn timestamp(value: &str) -> NaiveDateTime {
+        value.parse().unwrap()
+    }
+
+    /// Create a workflow run task with the specified `task_order`, start and end for timing tests
+    fn timing_task(
+        task_order: i32,
+        task_start: Option<&str>,
+        task_end: Option<&str>,
+    ) -> WorkflowRunTask {
+        let task_status = match (task_start, task_end) {
+            (Some(_), Some(_)) => TaskStatus::Complete,
+            (Some(_), None) => TaskStatus::Running,
+            (None, _) => TaskStatus::Waiting,
+        };
+        WorkflowRunTask {
+            task_order,
+            task_id: TaskId::from(i64::from(task_order)),
+            name: format!("task {task_order}"),
+            description: String::new(),
+            task_status,
+            parameters: None,
+            output: None,
+            rules: None,
+            task_start: task_start.map(timestamp),
+            task_end: task_end.map(timestamp),
+            progress: None,
+            logs: None,
+            retry_count: 0,
+            max_retries: 0,
+        }
+    }
+
+    /// Create a workflow run with the `status` and `tasks` for timing tests
+    fn timing_workflow_run(status: WorkflowRunStatus, tasks: Vec<WorkflowRunTask>) -> WorkflowRun {
+        WorkflowRun {
+            workflow_run_id: WorkflowRunId::from(1),
+            workflow_id: 1,
+            status,
+            executor_id: None,
+            progress: None,
+            priority: 0,
+            cancel_reason: None,
+            tasks,
+        }
+    }
+
+    #[test]
+    fn run_timing_summary_should_measure_complete_run() {
+        let workflow_run = timing_workflow_run(
+            WorkflowRunStatus::Complete,
+            vec![
+                timing_task(2, Some("2023-06-01T12:01:10"), Some("2023-06-01T12:01:15")),
+                timing_task(1, Some("2023-06-01T12:01:00"), Some("2023-06-01T12:01:10")),
+            ],
+        );
+
+        let summary = RunTimingSummary::new(
+            workflow_run,
+            Some(timestamp("2023-06-01T12:00:30")),
+            Some(timestamp("2023-06-01T12:01:00")),
+            timestamp("2023-06-01T13:00:00"),
+        );
+
+        assert_eq!(summary.queue_wait_ms, Some(30_000));
+        assert_eq!(summary.run_duration_ms, Some(15_000));
+        assert_eq!(summary.run_end, Some(timestamp("2023-06-01T12:01:15")));
+        assert_eq!(summary.slowest_task_order, Some(1));
+        let durations: Vec<(i32, Option<i64>)> = summary
+            .tasks
+            .iter()
+            .map(|task| (task.task_order, task.duration_ms))
+            .collect();
+        assert_eq!(durations, vec![(1, Some(10_000)), (2, Some(5_000))]);
+    }
+
+    #[test]
+    fn run_timing_summary_should_use_now_when_run_in_progress() {
+        let workflow_run = timing_workflow_run(
+            WorkflowRunStatus::Running,
+            vec![
+                timing_task(1, Some("2023-06-01T12:01:00"), Some("2023-06-01T12:01:10")),
+                timing_task(2, Some("2023-06-01T12:01:10"), None),
+                timing_task(3, None, None),
+            ],
+        );
+
+        let summary = RunTimingSummary::new(
+            workflow_run,
+            Some(timestamp("2023-06-01T12:00:30")),
+            Some(timestamp("2023-06-01T12:01:00")),
+            timestamp("2023-06-01T12:02:00"),
+        );
+
+        assert_eq!(summary.run_duration_ms, Some(60_000));
+        assert_eq!(summary.slowest_task_order, Some(2));
+        let durations: Vec<Option<i64>> =
+            summary.tasks.iter().map(|task| task.duration_ms).collect();
+        assert_eq!(durations, vec![Some(10_000), Some(50_000), None]);
+    }
+
+    #[test]
+    fn run_timing_summary_should_use_now_when_run_waiting_in_queue() {
+        let workflow_run = timing_workflow_run(
+            WorkflowRunStatus::Scheduled,
+            vec![timing_task(1, None, None)],
+        );
+
+        let summary = RunTimingSummary::new(
+            workflow_run,
+            Some(timestamp("2023-06-01T12:00:30")),
+            None,
+            timestamp("2023-06-01T12:01:00"),
+        );
+
+        assert_eq!(summary.queue_wait_ms, Some(30_000));
+        assert_eq!(summary.run_duration_ms, None);
+        assert_eq!(summary.slowest_task_order, None);
+    }
+}
@@ -1,12 +1,24 @@
 pub mod postgres;
 
+use std::time::Duration;
+
 use common::{
+    api::{
+        pagination::{CursorPage, CursorPagination},
+        sort::Sort,
+    },
     database::{listener::ChangeListener, Database},
     error::{EmError, EmResult},
 };
+use futures::{stream::BoxStream, StreamExt};
+use serde_json::Value;
+use tokio::sync::broadcast;
 
 use super::data::{
-    ExecutorWorkflowRun, TaskQueueRecord, TaskQueueRequest, TaskRule, WorkflowRun, WorkflowRunId,
+    ExecutorWorkflowRun, RunTimingSummary, TaskDetail, TaskLogLevel, TaskQueueRecord,
+    TaskQueueRequest, TaskRule, TaskStatus, ValidationReport, WorkflowRun, WorkflowRunFilter,
+    WorkflowRunHistory, WorkflowRunId, WorkflowRunProgress, WorkflowRunStatus,
+    WorkflowRunStatusMessage, WorkflowRunSummary,
 };
 use crate::{
     executor::{
@@ -16,6 +28,16 @@ use crate::{
     workflow::{data::WorkflowId, service::WorkflowsService},
 };
 
+/// Maximum number of workflow runs that can be created in a single call to
+/// [WorkflowRunsService::initialize_batch]
+pub const MAX_INITIALIZE_BATCH_SIZE: usize = 100;
+/// Maximum number of workflow runs that can be read in a single call to
+/// [WorkflowRunsService::read_many_by_ids]
+pub const MAX_READ_MANY_BATCH_SIZE: usize = 500;
+/// Default maximum number of log lines kept per task when appended through
+/// [TaskQueueService::append_task_log]
+pub const DEFAULT_MAX_TASK_LOG_LINES: i32 = 1000;
+
 #[async_trait::async_trait]
 pub trait WorkflowRunsService
 where
@@ -24,22 +46,90 @@ where
     type CancelListener: ChangeListener<Message = WorkflowRunCancelMessage>;
     type Database: Database;
     type ScheduledListener: ChangeListener<Message = WorkflowRunScheduledMessage>;
+    type StatusListener: ChangeListener<Message = WorkflowRunStatusMessage>;
     type WorkflowService: WorkflowsService;
 
     /// Initialize a new workflow run for the specified `workflow_id`. Returns the new [WorkflowRun]
     /// instance.
     async fn initialize(&self, workflow_id: &WorkflowId) -> EmResult<WorkflowRun>;
+    /// Initialize `count` new workflow runs for the specified `workflow_id`. The workflow is only
+    /// read once and all runs are created together. Returns the new [WorkflowRun] instances in the
+    /// order they were created. Returns [Err] if `count` is zero or greater than
+    /// [MAX_INITIALIZE_BATCH_SIZE].
+    async fn initialize_batch(
+        &self,
+        workflow_id: &WorkflowId,
+        count: usize,
+    ) -> EmResult<Vec<WorkflowRun>>;
     /// Read a single [WorkflowRun] record from `workflow.v_workflow_runs` for the specified
     /// `workflow_run_id`. Will return [Err] when the id does not match a record.
     async fn read_one(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun>;
+    /// Read a single [WorkflowRun] record for the specified `workflow_run_id` where the `tasks`
+    /// only include the tasks with a status within the `task_status_filter`. The filtering is
+    /// performed by the database. When the filter is [None], all tasks are included (i.e. the same
+    /// as [read_one][WorkflowRunsService::read_one]). Will return [Err] when the id does not match
+    /// a record.
+    async fn read_one_filtered(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+        task_status_filter: Option<Vec<TaskStatus>>,
+    ) -> EmResult<WorkflowRun>;
+    /// Read a single [WorkflowRunSummary] for the specified `workflow_run_id`. Unlike
+    /// [read_one][WorkflowRunsService::read_one], the tasks of the workflow run are not fetched
+    async fn read_one_summary(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+    ) -> EmResult<WorkflowRunSummary>;
+    /// Read the [RunTimingSummary] of the workflow run specified by `workflow_run_id`, containing
+    /// the queue wait, total run duration and duration of each task. Timing of a workflow run or
+    /// task that is still in progress is measured up to the current time.
+    async fn read_timing(&self, workflow_run_id: &WorkflowRunId) -> EmResult<RunTimingSummary>;
+    /// Read the [WorkflowRun] records from `workflow.v_workflow_runs` for every id within
+    /// `workflow_run_ids` in a single query. Records are returned in the order of the input ids,
+    /// while ids that do not match a record are omitted. Returns [Err] if more than
+    /// [MAX_READ_MANY_BATCH_SIZE] ids are requested.
+    async fn read_many_by_ids(
+        &self,
+        workflow_run_ids: &[WorkflowRunId],
+    ) -> EmResult<Vec<WorkflowRun>>;
     /// Read all [WorkflowRun] records found from `workflow.v_workflow_runs`
     async fn read_active(&self) -> EmResult<Vec<WorkflowRun>>;
+    /// Read a [WorkflowRunSummary] for every workflow run that is not 'Complete'
+    async fn read_active_summaries(&self) -> EmResult<Vec<WorkflowRunSummary>>;
+    /// Read a [WorkflowRunSummary] for every workflow run that contains at least one task with the
+    /// specified `status`
+    async fn read_by_task_status(&self, status: TaskStatus) -> EmResult<Vec<WorkflowRunSummary>>;
+    /// Read a page of [WorkflowRun] records from `workflow.v_workflow_runs`, ordered by
+    /// `workflow_run_id`. The page starts after the workflow run referenced by the `page` cursor
+    /// so iteration is stable when workflow runs are created between page requests.
+    async fn read_many_after(&self, page: &CursorPagination) -> EmResult<CursorPage<WorkflowRun>>;
+    /// Read [WorkflowRunHistory] records from `workflow_run.v_workflow_run_history` that match
+    /// the `filter` provided, ordered by the `sort` requested. The sortable columns are `status`,
+    /// `run_start` and `progress`. Any other column falls back to ordering by the most recent
+    /// start first. Implementations may serve this query from a read replica, so recently started
+    /// workflow runs can be missing.
+    async fn read_history(
+        &self,
+        filter: &WorkflowRunFilter,
+        sort: &Sort,
+    ) -> EmResult<Vec<WorkflowRunHistory>>;
     /// Process the next workflow run, setting it's state for execution before returning the
     /// [WorkflowRunId]. If no workflow run is available, then the function returns [None].
     async fn next_workflow_run(&self, executor_id: &ExecutorId) -> EmResult<Option<WorkflowRunId>>;
     /// Update the status of the workflow run to 'Canceled' and send a notification to the
-    /// [Executor][crate::executor::Executor] handling the workflow run to stop operations.
-    async fn cancel(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun>;
+    /// [Executor][crate::executor::Executor] handling the workflow run to stop operations. No
+    /// cancel reason is recorded.
+    async fn cancel(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
+        self.cancel_with_reason(workflow_run_id, None).await
+    }
+    /// Update the status of the workflow run to 'Canceled', storing the optional `reason` as the
+    /// [cancel_reason][WorkflowRun::cancel_reason] of the workflow run, and send a notification
+    /// to the [Executor][crate::executor::Executor] handling the workflow run to stop operations.
+    async fn cancel_with_reason(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+        reason: Option<&str>,
+    ) -> EmResult<WorkflowRun>;
     /// Schedule a workflow run to be picked up by an available
     /// [Executor][crate::executor::Executor]. Return a [WorkflowRun] with the new data from the
     /// scheduled record of `workflow_run_id`.
@@ -52,10 +142,37 @@ where
         workflow_run_id: &WorkflowRunId,
         executor_id: &ExecutorId,
     ) -> EmResult<WorkflowRun>;
+    /// Schedule a workflow run to be picked up by an available
+    /// [Executor][crate::executor::Executor] with the specified `priority`. Scheduled workflow runs
+    /// with a higher priority are claimed first. Returns a [WorkflowRun] with the new data from the
+    /// scheduled record of `workflow_run_id`.
+    async fn schedule_with_priority(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+        priority: i16,
+    ) -> EmResult<WorkflowRun>;
     /// Restart a workflow run to a 'Waiting' state. Copies current state of the `task_queue` before
     /// updating restarting all tasks and the workflow run itself. Returns a [WorkflowRun] with the
     /// new state of the workflow run for the specified `workflow_run_id`.
     async fn restart(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun>;
+    /// Pause a 'Running' workflow run at the next task boundary. Tasks that are already running
+    /// are allowed to finish but the owning [Executor][crate::executor::Executor] does not start
+    /// any new tasks. Returns [Err] if the workflow run is not 'Running'.
+    async fn pause(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun>;
+    /// Resume a 'Paused' workflow run by scheduling it to be picked up by an available
+    /// [Executor][crate::executor::Executor]. Returns [Err] if the workflow run is not 'Paused',
+    /// is still finishing its running tasks or has a task that must be retried first.
+    async fn resume(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun>;
+    /// Initialize a brand-new workflow run (with a new id) in the 'Waiting' state using the same
+    /// workflow and task parameters as the workflow run specified by `workflow_run_id`. The source
+    /// workflow run is left untouched. Returns [Err] if the source workflow is deprecated.
+    async fn clone_run(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun>;
+    /// Validate the tasks of the workflow specified by `workflow_id` without initializing a
+    /// workflow run or executing any task. Each task's url must be parseable and reachable and
+    /// the task's parameters must satisfy the task's parameters schema. Returns a
+    /// [ValidationReport] with the result of each task, or [Err] if the workflow cannot be
+    /// read.
+    async fn validate_workflow(&self, workflow_id: &WorkflowId) -> EmResult<ValidationReport>;
     /// Update the progress of a workflow run. The progress is not provided but rather calculated
     /// by the progress of it's tasks.
     async fn update_progress(&self, workflow_run_id: &WorkflowRunId) -> EmResult<()>;
@@ -76,6 +193,12 @@ where
     /// 'Waiting' status and schedules the workflow run for execution. Returns the new state of the
     /// [WorkflowRun] specified by `workflow_run_id`.
     async fn complete_move(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun>;
+    /// Recover 'Scheduled' and 'Running' workflow runs whose owning executor is no longer active
+    /// (e.g. the executor was killed without closing its session). Running tasks are reset to
+    /// 'Waiting' and the workflow runs are scheduled again so the next available
+    /// [Executor][crate::executor::Executor] picks them up. Returns the ids of the recovered
+    /// workflow runs.
+    async fn recover_orphaned(&self) -> EmResult<Vec<WorkflowRunId>>;
     /// Get a new workflow run scheduled listener for the specified `executor_id`. The
     /// [ChangeListener] checks a channel named `wr_scheduled_{executor_id}`
     async fn scheduled_listener(
@@ -85,6 +208,56 @@ where
     /// Get a new workflow run canceled listener for the specified `executor_id`. The
     /// [ChangeListener] checks a channel named `wr_canceled_{executor_id}`
     async fn cancel_listener(&self, executor_id: &ExecutorId) -> EmResult<Self::CancelListener>;
+    /// Subscribe to the progress updates of every workflow run sent after this call. Updates are
+    /// received from the `wr_progress_updates` channel by a single listener shared by every
+    /// subscriber of the service, so callers interested in a single workflow run must filter the
+    /// updates by `workflow_run_id`.
+    fn progress_updates(&self) -> broadcast::Receiver<WorkflowRunProgress>;
+    /// Get a new workflow run status listener for the specified `workflow_run_id`. The
+    /// [ChangeListener] checks a channel named `wr_status_{workflow_run_id}`. Anything that
+    /// changes the status of a workflow run must publish the new status name to that channel
+    /// (e.g. `v1:Running`), which is done by the `workflow_run.workflow_runs` status trigger.
+    async fn status_listener(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+    ) -> EmResult<Self::StatusListener>;
+    /// Subscribe to the status changes of the specified `workflow_run_id`. The current status of
+    /// the workflow run is yielded first, followed by every status transition published to the
+    /// [status_listener][WorkflowRunsService::status_listener] channel. The stream ends after a
+    /// terminal status is yielded or if the underlying listener fails.
+    async fn subscribe(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+    ) -> EmResult<BoxStream<'static, WorkflowRunStatus>>;
+    /// Wait for the specified `workflow_run_id` to reach a terminal status, returning that status.
+    /// Built on top of [subscribe][WorkflowRunsService::subscribe].
+    /// # Errors
+    /// This function will return an error if the subscription cannot be created, the status
+    /// updates end before a terminal status is reached or the `timeout` elapses first
+    async fn wait_for_completion(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+        timeout: Duration,
+    ) -> EmResult<WorkflowRunStatus> {
+        let mut statuses = self.subscribe(workflow_run_id).await?;
+        let terminal = async move {
+            while let Some(status) = statuses.next().await {
+                if status.is_terminal() {
+                    return Some(status);
+                }
+            }
+            None
+        };
+        match tokio::time::timeout(timeout, terminal).await {
+            Ok(Some(status)) => Ok(status),
+            Ok(None) => Err(EmError::Generic(format!(
+                "Status updates for workflow run {workflow_run_id} ended before it completed"
+            ))),
+            Err(_) => Err(EmError::Generic(format!(
+                "Workflow run {workflow_run_id} did not complete within {timeout:?}"
+            ))),
+        }
+    }
 }
 
 /// Service for fetching and interacting with `task_queue` data. Wraps a [Pool] and provides
@@ -100,34 +273,71 @@ where
     /// Read a single task record from `task.task_queue` for the specified `request`data. Will
     /// return [Err] when the ids in the `request` do not match a record.
     async fn read_one(&self, request: &TaskQueueRequest) -> EmResult<TaskQueueRecord>;
+    /// Read the full detail of a single task, including the accumulated rules, output and logs,
+    /// for the specified `request` data. Will return [EmError::MissingRecord] when the ids in the
+    /// `request` do not match a record.
+    async fn read_task_detail(&self, request: &TaskQueueRequest) -> EmResult<TaskDetail>;
+    /// Read the task rules checked so far for the specified `request` data, in the order they
+    /// were appended. A task without any rules returns an empty [Vec]. Will return
+    /// [EmError::MissingRecord] when the ids in the `request` do not match a record.
+    async fn read_task_rules(&self, request: &TaskQueueRequest) -> EmResult<Vec<TaskRule>>;
     /// Append the task `rule` data to the specified `task_queue` record
     async fn append_task_rule(&self, request: &TaskQueueRequest, rule: &TaskRule) -> EmResult<()>;
+    /// Append a log line with the specified `level` and `message` to the logs of the specified
+    /// `task_queue` record. Logs are bounded per task, so the oldest lines are removed once the
+    /// implementation's maximum number of lines is exceeded.
+    async fn append_task_log(
+        &self,
+        request: &TaskQueueRequest,
+        level: TaskLogLevel,
+        message: &str,
+    ) -> EmResult<()>;
     /// Update the specified `task_queue` record with the new progress value
     async fn set_task_progress(&self, request: &TaskQueueRequest, progress: i16) -> EmResult<()>;
     /// Retry the specified `task_queue` record. Note, the record must be in the 'Failed' or
     /// 'Rule Broken' state to qualify for a retry.
     async fn retry_task(&self, request: &TaskQueueRequest) -> EmResult<()>;
+    /// Retry every `task_queue` record of the workflow run that is in the 'Failed' or
+    /// 'Rule Broken' state as a single operation, returning the number of tasks retried. The
+    /// workflow run is only scheduled when at least 1 task was retried, so a run without any
+    /// retryable tasks is left untouched and 0 is returned.
+    async fn retry_all_failed(&self, workflow_run_id: &WorkflowRunId) -> EmResult<usize>;
     /// Complete the specified `task_queue` record to allow for continuing of a workflow run after
     /// a user interruption. Note, the record must be in the 'Paused' state for a successful
     /// complete.
     async fn complete_task(&self, request: &TaskQueueRequest) -> EmResult<()>;
-    /// Acquire the next available task for a workflow run execution. Modifies the next available
-    /// record to mark it as started. Will return [None] if there are no more available tasks to
-    /// run.
-    async fn next_task(&self, workflow_run_id: &WorkflowRunId)
-        -> EmResult<Option<TaskQueueRecord>>;
+    /// Acquire every currently runnable task for a workflow run execution. A task is runnable
+    /// once all the tasks it depends on are complete (tasks without explicit dependencies depend
+    /// on every previous task) and no task of the run is paused, failed or has a broken rule. At
+    /// most the workflow's `max_parallel_tasks`, minus the tasks of the run that are already
+    /// running, are returned. Each returned record is marked as started, so the caller is
+    /// responsible for running all of them. Will return an empty [Vec] if no task is currently
+    /// runnable. Concurrent calls for the same `workflow_run_id` never claim the same task.
+    async fn next_tasks(&self, workflow_run_id: &WorkflowRunId) -> EmResult<Vec<TaskQueueRecord>>;
     /// Run the specified task `record` to completion. See [TaskQueueService::remote_task_run] for
-    /// more details. Remote task execution is run against the [Pool::close_event] so in the event
-    /// of a pool close or database connection loss, the remote task execution is canceled.
-    async fn run_task(&self, record: &TaskQueueRecord) -> EmResult<(bool, Option<String>)>;
+    /// more details. The `record` parameters are validated against the task's parameters schema
+    /// before the remote call, returning an [EmError::InvalidTaskParameters] if the parameters
+    /// are not valid. If the `record` has a timeout, the run is failed with an
+    /// [EmError::TaskTimeout] once the timeout elapses. Remote task execution is run against the
+    /// [Pool::close_event] so in the event of a pool close or database connection loss, the remote
+    /// task execution is canceled. Templated parameters are resolved from the structured output of
+    /// the previous tasks (see [TaskQueueRecord::render_parameters]) before validation. `${NAME}`
+    /// templates within the task url are expanded before the request (see
+    /// [TaskQueueRecord::expand_url]), returning an [EmError::InvalidTaskUrl] if a referenced
+    /// variable is not defined.
+    async fn run_task(
+        &self,
+        record: &TaskQueueRecord,
+    ) -> EmResult<(bool, Option<String>, Option<Value>)>;
     /// Mark the specified task `record` as failed with the error message included
     async fn fail_task_run(&self, record: &TaskQueueRecord, error: EmError) -> EmResult<()>;
     /// Complete the specified task `record` as complete (or paused if the `is_paused` flag is
-    /// true). Includes an optional message if provided.
+    /// true). Includes an optional message and structured `output` if provided.
     async fn complete_task_run(
         &self,
         record: &TaskQueueRecord,
         is_paused: bool,
         message: Option<String>,
+        output: Option<Value>,
     ) -> EmResult<()>;
 }
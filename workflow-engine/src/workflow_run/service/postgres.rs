@@ -1,39 +1,65 @@
-use chrono::NaiveDateTime;
+use std::{
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveDateTime, Utc};
 use common::{
+    api::{
+        http_client::{http_client_config, shared_client, streaming_client},
+        pagination::{CursorPage, CursorPagination, CursorPaginationValidator},
+        sort::{Sort, SortColumns},
+        ApiRequestValidator,
+    },
+    audit::{postgres::PgAuditSink, AuditEvent, AuditSink},
     database::{
-        connection::finalize_transaction,
-        postgres::{listener::PgChangeListener, Postgres},
+        connection::{finalize_transaction, DualPool},
+        listener::{ChangeListener, ChannelNamespace},
+        postgres::{
+            listener::{spawn_broadcast, PgChangeListener},
+            Postgres,
+        },
+        with_retryable_transaction, DEFAULT_TRANSACTION_ATTEMPTS,
     },
     error::{EmError, EmResult},
 };
-use futures::StreamExt;
-use reqwest::{Client, Method};
-use serde_json::Value;
+use futures::{future::join_all, stream, stream::BoxStream, StreamExt};
+use log::error;
+use reqwest::{Client, Method, Url};
+use serde_json::{json, Value};
 use sqlx::{
     decode::Decode,
     encode::{Encode, IsNull},
     postgres::{
         types::{PgRecordDecoder, PgRecordEncoder},
-        PgArgumentBuffer, PgHasArrayType, PgListener, PgTypeInfo, PgValueRef,
+        PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef,
     },
     PgPool, Transaction, Type,
 };
+use tokio::sync::broadcast;
 
 use crate::{
     executor::{
         data::ExecutorId,
         utilities::{WorkflowRunCancelMessage, WorkflowRunScheduledMessage},
     },
+    metrics::EngineMetrics,
     workflow::{
-        data::{TaskId, WorkflowId},
+        data::{TaskId, WorkflowId, WorkflowTask},
         service::{postgres::PgWorkflowsService, WorkflowsService},
     },
     workflow_run::{
         data::{
-            ExecutorWorkflowRun, TaskQueueRecord, TaskQueueRequest, TaskResponse, TaskRule,
-            TaskStatus, WorkflowRun, WorkflowRunId, WorkflowRunStatus, WorkflowRunTask,
+            validate_parameters_schema, ExecutorWorkflowRun, RunTimingSummary, TaskDetail, TaskLog,
+            TaskLogLevel, TaskQueueRecord, TaskQueueRequest, TaskResponse, TaskRule, TaskStatus,
+            TaskUrlVariables, TaskValidation, ValidationReport, WorkflowRun, WorkflowRunFilter,
+            WorkflowRunHistory, WorkflowRunId, WorkflowRunProgress, WorkflowRunProgressMessage,
+            WorkflowRunStatus, WorkflowRunStatusMessage, WorkflowRunSummary, WorkflowRunTask,
+        },
+        service::{
+            TaskQueueService, WorkflowRunsService, DEFAULT_MAX_TASK_LOG_LINES,
+            MAX_INITIALIZE_BATCH_SIZE, MAX_READ_MANY_BATCH_SIZE,
         },
-        service::{TaskQueueService, WorkflowRunsService},
     },
 };
 
@@ -51,12 +77,15 @@ impl Encode<'_, sqlx::Postgres> for WorkflowRunTask {
         encoder.encode(self.task_start);
         encoder.encode(self.task_end);
         encoder.encode(self.progress);
+        encoder.encode(&self.logs);
+        encoder.encode(self.retry_count);
+        encoder.encode(self.max_retries);
         encoder.finish();
         IsNull::No
     }
 
     fn size_hint(&self) -> usize {
-        9usize * (4 + 4)
+        14usize * (4 + 4)
             + <i32 as Encode<sqlx::Postgres>>::size_hint(&self.task_order)
             + <TaskId as Encode<sqlx::Postgres>>::size_hint(&self.task_id)
             + <String as Encode<sqlx::Postgres>>::size_hint(&self.name)
@@ -68,6 +97,9 @@ impl Encode<'_, sqlx::Postgres> for WorkflowRunTask {
             + <Option<NaiveDateTime> as Encode<sqlx::Postgres>>::size_hint(&self.task_start)
             + <Option<NaiveDateTime> as Encode<sqlx::Postgres>>::size_hint(&self.task_end)
             + <Option<i16> as Encode<sqlx::Postgres>>::size_hint(&self.progress)
+            + <Option<Vec<TaskLog>> as Encode<sqlx::Postgres>>::size_hint(&self.logs)
+            + <i16 as Encode<sqlx::Postgres>>::size_hint(&self.retry_count)
+            + <i16 as Encode<sqlx::Postgres>>::size_hint(&self.max_retries)
     }
 }
 
@@ -87,6 +119,9 @@ impl<'r> Decode<'r, sqlx::Postgres> for WorkflowRunTask {
         let task_start = decoder.try_decode::<Option<NaiveDateTime>>()?;
         let task_end = decoder.try_decode::<Option<NaiveDateTime>>()?;
         let progress = decoder.try_decode::<Option<i16>>()?;
+        let logs = decoder.try_decode::<Option<Vec<TaskLog>>>()?;
+        let retry_count = decoder.try_decode::<i16>()?;
+        let max_retries = decoder.try_decode::<i16>()?;
         Ok(Self {
             task_order,
             task_id,
@@ -99,6 +134,9 @@ impl<'r> Decode<'r, sqlx::Postgres> for WorkflowRunTask {
             task_start,
             task_end,
             progress,
+            logs,
+            retry_count,
+            max_retries,
         })
     }
 }
@@ -115,21 +153,105 @@ impl PgHasArrayType for WorkflowRunTask {
     }
 }
 
-/// Service for fetching and interacting with workflow run data. Wraps a [PgPool] and provides
-/// interaction methods for the API and [Executor][crate::executor::Executor] instances.
+/// Number of progress updates buffered for each subscriber of
+/// [progress_updates][WorkflowRunsService::progress_updates] before the oldest updates are skipped
+const PROGRESS_UPDATES_BUFFER_SIZE: usize = 256;
+
+/// Service for fetching and interacting with workflow run data. Wraps a [DualPool] and provides
+/// interaction methods for the API and [Executor][crate::executor::Executor] instances. Read-only
+/// reporting queries (e.g. [read_history][WorkflowRunsService::read_history]) are sent to the
+/// replica pool while all other queries are sent to the primary pool.
 #[derive(Clone)]
 pub struct PgWorkflowRunsService {
-    pool: PgPool,
+    pools: DualPool<sqlx::Postgres>,
     workflow_service: PgWorkflowsService,
+    priority_aging: f64,
+    client: Client,
+    audit_sink: Option<PgAuditSink>,
+    channel_namespace: ChannelNamespace,
+    /// Sender of the shared progress updates listener. Spawned on the first subscription and
+    /// shared by every clone of the service
+    progress_updates: Arc<OnceLock<broadcast::Sender<WorkflowRunProgress>>>,
 }
 
 impl PgWorkflowRunsService {
     /// Create a new [PgWorkflowRunsService] with the referenced pool as the data source
     pub fn new(pool: &PgPool, workflow_service: &PgWorkflowsService) -> Self {
+        Self::with_pools(&DualPool::single(pool), workflow_service)
+    }
+
+    /// Create a new [PgWorkflowRunsService] with the referenced `pools` as the data source.
+    /// Read-only reporting queries are sent to the replica pool, so recently written data might
+    /// not be visible to those queries.
+    pub fn with_pools(
+        pools: &DualPool<sqlx::Postgres>,
+        workflow_service: &PgWorkflowsService,
+    ) -> Self {
         Self {
-            pool: pool.clone(),
+            pools: pools.clone(),
             workflow_service: workflow_service.clone(),
+            priority_aging: 0.0,
+            client: shared_client(),
+            audit_sink: None,
+            channel_namespace: ChannelNamespace::from_env(),
+            progress_updates: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Set the [PgAuditSink] that receives an [AuditEvent] for every mutation of a workflow run
+    /// (e.g. initializing, scheduling or canceling a run). Mutations are not audited by default.
+    pub fn with_audit_sink(mut self, audit_sink: &PgAuditSink) -> Self {
+        self.audit_sink = Some(audit_sink.clone());
+        self
+    }
+
+    /// Set the [ChannelNamespace] used to name the channels of listeners created by this service.
+    /// Defaults to the namespace of the `EM_CHANNEL_NS` environment variable. Must match the
+    /// namespace of the sessions that send notifications (see [with_channel_namespace]).
+    ///
+    /// [with_channel_namespace]: common::database::postgres::listener::with_channel_namespace
+    pub fn with_channel_namespace(mut self, channel_namespace: ChannelNamespace) -> Self {
+        self.channel_namespace = channel_namespace;
+        self
+    }
+
+    /// Emit an [AuditEvent] for the `action` performed against the `workflow_run_id` if an audit
+    /// sink has been set
+    async fn audit(&self, action: &str, workflow_run_id: &WorkflowRunId, details: Option<Value>) {
+        if let Some(audit_sink) = &self.audit_sink {
+            let target = format!("workflow_run:{workflow_run_id}");
+            audit_sink
+                .emit(AuditEvent::new(action, target, details))
+                .await;
+        }
+    }
+
+    /// Set the amount a scheduled workflow run's effective priority is raised for every minute it
+    /// waits to be claimed by an executor. Aging prevents low priority runs from starving behind a
+    /// steady stream of higher priority runs. A value of 0 (the default) disables aging.
+    pub const fn with_priority_aging(mut self, priority_aging: f64) -> Self {
+        self.priority_aging = priority_aging;
+        self
+    }
+
+    /// Check that the workflow specified by `workflow_id` exists and is not deprecated so it can be
+    /// used to initialize workflow runs
+    /// # Errors
+    /// This function will return an error if the workflow cannot be read or the workflow is
+    /// deprecated
+    async fn check_workflow_not_deprecated(&self, workflow_id: &WorkflowId) -> EmResult<()> {
+        let workflow = self.workflow_service.read_one(workflow_id).await?;
+        if workflow.is_deprecated {
+            return Err(EmError::InvalidRequest {
+                request: format!("Initialize workflow_run for workflow_id = {workflow_id}"),
+                reason: format!(
+                    "Cannot initialize a workflow_run with a deprecated workflow. Consider using \
+                     workflow_id = {:?}",
+                    workflow.new_workflow
+                ),
+            });
         }
+        Ok(())
     }
 
     /// Start a workflow run by executing the named procedure. Takes ownership of the `transaction`
@@ -167,6 +289,122 @@ impl PgWorkflowRunsService {
         finalize_transaction(result, transaction).await?;
         Ok(())
     }
+
+    /// Initialize and schedule a new workflow run using the `workflow_id` as a template within the
+    /// caller's `transaction`. The workflow run only exists once the caller commits the
+    /// `transaction` so a failure later in the transaction does not leave an orphaned workflow
+    /// run. Audit events are not emitted until [audit_initialize_scheduled] is called after the
+    /// commit.
+    ///
+    /// [audit_initialize_scheduled]: PgWorkflowRunsService::audit_initialize_scheduled
+    /// # Errors
+    /// This function will return an error if the workflow is missing or deprecated, or either the
+    /// `initialize_workflow_run` or `schedule_workflow_run` procedure fails
+    pub(crate) async fn initialize_scheduled(
+        &self,
+        workflow_id: &WorkflowId,
+        transaction: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> EmResult<WorkflowRunId> {
+        self.check_workflow_not_deprecated(workflow_id).await?;
+        let workflow_run_id: WorkflowRunId =
+            sqlx::query_scalar("call workflow_run.initialize_workflow_run($1,null)")
+                .bind(workflow_id)
+                .fetch_one(&mut *transaction)
+                .await?;
+        sqlx::query("call workflow_run.schedule_workflow_run($1)")
+            .bind(workflow_run_id)
+            .execute(&mut *transaction)
+            .await?;
+        Ok(workflow_run_id)
+    }
+
+    /// Schedule the workflow run specified by `workflow_run_id` within the caller's
+    /// `transaction`. The schedule audit event is not emitted so callers should
+    /// [audit][PgWorkflowRunsService::audit] the schedule once the `transaction` is committed.
+    /// # Errors
+    /// This function will return an error if the `schedule_workflow_run` procedure fails
+    async fn schedule_in_transaction(
+        workflow_run_id: &WorkflowRunId,
+        transaction: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> EmResult<()> {
+        sqlx::query("call workflow_run.schedule_workflow_run($1)")
+            .bind(workflow_run_id)
+            .execute(&mut *transaction)
+            .await?;
+        Ok(())
+    }
+
+    /// Update the progress of the workflow run specified by `workflow_run_id` within the caller's
+    /// `transaction` so the progress reflects the task changes made in the same `transaction`
+    /// # Errors
+    /// This function will return an error if the `set_workflow_run_progress` procedure fails
+    async fn update_progress_in_transaction(
+        workflow_run_id: &WorkflowRunId,
+        transaction: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> EmResult<()> {
+        sqlx::query("call workflow_run.set_workflow_run_progress($1)")
+            .bind(workflow_run_id)
+            .execute(&mut *transaction)
+            .await?;
+        Ok(())
+    }
+
+    /// Emit the initialize and schedule audit events of a workflow run created by
+    /// [initialize_scheduled][PgWorkflowRunsService::initialize_scheduled] once the transaction
+    /// that created the run is committed
+    pub(crate) async fn audit_initialize_scheduled(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+        workflow_id: &WorkflowId,
+    ) {
+        self.audit(
+            "workflow_run.initialize",
+            workflow_run_id,
+            Some(json!({ "workflow_id": workflow_id })),
+        )
+        .await;
+        self.audit("workflow_run.schedule", workflow_run_id, None)
+            .await;
+    }
+}
+
+/// Maximum duration of the request sent to check that a task url is reachable during
+/// [WorkflowRunsService::validate_workflow]
+const VALIDATE_TASK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Columns that [WorkflowRunHistory] records can be sorted by within
+/// [WorkflowRunsService::read_history]. Defaults to the most recent start first
+const WORKFLOW_RUN_HISTORY_SORT_COLUMNS: SortColumns = SortColumns::new(
+    &[
+        ("status", "wr.status"),
+        ("run_start", "wr.run_start"),
+        ("progress", "wr.progress"),
+    ],
+    "wr.run_start desc, wr.workflow_run_id desc",
+);
+
+/// Validate a single workflow `task` without running the task. The task's url must be parseable
+/// and reachable (any response to a `HEAD` request is accepted, since task services are not
+/// required to support the method) and the task's parameters must satisfy the
+/// `parameters_schema` of the task.
+async fn validate_task(
+    client: &Client,
+    task: &WorkflowTask,
+    parameters_schema: Option<&Value>,
+) -> TaskValidation {
+    let mut errors = Vec::new();
+    match Url::parse(&task.url) {
+        Ok(url) => {
+            if let Err(error) = client.head(url).timeout(VALIDATE_TASK_TIMEOUT).send().await {
+                errors.push(format!("Task url could not be reached. {error}"));
+            }
+        }
+        Err(error) => errors.push(format!("Task url is not valid. {error}")),
+    }
+    if let Err(error) = validate_parameters_schema(task.parameters.as_ref(), parameters_schema) {
+        errors.push(error.to_string());
+    }
+    TaskValidation::new(task, errors)
 }
 
 #[async_trait::async_trait]
@@ -174,37 +412,141 @@ impl WorkflowRunsService for PgWorkflowRunsService {
     type CancelListener = PgChangeListener<WorkflowRunCancelMessage>;
     type Database = Postgres;
     type ScheduledListener = PgChangeListener<WorkflowRunScheduledMessage>;
+    type StatusListener = PgChangeListener<WorkflowRunStatusMessage>;
     type WorkflowService = PgWorkflowsService;
 
     async fn initialize(&self, workflow_id: &WorkflowId) -> EmResult<WorkflowRun> {
-        let workflow = self.workflow_service.read_one(workflow_id).await?;
-        if workflow.is_deprecated {
-            return Err(EmError::Generic(format!(
-                "Cannot initialize a workflow_run with a deprecated workflow. Consider using \
-                 workflow_id = {:?}",
-                workflow.new_workflow
-            )));
-        }
+        self.check_workflow_not_deprecated(workflow_id).await?;
 
         let workflow_run_id =
             sqlx::query_scalar("call workflow_run.initialize_workflow_run($1,null)")
                 .bind(workflow_id)
-                .fetch_one(&self.pool)
+                .fetch_one(self.pools.primary())
                 .await?;
+        self.audit(
+            "workflow_run.initialize",
+            &workflow_run_id,
+            Some(json!({ "workflow_id": workflow_id })),
+        )
+        .await;
         self.read_one(&workflow_run_id).await
     }
 
+    async fn initialize_batch(
+        &self,
+        workflow_id: &WorkflowId,
+        count: usize,
+    ) -> EmResult<Vec<WorkflowRun>> {
+        if count == 0 || count > MAX_INITIALIZE_BATCH_SIZE {
+            return Err(EmError::InvalidRequest {
+                request: format!(
+                    "Initialize {count} workflow_runs for workflow_id = {workflow_id}"
+                ),
+                reason: format!(
+                    "Workflow run batch size must be between 1 and {MAX_INITIALIZE_BATCH_SIZE}"
+                ),
+            });
+        }
+        self.check_workflow_not_deprecated(workflow_id).await?;
+
+        let workflow_run_ids: Vec<i64> =
+            sqlx::query_scalar("call workflow_run.initialize_workflow_runs($1,$2,null)")
+                .bind(workflow_id)
+                .bind(count as i32)
+                .fetch_one(self.pools.primary())
+                .await?;
+        let result: Vec<WorkflowRun> = sqlx::query_as(
+            r#"
+            select
+                wr.workflow_run_id, wr.workflow_id, wr.status, wr.executor_id, wr.progress,
+                wr.priority, wr.cancel_reason, wr.tasks
+            from workflow_run.v_workflow_runs wr
+            where wr.workflow_run_id = any($1)
+            order by wr.workflow_run_id"#,
+        )
+        .bind(workflow_run_ids)
+        .fetch_all(self.pools.primary())
+        .await?;
+        for workflow_run in &result {
+            self.audit(
+                "workflow_run.initialize",
+                &workflow_run.workflow_run_id,
+                Some(json!({ "workflow_id": workflow_id })),
+            )
+            .await;
+        }
+        Ok(result)
+    }
+
     async fn read_one(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
         let result = sqlx::query_as(
             r#"
             select
                 wr.workflow_run_id, wr.workflow_id, wr.status, wr.executor_id, wr.progress,
-                wr.tasks
+                wr.priority, wr.cancel_reason, wr.tasks
             from workflow_run.v_workflow_runs wr
             where wr.workflow_run_id = $1"#,
         )
         .bind(workflow_run_id)
-        .fetch_optional(&self.pool)
+        .fetch_optional(self.pools.primary())
+        .await?;
+        result.map_or_else(
+            || {
+                Err(EmError::MissingRecord {
+                    pk: workflow_run_id.to_string(),
+                })
+            },
+            Ok,
+        )
+    }
+
+    async fn read_one_filtered(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+        task_status_filter: Option<Vec<TaskStatus>>,
+    ) -> EmResult<WorkflowRun> {
+        let statuses: Option<Vec<String>> =
+            task_status_filter.map(|statuses| statuses.iter().map(ToString::to_string).collect());
+        let result = sqlx::query_as(
+            r#"
+            select
+                wr.workflow_run_id, wr.workflow_id, wr.status, wr.executor_id, wr.progress,
+                wr.priority, wr.cancel_reason,
+                workflow_run.filter_workflow_run_tasks(
+                    wr.tasks,
+                    $2::text[]::workflow_run.task_status[]
+                ) tasks
+            from workflow_run.v_workflow_runs wr
+            where wr.workflow_run_id = $1"#,
+        )
+        .bind(workflow_run_id)
+        .bind(statuses)
+        .fetch_optional(self.pools.primary())
+        .await?;
+        result.map_or_else(
+            || {
+                Err(EmError::MissingRecord {
+                    pk: workflow_run_id.to_string(),
+                })
+            },
+            Ok,
+        )
+    }
+
+    async fn read_one_summary(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+    ) -> EmResult<WorkflowRunSummary> {
+        let result = sqlx::query_as(
+            r#"
+            select
+                wr.workflow_run_id, wr.workflow_id, wr.status, wr.executor_id, wr.progress,
+                wr.priority, wr.cancel_reason
+            from workflow_run.workflow_runs wr
+            where wr.workflow_run_id = $1"#,
+        )
+        .bind(workflow_run_id)
+        .fetch_optional(self.pools.primary())
         .await?;
         result.map_or_else(
             || {
@@ -216,26 +558,173 @@ impl WorkflowRunsService for PgWorkflowRunsService {
         )
     }
 
+    async fn read_timing(&self, workflow_run_id: &WorkflowRunId) -> EmResult<RunTimingSummary> {
+        let workflow_run = self.read_one(workflow_run_id).await?;
+        let (scheduled_at, run_start): (Option<NaiveDateTime>, Option<NaiveDateTime>) =
+            sqlx::query_as(
+                r#"
+                select wr.scheduled_at, wr.run_start
+                from workflow_run.workflow_runs wr
+                where wr.workflow_run_id = $1"#,
+            )
+            .bind(workflow_run_id)
+            .fetch_one(self.pools.primary())
+            .await?;
+        Ok(RunTimingSummary::new(
+            workflow_run,
+            scheduled_at,
+            run_start,
+            Utc::now().naive_utc(),
+        ))
+    }
+
+    async fn read_many_by_ids(
+        &self,
+        workflow_run_ids: &[WorkflowRunId],
+    ) -> EmResult<Vec<WorkflowRun>> {
+        if workflow_run_ids.len() > MAX_READ_MANY_BATCH_SIZE {
+            return Err(format!(
+                "Cannot read more than {MAX_READ_MANY_BATCH_SIZE} workflow runs at once. Got {}",
+                workflow_run_ids.len()
+            )
+            .into());
+        }
+        if workflow_run_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let ids: Vec<i64> = workflow_run_ids
+            .iter()
+            .map(|workflow_run_id| workflow_run_id.into_inner())
+            .collect();
+        let result = sqlx::query_as(
+            r#"
+            select
+                wr.workflow_run_id, wr.workflow_id, wr.status, wr.executor_id, wr.progress,
+                wr.priority, wr.cancel_reason, wr.tasks
+            from workflow_run.v_workflow_runs wr
+            join unnest($1::bigint[]) with ordinality as ids(workflow_run_id, id_order)
+            on ids.workflow_run_id = wr.workflow_run_id
+            order by ids.id_order"#,
+        )
+        .bind(ids)
+        .fetch_all(self.pools.primary())
+        .await?;
+        Ok(result)
+    }
+
     async fn read_active(&self) -> EmResult<Vec<WorkflowRun>> {
         let result = sqlx::query_as(
             r#"
             select
                 wr.workflow_run_id, wr.workflow_id, wr.status, wr.executor_id, wr.progress,
-                wr.tasks
+                wr.priority, wr.cancel_reason, wr.tasks
             from workflow_run.v_workflow_runs wr
             where wr.status != 'Complete'::workflow_run.workflow_run_status"#,
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.pools.primary())
+        .await?;
+        Ok(result)
+    }
+
+    async fn read_active_summaries(&self) -> EmResult<Vec<WorkflowRunSummary>> {
+        let result = sqlx::query_as(
+            r#"
+            select
+                wr.workflow_run_id, wr.workflow_id, wr.status, wr.executor_id, wr.progress,
+                wr.priority, wr.cancel_reason
+            from workflow_run.workflow_runs wr
+            where wr.status != 'Complete'::workflow_run.workflow_run_status"#,
+        )
+        .fetch_all(self.pools.primary())
+        .await?;
+        Ok(result)
+    }
+
+    async fn read_by_task_status(&self, status: TaskStatus) -> EmResult<Vec<WorkflowRunSummary>> {
+        let result = sqlx::query_as(
+            r#"
+            select
+                wr.workflow_run_id, wr.workflow_id, wr.status, wr.executor_id, wr.progress,
+                wr.priority, wr.cancel_reason
+            from workflow_run.workflow_runs wr
+            where exists(
+                select 1
+                from workflow_run.task_queue tq
+                where
+                    tq.workflow_run_id = wr.workflow_run_id
+                    and tq.status = $1
+            )
+            order by wr.workflow_run_id"#,
+        )
+        .bind(status)
+        .fetch_all(self.pools.primary())
+        .await?;
+        Ok(result)
+    }
+
+    async fn read_many_after(&self, page: &CursorPagination) -> EmResult<CursorPage<WorkflowRun>> {
+        CursorPaginationValidator::validate_request(page)?;
+        let items = sqlx::query_as(
+            r#"
+            select
+                wr.workflow_run_id, wr.workflow_id, wr.status, wr.executor_id, wr.progress,
+                wr.priority, wr.cancel_reason, wr.tasks
+            from workflow_run.v_workflow_runs wr
+            where $1::bigint is null or wr.workflow_run_id > $1
+            order by wr.workflow_run_id
+            limit $2"#,
+        )
+        .bind(page.after_key()?)
+        .bind(page.fetch_limit())
+        .fetch_all(self.pools.primary())
         .await?;
+        Ok(CursorPage::from_items(
+            items,
+            page,
+            |workflow_run: &WorkflowRun| workflow_run.workflow_run_id.into_inner(),
+        ))
+    }
+
+    async fn read_history(
+        &self,
+        filter: &WorkflowRunFilter,
+        sort: &Sort,
+    ) -> EmResult<Vec<WorkflowRunHistory>> {
+        let statuses: Vec<String> = filter.status.iter().map(ToString::to_string).collect();
+        let (started_after, started_before) = filter.started_between;
+        let query = format!(
+            r#"
+            select
+                wr.workflow_run_id, wr.workflow_id, wr.workflow_name, wr.status, wr.executor_id,
+                wr.progress, wr.priority, wr.run_start, wr.run_end
+            from workflow_run.v_workflow_run_history wr
+            where
+                ($1::bigint is null or wr.workflow_id = $1)
+                and (
+                    cardinality($2::text[]) = 0
+                    or wr.status = any($2::text[]::workflow_run.workflow_run_status[])
+                )
+                and wr.run_start between $3 and $4
+            order by {}"#,
+            WORKFLOW_RUN_HISTORY_SORT_COLUMNS.order_by(sort)
+        );
+        let result = sqlx::query_as(&query)
+            .bind(filter.workflow_id)
+            .bind(statuses)
+            .bind(started_after)
+            .bind(started_before)
+            .fetch_all(self.pools.replica())
+            .await?;
         Ok(result)
     }
 
     async fn next_workflow_run(&self, executor_id: &ExecutorId) -> EmResult<Option<WorkflowRunId>> {
-        let mut transaction = self.pool.begin().await?;
+        let mut transaction = self.pools.primary().begin().await?;
         let next_workflow: Option<(WorkflowRunId, bool)> = sqlx::query_as(
-            "select workflow_run_id, is_valid from workflow_run.next_workflow_run($1)",
+            "select workflow_run_id, is_valid from workflow_run.next_workflow_run($1,$2)",
         )
         .bind(executor_id)
+        .bind(self.priority_aging)
         .fetch_optional(&mut transaction)
         .await?;
         let Some((workflow_run_id, is_valid)) = next_workflow else {
@@ -252,19 +741,29 @@ impl WorkflowRunsService for PgWorkflowRunsService {
         Ok(Some(workflow_run_id))
     }
 
-    async fn cancel(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
-        sqlx::query("call workflow_run.cancel_workflow_run($1)")
+    async fn cancel_with_reason(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+        reason: Option<&str>,
+    ) -> EmResult<WorkflowRun> {
+        sqlx::query("call workflow_run.cancel_workflow_run($1,$2)")
             .bind(workflow_run_id)
-            .execute(&self.pool)
+            .bind(reason)
+            .execute(self.pools.primary())
             .await?;
+        let details = reason.map(|reason| json!({ "reason": reason }));
+        self.audit("workflow_run.cancel", workflow_run_id, details)
+            .await;
         self.read_one(workflow_run_id).await
     }
 
     async fn schedule(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
         sqlx::query("call workflow_run.schedule_workflow_run($1)")
             .bind(workflow_run_id)
-            .execute(&self.pool)
+            .execute(self.pools.primary())
             .await?;
+        self.audit("workflow_run.schedule", workflow_run_id, None)
+            .await;
         self.read_one(workflow_run_id).await
     }
 
@@ -276,8 +775,33 @@ impl WorkflowRunsService for PgWorkflowRunsService {
         sqlx::query("call workflow_run.schedule_workflow_run($1,$2)")
             .bind(workflow_run_id)
             .bind(executor_id)
-            .execute(&self.pool)
+            .execute(self.pools.primary())
+            .await?;
+        self.audit(
+            "workflow_run.schedule",
+            workflow_run_id,
+            Some(json!({ "executor_id": executor_id })),
+        )
+        .await;
+        self.read_one(workflow_run_id).await
+    }
+
+    async fn schedule_with_priority(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+        priority: i16,
+    ) -> EmResult<WorkflowRun> {
+        sqlx::query("call workflow_run.schedule_workflow_run($1,null,$2)")
+            .bind(workflow_run_id)
+            .bind(priority)
+            .execute(self.pools.primary())
             .await?;
+        self.audit(
+            "workflow_run.schedule",
+            workflow_run_id,
+            Some(json!({ "priority": priority })),
+        )
+        .await;
         self.read_one(workflow_run_id).await
     }
 
@@ -293,75 +817,265 @@ impl WorkflowRunsService for PgWorkflowRunsService {
 
         sqlx::query("call workflow_run.restart_workflow_run($1)")
             .bind(workflow_run_id)
-            .execute(&self.pool)
+            .execute(self.pools.primary())
             .await?;
+        self.audit("workflow_run.restart", workflow_run_id, None)
+            .await;
         self.read_one(workflow_run_id).await
     }
 
-    async fn update_progress(&self, workflow_run_id: &WorkflowRunId) -> EmResult<()> {
-        sqlx::query("call workflow_run.set_workflow_run_progress($1)")
+    async fn pause(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
+        let workflow_run = self.read_one(workflow_run_id).await?;
+        if workflow_run.status != WorkflowRunStatus::Running {
+            return Err(format!(
+                "Cannot pause a workflow run that is not running. Status = {}",
+                workflow_run.status
+            )
+            .into());
+        }
+
+        sqlx::query("call workflow_run.pause_workflow_run($1)")
             .bind(workflow_run_id)
-            .execute(&self.pool)
+            .execute(self.pools.primary())
             .await?;
-        Ok(())
+        self.audit("workflow_run.pause", workflow_run_id, None)
+            .await;
+        self.read_one(workflow_run_id).await
     }
 
-    async fn complete(&self, workflow_run_id: &WorkflowRunId) -> EmResult<()> {
-        sqlx::query("call workflow_run.complete_workflow_run($1)")
+    async fn resume(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
+        let workflow_run = self.read_one(workflow_run_id).await?;
+        if workflow_run.status != WorkflowRunStatus::Paused {
+            return Err(format!(
+                "Cannot resume a workflow run that is not paused. Status = {}",
+                workflow_run.status
+            )
+            .into());
+        }
+        if workflow_run.executor_id.is_some() {
+            return Err(
+                "Cannot resume a workflow run that is still finishing its running tasks. Please \
+                 try again once the running tasks are done"
+                    .into(),
+            );
+        }
+        if workflow_run.tasks.iter().any(|task| {
+            matches!(
+                task.task_status,
+                TaskStatus::Paused | TaskStatus::Failed | TaskStatus::RuleBroken
+            )
+        }) {
+            return Err(
+                "Cannot resume a workflow run with a paused, failed or rule broken task. Please \
+                 retry the task instead"
+                    .into(),
+            );
+        }
+
+        sqlx::query("call workflow_run.resume_workflow_run($1)")
             .bind(workflow_run_id)
-            .execute(&self.pool)
+            .execute(self.pools.primary())
             .await?;
-        Ok(())
+        self.audit("workflow_run.resume", workflow_run_id, None)
+            .await;
+        self.read_one(workflow_run_id).await
     }
 
-    async fn all_executor_workflows(
-        &self,
-        executor_id: &ExecutorId,
-    ) -> EmResult<Vec<ExecutorWorkflowRun>> {
-        let result = sqlx::query_as(
+    async fn validate_workflow(&self, workflow_id: &WorkflowId) -> EmResult<ValidationReport> {
+        let workflow = self.workflow_service.read_one(workflow_id).await?;
+        let task_ids: Vec<TaskId> = workflow.tasks.iter().map(|task| task.task_id).collect();
+        let schemas: Vec<(TaskId, Option<Value>)> = sqlx::query_as(
             r#"
-            select workflow_run_id, status, is_valid
-            from workflow_run.executor_workflows($1)"#,
+            select t.task_id, t.parameters_schema
+            from workflow.v_tasks t
+            where t.task_id = any($1)"#,
         )
-        .bind(executor_id)
-        .fetch_all(&self.pool)
+        .bind(task_ids)
+        .fetch_all(self.pools.primary())
         .await?;
-        Ok(result)
+        let validations = workflow.tasks.iter().map(|task| {
+            let parameters_schema = schemas
+                .iter()
+                .find(|(task_id, _)| *task_id == task.task_id)
+                .and_then(|(_, schema)| schema.as_ref());
+            validate_task(&self.client, task, parameters_schema)
+        });
+        let tasks = join_all(validations).await;
+        Ok(ValidationReport::new(workflow.workflow_id, tasks))
     }
 
-    async fn start_move(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
-        sqlx::query("call workflow_run.start_workflow_run_move($1)")
-            .bind(workflow_run_id)
-            .execute(&self.pool)
-            .await?;
-        self.read_one(workflow_run_id).await
-    }
+    async fn clone_run(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
+        let source = self.read_one(workflow_run_id).await?;
+        let workflow_id = WorkflowId::from(source.workflow_id);
+        self.check_workflow_not_deprecated(&workflow_id).await?;
 
-    async fn complete_move(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
-        sqlx::query("call workflow_run.complete_workflow_run_move($1)")
-            .bind(workflow_run_id)
-            .execute(&self.pool)
+        let mut transaction = self.pools.primary().begin().await?;
+        let new_workflow_run_id: WorkflowRunId =
+            match sqlx::query_scalar("call workflow_run.initialize_workflow_run($1,null)")
+                .bind(workflow_id)
+                .fetch_one(&mut transaction)
+                .await
+            {
+                Ok(inner) => inner,
+                Err(error) => {
+                    transaction.rollback().await?;
+                    return Err(error.into());
+                }
+            };
+        let result = sqlx::query(
+            r#"
+            update workflow_run.task_queue tq
+            set parameters = s.parameters
+            from workflow_run.task_queue s
+            where
+                tq.workflow_run_id = $2
+                and s.workflow_run_id = $1
+                and s.task_order = tq.task_order
+                and s.task_id = tq.task_id"#,
+        )
+        .bind(workflow_run_id)
+        .bind(new_workflow_run_id)
+        .execute(&mut transaction)
+        .await;
+        finalize_transaction(result, transaction).await?;
+        self.audit(
+            "workflow_run.clone",
+            &new_workflow_run_id,
+            Some(json!({ "source_workflow_run_id": workflow_run_id })),
+        )
+        .await;
+        self.read_one(&new_workflow_run_id).await
+    }
+
+    async fn update_progress(&self, workflow_run_id: &WorkflowRunId) -> EmResult<()> {
+        sqlx::query("call workflow_run.set_workflow_run_progress($1)")
+            .bind(workflow_run_id)
+            .execute(self.pools.primary())
+            .await?;
+        Ok(())
+    }
+
+    async fn complete(&self, workflow_run_id: &WorkflowRunId) -> EmResult<()> {
+        sqlx::query("call workflow_run.complete_workflow_run($1)")
+            .bind(workflow_run_id)
+            .execute(self.pools.primary())
+            .await?;
+        Ok(())
+    }
+
+    async fn all_executor_workflows(
+        &self,
+        executor_id: &ExecutorId,
+    ) -> EmResult<Vec<ExecutorWorkflowRun>> {
+        let result = sqlx::query_as(
+            r#"
+            select workflow_run_id, status, is_valid
+            from workflow_run.executor_workflows($1)"#,
+        )
+        .bind(executor_id)
+        .fetch_all(self.pools.primary())
+        .await?;
+        Ok(result)
+    }
+
+    async fn start_move(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
+        sqlx::query("call workflow_run.start_workflow_run_move($1)")
+            .bind(workflow_run_id)
+            .execute(self.pools.primary())
+            .await?;
+        self.read_one(workflow_run_id).await
+    }
+
+    async fn complete_move(&self, workflow_run_id: &WorkflowRunId) -> EmResult<WorkflowRun> {
+        sqlx::query("call workflow_run.complete_workflow_run_move($1)")
+            .bind(workflow_run_id)
+            .execute(self.pools.primary())
             .await?;
         self.read_one(workflow_run_id).await
     }
 
+    async fn recover_orphaned(&self) -> EmResult<Vec<WorkflowRunId>> {
+        let workflow_run_ids: Vec<WorkflowRunId> =
+            sqlx::query_scalar("select r from workflow_run.recover_orphaned_workflow_runs() r")
+                .fetch_all(self.pools.primary())
+                .await?;
+        for workflow_run_id in &workflow_run_ids {
+            self.audit("workflow_run.recover", workflow_run_id, None)
+                .await;
+        }
+        Ok(workflow_run_ids)
+    }
+
     async fn scheduled_listener(
         &self,
         executor_id: &ExecutorId,
     ) -> EmResult<Self::ScheduledListener> {
-        let mut listener = PgListener::connect_with(&self.pool).await?;
-        listener
-            .listen(&format!("wr_scheduled_{}", executor_id))
-            .await?;
-        Ok(PgChangeListener::new(listener))
+        let channel = self
+            .channel_namespace
+            .channel(&format!("wr_scheduled_{executor_id}"));
+        PgChangeListener::connect(self.pools.primary(), &channel).await
     }
 
     async fn cancel_listener(&self, executor_id: &ExecutorId) -> EmResult<Self::CancelListener> {
-        let mut listener = PgListener::connect_with(&self.pool).await?;
-        listener
-            .listen(&format!("wr_canceled_{}", executor_id))
-            .await?;
-        Ok(PgChangeListener::new(listener))
+        let channel = self
+            .channel_namespace
+            .channel(&format!("wr_canceled_{executor_id}"));
+        PgChangeListener::connect(self.pools.primary(), &channel).await
+    }
+
+    fn progress_updates(&self) -> broadcast::Receiver<WorkflowRunProgress> {
+        self.progress_updates
+            .get_or_init(|| {
+                let channel = self.channel_namespace.channel("wr_progress_updates");
+                spawn_broadcast(
+                    self.pools.primary(),
+                    &[&channel],
+                    PROGRESS_UPDATES_BUFFER_SIZE,
+                    |message: WorkflowRunProgressMessage| message.0,
+                )
+            })
+            .subscribe()
+    }
+
+    async fn status_listener(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+    ) -> EmResult<Self::StatusListener> {
+        let channel = self
+            .channel_namespace
+            .channel(&format!("wr_status_{workflow_run_id}"));
+        PgChangeListener::connect(self.pools.primary(), &channel).await
+    }
+
+    async fn subscribe(
+        &self,
+        workflow_run_id: &WorkflowRunId,
+    ) -> EmResult<BoxStream<'static, WorkflowRunStatus>> {
+        // Subscribe before reading the current state so no change between the 2 is missed
+        let listener = self.status_listener(workflow_run_id).await?;
+        let current = self.read_one(workflow_run_id).await?.status;
+        let is_done = current.is_terminal();
+        let updates = stream::unfold((listener, is_done), |(mut listener, is_done)| async move {
+            if is_done {
+                return None;
+            }
+            loop {
+                match listener.recv().await {
+                    Ok(message) => {
+                        let Some(status) = message.0 else {
+                            continue;
+                        };
+                        let is_done = status.is_terminal();
+                        return Some((status, (listener, is_done)));
+                    }
+                    Err(error) => {
+                        error!("Workflow run status stream closed. {error}");
+                        return None;
+                    }
+                }
+            }
+        });
+        Ok(stream::once(async move { current }).chain(updates).boxed())
     }
 }
 
@@ -411,11 +1125,63 @@ impl PgHasArrayType for TaskRule {
     }
 }
 
+impl Encode<'_, sqlx::Postgres> for TaskLog {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        let mut encoder = PgRecordEncoder::new(buf);
+        encoder.encode(self.level);
+        encoder.encode(&self.message);
+        encoder.encode(self.logged_at);
+        encoder.finish();
+        IsNull::No
+    }
+
+    fn size_hint(&self) -> usize {
+        3usize * (4 + 4)
+            + <TaskLogLevel as Encode<sqlx::Postgres>>::size_hint(&self.level)
+            + <String as Encode<sqlx::Postgres>>::size_hint(&self.message)
+            + <NaiveDateTime as Encode<sqlx::Postgres>>::size_hint(&self.logged_at)
+    }
+}
+
+impl<'r> Decode<'r, sqlx::Postgres> for TaskLog {
+    fn decode(
+        value: PgValueRef<'r>,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        let mut decoder = PgRecordDecoder::new(value)?;
+        let level: TaskLogLevel = decoder.try_decode()?;
+        let message: String = decoder.try_decode()?;
+        let logged_at: NaiveDateTime = decoder.try_decode()?;
+        Ok(Self {
+            level,
+            message,
+            logged_at,
+        })
+    }
+}
+
+impl Type<sqlx::Postgres> for TaskLog {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("task_log")
+    }
+}
+
+impl PgHasArrayType for TaskLog {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_task_log")
+    }
+}
+
 /// Postgres implementation of TaskQueueService
 #[derive(Clone)]
 pub struct PgTaskQueueService {
     pool: PgPool,
     workflow_runs_service: PgWorkflowRunsService,
+    /// Optional metrics updated as task runs are completed, failed and timed
+    metrics: Option<EngineMetrics>,
+    /// Maximum number of log lines kept per task. Older lines are removed as new lines arrive
+    max_task_log_lines: i32,
+    /// Variables used to expand `${NAME}` templates within task urls before each run
+    url_variables: TaskUrlVariables,
 }
 
 impl PgTaskQueueService {
@@ -424,18 +1190,62 @@ impl PgTaskQueueService {
         Self {
             pool: pool.clone(),
             workflow_runs_service: workflow_runs_service.clone(),
+            metrics: None,
+            max_task_log_lines: DEFAULT_MAX_TASK_LOG_LINES,
+            url_variables: TaskUrlVariables::default(),
+        }
+    }
+
+    /// Expand `${NAME}` templates within task urls using the specified `url_variables`
+    pub fn with_url_variables(mut self, url_variables: TaskUrlVariables) -> Self {
+        self.url_variables = url_variables;
+        self
+    }
+
+    /// Set the maximum number of log lines kept per task. Once the limit is reached, the oldest
+    /// lines are removed as new lines are appended.
+    pub const fn with_max_task_log_lines(mut self, max_task_log_lines: i32) -> Self {
+        self.max_task_log_lines = max_task_log_lines;
+        self
+    }
+
+    /// Record task run counts and `run_task` durations in the specified `metrics`
+    pub fn with_metrics(mut self, metrics: &EngineMetrics) -> Self {
+        self.metrics = Some(metrics.clone());
+        self
+    }
+
+    /// Run the task `record` to completion, rendering and validating the parameters and applying
+    /// the timeout (if any) of the task. See [TaskQueueService::run_task] for more details.
+    async fn execute_task(
+        &self,
+        record: &TaskQueueRecord,
+    ) -> EmResult<(bool, Option<String>, Option<Value>)> {
+        let mut record = record.clone();
+        record.expand_url(&self.url_variables)?;
+        record.render_parameters()?;
+        record.validate_parameters()?;
+        let mut close_event = self.pool.close_event();
+        let task_run = close_event.do_until(self.remote_task_run(&record));
+        let Some(timeout) = record.timeout else {
+            return task_run.await?;
+        };
+        match tokio::time::timeout(timeout, task_run).await {
+            Ok(result) => result?,
+            Err(_) => Err(EmError::TaskTimeout(timeout)),
         }
     }
 
     /// Process a response `message` from a remote task run. The expected format is of MessagePack
     /// and the contents are parsed to a [TaskResponse] variant. If the message is a
-    /// [TaskResponse::Done] message, the contents are returned as a tuple. Otherwise, a [None]
-    /// value is returned to signify the message has been processed but there are more to come.
+    /// [TaskResponse::Done] message, the contents (including the optional structured output) are
+    /// returned as a tuple. Otherwise, a [None] value is returned to signify the message has been
+    /// processed but there are more to come.
     async fn process_response_message(
         &self,
         message: &[u8],
         record: &TaskQueueRecord,
-    ) -> EmResult<Option<(bool, Option<String>)>> {
+    ) -> EmResult<Option<(bool, Option<String>, Option<Value>)>> {
         match rmp_serde::from_slice(message)? {
             TaskResponse::Progress(progress) => {
                 let request = TaskQueueRequest {
@@ -451,27 +1261,62 @@ impl PgTaskQueueService {
                 };
                 self.append_task_rule(&request, &rule).await?
             }
-            TaskResponse::Done { success, message } => return Ok(Some((success, message))),
+            TaskResponse::Log { level, message } => {
+                let request = TaskQueueRequest {
+                    workflow_run_id: record.workflow_run_id,
+                    task_order: record.task_order,
+                };
+                self.append_task_log(&request, level, &message).await?
+            }
+            TaskResponse::Done {
+                success,
+                message,
+                output,
+            } => return Ok(Some((success, message, output))),
         }
         Ok(None)
     }
 
-    /// Execute a remove task for the specified task `record`. Creates a new [Client] and proceeds
-    /// to make a POST request against the specified task url with the `record` as a serialized
-    /// MessagePack body. The result of the request is interpreted as a byte stream and
+    /// Execute a remove task for the specified task `record`. Uses the shared
+    /// [streaming_client] to make a POST request against the specified task url with the `record`
+    /// as a serialized MessagePack body. If the task endpoint does not respond with a success
+    /// status, a [TaskHttpStatus][EmError::TaskHttpStatus] error containing the response body is
+    /// returned. Otherwise, the result of the request is interpreted as a byte stream and
     /// [TaskResponse] messages are parsed from it until a [TaskResponse::Done] message is sent. If
-    /// the stream ends without a [TaskResponse::Done] message, a [ExitedTask][EmError::ExitedTask]
-    /// error is returned.
-    async fn remote_task_run(&self, record: &TaskQueueRecord) -> EmResult<(bool, Option<String>)> {
-        let client = Client::new();
+    /// the stream ends without a [TaskResponse::Done] message, a
+    /// [ExitedTask][EmError::ExitedTask] error is returned. Rather than a total deadline, the
+    /// task endpoint must respond and keep sending messages within the configured stream idle
+    /// timeout, otherwise a [TaskStreamIdle][EmError::TaskStreamIdle] error is returned.
+    async fn remote_task_run(
+        &self,
+        record: &TaskQueueRecord,
+    ) -> EmResult<(bool, Option<String>, Option<Value>)> {
+        let idle_timeout = http_client_config().stream_idle_timeout;
         let buffer = rmp_serde::to_vec(record)?;
-        let mut stream = client
+        let request = streaming_client()
             .request(Method::POST, &record.url)
             .body(buffer)
-            .send()
-            .await?
-            .bytes_stream();
-        while let Some(chunk) = stream.next().await {
+            .send();
+        let Ok(response) = tokio::time::timeout(idle_timeout, request).await else {
+            return Err(EmError::TaskStreamIdle(idle_timeout));
+        };
+        let response = response?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(EmError::TaskHttpStatus {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        let mut stream = response.bytes_stream();
+        loop {
+            let Ok(next_chunk) = tokio::time::timeout(idle_timeout, stream.next()).await else {
+                return Err(EmError::TaskStreamIdle(idle_timeout));
+            };
+            let Some(chunk) = next_chunk else {
+                break;
+            };
             let message = match chunk {
                 Ok(message) => message,
                 Err(error) => return Err(error.into()),
@@ -493,7 +1338,9 @@ impl TaskQueueService for PgTaskQueueService {
     async fn read_one(&self, request: &TaskQueueRequest) -> EmResult<TaskQueueRecord> {
         let result = sqlx::query_as(
             r#"
-            select tq.workflow_run_id, tq.task_order, tq.task_id, tq.status, tq.parameters, tq.url
+            select
+                tq.workflow_run_id, tq.task_order, tq.task_id, tq.status, tq.parameters, tq.url,
+                tq.timeout, tq.parameters_schema, tq.retry_count, tq.max_retries, tq.task_outputs
             from workflow_run.v_task_queue_record tq
             where
                 tq.workflow_run_id = $1
@@ -513,6 +1360,55 @@ impl TaskQueueService for PgTaskQueueService {
         )
     }
 
+    async fn read_task_detail(&self, request: &TaskQueueRequest) -> EmResult<TaskDetail> {
+        let result = sqlx::query_as(
+            r#"
+            select
+                td.workflow_run_id, td.task_order, td.task_id, td.name, td.description, td.status,
+                td.parameters, td.output, td.rules, td.task_start, td.task_end, td.progress,
+                td.logs, td.retry_count, td.max_retries, td.output_data
+            from workflow_run.v_task_queue_detail td
+            where
+                td.workflow_run_id = $1
+                and td.task_order = $2"#,
+        )
+        .bind(request.workflow_run_id)
+        .bind(request.task_order)
+        .fetch_optional(&self.pool)
+        .await?;
+        result.map_or_else(
+            || {
+                Err(EmError::MissingRecord {
+                    pk: format!("{} + {}", request.workflow_run_id, request.task_order),
+                })
+            },
+            Ok,
+        )
+    }
+
+    async fn read_task_rules(&self, request: &TaskQueueRequest) -> EmResult<Vec<TaskRule>> {
+        let result: Option<Option<Vec<TaskRule>>> = sqlx::query_scalar(
+            r#"
+            select tq.rules
+            from workflow_run.task_queue tq
+            where
+                tq.workflow_run_id = $1
+                and tq.task_order = $2"#,
+        )
+        .bind(request.workflow_run_id)
+        .bind(request.task_order)
+        .fetch_optional(&self.pool)
+        .await?;
+        result.map_or_else(
+            || {
+                Err(EmError::MissingRecord {
+                    pk: format!("{} + {}", request.workflow_run_id, request.task_order),
+                })
+            },
+            |rules| Ok(rules.unwrap_or_default()),
+        )
+    }
+
     async fn append_task_rule(&self, request: &TaskQueueRequest, rule: &TaskRule) -> EmResult<()> {
         if rule.name.trim().is_empty() {
             return Err("Task rule attribute 'name' cannot be empty or whitespace".into());
@@ -526,6 +1422,23 @@ impl TaskQueueService for PgTaskQueueService {
         Ok(())
     }
 
+    async fn append_task_log(
+        &self,
+        request: &TaskQueueRequest,
+        level: TaskLogLevel,
+        message: &str,
+    ) -> EmResult<()> {
+        sqlx::query("call workflow_run.append_task_log($1,$2,$3,$4,$5)")
+            .bind(request.workflow_run_id)
+            .bind(request.task_order)
+            .bind(level)
+            .bind(message)
+            .bind(self.max_task_log_lines)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     async fn set_task_progress(&self, request: &TaskQueueRequest, progress: i16) -> EmResult<()> {
         sqlx::query("call workflow_run.set_task_progress($1,$2,$3)")
             .bind(request.workflow_run_id)
@@ -541,112 +1454,137 @@ impl TaskQueueService for PgTaskQueueService {
         if task_queue_record.status != TaskStatus::RuleBroken
             && task_queue_record.status != TaskStatus::Failed
         {
-            return Err(EmError::Generic(
-                "Cannot retry task. Status must be 'Failed' or 'Rule Broken'".to_owned(),
-            ));
+            return Err(EmError::InvalidRequest {
+                request: format!(
+                    "Retry task_order = {} of workflow_run_id = {}",
+                    request.task_order, request.workflow_run_id
+                ),
+                reason: "Cannot retry task. Status must be 'Failed' or 'Rule Broken'".to_owned(),
+            });
         }
 
-        let mut transaction = self.pool.begin().await?;
-
-        let retry_result = sqlx::query("call workflow_run.retry_task($1,$2)")
-            .bind(request.workflow_run_id)
-            .bind(request.task_order)
-            .execute(&mut transaction)
+        with_retryable_transaction(
+            &self.pool,
+            DEFAULT_TRANSACTION_ATTEMPTS,
+            |mut transaction| async move {
+                let result = async {
+                    sqlx::query("call workflow_run.retry_task($1,$2)")
+                        .bind(request.workflow_run_id)
+                        .bind(request.task_order)
+                        .execute(&mut transaction)
+                        .await?;
+                    PgWorkflowRunsService::schedule_in_transaction(
+                        &request.workflow_run_id,
+                        &mut transaction,
+                    )
+                    .await
+                }
+                .await;
+                (transaction, result)
+            },
+        )
+        .await?;
+        self.workflow_runs_service
+            .audit("workflow_run.schedule", &request.workflow_run_id, None)
             .await;
-        if let Err(error) = retry_result {
-            transaction.rollback().await?;
-            return Err(error.into());
-        }
+        Ok(())
+    }
 
-        if let Err(error) = self
-            .workflow_runs_service
-            .schedule(&request.workflow_run_id)
-            .await
-        {
-            transaction.rollback().await?;
-            return Err(error);
+    async fn retry_all_failed(&self, workflow_run_id: &WorkflowRunId) -> EmResult<usize> {
+        let retried_count: i32 =
+            sqlx::query_scalar("call workflow_run.retry_all_failed_tasks($1,null)")
+                .bind(workflow_run_id)
+                .fetch_one(&self.pool)
+                .await?;
+        if retried_count > 0 {
+            self.workflow_runs_service
+                .audit("workflow_run.schedule", workflow_run_id, None)
+                .await;
         }
-
-        transaction.commit().await?;
-        Ok(())
+        Ok(usize::try_from(retried_count).unwrap_or_default())
     }
 
     async fn complete_task(&self, request: &TaskQueueRequest) -> EmResult<()> {
-        let mut transaction = self.pool.begin().await?;
-        let complete_task_result = sqlx::query("call workflow_run.complete_task($1,$2)")
-            .bind(request.workflow_run_id)
-            .bind(request.task_order)
-            .execute(&mut transaction)
+        with_retryable_transaction(
+            &self.pool,
+            DEFAULT_TRANSACTION_ATTEMPTS,
+            |mut transaction| async move {
+                let result = async {
+                    sqlx::query("call workflow_run.complete_task($1,$2)")
+                        .bind(request.workflow_run_id)
+                        .bind(request.task_order)
+                        .execute(&mut transaction)
+                        .await?;
+                    PgWorkflowRunsService::update_progress_in_transaction(
+                        &request.workflow_run_id,
+                        &mut transaction,
+                    )
+                    .await?;
+                    PgWorkflowRunsService::schedule_in_transaction(
+                        &request.workflow_run_id,
+                        &mut transaction,
+                    )
+                    .await
+                }
+                .await;
+                (transaction, result)
+            },
+        )
+        .await?;
+        self.workflow_runs_service
+            .audit("workflow_run.schedule", &request.workflow_run_id, None)
             .await;
-        if let Err(error) = complete_task_result {
-            transaction.rollback().await?;
-            return Err(error.into());
-        }
-
-        if let Err(error) = self
-            .workflow_runs_service
-            .update_progress(&request.workflow_run_id)
-            .await
-        {
-            transaction.rollback().await?;
-            return Err(error);
-        }
-
-        if let Err(error) = self
-            .workflow_runs_service
-            .schedule(&request.workflow_run_id)
-            .await
-        {
-            transaction.rollback().await?;
-            return Err(error);
-        }
-
-        transaction.commit().await?;
         Ok(())
     }
 
-    async fn next_task(
-        &self,
-        workflow_run_id: &WorkflowRunId,
-    ) -> EmResult<Option<TaskQueueRecord>> {
+    async fn next_tasks(&self, workflow_run_id: &WorkflowRunId) -> EmResult<Vec<TaskQueueRecord>> {
         let mut transaction = self.pool.begin().await?;
-        let fetch_result = sqlx::query_as(
+        let fetch_result: Result<Vec<TaskQueueRecord>, sqlx::Error> = sqlx::query_as(
             r#"
-            select nt.workflow_run_id, nt.task_order, nt.task_id, nt.status, nt.parameters, nt.url
-            from workflow_run.next_task($1) nt
-            where nt.task_order is not null"#,
+            select
+                nt.workflow_run_id, nt.task_order, nt.task_id, nt.status, nt.parameters, nt.url,
+                nt.timeout, nt.parameters_schema, nt.retry_count, nt.max_retries,
+                workflow_run.task_outputs(nt.workflow_run_id) task_outputs
+            from workflow_run.next_tasks($1) nt"#,
         )
         .bind(workflow_run_id)
-        .fetch_optional(&mut transaction)
+        .fetch_all(&mut transaction)
         .await;
 
-        let task_queue_record: TaskQueueRecord = match fetch_result {
-            Ok(Some(inner)) => inner,
-            Ok(None) => {
-                transaction.commit().await?;
-                return Ok(None);
-            }
+        let task_queue_records = match fetch_result {
+            Ok(inner) => inner,
             Err(error) => {
                 transaction.rollback().await?;
                 return Err(error.into());
             }
         };
 
-        let start_task_result = sqlx::query("call workflow_run.start_task_run($1,$2)")
-            .bind(task_queue_record.workflow_run_id)
-            .bind(task_queue_record.task_order)
-            .execute(&mut transaction)
-            .await;
+        for record in &task_queue_records {
+            let start_task_result = sqlx::query("call workflow_run.start_task_run($1,$2)")
+                .bind(record.workflow_run_id)
+                .bind(record.task_order)
+                .execute(&mut transaction)
+                .await;
+            if let Err(error) = start_task_result {
+                transaction.rollback().await?;
+                return Err(error.into());
+            }
+        }
 
-        finalize_transaction(start_task_result, transaction).await?;
-        Ok(Some(task_queue_record))
+        transaction.commit().await?;
+        Ok(task_queue_records)
     }
 
-    async fn run_task(&self, record: &TaskQueueRecord) -> EmResult<(bool, Option<String>)> {
-        self.pool
-            .close_event()
-            .do_until(self.remote_task_run(record))
-            .await?
+    async fn run_task(
+        &self,
+        record: &TaskQueueRecord,
+    ) -> EmResult<(bool, Option<String>, Option<Value>)> {
+        let start = Instant::now();
+        let result = self.execute_task(record).await;
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_run_task(start.elapsed());
+        }
+        result
     }
 
     async fn fail_task_run(&self, record: &TaskQueueRecord, error: EmError) -> EmResult<()> {
@@ -656,6 +1594,9 @@ impl TaskQueueService for PgTaskQueueService {
             .bind(error.to_string())
             .execute(&self.pool)
             .await?;
+        if let Some(metrics) = &self.metrics {
+            metrics.record_task_failed();
+        }
         Ok(())
     }
 
@@ -664,30 +1605,1288 @@ impl TaskQueueService for PgTaskQueueService {
         record: &TaskQueueRecord,
         is_paused: bool,
         message: Option<String>,
+        output: Option<Value>,
     ) -> EmResult<()> {
-        let mut transaction = self.pool.begin().await?;
-        let complete_result = sqlx::query("call workflow_run.complete_task_run($1,$2,$3,$4)")
-            .bind(record.workflow_run_id)
-            .bind(record.task_order)
-            .bind(is_paused)
-            .bind(message)
-            .execute(&mut transaction)
-            .await;
-        if let Err(error) = complete_result {
-            transaction.rollback().await?;
-            return Err(error.into());
+        with_retryable_transaction(
+            &self.pool,
+            DEFAULT_TRANSACTION_ATTEMPTS,
+            |mut transaction| {
+                let message = message.clone();
+                let output = output.clone();
+                async move {
+                    let result = async {
+                        sqlx::query("call workflow_run.complete_task_run($1,$2,$3,$4,$5)")
+                            .bind(record.workflow_run_id)
+                            .bind(record.task_order)
+                            .bind(is_paused)
+                            .bind(message)
+                            .bind(output)
+                            .execute(&mut transaction)
+                            .await?;
+                        PgWorkflowRunsService::update_progress_in_transaction(
+                            &record.workflow_run_id,
+                            &mut transaction,
+                        )
+                        .await
+                    }
+                    .await;
+                    (transaction, result)
+                }
+            },
+        )
+        .await?;
+        if let Some(metrics) = self.metrics.as_ref().filter(|_| !is_paused) {
+            metrics.record_task_completed();
         }
+        Ok(())
+    }
+}
 
-        if let Err(error) = self
-            .workflow_runs_service
-            .update_progress(&record.workflow_run_id)
-            .await
-        {
-            transaction.rollback().await?;
-            return Err(error);
+#[cfg(test)]
+#[allow(clippy::panic)]
+mod test {
+    use std::collections::HashSet;
+
+    use chrono::{Duration, Utc};
+    use common::{
+        api::{
+            pagination::{encode_cursor, CursorPagination},
+            sort::Sort,
+        },
+        audit::{postgres::PgAuditSink, with_actor, AuditEventFilter, AuditSink},
+        database::{connection::ConnectionBuilder, postgres::connection::PgConnectionBuilder},
+        error::{EmError, EmResult},
+    };
+    use futures::{future::join_all, StreamExt};
+    use rstest::rstest;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use tokio::sync::broadcast::error::RecvError;
+    use uuid::Uuid;
+
+    use super::{PgTaskQueueService, PgWorkflowRunsService};
+    use crate::{
+        database::{
+            db_options,
+            test::{cleanup_workflow, create_test_workflow, database},
+        },
+        workflow::{
+            data::{TaskId, WorkflowId},
+            service::{postgres::PgWorkflowsService, WorkflowsService},
+        },
+        workflow_run::{
+            data::{
+                TaskLogLevel, TaskQueueRequest, TaskRule, TaskStatus, WorkflowRunFilter,
+                WorkflowRunId, WorkflowRunProgress, WorkflowRunStatus,
+            },
+            service::{
+                TaskQueueService, WorkflowRunsService, MAX_INITIALIZE_BATCH_SIZE,
+                MAX_READ_MANY_BATCH_SIZE,
+            },
+        },
+    };
+
+    #[tokio::test]
+    async fn next_tasks_should_return_distinct_tasks_when_called_concurrently() -> EmResult<()> {
+        let task_count = 5;
+        // Concurrent calls each need their own connection so the single connection `database`
+        // fixture cannot be used
+        let pool = PgConnectionBuilder::create_pool_lazy(db_options()?, 10, 1);
+        let workflow_id = create_test_workflow(&pool, "next_tasks_concurrency", task_count).await?;
+        let workflow_service = PgWorkflowsService::new(&pool);
+        let workflow_runs_service = PgWorkflowRunsService::new(&pool, &workflow_service);
+        let task_queue_service = PgTaskQueueService::new(&pool, &workflow_runs_service);
+
+        let action = async {
+            let workflow_run = workflow_runs_service.initialize(&workflow_id).await?;
+            let handles = (0..task_count).map(|_| {
+                let service = task_queue_service.clone();
+                let workflow_run_id = workflow_run.workflow_run_id;
+                tokio::spawn(async move { service.next_tasks(&workflow_run_id).await })
+            });
+            let mut task_orders = Vec::new();
+            for result in join_all(handles).await {
+                let Ok(next_tasks) = result else {
+                    panic!("next_tasks call panicked");
+                };
+                task_orders.extend(next_tasks?.into_iter().map(|record| record.task_order));
+            }
+            EmResult::Ok(task_orders)
         }
+        .await;
+        cleanup_workflow(&pool, workflow_id).await?;
+        let task_orders = action?;
 
-        transaction.commit().await?;
+        let distinct_task_orders: HashSet<i32> = task_orders.iter().copied().collect();
+        assert!(!task_orders.is_empty());
+        assert_eq!(
+            task_orders.len(),
+            distinct_task_orders.len(),
+            "{task_orders:?}"
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn next_tasks_should_follow_task_dependencies(database: PgPool) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "next_tasks_dependencies", 4).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+        let task_queue_service = PgTaskQueueService::new(&database, &workflow_runs_service);
+
+        let action = async {
+            sqlx::query(
+                r#"
+                update workflow.workflow_tasks wt
+                set depends_on = case wt.task_order when 4 then array[2,3] else array[1] end
+                where
+                    wt.workflow_id = $1
+                    and wt.task_order > 1"#,
+            )
+            .bind(workflow_id)
+            .execute(&database)
+            .await?;
+            sqlx::query("call workflow.update_workflow($1,null,2::smallint)")
+                .bind(workflow_id)
+                .execute(&database)
+                .await?;
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+
+            let service = &task_queue_service;
+            let next_task_orders = || async move {
+                let records = service.next_tasks(&workflow_run_id).await?;
+                EmResult::Ok(
+                    records
+                        .iter()
+                        .map(|record| record.task_order)
+                        .collect::<Vec<i32>>(),
+                )
+            };
+            let complete_task = |task_order: i32| async move {
+                let record = service
+                    .read_one(&TaskQueueRequest::new(workflow_run_id, task_order))
+                    .await?;
+                service.complete_task_run(&record, false, None, None).await
+            };
+
+            let mut task_orders = vec![next_task_orders().await?];
+            for task_order in 1..=3 {
+                complete_task(task_order).await?;
+                task_orders.push(next_task_orders().await?);
+            }
+            EmResult::Ok(task_orders)
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let task_orders = action?;
+
+        assert_eq!(task_orders, vec![vec![1], vec![2, 3], vec![], vec![4]]);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn next_tasks_should_not_exceed_max_parallel_tasks(database: PgPool) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "next_tasks_max_parallel", 4).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+        let task_queue_service = PgTaskQueueService::new(&database, &workflow_runs_service);
+
+        let action = async {
+            sqlx::query(
+                r#"
+                update workflow.workflow_tasks wt
+                set depends_on = array[]::integer[]
+                where wt.workflow_id = $1"#,
+            )
+            .bind(workflow_id)
+            .execute(&database)
+            .await?;
+            sqlx::query("call workflow.update_workflow($1,null,2::smallint)")
+                .bind(workflow_id)
+                .execute(&database)
+                .await?;
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+
+            let service = &task_queue_service;
+            let next_task_orders = || async move {
+                let records = service.next_tasks(&workflow_run_id).await?;
+                EmResult::Ok(
+                    records
+                        .iter()
+                        .map(|record| record.task_order)
+                        .collect::<Vec<i32>>(),
+                )
+            };
+
+            let mut task_orders = vec![next_task_orders().await?, next_task_orders().await?];
+            let record = service
+                .read_one(&TaskQueueRequest::new(workflow_run_id, 1))
+                .await?;
+            service
+                .complete_task_run(&record, false, None, None)
+                .await?;
+            task_orders.push(next_task_orders().await?);
+            EmResult::Ok(task_orders)
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let task_orders = action?;
+
+        assert_eq!(task_orders, vec![vec![1, 2], vec![], vec![3]]);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn initialize_batch_should_create_distinct_runs_of_workflow(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let count = 3;
+        let workflow_id = create_test_workflow(&database, "initialize_batch", 2).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = workflow_runs_service
+            .initialize_batch(&workflow_id, count)
+            .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let workflow_runs = action?;
+
+        let distinct_ids: HashSet<WorkflowRunId> = workflow_runs
+            .iter()
+            .map(|workflow_run| workflow_run.workflow_run_id)
+            .collect();
+        assert_eq!(workflow_runs.len(), count);
+        assert_eq!(distinct_ids.len(), count);
+        assert!(workflow_runs
+            .iter()
+            .all(|workflow_run| WorkflowId::from(workflow_run.workflow_id) == workflow_id));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn clone_run_should_create_new_waiting_run_of_same_workflow(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "clone_run", 2).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let source = workflow_runs_service.initialize(&workflow_id).await?;
+            let clone = workflow_runs_service
+                .clone_run(&source.workflow_run_id)
+                .await?;
+            let source_after_clone = workflow_runs_service
+                .read_one(&source.workflow_run_id)
+                .await?;
+            EmResult::Ok((source, clone, source_after_clone))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (source, clone, source_after_clone) = action?;
+
+        assert!(clone.workflow_run_id != source.workflow_run_id);
+        assert_eq!(clone.workflow_id, source.workflow_id);
+        assert!(clone.status == WorkflowRunStatus::Waiting);
+        assert_eq!(clone.tasks.len(), source.tasks.len());
+        assert!(source_after_clone.status == WorkflowRunStatus::Waiting);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn append_task_log_should_keep_most_recent_lines_when_limit_exceeded(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "append_task_log", 1).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+        let task_queue_service =
+            PgTaskQueueService::new(&database, &workflow_runs_service).with_max_task_log_lines(2);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            let request = TaskQueueRequest::new(workflow_run_id, 1);
+            for message in ["first", "second", "third"] {
+                task_queue_service
+                    .append_task_log(&request, TaskLogLevel::Info, message)
+                    .await?;
+            }
+            workflow_runs_service.read_one(&workflow_run_id).await
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let workflow_run = action?;
+
+        let messages: Vec<String> = workflow_run
+            .tasks
+            .into_iter()
+            .flat_map(|task| task.logs.unwrap_or_default())
+            .map(|log| log.message)
+            .collect();
+        assert_eq!(messages, vec!["second", "third"]);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_task_detail_should_include_logs_and_fail_when_task_missing(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "read_task_detail", 1).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+        let task_queue_service = PgTaskQueueService::new(&database, &workflow_runs_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            let request = TaskQueueRequest::new(workflow_run_id, 1);
+            task_queue_service
+                .append_task_log(&request, TaskLogLevel::Info, "detail")
+                .await?;
+            let detail = task_queue_service.read_task_detail(&request).await?;
+            let missing = task_queue_service
+                .read_task_detail(&TaskQueueRequest::new(workflow_run_id, 2))
+                .await;
+            EmResult::Ok((detail, missing))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (detail, missing) = action?;
+
+        assert_eq!(detail.task_order, 1);
+        assert!(detail.status == TaskStatus::Waiting);
+        let messages: Vec<String> = detail
+            .logs
+            .unwrap_or_default()
+            .into_iter()
+            .map(|log| log.message)
+            .collect();
+        assert_eq!(messages, vec!["detail"]);
+        assert!(matches!(missing, Err(EmError::MissingRecord { .. })));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_task_rules_should_return_appended_rules_and_fail_when_task_missing(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "read_task_rules", 2).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+        let task_queue_service = PgTaskQueueService::new(&database, &workflow_runs_service);
+        let rule = TaskRule {
+            name: "row count".to_owned(),
+            failed: true,
+            message: Some("No rows loaded".to_owned()),
+        };
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            let request = TaskQueueRequest::new(workflow_run_id, 1);
+            task_queue_service.append_task_rule(&request, &rule).await?;
+            let rules = task_queue_service.read_task_rules(&request).await?;
+            let no_rules = task_queue_service
+                .read_task_rules(&TaskQueueRequest::new(workflow_run_id, 2))
+                .await?;
+            let missing = task_queue_service
+                .read_task_rules(&TaskQueueRequest::new(workflow_run_id, 3))
+                .await;
+            EmResult::Ok((rules, no_rules, missing))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (rules, no_rules, missing) = action?;
+
+        assert_eq!(rules.len(), 1);
+        assert!(rules.iter().all(|rule| rule.name() == "row count"
+            && rule.failed()
+            && rule.message() == Some("No rows loaded")));
+        assert!(no_rules.is_empty());
+        assert!(matches!(missing, Err(EmError::MissingRecord { .. })));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn retry_all_failed_should_return_zero_when_no_retryable_tasks(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "retry_all_failed_none", 2).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+        let task_queue_service = PgTaskQueueService::new(&database, &workflow_runs_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            let retried_count = task_queue_service
+                .retry_all_failed(&workflow_run_id)
+                .await?;
+            let workflow_run = workflow_runs_service.read_one(&workflow_run_id).await?;
+            EmResult::Ok((retried_count, workflow_run.status))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (retried_count, status) = action?;
+
+        assert_eq!(retried_count, 0);
+        assert!(status == WorkflowRunStatus::Waiting);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn retry_all_failed_should_retry_failed_tasks_and_schedule(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "retry_all_failed", 3).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+        let task_queue_service = PgTaskQueueService::new(&database, &workflow_runs_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            sqlx::query(
+                r#"
+                update workflow_run.task_queue
+                set status = case task_order
+                    when 1 then 'Failed'::workflow_run.task_status
+                    when 2 then 'Rule Broken'::workflow_run.task_status
+                    else 'Complete'::workflow_run.task_status
+                end
+                where workflow_run_id = $1"#,
+            )
+            .bind(workflow_run_id)
+            .execute(&database)
+            .await?;
+            let retried_count = task_queue_service
+                .retry_all_failed(&workflow_run_id)
+                .await?;
+            let workflow_run = workflow_runs_service.read_one(&workflow_run_id).await?;
+            let retried_tasks: i64 = sqlx::query_scalar(
+                r#"
+                select count(*)
+                from workflow_run.task_queue
+                where
+                    workflow_run_id = $1
+                    and status = 'Waiting'::workflow_run.task_status
+                    and retry_count = 1"#,
+            )
+            .bind(workflow_run_id)
+            .fetch_one(&database)
+            .await?;
+            EmResult::Ok((retried_count, workflow_run.status, retried_tasks))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (retried_count, status, retried_tasks) = action?;
+
+        assert_eq!(retried_count, 2);
+        assert_eq!(retried_tasks, 2);
+        assert!(status == WorkflowRunStatus::Scheduled);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::with_reason("cancel_with_reason", Some("Wrong parameters"))]
+    #[case::without_reason("cancel_without_reason", None)]
+    #[tokio::test]
+    async fn cancel_with_reason_should_store_reason_until_restart(
+        database: PgPool,
+        #[case] prefix: &str,
+        #[case] reason: Option<&str>,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, prefix, 1).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            let canceled = workflow_runs_service
+                .cancel_with_reason(&workflow_run_id, reason)
+                .await?;
+            let restarted = workflow_runs_service.restart(&workflow_run_id).await?;
+            EmResult::Ok((canceled, restarted))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (canceled, restarted) = action?;
+
+        assert!(canceled.status == WorkflowRunStatus::Canceled);
+        assert_eq!(canceled.cancel_reason.as_deref(), reason);
+        assert!(restarted.cancel_reason.is_none());
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn wait_for_completion_should_return_status_when_run_is_canceled(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "wait_for_completion", 1).await?;
+        // The status listener holds a connection of its own so the service needs a pool that has
+        // more than the single connection of the `database` fixture
+        let listener_pool = PgConnectionBuilder::create_pool_lazy(db_options()?, 2, 1);
+        let workflow_service = PgWorkflowsService::new(&listener_pool);
+        let workflow_runs_service = PgWorkflowRunsService::new(&listener_pool, &workflow_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            let waiting_service = workflow_runs_service.clone();
+            let waiter = tokio::spawn(async move {
+                waiting_service
+                    .wait_for_completion(&workflow_run_id, std::time::Duration::from_secs(10))
+                    .await
+            });
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            workflow_runs_service.cancel(&workflow_run_id).await?;
+            waiter.await.map_err(|error| error.to_string())?
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let status = action?;
+
+        assert!(status == WorkflowRunStatus::Canceled);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn subscribe_should_only_yield_current_status_when_run_is_terminal(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "subscribe_terminal", 1).await?;
+        // The status listener holds a connection of its own so the service needs a pool that has
+        // more than the single connection of the `database` fixture
+        let listener_pool = PgConnectionBuilder::create_pool_lazy(db_options()?, 2, 1);
+        let workflow_service = PgWorkflowsService::new(&listener_pool);
+        let workflow_runs_service = PgWorkflowRunsService::new(&listener_pool, &workflow_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            workflow_runs_service.cancel(&workflow_run_id).await?;
+            let statuses: Vec<WorkflowRunStatus> = workflow_runs_service
+                .subscribe(&workflow_run_id)
+                .await?
+                .collect()
+                .await;
+            EmResult::Ok(statuses)
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let statuses = action?;
+
+        assert!(statuses == vec![WorkflowRunStatus::Canceled]);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn wait_for_completion_should_fail_when_timeout_elapses(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "wait_for_completion_timeout", 1).await?;
+        // The status listener holds a connection of its own so the service needs a pool that has
+        // more than the single connection of the `database` fixture
+        let listener_pool = PgConnectionBuilder::create_pool_lazy(db_options()?, 2, 1);
+        let workflow_service = PgWorkflowsService::new(&listener_pool);
+        let workflow_runs_service = PgWorkflowRunsService::new(&listener_pool, &workflow_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            let result = workflow_runs_service
+                .wait_for_completion(&workflow_run_id, std::time::Duration::from_millis(200))
+                .await;
+            EmResult::Ok(result)
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let result = action?;
+
+        assert!(matches!(result, Err(EmError::Generic(_))));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn cancel_should_record_audit_event_for_current_actor(database: PgPool) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "cancel_audit", 1).await?;
+        let audit_sink = PgAuditSink::new(&database);
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service =
+            PgWorkflowRunsService::new(&database, &workflow_service).with_audit_sink(&audit_sink);
+        let actor = Uuid::new_v4();
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            with_actor(
+                actor,
+                workflow_runs_service.cancel_with_reason(&workflow_run_id, Some("test")),
+            )
+            .await?;
+            let filter = AuditEventFilter {
+                actor: Some(actor),
+                target: Some(format!("workflow_run:{workflow_run_id}")),
+                ..AuditEventFilter::default()
+            };
+            audit_sink.read_many(&filter).await
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let events = action?;
+
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events.first(),
+            Some(event) if event.action == "workflow_run.cancel"
+                && event.details == Some(json!({ "reason": "test" }))
+        ));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn pause_and_resume_should_reschedule_when_run_was_running(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "pause_and_resume", 1).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            sqlx::query(
+                r#"
+                update workflow_run.workflow_runs
+                set status = 'Running'::workflow_run.workflow_run_status
+                where workflow_run_id = $1"#,
+            )
+            .bind(workflow_run_id)
+            .execute(&database)
+            .await?;
+            let paused = workflow_runs_service.pause(&workflow_run_id).await?;
+            let resumed = workflow_runs_service.resume(&workflow_run_id).await?;
+            EmResult::Ok((paused, resumed))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (paused, resumed) = action?;
+
+        assert!(paused.status == WorkflowRunStatus::Paused);
+        assert!(resumed.status == WorkflowRunStatus::Scheduled);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn recover_orphaned_should_reschedule_runs_of_inactive_executors(
+        database: PgPool,
+    ) -> EmResult<()> {
+        // Recovery applies to every orphaned workflow run so the test runs within a rolled back
+        // transaction to leave the workflow runs of other tests untouched
+        let mut transaction = database.begin().await?;
+        let workflow_id = create_test_workflow(&mut transaction, "recover_orphaned", 2).await?;
+        let workflow_run_id: WorkflowRunId =
+            sqlx::query_scalar("call workflow_run.initialize_workflow_run($1,null)")
+                .bind(workflow_id)
+                .fetch_one(&mut transaction)
+                .await?;
+        sqlx::query(
+            r#"
+            with executor as (
+                insert into executor.executors(
+                    pid, username, application_name, client_addr, client_port, status
+                )
+                values(0, current_user, 'recover_orphaned', '127.0.0.1', 0, 'Canceled')
+                returning executor_id
+            ), workflow_run as (
+                update workflow_run.workflow_runs wr
+                set
+                    status = 'Running'::workflow_run.workflow_run_status,
+                    executor_id = e.executor_id
+                from executor e
+                where wr.workflow_run_id = $1
+            )
+            update workflow_run.task_queue
+            set status = 'Running'::workflow_run.task_status
+            where
+                workflow_run_id = $1
+                and task_order = 1"#,
+        )
+        .bind(workflow_run_id)
+        .execute(&mut transaction)
+        .await?;
+
+        let recovered: Vec<WorkflowRunId> =
+            sqlx::query_scalar("select r from workflow_run.recover_orphaned_workflow_runs() r")
+                .fetch_all(&mut transaction)
+                .await?;
+        let (status, running_task_count): (WorkflowRunStatus, i64) = sqlx::query_as(
+            r#"
+            select
+                wr.status,
+                (
+                    select count(*)
+                    from workflow_run.task_queue tq
+                    where
+                        tq.workflow_run_id = wr.workflow_run_id
+                        and tq.status = 'Running'::workflow_run.task_status
+                )
+            from workflow_run.workflow_runs wr
+            where wr.workflow_run_id = $1"#,
+        )
+        .bind(workflow_run_id)
+        .fetch_one(&mut transaction)
+        .await?;
+        transaction.rollback().await?;
+
+        assert!(recovered.contains(&workflow_run_id));
+        assert!(status == WorkflowRunStatus::Scheduled);
+        assert_eq!(running_task_count, 0);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn pause_and_resume_should_fail_when_run_is_waiting(database: PgPool) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "pause_waiting", 1).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            let pause_result = workflow_runs_service.pause(&workflow_run_id).await;
+            let resume_result = workflow_runs_service.resume(&workflow_run_id).await;
+            EmResult::Ok((pause_result, resume_result))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (pause_result, resume_result) = action?;
+
+        assert!(pause_result.is_err());
+        assert!(resume_result.is_err());
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn clone_run_should_fail_when_source_run_does_not_exist(database: PgPool) {
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let result = workflow_runs_service
+            .clone_run(&WorkflowRunId::from(-1))
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::zero(0)]
+    #[case::over_max(MAX_INITIALIZE_BATCH_SIZE + 1)]
+    #[tokio::test]
+    async fn initialize_batch_should_fail_when_count_out_of_bounds(
+        database: PgPool,
+        #[case] count: usize,
+    ) {
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let result = workflow_runs_service
+            .initialize_batch(&WorkflowId::from(1), count)
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_many_by_ids_should_preserve_input_order_and_omit_missing_ids(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "read_many_by_ids", 1).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let mut expected_ids: Vec<WorkflowRunId> = workflow_runs_service
+                .initialize_batch(&workflow_id, 3)
+                .await?
+                .iter()
+                .map(|workflow_run| workflow_run.workflow_run_id)
+                .collect();
+            expected_ids.reverse();
+            let mut requested_ids = expected_ids.clone();
+            requested_ids.insert(1, WorkflowRunId::from(-1));
+            let workflow_runs = workflow_runs_service
+                .read_many_by_ids(&requested_ids)
+                .await?;
+            EmResult::Ok((expected_ids, workflow_runs))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (expected_ids, workflow_runs) = action?;
+
+        let actual_ids: Vec<WorkflowRunId> = workflow_runs
+            .iter()
+            .map(|workflow_run| workflow_run.workflow_run_id)
+            .collect();
+        assert_eq!(actual_ids, expected_ids);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_one_summary_should_match_read_one_header(database: PgPool) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "read_one_summary", 2).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let workflow_run = workflow_runs_service.initialize(&workflow_id).await?;
+            let summary = workflow_runs_service
+                .read_one_summary(&workflow_run.workflow_run_id)
+                .await?;
+            EmResult::Ok((workflow_run, summary))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (workflow_run, summary) = action?;
+
+        assert_eq!(summary.workflow_run_id, workflow_run.workflow_run_id);
+        assert_eq!(summary.workflow_id, workflow_run.workflow_id);
+        assert!(summary.status == workflow_run.status);
+        assert_eq!(summary.priority, workflow_run.priority);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::no_filter("read_one_filtered_none", None, 2)]
+    #[case::matching_status("read_one_filtered_matching", Some(vec![TaskStatus::Waiting]), 2)]
+    #[case::other_status(
+        "read_one_filtered_other",
+        Some(vec![TaskStatus::Failed, TaskStatus::RuleBroken]),
+        0
+    )]
+    #[tokio::test]
+    async fn read_one_filtered_should_only_include_tasks_with_status(
+        database: PgPool,
+        #[case] prefix: &str,
+        #[case] task_status_filter: Option<Vec<TaskStatus>>,
+        #[case] expected_task_count: usize,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, prefix, 2).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            workflow_runs_service
+                .read_one_filtered(&workflow_run_id, task_status_filter)
+                .await
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let filtered = action?;
+
+        assert_eq!(filtered.tasks.len(), expected_task_count);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_by_task_status_should_only_include_runs_with_task_status(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "read_by_task_status", 2).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            let waiting = workflow_runs_service
+                .read_by_task_status(TaskStatus::Waiting)
+                .await?;
+            let failed = workflow_runs_service
+                .read_by_task_status(TaskStatus::Failed)
+                .await?;
+            EmResult::Ok((workflow_run_id, waiting, failed))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (workflow_run_id, waiting, failed) = action?;
+
+        assert!(waiting
+            .iter()
+            .any(|summary| summary.workflow_run_id == workflow_run_id));
+        assert!(!failed
+            .iter()
+            .any(|summary| summary.workflow_run_id == workflow_run_id));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_one_summary_should_fail_when_workflow_run_missing(database: PgPool) {
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let result = workflow_runs_service
+            .read_one_summary(&WorkflowRunId::from(-1))
+            .await;
+
+        assert!(
+            matches!(result, Err(EmError::MissingRecord { .. })),
+            "Expected a missing record error"
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_many_by_ids_should_fail_when_too_many_ids(database: PgPool) {
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+        let workflow_run_ids = vec![WorkflowRunId::from(1); MAX_READ_MANY_BATCH_SIZE + 1];
+
+        let result = workflow_runs_service
+            .read_many_by_ids(&workflow_run_ids)
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_many_after_should_not_skip_or_duplicate_when_run_inserted_between_pages(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "read_many_after", 1).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let mut expected_ids: Vec<WorkflowRunId> = workflow_runs_service
+                .initialize_batch(&workflow_id, 4)
+                .await?
+                .iter()
+                .map(|workflow_run| workflow_run.workflow_run_id)
+                .collect();
+            let start_key = expected_ids
+                .iter()
+                .map(|workflow_run_id| workflow_run_id.into_inner())
+                .min()
+                .unwrap_or_default()
+                - 1;
+
+            let mut page = CursorPagination::new(2, Some(encode_cursor(start_key)));
+            let mut seen_ids = Vec::new();
+            let mut inserted = false;
+            loop {
+                let result = workflow_runs_service.read_many_after(&page).await?;
+                seen_ids.extend(
+                    result
+                        .items
+                        .iter()
+                        .map(|workflow_run| workflow_run.workflow_run_id),
+                );
+                if !inserted {
+                    let workflow_run = workflow_runs_service.initialize(&workflow_id).await?;
+                    expected_ids.push(workflow_run.workflow_run_id);
+                    inserted = true;
+                }
+                let Some(next_cursor) = result.next_cursor else {
+                    break;
+                };
+                page = CursorPagination::new(2, Some(next_cursor));
+            }
+            EmResult::Ok((expected_ids, seen_ids))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (expected_ids, seen_ids) = action?;
+
+        let distinct_ids: HashSet<WorkflowRunId> = seen_ids.iter().copied().collect();
+        assert_eq!(seen_ids.len(), distinct_ids.len(), "{seen_ids:?}");
+        assert!(
+            expected_ids.iter().all(|id| distinct_ids.contains(id)),
+            "{expected_ids:?} not all in {seen_ids:?}"
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::any_status("read_history_any", vec![], true)]
+    #[case::running_status("read_history_running", vec![WorkflowRunStatus::Running], true)]
+    #[case::other_status("read_history_other", vec![WorkflowRunStatus::Complete], false)]
+    #[tokio::test]
+    async fn read_history_should_filter_by_status_when_run_started(
+        database: PgPool,
+        #[case] prefix: &str,
+        #[case] status: Vec<WorkflowRunStatus>,
+        #[case] is_included: bool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, prefix, 1).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            sqlx::query("call workflow_run.start_workflow_run($1, null)")
+                .bind(workflow_run_id)
+                .execute(&database)
+                .await?;
+            let now = Utc::now().naive_utc();
+            let filter = WorkflowRunFilter {
+                workflow_id: Some(workflow_id),
+                status,
+                started_between: (now - Duration::minutes(5), now + Duration::minutes(5)),
+            };
+            let history = workflow_runs_service
+                .read_history(&filter, &Sort::default())
+                .await?;
+            EmResult::Ok((workflow_run_id, history))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (workflow_run_id, history) = action?;
+
+        let found = history
+            .iter()
+            .any(|record| record.workflow_run_id == workflow_run_id);
+        assert_eq!(found, is_included);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::ascending(true)]
+    #[case::descending(false)]
+    #[tokio::test]
+    async fn read_history_should_order_by_run_start_when_sorted(
+        database: PgPool,
+        #[case] ascending: bool,
+    ) -> EmResult<()> {
+        let prefix = if ascending {
+            "read_history_sort_asc"
+        } else {
+            "read_history_sort_desc"
+        };
+        let workflow_id = create_test_workflow(&database, prefix, 1).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let mut started_ids = Vec::new();
+            for _ in 0..2 {
+                let workflow_run = workflow_runs_service.initialize(&workflow_id).await?;
+                sqlx::query("call workflow_run.start_workflow_run($1, null)")
+                    .bind(workflow_run.workflow_run_id)
+                    .execute(&database)
+                    .await?;
+                started_ids.push(workflow_run.workflow_run_id);
+            }
+            let now = Utc::now().naive_utc();
+            let filter = WorkflowRunFilter {
+                workflow_id: Some(workflow_id),
+                status: vec![],
+                started_between: (now - Duration::minutes(5), now + Duration::minutes(5)),
+            };
+            let history = workflow_runs_service
+                .read_history(&filter, &Sort::new("run_start", ascending))
+                .await?;
+            EmResult::Ok((started_ids, history))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (mut started_ids, history) = action?;
+
+        let history_ids: Vec<WorkflowRunId> = history
+            .iter()
+            .map(|record| record.workflow_run_id)
+            .collect();
+        if !ascending {
+            started_ids.reverse();
+        }
+        assert_eq!(history_ids, started_ids);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn read_timing_should_measure_started_run_up_to_now(database: PgPool) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "read_timing", 2).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let workflow_run_id = workflow_runs_service
+                .initialize(&workflow_id)
+                .await?
+                .workflow_run_id;
+            workflow_runs_service.schedule(&workflow_run_id).await?;
+            sqlx::query("call workflow_run.start_workflow_run($1, null)")
+                .bind(workflow_run_id)
+                .execute(&database)
+                .await?;
+            workflow_runs_service.read_timing(&workflow_run_id).await
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let timing = action?;
+
+        assert!(timing.scheduled_at.is_some());
+        assert!(timing.run_start.is_some());
+        assert!(timing.queue_wait_ms.is_some());
+        assert!(timing.run_duration_ms.is_some());
+        assert_eq!(timing.run_end, None);
+        assert_eq!(timing.tasks.len(), 2);
+        assert!(timing.tasks.iter().all(|task| task.duration_ms.is_none()));
+        Ok(())
+    }
+
+    /// Receive progress updates from the `updates` subscription until an update for the
+    /// `workflow_run_id` is found, ignoring updates of other workflow runs
+    async fn next_progress(
+        updates: &mut tokio::sync::broadcast::Receiver<WorkflowRunProgress>,
+        workflow_run_id: WorkflowRunId,
+    ) -> Option<WorkflowRunProgress> {
+        loop {
+            match updates.recv().await {
+                Ok(progress) if progress.workflow_run_id == workflow_run_id => {
+                    return Some(progress)
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn progress_updates_should_fan_out_to_every_subscriber(database: PgPool) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "progress_updates", 1).await?;
+        // The shared listener holds a connection of its own so the service needs a pool that
+        // has more than the single connection of the `database` fixture
+        let listener_pool = PgConnectionBuilder::create_pool_lazy(db_options()?, 2, 1);
+        let workflow_service = PgWorkflowsService::new(&listener_pool);
+        let workflow_runs_service = PgWorkflowRunsService::new(&listener_pool, &workflow_service);
+        let workflow_run_id = workflow_runs_service
+            .initialize(&workflow_id)
+            .await?
+            .workflow_run_id;
+        let mut first = workflow_runs_service.progress_updates();
+        let mut second = workflow_runs_service.clone().progress_updates();
+        // Allow the shared listener to start listening before the update is sent
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+        let action = async {
+            sqlx::query(
+                "update workflow_run.workflow_runs set progress = 50 where workflow_run_id = $1",
+            )
+            .bind(workflow_run_id)
+            .execute(&database)
+            .await?;
+            let timeout = std::time::Duration::from_secs(5);
+            let first_progress =
+                tokio::time::timeout(timeout, next_progress(&mut first, workflow_run_id)).await;
+            let second_progress =
+                tokio::time::timeout(timeout, next_progress(&mut second, workflow_run_id)).await;
+            EmResult::Ok((first_progress, second_progress))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (first_progress, second_progress) = action?;
+
+        let (Ok(Some(first_progress)), Ok(Some(second_progress))) =
+            (first_progress, second_progress)
+        else {
+            panic!("Every subscriber should receive the progress update");
+        };
+        assert_eq!(first_progress.progress, Some(50));
+        assert_eq!(second_progress.progress, Some(50));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn validate_workflow_should_report_every_task_of_workflow(
+        database: PgPool,
+    ) -> EmResult<()> {
+        let workflow_id = create_test_workflow(&database, "validate_workflow", 2).await?;
+        let workflow_service = PgWorkflowsService::new(&database);
+        let workflow_runs_service = PgWorkflowRunsService::new(&database, &workflow_service);
+
+        let action = async {
+            let workflow = workflow_service.read_one(&workflow_id).await?;
+            let report = workflow_runs_service
+                .validate_workflow(&workflow_id)
+                .await?;
+            EmResult::Ok((workflow, report))
+        }
+        .await;
+        cleanup_workflow(&database, workflow_id).await?;
+        let (workflow, report) = action?;
+
+        let expected_task_ids: Vec<TaskId> =
+            workflow.tasks.iter().map(|task| task.task_id).collect();
+        let task_ids: Vec<TaskId> = report.tasks.iter().map(|task| task.task_id).collect();
+        assert_eq!(report.workflow_id, workflow_id);
+        assert_eq!(task_ids, expected_task_ids);
         Ok(())
     }
 }
@@ -0,0 +1,111 @@
+use std::{env, time::Duration};
+
+use common::error::EmResult;
+use log::{error, info};
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::workflow_run::service::WorkflowRunsService;
+
+/// Default time (in seconds) between attempts to recover orphaned workflow runs
+const DEFAULT_RECOVERY_INTERVAL: u64 = 60;
+
+/// Configuration of the background supervisor started by [spawn_orphan_recovery]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveryConfig {
+    /// Time between attempts to recover orphaned workflow runs. [None] disables the supervisor
+    pub interval: Option<Duration>,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            interval: Some(Duration::from_secs(DEFAULT_RECOVERY_INTERVAL)),
+        }
+    }
+}
+
+impl RecoveryConfig {
+    /// Create a new [RecoveryConfig] from environment variables, using the default value for any
+    /// variable that is not set. The environment variables read are:
+    /// - WE_RECOVERY_INTERVAL -> time between recoveries in seconds, 0 disables recovery (default
+    /// 60)
+    /// # Errors
+    /// This function will return an error if an environment variable cannot be parsed
+    pub fn from_env() -> EmResult<Self> {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Create a new [RecoveryConfig] using `lookup` to read the values of the environment
+    /// variables described in [RecoveryConfig::from_env]
+    fn from_lookup<F>(lookup: F) -> EmResult<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let Some(value) = lookup("WE_RECOVERY_INTERVAL") else {
+            return Ok(Self::default());
+        };
+        let interval = Duration::from_secs(value.parse()?);
+        Ok(Self {
+            interval: (!interval.is_zero()).then_some(interval),
+        })
+    }
+}
+
+/// Spawn a background task that calls [WorkflowRunsService::recover_orphaned] every
+/// [RecoveryConfig::interval] so workflow runs owned by executors that died without closing their
+/// session are picked up by another executor. Recovered workflow runs are logged and failures are
+/// logged without stopping the task. Returns [None] if the supervisor is disabled.
+pub fn spawn_orphan_recovery<R>(service: &R, config: RecoveryConfig) -> Option<JoinHandle<()>>
+where
+    R: WorkflowRunsService,
+{
+    let period = config.interval?;
+    let service = service.clone();
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            match service.recover_orphaned().await {
+                Ok(workflow_run_ids) if workflow_run_ids.is_empty() => {}
+                Ok(workflow_run_ids) => info!(
+                    "Recovered {} orphaned workflow run(s): {:?}",
+                    workflow_run_ids.len(),
+                    workflow_run_ids
+                ),
+                Err(error) => error!("Could not recover orphaned workflow runs. {error}"),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::time::Duration;
+
+    use common::test::env_lookup;
+    use rstest::rstest;
+
+    use super::RecoveryConfig;
+
+    #[rstest]
+    #[case::not_set(&[], Some(Duration::from_secs(60)))]
+    #[case::set(&[("WE_RECOVERY_INTERVAL", "15")], Some(Duration::from_secs(15)))]
+    #[case::disabled(&[("WE_RECOVERY_INTERVAL", "0")], None)]
+    fn recovery_config_from_lookup_should_set_interval_when(
+        #[case] pairs: &[(&str, &str)],
+        #[case] expected: Option<Duration>,
+    ) {
+        let config = RecoveryConfig::from_lookup(env_lookup(pairs)).unwrap();
+
+        assert_eq!(config.interval, expected);
+    }
+
+    #[test]
+    fn recovery_config_from_lookup_should_fail_when_not_a_number() {
+        let result = RecoveryConfig::from_lookup(env_lookup(&[("WE_RECOVERY_INTERVAL", "soon")]));
+
+        assert!(result.is_err());
+    }
+}